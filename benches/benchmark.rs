@@ -0,0 +1,131 @@
+/*!
+
+Benchmarks for the core operations of the library. The fixtures are in `mod2lib::bench_utils`.
+
+Run with `cargo bench`. A single group can be selected by name, e.g. `cargo bench -- reduction`.
+
+*/
+
+use criterion::{
+  black_box,
+  criterion_group,
+  criterion_main,
+  BenchmarkId,
+  Criterion,
+  Throughput
+};
+
+use mod2lib::{
+  abstractions::ISTRING_BACKEND,
+  api::{symbol::Symbol, Arity},
+  bench_utils::{
+    collect_garbage,
+    AcNormalizationBench,
+    GcBench,
+    MatchingBench,
+    RandomTermGenerator,
+    ReductionBench
+  },
+  IString
+};
+
+const SEED: u64 = 0x5eed;
+
+fn dagification(c: &mut Criterion) {
+  let mut group     = c.benchmark_group("dagification");
+  let mut generator = RandomTermGenerator::new(4, SEED);
+
+  for height in [4, 6, 8] {
+    let term = generator.random_term(height, 3, 1);
+    group.throughput(Throughput::Elements(term.size() as u64));
+    group.bench_with_input(BenchmarkId::from_parameter(height), &term, |b, term| {
+      b.iter(|| {
+        term.dagify();
+        collect_garbage();
+      })
+    });
+  }
+
+  group.finish();
+}
+
+fn matching(c: &mut Criterion) {
+  let mut group     = c.benchmark_group("matching");
+  let mut generator = RandomTermGenerator::new(4, SEED);
+
+  for pattern_depth in [1, 2, 4] {
+    let mut bench = MatchingBench::new(&mut generator, 100, pattern_depth, 6, 3);
+    group.throughput(Throughput::Elements(100));
+    group.bench_function(BenchmarkId::from_parameter(pattern_depth), |b| b.iter(|| black_box(bench.run())));
+  }
+
+  group.finish();
+}
+
+fn ac_normalization(c: &mut Criterion) {
+  let mut group = c.benchmark_group("ac_normalization");
+
+  for size in [10, 100, 1_000] {
+    let bench = AcNormalizationBench::new(10, size, 8, SEED);
+    group.throughput(Throughput::Elements(10 * size as u64));
+    group.bench_function(BenchmarkId::from_parameter(size), |b| b.iter(|| black_box(bench.run())));
+  }
+
+  group.finish();
+}
+
+fn reduction(c: &mut Criterion) {
+  let mut group = c.benchmark_group("reduction");
+
+  for n in [10, 100] {
+    let bench = ReductionBench::naturals_addition(n);
+    group.bench_function(BenchmarkId::new("naturals_addition", n), |b| b.iter(|| black_box(bench.run())));
+  }
+
+  for n in [10, 15] {
+    let bench = ReductionBench::fibonacci(n);
+    group.bench_function(BenchmarkId::new("fibonacci", n), |b| b.iter(|| black_box(bench.run())));
+  }
+
+  for length in [10, 30] {
+    let bench = ReductionBench::sorting(length, SEED);
+    group.bench_function(BenchmarkId::new("sorting", length), |b| b.iter(|| black_box(bench.run())));
+  }
+
+  group.finish();
+}
+
+/// Symbol creation interns a new name per symbol. Compare the backends with `cargo bench -- interning` and
+/// `cargo bench --features ustr -- interning`.
+fn interning(c: &mut Criterion) {
+  let mut group = c.benchmark_group(format!("interning/{}", ISTRING_BACKEND));
+  let mut round = 0u64;
+
+  for count in [1_000u64, 10_000] {
+    group.throughput(Throughput::Elements(count));
+    group.bench_function(BenchmarkId::new("fresh_symbols", count), |b| b.iter(|| {
+      round += 1;
+      for i in 0..count {
+        black_box(Symbol::new(IString::from(format!("f{}_{}", round, i)), Arity::Value(2)));
+      }
+    }));
+    group.bench_function(BenchmarkId::new("repeated_symbols", count), |b| b.iter(|| {
+      for i in 0..count {
+        black_box(Symbol::new(IString::from(format!("g{}", i % 64)), Arity::Value(2)));
+      }
+    }));
+  }
+
+  group.finish();
+}
+
+fn garbage_collection(c: &mut Criterion) {
+  let mut group = c.benchmark_group("garbage_collection");
+  let mut bench = GcBench::new(10, 6, 4, SEED);
+
+  group.bench_function("build_and_collect", |b| b.iter(|| black_box(bench.run())));
+  group.finish();
+}
+
+criterion_group!(benches, dagification, matching, ac_normalization, reduction, interning, garbage_collection);
+criterion_main!(benches);
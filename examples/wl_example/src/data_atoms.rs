@@ -12,7 +12,8 @@ ToDo: Why do uses of `implement_data_atom!` require `use paste::paste`?
 
 use std::{
   any::Any,
-  fmt::Display
+  fmt::Display,
+  hash::{Hash, Hasher}
 };
 use std::ops::Deref;
 use once_cell::sync::Lazy;
@@ -71,6 +72,10 @@ impl DataAtom for FloatAtom {
     }
   }
 
+  fn hash_data(&self, mut state: &mut dyn Hasher) {
+    self.0.hash(&mut state)
+  }
+
   fn symbol(&self) -> SymbolPtr {
     let ptr: *const Symbol = unsafe{ &*FLOAT_SYMBOL };
     ptr as SymbolPtr
@@ -81,9 +86,10 @@ impl DataAtom for FloatAtom {
 pub static FLOAT_SYMBOL: Lazy<Symbol> = Lazy::new(|| {
   Symbol{
     name:        IString::from("Float"),  // The convention is `Name` is the symbol for `NameAtom`.
-    arity:       Arity::Unspecified,                  // Data is generally "nullary", but we can leave it unspecified.
+    arity:       Arity::CONSTANT,                     // `DataAtom`s are constants.
     attributes:  SymbolAttribute::Constructor.into(), // All `DataAtom`s have `SymbolAttribute::Constructor`.
     symbol_type: SymbolType::Data,                    // All `DataAtom`s have `symbol_type` `SymbolType::Data`.
+    sort_table:  Default::default(),
     hash_value:  0,
   }
 });
@@ -0,0 +1,30 @@
+/*!
+
+Parses arbitrary text as a module followed by a term over it, as in `mod M is … endm f(a)`, and reduces the term.
+Malformed text must be reported as a parse error rather than crash the library, and the reduction is bounded, so a
+module with nonterminating equations must stop at the limit rather than hang.
+
+Run with `cargo fuzz run parse` from the repository root.
+
+*/
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use mod2lib::{
+  bench_utils::collect_garbage,
+  testing::parse_and_reduce_bounded
+};
+
+/// Kept small for the same reason as in the `reduce` target: reduction recurses on the depth of the subject.
+const MAX_REWRITES: usize = 1_000;
+
+fuzz_target!(|data: &[u8]| {
+  let Ok(source) = core::str::from_utf8(data) else {
+    return;
+  };
+
+  let _ = parse_and_reduce_bounded(source, MAX_REWRITES);
+  collect_garbage();
+});
@@ -0,0 +1,33 @@
+/*!
+
+Reduces an adversarial subject with an adversarial rule set. The input is interpreted as a sequence of choices that
+generates a signature, an orthogonal rule set that may not terminate, and a subject. Reduction is bounded, so a
+nonterminating rule set must stop at the limit rather than hang.
+
+Run with `cargo fuzz run reduce` from the repository root.
+
+*/
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use mod2lib::{
+  bench_utils::collect_garbage,
+  testing::{reduce_bounded, Choices, RuleSet, SortId, SortedSignature}
+};
+
+/// Kept small because reduction recurses on the depth of the subject, which nonterminating rules can grow by a level
+/// per rewrite.
+const MAX_REWRITES: usize = 1_000;
+
+fuzz_target!(|data: &[u8]| {
+  let mut choices = Choices::from_bytes(data);
+  let signature   = SortedSignature::random(&mut choices, 3, 8, 3);
+  let rule_set    = RuleSet::random_orthogonal(&signature, &mut choices, 3);
+  let sort        = SortId(choices.choose(signature.sort_count()));
+  let subject     = signature.random_term(&mut choices, sort, 6);
+
+  let _ = reduce_bounded(rule_set, &subject, MAX_REWRITES);
+  collect_garbage();
+});
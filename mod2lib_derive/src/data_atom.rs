@@ -0,0 +1,98 @@
+/*!
+
+The `DataAtom` derive. See `derive_data_atom`.
+
+*/
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{DeriveInput, Error, Ident, LitInt, LitStr};
+
+pub(crate) fn data_atom(input: &DeriveInput) -> Result<TokenStream, Error> {
+  if !input.generics.params.is_empty() {
+    return Err(Error::new_spanned(
+      &input.generics,
+      "DataAtom can't be derived for a generic type, as each type of atom needs a symbol of its own"
+    ));
+  }
+
+  let ident      = &input.ident;
+  let visibility = &input.vis;
+  let mut name   = LitStr::new(&ident.to_string(), ident.span());
+  let mut arity  = LitInt::new("0", Span::call_site());
+  for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("data_atom")) {
+    attribute.parse_nested_meta(|meta| {
+      if meta.path.is_ident("name") {
+        name = meta.value()?.parse()?;
+        Ok(())
+      } else if meta.path.is_ident("arity") {
+        arity = meta.value()?.parse()?;
+        arity.base10_parse::<u16>()?;
+        Ok(())
+      } else {
+        Err(meta.error("expected `name` or `arity`"))
+      }
+    })?;
+  }
+
+  let symbol = Ident::new(&format!("{}_SYMBOL", screaming_snake_case(&ident.to_string())), ident.span());
+
+  Ok(quote! {
+    #[allow(non_upper_case_globals)]
+    #visibility static #symbol: ::mod2lib::abstractions::Lazy<::mod2lib::api::symbol::Symbol> =
+      ::mod2lib::abstractions::Lazy::new(|| {
+        let mut symbol = ::mod2lib::api::symbol::Symbol::new(
+          ::mod2lib::IString::from(#name),
+          ::mod2lib::api::Arity::Value(#arity)
+        );
+        symbol.attributes  = ::mod2lib::api::symbol::SymbolAttribute::Constructor.into();
+        symbol.symbol_type = ::mod2lib::api::symbol::SymbolType::Data;
+        symbol
+      });
+
+    impl ::mod2lib::api::atom::DataAtom for #ident {
+      fn as_any(&self) -> &dyn ::core::any::Any {
+        self
+      }
+
+      fn eq(&self, other: &dyn ::mod2lib::api::atom::DataAtom) -> bool {
+        other.as_any().downcast_ref::<#ident>().is_some_and(|other| self == other)
+      }
+
+      fn hash_data(&self, mut state: &mut dyn ::core::hash::Hasher) {
+        ::core::hash::Hash::hash(self, &mut state)
+      }
+
+      fn symbol(&self) -> ::mod2lib::api::symbol::SymbolPtr {
+        let symbol: *const ::mod2lib::api::symbol::Symbol = &*#symbol;
+        symbol as ::mod2lib::api::symbol::SymbolPtr
+      }
+    }
+
+    impl ::core::convert::From<#ident> for ::mod2lib::api::atom::Atom {
+      fn from(data: #ident) -> Self {
+        ::mod2lib::api::atom::Atom::Data(::mod2lib::macro_support::Box::new(data))
+      }
+    }
+  })
+}
+
+/// `RgbColor` becomes `RGB_COLOR`, and `HTTPStatus` becomes `HTTP_STATUS`.
+fn screaming_snake_case(name: &str) -> String {
+  let characters: Vec<char> = name.chars().collect();
+  let mut result            = String::new();
+  for (index, &character) in characters.iter().enumerate() {
+    if index > 0 && character.is_uppercase() {
+      let previous = characters[index - 1];
+      let next     = characters.get(index + 1);
+      if previous.is_lowercase() || previous.is_numeric()
+          || (previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+      {
+        result.push('_');
+      }
+    }
+    result.extend(character.to_uppercase());
+  }
+
+  result
+}
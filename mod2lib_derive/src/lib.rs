@@ -0,0 +1,143 @@
+/*!
+
+Derive macros for mod2lib. Use them through `mod2lib`, which re-exports them, rather than depending on this crate:
+
+```ignore
+use mod2lib::api::atom::DataAtom;
+
+#[derive(DataAtom, PartialEq, Eq, Hash, Debug)]
+#[data_atom(name = "Color")]
+pub struct Color(u8, u8, u8);
+
+let module = mod2lib::module!("mod UNIT is sort S . op a : -> S . endm");
+let term   = mod2lib::term!(module, "a");
+```
+
+*/
+
+mod data_atom;
+mod signature;
+mod syntax_tree;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+  parse::{Parse, ParseStream},
+  parse_macro_input,
+  DeriveInput,
+  Error,
+  Expr,
+  LitStr,
+  Token
+};
+
+use mod2lib_syntax::{parse_module, parse_term, SyntaxError};
+
+use crate::{
+  data_atom::data_atom,
+  signature::{check_module, check_term},
+  syntax_tree::{module_tokens, term_tokens}
+};
+
+/**
+Implements `DataAtom` for a struct or enum, with a static symbol for the type's atoms. See `mod2lib::api::atom`.
+
+The type must implement `Display`, which is how its atoms are printed, and `PartialEq` and `Hash`, which are how they
+are compared and hashed. It can't be generic, as each type of atom has a symbol of its own.
+
+The derive generates
+
+ - a static symbol named after the type in screaming snake case with `_SYMBOL` appended, `COLOR_SYMBOL` for `Color`,
+   with the visibility of the type,
+ - the implementation of `DataAtom`, whose `symbol` is a pointer to the static symbol, and
+ - `From<Type> for Atom`, which makes an `Atom::Data` of a value.
+
+The `data_atom` attribute sets the symbol's name, which is the name of the type by default, and its arity, which is 0
+by default: `#[data_atom(name = "Rgb", arity = 0)]`.
+*/
+#[proc_macro_derive(DataAtom, attributes(data_atom))]
+pub fn derive_data_atom(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  match data_atom(&input) {
+    Ok(tokens) => tokens.into(),
+    Err(error) => error.to_compile_error().into(),
+  }
+}
+
+
+/**
+Builds the module written in the string literal, as `parse_module` would, with the module parsed and its syntax checked
+at compile time: a module that doesn't parse, or that uses a sort, operator, or variable it doesn't declare, is a
+compile error.
+
+```ignore
+let module = module!("
+  mod PEANO is
+    sort Nat .
+    ops 0 : -> Nat .
+    op s : Nat -> Nat .
+  endm
+");
+```
+
+The macro parses the source with `mod2lib_syntax`, the parser `parse_module` uses, and expands to the module built from
+the syntax tree, so the source isn't parsed again at run time. The modules the module imports are only known at run
+time, so what is checked at compile time for a module with imports is its syntax, and the declarations the module
+makes itself.
+*/
+#[proc_macro]
+pub fn module(input: TokenStream) -> TokenStream {
+  let source = parse_macro_input!(input as LitStr);
+  let module = match parse_module(&source.value()).and_then(|module| check_module(&module).map(|_| module)) {
+    Ok(module) => module,
+    Err(error) => return compile_error(&source, error),
+  };
+  let module = module_tokens(&module);
+
+  quote!(::mod2lib::macro_support::module(#module)).into()
+}
+
+/**
+Builds the term written in the string literal over a module, as `parse_term` would, without parsing it at run time.
+
+```ignore
+let pattern = term!(module, "plus(s(X), Y:Nat)");
+```
+
+The term's syntax is checked at compile time, along with the number of arguments each operator is given agreeing
+wherever the operator occurs. The module is only known at run time, when the term is checked against the module's
+signature as `parse_term` checks it, so the expression panics with `parse_term`'s error if the term uses a sort or
+symbol the module doesn't declare, or gives an operator the wrong number of arguments.
+*/
+#[proc_macro]
+pub fn term(input: TokenStream) -> TokenStream {
+  let TermInput { module, source } = parse_macro_input!(input as TermInput);
+  let term = match parse_term(&source.value()).and_then(|term| check_term(&term).map(|_| term)) {
+    Ok(term)   => term,
+    Err(error) => return compile_error(&source, error),
+  };
+  let term = term_tokens(&term);
+
+  quote!(::mod2lib::macro_support::term(&#module, #term)).into()
+}
+
+fn compile_error(source: &LitStr, error: SyntaxError) -> TokenStream {
+  Error::new(source.span(), error).to_compile_error().into()
+}
+
+/// The input of `term!`, a module and a string literal
+struct TermInput {
+  module: Expr,
+  source: LitStr,
+}
+
+impl Parse for TermInput {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let module = input.parse()?;
+    input.parse::<Token![,]>()?;
+    let source = input.parse()?;
+    input.parse::<Option<Token![,]>>()?;
+
+    Ok(TermInput { module, source })
+  }
+}
@@ -0,0 +1,316 @@
+/*!
+
+A compile-time check of the syntax trees `mod2lib_syntax` parses against the signature they declare, for the
+`module!` and `term!` macros: that sorts, operators, and variables are declared before they are used, that operators
+are applied to as many arguments as they are declared with, and that the variables of a right-hand side occur in the
+left-hand side. Error messages match those of `mod2lib::core::parser`, which builds the module from the same tree.
+
+The parser infers the sorts of undeclared variables from the subsort relation, which the check doesn't compute, so an
+undeclared name without arguments is taken for a variable wherever it is an argument. A retract `r:A>B` is an operator
+of one argument whose sorts must be declared. A module that imports other modules may use the sorts and operators
+they declare, which aren't known at compile time, so the names a module with imports doesn't declare itself are
+assumed to be imported. Whatever the check lets through is still checked when the module is built.
+
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use mod2lib_syntax::{
+  Declaration,
+  ModuleSyntax,
+  OperatorAttribute,
+  OperatorSyntax,
+  StatementSyntax,
+  SyntaxError,
+  TermSyntax
+};
+
+/// Checks `module` against the signature it declares.
+pub(crate) fn check_module(module: &ModuleSyntax) -> Result<(), SyntaxError> {
+  // Imports can come after the declarations that use what they import.
+  let has_imports   = module.declarations.iter().any(|declaration| matches!(declaration, Declaration::Import { .. }));
+  let mut signature = Signature::new(has_imports);
+  for declaration in module.declarations.iter() {
+    match declaration {
+      Declaration::Sorts { line, names }           => signature.sorts(*line, names)?,
+      Declaration::Subsorts { line, groups }       => {
+        for sort in groups.iter().flatten() {
+          signature.sort(sort, *line)?;
+        }
+      }
+      Declaration::Operators(operators)            => signature.operators(operators)?,
+      Declaration::Variables { line, names, sort } => signature.variables(*line, names, sort)?,
+      Declaration::Statement(statement)            => signature.statement(statement)?,
+      Declaration::Import { .. }                   => {}
+    }
+  }
+
+  Ok(())
+}
+
+/// Checks that each operator of `term` is given the same number of arguments wherever it occurs, which is all that can
+/// be checked without the module.
+pub(crate) fn check_term(term: &TermSyntax) -> Result<(), SyntaxError> {
+  Signature::new(true).term(term, false).map(|_| ())
+}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, SyntaxError> {
+  Err(SyntaxError { line, message: message.into() })
+}
+
+struct Signature {
+  /// Whether names not declared by the module are assumed to be declared by an import
+  open     : bool,
+  sorts    : HashSet<String>,
+  /// The number of arguments of each operator
+  operators: HashMap<String, usize>,
+  /// The sort of each variable
+  variables: HashMap<String, String>,
+  /// The undeclared variables of the statement being checked, whose sorts the parser infers
+  inferred : HashSet<String>,
+}
+
+impl Signature {
+  fn new(open: bool) -> Self {
+    Signature {
+      open,
+      sorts    : HashSet::new(),
+      operators: HashMap::new(),
+      variables: HashMap::new(),
+      inferred : HashSet::new(),
+    }
+  }
+
+  fn sorts(&mut self, line: usize, names: &[String]) -> Result<(), SyntaxError> {
+    for name in names {
+      if !self.sorts.insert(name.clone()) {
+        return error(line, format!("sort {} is declared twice", name));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn sort(&self, name: &str, line: usize) -> Result<(), SyntaxError> {
+    match self.open || self.sorts.contains(name) {
+      true  => Ok(()),
+      false => error(line, format!("undeclared sort {}", name)),
+    }
+  }
+
+  fn operators(&mut self, syntax: &OperatorSyntax) -> Result<(), SyntaxError> {
+    let line = syntax.line;
+    for sort in syntax.domain.iter() {
+      self.sort(sort, line)?;
+    }
+    self.sort(&syntax.range, line)?;
+
+    let mut axioms = false;
+    for attribute in syntax.attributes.iter() {
+      match attribute {
+        OperatorAttribute::Associative | OperatorAttribute::Commutative | OperatorAttribute::Idempotent => {
+          axioms = true;
+        }
+        OperatorAttribute::Identity { constant, .. } => {
+          if !self.open && self.operators.get(constant) != Some(&0) {
+            return error(line, format!("the identity {} is not a declared constant", constant));
+          }
+          axioms = true;
+        }
+        _ => {}
+      }
+    }
+
+    let arity = syntax.domain.len();
+    if axioms && arity != 2 {
+      return error(line, "only binary operators can be associative, commutative, idempotent, or have an identity");
+    }
+    for name in syntax.names.iter() {
+      if self.variables.contains_key(name) {
+        return error(line, format!("{} is already declared as a variable", name));
+      }
+      if self.operators.insert(name.clone(), arity).is_some_and(|existing| existing != arity) {
+        return error(line, format!("{} is already declared with a different number of arguments", name));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn variables(&mut self, line: usize, names: &[String], sort: &str) -> Result<(), SyntaxError> {
+    self.sort(sort, line)?;
+    for name in names {
+      if self.operators.contains_key(name) {
+        return error(line, format!("{} is already declared as an operator", name));
+      }
+      if self.variables.insert(name.clone(), sort.to_string()).is_some_and(|existing| existing != sort) {
+        return error(line, format!("variable {} is already declared with a different sort", name));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn statement(&mut self, statement: &StatementSyntax) -> Result<(), SyntaxError> {
+    self.inferred.clear();
+    let lhs_variables = self.term(&statement.lhs, false)?;
+    let rhs_variables = self.term(&statement.rhs, false)?;
+
+    let lhs = &statement.lhs;
+    if lhs.args.is_empty() && (self.variables.contains_key(&lhs.name) || self.sorted_variable(&lhs.name).is_some()) {
+      return error(statement.line, "the left-hand side of a statement can't be a variable");
+    }
+    if let Some(unbound) = rhs_variables.iter().find(|&variable| !lhs_variables.contains(variable)) {
+      return error(statement.line, format!("variable {} of the right-hand side is unbound", unbound));
+    }
+
+    Ok(())
+  }
+
+  /// The name and sort of the on-the-fly variable `name`, if it is one
+  fn sorted_variable<'n>(&self, name: &'n str) -> Option<(&'n str, &'n str)> {
+    name.split_once(':')
+        .filter(|(variable, sort)| !variable.is_empty() && !sort.is_empty() && !self.operators.contains_key(name))
+  }
+
+  /// Checks `term`, returning the names of its variables. An undeclared name without arguments that is an argument is
+  /// a variable whose sort the parser infers.
+  fn term(&mut self, term: &TermSyntax, is_argument: bool) -> Result<HashSet<String>, SyntaxError> {
+    let (name, line)  = (&term.name, term.line);
+    let mut variables = HashSet::new();
+    for arg in term.args.iter() {
+      variables.extend(self.term(arg, true)?);
+    }
+
+    if let Some((from, to)) = retract_sorts(name).filter(|_| !self.operators.contains_key(name)) {
+      self.sort(from, line)?;
+      self.sort(to, line)?;
+      if term.args.len() != 1 {
+        return error(line, format!("{} takes 1 arguments but is given {}", name, term.args.len()));
+      }
+      return Ok(variables);
+    }
+
+    if let Some((variable, sort)) = self.sorted_variable(name) {
+      if !term.args.is_empty() {
+        return error(line, format!("variable {} has arguments", name));
+      }
+      self.sort(sort, line)?;
+      let full_name = match self.variables.get(variable).is_some_and(|declared| declared == sort) {
+        true  => variable.to_string(),
+        false => name.clone(),
+      };
+      variables.insert(full_name);
+      return Ok(variables);
+    }
+
+    if self.variables.contains_key(name) || self.inferred.contains(name) {
+      if !term.args.is_empty() {
+        return error(line, format!("variable {} has arguments", name));
+      }
+      variables.insert(name.clone());
+      return Ok(variables);
+    }
+    match self.operators.get(name) {
+      Some(&arity) if arity != term.args.len() => {
+        return error(line, format!("{} takes {} arguments but is given {}", name, arity, term.args.len()));
+      }
+      Some(_) => {}
+      // The operator is imported or, for `term!`, in a module not known until run time, so the number of arguments it
+      // is first given must be its arity.
+      None if self.open => { self.operators.insert(name.clone(), term.args.len()); }
+      None if is_argument && term.args.is_empty() => {
+        self.inferred.insert(name.clone());
+        variables.insert(name.clone());
+      }
+      None => return error(line, format!("undeclared operator or variable {}", name)),
+    }
+
+    Ok(variables)
+  }
+}
+
+/// The sorts of the retract `r:A>B`, if `name` is the name of one
+fn retract_sorts(name: &str) -> Option<(&str, &str)> {
+  let (from, to) = name.strip_prefix("r:")?.split_once('>')?;
+  (!from.is_empty() && !to.is_empty()).then_some((from, to))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use mod2lib_syntax::{parse_module, parse_term};
+
+  use super::*;
+
+  const PEANO: &str = "
+    mod PEANO is
+      sort Nat .
+      op 0 : -> Nat [ctor] .
+      op s : Nat -> Nat [ctor] .
+      op plus : Nat Nat -> Nat [assoc comm id: 0] .
+      vars X Y : Nat .
+      eq plus(0, Y) = Y .  *** a comment
+      eq [plus-s] : plus(s(X), Y) = s(plus(X, Y:Nat)) .
+      rl s(N:Nat) => N:Nat [prio 1 weight 2] .
+    endm
+  ";
+
+  fn check(source: &str) -> Result<(), SyntaxError> {
+    check_module(&parse_module(source)?)
+  }
+
+  fn error(source: &str) -> String {
+    check(source).expect_err("the module should not check").to_string()
+  }
+
+  #[test]
+  fn well_formed_modules_check() {
+    assert!(check(PEANO).is_ok());
+    assert!(check("mod M is sort S . op f : S -> S . eq f(f(X)) = X . endm").is_ok());
+    // Names a module doesn't declare may be imported.
+    assert!(check("mod M is including NAT . op f : Nat -> Nat . eq f(X:Nat) = plus(X:Nat, 1) . endm").is_ok());
+  }
+
+  #[test]
+  fn errors_have_the_parsers_messages_and_lines() {
+    assert_eq!(
+      error(&PEANO.replace("s(plus(X, Y:Nat))", "Z")),
+      "parse error at line 9: undeclared operator or variable Z"
+    );
+    // The parser infers the sort of `Z`.
+    assert_eq!(
+      error(&PEANO.replace("s(plus(X, Y:Nat))", "s(plus(X, Z))")),
+      "parse error at line 9: variable Z of the right-hand side is unbound"
+    );
+    assert_eq!(
+      error(&PEANO.replace("s(plus(X, Y:Nat))", "r:Nat>NzNat(X)")),
+      "parse error at line 9: undeclared sort NzNat"
+    );
+    assert_eq!(
+      error(&PEANO.replace("s(plus(X, Y:Nat))", "s(X, Y)")),
+      "parse error at line 9: s takes 1 arguments but is given 2"
+    );
+    assert_eq!(
+      error(&PEANO.replace("N:Nat [prio", "M:Nat [prio")),
+      "parse error at line 10: variable M:Nat of the right-hand side is unbound"
+    );
+    assert_eq!(
+      error(&PEANO.replace("Nat [ctor] .\n      op s", "Int [ctor] .\n      op s")),
+      "parse error at line 4: undeclared sort Int"
+    );
+    assert_eq!(
+      error(&PEANO.replace("eq plus(0, Y)", "eq Y")),
+      "parse error at line 8: the left-hand side of a statement can't be a variable"
+    );
+  }
+
+  #[test]
+  fn terms_give_operators_consistent_arguments() {
+    assert!(check_term(&parse_term("f(X:Nat, g(a))").unwrap()).is_ok());
+    assert_eq!(
+      check_term(&parse_term("f(a, f(a))").unwrap()).unwrap_err().to_string(),
+      "parse error at line 1: f takes 1 arguments but is given 2"
+    );
+  }
+}
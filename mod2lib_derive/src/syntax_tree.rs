@@ -0,0 +1,147 @@
+/*!
+
+The expressions that rebuild the syntax trees `module!` and `term!` parse at compile time when the generated code
+runs, so that the module and the term are built from the trees without parsing their source again.
+
+*/
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use mod2lib_syntax::{
+  Declaration,
+  IdentitySide,
+  ModuleSyntax,
+  OperatorAttribute,
+  OperatorSyntax,
+  StatementSyntax,
+  TermSyntax
+};
+
+pub(crate) fn module_tokens(module: &ModuleSyntax) -> TokenStream {
+  let name         = string(&module.name);
+  let declarations = module.declarations.iter().map(declaration_tokens);
+
+  quote!(::mod2lib::macro_support::syntax::ModuleSyntax {
+    name        : #name,
+    declarations: ::mod2lib::macro_support::vec![#(#declarations),*],
+  })
+}
+
+pub(crate) fn term_tokens(term: &TermSyntax) -> TokenStream {
+  let TermSyntax { name, args, line } = term;
+  let name = string(name);
+  let args = args.iter().map(term_tokens);
+
+  quote!(::mod2lib::macro_support::syntax::TermSyntax {
+    name: #name,
+    args: ::mod2lib::macro_support::vec![#(#args),*],
+    line: #line,
+  })
+}
+
+fn declaration_tokens(declaration: &Declaration) -> TokenStream {
+  match declaration {
+    Declaration::Sorts { line, names } => {
+      let names = strings(names);
+      quote!(::mod2lib::macro_support::syntax::Declaration::Sorts { line: #line, names: #names })
+    }
+    Declaration::Subsorts { line, groups } => {
+      let groups = groups.iter().map(|group| strings(group));
+      quote!(::mod2lib::macro_support::syntax::Declaration::Subsorts {
+        line  : #line,
+        groups: ::mod2lib::macro_support::vec![#(#groups),*],
+      })
+    }
+    Declaration::Operators(operators) => {
+      let operators = operator_tokens(operators);
+      quote!(::mod2lib::macro_support::syntax::Declaration::Operators(#operators))
+    }
+    Declaration::Variables { line, names, sort } => {
+      let (names, sort) = (strings(names), string(sort));
+      quote!(::mod2lib::macro_support::syntax::Declaration::Variables { line: #line, names: #names, sort: #sort })
+    }
+    Declaration::Statement(statement) => {
+      let statement = statement_tokens(statement);
+      quote!(::mod2lib::macro_support::syntax::Declaration::Statement(#statement))
+    }
+    Declaration::Import { line, name } => {
+      let name = string(name);
+      quote!(::mod2lib::macro_support::syntax::Declaration::Import { line: #line, name: #name })
+    }
+  }
+}
+
+fn operator_tokens(operators: &OperatorSyntax) -> TokenStream {
+  let OperatorSyntax { line, names, domain, range, attributes } = operators;
+  let (names, domain, range) = (strings(names), strings(domain), string(range));
+  let attributes             = attributes.iter().map(|attribute| {
+    let variant = match attribute {
+      OperatorAttribute::Constructor => quote!(Constructor),
+      OperatorAttribute::Descent     => quote!(Descent),
+      OperatorAttribute::Binder      => quote!(Binder),
+      OperatorAttribute::Latex(text) => {
+        let text = string(text);
+        quote!(Latex(#text))
+      }
+      OperatorAttribute::Strategy(strategy) => quote!(Strategy(::mod2lib::macro_support::vec![#(#strategy),*])),
+      OperatorAttribute::Associative => quote!(Associative),
+      OperatorAttribute::Commutative => quote!(Commutative),
+      OperatorAttribute::Idempotent  => quote!(Idempotent),
+      OperatorAttribute::Identity { side, constant } => {
+        let side = match side {
+          IdentitySide::Left  => quote!(Left),
+          IdentitySide::Right => quote!(Right),
+          IdentitySide::Both  => quote!(Both),
+        };
+        let constant = string(constant);
+        quote!(Identity { side: ::mod2lib::macro_support::syntax::IdentitySide::#side, constant: #constant })
+      }
+    };
+    quote!(::mod2lib::macro_support::syntax::OperatorAttribute::#variant)
+  });
+
+  quote!(::mod2lib::macro_support::syntax::OperatorSyntax {
+    line      : #line,
+    names     : #names,
+    domain    : #domain,
+    range     : #range,
+    attributes: ::mod2lib::macro_support::vec![#(#attributes),*],
+  })
+}
+
+fn statement_tokens(statement: &StatementSyntax) -> TokenStream {
+  let StatementSyntax { line, label, is_rule, lhs, rhs, selection } = statement;
+  let label     = option(label.as_ref().map(|label| string(label)));
+  let (lhs, rhs) = (term_tokens(lhs), term_tokens(rhs));
+  let selection = option(selection.map(|selection| {
+    let priority = option(selection.priority.map(|priority| quote!(#priority)));
+    let weight   = option(selection.weight.map(|weight| quote!(#weight)));
+    quote!(::mod2lib::macro_support::syntax::SelectionSyntax { priority: #priority, weight: #weight })
+  }));
+
+  quote!(::mod2lib::macro_support::syntax::StatementSyntax {
+    line     : #line,
+    label    : #label,
+    is_rule  : #is_rule,
+    lhs      : #lhs,
+    rhs      : #rhs,
+    selection: #selection,
+  })
+}
+
+fn string(text: &str) -> TokenStream {
+  quote!(::mod2lib::macro_support::String::from(#text))
+}
+
+fn strings(texts: &[String]) -> TokenStream {
+  let texts = texts.iter().map(|text| string(text));
+  quote!(::mod2lib::macro_support::vec![#(#texts),*])
+}
+
+fn option(value: Option<TokenStream>) -> TokenStream {
+  match value {
+    Some(value) => quote!(::core::option::Option::Some(#value)),
+    None        => quote!(::core::option::Option::None),
+  }
+}
@@ -0,0 +1,34 @@
+/*!
+
+The tokenizer and parser of the module language, shared by `mod2lib`, which builds modules and terms from the syntax
+trees, and by the `module!` and `term!` macros of `mod2lib_derive`, which parse their input at compile time. Keeping
+one grammar in one place means the macros accept exactly what the library's parser accepts.
+
+The parser only checks syntax. Whether a name is a declared sort, operator, or variable depends on the signature, so
+those checks happen when a module is built from its tree.
+
+*/
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod syntax;
+mod token;
+
+pub use syntax::{
+  parse_imports,
+  parse_module,
+  parse_module_tokens,
+  parse_statement_tokens,
+  parse_term,
+  Declaration,
+  IdentitySide,
+  ModuleSyntax,
+  OperatorAttribute,
+  OperatorSyntax,
+  SelectionSyntax,
+  StatementSyntax,
+  SyntaxError,
+  TermSyntax
+};
+pub use token::{lex, tokenize, Position, Span, Token};
@@ -0,0 +1,591 @@
+/*!
+
+Syntax trees of modules and terms, and the parser that reads them from tokens. The trees keep names as they are
+written, along with the lines they are on, and leave what the names refer to for the module's signature to decide.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+use crate::token::{tokenize, Token};
+
+/// A syntax error, at a line of the source starting at 1
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SyntaxError {
+  pub line   : usize,
+  pub message: String,
+}
+
+impl Display for SyntaxError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "parse error at line {}: {}", self.line, self.message)
+  }
+}
+
+impl Error for SyntaxError {}
+
+// region Syntax trees
+
+/// `mod NAME is … endm`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ModuleSyntax {
+  pub name        : String,
+  pub declarations: Vec<Declaration>,
+}
+
+/// A declaration or statement of a module, from its keyword to its period. Each has the line of its keyword.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Declaration {
+  /// `sorts S₁ … .`
+  Sorts { line: usize, names: Vec<String> },
+  /// `subsorts S₁ … < T₁ … < … .`, as the groups of sorts between the `<`s
+  Subsorts { line: usize, groups: Vec<Vec<String>> },
+  Operators(OperatorSyntax),
+  /// `vars X₁ … : S .`
+  Variables { line: usize, names: Vec<String>, sort: String },
+  Statement(StatementSyntax),
+  /// `including NAME .` or `protecting NAME .`
+  Import { line: usize, name: String },
+}
+
+/// `ops f₁ … : S₁ … -> S [attributes] .`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct OperatorSyntax {
+  pub line      : usize,
+  pub names     : Vec<String>,
+  pub domain    : Vec<String>,
+  pub range     : String,
+  pub attributes: Vec<OperatorAttribute>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum OperatorAttribute {
+  Constructor,
+  Descent,
+  Binder,
+  Latex(String),
+  /// `strat (i₁ …)`
+  Strategy(Vec<usize>),
+  Associative,
+  Commutative,
+  Idempotent,
+  /// `id: c`, `left id: c`, or `right id: c`
+  Identity { side: IdentitySide, constant: String },
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum IdentitySide {
+  Left,
+  Right,
+  Both,
+}
+
+/// `eq [label] : lhs = rhs .` or `rl [label] : lhs => rhs [prio n weight n] .`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct StatementSyntax {
+  pub line     : usize,
+  pub label    : Option<String>,
+  pub is_rule  : bool,
+  pub lhs      : TermSyntax,
+  pub rhs      : TermSyntax,
+  /// The bracketed attributes of a rule, if it has the brackets
+  pub selection: Option<SelectionSyntax>,
+}
+
+/// The `prio` and `weight` of a rule, either of which may be left out
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct SelectionSyntax {
+  pub priority: Option<i32>,
+  pub weight  : Option<u32>,
+}
+
+/// A name applied to arguments, if there are any. Whether the name is an operator, a variable, an on-the-fly variable
+/// `X:S`, a retract, or a quoted identifier is up to the signature the term is read over.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct TermSyntax {
+  pub name: String,
+  pub args: Vec<TermSyntax>,
+  /// The line of the name
+  pub line: usize,
+}
+
+// endregion Syntax trees
+
+/// Parses the module in `source`, which holds exactly one module.
+pub fn parse_module(source: &str) -> Result<ModuleSyntax, SyntaxError> {
+  parse_module_tokens(tokenize(source))
+}
+
+/// Parses a module from its tokens, as `parse_module` does.
+pub fn parse_module_tokens(tokens: Vec<(Token, usize)>) -> Result<ModuleSyntax, SyntaxError> {
+  let mut parser = Parser::new(tokens);
+  let module     = parser.module()?;
+  parser.expect_end()?;
+
+  Ok(module)
+}
+
+/// Parses the equation or rule whose tokens, from its keyword to its period, are `tokens`.
+pub fn parse_statement_tokens(tokens: Vec<(Token, usize)>) -> Result<StatementSyntax, SyntaxError> {
+  let mut parser = Parser::new(tokens);
+  let line       = parser.line();
+  let keyword    = parser.word()?;
+  let statement  = match keyword.as_str() {
+    "eq" => parser.statement(line, false)?,
+    "rl" => parser.statement(line, true)?,
+    _    => {
+      parser.position -= 1;
+      return parser.error(format!("expected an equation or rule, found `{}`", keyword));
+    }
+  };
+  parser.expect_end()?;
+
+  Ok(statement)
+}
+
+/// Parses the term in `source`.
+pub fn parse_term(source: &str) -> Result<TermSyntax, SyntaxError> {
+  let mut parser = Parser::new(tokenize(source));
+  let term       = parser.term()?;
+  parser.expect_end()?;
+
+  Ok(term)
+}
+
+/// Reads the name of the module in `source` and the names of the modules it imports directly, in order, without
+/// parsing the rest of the module, so that the imports of a module with errors in its other declarations are known.
+pub fn parse_imports(source: &str) -> Result<(String, Vec<String>), SyntaxError> {
+  let mut parser = Parser::new(tokenize(source));
+  let name       = parser.module_header()?;
+
+  // An import is a declaration, so it begins the body or follows the period ending another declaration.
+  let body        = parser.position;
+  let mut imports = Vec::new();
+  while let Some(token) = parser.next() {
+    let start  = parser.position - 1;
+    let import = matches!(&token, Token::Word(word) if is_import(word));
+    if import && (start == body || parser.tokens[start - 1].0 == Token::Period) {
+      let import = parser.word()?;
+      if !imports.contains(&import) {
+        imports.push(import);
+      }
+    }
+  }
+
+  Ok((name, imports))
+}
+
+fn is_import(word: &str) -> bool {
+  word == "including" || word == "protecting"
+}
+
+struct Parser {
+  tokens  : Vec<(Token, usize)>,
+  position: usize,
+}
+
+impl Parser {
+  fn new(tokens: Vec<(Token, usize)>) -> Self {
+    Parser { tokens, position: 0 }
+  }
+
+  // region Token helpers
+
+  /// The line of the current token, or of the last token at the end of input
+  fn line(&self) -> usize {
+    self.tokens
+        .get(self.position)
+        .or(self.tokens.last())
+        .map_or(1, |(_, line)| *line)
+  }
+
+  fn error<T>(&self, message: impl Into<String>) -> Result<T, SyntaxError> {
+    Err(SyntaxError { line: self.line(), message: message.into() })
+  }
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position).map(|(token, _)| token)
+  }
+
+  fn next(&mut self) -> Option<Token> {
+    let token = self.tokens.get(self.position).map(|(token, _)| token.clone());
+    self.position += 1;
+    token
+  }
+
+  fn is_word(&self, word: &str) -> bool {
+    matches!(self.peek(), Some(Token::Word(next)) if next == word)
+  }
+
+  fn expect(&mut self, expected: Token) -> Result<(), SyntaxError> {
+    match self.peek() {
+      Some(token) if *token == expected => {
+        self.position += 1;
+        Ok(())
+      }
+      Some(token) => self.error(format!("expected {}, found {}", expected, token)),
+      None        => self.error(format!("expected {}, found the end of the input", expected)),
+    }
+  }
+
+  fn expect_word(&mut self, expected: &str) -> Result<(), SyntaxError> {
+    self.expect(Token::Word(expected.to_string()))
+  }
+
+  fn expect_end(&self) -> Result<(), SyntaxError> {
+    match self.peek() {
+      None        => Ok(()),
+      Some(token) => self.error(format!("unexpected {} after the end", token)),
+    }
+  }
+
+  /// Takes the next token, which must be a word other than a period.
+  fn word(&mut self) -> Result<String, SyntaxError> {
+    match self.peek() {
+      Some(Token::Word(word)) => {
+        let word = word.clone();
+        self.position += 1;
+        Ok(word)
+      }
+      Some(token) => self.error(format!("expected a name, found {}", token)),
+      None        => self.error("expected a name, found the end of the input"),
+    }
+  }
+
+  /// Takes the next token, which must be a string literal.
+  fn string(&mut self) -> Result<String, SyntaxError> {
+    match self.peek() {
+      Some(Token::String(text)) => {
+        let text = text.clone();
+        self.position += 1;
+        Ok(text)
+      }
+      Some(token) => self.error(format!("expected a string, found {}", token)),
+      None        => self.error("expected a string, found the end of the input"),
+    }
+  }
+
+  /// Takes words up to, but not including, one of the `terminators`.
+  fn words_until(&mut self, terminators: &[&str]) -> Result<Vec<String>, SyntaxError> {
+    let mut words = Vec::new();
+    while !terminators.iter().any(|terminator| self.is_word(terminator)) && self.peek() != Some(&Token::Period) {
+      words.push(self.word()?);
+    }
+
+    Ok(words)
+  }
+
+  // endregion Token helpers
+
+  // region Declarations
+
+  /// Reads `mod NAME is` and returns the name.
+  fn module_header(&mut self) -> Result<String, SyntaxError> {
+    if !self.is_word("mod") && !self.is_word("fmod") {
+      return self.error("expected `mod`");
+    }
+    self.position += 1;
+    let name = self.word()?;
+    self.expect_word("is")?;
+
+    Ok(name)
+  }
+
+  fn module(&mut self) -> Result<ModuleSyntax, SyntaxError> {
+    let name             = self.module_header()?;
+    let mut declarations = Vec::new();
+
+    loop {
+      let line        = self.line();
+      let keyword     = self.word()?;
+      let declaration = match keyword.as_str() {
+        "endm" | "endfm"           => return Ok(ModuleSyntax { name, declarations }),
+        "sort" | "sorts"           => self.sorts(line)?,
+        "subsort" | "subsorts"     => self.subsorts(line)?,
+        "op" | "ops"               => self.operators(line)?,
+        "var" | "vars"             => self.variables(line)?,
+        "eq"                       => Declaration::Statement(self.statement(line, false)?),
+        "rl"                       => Declaration::Statement(self.statement(line, true)?),
+        "including" | "protecting" => {
+          let name = self.word()?;
+          self.expect(Token::Period)?;
+          Declaration::Import { line, name }
+        }
+        _                          => {
+          self.position -= 1;
+          return self.error(format!("expected a declaration, found `{}`", keyword));
+        }
+      };
+      declarations.push(declaration);
+    }
+  }
+
+  fn sorts(&mut self, line: usize) -> Result<Declaration, SyntaxError> {
+    let names = self.words_until(&[])?;
+    if names.is_empty() {
+      return self.error("expected a sort name");
+    }
+    self.expect(Token::Period)?;
+
+    Ok(Declaration::Sorts { line, names })
+  }
+
+  /// Reads `S₁ … < T₁ … < …`.
+  fn subsorts(&mut self, line: usize) -> Result<Declaration, SyntaxError> {
+    let mut groups = vec![self.words_until(&["<"])?];
+    while self.is_word("<") {
+      self.position += 1;
+      groups.push(self.words_until(&["<"])?);
+    }
+    if groups.len() < 2 || groups.iter().any(Vec::is_empty) {
+      return self.error("expected subsorts of the form `A < B`");
+    }
+    self.expect(Token::Period)?;
+
+    Ok(Declaration::Subsorts { line, groups })
+  }
+
+  /// Reads `f₁ … : S₁ … -> S [attributes]`.
+  fn operators(&mut self, line: usize) -> Result<Declaration, SyntaxError> {
+    let names = self.words_until(&[":"])?;
+    if names.is_empty() {
+      return self.error("expected an operator name");
+    }
+    self.expect_word(":")?;
+    let domain = self.words_until(&["->"])?;
+    self.expect_word("->")?;
+    let range = self.word()?;
+
+    let mut attributes = Vec::new();
+    if self.peek() == Some(&Token::OpenBracket) {
+      self.position += 1;
+      while self.peek() != Some(&Token::CloseBracket) {
+        let attribute = match self.word()?.as_str() {
+          "ctor"    => OperatorAttribute::Constructor,
+          "descent" => OperatorAttribute::Descent,
+          "binder"  => OperatorAttribute::Binder,
+          "latex"   => OperatorAttribute::Latex(self.string()?),
+          "strat"   => OperatorAttribute::Strategy(self.strategy()?),
+          "assoc"   => OperatorAttribute::Associative,
+          "comm"    => OperatorAttribute::Commutative,
+          "idem"    => OperatorAttribute::Idempotent,
+          "id:"     => OperatorAttribute::Identity { side: IdentitySide::Both, constant: self.word()? },
+          "left"    => {
+            self.expect_word("id:")?;
+            OperatorAttribute::Identity { side: IdentitySide::Left, constant: self.word()? }
+          }
+          "right"   => {
+            self.expect_word("id:")?;
+            OperatorAttribute::Identity { side: IdentitySide::Right, constant: self.word()? }
+          }
+          attribute => {
+            self.position -= 1;
+            return self.error(format!("unsupported operator attribute `{}`", attribute));
+          }
+        };
+        attributes.push(attribute);
+      }
+      self.position += 1;
+    }
+    self.expect(Token::Period)?;
+
+    Ok(Declaration::Operators(OperatorSyntax { line, names, domain, range, attributes }))
+  }
+
+  /// Reads the parenthesized argument indices of a `strat` attribute.
+  fn strategy(&mut self) -> Result<Vec<usize>, SyntaxError> {
+    self.expect(Token::Open)?;
+    let mut strategy = Vec::new();
+    while self.peek() != Some(&Token::Close) {
+      match self.word()?.parse::<usize>() {
+        Ok(index) => strategy.push(index),
+        Err(_)    => {
+          self.position -= 1;
+          return self.error("expected an argument index in a strategy");
+        }
+      }
+    }
+    self.position += 1;
+
+    Ok(strategy)
+  }
+
+  /// Reads `X₁ … : S`.
+  fn variables(&mut self, line: usize) -> Result<Declaration, SyntaxError> {
+    let names = self.words_until(&[":"])?;
+    if names.is_empty() {
+      return self.error("expected a variable name");
+    }
+    self.expect_word(":")?;
+    let sort = self.word()?;
+    self.expect(Token::Period)?;
+
+    Ok(Declaration::Variables { line, names, sort })
+  }
+
+  /// Reads `[label] : lhs = rhs`, or `=>` for a rule, with an optional label.
+  fn statement(&mut self, line: usize, is_rule: bool) -> Result<StatementSyntax, SyntaxError> {
+    let mut label = None;
+    if self.peek() == Some(&Token::OpenBracket) {
+      self.position += 1;
+      label = Some(self.word()?);
+      self.expect(Token::CloseBracket)?;
+      self.expect_word(":")?;
+    }
+
+    let lhs = self.term()?;
+    self.expect_word(if is_rule { "=>" } else { "=" })?;
+    let rhs       = self.term()?;
+    let selection = match self.peek() == Some(&Token::OpenBracket) {
+      true  => Some(self.selection(is_rule)?),
+      false => None,
+    };
+    self.expect(Token::Period)?;
+
+    Ok(StatementSyntax { line, label, is_rule, lhs, rhs, selection })
+  }
+
+  /// Reads the bracketed attributes `prio n` and `weight n` of a rule, either of which may be left out.
+  fn selection(&mut self, is_rule: bool) -> Result<SelectionSyntax, SyntaxError> {
+    if !is_rule {
+      return self.error("only rules can have a priority or weight");
+    }
+    self.expect(Token::OpenBracket)?;
+
+    let mut selection = SelectionSyntax::default();
+    while self.peek() != Some(&Token::CloseBracket) {
+      let attribute = self.word()?;
+      let value     = self.word()?;
+      let parsed    = match attribute.as_str() {
+        "prio"   => value.parse().map(|priority| selection.priority = Some(priority)).is_ok(),
+        "weight" => value.parse().map(|weight| selection.weight = Some(weight)).is_ok(),
+        _        => {
+          self.position -= 2;
+          return self.error(format!("unsupported statement attribute {}", attribute));
+        }
+      };
+      if !parsed {
+        self.position -= 1;
+        return self.error(format!("expected a number after {}, found {}", attribute, value));
+      }
+    }
+    self.position += 1;
+
+    Ok(selection)
+  }
+
+  // endregion Declarations
+
+  fn term(&mut self) -> Result<TermSyntax, SyntaxError> {
+    let line = self.line();
+    let name = self.word()?;
+
+    let mut args = Vec::new();
+    if self.peek() == Some(&Token::Open) {
+      self.position += 1;
+      loop {
+        args.push(self.term()?);
+        match self.next() {
+          Some(Token::Comma) => {}
+          Some(Token::Close) => break,
+          _                  => {
+            self.position -= 1;
+            return self.error("expected `,` or `)` in an argument list");
+          }
+        }
+      }
+    }
+
+    Ok(TermSyntax { name, args, line })
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use alloc::{string::ToString, vec};
+
+  use crate::syntax::{
+    parse_imports,
+    parse_module,
+    parse_term,
+    Declaration,
+    IdentitySide,
+    OperatorAttribute,
+    SelectionSyntax
+  };
+
+  const PEANO: &str = "
+    mod PEANO is
+      sort Nat .
+      op 0 : -> Nat [ctor] .
+      op s : Nat -> Nat [ctor strat (1 0)] .
+      op plus : Nat Nat -> Nat [assoc comm id: 0] .
+      vars X Y : Nat .
+      eq plus(0, Y) = Y .  *** a comment
+      eq [plus-s] : plus(s(X), Y) = s(plus(X, Y:Nat)) .
+      rl s(N:Nat) => N:Nat [prio 1 weight 2] .
+    endm
+  ";
+
+  fn error(source: &str) -> alloc::string::String {
+    parse_module(source).expect_err("the module should not parse").to_string()
+  }
+
+  #[test]
+  fn modules_parse_to_their_declarations() {
+    let module = parse_module(PEANO).unwrap();
+    assert_eq!(module.name, "PEANO");
+    assert_eq!(module.declarations.len(), 8);
+
+    let Declaration::Operators(plus) = &module.declarations[3] else { panic!("expected an operator declaration") };
+    assert_eq!((plus.line, plus.domain.len(), plus.range.as_str()), (6, 2, "Nat"));
+    assert_eq!(
+      plus.attributes[2],
+      OperatorAttribute::Identity { side: IdentitySide::Both, constant: "0".to_string() }
+    );
+    let Declaration::Operators(s) = &module.declarations[2] else { panic!("expected an operator declaration") };
+    assert_eq!(s.attributes[1], OperatorAttribute::Strategy(vec![1, 0]));
+
+    let Declaration::Statement(rule) = &module.declarations[7] else { panic!("expected a statement") };
+    assert!(rule.is_rule);
+    assert_eq!(rule.lhs.args[0].name, "N:Nat");
+    assert_eq!(rule.selection, Some(SelectionSyntax { priority: Some(1), weight: Some(2) }));
+    let Declaration::Statement(plus_s) = &module.declarations[6] else { panic!("expected a statement") };
+    assert_eq!((plus_s.line, plus_s.label.as_deref()), (9, Some("plus-s")));
+  }
+
+  #[test]
+  fn syntax_errors_have_lines() {
+    assert_eq!(error("mod M is sort S . op a : -> S endm"), "parse error at line 1: expected `.`, found `endm`");
+    assert_eq!(
+      error(&PEANO.replace("s(plus(X, Y:Nat))", "s(plus(X, Y:Nat)")),
+      "parse error at line 9: expected `,` or `)` in an argument list"
+    );
+    assert_eq!(
+      error(&PEANO.replace("eq plus(0, Y) = Y", "eq plus(0, Y) = Y [prio 1]")),
+      "parse error at line 8: only rules can have a priority or weight"
+    );
+    assert_eq!(
+      error(&PEANO.replace("[ctor] .\n      op s", "[ctr] .\n      op s")),
+      "parse error at line 4: unsupported operator attribute `ctr`"
+    );
+    assert_eq!(error("mod M is subsort A . endm"), "parse error at line 1: expected subsorts of the form `A < B`");
+  }
+
+  #[test]
+  fn terms_and_imports_parse_alone() {
+    let term = parse_term("f(X:Nat, g(a))").unwrap();
+    assert_eq!((term.name.as_str(), term.args.len(), term.args[1].args[0].name.as_str()), ("f", 2, "a"));
+    assert!(parse_term("f(a").is_err());
+    assert!(parse_term("f(a) b").is_err());
+
+    let source          = "mod M is including NAT . op including : -> Nat . protecting BOOL . oops";
+    let (name, imports) = parse_imports(source).unwrap();
+    assert_eq!((name.as_str(), imports), ("M", vec!["NAT".to_string(), "BOOL".to_string()]));
+  }
+}
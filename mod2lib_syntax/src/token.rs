@@ -0,0 +1,148 @@
+/*!
+
+The tokens of the module language. A period ends a statement when it stands alone or ends a word, so `eq X = 0.` and
+`eq X = 0 .` are the same. Comments begin with `***` or `---` and run to the end of the line.
+
+Lines count from 1 and columns count characters from 0.
+
+*/
+
+use core::fmt::{Display, Formatter};
+use alloc::{string::{String, ToString}, vec::Vec};
+
+/// A position in a source
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Position {
+  pub line  : usize,
+  pub column: usize,
+}
+
+/// The text of a source from `start` up to, but not including, `end`
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Span {
+  pub start: Position,
+  pub end  : Position,
+}
+
+impl Span {
+  /// The span of the columns from `start` up to `end` of the line `line`
+  pub fn on_line(line: usize, start: usize, end: usize) -> Span {
+    Span {
+      start: Position { line, column: start },
+      end  : Position { line, column: end },
+    }
+  }
+
+  #[inline(always)]
+  pub fn contains(&self, position: Position) -> bool {
+    self.start <= position && position < self.end
+  }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Token {
+  Word(String),
+  /// A string literal, `"…"`, in which `\"` and `\\` stand for `"` and `\`
+  String(String),
+  Open,
+  Close,
+  Comma,
+  OpenBracket,
+  CloseBracket,
+  Period,
+}
+
+impl Display for Token {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Token::Word(word)    => write!(f, "`{}`", word),
+      Token::String(text)  => write!(f, "{:?}", text),
+      Token::Open          => write!(f, "`(`"),
+      Token::Close         => write!(f, "`)`"),
+      Token::Comma         => write!(f, "`,`"),
+      Token::OpenBracket   => write!(f, "`[`"),
+      Token::CloseBracket  => write!(f, "`]`"),
+      Token::Period        => write!(f, "`.`"),
+    }
+  }
+}
+
+/// Splits `source` into tokens paired with their line numbers.
+pub fn tokenize(source: &str) -> Vec<(Token, usize)> {
+  lex(source).0
+             .into_iter()
+             .map(|(token, span)| (token, span.start.line))
+             .collect()
+}
+
+/// Splits `source` into tokens paired with their spans, as `tokenize` does, and also returns the spans of the comments.
+pub fn lex(source: &str) -> (Vec<(Token, Span)>, Vec<Span>) {
+  let mut tokens   = Vec::new();
+  let mut comments = Vec::new();
+
+  for (index, line) in source.lines().enumerate() {
+    let line_number = index + 1;
+    let span        = |start: usize, end: usize| Span::on_line(line_number, start, end);
+    let mut chars   = line.chars().enumerate().peekable();
+
+    while let Some(&(column, c)) = chars.peek() {
+      let token = match c {
+        c if c.is_whitespace() => { chars.next(); continue; }
+        '(' => Token::Open,
+        ')' => Token::Close,
+        ',' => Token::Comma,
+        '[' => Token::OpenBracket,
+        ']' => Token::CloseBracket,
+
+        '"' => {
+          chars.next();
+          let mut text = String::new();
+          let mut end  = line.chars().count();
+          while let Some((index, c)) = chars.next() {
+            match c {
+              '"'  => {
+                end = index + 1;
+                break;
+              }
+              '\\' => text.extend(chars.next().map(|(_, c)| c)),
+              c    => text.push(c),
+            }
+          }
+          tokens.push((Token::String(text), span(column, end)));
+          continue;
+        }
+
+        _ => {
+          let mut word = String::new();
+          while let Some(&(_, c)) = chars.peek() {
+            if c.is_whitespace() || matches!(c, '(' | ')' | ',' | '[' | ']' | '"') {
+              break;
+            }
+            word.push(c);
+            chars.next();
+          }
+
+          let end = column + word.chars().count();
+          if word.starts_with("***") || word.starts_with("---") {
+            comments.push(span(column, line.chars().count()));
+            break;
+          }
+          if word == "." {
+            tokens.push((Token::Period, span(column, end)));
+          } else if let Some(stripped) = word.strip_suffix('.') {
+            tokens.push((Token::Word(stripped.to_string()), span(column, end - 1)));
+            tokens.push((Token::Period, span(end - 1, end)));
+          } else {
+            tokens.push((Token::Word(word), span(column, end)));
+          }
+          continue;
+        }
+      };
+
+      chars.next();
+      tokens.push((token, span(column, column + 1)));
+    }
+  }
+
+  (tokens, comments)
+}
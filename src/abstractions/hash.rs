@@ -10,7 +10,7 @@ Maude:
  * The Term version is in symmetry with DagNode version.
 
  */
-use std::{
+use core::{
   hash::{BuildHasher, Hasher},
   num::Wrapping,
   ops::{BitXor, Mul, Shl, Shr},
@@ -124,12 +124,12 @@ mod tests {
   #[test]
   fn test_hash2() {
     let result = hash2(1u64, 2u64);
-    let expected = Wrapping(1u64) * Wrapping(1u64) ^ Wrapping(1u64 >> 16) ^ Wrapping(2u64);
+    let expected = (Wrapping(1u64) * Wrapping(1u64)) ^ Wrapping(1u64 >> 16) ^ Wrapping(2u64);
     assert_eq!(result, expected.0);
 
     // test with different values
     let result = hash2(123u64, 456u64);
-    let expected = Wrapping(123u64) * Wrapping(123u64) ^ Wrapping(123u64 >> 16) ^ Wrapping(456u64);
+    let expected = (Wrapping(123u64) * Wrapping(123u64)) ^ Wrapping(123u64 >> 16) ^ Wrapping(456u64);
     assert_eq!(result, expected.0);
   }
 
@@ -198,10 +198,12 @@ mod tests {
       .wrapping_add(3u64)
       .shl(7u64)
       .wrapping_add(4u64);
-    assert_eq!(hasher.finish(), hash2(v1, v2));
+    // Consistent with `test_fast_hasher`
+    let hash_result = hash3(0u64, v1, v2);
+    assert_eq!(hasher.finish(), hash_result);
 
     1u32.hash(&mut hasher);
-    assert_eq!(hasher.finish(), hash2(hash2(v1, v2), 1u64));
+    assert_eq!(hasher.finish(), hash3(hash_result, hash_result, 1u64));
   }
 
   #[test]
@@ -39,7 +39,7 @@ Both macros rely on raw pointers, making them unsafe. These macros bypass Rust
  - **No Double-Free:** Ensure that the same pointer is not freed twice.
  - **No Use-After-Free:** Once `heap_destroy!` has been called on a pointer, any attempt to dereference or use that pointer is undefined behavior.
  - **No Aliased Mutable Pointers:** Ensure that no other code holds a mutable or immutable reference to the object when
-    you call `heap_destroy!`.
+   you call `heap_destroy!`.
 
 
 ## Use Cases
@@ -60,10 +60,10 @@ This module is useful in scenarios where you need to:
 macro_rules! heap_construct {
     ($expr:expr) => {{
         // Use Box::new to create the object on the heap
-        let boxed = Box::new($expr);
+        let boxed = $crate::macro_support::Box::new($expr);
         // Convert the Box into a raw pointer, transferring ownership
         // and thus preventing automatic deallocation
-        Box::into_raw(boxed)
+        $crate::macro_support::Box::into_raw(boxed)
     }};
 }
 pub use heap_construct;
@@ -82,7 +82,7 @@ macro_rules! heap_destroy {
         // Convert the raw pointer back into a Box, taking ownership back
         // and enabling Rust's automatic memory management
         unsafe {
-            let _ = Box::from_raw($ptr);
+            let _ = $crate::macro_support::Box::from_raw($ptr);
         }
         // The Box is dropped here, and the memory is deallocated
     }};
@@ -0,0 +1,214 @@
+/*!
+
+Backend specific functionality of `IString`, the interned string type. Which crate backs `IString` is chosen with the
+`ustr` feature. Without `std`, neither crate builds, and `IString` is the type defined here, a leaked string interned in
+a global set.
+
+*/
+
+use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+#[cfg(not(feature = "std"))]
+use core::{
+  cmp::Ordering,
+  fmt::{Debug, Display, Formatter},
+  hash::{Hash, Hasher},
+  ops::Deref,
+  ptr
+};
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String};
+
+#[cfg(feature = "std")]
+use crate::abstractions::IString;
+use crate::abstractions::{Lazy, Mutex, Set};
+
+/// The name of the crate backing `IString`
+#[cfg(all(feature = "std", not(feature = "ustr")))]
+pub const ISTRING_BACKEND: &str = "string_cache";
+/// The name of the crate backing `IString`
+#[cfg(not(feature = "std"))]
+pub const ISTRING_BACKEND: &str = "builtin";
+/// The name of the crate backing `IString`
+#[cfg(feature = "ustr")]
+pub const ISTRING_BACKEND: &str = "ustr";
+
+/// A numeric id of an interned string. Equal strings have equal ids and distinct live strings have distinct ids. Ids
+/// are stable while the string is alive but not across processes, so they give a cheap total order on strings, for
+/// example to order symbols, that is not alphabetical.
+#[cfg(all(feature = "std", not(feature = "ustr")))]
+#[inline(always)]
+pub fn interned_id(string: &IString) -> u64 {
+  // An index into the static set, the inline bytes, or the address of the entry in the dynamic set
+  string.unsafe_data()
+}
+
+/// A numeric id of an interned string. Equal strings have equal ids and distinct strings have distinct ids. Ids are
+/// stable for the lifetime of the process but not across processes, so they give a cheap total order on strings, for
+/// example to order symbols, that is not alphabetical.
+#[cfg(feature = "ustr")]
+#[inline(always)]
+pub fn interned_id(string: &IString) -> u64 {
+  // `ustr` never frees its strings, so the address is unique.
+  string.as_char_ptr() as u64
+}
+
+/// A numeric id of an interned string. Equal strings have equal ids and distinct strings have distinct ids. Ids are
+/// stable for the lifetime of the process but not across processes, so they give a cheap total order on strings, for
+/// example to order symbols, that is not alphabetical.
+#[cfg(not(feature = "std"))]
+#[inline(always)]
+pub fn interned_id(string: &IString) -> u64 {
+  // Interned strings are never freed, so the address is unique.
+  string.0.as_ptr() as u64
+}
+
+/// An interned string: equal strings share one allocation, so they compare and hash by address. Interned strings are
+/// never freed.
+#[cfg(not(feature = "std"))]
+#[derive(Clone)]
+pub struct IString(&'static str);
+
+/// Every string interned as an `IString`
+#[cfg(not(feature = "std"))]
+static STRINGS: Lazy<Mutex<Set<&'static str>>> = Lazy::new(|| Mutex::new(Set::default()));
+
+#[cfg(not(feature = "std"))]
+impl IString {
+  pub fn as_str(&self) -> &'static str {
+    self.0
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<&str> for IString {
+  fn from(string: &str) -> Self {
+    let mut strings = STRINGS.lock().unwrap();
+    match strings.get(string) {
+      Some(interned) => IString(interned),
+      None => {
+        let interned: &'static str = Box::leak(Box::from(string));
+        strings.insert(interned);
+        IString(interned)
+      }
+    }
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<String> for IString {
+  fn from(string: String) -> Self {
+    IString::from(string.as_str())
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl Default for IString {
+  fn default() -> Self {
+    IString::from("")
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl Deref for IString {
+  type Target = str;
+
+  fn deref(&self) -> &str {
+    self.0
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl AsRef<str> for IString {
+  fn as_ref(&self) -> &str {
+    self.0
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl PartialEq for IString {
+  fn eq(&self, other: &Self) -> bool {
+    ptr::eq(self.0, other.0)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl Eq for IString {}
+
+#[cfg(not(feature = "std"))]
+impl PartialEq<str> for IString {
+  fn eq(&self, other: &str) -> bool {
+    self.0 == other
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl PartialEq<&str> for IString {
+  fn eq(&self, other: &&str) -> bool {
+    self.0 == *other
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl Hash for IString {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.0.as_ptr().hash(state)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl Ord for IString {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.cmp(other.0)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl PartialOrd for IString {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl Display for IString {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    Display::fmt(self.0, f)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl Debug for IString {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    Debug::fmt(self.0, f)
+  }
+}
+
+/// The strings interned with `intern`, so that a repeated name can be recognized as a hit. They are kept for the life
+/// of the process, as every string is with the `ustr` backend.
+static INTERNED: Lazy<Mutex<Set<IString>>> = Lazy::new(|| Mutex::new(Set::default()));
+static INTERN_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+static INTERN_HITS    : AtomicUsize = AtomicUsize::new(0);
+
+/// Interns `string`, counting the request and whether `string` had been interned by an earlier call. The parser and the
+/// snapshot reader intern names with this function, so the counts reported by `core::statistics` show how often names
+/// in a signature repeat. Strings interned with `IString::from` directly are not counted.
+pub fn intern(string: impl AsRef<str>) -> IString {
+  let string = IString::from(string.as_ref());
+  INTERN_REQUESTS.fetch_add(1, Relaxed);
+  if !INTERNED.lock().expect("interned string set poisoned").insert(string.clone()) {
+    INTERN_HITS.fetch_add(1, Relaxed);
+  }
+
+  string
+}
+
+/// The number of calls to `intern` and the number of those that interned a string interned by an earlier call
+pub(crate) fn intern_counts() -> (usize, usize) {
+  (INTERN_REQUESTS.load(Relaxed), INTERN_HITS.load(Relaxed))
+}
+
+/// Resets the counts returned by `intern_counts`. Strings interned before are still recognized as hits.
+pub(crate) fn reset_intern_counts() {
+  INTERN_REQUESTS.store(0, Relaxed);
+  INTERN_HITS.store(0, Relaxed);
+}
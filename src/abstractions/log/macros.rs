@@ -1,148 +1,92 @@
-//! Macros for generating log messages.
+//! Macros for generating log messages. Each expands to `__log_event!`, which emits a `tracing` event with `std` and
+//! without `std` only checks the format string and its arguments.
 
+#[doc(hidden)]
+#[cfg(feature = "std")]
 #[macro_export]
-macro_rules! critical {
-    ($threshold:expr, $($arg:tt)+) => {
+macro_rules! __log_event {
+    ($level:ident, $threshold:expr, [$($field:ident = $value:expr),*], $($arg:tt)+) => {
         {
             $crate::log::init_logger();
             tracing::event!(
-                tracing::Level::ERROR,
-                critical = true,
+                tracing::Level::$level,
+                $($field = $value,)*
                 threshold = $threshold,
                 message = format_args!($($arg)+)
             );
         }
     };
-    ($($arg:tt)+) => {
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! __log_event {
+    ($level:ident, $threshold:expr, [$($field:ident = $value:expr),*], $($arg:tt)+) => {
         {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::ERROR,
-                critical = true,
-                threshold = 0,
-                message = format_args!($($arg)+)
-            );
+            let _ = $threshold;
+            let _ = ::core::format_args!($($arg)+);
         }
     };
 }
 
+#[macro_export]
+macro_rules! critical {
+    ($threshold:expr, $($arg:tt)+) => {
+        $crate::__log_event!(ERROR, $threshold, [critical = true], $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::__log_event!(ERROR, 0, [critical = true], $($arg)+)
+    };
+}
+
 #[macro_export]
 macro_rules! error {
     ($threshold:expr, $($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::ERROR,
-                threshold = $threshold,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(ERROR, $threshold, [], $($arg)+)
     };
     ($($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::ERROR,
-                threshold = 0,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(ERROR, 0, [], $($arg)+)
     };
 }
 
 #[macro_export]
 macro_rules! warning {
     ($threshold:expr, $($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::WARN,
-                threshold = $threshold,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(WARN, $threshold, [], $($arg)+)
     };
     ($($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::WARN,
-                threshold = 0,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(WARN, 0, [], $($arg)+)
     };
 }
 
 #[macro_export]
 macro_rules! info {
     ($threshold:expr, $($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::INFO,
-                threshold = $threshold,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(INFO, $threshold, [], $($arg)+)
     };
     ($($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::INFO,
-                threshold = 0,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(INFO, 0, [], $($arg)+)
     };
 }
 
 #[macro_export]
 macro_rules! debug {
     ($threshold:expr, $($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::DEBUG,
-                threshold = $threshold,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(DEBUG, $threshold, [], $($arg)+)
     };
     ($($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::DEBUG,
-                threshold = 0,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(DEBUG, 0, [], $($arg)+)
     };
 }
 
 #[macro_export]
 macro_rules! trace {
     ($threshold:expr, $($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::TRACE,
-                threshold = $threshold,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(TRACE, $threshold, [], $($arg)+)
     };
     ($($arg:tt)+) => {
-        {
-            $crate::log::init_logger();
-            tracing::event!(
-                tracing::Level::TRACE,
-                threshold = 0,
-                message = format_args!($($arg)+)
-            );
-        }
+        $crate::__log_event!(TRACE, 0, [], $($arg)+)
     };
 }
 
@@ -106,28 +106,34 @@ fn main() {
  - **Dynamic Threshold Adjustment:** Use `set_global_logging_threshold` to change the logging threshold at runtime.
  - **Automatic Logger Initialization:** The logging macros handle logger initialization automatically; no explicit initialization is required.
  - **Thread Safety:** The global logging threshold is managed using atomic operations, ensuring thread safety.
+ - **Without `std`:** Messages are not emitted. The macros still check their format strings and arguments.
 
 */
+#[cfg(feature = "std")]
 mod formatter;
+#[cfg(feature = "std")]
 mod threshold_filter;
 mod macros;
 
-use std::sync::{
-    atomic::{AtomicU8, Ordering},
-    LazyLock
-  };
+use core::sync::atomic::{AtomicU8, Ordering};
+#[cfg(feature = "std")]
+use std::sync::LazyLock;
 
+#[cfg(feature = "std")]
 use tracing_subscriber::{
   fmt,
   layer::SubscriberExt,
   Registry
 };
 
+#[cfg(feature = "std")]
 use threshold_filter::ThresholdFilterLayer;
+#[cfg(feature = "std")]
 use formatter::CustomFieldFormatter;
 pub use macros::*;
 
 /// Used for implicit initialization.
+#[cfg(feature = "std")]
 static INIT_LOGGER: LazyLock<()> = LazyLock::new(|| {
   let subscriber = Registry::default()
       .with(ThresholdFilterLayer)
@@ -145,11 +151,16 @@ static INIT_LOGGER: LazyLock<()> = LazyLock::new(|| {
 });
 
 /// This does not need to be called directly. Initializes the logging system.
+#[cfg(feature = "std")]
 pub fn init_logger() {
   LazyLock::force(&INIT_LOGGER);
 }
 
-///
+/// This does not need to be called directly. Without `std` there is nowhere to log to, so this does nothing.
+#[cfg(not(feature = "std"))]
+pub fn init_logger() {}
+
+/// Messages at or below this level are logged.
 static GLOBAL_LOGGING_THRESHOLD: AtomicU8 = AtomicU8::new(3); // Default threshold
 
 /// Sets the global threshold before the logger is initialized.
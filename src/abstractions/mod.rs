@@ -33,33 +33,49 @@ encapsulated in the `log` module.
 mod nat_set;
 mod rccell;
 mod string_util;
+mod istring;
 mod heap;
 pub(crate) mod hash;
 mod erased;
 mod graph;
+mod platform;
 
-use std::collections::HashSet as StdHashSet;
-use std::collections::HashMap as StdHashMap;
+#[cfg(feature = "std")]
+use std::collections::{HashMap as BackingHashMap, HashSet as BackingHashSet};
+#[cfg(not(feature = "std"))]
+use hashbrown::{HashMap as BackingHashMap, HashSet as BackingHashSet};
 
 
 // Logging
 pub mod log;
 
 // Interned string. Use `DefaultAtom` for a global cache that can be used across threads. Use `Atom` for a thread-local
-// string cache.
+// string cache. The `ustr` feature selects `ustr::Ustr` instead, and without `std` the crate interns strings itself.
+#[cfg(all(feature = "std", not(feature = "ustr")))]
 pub use string_cache::DefaultAtom as IString;
-// pub use ustr::Ustr as IString;
+#[cfg(feature = "ustr")]
+pub use ustr::Ustr as IString;
+#[cfg(not(feature = "std"))]
+pub use istring::IString;
+pub use istring::{intern, interned_id, ISTRING_BACKEND};
+pub(crate) use istring::{intern_counts, reset_intern_counts};
 
 // Heap construction/destruction
+#[doc(hidden)]
 pub use heap::{heap_construct, heap_destroy};
 
+// A set of (small) natural numbers
+pub use nat_set::{NatSet, NatSetIterator};
+
+// Locks, lazy statics, thread locals, and a clock, from the standard library or, without `std`, standing in for it
+pub use platform::{Instant, Lazy, Mutex, MutexGuard};
+#[cfg(not(feature = "std"))]
+pub use platform::{LocalKey, WouldBlock};
+
 // region Items meant to be used only internally
 
 pub(crate) use graph::Graph;
 
-// A set of (small) natural numbers
-pub(crate) use nat_set::NatSet;
-
 // Reference counted pointers with mutable stable, and complementary weak pointers.
 pub(crate) use rccell::{rc_cell, RcCell, WeakCell};
 
@@ -69,9 +85,40 @@ pub(crate) use string_util::{join_string, join_iter, int_to_subscript};
 
 /// A `ThingSet` is a hash set of `*const dyn Things`. They are useful if you need to test membership but never need
 /// to access the original `Thing`.
-pub type Set<T> = StdHashSet<T>; // This replaces Maude's `PointerSet` in most situations.
-
-pub type HashMap<S, T> = StdHashMap<S, T>;
+pub type Set<T> = BackingHashSet<T, MapHasher>; // This replaces Maude's `PointerSet` in most situations.
+
+pub type HashMap<S, T> = BackingHashMap<S, T, MapHasher>;
+
+/// A view into a single entry of a `HashMap`
+#[cfg(feature = "std")]
+pub use std::collections::hash_map::Entry;
+/// A view into a single entry of a `HashMap`
+#[cfg(not(feature = "std"))]
+pub use hashbrown::hash_map::Entry;
+
+/// The hasher of `HashMap` and `Set`. It is randomly seeded unless the `deterministic` feature is enabled, in which
+/// case maps with the same contents inserted in the same order iterate in the same order in every run.
+#[cfg(all(feature = "std", not(feature = "deterministic")))]
+pub type MapHasher = std::collections::hash_map::RandomState;
+#[cfg(all(not(feature = "std"), not(feature = "deterministic")))]
+pub type MapHasher = hashbrown::DefaultHashBuilder;
+#[cfg(feature = "deterministic")]
+pub type MapHasher = FixedState;
+
+/// Builds hashers that hash equal values to equal hashes in every run.
+#[cfg(feature = "std")]
+pub type FixedState = core::hash::BuildHasherDefault<std::hash::DefaultHasher>;
+/// Builds hashers that hash equal values to equal hashes in every run.
+#[cfg(not(feature = "std"))]
+pub type FixedState = foldhash::fast::FixedState;
+
+/// Whether the library was built with the `deterministic` feature, which makes runs reproducible byte for byte: `HashMap`
+/// and `Set` iterate in the same order in every run, the symbols of a module are numbered in the order they are added to
+/// the module, and symbols with the same number are ordered by name rather than by the address of the interned name.
+///
+/// Garbage collection never changes results, but the node allocator is shared by all threads, so a test suite is
+/// only reproducible if its tests run on one thread.
+pub const DETERMINISTIC: bool = cfg!(feature = "deterministic");
 
 pub use erased::DynHash;
 
@@ -2,8 +2,18 @@
 
 A thin wrapper around BitSet (the bit-set crate). We could just use a type alias if we didn't also need a `min` method.
 
+A `NatSet` is a set of small natural numbers, like the indices of the variables occurring in a term or of the sorts
+below a sort. Its memory is proportional to its largest element. Besides the named set operations, the bitwise
+operators are overloaded: `a | b` is the union, `a & b` the intersection, `a - b` the difference, and `a ^ b` the
+symmetric difference, each with an in-place assigning form.
+
 */
 
+use core::{
+  fmt::{Debug, Formatter},
+  ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign}
+};
+
 use bit_set::BitSet;
 pub use bit_set::Iter as BitSetIterator;
 
@@ -23,9 +33,22 @@ impl NatSet {
     self.0.iter().next()
   }
 
+  /// The largest element of the set.
+  #[inline(always)]
+  pub fn max_value(&self) -> Option<usize> {
+    self.0.iter().last()
+  }
+
   #[inline(always)]
-  pub fn contains(&self, value: u8) -> bool {
-    self.0.contains(value as usize)
+  pub fn contains(&self, value: usize) -> bool {
+    self.0.contains(value)
+  }
+
+  /// The number of elements the set can hold without reallocating, that is, one more than the largest element it can
+  /// hold.
+  #[inline(always)]
+  pub fn capacity(&self) -> usize {
+    self.0.capacity()
   }
 
   /// Returns the difference with the other specified bit vector.
@@ -56,6 +79,12 @@ impl NatSet {
     new_set
   }
 
+  /// Makes this bit vector the intersection with the specified other bit vector in-place.
+  #[inline(always)]
+  pub fn intersection_in_place(&mut self, other: &NatSet) {
+    self.0.intersect_with(&other.0);
+  }
+
   #[inline(always)]
   pub fn is_disjoint(&self, other: &NatSet) -> bool {
     self.0.is_disjoint(&other.0)
@@ -77,7 +106,7 @@ impl NatSet {
   }
 
   #[inline(always)]
-  pub fn iter(&self) -> NatSetIterator {
+  pub fn iter(&self) -> NatSetIterator<'_> {
     self.0.iter()
   }
 
@@ -97,6 +126,7 @@ impl NatSet {
     self.0.remove(value)
   }
 
+  /// Reserves capacity for elements up to `len - 1`.
   #[inline(always)]
   pub fn reserve_len(&mut self, len: usize) {
     self.0.reserve_len(len)
@@ -115,15 +145,15 @@ impl NatSet {
   /// Returns the symmetric difference with the other specified bit vector.
   #[inline(always)]
   pub fn symmetric_difference(&self, other: &NatSet) -> NatSet {
-    let new_set = self.clone();
-    new_set.0.symmetric_difference(&other.0);
+    let mut new_set = self.clone();
+    new_set.0.symmetric_difference_with(&other.0);
     new_set
   }
 
-  /// Returns the symmetric difference with the other specified bit vector.
+  /// Makes this bit vector the symmetric difference with the specified other bit vector in-place.
   #[inline(always)]
   pub fn symmetric_difference_in_place(&mut self, other: &NatSet) {
-    self.0.symmetric_difference(&other.0);
+    self.0.symmetric_difference_with(&other.0);
   }
 
   /// Returns the union with the other specified bit vector.
@@ -145,3 +175,87 @@ impl NatSet {
     NatSet(BitSet::with_capacity(nbits))
   }
 }
+
+impl Debug for NatSet {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.debug_set().entries(self.iter()).finish()
+  }
+}
+
+impl<'a> IntoIterator for &'a NatSet {
+  type Item     = usize;
+  type IntoIter = NatSetIterator<'a>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl FromIterator<usize> for NatSet {
+  fn from_iter<I: IntoIterator<Item=usize>>(iter: I) -> Self {
+    let mut set = NatSet::new();
+    set.extend(iter);
+    set
+  }
+}
+
+impl Extend<usize> for NatSet {
+  fn extend<I: IntoIterator<Item=usize>>(&mut self, iter: I) {
+    for value in iter {
+      self.insert(value);
+    }
+  }
+}
+
+macro_rules! impl_set_operator {
+  ($operator:ident, $method:ident, $assign_operator:ident, $assign_method:ident, $in_place:ident, $function:ident) => {
+    impl $operator<&NatSet> for &NatSet {
+      type Output = NatSet;
+
+      #[inline(always)]
+      fn $method(self, other: &NatSet) -> NatSet {
+        self.$function(other)
+      }
+    }
+
+    impl $assign_operator<&NatSet> for NatSet {
+      #[inline(always)]
+      fn $assign_method(&mut self, other: &NatSet) {
+        self.$in_place(other)
+      }
+    }
+  };
+}
+
+impl_set_operator!(BitOr, bitor, BitOrAssign, bitor_assign, union_in_place, union);
+impl_set_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, intersection_in_place, intersection);
+impl_set_operator!(Sub, sub, SubAssign, sub_assign, difference_in_place, difference);
+impl_set_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign, symmetric_difference_in_place, symmetric_difference);
+
+
+#[cfg(test)]
+mod tests {
+  use crate::abstractions::NatSet;
+
+  #[test]
+  fn set_operations() {
+    let a: NatSet = [1, 3, 5, 200].into_iter().collect();
+    let b: NatSet = [3, 4, 5].into_iter().collect();
+
+    assert_eq!((&a | &b).iter().collect::<Vec<_>>(), vec![1, 3, 4, 5, 200]);
+    assert_eq!((&a & &b).iter().collect::<Vec<_>>(), vec![3, 5]);
+    assert_eq!((&a - &b).iter().collect::<Vec<_>>(), vec![1, 200]);
+    assert_eq!((&a ^ &b).iter().collect::<Vec<_>>(), vec![1, 4, 200]);
+
+    let mut c = a.clone();
+    c -= &b;
+    c |= &[7].into_iter().collect();
+    assert_eq!(format!("{:?}", c), "{1, 7, 200}");
+
+    assert!(a.contains(200));
+    assert_eq!((a.min_value(), a.max_value()), (Some(1), Some(200)));
+    assert!((&a & &b).is_subset(&b));
+    assert!(NatSet::with_capacity(64).capacity() >= 64);
+  }
+}
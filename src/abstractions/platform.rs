@@ -0,0 +1,108 @@
+/*!
+
+The parts of the standard library the engine needs that have no counterpart in `core` or `alloc`: locks, lazily
+initialized statics, thread locals, and a clock. With the `std` feature these are the standard library's own. Without
+it, locks and lazy statics spin, using the `spin` crate, thread locals are ordinary statics, and the clock always reads
+zero, so timings and timeouts measured with it are zero.
+
+The engine without `std` runs on a single thread, which is what makes the thread locals sound. See
+`doc/DesignIssues.md`.
+
+*/
+
+#[cfg(not(feature = "std"))]
+use core::{
+  cell::RefCell,
+  convert::Infallible,
+  time::Duration
+};
+
+#[cfg(feature = "std")]
+pub use std::{
+  sync::{LazyLock as Lazy, Mutex, MutexGuard},
+  time::Instant
+};
+
+#[cfg(not(feature = "std"))]
+pub use spin::Lazy;
+#[cfg(not(feature = "std"))]
+pub use spin::MutexGuard;
+
+/// A spin lock with the interface of `std::sync::Mutex`. It is never poisoned, so `lock` never fails.
+#[cfg(not(feature = "std"))]
+#[derive(Default)]
+pub struct Mutex<T: ?Sized>(spin::Mutex<T>);
+
+/// Returned by `Mutex::try_lock` when the lock is held.
+#[cfg(not(feature = "std"))]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct WouldBlock;
+
+#[cfg(not(feature = "std"))]
+impl<T> Mutex<T> {
+  pub const fn new(value: T) -> Self {
+    Mutex(spin::Mutex::new(value))
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: ?Sized> Mutex<T> {
+  #[inline(always)]
+  pub fn lock(&self) -> Result<MutexGuard<'_, T>, Infallible> {
+    Ok(self.0.lock())
+  }
+
+  #[inline(always)]
+  pub fn try_lock(&self) -> Result<MutexGuard<'_, T>, WouldBlock> {
+    self.0.try_lock().ok_or(WouldBlock)
+  }
+}
+
+/// Stands in for a thread local declared with `thread_local!`. Without `std` the engine runs on a single thread, so
+/// this is a lazily initialized static.
+#[cfg(not(feature = "std"))]
+pub struct LocalKey<T: 'static>(Lazy<T>);
+
+// SAFETY: Without `std` the engine runs on a single thread, so the value is never shared between threads.
+#[cfg(not(feature = "std"))]
+unsafe impl<T> Sync for LocalKey<T> {}
+
+#[cfg(not(feature = "std"))]
+impl<T: 'static> LocalKey<T> {
+  pub const fn new(init: fn() -> T) -> Self {
+    LocalKey(Lazy::new(init))
+  }
+
+  pub fn with<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> R {
+    f(&self.0)
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: 'static> LocalKey<RefCell<T>> {
+  pub fn with_borrow<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> R {
+    f(&self.0.borrow())
+  }
+
+  pub fn with_borrow_mut<F: FnOnce(&mut T) -> R, R>(&'static self, f: F) -> R {
+    f(&mut self.0.borrow_mut())
+  }
+}
+
+/// A clock that always reads zero, standing in for `std::time::Instant`
+#[cfg(not(feature = "std"))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Instant;
+
+#[cfg(not(feature = "std"))]
+impl Instant {
+  #[inline(always)]
+  pub fn now() -> Self {
+    Instant
+  }
+
+  #[inline(always)]
+  pub fn elapsed(&self) -> Duration {
+    Duration::ZERO
+  }
+}
@@ -33,15 +33,15 @@ assert!(d.upgrade().is_some());  // Use the upgrade method to get a RcCell point
 
 */
 
-use std::{
+use core::{
   cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut},
   cmp::PartialEq,
   fmt::Debug,
   hash::{Hash, Hasher},
   ops::Deref,
-  pin::Pin,
-  rc::{Rc, Weak},
+  pin::Pin
 };
+use alloc::rc::{Rc, Weak};
 
 /// Wrapper for `Rc<RefCell<T>>`.
 #[derive(Debug, Default, Eq)]
@@ -160,7 +160,7 @@ impl<T: ?Sized> RcCell<T> {
   /// let x_ref = x.try_borrow();
   /// assert!(x_ref.is_ok());
   /// ```
-  pub fn try_borrow(&self) -> Result<Ref<T>, BorrowError> {
+  pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
     self.0.try_borrow()
   }
 
@@ -176,7 +176,7 @@ impl<T: ?Sized> RcCell<T> {
   /// let mut x_ref = x.try_borrow_mut();
   /// assert!(x_ref.is_ok());
   /// ```
-  pub fn try_borrow_mut(&self) -> Result<RefMut<T>, BorrowMutError> {
+  pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
     self.0.try_borrow_mut()
   }
 
@@ -190,7 +190,7 @@ impl<T: ?Sized> RcCell<T> {
   /// let x = RcCell::new(1);
   /// let x_ref = x.borrow();
   /// ```
-  pub fn borrow(&self) -> Ref<T> {
+  pub fn borrow(&self) -> Ref<'_, T> {
     self.0.borrow()
   }
 
@@ -204,7 +204,7 @@ impl<T: ?Sized> RcCell<T> {
   /// let x = RcCell::new(1);
   /// let x_ref = x.borrow_mut();
   /// ```
-  pub fn borrow_mut(&self) -> RefMut<T> {
+  pub fn borrow_mut(&self) -> RefMut<'_, T> {
     self.0.borrow_mut()
   }
 }
@@ -244,7 +244,7 @@ impl<T: ?Sized> PartialEq for RcCell<T> {
   /// The `RefCell<T>` trait does not implement `PartialEq`, and borrowing its inner value can cause a lot of panic
   /// errors. Therefore, `PartialEq` will check that two `RefCell<T>` point to the exact same allocation.
   fn eq(&self, other: &Self) -> bool {
-    RcCell::ptr_eq(&self, &other)
+    RcCell::ptr_eq(self, other)
   }
 }
 
@@ -328,7 +328,7 @@ impl<T: ?Sized> Clone for WeakCell<T> {
 // Therefore, `PartialEq` will check that two `RefCell<T>` point to the exact same allocation.
 impl<T: ?Sized> PartialEq for WeakCell<T> {
   fn eq(&self, other: &Self) -> bool {
-    WeakCell::ptr_eq(&self, &other)
+    WeakCell::ptr_eq(self, other)
   }
 }
 
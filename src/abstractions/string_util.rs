@@ -1,5 +1,6 @@
-use std::iter::once;
-use std::fmt::Display;
+use core::iter::once;
+use alloc::string::{String, ToString};
+use core::fmt::Display;
 
 /**
 Join an iterator of strings, which doesn't exist in the stdlib. (C.f. `Vec::join(…)`)
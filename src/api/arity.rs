@@ -0,0 +1,188 @@
+/*!
+
+The arity of a symbol is the number of arguments a term headed by the symbol takes.
+
+| Arity              | Arguments            | Used by                                                        |
+|:-------------------|:---------------------|:---------------------------------------------------------------|
+| `Value(n)`         | exactly `n`          | ordinary symbols; variables and data atoms are `Value(0)`      |
+| `Variadic`         | any number           | symbols taking flattened argument lists                        |
+| `Unspecified`      | any number           | symbols declared before their arity is known                   |
+| `Any`              | any number           | the builtin sort `Any`, which matches symbols of every arity   |
+| `None`             | none                 | the builtin sort `None`                                        |
+
+Arities convert to and from integers with checked conversions. Only `Value(n)` has an integer value.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter},
+  str::FromStr
+};
+use alloc::string::{String, ToString};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum Arity {
+  /// The arity of the builtin sort `Any`. Accepts any number of arguments.
+  Any,
+  /// No arguments. Equivalent to `Value(0)` for checking arguments.
+  None,
+  /// Not yet known. Accepts any number of arguments.
+  #[default]
+  Unspecified,
+
+  /// Any number of arguments
+  Variadic,
+  /// Exactly this many arguments
+  Value(u16)
+}
+
+impl Arity {
+  /// The arity of constants, variables, and data atoms
+  pub const CONSTANT: Arity = Arity::Value(0);
+
+  /// The arity `Value(count)`, failing if `count` is too large for an arity.
+  pub fn new(count: usize) -> Result<Arity, ArityConversionError> {
+    u16::try_from(count)
+        .map(Arity::Value)
+        .map_err(|_| ArityConversionError::TooLarge(count))
+  }
+
+  /// The number of arguments, if it is fixed.
+  #[inline(always)]
+  pub fn value(&self) -> Option<u16> {
+    match self {
+      Arity::Value(value) => Some(*value),
+      Arity::None         => Some(0),
+      _                   => None
+    }
+  }
+
+  /// Whether terms take a fixed number of arguments.
+  #[inline(always)]
+  pub fn is_fixed(&self) -> bool {
+    self.value().is_some()
+  }
+
+  /// Whether a term may have `count` arguments.
+  #[inline(always)]
+  pub fn accepts(&self, count: usize) -> bool {
+    match self.value() {
+      Some(value) => value as usize == count,
+      None        => true
+    }
+  }
+
+  /// The largest number of arguments a term may have, if there is one.
+  #[inline(always)]
+  pub fn max_args(&self) -> Option<usize> {
+    self.value().map(|value| value as usize)
+  }
+}
+
+impl From<u16> for Arity {
+  #[inline(always)]
+  fn from(value: u16) -> Self {
+    Arity::Value(value)
+  }
+}
+
+impl TryFrom<usize> for Arity {
+  type Error = ArityConversionError;
+
+  #[inline(always)]
+  fn try_from(count: usize) -> Result<Self, Self::Error> {
+    Arity::new(count)
+  }
+}
+
+impl TryFrom<Arity> for u16 {
+  type Error = ArityConversionError;
+
+  fn try_from(arity: Arity) -> Result<Self, Self::Error> {
+    arity.value().ok_or(ArityConversionError::NotFixed(arity))
+  }
+}
+
+impl TryFrom<Arity> for usize {
+  type Error = ArityConversionError;
+
+  fn try_from(arity: Arity) -> Result<Self, Self::Error> {
+    u16::try_from(arity).map(|value| value as usize)
+  }
+}
+
+impl Display for Arity {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Arity::Value(value) => write!(f, "{}", value),
+      Arity::Any          => write!(f, "any"),
+      Arity::None         => write!(f, "none"),
+      Arity::Unspecified  => write!(f, "unspecified"),
+      Arity::Variadic     => write!(f, "variadic"),
+    }
+  }
+}
+
+/// Parses the format written by `Display`.
+impl FromStr for Arity {
+  type Err = ArityConversionError;
+
+  fn from_str(string: &str) -> Result<Self, Self::Err> {
+    match string {
+      "any"         => Ok(Arity::Any),
+      "none"        => Ok(Arity::None),
+      "unspecified" => Ok(Arity::Unspecified),
+      "variadic"    => Ok(Arity::Variadic),
+      number        => {
+        let count = number.parse::<usize>().map_err(|_| ArityConversionError::Malformed(number.to_string()))?;
+        Arity::new(count)
+      }
+    }
+  }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ArityConversionError {
+  /// The count is larger than the largest arity.
+  TooLarge(usize),
+  /// The arity has no integer value.
+  NotFixed(Arity),
+  /// The string is not an arity.
+  Malformed(String),
+}
+
+impl Display for ArityConversionError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      ArityConversionError::TooLarge(count)  => write!(f, "{} arguments exceed the largest arity {}", count, u16::MAX),
+      ArityConversionError::NotFixed(arity)  => write!(f, "the arity {} has no integer value", arity),
+      ArityConversionError::Malformed(string) => write!(f, "{:?} is not an arity", string),
+    }
+  }
+}
+
+impl Error for ArityConversionError {}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::api::arity::{Arity, ArityConversionError};
+
+  #[test]
+  fn arity_conversions() {
+    assert_eq!(Arity::try_from(3usize), Ok(Arity::Value(3)));
+    assert_eq!(Arity::new(70_000), Err(ArityConversionError::TooLarge(70_000)));
+    assert_eq!(u16::try_from(Arity::Value(2)), Ok(2));
+    assert_eq!(usize::try_from(Arity::Variadic), Err(ArityConversionError::NotFixed(Arity::Variadic)));
+
+    for arity in [Arity::Any, Arity::None, Arity::Unspecified, Arity::Variadic, Arity::Value(7)] {
+      assert_eq!(arity.to_string().parse::<Arity>(), Ok(arity));
+    }
+    assert!("-1".parse::<Arity>().is_err());
+
+    assert!(Arity::Variadic.accepts(5));
+    assert!(Arity::None.accepts(0) && !Arity::None.accepts(1));
+    assert_eq!(Arity::Value(2).max_args(), Some(2));
+  }
+}
@@ -0,0 +1,193 @@
+/*!
+
+Two views of the arguments of a node whose symbol is associative, for exporters and encodings that need one shape or
+the other without building a new DAG. The engine may store `f(a, b, c)` flattened, with three arguments, or nested, as
+`f(a, f(b, c))` or `f(f(a, b), c)`, and the views are the same whichever way it is stored.
+
+ - `DagNode::iter_flattened_args` iterates over the arguments of the node with the nested applications of its symbol
+   flattened, giving `a`, `b`, and `c`, lazily.
+ - `DagNode::binary_view` sees the node as nested binary applications nested to the right, `f(a, f(b, c))`. The view
+   of `f(b, c)` is made on demand by `BinaryView::right`, from the flattened arguments, which are collected once and
+   shared by the views below it.
+
+```ignore
+fn nested_to_smt(view: &BinaryView) -> String {
+  let right = match view.right() {
+    BinaryArg::Node(right) => to_smt(right),
+    BinaryArg::View(right) => nested_to_smt(&right),
+  };
+  format!("({} {} {})", view.symbol_ref().name, to_smt(view.left()), right)
+}
+```
+
+The arguments of a node whose symbol isn't associative are not flattened, and such a node has no binary view. Whether
+the symbol is also commutative makes no difference: the arguments are in the order in which they are stored.
+
+*/
+
+use alloc::{boxed::Box, rc::Rc, vec, vec::Vec};
+
+use crate::api::{
+  dag_node::{DagNode, DagNodePtr},
+  symbol::{Symbol, SymbolAttribute, SymbolPtr}
+};
+
+/// The arguments of a node with nested applications of its associative symbol flattened, left to right. See
+/// `assoc_view`.
+pub struct FlattenedArgs {
+  /// The symbol whose applications are flattened, or null if the node's symbol isn't associative
+  symbol: SymbolPtr,
+  /// The arguments still to be given of each application being flattened, innermost last
+  stack : Vec<Box<dyn Iterator<Item=DagNodePtr>>>,
+}
+
+impl Iterator for FlattenedArgs {
+  type Item = DagNodePtr;
+
+  fn next(&mut self) -> Option<DagNodePtr> {
+    while let Some(args) = self.stack.last_mut() {
+      let Some(arg) = args.next() else {
+        self.stack.pop();
+        continue;
+      };
+      if !self.symbol.is_null() && core::ptr::addr_eq(unsafe{ &*arg }.symbol(), self.symbol) {
+        self.stack.push(unsafe{ &*arg }.iter_args());
+        continue;
+      }
+      return Some(arg);
+    }
+
+    None
+  }
+}
+
+/// A node whose symbol is associative, seen as an application of its symbol to two arguments nested to the right. See
+/// `assoc_view`.
+#[derive(Clone)]
+pub struct BinaryView {
+  symbol: SymbolPtr,
+  /// The flattened arguments of the node the view was made from, shared with the views made from this one
+  args  : Rc<[DagNodePtr]>,
+  /// The index in `args` of the view's left argument. The view covers the arguments from here on, at least two.
+  start : usize,
+}
+
+/// The right argument of a `BinaryView`
+#[derive(Clone)]
+pub enum BinaryArg {
+  /// The last of the flattened arguments
+  Node(DagNodePtr),
+  /// The application of the symbol to the flattened arguments after the left one
+  View(BinaryView),
+}
+
+impl BinaryView {
+  #[inline(always)]
+  pub fn symbol(&self) -> SymbolPtr {
+    self.symbol
+  }
+
+  #[inline(always)]
+  pub fn symbol_ref(&self) -> &Symbol {
+    unsafe{ &*self.symbol }
+  }
+
+  /// The left argument, which is never an application of the symbol
+  #[inline(always)]
+  pub fn left(&self) -> DagNodePtr {
+    self.args[self.start]
+  }
+
+  /// The right argument, a view itself unless only one flattened argument is left
+  pub fn right(&self) -> BinaryArg {
+    match self.start + 2 == self.args.len() {
+      true  => BinaryArg::Node(self.args[self.start + 1]),
+      false => BinaryArg::View(BinaryView { start: self.start + 1, ..self.clone() }),
+    }
+  }
+
+  /// The number of flattened arguments the view covers, which is one more than the number of applications it stands
+  /// for
+  #[inline(always)]
+  pub fn arg_count(&self) -> usize {
+    self.args.len() - self.start
+  }
+
+  /// The flattened arguments the view covers
+  #[inline(always)]
+  pub fn args(&self) -> &[DagNodePtr] {
+    &self.args[self.start..]
+  }
+}
+
+impl dyn DagNode + '_ {
+  /// The arguments of the node, with nested applications of its symbol flattened if the symbol is associative. See
+  /// `assoc_view`.
+  pub fn iter_flattened_args(&self) -> FlattenedArgs {
+    let symbol = match self.symbol_ref().attributes.contains(SymbolAttribute::Associative) {
+      true  => self.symbol(),
+      false => core::ptr::null_mut(),
+    };
+
+    FlattenedArgs { symbol, stack: vec![self.iter_args()] }
+  }
+
+  /// The node seen as nested binary applications of its symbol, nested to the right, if its symbol is associative and
+  /// it has at least two flattened arguments. See `assoc_view`.
+  pub fn binary_view(&self) -> Option<BinaryView> {
+    if !self.symbol_ref().attributes.contains(SymbolAttribute::Associative) {
+      return None;
+    }
+    let args: Rc<[DagNodePtr]> = self.iter_flattened_args().collect();
+
+    (args.len() >= 2).then(|| BinaryView { symbol: self.symbol(), args, start: 0 })
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    api::{
+      assoc_view::{BinaryArg, BinaryView},
+      dag_node::DagNodePtr
+    },
+    core::{parser::{parse_module, parse_term}, proof_trace::dag_to_string}
+  };
+
+  /// The view written as nested binary applications
+  fn binary(view: &BinaryView) -> String {
+    let right = match view.right() {
+      BinaryArg::Node(right) => dag_to_string(right),
+      BinaryArg::View(right) => binary(&right),
+    };
+    format!("{}({}, {})", view.symbol_ref().name, dag_to_string(view.left()), right)
+  }
+
+  #[test]
+  fn associative_arguments_have_both_shapes() {
+    let module = parse_module("
+      mod LISTS is
+        sort L .
+        ops a b c d : -> L .
+        op g : L -> L .
+        op f : L L -> L [assoc] .
+      endm
+    ").unwrap();
+    let dag = |term: &str| parse_term(&module, term).unwrap().term_to_dag(false);
+    let names = |args: &mut dyn Iterator<Item=DagNodePtr>| args.map(dag_to_string).collect::<Vec<_>>().join(" ");
+
+    for term in ["f(f(a, g(f(b, c))), f(c, d))", "f(a, f(g(f(b, c)), f(c, d)))"] {
+      let node = unsafe{ &*dag(term) };
+      assert_eq!(names(&mut node.iter_flattened_args()), "a g(f(b, c)) c d");
+      let view = node.binary_view().unwrap();
+      assert_eq!(view.arg_count(), 4);
+      assert_eq!(binary(&view), "f(a, f(g(f(b, c)), f(c, d)))");
+    }
+
+    // Only associative symbols are flattened.
+    let node = unsafe{ &*dag("g(f(a, b))") };
+    assert_eq!(names(&mut node.iter_flattened_args()), "f(a, b)");
+    assert!(node.binary_view().is_none());
+  }
+}
@@ -10,14 +10,49 @@ An `Atom` is an indivisible (atomic) element. An `Atom` can be
 
 The type system is independent of these categories; that is, sorts/kinds are not represented by any of these entities.
 
+# Building Terms
+
+An atom is a front end for terms. `Atom::to_term` makes the term of a variable or symbol atom, and `Atom::apply` applies
+a symbol atom to the terms of its arguments, so that whole terms can be built out of atoms:
+
+```ignore
+let term = f.apply(vec![x.to_term()?, a.to_term()?])?; // f(X, a)
+```
+
+Sequence variables and data atoms have no terms yet, and converting them is an error.
+
 # Defining Data Atoms
 
-The `DataAtom` trait can be implemented for any type that implements `Display + Any + Eq + Hash`.
+The `DataAtom` trait can be implemented for any type that implements `Display + Any + Eq + Hash`. It is easiest to
+derive it, which also defines the static symbol of the type's atoms, and `From` the type for `Atom`:
+
+```
+use core::fmt::{Display, Formatter};
+use mod2lib::api::atom::{Atom, DataAtom};
+
+#[derive(DataAtom, PartialEq, Eq, Hash, Debug)]
+#[data_atom(name = "Rgb")]
+pub struct Color(u8, u8, u8);
+
+impl Display for Color {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+  }
+}
+
+let atom = Atom::from(Color(255, 0, 0));
+assert_eq!(atom.to_string(), "#ff0000");
+assert_eq!(COLOR_SYMBOL.name.as_ref(), "Rgb");
+```
+
+The attribute is optional. It sets the name of the symbol, the name of the type by default, and its arity, 0 by
+default. The `implement_data_atom!` macro wraps an existing type in a newtype that implements the trait instead.
 
 */
 
-use std::{
+use core::{
   any::Any,
+  error::Error,
   fmt::{
     Debug,
     Display,
@@ -25,18 +60,50 @@ use std::{
   },
   hash::{Hash, Hasher}
 };
+use alloc::{boxed::Box, string::{String, ToString}, vec::Vec};
 
 use crate::{
+  abstractions::IString,
   api::{
+    free_theory::FreeTerm,
     symbol::{
       Symbol,
       SymbolPtr
     },
-    variable::Variable
-  },
-  abstractions::DynHash
+    term::BxTerm,
+    variable::{Variable, VariableType},
+    variable_theory::VariableTerm
+  }
 };
 
+/// Returned when an atom, or the application of one, has no term.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum AtomConversionError {
+  /// Sequence variables have no terms yet.
+  SequenceVariable(IString),
+  /// Data atoms have no terms yet. Holds the atom as displayed.
+  DataAtom(String),
+  /// Only symbols can be applied to arguments. Holds the atom as displayed.
+  NotASymbol(String),
+  /// The symbol doesn't take that many arguments.
+  WrongArity { symbol: IString, args: usize },
+}
+
+impl Display for AtomConversionError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      AtomConversionError::SequenceVariable(name) => write!(f, "the sequence variable {} has no term", name),
+      AtomConversionError::DataAtom(atom)         => write!(f, "the data atom {} has no term", atom),
+      AtomConversionError::NotASymbol(atom)       => write!(f, "{} is not a symbol and can't be applied", atom),
+      AtomConversionError::WrongArity { symbol, args } => {
+        write!(f, "{} can't be applied to {} arguments", symbol, args)
+      }
+    }
+  }
+}
+
+impl Error for AtomConversionError {}
+
 
 #[derive(Eq, PartialEq, Hash)]
 pub enum Atom {
@@ -54,10 +121,41 @@ impl Atom {
       Atom::Data(data) => data.symbol()
     }
   }
+
+  /// The term of the atom: a variable term for a variable, and a constant for a symbol.
+  pub fn to_term(&self) -> Result<BxTerm, AtomConversionError> {
+    self.apply(Vec::new())
+  }
+
+  /// The term applying the atom, which must be a symbol, to `args`. With no arguments, this is `to_term`.
+  pub fn apply(&self, args: Vec<BxTerm>) -> Result<BxTerm, AtomConversionError> {
+    match self {
+      Atom::Variable(variable) if args.is_empty() => match variable.variable_type {
+        VariableType::Blank => Ok(Box::new(VariableTerm::new(variable.symbol))),
+        _                   => Err(AtomConversionError::SequenceVariable(unsafe{ &*variable.symbol }.name.clone())),
+      },
+
+      Atom::Symbol(symbol) => {
+        let symbol_ref = unsafe{ &**symbol };
+        if symbol_ref.is_variable() && args.is_empty() {
+          return Ok(Box::new(VariableTerm::new(*symbol)));
+        }
+        if symbol_ref.is_variable() || !symbol_ref.arity.accepts(args.len()) {
+          return Err(AtomConversionError::WrongArity { symbol: symbol_ref.name.clone(), args: args.len() });
+        }
+
+        Ok(FreeTerm::with_args(*symbol, args))
+      }
+
+      Atom::Data(data) => Err(AtomConversionError::DataAtom(data.to_string())),
+
+      Atom::Variable(_) => Err(AtomConversionError::NotASymbol(self.to_string())),
+    }
+  }
 }
 
 impl Display for Atom {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     match self {
 
       Atom::Variable(v) => {
@@ -80,7 +178,7 @@ impl Display for Atom {
 }
 
 impl Debug for Atom {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     Display::fmt(self, f)
   }
 }
@@ -97,8 +195,8 @@ pub trait DataAtom: Display {
   /// Equality between atoms of this kind
   fn eq(&self, other: &dyn DataAtom) -> bool;
 
-  // / Forward hasher to data
-  // fn hash(&self, state: &mut dyn Hasher);
+  /// Hashes the data, consistently with `eq`
+  fn hash_data(&self, state: &mut dyn Hasher);
 
   /// The symbol associated to this data type
   fn symbol(&self) -> SymbolPtr;
@@ -118,9 +216,9 @@ impl Eq for Box<dyn DataAtom> {}
 //   }
 // }
 
-impl Hash for dyn DataAtom {
+impl Hash for dyn DataAtom + '_ {
   fn hash<H: Hasher>(&self, state: &mut H) {
-    self.dyn_hash(state)
+    self.hash_data(state)
   }
 }
 
@@ -151,9 +249,9 @@ When called, the macro expands into the following:
 ## Example Usage
 
 ```rust
-use std::any::Any;
-use once_cell::sync::Lazy;
+use core::any::Any;
 use paste::paste;
+use mod2lib::abstractions::Lazy;
 use mod2lib::api::atom::{implement_data_atom, Atom, DataAtom};
 use mod2lib::api::symbol::{Symbol, SymbolPtr, SymbolType, SymbolAttribute};
 use mod2lib::IString;
@@ -173,7 +271,6 @@ fn main() {
 ```
 
 */
-
 #[macro_export]
 macro_rules! implement_data_atom {
   ($name:ident, $type:ty) => {
@@ -190,8 +287,8 @@ macro_rules! implement_data_atom {
       }
     }
 
-    impl std::fmt::Display for [<$name Atom>] {
-      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    impl core::fmt::Display for [<$name Atom>] {
+      fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.0)
       }
     }
@@ -210,8 +307,12 @@ macro_rules! implement_data_atom {
         }
       }
 
+      fn hash_data(&self, mut state: &mut dyn core::hash::Hasher) {
+        core::hash::Hash::hash(&self.0, &mut state)
+      }
+
       fn symbol(&self) -> SymbolPtr {
-        let ptr: *const Symbol = unsafe{&*[<$name:snake:upper _SYMBOL>]};
+        let ptr: *const Symbol = &*[<$name:snake:upper _SYMBOL>];
         ptr as SymbolPtr
       }
     }
@@ -220,10 +321,12 @@ macro_rules! implement_data_atom {
     pub static [<$name:snake:upper _SYMBOL>]: Lazy<Symbol> = Lazy::new(|| {
       Symbol {
           name:        IString::from(stringify!($name)),  // Use the identifier as a string
-          // ToDo: What should the arity of a `DataAtom` have?
-          arity:       Arity::Unspecified,
+          arity:       Arity::CONSTANT,
           attributes:  SymbolAttribute::Constructor.into(),
           symbol_type: SymbolType::Data,
+          sort_table:  Default::default(),
+          latex:       None,
+          strategy:    Vec::new(),
           hash_value:  0
         }
     });
@@ -232,5 +335,90 @@ macro_rules! implement_data_atom {
   }; // end macro pattern
 }
 pub use implement_data_atom;
+pub use mod2lib_derive::DataAtom;
+
 
+#[cfg(test)]
+mod tests {
+  use std::fmt::{Display, Formatter};
 
+  use crate::{
+    abstractions::IString,
+    api::{
+      atom::{Atom, AtomConversionError, DataAtom},
+      integer::integer,
+      symbol::{Symbol, SymbolPtr, SymbolType},
+      variable::{Variable, VariableType},
+      Arity
+    },
+    core::format::{FormatStyle, Formattable}
+  };
+
+  #[test]
+  fn atoms_build_terms() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let (f, a, x): (SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut a, &mut x);
+
+    let variable = |variable_type| Atom::Variable(Variable { symbol: x, variable_type });
+    let args     = vec![variable(VariableType::Blank).to_term().unwrap(), Atom::Symbol(a).to_term().unwrap()];
+    let term     = Atom::Symbol(f).apply(args).unwrap();
+    assert_eq!(term.repr(FormatStyle::Simple), "f(X, a)");
+    assert!(term.iter_args().next().unwrap().is_variable());
+
+    assert_eq!(
+      Atom::Symbol(f).to_term().err(),
+      Some(AtomConversionError::WrongArity { symbol: IString::from("f"), args: 0 })
+    );
+    assert_eq!(
+      variable(VariableType::Sequence).to_term().err(),
+      Some(AtomConversionError::SequenceVariable(IString::from("X")))
+    );
+    assert!(matches!(
+      variable(VariableType::Blank).apply(vec![Atom::Symbol(a).to_term().unwrap()]),
+      Err(AtomConversionError::NotASymbol(_))
+    ));
+    assert_eq!(Atom::Data(integer(7)).to_term().err(), Some(AtomConversionError::DataAtom("7".to_string())));
+  }
+
+  #[derive(DataAtom, PartialEq, Eq, Hash, Debug)]
+  struct RgbColor(u8, u8, u8);
+
+  #[derive(DataAtom, PartialEq, Eq, Hash, Debug)]
+  #[data_atom(name = "Pair", arity = 2)]
+  enum HTTPStatus {
+    Ok,
+    NotFound,
+  }
+
+  impl Display for RgbColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+      write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+  }
+
+  impl Display for HTTPStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+      write!(f, "{:?}", self)
+    }
+  }
+
+  #[test]
+  fn data_atoms_can_be_derived() {
+    let Atom::Data(red) = Atom::from(RgbColor(255, 0, 0)) else { unreachable!() };
+    assert_eq!(red.to_string(), "#ff0000");
+    assert!(DataAtom::eq(red.as_ref(), &RgbColor(255, 0, 0)));
+    assert!(!DataAtom::eq(red.as_ref(), &RgbColor(0, 0, 255)));
+    assert!(!DataAtom::eq(red.as_ref(), &HTTPStatus::Ok));
+
+    let symbol = unsafe{ &*red.symbol() };
+    assert!(std::ptr::eq(symbol, &*RGB_COLOR_SYMBOL));
+    assert_eq!(symbol.name.as_ref(), "RgbColor");
+    assert_eq!((symbol.arity, symbol.symbol_type), (Arity::Value(0), SymbolType::Data));
+
+    let status = unsafe{ &*HTTPStatus::NotFound.symbol() };
+    assert_eq!((status.name.as_ref(), status.arity), ("Pair", Arity::Value(2)));
+    assert!(std::ptr::eq(status, &*HTTP_STATUS_SYMBOL));
+  }
+}
@@ -0,0 +1,155 @@
+/*!
+
+A `CheckedDagNodePtr` is a node pointer that knows when it has gone stale. A `DagNodePtr` kept past the death of its
+node points at whatever node the allocator puts in its storage next, and using it corrupts the DAG far from the bug. A
+checked handle instead records the generation of its node when it is made (see `allocator::generation`), and
+dereferencing it after the storage has been reused panics, saying where the handle was made, for which node, and where
+it was used.
+
+Generations are only kept in builds with the `gc_debug` feature. Without it a checked handle is just a pointer.
+
+```ignore
+let handle = CheckedDagNodePtr::new(node);
+// ... the node dies, and a collection reuses its storage ...
+handle.symbol_ref(); // Panics: "stale node pointer to f, made at src/lib.rs:10:16 in generation 0, used at ..."
+```
+
+A handle can't tell whether its node is dead until the storage is reused, so holding a node in a `RootContainer` is
+still the way to keep it alive.
+
+*/
+
+use core::{
+  fmt::{Debug, Formatter},
+  ops::Deref,
+  panic::Location
+};
+
+use crate::{
+  abstractions::IString,
+  api::dag_node::{DagNode, DagNodePtr},
+  core::{
+    allocator::generation,
+    dag_node_core::{DagNodeCore, ThinDagNodePtr}
+  }
+};
+
+/// A node pointer that panics when used after its node's storage has been reused. See the module documentation.
+#[derive(Clone)]
+pub struct CheckedDagNodePtr {
+  node      : DagNodePtr,
+  generation: u64,
+  /// The symbol of the node when the handle was made
+  symbol    : IString,
+  /// Where the handle was made
+  origin    : &'static Location<'static>,
+}
+
+impl CheckedDagNodePtr {
+  /// A handle to `node`, which must be alive.
+  #[track_caller]
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn new(node: DagNodePtr) -> Self {
+    let node_ref = unsafe{ &*node };
+    CheckedDagNodePtr {
+      node,
+      generation: generation(thin(node)),
+      symbol    : node_ref.symbol_ref().name.clone(),
+      origin    : Location::caller(),
+    }
+  }
+
+  /// Whether the node's storage has been reused since the handle was made
+  #[inline(always)]
+  pub fn is_stale(&self) -> bool {
+    generation(thin(self.node)) != self.generation
+  }
+
+  /// The pointer, after checking that it isn't stale.
+  #[track_caller]
+  pub fn get(&self) -> DagNodePtr {
+    self.check();
+    self.node
+  }
+
+  /// The pointer, without checking it.
+  #[inline(always)]
+  pub fn get_unchecked(&self) -> DagNodePtr {
+    self.node
+  }
+
+  #[track_caller]
+  fn check(&self) {
+    let current = generation(thin(self.node));
+    if current != self.generation {
+      panic!(
+        "stale node pointer to {}, made at {} in generation {}, used at {} in generation {}",
+        self.symbol,
+        self.origin,
+        self.generation,
+        Location::caller(),
+        current
+      );
+    }
+  }
+}
+
+impl Deref for CheckedDagNodePtr {
+  type Target = dyn DagNode;
+
+  #[track_caller]
+  fn deref(&self) -> &Self::Target {
+    self.check();
+    unsafe{ &*self.node }
+  }
+}
+
+impl Debug for CheckedDagNodePtr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "CheckedDagNodePtr({}, generation {}, made at {})", self.symbol, self.generation, self.origin)
+  }
+}
+
+#[inline(always)]
+fn thin(node: DagNodePtr) -> ThinDagNodePtr {
+  unsafe{ &*node }.core() as *const DagNodeCore as ThinDagNodePtr
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      checked_dag_node::CheckedDagNodePtr,
+      free_theory::FreeDagNode,
+      symbol::{Symbol, SymbolPtr},
+      Arity
+    },
+    core::{allocator::advance_generation, RootContainer}
+  };
+
+  #[test]
+  fn reused_storage_makes_handles_stale() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(0));
+    let f: SymbolPtr = &mut f;
+
+    // Rooted, so that only the simulated reuse below can reuse it
+    let node   = FreeDagNode::new(f);
+    let _root  = RootContainer::new(node);
+    let handle = CheckedDagNodePtr::new(node);
+    assert!(!handle.is_stale());
+    assert!(std::ptr::addr_eq(handle.get(), node));
+    assert_eq!(handle.symbol_ref().name.as_ref(), "f");
+
+    // Simulate the allocator handing the storage out again.
+    advance_generation(super::thin(node));
+    assert_eq!(handle.is_stale(), cfg!(feature = "gc_debug"));
+    if cfg!(feature = "gc_debug") {
+      let message = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handle.get())).unwrap_err();
+      let message = message.downcast_ref::<String>().unwrap();
+      assert!(message.starts_with("stale node pointer to f, made at src/api/checked_dag_node.rs:"));
+      assert!(std::ptr::addr_eq(CheckedDagNodePtr::new(node).get(), handle.get_unchecked()));
+    }
+  }
+}
@@ -9,21 +9,26 @@ Requirements of implementers of `DagNode`:
 
 */
 
-use std::{
-  rc::Rc,
+use core::{
+  error::Error,
   fmt::{Display, Formatter},
   cmp::Ordering,
   any::Any,
   iter::Iterator
 };
-use std::cmp::max;
+use core::cmp::max;
+use alloc::{boxed::Box, rc::Rc, string::{String, ToString}, vec::Vec};
 use crate::{
+  abstractions::{IString, Set},
   api::{
     Arity,
-    symbol::{Symbol, SymbolPtr}
+    atom::DataAtom,
+    symbol::{Symbol, SymbolPtr},
+    value::Value
   },
   core::{
     allocator::{
+      debug_id,
       gc_vector::{GCVector, GCVectorRefMut},
       increment_active_node_count
     },
@@ -33,16 +38,32 @@ use crate::{
       DagNodeFlags,
       ThinDagNodePtr
     },
-    sort::{SortPtr, SpecialSort}
+    sort::{SortPtr, SpecialSort},
+    theory_table::theory_entry
   }
 };
-use crate::core::format::{FormatStyle, Formattable};
+use crate::core::format::{format_dag, FormatStyle, Formattable, PrintSettings};
 
 // A fat pointer to a trait object. For a thin pointer to a DagNodeCore, use ThinDagNodePtr
 pub type DagNodePtr    = *mut dyn DagNode;
 pub type DagNodeVector = GCVector<DagNodePtr>;
 pub type DagNodeVectorRefMut = GCVectorRefMut<DagNodePtr>;
 
+/// An argument was inserted into a node that already has as many arguments as the arity of its symbol.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ArityExceeded {
+  pub symbol: IString,
+  pub arity : u16,
+}
+
+impl Display for ArityExceeded {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "tried to give {} more than {} arguments", self.symbol, self.arity)
+  }
+}
+
+impl Error for ArityExceeded {}
+
 /// Commutative theories can have this more compact representation
 #[derive(Copy, Clone)]
 pub struct DagPair {
@@ -64,7 +85,7 @@ pub trait DagNode {
   // }
 
   #[inline(always)]
-  fn as_ptr_mut(&self) -> *mut dyn DagNode where Self: Sized {
+  fn as_ptr_mut(&self) -> *mut dyn DagNode where Self: Sized + 'static {
     let ptr: *const dyn DagNode = self;
     ptr as *mut dyn DagNode
   }
@@ -76,6 +97,19 @@ pub trait DagNode {
   fn core(&self) -> &DagNodeCore;
   fn core_mut(&mut self) -> &mut DagNodeCore;
 
+  /// An id for the node that stays the same for as long as the node is alive, for debug output and tools that need to
+  /// cross-reference nodes. Ids are allocated the first time they are asked for.
+  #[inline(always)]
+  fn debug_id(&self) -> u64 {
+    debug_id(self.core() as *const DagNodeCore as ThinDagNodePtr)
+  }
+
+  /// The data atom the node holds, if it is a `DataDagNode`
+  #[inline(always)]
+  fn data(&self) -> Option<&dyn DataAtom> {
+    None
+  }
+
   #[inline(always)]
   fn arity(&self) -> Arity {
     if self.symbol().is_null() {
@@ -87,39 +121,53 @@ pub trait DagNode {
 
   /// MUST override if Self::args is not a `DagNodeVector`
   fn iter_args(&self) -> Box<dyn Iterator<Item=DagNodePtr>> {
-    // For assertions
-    // ToDo: These assertions will need to change for variadic nodes.
-    let arity = if let Arity::Value(v) = self.arity() { v } else { 0 };
+    // For assertions. A symbol without a fixed arity can have any number of arguments in any representation.
+    let arity = self.arity().value();
 
     // The empty case
     if self.core().args.is_null() {
-      assert_eq!(arity, 0);
-      Box::new(std::iter::empty())
+      assert!(arity.is_none_or(|arity| arity == 0));
+      Box::new(core::iter::empty())
     } // The vector case
     else if self.core().needs_destruction() {
-      assert!(arity>1);
+      assert!(arity.is_none_or(|arity| arity > 1));
 
       let node_vector: DagNodeVectorRefMut = arg_to_node_vec(self.core().args);
       Box::new(node_vector.iter().cloned())
-    } // The singleton case
+    } // The inline case
     else {
-      assert_eq!(arity, 1);
-
-      let node = arg_to_dag_node(self.core().args);
+      assert!(arity.is_none_or(|arity| arity == 1 || arity == 2));
+
+      // Make fat pointers to the inline nodes and return an iterator that owns them. (An iterator over a slice of
+      // local nodes would outlive them.)
+      let first  = arg_to_dag_node(self.core().args);
+      let second = self.core().second_arg;
+      let second = (!second.is_null()).then(|| arg_to_dag_node(second));
+      Box::new(core::iter::once(first).chain(second))
+    }
+  }
 
-      // Make a fat pointer to the single node and return an iterator to it. This allows `self` to
-      // escape the method. Of course, `self` actually points to a `DagNode` that is valid for the
-      // lifetime of the program, so even in the event of the GC equivalent of a dangling pointer
-      // or use after free, this will be safe. (Strictly speaking, it's probably UB.)
-      let v = unsafe { std::slice::from_raw_parts(&node, 1) };
-      Box::new(v.iter().map(|n| *n))
+  /// Appends `new_child` to the arguments of `self`. Fails without changing `self` if `self` already has as many
+  /// arguments as the arity of its symbol. Symbols of arity `Arity::None` take no arguments, and symbols of any other
+  /// non-numeric arity take any number.
+  fn insert_child(&mut self, new_child: DagNodePtr) -> Result<(), ArityExceeded> {
+    if let Some(arity) = self.arity().value() {
+      if self.len() >= arity as usize {
+        return Err(ArityExceeded { symbol: self.symbol_ref().name.clone(), arity });
+      }
     }
+
+    self.insert_child_unchecked(new_child);
+    Ok(())
   }
 
+  /// Appends `new_child` to the arguments of `self` without checking the arity of its symbol. This is the fast path
+  /// used by the engine, which only builds nodes with the right number of arguments.
+  ///
   /// MUST override if Self::args is not a `DagNodeVector`
-  fn insert_child(&mut self, new_child: DagNodePtr){
+  fn insert_child_unchecked(&mut self, new_child: DagNodePtr){
     assert!(!new_child.is_null());
-    // ToDo: Should we signal if arity is exceeded and/or DagNodeVector needs to reallocate?
+    self.invalidate_sort();
 
     // Empty case
     if self.core().args.is_null() {
@@ -129,21 +177,27 @@ pub trait DagNode {
       let node_vec: DagNodeVectorRefMut = arg_to_node_vec(self.core_mut().args);
       node_vec.push(new_child)
     } // Singleton case
+    else if self.core().second_arg.is_null() {
+      self.core_mut().second_arg = new_child as *mut u8;
+    } // Inline pair case
     else {
-      let existing_child = arg_to_dag_node(self.core_mut().args);
-      let arity = if let Arity::Value(arity) = self.arity() {
-        max(arity, 2)
+      let first  = arg_to_dag_node(self.core().args);
+      let second = arg_to_dag_node(self.core().second_arg);
+      let arity  = if let Arity::Value(arity) = self.arity() {
+        max(arity, 3)
       } else {
-        2
+        4
       };
       let node_vec   = DagNodeVector::with_capacity(arity as usize);
 
-      node_vec.push(existing_child);
+      node_vec.push(first);
+      node_vec.push(second);
       node_vec.push(new_child);
 
       // Take ownership
       self.set_flags(DagNodeFlag::NeedsDestruction.into());
-      self.core_mut().args = (node_vec as *mut DagNodeVector) as *mut u8;
+      self.core_mut().args       = (node_vec as *mut DagNodeVector) as *mut u8;
+      self.core_mut().second_arg = core::ptr::null_mut();
     }
   }
 
@@ -151,63 +205,75 @@ pub trait DagNode {
   /// Gives the top symbol of this term.
   #[inline(always)]
   fn symbol(&self) -> SymbolPtr {
-    self.core().symbol
+    self.core().symbol()
   }
 
 
   /// Convenience method that gets and dereferences the symbol
   #[inline(always)]
   fn symbol_ref(&self) -> &Symbol {
-    unsafe{ &*self.core().symbol }
+    unsafe{ &*self.core().symbol() }
   }
 
 
   // Todo: Is this needed?
   #[inline(always)]
   fn symbol_ref_mut(&mut self) -> &mut Symbol {
-    unsafe{ &mut *self.core().symbol }
+    unsafe{ &mut *self.core().symbol() }
   }
 
 
-  // ToDo: Implement DagNodeCore::get_sort() when `SortTable` is implemented.
-  #[inline(always)]
+  /// The sort of the node, or `None` if it has not been computed or can't be. See `compute_sort`.
   fn get_sort(&self) -> Option<SortPtr> {
-    unimplemented!()
-    /*
-    let sort_index: i8 = self.sort_index();
-    match sort_index {
-      n if n == SpecialSort::Unknown as i8 => None,
-
-      // Anything else
-      sort_index => {
-        self
-            .dag_node_members()
-            .top_symbol
-            .sort_table()
-            .range_component()
-            .borrow()
-            .sort(sort_index)
-            .upgrade()
-      }
+    self.symbol_ref().sort_table.sort_with_index(self.sort_index())
+  }
+
+
+  /// Computes the sort of the term rooted at `self` bottom up from the operator declarations of its symbols, caching the
+  /// sort of each node in the node. An ill-sorted term is given the error sort of its kind. The sort is `None` if it
+  /// can't be determined, as for a term whose symbol has no declarations.
+  fn compute_sort(&mut self) -> Option<SortPtr> {
+    if self.sort_index() == SpecialSort::Unknown {
+      let arg_sorts: Vec<Option<SortPtr>> = self.iter_args().map(|arg| unsafe{ &mut *arg }.compute_sort()).collect();
+      let sort_index = self.symbol_ref().sort_table.compute_sort_index(&arg_sorts);
+      self.set_sort_index(sort_index);
     }
-    */
+
+    self.get_sort()
+  }
+
+
+  /// Whether the node's sort is the error sort of its kind, that is, whether the term is known to be ill-sorted. The
+  /// sort must have been computed by `compute_sort`.
+  #[inline(always)]
+  fn is_error_sorted(&self) -> bool {
+    self.sort_index() == SpecialSort::ErrorSort
+  }
+
+
+  /// Forgets the cached sort, which `compute_sort` then computes again. A node whose arguments change must forget its
+  /// sort, as must the nodes above it.
+  #[inline(always)]
+  fn invalidate_sort(&mut self) {
+    self.set_sort_index(SpecialSort::Unknown as i8);
   }
 
 
   #[inline(always)]
   fn set_sort_index(&mut self, sort_index: i8) {
-    self.core_mut().sort_index = sort_index;
+    self.core_mut().set_sort_index(sort_index);
   }
 
 
   #[inline(always)]
   fn sort_index(&self) -> i8 {
-    self.core().sort_index
+    self.core().sort_index()
   }
 
 
   /// Set the sort to best of original and other sorts
   #[inline(always)]
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
   fn upgrade_sort_index(&mut self, other: DagNodePtr) {
     let other = unsafe{ &*other };
     //  We set the sort to best of original and other sorts; that is:
@@ -236,26 +302,37 @@ pub trait DagNode {
 
       node_vector.len()
 
-    } // The singleton case
+    } // The inline case
     else {
-      1
+      1 + !self.core().second_arg.is_null() as usize
     }
   }
 
+  /// Whether the node has no arguments
+  #[inline(always)]
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
 
   #[inline(always)]
   fn flags(&self) -> DagNodeFlags {
-    self.core().flags
+    self.core().flags()
+  }
+
+  #[inline(always)]
+  fn is_reduced(&self) -> bool {
+    self.core().flags().contains(DagNodeFlag::Reduced)
   }
 
   #[inline(always)]
   fn set_reduced(&mut self) {
-    self.core_mut().flags.insert(DagNodeFlag::Reduced);
+    self.core_mut().insert_flags(DagNodeFlag::Reduced.into());
   }
 
   #[inline(always)]
   fn set_flags(&mut self, flags: DagNodeFlags) {
-    self.core_mut().flags.insert(flags);
+    self.core_mut().insert_flags(flags);
   }
 
   // endregion Accessors
@@ -263,6 +340,7 @@ pub trait DagNode {
   // region Comparison
 
   /// Defines a partial order on `DagNode`s by comparing the symbols and the arguments recursively.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
   fn compare(&self, other: DagNodePtr) -> Ordering {
     let other_ref = unsafe{ &*other };
     let symbol_order = self.symbol_ref().compare(other_ref.symbol_ref());
@@ -274,13 +352,14 @@ pub trait DagNode {
   }
 
   /// MUST be overridden is `Self::args` something other than a `DagNodeVector`.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
   fn compare_arguments(&self, other: DagNodePtr) -> Ordering {
     let other  = unsafe { &*other };
     let symbol = self.symbol_ref();
 
     assert!(symbol == other.symbol_ref(), "symbols differ");
 
-    if other.core().theory_tag != self.core().theory_tag {
+    if other.core().theory() != self.core().theory() {
       // if let None = other.as_any().downcast_ref::<FreeDagNode>() {
       // Not even the same theory. It's not clear what to return in this case, so just compare symbols.
       return symbol.compare(other.symbol_ref());
@@ -290,20 +369,29 @@ pub trait DagNode {
       return Ordering::Equal;
     }
     else if (false, false) == (self.core().args.is_null(), other.core().args.is_null()) {
-      if (false, false) == (self.core().needs_destruction(), other.core().needs_destruction()) {
-        // Singleton case
-        let self_child     : DagNodePtr = arg_to_dag_node(self.core().args);
-        let other_child_ptr: DagNodePtr = arg_to_dag_node(other.core().args);
-
-        // Fast bail on equal pointers.
-        if std::ptr::addr_eq(self_child, other_child_ptr) {
-          return Ordering::Equal; // Points to same node
+      if (true, true) != (self.core().needs_destruction(), other.core().needs_destruction()) {
+        // The inline case, or a node with inline arguments and one whose arguments outgrew them
+        let (self_len, other_len) = (self.len(), other.len());
+        if self_len != other_len {
+          return self_len.cmp(&other_len);
         }
-        let self_child = unsafe{ &*self_child };
 
-        return self_child.compare(other_child_ptr);
+        for index in 0..self_len {
+          let self_child : DagNodePtr = self.core().arg(index);
+          let other_child: DagNodePtr = other.core().arg(index);
+
+          // Fast bail on equal pointers.
+          if core::ptr::addr_eq(self_child, other_child) {
+            continue; // Points to same node
+          }
+
+          let result = unsafe{ &*self_child }.compare(other_child);
+          if result.is_ne() {
+            return result;
+          }
+        }
       }
-      else if (true, true) == (self.core().needs_destruction(), other.core().needs_destruction()) {
+      else {
         // The vector case
         let self_arg_vec : &DagNodeVector = arg_to_node_vec(self.core().args);
         let other_arg_vec: &DagNodeVector = arg_to_node_vec(other.core().args);
@@ -321,7 +409,7 @@ pub trait DagNode {
         // Maude structures this so that it's tail call optimized, but we don't have that guarantee.
         for (&p, &q) in self_arg_vec.iter().zip(other_arg_vec.iter()) {
           // Fast bail on equal pointers.
-          if std::ptr::addr_eq(p, q) {
+          if core::ptr::addr_eq(p, q) {
             continue; // Points to same node
           }
 
@@ -347,9 +435,10 @@ pub trait DagNode {
     Ordering::Equal
   }
 
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
   fn equals(&self, other: DagNodePtr) -> bool {
     let other_ref = unsafe{ &*other };
-    std::ptr::addr_eq(self, other)
+    core::ptr::addr_eq(self, other)
       || (
       self.symbol_ref() == other_ref.symbol_ref()
           && self.compare_arguments(other) == Ordering::Equal
@@ -367,7 +456,7 @@ pub trait DagNode {
     }
 
     increment_active_node_count();
-    self.core_mut().flags.insert(DagNodeFlag::Marked);
+    self.core_mut().insert_flags(DagNodeFlag::Marked.into());
 
     // The empty case
     if self.core().args.is_null() {
@@ -379,7 +468,7 @@ pub trait DagNode {
 
         for node_ptr in node_vector.iter_mut() {
           assert!(!node_ptr.is_null());
-          let node: &mut dyn DagNode = unsafe { node_ptr.as_mut_unchecked() };
+          let node: &mut dyn DagNode = unsafe { &mut **node_ptr };
           node.mark();
         }
       }
@@ -387,30 +476,49 @@ pub trait DagNode {
       let node_vector: DagNodeVectorRefMut = arg_to_node_vec(self.core().args);
       self.core_mut().args = (node_vector.copy() as *mut DagNodeVector) as *mut u8;
 
-    } // The singleton case
+    } // The inline case
     else {
       // Guaranteed to be non-null.
-      let node: &mut dyn DagNode = unsafe{ arg_to_dag_node(self.core().args).as_mut_unchecked() };
+      let node: &mut dyn DagNode = unsafe{ &mut *arg_to_dag_node(self.core().args) };
       node.mark();
+      if !self.core().second_arg.is_null() {
+        let node: &mut dyn DagNode = unsafe{ &mut *arg_to_dag_node(self.core().second_arg) };
+        node.mark();
+      }
     }
   } // end fn mark
 
   // endregion GC related methods
 }
 
+impl dyn DagNode + '_ {
+  /// The data atom the node holds, if it is a `DataDagNode` holding an atom of type `T`.
+  ///
+  /// ```ignore
+  /// let count = unsafe{ &*normal_form }.as_data::<IntegerAtom>().map(|integer| integer.0);
+  /// ```
+  pub fn as_data<T: DataAtom + 'static>(&self) -> Option<&T> {
+    self.data()?.as_any().downcast_ref::<T>()
+  }
+
+  /// The value of the DAG rooted at the node. See `Value`.
+  pub fn to_value(&self) -> Value {
+    Value::of(self)
+  }
+}
+
 impl Formattable for &dyn DagNode {
-  fn repr(&self, _style: FormatStyle) -> String {
-    if self.symbol().is_null() {
-      "null".to_string()
-    }
-    else {
-      format!("<{}>", self.symbol_ref())
+  /// Writes the whole DAG rooted at the node, in prefix notation unless `style` is `Pretty` or `Latex`.
+  fn repr_with(&self, style: FormatStyle, settings: &PrintSettings) -> String {
+    match self.symbol().is_null() {
+      true  => "null".to_string(),
+      false => format_dag(*self, style, settings)
     }
   }
 }
 
 impl Display for dyn DagNode {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     write!(f, "{}", self.repr(FormatStyle::default()))
   }
 }
@@ -418,6 +526,46 @@ impl Display for dyn DagNode {
 
 // Unsafe private free functions
 
+/// Returns the subterms of the term rooted at `node` at which it becomes ill-sorted, that is, the error-sorted subterms
+/// whose arguments are all well-sorted, computing sorts as necessary. A well-sorted term has none. Shared subterms are
+/// reported once.
+pub fn ill_sorted_subterms(node: DagNodePtr) -> Vec<DagNodePtr> {
+  let mut visited = Set::default();
+  let mut found   = Vec::new();
+
+  unsafe{ &mut *node }.compute_sort();
+  collect_ill_sorted(node, &mut visited, &mut found);
+
+  found
+}
+
+fn collect_ill_sorted(node: DagNodePtr, visited: &mut Set<*mut u8>, found: &mut Vec<DagNodePtr>) {
+  let node_ref = unsafe{ &*node };
+  if !node_ref.is_error_sorted() || !visited.insert(node as *mut u8) {
+    return;
+  }
+
+  let mut ill_sorted_args = false;
+  for arg in node_ref.iter_args() {
+    if unsafe{ &*arg }.is_error_sorted() {
+      ill_sorted_args = true;
+      collect_ill_sorted(arg, visited, found);
+    }
+  }
+
+  if !ill_sorted_args {
+    found.push(node);
+  }
+}
+
+/// The pointer to `node`, which must live in the node allocator, as every node does, so that the pointer outlives the
+/// reference. Predicates on bindings are given references, and this lets them build on the binding.
+#[inline(always)]
+pub fn as_node_ptr(node: &dyn DagNode) -> DagNodePtr {
+  let node: *const dyn DagNode = node;
+  unsafe{ core::mem::transmute::<*const dyn DagNode, DagNodePtr>(node) }
+}
+
 /// Reinterprets `args` as a `DagNodePtr`. The caller MUST be sure
 /// that `args` actually points to a `DagNode`.
 #[inline(always)]
@@ -429,5 +577,5 @@ pub fn arg_to_dag_node(args: *mut u8) -> DagNodePtr {
 /// be sure that `args` actually points to a `DagNodeVector`.
 #[inline(always)]
 pub fn arg_to_node_vec(args: *mut u8) -> DagNodeVectorRefMut {
-  unsafe { (args as *mut DagNodeVector).as_mut_unchecked() }
+  unsafe { &mut *(args as *mut DagNodeVector) }
 }
@@ -0,0 +1,221 @@
+/*!
+
+Streaming traversal of DAGs. `DagNode::visit` walks the DAG below a node depth first, left to right, and reports each
+node to a `DagVisitor` as it goes, so exporters and analyzers can process a DAG of any size without building strings or
+vectors of nodes.
+
+ - `pre` is called when the traversal first reaches a node, before its arguments.
+ - `post` is called after its arguments.
+ - `shared` is called, instead of `pre` and `post`, each time the traversal reaches a node again through another
+   parent. Its arguments are not visited again, so each distinct node is visited once.
+
+Each callback is given the node's depth, the root having depth zero, and returns a `VisitControl` that can skip the
+arguments of a node or stop the traversal. The traversal keeps its own stack rather than recursing, so deep DAGs don't
+overflow the call stack, and remembers the addresses of the nodes it has visited to recognize shared ones.
+
+```
+use mod2lib::prelude::*;
+
+struct CountNodes(usize);
+
+impl DagVisitor for CountNodes {
+  fn pre(&mut self, _node: &dyn DagNode, _depth: usize) -> VisitControl {
+    self.0 += 1;
+    VisitControl::Continue
+  }
+}
+
+let module = parse_module("
+  mod SHARING is
+    sort S .
+    ops a : -> S .
+    op f : S S -> S .
+  endm
+").unwrap();
+let root        = parse_term(&module, "f(a, a)").unwrap().term_to_dag(false);
+let mut counter = CountNodes(0);
+unsafe{ &*root }.visit(&mut counter);
+assert_eq!(counter.0, 2);
+```
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::Set,
+  api::dag_node::DagNode
+};
+
+/// What the traversal does after a `DagVisitor` callback
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum VisitControl {
+  #[default]
+  Continue,
+  /// Don't visit the arguments of the node. Only meaningful from `pre`; `post` is still called.
+  SkipChildren,
+  /// End the traversal now.
+  Stop,
+}
+
+/// Receives the nodes of a DAG from `DagNode::visit`. Every callback continues by default. See the module
+/// documentation.
+pub trait DagVisitor {
+  /// Called when the traversal first reaches `node`, before its arguments
+  fn pre(&mut self, _node: &dyn DagNode, _depth: usize) -> VisitControl {
+    VisitControl::Continue
+  }
+
+  /// Called after the arguments of `node` have been visited or skipped
+  fn post(&mut self, _node: &dyn DagNode, _depth: usize) -> VisitControl {
+    VisitControl::Continue
+  }
+
+  /// Called when the traversal reaches `node`, which it has already visited, through another parent
+  fn shared(&mut self, _node: &dyn DagNode, _depth: usize) -> VisitControl {
+    VisitControl::Continue
+  }
+}
+
+impl dyn DagNode {
+  /// Walks the DAG below the node, reporting its nodes to `visitor`. Returns `false` if the visitor stopped the
+  /// traversal. See `dag_visitor`.
+  pub fn visit<V: DagVisitor + ?Sized>(&self, visitor: &mut V) -> bool {
+    let mut visited: Set<*const u8>         = Set::default();
+    // Each node being visited, with the index of its next argument
+    let mut stack: Vec<(&dyn DagNode, usize)> = Vec::new();
+
+    if !enter(self, 0, visitor, &mut visited, &mut stack) {
+      return false;
+    }
+
+    while let Some((node, next)) = stack.last_mut() {
+      let node = *node;
+      if *next < node.len() {
+        let arg = node.core().arg(*next);
+        *next += 1;
+        if !enter(unsafe{ &*arg }, stack.len(), visitor, &mut visited, &mut stack) {
+          return false;
+        }
+      } else {
+        stack.pop();
+        if visitor.post(node, stack.len()) == VisitControl::Stop {
+          return false;
+        }
+      }
+    }
+
+    true
+  }
+}
+
+/// Reports `node`, reached at `depth`, to `visitor`, and pushes it if its arguments are to be visited. Returns `false`
+/// if the visitor stopped the traversal.
+fn enter<'n, V: DagVisitor + ?Sized>(
+  node   : &'n dyn DagNode,
+  depth  : usize,
+  visitor: &mut V,
+  visited: &mut Set<*const u8>,
+  stack  : &mut Vec<(&'n dyn DagNode, usize)>
+) -> bool
+{
+  if !visited.insert(node as *const dyn DagNode as *const u8) {
+    return visitor.shared(node, depth) != VisitControl::Stop;
+  }
+
+  match visitor.pre(node, depth) {
+    VisitControl::Continue     => {
+      stack.push((node, 0));
+      true
+    }
+    VisitControl::SkipChildren => visitor.post(node, depth) != VisitControl::Stop,
+    VisitControl::Stop         => false,
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    api::{
+      dag_node::DagNode,
+      dag_visitor::{DagVisitor, VisitControl}
+    },
+    core::{
+      ingest::{ingest, NodeRecord},
+      parser::parse_module,
+      RootContainer
+    }
+  };
+
+  /// Records the events of a traversal, stopping after `limit` of them
+  struct Recorder {
+    events: Vec<String>,
+    limit : usize,
+    skip  : &'static str,
+  }
+
+  impl Recorder {
+    fn record(&mut self, event: &str, node: &dyn DagNode, depth: usize) -> VisitControl {
+      self.events.push(format!("{} {} {}", event, node.symbol_ref().name, depth));
+      match self.events.len() >= self.limit {
+        true  => VisitControl::Stop,
+        false => VisitControl::Continue,
+      }
+    }
+  }
+
+  impl DagVisitor for Recorder {
+    fn pre(&mut self, node: &dyn DagNode, depth: usize) -> VisitControl {
+      match self.record("pre", node, depth) {
+        VisitControl::Continue if node.symbol_ref().name.as_ref() == self.skip => VisitControl::SkipChildren,
+        control => control,
+      }
+    }
+
+    fn post(&mut self, node: &dyn DagNode, depth: usize) -> VisitControl {
+      self.record("post", node, depth)
+    }
+
+    fn shared(&mut self, node: &dyn DagNode, depth: usize) -> VisitControl {
+      self.record("shared", node, depth)
+    }
+  }
+
+  #[test]
+  fn visitors_see_each_node_once() {
+    let module = parse_module("
+      mod VISIT is
+        sort S .
+        op a : -> S .
+        op g : S -> S .
+        op f : S S -> S .
+      endm
+    ").unwrap();
+    let symbols = ["a", "g", "f"].map(|name| module.arena.symbol(&name.into()).unwrap());
+    // f(g(a), g(a)) with g(a) shared
+    let records = [
+      NodeRecord::Symbol { symbol: 0, arity: 0 },
+      NodeRecord::Symbol { symbol: 1, arity: 1 },
+      NodeRecord::Shared(1),
+      NodeRecord::Symbol { symbol: 2, arity: 2 },
+    ];
+    let root  = ingest(&symbols, &[], &records).unwrap();
+    let _root = RootContainer::new(root);
+
+    let mut recorder = Recorder { events: Vec::new(), limit: usize::MAX, skip: "" };
+    assert!(unsafe{ &*root }.visit(&mut recorder));
+    assert_eq!(
+      recorder.events,
+      ["pre f 0", "pre g 1", "pre a 2", "post a 2", "post g 1", "shared g 1", "post f 0"]
+    );
+
+    let mut recorder = Recorder { events: Vec::new(), limit: usize::MAX, skip: "g" };
+    assert!(unsafe{ &*root }.visit(&mut recorder));
+    assert_eq!(recorder.events, ["pre f 0", "pre g 1", "post g 1", "shared g 1", "post f 0"]);
+
+    let mut recorder = Recorder { events: Vec::new(), limit: 3, skip: "" };
+    assert!(!unsafe{ &*root }.visit(&mut recorder));
+    assert_eq!(recorder.events.len(), 3);
+  }
+}
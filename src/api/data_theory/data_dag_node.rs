@@ -0,0 +1,145 @@
+use core::{
+  any::Any,
+  cmp::Ordering
+};
+use alloc::{boxed::Box, string::ToString};
+
+use crate::{
+  api::{
+    atom::DataAtom,
+    dag_node::{DagNode, DagNodePtr}
+  },
+  core::{
+    allocator::increment_active_node_count,
+    dag_node_core::{DagNodeCore, DagNodeFlag, DagNodeTheory},
+    sort::{predicate::active_sort_of, SortPtr}
+  }
+};
+
+/// A data atom that occurs in a DAG, a constant headed by the atom's symbol that holds the atom itself.
+///
+/// The `args` of the core point to a `DataPayload` rather than to arguments, so the node overrides every `DagNode`
+/// method that reads them. The node owns the payload, which is dropped with the node when it is swept, so it is flagged
+/// `NeedsDestruction`.
+///
+/// A data atom's sort depends on the module, through its sort predicates (see `sort::predicate`), so the node has no
+/// sort until one is set, as `Module::sort_data_atoms` does, or computed by `compute_sort`. The sort is kept in the
+/// payload, as a sort index is relative to the sort table of the node's symbol, and data symbols are shared by every
+/// module.
+#[repr(transparent)]
+pub struct DataDagNode(DagNodeCore);
+
+struct DataPayload {
+  atom: Box<dyn DataAtom>,
+  sort: Option<SortPtr>,
+}
+
+impl DataDagNode {
+  #[allow(clippy::new_ret_no_self)]
+  pub fn new(atom: Box<dyn DataAtom>) -> DagNodePtr {
+    let node     = DagNodeCore::with_theory(atom.symbol(), DagNodeTheory::Data);
+    let node_mut = unsafe{ &mut *node };
+
+    // A symbol with arity above 1 got an argument vector, which lives in the arena and needs no freeing.
+    node_mut.core_mut().args = Box::into_raw(Box::new(DataPayload { atom, sort: None })) as *mut u8;
+    node_mut.set_flags(DagNodeFlag::NeedsDestruction.into());
+
+    node
+  }
+
+  #[inline(always)]
+  pub fn atom(&self) -> &dyn DataAtom {
+    self.payload().atom.as_ref()
+  }
+
+  /// Sets the sort of the node, which has none to begin with. See the type's documentation.
+  #[inline(always)]
+  pub fn set_sort(&mut self, sort: Option<SortPtr>) {
+    unsafe{ &mut *(self.0.args as *mut DataPayload) }.sort = sort;
+  }
+
+  #[inline(always)]
+  fn payload(&self) -> &DataPayload {
+    unsafe{ &*(self.0.args as *const DataPayload) }
+  }
+}
+
+impl DagNode for DataDagNode {
+  #[inline(always)]
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  #[inline(always)]
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  #[inline(always)]
+  fn core(&self) -> &DagNodeCore {
+    &self.0
+  }
+
+  #[inline(always)]
+  fn core_mut(&mut self) -> &mut DagNodeCore {
+    &mut self.0
+  }
+
+  #[inline(always)]
+  fn data(&self) -> Option<&dyn DataAtom> {
+    Some(self.atom())
+  }
+
+  #[inline(always)]
+  fn get_sort(&self) -> Option<SortPtr> {
+    self.payload().sort
+  }
+
+  /// A node without a sort is sorted by the sort predicates of the module being reduced. See `sort::predicate`.
+  fn compute_sort(&mut self) -> Option<SortPtr> {
+    if self.payload().sort.is_none() {
+      let sort = active_sort_of(self.atom());
+      self.set_sort(sort);
+    }
+
+    self.payload().sort
+  }
+
+  fn iter_args(&self) -> Box<dyn Iterator<Item=DagNodePtr>> {
+    Box::new(core::iter::empty())
+  }
+
+  fn insert_child_unchecked(&mut self, _new_child: DagNodePtr) {
+    panic!("tried to give the data atom {} an argument", self.atom());
+  }
+
+  #[inline(always)]
+  fn len(&self) -> usize {
+    0
+  }
+
+  /// Atoms are only equal or not, so unequal atoms of the same symbol are ordered by their text.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  fn compare_arguments(&self, other: DagNodePtr) -> Ordering {
+    match unsafe{ &*other }.data() {
+      Some(atom) if self.atom().eq(atom) => Ordering::Equal,
+      Some(atom) => self.atom().to_string().cmp(&atom.to_string()),
+      None       => self.symbol_ref().compare(unsafe{ &*other }.symbol_ref()),
+    }
+  }
+
+  fn mark(&'static mut self) {
+    if self.core().is_marked() {
+      return;
+    }
+
+    increment_active_node_count();
+    self.core_mut().insert_flags(DagNodeFlag::Marked.into());
+  }
+}
+
+impl Drop for DataDagNode {
+  fn drop(&mut self) {
+    drop(unsafe{ Box::from_raw(self.0.args as *mut DataPayload) });
+  }
+}
@@ -0,0 +1,3 @@
+mod data_dag_node;
+
+pub use data_dag_node::DataDagNode;
@@ -0,0 +1,65 @@
+/*!
+
+Built-in floating point data atoms. Two float atoms are equal when their values have the same bits, so that equality
+agrees with hashing: `NaN` equals itself, and `0.0` and `-0.0` differ.
+
+*/
+
+use core::{
+  any::Any,
+  fmt::{Display, Formatter},
+  hash::Hash
+};
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::{IString, Lazy},
+  api::{
+    atom::DataAtom,
+    symbol::{Symbol, SymbolAttribute, SymbolPtr, SymbolType},
+    Arity
+  }
+};
+
+/// The symbol of float atoms
+#[allow(non_upper_case_globals)]
+pub static FLOAT_SYMBOL: Lazy<Symbol> = Lazy::new(|| {
+  Symbol {
+    name       : IString::from("Float"),
+    arity      : Arity::CONSTANT,
+    attributes : SymbolAttribute::Constructor.into(),
+    symbol_type: SymbolType::Data,
+    sort_table : Default::default(),
+    latex      : None,
+    strategy   : Vec::new(),
+    hash_value : 0
+  }
+});
+
+#[derive(Copy, Clone, Debug)]
+pub struct FloatAtom(pub f64);
+
+impl Display for FloatAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{:?}", self.0)
+  }
+}
+
+impl DataAtom for FloatAtom {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn eq(&self, other: &dyn DataAtom) -> bool {
+    other.as_any().downcast_ref::<FloatAtom>().is_some_and(|other| self.0.to_bits() == other.0.to_bits())
+  }
+
+  fn hash_data(&self, mut state: &mut dyn core::hash::Hasher) {
+    self.0.to_bits().hash(&mut state)
+  }
+
+  fn symbol(&self) -> SymbolPtr {
+    let ptr: *const Symbol = &*FLOAT_SYMBOL;
+    ptr as SymbolPtr
+  }
+}
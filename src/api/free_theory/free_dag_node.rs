@@ -1,10 +1,11 @@
-use std::{
+use core::{
   any::Any,
   cmp::{
     max,
     Ordering
   }
 };
+use alloc::vec::Vec;
 
 use crate::{
   core::{
@@ -34,10 +35,12 @@ use crate::{
   }
 };
 
+#[repr(transparent)]
 pub struct FreeDagNode(DagNodeCore);
 
 impl FreeDagNode {
 
+  #[allow(clippy::new_ret_no_self)]
   pub fn new(symbol: SymbolPtr) -> DagNodePtr {
     assert!(!symbol.is_null());
     DagNodeCore::with_theory(symbol, DagNodeTheory::Free)
@@ -48,6 +51,14 @@ impl FreeDagNode {
     let node     = DagNodeCore::with_theory(symbol, DagNodeTheory::Free);
     let node_mut = unsafe{ &mut *node };
 
+    // Up to two arguments are inline.
+    if args.len() <= 2 && !node_mut.core().needs_destruction() {
+      for &arg in args.iter() {
+        node_mut.insert_child_unchecked(arg);
+      }
+      return node;
+    }
+
     node_mut.set_flags(DagNodeFlag::NeedsDestruction.into());
     node_mut.core_mut().args = (DagNodeVector::from_slice(args) as *mut DagNodeVector) as *mut u8;
 
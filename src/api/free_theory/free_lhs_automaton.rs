@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+
+use crate::{
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::SymbolPtr,
+    NONE
+  },
+  core::{
+    automata::{
+      RcLHSAutomaton,
+      LHSAutomaton
+    },
+    substitution::Substitution
+  }
+};
+
+/// Matches a subject whose top symbol is `symbol` by matching each of its arguments against the corresponding
+/// argument automaton from left to right. If `save_index` is not `NONE`, the matched subject is bound to that slot so
+/// the right-hand side can reuse it.
+///
+/// Arguments whose automata find at most one match are matched in turn. Once an argument automaton can find several,
+/// the arguments after it are matched for each of its matches, until one combined match is accepted.
+pub struct FreeLHSAutomaton {
+  symbol       : SymbolPtr,
+  arg_automata : Vec<RcLHSAutomaton>,
+  save_index   : i32,
+  deterministic: bool,
+}
+
+impl FreeLHSAutomaton {
+  pub fn new(symbol: SymbolPtr, arg_automata: Vec<RcLHSAutomaton>, save_index: i32) -> Self {
+    let deterministic = arg_automata.iter().all(|automaton| automaton.is_deterministic());
+
+    Self {
+      symbol,
+      arg_automata,
+      save_index,
+      deterministic
+    }
+  }
+
+  #[inline(always)]
+  fn matches_top(&self, subject: &dyn DagNode) -> bool {
+    core::ptr::addr_eq(subject.symbol(), self.symbol) && subject.len() == self.arg_automata.len()
+  }
+
+  /// Matches the arguments of `subject` from the `next`th on, then calls `accept`.
+  fn match_args(
+    &self,
+    next    : usize,
+    subject : DagNodePtr,
+    solution: &mut Substitution,
+    accept  : &mut dyn FnMut(&mut Substitution) -> bool
+  ) -> bool
+  {
+    let subject_ref: &dyn DagNode = unsafe{ &*subject };
+
+    for (index, (automaton, arg)) in self.arg_automata.iter().zip(subject_ref.iter_args()).enumerate().skip(next) {
+      if !automaton.is_deterministic() {
+        let mut rest = |solution: &mut Substitution| self.match_args(index + 1, subject, solution, accept);
+        return automaton.match_each(arg, solution, &mut rest);
+      }
+      if !automaton.match_(arg, solution) {
+        return false;
+      }
+    }
+
+    if self.save_index != NONE {
+      solution.bind(self.save_index, Some(subject));
+    }
+
+    accept(solution)
+  }
+}
+
+impl LHSAutomaton for FreeLHSAutomaton {
+  fn match_(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool {
+    if !self.matches_top(unsafe{ &*subject }) {
+      return false;
+    }
+
+    self.match_args(0, subject, solution, &mut |_| true)
+  }
+
+  fn match_each(
+    &self,
+    subject : DagNodePtr,
+    solution: &mut Substitution,
+    accept  : &mut dyn FnMut(&mut Substitution) -> bool
+  ) -> bool
+  {
+    self.matches_top(unsafe{ &*subject }) && self.match_args(0, subject, solution, accept)
+  }
+
+  #[inline(always)]
+  fn is_deterministic(&self) -> bool {
+    self.deterministic
+  }
+}
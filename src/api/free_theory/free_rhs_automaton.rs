@@ -0,0 +1,54 @@
+use alloc::vec::Vec;
+
+use crate::{
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    free_theory::FreeDagNode,
+    symbol::SymbolPtr
+  },
+  core::{
+    automata::RHSAutomaton,
+    substitution::Substitution,
+    VariableInfo
+  }
+};
+
+/// Builds a node with top symbol `symbol` whose arguments are the nodes bound to the `sources` slots, and binds it to
+/// the `destination` slot.
+pub struct FreeRHSAutomaton {
+  symbol     : SymbolPtr,
+  sources    : Vec<i32>,
+  destination: i32,
+}
+
+impl FreeRHSAutomaton {
+  pub fn new(symbol: SymbolPtr, sources: Vec<i32>, destination: i32) -> Self {
+    Self {
+      symbol,
+      sources,
+      destination
+    }
+  }
+}
+
+impl RHSAutomaton for FreeRHSAutomaton {
+  fn remap_indices(&mut self, variable_info: &VariableInfo) {
+    for source in self.sources.iter_mut() {
+      *source = variable_info.remap_index(*source);
+    }
+    self.destination = variable_info.remap_index(self.destination);
+  }
+
+  fn construct(&self, matcher: &mut Substitution) -> DagNodePtr {
+    let node     = FreeDagNode::new(self.symbol);
+    let node_mut = unsafe{ &mut *node };
+
+    for source in self.sources.iter() {
+      let arg = matcher.get(*source).expect("right-hand side source slot is unbound");
+      node_mut.insert_child_unchecked(arg);
+    }
+
+    matcher.bind(self.destination, Some(node));
+    node
+  }
+}
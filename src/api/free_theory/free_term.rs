@@ -1,8 +1,9 @@
-use std::{
+use core::{
   cmp::Ordering,
   any::Any,
   fmt::{Display, Formatter, Pointer}
 };
+use alloc::{boxed::Box, rc::Rc, string::String, vec, vec::Vec};
 
 use crate::{
   abstractions::{
@@ -13,21 +14,37 @@ use crate::{
     dag_node::{
       DagNode,
       DagNodeVector,
-      DagNodePtr,
-      arg_to_node_vec
+      DagNodePtr
     },
     term::{
       BxTerm,
       Term
     },
     symbol::SymbolPtr,
-    free_theory::free_dag_node::FreeDagNode
+    free_theory::{
+      free_dag_node::FreeDagNode,
+      free_lhs_automaton::FreeLHSAutomaton,
+      free_rhs_automaton::FreeRHSAutomaton
+    }
   },
   core::{
+    automata::{
+      has_associative_top,
+      AssociativeLHSAutomaton,
+      CollapseLHSAutomaton,
+      RcLHSAutomaton,
+      EqualityConstraint,
+      RHSBuilder,
+      SharedAutomata,
+      TermBag
+    },
     format::{
+      format_term,
       FormatStyle,
-      Formattable
+      Formattable,
+      PrintSettings
     },
+    renaming::VariableRenaming,
     term_core::TermCore,
     dag_node_core::{
       DagNodeCore,
@@ -54,47 +71,29 @@ impl FreeTerm {
       visited   : false,
     }
   }
+
+  /// A boxed term with `symbol` at the top and `args` as its arguments.
+  pub fn with_args(symbol: SymbolPtr, args: Vec<BxTerm>) -> BxTerm {
+    let mut term = FreeTerm::new(symbol);
+    term.args    = args;
+    Box::new(term)
+  }
 }
 
 impl Display for FreeTerm {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     (self as &dyn Term).fmt(f)
   }
 }
 
 impl Formattable for FreeTerm {
-  fn repr(&self, style: FormatStyle) -> String {
-    let mut accumulator = String::new();
-    match style {
-      FormatStyle::Simple => {
-        accumulator.push_str(self.symbol_ref().repr(style).as_str());
-      }
-
-      FormatStyle::Debug | _ => {
-        accumulator.push_str(format!("free<{}>", self.symbol_ref().repr(style)).as_str());
-      }
-    }
-
-    accumulator.push_str(format!("free<{}>", self.symbol_ref().repr(style)).as_str());
-    if !self.args.is_empty() {
-      accumulator.push('(');
-      accumulator.push_str(
-        self
-            .args
-            .iter()
-            .map(|arg| arg.repr(style))
-            .collect::<Vec<String>>()
-            .join(", ").as_str()
-      );
-      accumulator.push(')');
-    }
-
-    accumulator
+  fn repr_with(&self, style: FormatStyle, settings: &PrintSettings) -> String {
+    format_term(self, style, settings)
   }
 }
 
 // impl Display for FreeTerm {
-//   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+//   fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
 //     todo!()
 //   }
 // }
@@ -156,10 +155,15 @@ impl Term for FreeTerm {
     Box::new(self.args.iter().map(|arg| arg.as_ref()))
   }
 
+  #[inline(always)]
+  fn args_mut(&mut self) -> &mut [BxTerm] {
+    &mut self.args
+  }
+
   // region Comparison Methods
 
   fn compare_term_arguments(&self, other: &dyn Term) -> Ordering {
-    assert!(&self.symbol_ref() == &other.symbol_ref(), "symbols differ");
+    assert!(self.symbol_ref() == other.symbol_ref(), "symbols differ");
 
     if let Some(other) = other.as_any().downcast_ref::<FreeTerm>() {
       for (arg_self, arg_other) in self.args.iter().zip(other.args.iter()) {
@@ -168,7 +172,7 @@ impl Term for FreeTerm {
           return r;
         }
       }
-      return Ordering::Equal;
+      Ordering::Equal
     } else {
       unreachable!("Could not downcast Term to FreeTerm. This is a bug.")
     }
@@ -210,49 +214,152 @@ impl Term for FreeTerm {
   fn dagify_aux(&self) -> DagNodePtr {
     let new_node = FreeDagNode::new(self.symbol());
     let new_node_ref = unsafe{ &mut *new_node };
-    let args = arg_to_node_vec(new_node_ref.core().args);
 
     for arg in self.args.iter() {
       let node = arg.dagify();
-      _ = args.push(node);
+      new_node_ref.insert_child_unchecked(node);
     }
 
     new_node
   }
-/*
-  // region Compiler-related
+
+  fn rename_variables(&self, renaming: &mut VariableRenaming) -> BxTerm {
+    let mut term = FreeTerm::new(self.symbol());
+    term.args    = self.args.iter().map(|arg| arg.rename_variables(renaming)).collect();
+
+    Box::new(term)
+  }
+
+  fn deep_copy(&self) -> BxTerm {
+    Box::new(
+      FreeTerm {
+        core      : self.core.clone(),
+        args      : self.args.iter().map(|arg| arg.deep_copy()).collect(),
+        slot_index: self.slot_index,
+        visited   : self.visited,
+      }
+    )
+  }
+
   #[inline(always)]
+  fn with_new_args(&self, args: Vec<BxTerm>) -> BxTerm {
+    FreeTerm::with_args(self.symbol(), args)
+  }
+
+  // region Compiler-related
+
+  fn index_variables(&mut self, variable_info: &mut VariableInfo) {
+    let mut occurs_set = NatSet::new();
+
+    for arg in self.args.iter_mut() {
+      arg.index_variables(variable_info);
+      occurs_set.union_in_place(arg.occurs_below());
+    }
+
+    *self.occurs_below_mut() = occurs_set;
+  }
+
+  /// The context of an argument is the context of `self` together with the variables of the other arguments.
+  fn determine_context_variables(&mut self) {
+    let context     = self.occurs_in_context().clone();
+    let arg_occurs: Vec<NatSet> = self.args.iter().map(|arg| arg.occurs_below().clone()).collect();
+
+    for (i, arg) in self.args.iter_mut().enumerate() {
+      let arg_context = arg.occurs_in_context_mut();
+      arg_context.union_in_place(&context);
+      for (j, occurs) in arg_occurs.iter().enumerate() {
+        if i != j {
+          arg_context.union_in_place(occurs);
+        }
+      }
+
+      arg.determine_context_variables();
+    }
+  }
+
   fn compile_lhs(
     &self,
-    match_at_top: bool,
-    variable_info: &VariableInfo,
-    bound_uniquely: &mut NatSet,
-  ) -> (RcLHSAutomaton, bool) {
-    FreeTerm::compile_lhs(self, match_at_top, variable_info, bound_uniquely)
+    variable_info  : &mut VariableInfo,
+    bound_uniquely : &mut NatSet,
+    constraints    : &mut Vec<EqualityConstraint>,
+    shared_automata: &mut SharedAutomata
+  ) -> RcLHSAutomaton
+  {
+    if has_associative_top(self) {
+      return Rc::new(AssociativeLHSAutomaton::new(self, variable_info, bound_uniquely, constraints, shared_automata));
+    }
+
+    let arg_automata: Vec<RcLHSAutomaton> = self
+        .args
+        .iter()
+        .map(|arg| shared_automata.compile_lhs(arg.as_ref(), variable_info, bound_uniquely, constraints))
+        .collect();
+
+    let save_index = self.core().save_index;
+    match &self.core().collapse {
+      None => Rc::new(FreeLHSAutomaton::new(self.symbol(), arg_automata, save_index)),
+      Some(collapse) => {
+        let structural = Rc::new(FreeLHSAutomaton::new(self.symbol(), arg_automata.clone(), save_index));
+        Rc::new(CollapseLHSAutomaton::new(self.symbol(), structural, collapse, &arg_automata, save_index))
+      }
+    }
   }
 
-  /// The theory-dependent part of `compile_rhs` called by `term_compiler::compile_rhs(…)`. Returns
-  /// the `save_index`.
-  #[inline(always)]
+  fn find_available_terms_aux(&mut self, available_terms: &mut TermBag, eager_context: bool, at_top: bool) {
+    if !at_top {
+      available_terms.insert_matched_term(self, eager_context);
+    }
+
+    // The arguments with the same associative symbol are flattened into this term's match, so they aren't matched,
+    // and their instances aren't available, on their own.
+    let associative = has_associative_top(self);
+    let symbol      = unsafe{ &*self.symbol() };
+    for (index, arg) in self.args.iter_mut().enumerate() {
+      let flattened = associative && !arg.is_variable() && core::ptr::eq(arg.symbol_ref(), symbol);
+      arg.find_available_terms(available_terms, eager_context && symbol.eager_argument(index), flattened);
+    }
+  }
+
+  /// Compiles the arguments first so that their slots are filled before this term is constructed.
   fn compile_rhs_aux(
     &mut self,
-    rhs_builder: &mut RHSBuilder,
-    variable_info: &VariableInfo,
+    rhs_builder    : &mut RHSBuilder,
+    variable_info  : &mut VariableInfo,
     available_terms: &mut TermBag,
-    eager_context: bool,
-  ) -> i32 {
-    FreeTerm::compile_rhs_aux(&mut self, rhs_builder, variable_info, available_terms, eager_context)
+    eager_context  : bool
+  ) -> i32
+  {
+    let symbol = unsafe{ &*self.symbol() };
+    let sources: Vec<i32> = self
+        .args
+        .iter_mut()
+        .enumerate()
+        .map(|(index, arg)| {
+          let eager = eager_context && symbol.eager_argument(index);
+          arg.compile_rhs(rhs_builder, variable_info, available_terms, eager)
+        })
+        .collect();
+
+    // The sources stay live until this term is constructed.
+    for source in sources.iter() {
+      variable_info.use_index(*source);
+    }
+
+    let index = variable_info.make_construction_index();
+    rhs_builder.add_rhs_automaton(Box::new(FreeRHSAutomaton::new(self.symbol(), sources, index)));
+
+    index
   }
 
+  // endregion
+/*
+  // region Compiler-related
+
   #[inline(always)]
   fn analyse_constraint_propagation(&mut self, bound_uniquely: &mut NatSet) {
     FreeTerm::analyse_constraint_propagation(self, bound_uniquely)
   }
 
-  #[inline(always)]
-  fn find_available_terms_aux(&self, available_terms: &mut TermBag, eager_context: bool, at_top: bool) {
-    FreeTerm::find_available_terms_aux(&self, available_terms, eager_context, at_top);
-  }
   // endregion
   */
 }
@@ -261,9 +368,81 @@ impl Term for FreeTerm {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::{
+    abstractions::IString,
+    api::{Arity, symbol::Symbol}
+  };
 
   #[test]
   fn test_free_term() {
 
   }
+
+  #[test]
+  fn dagified_terms_keep_their_arguments() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+
+    // `g(f(a, g(b)))` has nodes with no arguments, a single argument, and a vector of arguments.
+    let mut inner = FreeTerm::new(&mut g);
+    inner.args    = vec![Box::new(FreeTerm::new(&mut b))];
+    let mut pair  = FreeTerm::new(&mut f);
+    pair.args     = vec![Box::new(FreeTerm::new(&mut a)), Box::new(inner)];
+    let mut term  = FreeTerm::new(&mut g);
+    term.args     = vec![Box::new(pair)];
+
+    let node = term.term_to_dag(false);
+    assert_eq!(node_to_string(node), "g(f(a, g(b)))");
+  }
+
+  #[test]
+  fn dagify_tells_apart_terms_with_the_same_hash() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut s = Symbol::new(IString::from("s"), Arity::Value(1));
+    let mut x = Symbol::new(IString::from("x"), Arity::Value(0));
+
+    // Applying `s` twice gives back the hash of the argument, so `s(s(x))` has the same hash as `x`.
+    let mut s_x   = FreeTerm::new(&mut s);
+    s_x.args      = vec![Box::new(FreeTerm::new(&mut x))];
+    let mut s_s_x = FreeTerm::new(&mut s);
+    s_s_x.args    = vec![Box::new(s_x)];
+    let mut term  = FreeTerm::new(&mut f);
+    term.args     = vec![Box::new(FreeTerm::new(&mut x)), Box::new(s_s_x)];
+
+    let node = term.term_to_dag(false);
+    assert_eq!(node_to_string(node), "f(x, s(s(x)))");
+  }
+
+  #[test]
+  fn lazy_arguments_are_only_available_in_a_lazy_context() {
+    let mut l = Symbol::new(IString::from("l"), Arity::Value(2));
+    let mut s = Symbol::new(IString::from("s"), Arity::Value(1));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+    // `l` reduces its first argument only.
+    l.set_strategy(vec![1, 0]).unwrap();
+    let (l, s, a, b): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut l, &mut s, &mut a, &mut b);
+    let s_of = |symbol| FreeTerm::with_args(s, vec![FreeTerm::with_args(symbol, vec![])]);
+
+    let mut term = FreeTerm::with_args(l, vec![s_of(a), s_of(b)]);
+    let mut bag  = TermBag::new();
+    term.find_available_terms(&mut bag, true, true);
+
+    assert!(bag.find_term(s_of(a).as_ref(), true).is_some());
+    assert!(bag.find_term(s_of(b).as_ref(), true).is_none());
+    assert!(bag.find_term(s_of(b).as_ref(), false).is_some());
+  }
+
+  fn node_to_string(node: DagNodePtr) -> String {
+    let node = unsafe{ &*node };
+    match node.len() {
+      0 => node.symbol_ref().name.to_string(),
+      _ => {
+        let args: Vec<String> = node.iter_args().map(node_to_string).collect();
+        format!("{}({})", node.symbol_ref().name, args.join(", "))
+      }
+    }
+  }
 }
@@ -1,5 +1,11 @@
 mod free_term;
 mod free_dag_node;
+mod free_lhs_automaton;
+mod free_rhs_automaton;
 
 pub use free_term::FreeTerm;
 pub use free_dag_node::FreeDagNode;
+#[doc(hidden)]
+pub use free_lhs_automaton::FreeLHSAutomaton;
+#[doc(hidden)]
+pub use free_rhs_automaton::FreeRHSAutomaton;
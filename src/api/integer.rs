@@ -0,0 +1,215 @@
+/*!
+
+Built-in integer data atoms with arbitrary precision. An integer that fits in an `i64` is an `IntegerAtom`, and any
+other is a `BigIntAtom`. The arithmetic functions compute on machine integers while they can and promote the result to
+a `BigIntAtom` when an operation overflows, rather than wrapping or panicking, and demote a result that fits in an
+`i64` back to an `IntegerAtom`.
+
+Every integer thus has exactly one representation, so two integer atoms are equal exactly when they have the same type
+and the same value, and `eq` and hashing agree with equality of the values. `BigIntAtom`s can only be made by `integer`
+and the arithmetic functions, which keep this invariant. Both types have the symbol `Integer`.
+
+```ignore
+let sum = add(integer(i64::MAX).as_ref(), integer(1).as_ref()).unwrap(); // A BigIntAtom
+let max = sub(sum.as_ref(), integer(1).as_ref()).unwrap();               // An IntegerAtom again
+```
+
+*/
+
+use core::{
+  any::Any,
+  fmt::{Display, Formatter},
+  hash::{Hash, Hasher}
+};
+use alloc::{boxed::Box, vec::Vec};
+
+use num_bigint::BigInt;
+
+use crate::{
+  abstractions::{IString, Lazy},
+  api::{
+    atom::DataAtom,
+    symbol::{Symbol, SymbolAttribute, SymbolPtr, SymbolType},
+    Arity
+  }
+};
+
+/// The symbol of integer atoms of both representations
+#[allow(non_upper_case_globals)]
+pub static INTEGER_SYMBOL: Lazy<Symbol> = Lazy::new(|| {
+  Symbol {
+    name       : IString::from("Integer"),
+    arity      : Arity::CONSTANT,
+    attributes : SymbolAttribute::Constructor.into(),
+    symbol_type: SymbolType::Data,
+    sort_table : Default::default(),
+    latex      : None,
+    strategy   : Vec::new(),
+    hash_value : 0
+  }
+});
+
+/// An integer that fits in an `i64`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct IntegerAtom(pub i64);
+
+/// An integer that doesn't fit in an `i64`. See the module documentation.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BigIntAtom(BigInt);
+
+impl BigIntAtom {
+  #[inline(always)]
+  pub fn value(&self) -> &BigInt {
+    &self.0
+  }
+}
+
+impl Display for IntegerAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl Display for BigIntAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl DataAtom for IntegerAtom {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn eq(&self, other: &dyn DataAtom) -> bool {
+    other.as_any().downcast_ref::<IntegerAtom>().is_some_and(|other| self.0 == other.0)
+  }
+
+  fn hash_data(&self, mut state: &mut dyn Hasher) {
+    self.0.hash(&mut state)
+  }
+
+  fn symbol(&self) -> SymbolPtr {
+    integer_symbol()
+  }
+}
+
+impl DataAtom for BigIntAtom {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn eq(&self, other: &dyn DataAtom) -> bool {
+    other.as_any().downcast_ref::<BigIntAtom>().is_some_and(|other| self.0 == other.0)
+  }
+
+  fn hash_data(&self, mut state: &mut dyn Hasher) {
+    self.0.hash(&mut state)
+  }
+
+  fn symbol(&self) -> SymbolPtr {
+    integer_symbol()
+  }
+}
+
+fn integer_symbol() -> SymbolPtr {
+  let ptr: *const Symbol = &*INTEGER_SYMBOL;
+  ptr as SymbolPtr
+}
+
+/// The integer atom with value `value`, in its representation
+pub fn integer(value: impl Into<BigInt>) -> Box<dyn DataAtom> {
+  let value = value.into();
+  match i64::try_from(&value) {
+    Ok(small) => Box::new(IntegerAtom(small)),
+    Err(_)    => Box::new(BigIntAtom(value)),
+  }
+}
+
+/// The value of `atom`, or `None` if it isn't an integer atom
+pub fn integer_value(atom: &dyn DataAtom) -> Option<BigInt> {
+  if let Some(small) = atom.as_any().downcast_ref::<IntegerAtom>() {
+    return Some(BigInt::from(small.0));
+  }
+  atom.as_any().downcast_ref::<BigIntAtom>().map(|big| big.0.clone())
+}
+
+/// `a + b`, or `None` if either isn't an integer atom
+pub fn add(a: &dyn DataAtom, b: &dyn DataAtom) -> Option<Box<dyn DataAtom>> {
+  binary(a, b, i64::checked_add, |a, b| a + b)
+}
+
+/// `a - b`, or `None` if either isn't an integer atom
+pub fn sub(a: &dyn DataAtom, b: &dyn DataAtom) -> Option<Box<dyn DataAtom>> {
+  binary(a, b, i64::checked_sub, |a, b| a - b)
+}
+
+/// `a * b`, or `None` if either isn't an integer atom
+pub fn mul(a: &dyn DataAtom, b: &dyn DataAtom) -> Option<Box<dyn DataAtom>> {
+  binary(a, b, i64::checked_mul, |a, b| a * b)
+}
+
+/// `-a`, or `None` if it isn't an integer atom
+pub fn neg(a: &dyn DataAtom) -> Option<Box<dyn DataAtom>> {
+  if let Some(small) = a.as_any().downcast_ref::<IntegerAtom>().and_then(|small| small.0.checked_neg()) {
+    return Some(Box::new(IntegerAtom(small)));
+  }
+  Some(integer(-integer_value(a)?))
+}
+
+/// Applies `small` to machine integers, falling back on `big` when either operand is a `BigIntAtom` or `small`
+/// overflows.
+fn binary(
+  a    : &dyn DataAtom,
+  b    : &dyn DataAtom,
+  small: fn(i64, i64) -> Option<i64>,
+  big  : fn(BigInt, BigInt) -> BigInt
+) -> Option<Box<dyn DataAtom>> {
+  let as_small = |atom: &dyn DataAtom| atom.as_any().downcast_ref::<IntegerAtom>().map(|small| small.0);
+  if let Some(result) = as_small(a).zip(as_small(b)).and_then(|(a, b)| small(a, b)) {
+    return Some(Box::new(IntegerAtom(result)));
+  }
+
+  Some(integer(big(integer_value(a)?, integer_value(b)?)))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::hash::{DefaultHasher, Hash, Hasher};
+
+  use num_bigint::BigInt;
+
+  use crate::api::{
+    atom::DataAtom,
+    integer::{add, integer, mul, neg, sub, BigIntAtom, IntegerAtom}
+  };
+
+  fn hash(atom: &dyn DataAtom) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    atom.hash(&mut hasher);
+    hasher.finish()
+  }
+
+  #[test]
+  fn overflow_promotes_to_big_integers() {
+    let max = integer(i64::MAX);
+    let one = integer(1);
+
+    let sum = add(max.as_ref(), one.as_ref()).unwrap();
+    assert!(sum.as_any().is::<BigIntAtom>());
+    assert_eq!(sum.to_string(), "9223372036854775808");
+
+    // Coming back into range demotes the result, which is then equal to the machine integer.
+    let back = sub(sum.as_ref(), one.as_ref()).unwrap();
+    assert!(back.as_any().is::<IntegerAtom>());
+    assert_eq!(hash(back.as_ref()), hash(max.as_ref()));
+
+    let square = mul(max.as_ref(), max.as_ref()).unwrap();
+    assert!(back == max);
+    assert_eq!(square.to_string(), (BigInt::from(i64::MAX) * i64::MAX).to_string());
+    assert!(neg(integer(i64::MIN).as_ref()).unwrap() == sum);
+    assert!(add(integer(2).as_ref(), integer(3).as_ref()).unwrap() == integer(5));
+    assert!(integer(5) != integer(6));
+  }
+}
@@ -0,0 +1,20 @@
+/*!
+
+Built-in string data atoms, which are printed quoted and escaped, as Rust would debug print them.
+
+*/
+
+use core::fmt::{Display, Formatter};
+use alloc::string::String;
+
+use crate::api::atom::DataAtom;
+
+#[derive(DataAtom, Clone, PartialEq, Eq, Hash, Debug)]
+#[data_atom(name = "String")]
+pub struct StringAtom(pub String);
+
+impl Display for StringAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{:?}", self.0)
+  }
+}
@@ -10,22 +10,37 @@ the symbol `Integer` for example.
 
 */
 
-use std::fmt::Display;
+use core::{
+  error::Error,
+  fmt::{Display, Formatter},
+  sync::atomic::{AtomicU32, Ordering}
+};
+use alloc::{string::{String, ToString}, vec, vec::Vec};
 
 use enumflags2::{bitflags, make_bitflags, BitFlags};
 
 use crate::{
   abstractions::{
     int_to_subscript,
+    interned_id,
+    DETERMINISTIC,
     Set,
     IString
   },
   api::Arity,
-  core::format::{FormatStyle, Formattable}
+  core::{
+    format::{FormatStyle, Formattable, PrintSettings},
+    latex::symbol_to_latex,
+    statistics::record_symbol_created,
+    sort::{
+      sort_table::SortTable,
+      SortPtr
+    }
+  }
 };
 
 pub type SymbolPtr = *mut Symbol;
-pub type SymbolSet = Set<Symbol>;
+pub type SymbolSet = Set<SymbolPtr>;
 
 
 #[derive(Clone, Eq, PartialEq, Hash)]
@@ -35,6 +50,12 @@ pub struct Symbol {
   pub arity      : Arity,
   pub attributes : SymbolAttributes,
   pub symbol_type: SymbolType,
+  /// The operator declarations of the symbol, which determine the sorts of terms headed by the symbol
+  pub sort_table : SortTable,
+  /// The LaTeX for the symbol, in which `#1`, `#2`, … stand for its arguments. Set with `set_latex`.
+  pub latex      : Option<IString>,
+  /// The evaluation strategy of the symbol, or empty for the default strategy. Set with `set_strategy`.
+  pub strategy   : Vec<usize>,
 
   // ToDo: Can the `IString` value be used as the `hash_value`?
   // Unique integer for comparing symbols, also called order.
@@ -46,41 +67,129 @@ pub struct Symbol {
 impl Symbol {
   pub fn new(name: IString, arity: Arity) -> Symbol {
     // Compute hash
-    static mut SYMBOL_COUNT: u32 = 0;
-    unsafe{ SYMBOL_COUNT += 1; }
-    let numeric_arity: u32 = if let Arity::Value(v) = arity {
-      v as u32
-    } else {
-      0
-    };
-    let hash_value = unsafe{ SYMBOL_COUNT } | (numeric_arity << 24); // Maude: self.arity << 24
+    static SYMBOL_COUNT: AtomicU32 = AtomicU32::new(0);
+    let symbol_count = SYMBOL_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+    record_symbol_created();
 
-    let symbol = Symbol{
+    let mut symbol = Symbol{
       name,
       arity,
       attributes : SymbolAttributes::default(),
       symbol_type: SymbolType::default(),
-      hash_value
+      sort_table : SortTable::default(),
+      latex      : None,
+      strategy   : Vec::new(),
+      hash_value : 0
+    };
+    symbol.renumber(symbol_count);
+
+    symbol
+  }
+
+  /// Recomputes the `hash_value` of the symbol from `number`, which should be unique among the symbols the symbol is
+  /// compared with. With the `deterministic` feature, a `ModuleArena` renumbers its symbols in the order they are
+  /// added, so that symbol order doesn't depend on how many symbols were created before.
+  pub fn renumber(&mut self, number: u32) {
+    let numeric_arity: u32 = if let Arity::Value(v) = self.arity {
+      v as u32
+    } else {
+      0
     };
+    self.hash_value = number | (numeric_arity << 24); // Maude: self.arity << 24
+  }
+
+
+  /// Creates a new symbol for a variable with the given name.
+  pub fn new_variable(name: IString) -> Symbol {
+    let mut symbol = Symbol::new(name, Arity::Value(0));
+    symbol.symbol_type = SymbolType::Variable;
 
     symbol
   }
 
 
+  /// Creates a new symbol for a variable of sort `sort`, which only binds to terms whose sort is `sort` or one of its
+  /// subsorts. The sort is recorded as the range of a constant declaration, so a variable node has the sort as well.
+  pub fn new_sorted_variable(name: IString, sort: SortPtr) -> Symbol {
+    let mut symbol = Symbol::new_variable(name);
+    symbol.sort_table.add_op_declaration(vec![], sort, false);
+
+    symbol
+  }
+
+
+  /// Gives the symbol its own LaTeX for `FormatStyle::Latex`. See `core::latex`.
+  pub fn set_latex(&mut self, latex: IString) {
+    self.attributes.insert(SymbolAttribute::Latex);
+    self.latex = Some(latex);
+  }
+
+
+  /**
+  Gives the symbol a Maude-style evaluation strategy, which controls which arguments are reduced before the symbol's
+  equations are tried, and when. `0` stands for trying the equations at the top and `i` for reducing the `i`th argument.
+  The default strategy reduces every argument and then tries the equations, `(1 2 … n 0)`.
+
+  Arguments that are not listed are never reduced, so they are reduced only if an equation moves them to a position
+  that is, and `(0)` makes the symbol lazy. The equations are always tried once the listed arguments are reduced: a `0`
+  is appended to a strategy that doesn't end with one.
+  */
+  pub fn set_strategy(&mut self, mut strategy: Vec<usize>) -> Result<(), StrategyError> {
+    let arity = match self.arity.value() {
+      Some(arity) => arity as usize,
+      None        => return Err(StrategyError::NotFixed(self.arity)),
+    };
+    if let Some(&index) = strategy.iter().find(|&&index| index > arity) {
+      return Err(StrategyError::OutOfRange { index, arity });
+    }
+
+    if strategy.last() != Some(&0) {
+      strategy.push(0);
+    }
+    self.attributes.insert(SymbolAttribute::Strategy);
+    self.strategy = strategy;
+    Ok(())
+  }
+
+  /// Whether the argument with index `index`, counting from zero, is reduced before the symbol's equations are first
+  /// tried, which is the case for every argument under the default strategy.
+  pub fn eager_argument(&self, index: usize) -> bool {
+    self.strategy.is_empty() || self.strategy.iter().take_while(|&&step| step != 0).any(|&step| step == index + 1)
+  }
+
+
   #[inline(always)]
   pub fn is_variable(&self) -> bool {
     self.symbol_type == SymbolType::Variable
   }
 
 
-  /// Comparison based only on name and arity
-  pub fn compare(&self, other: &Symbol) -> std::cmp::Ordering {
-    self.hash_value.cmp(&other.hash_value)
+  /// The declared sort of a variable symbol, or `None` if the symbol is not a variable or the variable is unsorted and
+  /// so binds to anything.
+  pub fn variable_sort(&self) -> Option<SortPtr> {
+    match self.is_variable() {
+      true  => self.sort_table.declarations().first().map(|declaration| declaration.range),
+      false => None
+    }
+  }
+
+
+  /// Comparison based only on name and arity. Symbols with the same `hash_value`, like the symbols of data atoms, are
+  /// ordered by the ids of their interned names, or alphabetically with the `deterministic` feature.
+  pub fn compare(&self, other: &Symbol) -> core::cmp::Ordering {
+    self.hash_value
+        .cmp(&other.hash_value)
+        .then_with(
+          || match DETERMINISTIC {
+            true  => self.name.as_ref().cmp(other.name.as_ref()),
+            false => interned_id(&self.name).cmp(&interned_id(&other.name))
+          }
+        )
   }
 }
 
 impl Display for Symbol {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self.arity {
       Arity::Variadic => write!(f, "{}ᵥ", self.name),
       Arity::Value(arity) => write!(f, "{}{}", self.name, int_to_subscript(arity as u32)),
@@ -90,10 +199,35 @@ impl Display for Symbol {
   }
 }
 
+/// Returned by `Symbol::set_strategy` for a strategy that doesn't fit the symbol.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum StrategyError {
+  /// The strategy names an argument the symbol doesn't have.
+  OutOfRange { index: usize, arity: usize },
+  /// The symbol doesn't have a fixed number of arguments.
+  NotFixed(Arity),
+}
+
+impl Display for StrategyError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      StrategyError::OutOfRange { index, arity } => {
+        write!(f, "the strategy names argument {} of a symbol with {} arguments", index, arity)
+      }
+      StrategyError::NotFixed(arity) => write!(f, "a symbol of arity {} can't have a strategy", arity),
+    }
+  }
+}
+
+impl Error for StrategyError {}
+
 impl Formattable for Symbol {
-  fn repr(&self, _style: FormatStyle) -> String {
-    // ToDo: Probably defer to `Display` here.
-    self.name.to_string()
+  fn repr_with(&self, style: FormatStyle, _settings: &PrintSettings) -> String {
+    match style {
+      FormatStyle::Latex => symbol_to_latex(self),
+      // ToDo: Probably defer to `Display` here.
+      _ => self.name.to_string()
+    }
   }
 }
 
@@ -109,7 +243,7 @@ pub enum SymbolType {
 
 #[bitflags]
 #[repr(u32)]
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum SymbolAttribute {
   // Syntactic attributes
   Precedence,
@@ -130,6 +264,12 @@ pub enum SymbolAttribute {
   RightIdentity,
   Idempotent,
   Iterated,
+  /// The symbol binds the variable in its first argument in its remaining arguments, like `lambda(X, body)`.
+  Binder,
+  /// The symbol is a retract `r:A>B`, which reduction removes once its argument has the sort `B`. See `core::retract`.
+  Retract,
+  /// The symbol is a descent function of the metalevel, which reduction evaluates in Rust. See `core::metalevel`.
+  Descent,
 }
 
 pub type SymbolAttributes = BitFlags<SymbolAttribute, u32>;
@@ -157,7 +297,7 @@ impl SymbolAttribute {
     }
   );
 
-  ///	Simple attributes are just a flag without additional data. They produce a warning if given twice.
+  /// Simple attributes are just a flag without additional data. They produce a warning if given twice.
   pub const SimpleAttributes: SymbolAttributes = make_bitflags!(
     SymbolAttribute::{
       Associative
@@ -166,6 +306,7 @@ impl SymbolAttribute {
       | Memoized
       | Constructor
       | Iterated
+      | Binder
     }
   );
 
@@ -186,8 +327,32 @@ impl SymbolAttribute {
       | RightIdentity
       | Idempotent
       | Iterated
+      | Binder
     }
   );
+
+  /// The attribute as it is written in Maude, as in `[assoc comm]`
+  pub fn name(self) -> &'static str {
+    match self {
+      SymbolAttribute::Precedence    => "prec",
+      SymbolAttribute::Gather        => "gather",
+      SymbolAttribute::Format        => "format",
+      SymbolAttribute::Latex         => "latex",
+      SymbolAttribute::Strategy      => "strat",
+      SymbolAttribute::Memoized      => "memo",
+      SymbolAttribute::Frozen        => "frozen",
+      SymbolAttribute::Constructor   => "ctor",
+      SymbolAttribute::Associative   => "assoc",
+      SymbolAttribute::Commutative   => "comm",
+      SymbolAttribute::LeftIdentity  => "left id",
+      SymbolAttribute::RightIdentity => "right id",
+      SymbolAttribute::Idempotent    => "idem",
+      SymbolAttribute::Iterated      => "iter",
+      SymbolAttribute::Binder        => "binder",
+      SymbolAttribute::Retract       => "retract",
+      SymbolAttribute::Descent       => "descent",
+    }
+  }
 }
 
 
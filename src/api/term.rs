@@ -6,33 +6,41 @@ and `Term::core_mut()`. This allows a lot of shared implementation in `TermCore`
 
 */
 
-use std::{
+use core::{
   any::Any,
   fmt::{Display, Formatter},
   hash::{Hash, Hasher},
   cmp::Ordering,
-  collections::{
-    HashMap,
-    hash_map::Entry
-  },
   sync::atomic::Ordering::Relaxed
 };
+use alloc::{boxed::Box, vec::Vec};
 
 use crate::{
   abstractions::{
+    HashMap,
     NatSet,
-    RcCell
+    RcCell,
+    Set
   },
   api::{
     dag_node::{DagNodePtr, DagNode},
+    NONE,
     UNDEFINED,
     symbol::{
       Symbol,
       SymbolPtr,
       SymbolSet
-    }
+    },
+    variable_theory::VariableTerm
   },
   core::{
+    automata::{
+      RcLHSAutomaton,
+      EqualityConstraint,
+      RHSBuilder,
+      SharedAutomata,
+      TermBag
+    },
     dag_node_core::{
       DagNodeCore,
       DagNodeFlag,
@@ -41,14 +49,19 @@ use crate::{
       FormatStyle,
       Formattable
     },
+    renaming::VariableRenaming,
+    sort::SortPtr,
+    statistics::record_conversion_lookup,
     term_core::{
       cache_node_for_term,
       clear_cache_and_set_sort_info,
       lookup_node_for_term,
+      set_sort_info,
       TermAttribute,
       TermCore
     },
-    substitution::Substitution
+    substitution::Substitution,
+    VariableInfo
   }
 };
 
@@ -71,8 +84,8 @@ pub trait Term: Formattable {
   fn core(&self) -> &TermCore;
   fn core_mut(&mut self) -> &mut TermCore;
 
-  /// This method should construct a new `Term` of the concrete implementing type,
-  /// including its `TermCore` member, and return it wrapped in an `Rc`.
+  // This method should construct a new `Term` of the concrete implementing type,
+  // including its `TermCore` member, and return it wrapped in an `Rc`.
   // fn new(symbol: SymbolPtr) -> BxTerm;
 
   // region Accessors
@@ -136,10 +149,35 @@ pub trait Term: Formattable {
     self.core().collapse_symbols()
   }
 
+  /// The sort of the term, if it has been computed by `compute_sort`.
+  #[inline(always)]
+  fn sort(&self) -> Option<SortPtr> {
+    self.core().sort.get()
+  }
+
+  /// Computes the sort of the term bottom up from the operator declarations of its symbols, caching the sort of each
+  /// subterm. A sorted variable has its declared sort, and an ill-sorted term has the error sort of its kind. The sort
+  /// is `None` if it can't be determined, as for a term with an unsorted variable or a symbol without declarations.
+  fn compute_sort(&self) -> Option<SortPtr> {
+    if let Some(sort) = self.sort() {
+      return Some(sort);
+    }
+
+    let arg_sorts: Vec<Option<SortPtr>> = self.iter_args().map(|arg| arg.compute_sort()).collect();
+    let sort_table = &self.symbol_ref().sort_table;
+    let sort       = sort_table.sort_with_index(sort_table.compute_sort_index(&arg_sorts));
+    self.core().sort.set(sort);
+
+    sort
+  }
+
   /// Returns an iterator over the arguments of the term
   fn iter_args(&self) -> Box<dyn Iterator<Item = &dyn Term> + '_>;
   // Implement an empty iterator with:
-  //    Box::new(std::iter::empty::<&dyn Term>())
+  //    Box::new(core::iter::empty::<&dyn Term>())
+
+  /// The arguments of the term, to be changed in place. A term of a theory whose terms have no arguments has none.
+  fn args_mut(&mut self) -> &mut [BxTerm];
 
   #[inline(always)]
   fn symbol_ref(&self) -> &'static Symbol {
@@ -151,6 +189,20 @@ pub trait Term: Formattable {
     self.core().symbol
   }
 
+  /// A term is linear if no variable occurs in it more than once. Matching a linear pattern never requires comparing
+  /// bindings.
+  fn is_linear(&self) -> bool {
+    fn visit(term: &dyn Term, seen: &mut Set<u32>) -> bool {
+      if term.is_variable() {
+        return seen.insert(term.symbol_ref().hash_value);
+      }
+      term.iter_args().all(|arg| visit(arg, seen))
+    }
+
+    let mut seen = Set::default();
+    self.is_variable() || self.iter_args().all(|arg| visit(arg, &mut seen))
+  }
+
   /// Compute the number of nodes in the term tree
   fn compute_size(&self) -> i32 {
     let cached_size = &self.core().cached_size;
@@ -192,7 +244,7 @@ pub trait Term: Formattable {
       return self.partial_compare_unstable(partial_substitution, other);
     }
 
-    if std::ptr::addr_eq(self.symbol(), other.symbol()) {
+    if core::ptr::addr_eq(self.symbol(), other.symbol()) {
       // Only used for `FreeTerm`
       return self.partial_compare_arguments(partial_substitution, other);
     }
@@ -228,6 +280,8 @@ pub trait Term: Formattable {
 
   // region DAG Creation
 
+  /// Converts the term to a DAG with structural sharing. If `set_sort_info` is true, each node is given the sort of its
+  /// subterm as computed by `compute_sort`, if it has been computed.
   #[inline(always)]
   fn term_to_dag(&self, set_sort_info: bool) -> DagNodePtr {
     clear_cache_and_set_sort_info(set_sort_info);
@@ -239,11 +293,21 @@ pub trait Term: Formattable {
   /// calls `dagify(…)` on its children and then converts itself to a type implementing DagNode, returning `DagNodePtr`.
   fn dagify(&self) -> DagNodePtr {
     let semantic_hash = self.semantic_hash();
-    if let Some(dag_node) = lookup_node_for_term(semantic_hash) {
-      return dag_node;
+    let cached_node   = lookup_node_for_term(semantic_hash);
+    if let Some(dag_node) = cached_node {
+      // Distinct terms can have the same hash, e.g. `s(s(x))` and `x`, so the cached node must be checked.
+      let cached = unsafe{ &*dag_node };
+      if core::ptr::addr_eq(cached.symbol(), self.symbol()) && self.compare_dag_node(cached).is_eq() {
+        record_conversion_lookup(true, false);
+        return dag_node;
+      }
     }
+    record_conversion_lookup(false, cached_node.is_some());
 
     let dag_node = self.dagify_aux();
+    if let Some(sort) = self.sort().filter(|_| set_sort_info()) {
+      unsafe{ &mut *dag_node }.set_sort_index(unsafe{ &*sort }.index_within_kind as i8);
+    }
     cache_node_for_term(semantic_hash, dag_node);
 
     dag_node
@@ -254,6 +318,95 @@ pub trait Term: Formattable {
 
   // endregion
 
+  /// A copy of the term with its variables renamed canonically by `renaming`. See `core::renaming`.
+  fn rename_variables(&self, renaming: &mut VariableRenaming) -> BxTerm;
+
+  /// A copy of the term with the same symbols and everything compilation recorded in it, such as variable indices,
+  /// sorts, and save indices, so that the copy can stand in for the term in a compiled statement.
+  fn deep_copy(&self) -> BxTerm;
+
+  /// A term of the same theory and top symbol as this one with the arguments `args`, which must be as many as the
+  /// symbol takes. Nothing compilation recorded in the term is carried over.
+  fn with_new_args(&self, args: Vec<BxTerm>) -> BxTerm;
+
+
+  // region Compiler-related
+
+  /// Assigns each variable occurring in the term an index in `variable_info`, computing the `occurs_below` sets
+  /// along the way. This must be done before the term is compiled.
+  fn index_variables(&mut self, variable_info: &mut VariableInfo);
+
+  /// Computes the `occurs_in_context` set of every proper subterm, the variables occurring outside the subterm in the
+  /// statement the term belongs to. The context set of `self` must already be set. Called after `index_variables`.
+  /// Terms without arguments have nothing to do.
+  fn determine_context_variables(&mut self) {}
+
+  /// Compiles the term into an automaton that matches it against subjects. Variables in `bound_uniquely` are already
+  /// bound, and variables the term binds are added to it. Occurrences of variables that are already bound are
+  /// compiled into `EqualityConstraint`s pushed onto `constraints`, which are checked after structural matching.
+  /// Implementations compile their arguments through `shared_automata` so that identical subpatterns share automata.
+  fn compile_lhs(
+    &self,
+    variable_info  : &mut VariableInfo,
+    bound_uniquely : &mut NatSet,
+    constraints    : &mut Vec<EqualityConstraint>,
+    shared_automata: &mut SharedAutomata
+  ) -> RcLHSAutomaton;
+
+  /// Records in `available_terms` the terms whose instances will be available once this term has been matched. The
+  /// top of a left-hand side is the redex itself, which is replaced by the right-hand side, so it is not available.
+  #[inline(always)]
+  fn find_available_terms(&mut self, available_terms: &mut TermBag, eager_context: bool, at_top: bool) {
+    self.find_available_terms_aux(available_terms, eager_context, at_top);
+  }
+
+  /// The theory-dependent part of `find_available_terms`.
+  fn find_available_terms_aux(&mut self, available_terms: &mut TermBag, eager_context: bool, at_top: bool);
+
+  /// Compiles the construction of this term into `rhs_builder`, returning the slot that will hold the constructed
+  /// node. If an equal term is available in `available_terms`, its slot is used instead and nothing is built. A
+  /// matched left-hand side subterm that is reused this way is given a `save_index` so that its automaton records
+  /// the subject it matched.
+  fn compile_rhs(
+    &mut self,
+    rhs_builder    : &mut RHSBuilder,
+    variable_info  : &mut VariableInfo,
+    available_terms: &mut TermBag,
+    eager_context  : bool
+  ) -> i32
+  {
+    let this = self.as_ptr() as *mut dyn Term;
+
+    if let Some(available) = available_terms.find_term(unsafe{ &*this }, eager_context) {
+      let available = unsafe{ &mut *available };
+      if available.core().save_index == NONE {
+        if let Some(variable) = available.as_any().downcast_ref::<VariableTerm>() {
+          return variable.index();
+        }
+        available.core_mut().save_index = variable_info.make_protected_variable();
+      }
+      return available.core().save_index;
+    }
+
+    let index = self.compile_rhs_aux(rhs_builder, variable_info, available_terms, eager_context);
+    variable_info.use_index(index);
+    self.core_mut().save_index = index;
+    available_terms.insert_built_term(this, eager_context);
+
+    index
+  }
+
+  /// The theory-dependent part of `compile_rhs`. Returns the construction index of the constructed node.
+  fn compile_rhs_aux(
+    &mut self,
+    rhs_builder    : &mut RHSBuilder,
+    variable_info  : &mut VariableInfo,
+    available_terms: &mut TermBag,
+    eager_context  : bool
+  ) -> i32;
+
+  // endregion
+
 }
 
 
@@ -278,7 +431,7 @@ impl Eq for dyn Term {}
 
 
 impl Display for dyn Term {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     write!(f, "[{}]", self.symbol_ref())
   }
 }
@@ -0,0 +1,137 @@
+/*!
+
+The ground value of a DAG, for embedding code that wants the result of a reduction back as Rust data without
+downcasting atoms through `Any`:
+
+```ignore
+let normal_form = context.reduce(subject);
+match unsafe{ &*normal_form }.to_value() {
+  Value::Int(count) => println!("{} items", count),
+  Value::Other(dag) => println!("no count, but {}", unsafe{ &*dag }),
+  _ => {}
+}
+```
+
+Data atoms of the built-in types become the corresponding values, and the lists of the prelude's `LIST` module, built
+from `nil` and `cons`, become lists of the values of their elements. Everything else, including data atoms of other
+types, which `DagNode::as_data` extracts, is `Other`.
+
+*/
+
+use alloc::{string::String, vec::Vec};
+
+use num_bigint::BigInt;
+
+use crate::api::{
+  dag_node::{as_node_ptr, DagNode, DagNodePtr},
+  float::FloatAtom,
+  integer::{BigIntAtom, IntegerAtom},
+  string::StringAtom
+};
+
+/// The value of a DAG. See the module documentation.
+#[derive(Clone, Debug)]
+pub enum Value {
+  Int(i64),
+  /// An integer that doesn't fit in an `i64`
+  BigInt(BigInt),
+  Float(f64),
+  String(String),
+  List(Vec<Value>),
+  /// A DAG with no value of the other kinds
+  Other(DagNodePtr),
+}
+
+impl Value {
+  pub(crate) fn of(node: &dyn DagNode) -> Value {
+    if let Some(atom) = node.data() {
+      let atom = atom.as_any();
+      if let Some(integer) = atom.downcast_ref::<IntegerAtom>() {
+        return Value::Int(integer.0);
+      }
+      if let Some(integer) = atom.downcast_ref::<BigIntAtom>() {
+        return Value::BigInt(integer.value().clone());
+      }
+      if let Some(float) = atom.downcast_ref::<FloatAtom>() {
+        return Value::Float(float.0);
+      }
+      if let Some(string) = atom.downcast_ref::<StringAtom>() {
+        return Value::String(string.0.clone());
+      }
+      return Value::Other(as_node_ptr(node));
+    }
+
+    // A list is a chain of `cons` nodes ending in `nil`.
+    let mut elements = Vec::new();
+    let mut tail     = node;
+    while tail.symbol_ref().name.as_ref() == "cons" && tail.len() == 2 {
+      let mut args = tail.iter_args();
+      let (head, rest) = (args.next().unwrap(), args.next().unwrap());
+      elements.push(unsafe{ &*head }.to_value());
+      tail = unsafe{ &*rest };
+    }
+    match tail.symbol_ref().name.as_ref() == "nil" && tail.is_empty() {
+      true  => Value::List(elements),
+      false => Value::Other(as_node_ptr(node)),
+    }
+  }
+}
+
+impl PartialEq for Value {
+  fn eq(&self, other: &Self) -> bool {
+    match (self, other) {
+      (Value::Int(value), Value::Int(other))       => value == other,
+      (Value::BigInt(value), Value::BigInt(other)) => value == other,
+      (Value::Float(value), Value::Float(other))   => value == other,
+      (Value::String(value), Value::String(other)) => value == other,
+      (Value::List(value), Value::List(other))     => value == other,
+      (Value::Other(value), Value::Other(other))   => core::ptr::addr_eq(*value, *other),
+      _                                            => false,
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    api::{
+      data_theory::DataDagNode,
+      dag_node::DagNode,
+      float::FloatAtom,
+      free_theory::FreeDagNode,
+      integer::{integer, IntegerAtom},
+      string::StringAtom,
+      value::Value
+    },
+    core::{module::Module, RootContainer}
+  };
+
+  #[test]
+  fn reduced_results_give_their_values_back() {
+    let module = Module::prelude_module("LIST").unwrap();
+    let symbol = |name: &str| module.arena.symbol(&name.into()).unwrap();
+    let cons   = |head, tail| {
+      let node = FreeDagNode::new(symbol("cons"));
+      unsafe{ &mut *node }.insert_child(head).unwrap();
+      unsafe{ &mut *node }.insert_child(tail).unwrap();
+      node
+    };
+
+    let seven = DataDagNode::new(integer(7));
+    let x     = DataDagNode::new(Box::new(StringAtom("x".to_string())));
+    let list  = cons(seven, cons(x, FreeDagNode::new(symbol("nil"))));
+    let _root = RootContainer::new(list);
+    let list  = unsafe{ &*list };
+    assert_eq!(list.to_string(), "cons(7, cons(\"x\", nil))");
+    assert_eq!(list.to_value(), Value::List(vec![Value::Int(7), Value::String("x".to_string())]));
+    assert_eq!(unsafe{ &*seven }.as_data::<IntegerAtom>(), Some(&IntegerAtom(7)));
+    assert_eq!(unsafe{ &*seven }.as_data::<FloatAtom>().map(|float| float.0), None);
+    assert_eq!(unsafe{ &*DataDagNode::new(Box::new(FloatAtom(0.5))) }.to_value(), Value::Float(0.5));
+
+    let open = cons(seven, FreeDagNode::new(symbol("append")));
+    assert!(matches!(unsafe{ &*open }.to_value(), Value::Other(node) if std::ptr::addr_eq(node, open)));
+    assert!(unsafe{ &*seven }.equals(DataDagNode::new(integer(7))));
+    assert!(!unsafe{ &*seven }.equals(DataDagNode::new(integer(8))));
+  }
+}
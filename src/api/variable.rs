@@ -1,5 +1,8 @@
-use std::fmt::{Display, Formatter};
-use crate::api::symbol::{Symbol, SymbolPtr};
+use core::fmt::{Display, Formatter};
+use crate::{
+  api::symbol::{Symbol, SymbolPtr},
+  core::sort::SortPtr
+};
 
 /// The `VariableType` of a variable determines what the variable is able to bind to. A `Blank` variable binds to a
 /// single `Term`, a `Sequence` variable binds to a sequence of one or more `Term`s, and a `NullSequence` binds to a
@@ -11,22 +14,35 @@ pub enum VariableType {
   NullSequence,   // Zero-or-more wildcard (a blank null sequence)
 }
 
+/// A variable, which binds to terms whose sort is at most the declared sort of its symbol, if it has one.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Variable {
   pub symbol:        SymbolPtr,
   pub variable_type: VariableType,
 }
 
+impl Variable {
+  /// The declared sort of the variable, or `None` if it binds to terms of any sort.
+  #[inline(always)]
+  pub fn sort(&self) -> Option<SortPtr> {
+    unsafe{ &*self.symbol }.variable_sort()
+  }
+}
+
 impl Display for Variable {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     let symbol: &Symbol = unsafe {
       &*(self.symbol)
     };
 
     match self.variable_type {
-      VariableType::Blank        => write!(f, "{}_",   symbol),
-      VariableType::Sequence     => write!(f, "{}__",  symbol),
-      VariableType::NullSequence => write!(f, "{}___", symbol),
+      VariableType::Blank        => write!(f, "{}_",   symbol)?,
+      VariableType::Sequence     => write!(f, "{}__",  symbol)?,
+      VariableType::NullSequence => write!(f, "{}___", symbol)?,
+    }
+    match self.sort() {
+      Some(sort) => write!(f, ":{}", unsafe{ &*sort }),
+      None       => Ok(())
     }
   }
 }
@@ -0,0 +1,165 @@
+/*!
+
+A `ConstrainedVariable` is a pattern variable with side constraints on what it may bind to: membership in a sort,
+any number of user predicates, and any number of pattern tests. Predicates are typically used to restrict a variable to
+data atoms with some property, like a positive integer.
+
+```ignore
+let positive = ConstrainedVariable::new(x)
+    .with_sort(nat)
+    .with_predicate(|node| is_positive(node));
+let pattern = free_term(f, vec![positive.into_term()]);
+```
+
+Constraints are delayed. They are not checked while the subject is matched structurally but by `CompiledPattern` once
+structural matching and the equality constraints of non-linear variables have succeeded, when every binding is known.
+A pattern whose structure doesn't match the subject therefore never runs a predicate, and a variable occurring more
+than once has its constraints checked once.
+
+Pattern tests, like `x_?IntegerQ`, are the exception. They are checked by the variable's automaton as soon as it would
+bind, before the rest of the subject is matched, so a failing test prunes the search early: an associative pattern
+doesn't go on to try the remaining arguments against a split that already fails, for example. The price is that a test
+may run for a binding that a later part of the match rejects anyway, and that it runs once per occurrence of the
+variable. Use a test for a cheap predicate that rejects often and a predicate for everything else.
+
+A variable can also be restricted to the bindings matched by one of several patterns, like `x:(a|f[_])`, or by none of
+them, like `x:Except[a]`. See `pattern_constructs`.
+
+*/
+
+use core::fmt::{Debug, Formatter};
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+
+use crate::{
+  api::{
+    dag_node::{as_node_ptr, DagNode, DagNodePtr},
+    symbol::SymbolPtr,
+    term::BxTerm,
+    variable_theory::VariableTerm
+  },
+  core::{
+    pattern_cache::CachedPattern,
+    sort::{SortPtr, SpecialSort}
+  }
+};
+
+/// A user predicate on the bindings of a `ConstrainedVariable`
+pub type VariablePredicate = Rc<dyn Fn(&dyn DagNode) -> bool>;
+
+#[derive(Clone)]
+pub struct ConstrainedVariable {
+  symbol    : SymbolPtr,
+  sort      : Option<SortPtr>,
+  predicates: Vec<VariablePredicate>,
+  /// Predicates checked during matching rather than after
+  tests     : Vec<VariablePredicate>,
+}
+
+impl ConstrainedVariable {
+  /// A variable with no constraints yet. The `symbol` must be a variable symbol.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn new(symbol: SymbolPtr) -> Self {
+    assert!(unsafe{ &*symbol }.is_variable(), "tried to constrain a non-variable symbol");
+
+    ConstrainedVariable {
+      symbol,
+      sort      : None,
+      predicates: Vec::new(),
+      tests     : Vec::new(),
+    }
+  }
+
+  /// Restricts the variable to bindings whose sort is `sort` or one of its subsorts.
+  pub fn with_sort(mut self, sort: SortPtr) -> Self {
+    self.sort = Some(sort);
+    self
+  }
+
+  /// Restricts the variable to bindings satisfying `predicate`.
+  pub fn with_predicate(mut self, predicate: impl Fn(&dyn DagNode) -> bool + 'static) -> Self {
+    self.predicates.push(Rc::new(predicate));
+    self
+  }
+
+  /// Restricts the variable to bindings satisfying `test`, which is checked during matching. See the module
+  /// documentation.
+  pub fn with_test(mut self, test: impl Fn(&dyn DagNode) -> bool + 'static) -> Self {
+    self.tests.push(Rc::new(test));
+    self
+  }
+
+  /// Restricts the variable to bindings matched by at least one of `alternatives`. See `pattern_constructs`.
+  pub fn with_alternatives(self, alternatives: Vec<Rc<CachedPattern>>) -> Self {
+    self.with_predicate(move |node| alternatives.iter().any(|pattern| pattern.match_(as_node_ptr(node)).is_some()))
+  }
+
+  /// Restricts the variable to bindings not matched by `excluded`. See `pattern_constructs`.
+  pub fn except(self, excluded: Rc<CachedPattern>) -> Self {
+    self.with_predicate(move |node| excluded.match_(as_node_ptr(node)).is_none())
+  }
+
+  /// The same constraints on the variable `symbol`
+  pub(crate) fn with_symbol(&self, symbol: SymbolPtr) -> Self {
+    ConstrainedVariable { symbol, ..self.clone() }
+  }
+
+  #[inline(always)]
+  pub fn symbol(&self) -> SymbolPtr {
+    self.symbol
+  }
+
+  #[inline(always)]
+  pub fn sort(&self) -> Option<SortPtr> {
+    self.sort
+  }
+
+  /// The pattern tests, which the variable's automaton checks
+  #[inline(always)]
+  pub fn tests(&self) -> &[VariablePredicate] {
+    &self.tests
+  }
+
+  /// Whether `binding` satisfies every delayed constraint, that is, every constraint but the tests. The sort of the
+  /// binding is computed on demand, and a binding whose sort can't be computed fails a sort constraint.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn is_satisfied_by(&self, binding: DagNodePtr) -> bool {
+    if let Some(sort) = self.sort {
+      let binding = unsafe{ &mut *binding };
+      binding.compute_sort();
+      let sort_index = binding.sort_index();
+      if sort_index == SpecialSort::Unknown || !unsafe{ &*sort }.geq_index(sort_index as usize) {
+        return false;
+      }
+    }
+
+    let binding = unsafe{ &*binding };
+    self.predicates.iter().all(|predicate| predicate(binding))
+  }
+
+  /// Whether `self` and `other` impose the same constraints, as they do if one is a clone of the other.
+  pub(crate) fn same_constraints(&self, other: &ConstrainedVariable) -> bool {
+    self.sort == other.sort
+        && same_predicates(&self.predicates, &other.predicates)
+        && same_predicates(&self.tests, &other.tests)
+  }
+
+  /// A pattern term for the variable.
+  pub fn into_term(self) -> BxTerm {
+    Box::new(VariableTerm::constrained(self))
+  }
+}
+
+fn same_predicates(first: &[VariablePredicate], second: &[VariablePredicate]) -> bool {
+  first.len() == second.len() && first.iter().zip(second.iter()).all(|(a, b)| Rc::ptr_eq(a, b))
+}
+
+impl Debug for ConstrainedVariable {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("ConstrainedVariable")
+     .field("symbol", &unsafe{ &*self.symbol }.name)
+     .field("sort", &self.sort.map(|sort| unsafe{ &*sort }.name.clone()))
+     .field("predicates", &self.predicates.len())
+     .field("tests", &self.tests.len())
+     .finish()
+  }
+}
@@ -0,0 +1,10 @@
+mod variable_term;
+mod variable_dag_node;
+mod variable_lhs_automaton;
+mod constrained_variable;
+
+pub use variable_term::VariableTerm;
+pub use variable_dag_node::VariableDagNode;
+#[doc(hidden)]
+pub use variable_lhs_automaton::VariableLHSAutomaton;
+pub use constrained_variable::{ConstrainedVariable, VariablePredicate};
@@ -0,0 +1,47 @@
+use core::any::Any;
+
+use crate::{
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::SymbolPtr
+  },
+  core::dag_node_core::{
+    DagNodeCore,
+    DagNodeTheory
+  }
+};
+
+/// A variable that occurs in a DAG. Variable nodes never have arguments, so the default `DagNode` implementations
+/// for the (empty) `args` apply.
+#[repr(transparent)]
+pub struct VariableDagNode(DagNodeCore);
+
+impl VariableDagNode {
+  #[allow(clippy::new_ret_no_self)]
+  pub fn new(symbol: SymbolPtr) -> DagNodePtr {
+    assert!(!symbol.is_null());
+    DagNodeCore::with_theory(symbol, DagNodeTheory::Variable)
+  }
+}
+
+impl DagNode for VariableDagNode {
+  #[inline(always)]
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  #[inline(always)]
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  #[inline(always)]
+  fn core(&self) -> &DagNodeCore {
+    &self.0
+  }
+
+  #[inline(always)]
+  fn core_mut(&mut self) -> &mut DagNodeCore {
+    &mut self.0
+  }
+}
@@ -0,0 +1,56 @@
+use alloc::vec::Vec;
+
+use crate::{
+  api::{
+    dag_node::DagNodePtr,
+    variable_theory::VariablePredicate
+  },
+  core::{
+    automata::LHSAutomaton,
+    sort::SortPtr,
+    substitution::Substitution
+  }
+};
+
+/// Binds the subject to the variable's slot in the substitution. Repeated occurrences of a variable are compiled to
+/// distinct slots, so the slot is always unbound when this automaton runs. See `CompiledPattern`.
+///
+/// A sorted variable only binds a subject whose sort, computed on demand, is the variable's sort or one of its
+/// subsorts. A subject whose sort can't be computed doesn't match. A variable with pattern tests (see
+/// `ConstrainedVariable`) only binds a subject that passes all of them.
+pub struct VariableLHSAutomaton {
+  index: i32,
+  sort : Option<SortPtr>,
+  tests: Vec<VariablePredicate>,
+}
+
+impl VariableLHSAutomaton {
+  pub fn new(index: i32, sort: Option<SortPtr>) -> Self {
+    Self { index, sort, tests: Vec::new() }
+  }
+
+  /// The same automaton, checking `tests` before it binds
+  pub fn with_tests(mut self, tests: Vec<VariablePredicate>) -> Self {
+    self.tests = tests;
+    self
+  }
+}
+
+impl LHSAutomaton for VariableLHSAutomaton {
+  #[inline(always)]
+  fn match_(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool {
+    if let Some(sort) = self.sort {
+      match unsafe{ &mut *subject }.compute_sort() {
+        Some(subject_sort) if unsafe{ (*subject_sort).leq(&*sort) } => {}
+        _ => return false
+      }
+    }
+
+    if !self.tests.iter().all(|test| test(unsafe{ &*subject })) {
+      return false;
+    }
+
+    solution.bind(self.index, Some(subject));
+    true
+  }
+}
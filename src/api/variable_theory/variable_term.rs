@@ -0,0 +1,236 @@
+use core::{
+  any::Any,
+  cmp::Ordering,
+  fmt::{Display, Formatter}
+};
+use alloc::{boxed::Box, rc::Rc, string::String, vec::Vec};
+
+use crate::{
+  abstractions::NatSet,
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::SymbolPtr,
+    term::{BxTerm, Term},
+    variable_theory::{
+      constrained_variable::{ConstrainedVariable, VariablePredicate},
+      variable_dag_node::VariableDagNode,
+      variable_lhs_automaton::VariableLHSAutomaton
+    },
+    UNDEFINED
+  },
+  core::{
+    automata::{
+      RcLHSAutomaton,
+      EqualityConstraint,
+      RHSBuilder,
+      SharedAutomata,
+      TermBag
+    },
+    format::{
+      format_term,
+      FormatStyle,
+      Formattable,
+      PrintSettings
+    },
+    renaming::VariableRenaming,
+    term_core::TermCore,
+    VariableInfo
+  }
+};
+
+pub struct VariableTerm {
+  core            : TermCore,
+  /// The index of the variable within its `VariableInfo`. It is assigned by `Term::index_variables`.
+  pub(crate) index: i32,
+  /// Side constraints on the variable's binding, checked by `CompiledPattern` or, for tests, by the automaton
+  constraint      : Option<Rc<ConstrainedVariable>>,
+}
+
+impl VariableTerm {
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn new(symbol: SymbolPtr) -> Self {
+    assert!(!symbol.is_null());
+    assert!(unsafe{ &*symbol }.is_variable(), "tried to create a variable term from a non-variable symbol");
+
+    Self {
+      core      : TermCore::new(symbol),
+      index     : UNDEFINED,
+      constraint: None,
+    }
+  }
+
+  /// A variable term whose bindings must satisfy `constraint`.
+  pub fn constrained(constraint: ConstrainedVariable) -> Self {
+    let mut term    = Self::new(constraint.symbol());
+    term.constraint = Some(Rc::new(constraint));
+
+    term
+  }
+
+  #[inline(always)]
+  pub fn constraint(&self) -> Option<&Rc<ConstrainedVariable>> {
+    self.constraint.as_ref()
+  }
+
+  /// The pattern tests of the constraint, if any
+  #[inline(always)]
+  pub fn tests(&self) -> &[VariablePredicate] {
+    self.constraint.as_ref().map_or(&[], |constraint| constraint.tests())
+  }
+
+  #[inline(always)]
+  pub fn index(&self) -> i32 {
+    self.index
+  }
+}
+
+impl Display for VariableTerm {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    (self as &dyn Term).fmt(f)
+  }
+}
+
+impl Formattable for VariableTerm {
+  fn repr_with(&self, style: FormatStyle, settings: &PrintSettings) -> String {
+    format_term(self, style, settings)
+  }
+}
+
+impl Term for VariableTerm {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  fn as_ptr(&self) -> *const dyn Term {
+    self
+  }
+
+  fn semantic_hash(&self) -> u32 {
+    self.symbol_ref().hash_value
+  }
+
+  fn normalize(&mut self, _full: bool) -> (u32, bool) {
+    (self.semantic_hash(), false)
+  }
+
+  fn core(&self) -> &TermCore {
+    &self.core
+  }
+
+  fn core_mut(&mut self) -> &mut TermCore {
+    &mut self.core
+  }
+
+  fn iter_args(&self) -> Box<dyn Iterator<Item=&dyn Term> + '_> {
+    Box::new(core::iter::empty::<&dyn Term>())
+  }
+
+  #[inline(always)]
+  fn args_mut(&mut self) -> &mut [BxTerm] {
+    &mut []
+  }
+
+  // region Comparison Methods
+
+  /// Variables are equal if their symbols are equal, which is checked by the caller.
+  fn compare_term_arguments(&self, _other: &dyn Term) -> Ordering {
+    Ordering::Equal
+  }
+
+  fn compare_dag_arguments(&self, _other: &dyn DagNode) -> Ordering {
+    Ordering::Equal
+  }
+
+  // endregion
+
+  fn dagify_aux(&self) -> DagNodePtr {
+    VariableDagNode::new(self.symbol())
+  }
+
+  /// The constraint, if any, carries over to the renamed variable.
+  fn rename_variables(&self, renaming: &mut VariableRenaming) -> BxTerm {
+    let symbol = renaming.rename(self.symbol());
+    match &self.constraint {
+      Some(constraint) => Box::new(VariableTerm::constrained(constraint.with_symbol(symbol))),
+      None             => Box::new(VariableTerm::new(symbol)),
+    }
+  }
+
+  fn deep_copy(&self) -> BxTerm {
+    Box::new(
+      VariableTerm {
+        core      : self.core.clone(),
+        index     : self.index,
+        constraint: self.constraint.clone(),
+      }
+    )
+  }
+
+  fn with_new_args(&self, args: Vec<BxTerm>) -> BxTerm {
+    debug_assert!(args.is_empty(), "a variable has no arguments");
+    Box::new(VariableTerm::new(self.symbol()))
+  }
+
+  // region Compiler-related
+
+  fn index_variables(&mut self, variable_info: &mut VariableInfo) {
+    // The `VariableInfo` belongs to the statement that owns this term, so it never outlives `self`.
+    let this: &'static dyn Term = unsafe{ &*(self as *const dyn Term) };
+    let index  = variable_info.variable_to_index(this);
+    self.index = index;
+
+    let occurs_set = self.occurs_below_mut();
+    occurs_set.clear();
+    occurs_set.insert(index as usize);
+  }
+
+  fn compile_lhs(
+    &self,
+    variable_info   : &mut VariableInfo,
+    bound_uniquely  : &mut NatSet,
+    constraints     : &mut Vec<EqualityConstraint>,
+    _shared_automata: &mut SharedAutomata
+  ) -> RcLHSAutomaton
+  {
+    assert_ne!(self.index, UNDEFINED, "tried to compile a variable that has not been indexed");
+
+    let index = if bound_uniquely.contains(self.index as usize) {
+      // A repeated (non-linear) occurrence binds a fresh slot which is compared against the original binding after
+      // structural matching succeeds.
+      let duplicate = variable_info.make_protected_variable();
+      constraints.push(EqualityConstraint { original: self.index, duplicate });
+      duplicate
+    } else {
+      bound_uniquely.insert(self.index as usize);
+      self.index
+    };
+
+    let automaton = VariableLHSAutomaton::new(index, self.symbol_ref().variable_sort());
+    Rc::new(automaton.with_tests(self.tests().to_vec()))
+  }
+
+  /// A variable is bound by matching, so its binding is available wherever it occurs, even if it occurs under a lazy
+  /// argument: a construction in an eager context reduces the binding when it reduces the constructed node.
+  fn find_available_terms_aux(&mut self, available_terms: &mut TermBag, _eager_context: bool, _at_top: bool) {
+    available_terms.insert_matched_term(self, true);
+  }
+
+  /// Bound variables are always found in the `TermBag` by `compile_rhs`, so this is only reached for a variable that
+  /// does not occur in the left-hand side.
+  fn compile_rhs_aux(
+    &mut self,
+    _rhs_builder    : &mut RHSBuilder,
+    _variable_info  : &mut VariableInfo,
+    _available_terms: &mut TermBag,
+    _eager_context  : bool
+  ) -> i32
+  {
+    panic!("unbound variable {} in right-hand side", self.symbol_ref());
+  }
+
+  // endregion
+}
@@ -0,0 +1,606 @@
+/*!
+
+Utilities for benchmarking the library: random term and DAG generation, and self-contained fixtures for the operations
+the benches in `benches/` measure. Each fixture owns everything it allocates (symbols, modules, and GC roots), so a
+bench only needs to construct a fixture and call `run` in its timing loop.
+
+DAGs created by a fixture's `run` method are garbage. The fixtures call `collect_garbage` at the end of `run` so that
+long benchmark runs don't exhaust memory.
+
+*/
+
+use alloc::{boxed::Box, format, rc::Rc, vec, vec::Vec};
+
+use rand::{
+  rngs::StdRng,
+  Rng,
+  SeedableRng
+};
+
+use crate::{
+  abstractions::{
+    IString,
+    NatSet
+  },
+  api::{
+    Arity,
+    dag_node::DagNodePtr,
+    free_theory::{FreeDagNode, FreeTerm},
+    symbol::{Symbol, SymbolAttribute, SymbolPtr},
+    term::BxTerm,
+    variable_theory::VariableTerm
+  },
+  core::{
+    allocator::ok_to_collect_garbage,
+    automata::CompiledPattern,
+    canonicalize::canonicalize,
+    module::Module,
+    pre_equation::{PreEquation, PreEquationKind},
+    rewriting_context::RewritingContext,
+    substitution::Substitution,
+    RootContainer,
+    VariableInfo
+  },
+  testing::build_random_tree
+};
+
+/// Runs the garbage collector if it wants to run. Only nodes reachable from a `RootContainer` survive.
+#[inline(always)]
+pub fn collect_garbage() {
+  ok_to_collect_garbage();
+}
+
+// region Random terms
+
+fn random_term_aux<R: Rng + ?Sized>(
+  rng       : &mut R,
+  symbols   : &[SymbolPtr],
+  max_height: usize,
+  max_width : usize,
+  min_width : usize,
+) -> BxTerm {
+  let arity = if max_height == 0 { 0 } else { rng.random_range(min_width..=max_width) };
+  let mut term = FreeTerm::new(symbols[arity]);
+  term.args = (0..arity)
+      .map(|_| random_term_aux(rng, symbols, max_height - 1, max_width, min_width))
+      .collect();
+
+  Box::new(term)
+}
+
+/// A signature with one free symbol `f{i}` of each arity `i` from 0 to the maximum arity.
+#[allow(clippy::vec_box)]
+pub struct RandomSignature {
+  symbols: Vec<Box<Symbol>>,
+}
+
+impl RandomSignature {
+  pub fn new(max_arity: usize) -> Self {
+    let symbols = (0..=max_arity)
+        .map(|arity| Box::new(Symbol::new(IString::from(format!("f{}", arity)), Arity::Value(arity as u16))))
+        .collect();
+
+    RandomSignature { symbols }
+  }
+
+  #[inline(always)]
+  pub fn max_arity(&self) -> usize {
+    self.symbols.len() - 1
+  }
+
+  /// The symbols indexed by arity.
+  fn symbol_ptrs(&self) -> Vec<SymbolPtr> {
+    self.symbols.iter().map(|symbol| symbol.as_ref() as *const Symbol as SymbolPtr).collect()
+  }
+}
+
+/// Generates random terms and DAGs over a `RandomSignature`. Generation is deterministic for a given seed.
+pub struct RandomTermGenerator {
+  signature: Rc<RandomSignature>,
+  symbols  : Vec<SymbolPtr>,
+  rng      : StdRng,
+}
+
+impl RandomTermGenerator {
+  pub fn new(max_arity: usize, seed: u64) -> Self {
+    let signature = Rc::new(RandomSignature::new(max_arity));
+    let symbols   = signature.symbol_ptrs();
+
+    RandomTermGenerator {
+      signature,
+      symbols,
+      rng: StdRng::seed_from_u64(seed)
+    }
+  }
+
+  #[inline(always)]
+  pub fn signature(&self) -> &RandomSignature {
+    &self.signature
+  }
+
+  fn clamp_widths(&self, max_width: usize, min_width: usize) -> (usize, usize) {
+    let max_width = core::cmp::min(max_width, self.signature.max_arity());
+    (max_width, core::cmp::min(min_width, max_width))
+  }
+
+  /// A random term of height at most `max_height` in which every non-leaf node has between `min_width` and
+  /// `max_width` arguments.
+  pub fn random_term(&mut self, max_height: usize, max_width: usize, min_width: usize) -> RandomTerm {
+    let (max_width, min_width) = self.clamp_widths(max_width, min_width);
+    let term = random_term_aux(&mut self.rng, &self.symbols, max_height, max_width, min_width);
+
+    RandomTerm {
+      term,
+      _signature: self.signature.clone()
+    }
+  }
+
+  /// A random rooted DAG (in fact a tree) with the same shape parameters as `random_term`.
+  pub fn random_dag(&mut self, max_height: usize, max_width: usize, min_width: usize) -> RandomDag {
+    let (max_width, min_width) = self.clamp_widths(max_width, min_width);
+    let arity = if max_height == 0 { 0 } else { self.rng.random_range(min_width..=max_width) };
+    let node  = FreeDagNode::new(self.symbols[arity]);
+    build_random_tree(&mut self.rng, &self.symbols, node, max_height, max_width, min_width);
+
+    RandomDag {
+      _root     : RootContainer::new(node),
+      node,
+      _signature: self.signature.clone()
+    }
+  }
+}
+
+/// A random term. It keeps its signature alive.
+pub struct RandomTerm {
+  term      : BxTerm,
+  _signature: Rc<RandomSignature>,
+}
+
+impl RandomTerm {
+  /// The number of nodes in the term
+  #[inline(always)]
+  pub fn size(&self) -> usize {
+    self.term.compute_size() as usize
+  }
+
+  /// Converts the term to a DAG, which is immediately garbage.
+  pub fn dagify(&self) {
+    self.term.term_to_dag(false);
+  }
+}
+
+/// A random DAG that is protected from garbage collection for as long as it lives. It keeps its signature alive.
+pub struct RandomDag {
+  _root     : Box<RootContainer>,
+  node      : DagNodePtr,
+  _signature: Rc<RandomSignature>,
+}
+
+impl RandomDag {
+  /// The number of nodes in the DAG, counting shared nodes once per occurrence
+  pub fn size(&self) -> usize {
+    fn size_aux(node: DagNodePtr) -> usize {
+      1 + unsafe{ &*node }.iter_args().map(size_aux).sum::<usize>()
+    }
+    size_aux(self.node)
+  }
+
+  #[inline(always)]
+  pub(crate) fn node(&self) -> DagNodePtr {
+    self.node
+  }
+}
+
+// endregion Random terms
+
+// region Matching
+
+/// Matches a compiled pattern against a batch of random subjects. The pattern is the top `pattern_depth` levels of
+/// the first subject with fresh variables below, so at least one subject matches.
+#[allow(clippy::vec_box)]
+pub struct MatchingBench {
+  subjects     : Vec<RandomDag>,
+  pattern      : CompiledPattern,
+  solution     : Substitution,
+  // The `VariableInfo` refers to the pattern's variable terms, and the terms refer to the variable symbols.
+  variable_info: VariableInfo,
+  pattern_term : BxTerm,
+  variables    : Vec<Box<Symbol>>,
+}
+
+impl MatchingBench {
+  pub fn new(
+    generator    : &mut RandomTermGenerator,
+    subject_count: usize,
+    pattern_depth: usize,
+    max_height   : usize,
+    max_width    : usize,
+  ) -> Self {
+    let subjects: Vec<RandomDag> = (0..subject_count.max(1))
+        .map(|_| generator.random_dag(max_height, max_width, 1))
+        .collect();
+
+    let mut variables    = Vec::new();
+    let mut pattern_term = pattern_from_dag(subjects[0].node(), pattern_depth, &mut variables);
+
+    let mut variable_info = VariableInfo::new();
+    pattern_term.index_variables(&mut variable_info);
+    let pattern  = CompiledPattern::new(pattern_term.as_ref(), &mut variable_info, &mut NatSet::new());
+    let solution = Substitution::with_capacity(variable_info.protected_variable_count() as usize);
+
+    MatchingBench {
+      subjects,
+      pattern,
+      solution,
+      variable_info,
+      pattern_term,
+      variables
+    }
+  }
+
+  /// Matches the pattern against every subject. Returns the number of subjects that matched.
+  pub fn run(&mut self) -> usize {
+    let size = self.variable_info.protected_variable_count() as usize;
+    let mut matched = 0;
+
+    for subject in self.subjects.iter() {
+      self.solution.clear_first_n(size);
+      if self.pattern.match_(subject.node(), &mut self.solution) {
+        matched += 1;
+      }
+    }
+
+    matched
+  }
+}
+
+/// A linear pattern with the same top `depth` levels as `node` and a fresh variable below.
+#[allow(clippy::vec_box)]
+fn pattern_from_dag(node: DagNodePtr, depth: usize, variables: &mut Vec<Box<Symbol>>) -> BxTerm {
+  if depth == 0 {
+    let variable = Box::new(Symbol::new_variable(IString::from(format!("X{}", variables.len()))));
+    let term     = VariableTerm::new(variable.as_ref() as *const Symbol as SymbolPtr);
+    variables.push(variable);
+    return Box::new(term);
+  }
+
+  let node     = unsafe{ &*node };
+  let mut term = FreeTerm::new(node.symbol());
+  term.args    = node.iter_args().map(|arg| pattern_from_dag(arg, depth - 1, variables)).collect();
+
+  Box::new(term)
+}
+
+// endregion Matching
+
+// region Reduction
+
+fn app(symbol: SymbolPtr, args: Vec<BxTerm>) -> BxTerm {
+  FreeTerm::with_args(symbol, args)
+}
+
+fn var(symbol: SymbolPtr) -> BxTerm {
+  Box::new(VariableTerm::new(symbol))
+}
+
+fn dag(symbol: SymbolPtr, args: &[DagNodePtr]) -> DagNodePtr {
+  let node     = FreeDagNode::new(symbol);
+  let node_mut = unsafe{ &mut *node };
+  for &arg in args {
+    node_mut.insert_child_unchecked(arg);
+  }
+  node
+}
+
+/// Builds the module for a reduction fixture. The module owns the symbols.
+#[derive(Default)]
+struct ModuleBuilder {
+  module: Module,
+}
+
+impl ModuleBuilder {
+  fn symbol(&mut self, name: &str, arity: u16) -> SymbolPtr {
+    self.module.arena.add_symbol(Symbol::new(IString::from(name), Arity::Value(arity)))
+  }
+
+  fn variable(&mut self, name: &str) -> SymbolPtr {
+    self.module.arena.add_symbol(Symbol::new_variable(IString::from(name)))
+  }
+
+  fn equation(&mut self, lhs: BxTerm, rhs: BxTerm) {
+    self.module.equations.push(PreEquation::new(None, lhs, PreEquationKind::Equation { rhs_term: rhs }, vec![]));
+  }
+
+  fn build(mut self) -> Module {
+    self.module.compile_statements().expect("a benchmark equation has an unbound variable");
+    self.module
+  }
+
+  /// Adds Peano naturals with `plus`, returning `(zero, s, plus)`.
+  fn naturals(&mut self) -> (SymbolPtr, SymbolPtr, SymbolPtr) {
+    let zero = self.symbol("0", 0);
+    let s    = self.symbol("s", 1);
+    let plus = self.symbol("plus", 2);
+    let x    = self.variable("X");
+    let y    = self.variable("Y");
+
+    // plus(0, Y) = Y
+    self.equation(app(plus, vec![app(zero, vec![]), var(y)]), var(y));
+    // plus(s(X), Y) = s(plus(X, Y))
+    self.equation(app(plus, vec![app(s, vec![var(x)]), var(y)]), app(s, vec![app(plus, vec![var(x), var(y)])]));
+
+    (zero, s, plus)
+  }
+}
+
+fn numeral(zero: SymbolPtr, s: SymbolPtr, n: usize) -> DagNodePtr {
+  let mut node = dag(zero, &[]);
+  for _ in 0..n {
+    node = dag(s, &[node]);
+  }
+  node
+}
+
+/// Reduces a fixed subject with the equations of one of the standard examples.
+pub struct ReductionBench {
+  // The root must be dropped before the module that owns the symbols.
+  _root  : Box<RootContainer>,
+  subject: DagNodePtr,
+  module : Module,
+}
+
+impl ReductionBench {
+  fn new(module: Module, subject: DagNodePtr) -> Self {
+    ReductionBench {
+      _root: RootContainer::new(subject),
+      subject,
+      module
+    }
+  }
+
+  /// `plus(n, n)` with Peano naturals.
+  pub fn naturals_addition(n: usize) -> Self {
+    let mut builder     = ModuleBuilder::default();
+    let (zero, s, plus) = builder.naturals();
+    let subject         = dag(plus, &[numeral(zero, s, n), numeral(zero, s, n)]);
+
+    ReductionBench::new(builder.build(), subject)
+  }
+
+  /// `fib(n)` with Peano naturals.
+  pub fn fibonacci(n: usize) -> Self {
+    let mut builder     = ModuleBuilder::default();
+    let (zero, s, plus) = builder.naturals();
+    let fib             = builder.symbol("fib", 1);
+    let n_var           = builder.variable("N");
+
+    // fib(0) = 0
+    builder.equation(app(fib, vec![app(zero, vec![])]), app(zero, vec![]));
+    // fib(s(0)) = s(0)
+    builder.equation(app(fib, vec![app(s, vec![app(zero, vec![])])]), app(s, vec![app(zero, vec![])]));
+    // fib(s(s(N))) = plus(fib(s(N)), fib(N))
+    builder.equation(
+      app(fib, vec![app(s, vec![app(s, vec![var(n_var)])])]),
+      app(plus, vec![app(fib, vec![app(s, vec![var(n_var)])]), app(fib, vec![var(n_var)])])
+    );
+
+    let subject = dag(fib, &[numeral(zero, s, n)]);
+    ReductionBench::new(builder.build(), subject)
+  }
+
+  /// Insertion sort of a random list of `length` naturals less than `length`.
+  pub fn sorting(length: usize, seed: u64) -> Self {
+    let mut builder    = ModuleBuilder::default();
+    let (zero, s, _)   = builder.naturals();
+    let true_          = builder.symbol("true", 0);
+    let false_         = builder.symbol("false", 0);
+    let leq            = builder.symbol("leq", 2);
+    let if_            = builder.symbol("if", 3);
+    let nil            = builder.symbol("nil", 0);
+    let cons           = builder.symbol("cons", 2);
+    let insert         = builder.symbol("insert", 2);
+    let sort           = builder.symbol("sort", 1);
+    let (n, m, l)      = (builder.variable("N"), builder.variable("M"), builder.variable("L"));
+    let (a, b)         = (builder.variable("A"), builder.variable("B"));
+
+    // leq(0, N) = true
+    builder.equation(app(leq, vec![app(zero, vec![]), var(n)]), app(true_, vec![]));
+    // leq(s(N), 0) = false
+    builder.equation(app(leq, vec![app(s, vec![var(n)]), app(zero, vec![])]), app(false_, vec![]));
+    // leq(s(N), s(M)) = leq(N, M)
+    builder.equation(app(leq, vec![app(s, vec![var(n)]), app(s, vec![var(m)])]), app(leq, vec![var(n), var(m)]));
+    // if(true, A, B) = A
+    builder.equation(app(if_, vec![app(true_, vec![]), var(a), var(b)]), var(a));
+    // if(false, A, B) = B
+    builder.equation(app(if_, vec![app(false_, vec![]), var(a), var(b)]), var(b));
+    // insert(N, nil) = cons(N, nil)
+    builder.equation(app(insert, vec![var(n), app(nil, vec![])]), app(cons, vec![var(n), app(nil, vec![])]));
+    // insert(N, cons(M, L)) = if(leq(N, M), cons(N, cons(M, L)), cons(M, insert(N, L)))
+    builder.equation(
+      app(insert, vec![var(n), app(cons, vec![var(m), var(l)])]),
+      app(
+        if_,
+        vec![
+          app(leq, vec![var(n), var(m)]),
+          app(cons, vec![var(n), app(cons, vec![var(m), var(l)])]),
+          app(cons, vec![var(m), app(insert, vec![var(n), var(l)])])
+        ]
+      )
+    );
+    // sort(nil) = nil
+    builder.equation(app(sort, vec![app(nil, vec![])]), app(nil, vec![]));
+    // sort(cons(N, L)) = insert(N, sort(L))
+    builder.equation(
+      app(sort, vec![app(cons, vec![var(n), var(l)])]),
+      app(insert, vec![var(n), app(sort, vec![var(l)])])
+    );
+
+    let mut rng  = StdRng::seed_from_u64(seed);
+    let mut list = dag(nil, &[]);
+    for _ in 0..length {
+      list = dag(cons, &[numeral(zero, s, rng.random_range(0..length.max(1))), list]);
+    }
+
+    let subject = dag(sort, &[list]);
+    ReductionBench::new(builder.build(), subject)
+  }
+
+  /// Reduces the subject, returning the normal form. The normal form is not rooted.
+  pub(crate) fn reduce(&self) -> (DagNodePtr, usize) {
+    let mut context = RewritingContext::new(&self.module);
+    let result      = context.reduce(self.subject);
+    (result, context.equation_count())
+  }
+
+  /// Reduces the subject and then collects garbage. Returns the number of equational rewrites.
+  pub fn run(&self) -> usize {
+    let (_, equation_count) = self.reduce();
+    collect_garbage();
+    equation_count
+  }
+}
+
+// endregion Reduction
+
+// region AC normalization
+
+fn random_union<R: Rng + ?Sized>(rng: &mut R, union: SymbolPtr, leaves: &[SymbolPtr], size: usize) -> BxTerm {
+  if size <= 1 {
+    return app(leaves[rng.random_range(0..leaves.len())], vec![]);
+  }
+  let left = rng.random_range(1..size);
+  app(union, vec![random_union(rng, union, leaves, left), random_union(rng, union, leaves, size - left)])
+}
+
+/// Puts randomly nested terms of an ACI `union` with identity `empty` in canonical form. The leaves are drawn from
+/// `empty` and a fixed set of constants, so canonicalization flattens, removes identities, sorts, and deduplicates.
+pub struct AcNormalizationBench {
+  // The terms must be dropped before the module that owns the symbols.
+  terms : Vec<BxTerm>,
+  module: Module,
+}
+
+impl AcNormalizationBench {
+  /// `term_count` terms, each with `size` leaves drawn from `constant_count` constants and `empty`.
+  pub fn new(term_count: usize, size: usize, constant_count: usize, seed: u64) -> Self {
+    let mut builder = ModuleBuilder::default();
+    let union       = builder.symbol("union", 2);
+    let empty       = builder.symbol("empty", 0);
+    unsafe{ &mut *union }.attributes = SymbolAttribute::Associative | SymbolAttribute::Commutative
+        | SymbolAttribute::Idempotent | SymbolAttribute::LeftIdentity | SymbolAttribute::RightIdentity;
+    builder.module.identities.insert(union, empty);
+
+    let mut leaves = vec![empty];
+    leaves.extend((0..constant_count).map(|i| builder.symbol(&format!("c{}", i), 0)));
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let terms   = (0..term_count).map(|_| random_union(&mut rng, union, &leaves, size)).collect();
+
+    AcNormalizationBench {
+      terms,
+      module: builder.build()
+    }
+  }
+
+  /// Canonicalizes every term. Returns the total size of the canonical forms.
+  pub fn run(&self) -> usize {
+    self.terms
+        .iter()
+        .map(|term| canonicalize(term.as_ref(), &self.module).compute_size() as usize)
+        .sum()
+  }
+}
+
+// endregion AC normalization
+
+// region Garbage collection
+
+/// Repeatedly builds random DAGs, drops them, and collects garbage.
+pub struct GcBench {
+  generator : RandomTermGenerator,
+  dag_count : usize,
+  max_height: usize,
+  max_width : usize,
+}
+
+impl GcBench {
+  pub fn new(dag_count: usize, max_height: usize, max_width: usize, seed: u64) -> Self {
+    GcBench {
+      generator: RandomTermGenerator::new(max_width, seed),
+      dag_count,
+      max_height,
+      max_width
+    }
+  }
+
+  /// Builds and drops `dag_count` DAGs, then collects garbage. Returns the number of nodes allocated.
+  pub fn run(&mut self) -> usize {
+    let dags: Vec<RandomDag> = (0..self.dag_count)
+        .map(|_| self.generator.random_dag(self.max_height, self.max_width, 0))
+        .collect();
+    let allocated = dags.iter().map(|dag| dag.size()).sum();
+
+    drop(dags);
+    collect_garbage();
+    allocated
+  }
+}
+
+// endregion Garbage collection
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    api::dag_node::DagNodePtr,
+    bench_utils::{AcNormalizationBench, MatchingBench, RandomTermGenerator, ReductionBench},
+    core::RootContainer
+  };
+
+  /// Counts the `s` symbols of a Peano numeral.
+  fn numeral_value(mut node: DagNodePtr) -> usize {
+    let mut value = 0;
+    while let Some(arg) = unsafe{ &*node }.iter_args().next() {
+      value += 1;
+      node = arg;
+    }
+    value
+  }
+
+  #[test]
+  fn standard_examples_reduce() {
+    let (sum, _) = ReductionBench::naturals_addition(7).reduce();
+    assert_eq!(numeral_value(sum), 14);
+
+    let (fib, _) = ReductionBench::fibonacci(10).reduce();
+    assert_eq!(numeral_value(fib), 55);
+
+    let (sorted, _) = ReductionBench::sorting(8, 0).reduce();
+    let _root       = RootContainer::new(sorted);
+    let mut values  = Vec::new();
+    let mut list    = sorted;
+    while unsafe{ &*list }.len() == 2 {
+      let args: Vec<DagNodePtr> = unsafe{ &*list }.iter_args().collect();
+      values.push(numeral_value(args[0]));
+      list = args[1];
+    }
+    assert_eq!(values.len(), 8);
+    assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+  }
+
+  #[test]
+  fn matching_bench_matches_its_source() {
+    let mut generator = RandomTermGenerator::new(3, 7);
+    let mut bench     = MatchingBench::new(&mut generator, 10, 2, 4, 3);
+    assert!(bench.run() >= 1);
+  }
+
+  #[test]
+  fn ac_normalization_bench_dedups_its_leaves() {
+    // Each canonical form is `empty`, a constant, or a flat union of distinct constants.
+    let bench = AcNormalizationBench::new(20, 50, 4, 3);
+    let total = bench.run();
+    assert!(total >= 20);
+    assert!(total <= 20 * (2 * 4 - 1));
+  }
+}
@@ -4,10 +4,8 @@ An arena allocator for `DagNode`s.
 
 */
 
-use std::{
-  mem::MaybeUninit,
-  ptr::null_mut
-};
+use core::ptr::{addr_of_mut, null_mut};
+use alloc::boxed::Box;
 
 use crate::{
   core::dag_node_core::DagNodeCore,
@@ -25,25 +23,21 @@ pub struct Arena {
 impl Arena {
   #[inline(always)]
   pub fn allocate_new_arena() -> *mut Arena {
-
-    // Create an uninitialized array
-    let data: [MaybeUninit<DagNodeCore>; ARENA_SIZE] = unsafe { MaybeUninit::uninit().assume_init() };
-
-    /* Each node is initialized on allocation, so we don't bother with this.
-    // Initialize each element
-    for elem in &mut data {
-      unsafe {
-        std::ptr::write(elem.as_mut_ptr(), DagNode::default());
+    // Allocated in place, as the array is too big to build on the stack and move.
+    let mut arena = Box::<Arena>::new_uninit();
+    let arena_ptr = arena.as_mut_ptr();
+
+    // The allocator sweeps nodes it has never handed out, so, as in Maude, every node starts with its flags clear,
+    // which makes it free without destruction. The other fields are written when the node is allocated.
+    unsafe {
+      addr_of_mut!((*arena_ptr).next_arena).write(null_mut());
+      let first_node = addr_of_mut!((*arena_ptr).data) as *mut DagNodeCore;
+      for index in 0..ARENA_SIZE {
+        DagNodeCore::clear_header(first_node.add(index));
       }
     }
-    */
-
-    let arena = Box::new(Arena{
-      next_arena: null_mut(),
-      data      : unsafe { std::mem::transmute::<_, [DagNodeCore; ARENA_SIZE]>(data) }
-    });
 
-    Box::into_raw(arena)
+    Box::into_raw(arena) as *mut Arena
   }
 
   #[inline(always)]
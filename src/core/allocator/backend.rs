@@ -0,0 +1,151 @@
+/*!
+
+The node allocator is abstracted behind the `NodeAllocatorBackend` trait, so that memory bugs can be chased with a
+backend that suits the tool at hand. Two backends are built in:
+
+ - `NodeAllocator`, the arena allocator, which is fast but reuses the storage of dead nodes in place, so that a stale
+   pointer silently reads whatever node was allocated in its place.
+ - `MallocNodeAllocator`, which allocates every node with the system allocator and frees it when it is swept, so that
+   AddressSanitizer and Valgrind report the use of a stale pointer where it happens. With this backend, the storage
+   allocator also gives each argument vector a heap allocation of its own, freed by the collection that leaves it
+   behind, so that stale argument vectors are caught too.
+
+`CountingNodeAllocator` wraps another backend and counts what it does, for tests, and any other implementation of the
+trait can be installed with `install_node_allocator`.
+
+The built-in backend is chosen by the `backend` of the `AllocatorConfig`, which defaults to the malloc backend in builds
+with the `malloc_nodes` feature and to the arena backend otherwise. Nodes can't move between backends, so the backend
+can only be changed before the first node is allocated, and `ThreadBoundEngine::configure` fails with
+`ConfigError::AllocatorInUse` after.
+
+Every backend shares the mark phase, `mark_live_nodes`, and the storage allocator for the arguments of nodes. A backend
+only decides where nodes live and when to collect.
+
+*/
+
+use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use alloc::{boxed::Box, sync::Arc};
+
+use crate::core::{
+  allocator::{
+    malloc_allocator::MallocNodeAllocator,
+    node_allocator::NodeAllocator,
+    storage_allocator::acquire_storage_allocator
+  },
+  config::AllocatorConfig,
+  dag_node_core::DagNodeCore
+};
+
+/// The built-in node allocator backends. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum AllocatorBackend {
+  /// The arena allocator, `NodeAllocator`
+  #[cfg_attr(not(feature = "malloc_nodes"), default)]
+  Arena,
+  /// A heap allocation per node, `MallocNodeAllocator`
+  #[cfg_attr(feature = "malloc_nodes", default)]
+  Malloc,
+}
+
+impl AllocatorBackend {
+  /// A new, empty backend of this kind
+  pub fn create(self) -> Box<dyn NodeAllocatorBackend> {
+    match self {
+      AllocatorBackend::Arena  => Box::new(NodeAllocator::new()),
+      AllocatorBackend::Malloc => Box::new(MallocNodeAllocator::new()),
+    }
+  }
+}
+
+/// Where nodes are allocated and when they are collected. See the module documentation.
+pub trait NodeAllocatorBackend: Send {
+  /// The built-in backend this backend is, or wraps
+  fn kind(&self) -> AllocatorBackend;
+
+  /// Whether the backend has allocated any node, after which it can't be replaced
+  fn has_allocated(&self) -> bool;
+
+  /// Sets whether collections are reported and how the backend sizes its capacity.
+  fn configure(&mut self, report_gc: bool, tuning: AllocatorConfig);
+
+  /// Collects garbage if the backend or the storage allocator wants to.
+  fn ok_to_collect_garbage(&mut self);
+
+  /// Whether the backend wants to collect garbage
+  fn want_to_collect_garbage(&self) -> bool;
+
+  /// Allocates storage for a node and counts it active. The storage may hold a dead node, so the caller initializes
+  /// every field.
+  fn allocate_dag_node(&mut self) -> *mut DagNodeCore;
+
+  /// The number of arenas the backend allocates nodes from, which is zero for backends without arenas
+  fn arena_count(&self) -> usize {
+    0
+  }
+
+  /// Prints the state of the backend, if it has state worth printing.
+  fn dump_memory_variables(&self) {}
+}
+
+/// What a `CountingNodeAllocator` has counted
+#[derive(Default, Debug)]
+pub struct AllocationCounts {
+  pub allocations: AtomicUsize,
+  pub collections: AtomicUsize,
+}
+
+/// A backend that counts the allocations and collections of the backend it wraps. The counts are shared, so they can
+/// be read while the backend is installed.
+pub struct CountingNodeAllocator {
+  inner : Box<dyn NodeAllocatorBackend>,
+  counts: Arc<AllocationCounts>,
+}
+
+impl CountingNodeAllocator {
+  pub fn new(inner: Box<dyn NodeAllocatorBackend>) -> Self {
+    CountingNodeAllocator { inner, counts: Arc::default() }
+  }
+
+  #[inline(always)]
+  pub fn counts(&self) -> Arc<AllocationCounts> {
+    self.counts.clone()
+  }
+}
+
+impl NodeAllocatorBackend for CountingNodeAllocator {
+  fn kind(&self) -> AllocatorBackend {
+    self.inner.kind()
+  }
+
+  fn has_allocated(&self) -> bool {
+    self.inner.has_allocated()
+  }
+
+  fn configure(&mut self, report_gc: bool, tuning: AllocatorConfig) {
+    self.inner.configure(report_gc, tuning)
+  }
+
+  fn ok_to_collect_garbage(&mut self) {
+    if self.inner.want_to_collect_garbage() || acquire_storage_allocator().want_to_collect_garbage() {
+      self.counts.collections.fetch_add(1, Relaxed);
+    }
+    self.inner.ok_to_collect_garbage()
+  }
+
+  fn want_to_collect_garbage(&self) -> bool {
+    self.inner.want_to_collect_garbage()
+  }
+
+  fn allocate_dag_node(&mut self) -> *mut DagNodeCore {
+    self.counts.allocations.fetch_add(1, Relaxed);
+    self.inner.allocate_dag_node()
+  }
+
+  fn arena_count(&self) -> usize {
+    self.inner.arena_count()
+  }
+
+  fn dump_memory_variables(&self) {
+    self.inner.dump_memory_variables()
+  }
+}
@@ -4,7 +4,8 @@ A `Bucket` is a small arena. We might use bumpalo or something instead.
 
 */
 
-use std::ptr::{null_mut, NonNull};
+use core::ptr::{null_mut, NonNull};
+use alloc::{boxed::Box, vec};
 
 pub type Void = u8;
 
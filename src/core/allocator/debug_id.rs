@@ -0,0 +1,51 @@
+/*!
+
+Debug ids identify live DAG nodes in debug output, traces, and graph exports, so that tools can tell nodes apart and
+cross-reference them. A node's id is allocated the first time it is asked for and stays the same for as long as the
+node is alive. Collections don't change it: the collector moves argument vectors but never nodes.
+
+Node storage is reused once a node is dead, so ids are kept in a side table keyed by address rather than in the node,
+which has no room for them anyway. When the allocator hands out a node, the id of the dead node that was stored there
+is forgotten, so a new node never inherits an old node's id. Until the first id is allocated, forgetting costs a single
+atomic load.
+
+*/
+
+use core::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use crate::{
+  abstractions::{HashMap, Lazy, Mutex},
+  core::dag_node_core::ThinDagNodePtr
+};
+
+#[derive(Default)]
+struct DebugIds {
+  ids    : HashMap<usize, u64>,
+  next_id: u64,
+}
+
+static DEBUG_IDS: Lazy<Mutex<DebugIds>> = Lazy::new(|| Mutex::new(DebugIds::default()));
+/// Whether any id has been allocated, so that allocation can skip the table until one has
+static HAS_DEBUG_IDS: AtomicBool = AtomicBool::new(false);
+
+/// The debug id of `node`, allocating one if the node doesn't have one yet. Ids start at 1.
+pub(crate) fn debug_id(node: ThinDagNodePtr) -> u64 {
+  let mut debug_ids = DEBUG_IDS.lock().expect("debug id table poisoned");
+  HAS_DEBUG_IDS.store(true, Relaxed);
+
+  let DebugIds { ids, next_id } = &mut *debug_ids;
+  *ids.entry(node as usize).or_insert_with(
+    || {
+      *next_id += 1;
+      *next_id
+    }
+  )
+}
+
+/// Forgets the id of the dead node whose storage is about to be reused for `node`.
+#[inline(always)]
+pub(crate) fn forget_debug_id(node: ThinDagNodePtr) {
+  if HAS_DEBUG_IDS.load(Relaxed) {
+    DEBUG_IDS.lock().expect("debug id table poisoned").ids.remove(&(node as usize));
+  }
+}
@@ -0,0 +1,274 @@
+/*!
+
+Reports of garbage collections. A collection runs while the node allocator's mutex is held, and the storage allocator's
+for part of it, so printing or logging from inside it would call into a log sink with both held. A sink that allocates
+nodes, or collects garbage, would then deadlock on the allocator.
+
+Collections instead record what they did as `GcEvent`s in a fixed-size ring buffer, which neither allocates nor takes a
+lock, and the events are logged after the collection, once the allocator mutexes have been released, by the public
+`ok_to_collect_garbage`, or by whoever calls `drain_gc_log`.
+
+## Reentrancy
+
+ - Recording an event never blocks, allocates, or calls out of the engine. If the buffer is full, the event is dropped
+   and counted, and the next drain warns of the dropped events.
+ - Events are logged with no allocator mutex held, so a log sink may allocate nodes and collect garbage. Events of a
+   collection the sink triggers are logged by that collection's drain, or by the drain already in progress.
+ - Draining can run on any thread and concurrently with recording. Each event is delivered once.
+
+The debug output of the `gc_debug` feature is still written from inside collections.
+
+Collections that `acquire_node_allocator` is used to run directly, as tests do, leave their events in the buffer until
+the next drain.
+
+*/
+
+use core::{
+  cell::UnsafeCell,
+  fmt::{Display, Formatter},
+  mem::MaybeUninit,
+  sync::atomic::{
+    AtomicUsize,
+    Ordering::{Acquire, Relaxed, Release}
+  }
+};
+
+use crate::log::{info, warning};
+
+/// The number of events the buffer holds
+const GC_LOG_CAPACITY: usize = 256;
+
+/// The global buffer of collection reports
+static GC_LOG: GcLog = GcLog::new();
+
+/// What a collection did
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GcEvent {
+  /// A collection with the given number started.
+  Collection(u64),
+  /// The arena allocator swept its arenas. Node counts are before and after the collection.
+  Arenas {
+    arenas  : u32,
+    capacity: usize,
+    before  : usize,
+    after   : usize,
+  },
+  /// The malloc backend swept its nodes. Node counts are before and after the collection.
+  HeapNodes {
+    before: usize,
+    after : usize,
+  },
+  /// The storage allocator swept its buckets. Byte counts are before and after the collection.
+  Storage {
+    buckets: u32,
+    bytes  : usize,
+    before : usize,
+    after  : usize,
+  },
+}
+
+impl Display for GcEvent {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    const MB: f64 = 1024.0 * 1024.0;
+    let node_mb   = |nodes: usize| (nodes * size_of::<crate::core::dag_node_core::DagNodeCore>()) as f64 / MB;
+
+    match *self {
+      GcEvent::Collection(number) => write!(f, "Collection: {}", number),
+
+      GcEvent::Arenas { arenas, capacity, before, after } => {
+        writeln!(
+          f,
+          "{:<10} {:<10} {:<10} {:<10} {:<13} {:<10} {:<10} {:<10} {:<10}",
+          "Arenas", "Nodes", "Size (MB)", "In use", "In use (MB)", "Collected", "Col. (MB)", "Now", "Now (MB)"
+        )?;
+        write!(
+          f,
+          "{:<10} {:<10} {:<10.2} {:<10} {:<13.2} {:<10} {:<10.2} {:<10} {:<10.2}",
+          arenas,
+          capacity,
+          node_mb(capacity),
+          before,
+          node_mb(before),
+          before - after,
+          node_mb(before - after),
+          after,
+          node_mb(after)
+        )
+      }
+
+      GcEvent::HeapNodes { before, after } => {
+        writeln!(f, "{:<10} {:<10} {:<10}", "Nodes", "Collected", "Now")?;
+        write!(f, "{:<10} {:<10} {:<10}", before, before - after, after)
+      }
+
+      GcEvent::Storage { buckets, bytes, before, after } => {
+        writeln!(
+          f,
+          "{:<10} {:<10} {:<10} {:<10} {:<13} {:<10} {:<10} {:<10} {:<10}",
+          "Buckets", "Bytes", "Size (MB)", "In use", "In use (MB)", "Collected", "Col. (MB)", "Now", "Now (MB)"
+        )?;
+        write!(
+          f,
+          "{:<10} {:<10} {:<10.2} {:<10} {:<13.2} {:<10} {:<10.2} {:<10} {:<10.2}",
+          buckets,
+          bytes,
+          bytes as f64 / MB,
+          before,
+          before as f64 / MB,
+          before - after,
+          (before - after) as f64 / MB,
+          after,
+          after as f64 / MB
+        )
+      }
+    }
+  }
+}
+
+/// Records `event` for the next drain. Never blocks or allocates.
+#[inline(always)]
+pub(crate) fn record_gc_event(event: GcEvent) {
+  GC_LOG.push(event);
+}
+
+/// Passes each recorded event to `sink`, oldest first, and returns the number of events dropped because the buffer was
+/// full since the last drain. Must not be called while holding an allocator.
+pub fn drain_gc_log(mut sink: impl FnMut(GcEvent)) -> usize {
+  while let Some(event) = GC_LOG.pop() {
+    sink(event);
+  }
+  GC_LOG.dropped.swap(0, Relaxed)
+}
+
+/// Logs the recorded events: the start of a collection is printed, and the statistics are logged at level 1. Without
+/// `std` there is nowhere to print, and the start of a collection is logged too.
+pub(crate) fn report_gc_events() {
+  let dropped = drain_gc_log(|event| match event {
+    #[cfg(feature = "std")]
+    GcEvent::Collection(_) => println!("{}", event),
+    _                      => info!(1, "{}", event),
+  });
+  if dropped > 0 {
+    warning!(1, "{} garbage collection reports were dropped because they weren't drained in time", dropped);
+  }
+}
+
+struct Slot {
+  /// The slot's sequence number minus its index, so that every slot starts at 0. See `GcLog`.
+  stamp: AtomicUsize,
+  event: UnsafeCell<MaybeUninit<GcEvent>>,
+}
+
+impl Slot {
+  const fn new() -> Self {
+    Slot { stamp: AtomicUsize::new(0), event: UnsafeCell::new(MaybeUninit::uninit()) }
+  }
+}
+
+/// A bounded lock-free queue of events after Dmitry Vyukov's. The slot at index `i` has a sequence number, which is
+/// `p` when position `p` may be written to it, `p + 1` once it has been, and `p + capacity` once it has been read.
+pub(crate) struct GcLog {
+  slots  : [Slot; GC_LOG_CAPACITY],
+  /// The position of the next event to read
+  head   : AtomicUsize,
+  /// The position of the next event to write
+  tail   : AtomicUsize,
+  dropped: AtomicUsize,
+}
+
+// SAFETY: Only the `UnsafeCell`s of the slots keep `GcLog` from being `Sync`. A thread writes a slot's event only after
+// its stamp says the slot is free and the thread has claimed the position with the compare-exchange of `tail`, and
+// publishes it with a release store of the stamp. A thread reads the event only after an acquire load of the stamp
+// says it is written and the thread has claimed the position with the compare-exchange of `head`, and frees the slot
+// with another release store. So a slot has at most one writer or one reader at a time, the reader sees the whole
+// event, and `GcEvent` is plain data, so it may move to the reading thread.
+unsafe impl Sync for GcLog {}
+
+impl GcLog {
+  pub(crate) const fn new() -> Self {
+    GcLog {
+      slots  : [const { Slot::new() }; GC_LOG_CAPACITY],
+      head   : AtomicUsize::new(0),
+      tail   : AtomicUsize::new(0),
+      dropped: AtomicUsize::new(0),
+    }
+  }
+
+  /// Appends `event`, or drops it and returns `false` if the queue is full.
+  pub(crate) fn push(&self, event: GcEvent) -> bool {
+    let mut position = self.tail.load(Relaxed);
+    loop {
+      let index    = position % GC_LOG_CAPACITY;
+      let slot     = &self.slots[index];
+      let sequence = slot.stamp.load(Acquire).wrapping_add(index);
+
+      match (sequence as isize).wrapping_sub(position as isize) {
+        0 => match self.tail.compare_exchange_weak(position, position + 1, Relaxed, Relaxed) {
+          Ok(_) => {
+            unsafe{ (*slot.event.get()).write(event); }
+            slot.stamp.store((position + 1).wrapping_sub(index), Release);
+            return true;
+          }
+          Err(current) => position = current,
+        },
+        difference if difference < 0 => {
+          self.dropped.fetch_add(1, Relaxed);
+          return false;
+        }
+        _ => position = self.tail.load(Relaxed),
+      }
+    }
+  }
+
+  /// Removes the oldest event, if there is one.
+  pub(crate) fn pop(&self) -> Option<GcEvent> {
+    let mut position = self.head.load(Relaxed);
+    loop {
+      let index    = position % GC_LOG_CAPACITY;
+      let slot     = &self.slots[index];
+      let sequence = slot.stamp.load(Acquire).wrapping_add(index);
+
+      match (sequence as isize).wrapping_sub(position.wrapping_add(1) as isize) {
+        0 => match self.head.compare_exchange_weak(position, position + 1, Relaxed, Relaxed) {
+          Ok(_) => {
+            let event = unsafe{ (*slot.event.get()).assume_init() };
+            slot.stamp.store((position + GC_LOG_CAPACITY).wrapping_sub(index), Release);
+            return Some(event);
+          }
+          Err(current) => position = current,
+        },
+        difference if difference < 0 => return None,
+        _ => position = self.head.load(Relaxed),
+      }
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::Ordering::Relaxed;
+
+  use crate::core::allocator::gc_log::{GcEvent, GcLog, GC_LOG_CAPACITY};
+
+  #[test]
+  fn events_are_drained_in_order_and_overflow_is_counted() {
+    let log = GcLog::new();
+    assert_eq!(log.pop(), None);
+
+    for round in 0..3 {
+      for number in 0..GC_LOG_CAPACITY as u64 {
+        assert!(log.push(GcEvent::Collection(number)));
+      }
+      assert!(!log.push(GcEvent::HeapNodes { before: 2, after: 1 }));
+      for number in 0..GC_LOG_CAPACITY as u64 {
+        assert_eq!(log.pop(), Some(GcEvent::Collection(number)), "round {}", round);
+      }
+      assert_eq!(log.pop(), None);
+    }
+    assert_eq!(log.dropped.load(Relaxed), 3);
+
+    let report = GcEvent::HeapNodes { before: 10, after: 4 }.to_string();
+    assert_eq!(report.lines().nth(1).unwrap().split_whitespace().collect::<Vec<_>>(), ["10", "6", "4"]);
+  }
+}
@@ -4,7 +4,7 @@ A vector allocated from Bucket storage.
 
 */
 
-use std::{
+use core::{
   ops::{Index, IndexMut},
   marker::PhantomPinned,
   cmp::min
@@ -38,7 +38,7 @@ impl<T: Copy + 'static> GCVector<T> {
     unsafe {
       let node_vector_ptr: *mut GCVector<T> =
           { acquire_storage_allocator().allocate_storage(size_of::<GCVector<T>>()) as *mut GCVector<T> };
-      let node_vector: &mut GCVector<T>     = node_vector_ptr.as_mut_unchecked();
+      let node_vector: &mut GCVector<T>     = &mut *node_vector_ptr;
 
       // Initialize the NodeVector
       node_vector.length   = 0;
@@ -47,7 +47,7 @@ impl<T: Copy + 'static> GCVector<T> {
       // Allocate the memory slice. Two separate allocations are needed to maintain alignment.
       let needed_memory    = capacity * size_of::<T>();
       let data_ptr         = { acquire_storage_allocator().allocate_storage(needed_memory) as *mut T };
-      node_vector.data     = std::slice::from_raw_parts_mut(data_ptr, capacity);
+      node_vector.data     = core::slice::from_raw_parts_mut(data_ptr, capacity);
 
       node_vector
     }
@@ -104,12 +104,12 @@ impl<T: Copy + 'static> GCVector<T> {
   // endregion Constructors
 
   // Immutable iterator
-  pub fn iter(&'static self) -> std::slice::Iter<'static, T> {
+  pub fn iter(&'static self) -> core::slice::Iter<'static, T> {
     self.data[..self.length].iter()
   }
 
   // Mutable iterator
-  pub fn iter_mut(&'static mut self) -> std::slice::IterMut<'static, T> {
+  pub fn iter_mut(&'static mut self) -> core::slice::IterMut<'static, T> {
     self.data[..self.length].iter_mut()
   }
 
@@ -138,7 +138,7 @@ impl<T: Copy + 'static> GCVector<T> {
       panic!("node_vec.len: {}, capacity: {}, data.len: {}", self.length, self.capacity, self.data.len());
       // ToDo: Should the vector grow geometrically?
       // let new_vec = self.copy_with_capacity(self.capacity + 1);
-      // std::mem::swap(self, new_vec);
+      // core::mem::swap(self, new_vec);
     }
 
     self.data[self.length] = node;
@@ -174,7 +174,7 @@ impl<T> IndexMut<usize> for GCVector<T> {
 
 impl<'a, T> IntoIterator for &'a GCVector<T> {
   type Item = &'a T;
-  type IntoIter = std::slice::Iter<'a, T>;
+  type IntoIter = core::slice::Iter<'a, T>;
 
   fn into_iter(self) -> Self::IntoIter {
     self.data.iter()
@@ -183,7 +183,7 @@ impl<'a, T> IntoIterator for &'a GCVector<T> {
 
 impl<'a, T> IntoIterator for &'a mut GCVector<T> {
   type Item = &'a mut T;
-  type IntoIter = std::slice::IterMut<'a, T>;
+  type IntoIter = core::slice::IterMut<'a, T>;
 
   fn into_iter(self) -> Self::IntoIter {
     self.data.iter_mut()
@@ -0,0 +1,53 @@
+/*!
+
+Generation stamps for catching stale node pointers. Node storage is reused once a node is dead, so a `DagNodePtr` kept
+past the death of its node silently points at whatever node is allocated there next. In builds with the `gc_debug`
+feature, the allocator stamps each node address with a generation that it advances whenever it hands the address out
+again, and a `CheckedDagNodePtr` remembers the generation its node had when the handle was made. A handle whose
+generation no longer matches points at a different node.
+
+Like debug ids, stamps are kept in a side table keyed by address, and only for addresses a checked handle has been made
+for. Until the first handle is made, advancing a generation costs a single atomic load, and without the feature it
+costs nothing and every generation is 0.
+
+*/
+
+#[cfg(feature = "gc_debug")]
+use core::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+#[cfg(feature = "gc_debug")]
+use crate::abstractions::{HashMap, Lazy, Mutex};
+use crate::core::dag_node_core::ThinDagNodePtr;
+
+/// The generations of the addresses checked handles have been made for
+#[cfg(feature = "gc_debug")]
+static GENERATIONS: Lazy<Mutex<HashMap<usize, u64>>> = Lazy::new(|| Mutex::new(HashMap::default()));
+/// Whether any address is stamped, so that allocation can skip the table until one is
+#[cfg(feature = "gc_debug")]
+static HAS_GENERATIONS: AtomicBool = AtomicBool::new(false);
+
+/// The generation of the node at `node`, stamping the address if it isn't already.
+#[cfg(feature = "gc_debug")]
+pub(crate) fn generation(node: ThinDagNodePtr) -> u64 {
+  let mut generations = GENERATIONS.lock().expect("generation table poisoned");
+  HAS_GENERATIONS.store(true, Relaxed);
+
+  *generations.entry(node as usize).or_insert(0)
+}
+
+#[cfg(not(feature = "gc_debug"))]
+#[inline(always)]
+pub(crate) fn generation(_node: ThinDagNodePtr) -> u64 {
+  0
+}
+
+/// Advances the generation of the address `node`, whose storage is about to be reused for a new node.
+#[inline(always)]
+pub(crate) fn advance_generation(_node: ThinDagNodePtr) {
+  #[cfg(feature = "gc_debug")]
+  if HAS_GENERATIONS.load(Relaxed) {
+    if let Some(generation) = GENERATIONS.lock().expect("generation table poisoned").get_mut(&(_node as usize)) {
+      *generation += 1;
+    }
+  }
+}
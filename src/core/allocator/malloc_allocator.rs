@@ -0,0 +1,163 @@
+/*!
+
+A node allocator backend that allocates each node with the system allocator and frees it as soon as a collection finds
+it dead. It is much slower than the arena allocator, but the storage of a dead node is returned to the system rather
+than reused in place, so memory checkers like AddressSanitizer and Valgrind catch the use of a stale node pointer.
+Configuring this backend also puts the storage allocator in heap storage mode, in which argument vectors are freed the
+same way (see `storage_allocator`).
+
+To run the test suite under AddressSanitizer in this mode:
+
+```text
+RUSTFLAGS=-Zsanitizer=address cargo +nightly test --features malloc_nodes --target x86_64-unknown-linux-gnu
+```
+
+The backend keeps a list of the nodes it has allocated. A collection marks the live nodes like any other and then
+sweeps the list, freeing the unmarked nodes after running their destructors. The backend wants to collect once it holds
+as many nodes as its capacity, which after a collection is the slop factor of the `AllocatorConfig` times the number of
+live nodes, and never less than an arena's worth.
+
+*/
+
+use alloc::vec::Vec;
+
+use alloc::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+
+use crate::{
+  core::{
+    allocator::{
+      backend::{AllocatorBackend, NodeAllocatorBackend},
+      gc_log::{record_gc_event, GcEvent},
+      node_allocator::{
+        ceil,
+        increment_active_node_count,
+        mark_live_nodes,
+        next_collection_number,
+        slop_factor,
+        ARENA_SIZE
+      },
+      storage_allocator::acquire_storage_allocator
+    },
+    config::AllocatorConfig,
+    dag_node_core::{DagNodeCore, DagNodeFlag}
+  }
+};
+
+/// See the module documentation.
+pub struct MallocNodeAllocator {
+  show_gc : bool,
+  tuning  : AllocatorConfig,
+  /// Every node allocated and not yet freed
+  nodes   : Vec<*mut DagNodeCore>,
+  /// The backend wants to collect once it holds this many nodes.
+  capacity: usize,
+}
+
+// Access is hidden behind a mutex.
+unsafe impl Send for MallocNodeAllocator {}
+
+impl Default for MallocNodeAllocator {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MallocNodeAllocator {
+  pub fn new() -> Self {
+    MallocNodeAllocator {
+      show_gc : true,
+      tuning  : AllocatorConfig::default(),
+      nodes   : Vec::new(),
+      capacity: ARENA_SIZE,
+    }
+  }
+
+  /// The number of nodes allocated and not yet freed, live or not
+  #[inline(always)]
+  pub fn node_count(&self) -> usize {
+    self.nodes.len()
+  }
+
+  unsafe fn collect_garbage(&mut self) {
+    let gc_count = next_collection_number();
+    if self.show_gc {
+      record_gc_event(GcEvent::Collection(gc_count));
+    }
+
+    mark_live_nodes();
+
+    let old_node_count = self.nodes.len();
+    self.nodes.retain(|&node| {
+      let node_mut = &mut *node;
+      if node_mut.is_marked() {
+        node_mut.remove_flags(DagNodeFlag::Marked.into());
+        return true;
+      }
+
+      if node_mut.needs_destruction() {
+        DagNodeCore::drop_in_place(node);
+      }
+      dealloc(node as *mut u8, Layout::new::<DagNodeCore>());
+      false
+    });
+    let active_node_count = self.nodes.len();
+
+    if self.show_gc {
+      record_gc_event(GcEvent::HeapNodes { before: old_node_count, after: active_node_count });
+    }
+
+    let ideal_capacity = ceil(active_node_count as f64 * slop_factor(&self.tuning, active_node_count)) as usize;
+    self.capacity      = ideal_capacity.max(ARENA_SIZE);
+  }
+}
+
+impl NodeAllocatorBackend for MallocNodeAllocator {
+  fn kind(&self) -> AllocatorBackend {
+    AllocatorBackend::Malloc
+  }
+
+  fn has_allocated(&self) -> bool {
+    !self.nodes.is_empty()
+  }
+
+  fn configure(&mut self, report_gc: bool, tuning: AllocatorConfig) {
+    self.show_gc = report_gc;
+    self.tuning  = tuning;
+  }
+
+  fn ok_to_collect_garbage(&mut self) {
+    if self.want_to_collect_garbage() || acquire_storage_allocator().want_to_collect_garbage() {
+      unsafe{ self.collect_garbage(); }
+    }
+  }
+
+  fn want_to_collect_garbage(&self) -> bool {
+    self.nodes.len() >= self.capacity
+  }
+
+  fn allocate_dag_node(&mut self) -> *mut DagNodeCore {
+    // Zeroed, so that the flags of a node that is swept before it is initialized are empty.
+    let layout = Layout::new::<DagNodeCore>();
+    let node   = unsafe{ alloc_zeroed(layout) } as *mut DagNodeCore;
+    if node.is_null() {
+      handle_alloc_error(layout);
+    }
+
+    self.nodes.push(node);
+    increment_active_node_count();
+    node
+  }
+}
+
+impl Drop for MallocNodeAllocator {
+  fn drop(&mut self) {
+    for &node in &self.nodes {
+      unsafe {
+        if (*node).needs_destruction() {
+          DagNodeCore::drop_in_place(node);
+        }
+        dealloc(node as *mut u8, Layout::new::<DagNodeCore>());
+      }
+    }
+  }
+}
@@ -4,12 +4,18 @@ The allocator for garbage collected memory. This is really two different allocat
  1. An arena allocator exclusively for allocating `DagNode` objects. All garbage collected nodes must be allocated with this allocator.
  2. A "bucket" allocator exclusively for allocating any memory owned by `DagNode` objects. Nodes may have several arguments, which are other nodes. The arguments are stored as arrays of pointers to the argument nodes, and nodes must allocate these arrays of pointers using the bucket allocator and hold on to a pointer to the array.
 
+Collections report what they did through `gc_log`, which is drained once the allocators are released.
 
 */
 #![allow(unused_imports)]
 mod arena;
+mod backend;
 mod bucket;
+mod debug_id;
+mod gc_log;
 pub(crate) mod gc_vector;
+mod generation;
+mod malloc_allocator;
 mod node_allocator;
 mod storage_allocator;
 
@@ -21,12 +27,33 @@ use node_allocator::acquire_node_allocator;
 use storage_allocator::acquire_storage_allocator;
 // Needed within all node `mark()` methods
 pub(crate) use node_allocator::increment_active_node_count;
+pub(crate) use debug_id::debug_id;
+pub(crate) use generation::{advance_generation, generation};
+pub(crate) use node_allocator::{
+  configure_allocators,
+  ARENA_SIZE,
+  BIG_MODEL_SLOP,
+  LOWER_BOUND,
+  RESERVE_SIZE,
+  SMALL_MODEL_SLOP,
+  UPPER_BOUND
+};
 
 // These are the only public API
+pub use backend::{
+  AllocationCounts,
+  AllocatorBackend,
+  CountingNodeAllocator,
+  NodeAllocatorBackend
+};
+pub use gc_log::{drain_gc_log, GcEvent};
+pub use malloc_allocator::MallocNodeAllocator;
 pub use node_allocator::{
   ok_to_collect_garbage,
   want_to_collect_garbage,
-  allocate_dag_node
+  allocate_dag_node,
+  install_node_allocator,
+  NodeAllocator
 };
 
 
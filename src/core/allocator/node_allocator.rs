@@ -9,23 +9,20 @@ When garbage collection is triggered, the allocator then sweeps the remaining (n
 
 Since the sweep phase is done lazily, the time it takes to sweep the arenas is amortized between garbage collection events. Because garbage collection is triggered when the linear search for free nodes nears the end of the last arena, allocating a "slop factor" of extra arenas keeps garbage collection events low.
 
+The arena allocator is the default `NodeAllocatorBackend`. The global node allocator is whichever backend is installed;
+see `backend`.
+
 */
 
-use std::{
-  sync::{
-    atomic::{
-      Ordering::Relaxed,
-      AtomicUsize
-    },
-    Mutex,
-    MutexGuard,
-  },
-  ptr::drop_in_place,
+use core::sync::atomic::{
+  Ordering::Relaxed,
+  AtomicU64,
+  AtomicUsize
 };
-
-use once_cell::sync::Lazy;
+use alloc::boxed::Box;
 
 use crate::{
+  abstractions::{Instant, Lazy, Mutex, MutexGuard},
   core::dag_node_core::{
     ThinDagNodePtr,
     DagNodeCore,
@@ -35,9 +32,16 @@ use crate::{
   core::{
     allocator::{
       arena::Arena,
+      backend::{AllocatorBackend, NodeAllocatorBackend},
+      debug_id::forget_debug_id,
+      gc_log::{record_gc_event, report_gc_events, GcEvent},
+      generation::advance_generation,
       storage_allocator::acquire_storage_allocator
     },
+    config::{AllocatorConfig, ConfigError},
+    observer::{is_observed, notify, GcSummary},
     root_container::mark_roots,
+    thread_bound::check_owner,
   },
   log::{
     debug,
@@ -45,31 +49,74 @@ use crate::{
   }
 };
 
-// Constant Allocator Parameters
-const SMALL_MODEL_SLOP: f64   = 8.0;
-const BIG_MODEL_SLOP  : f64   = 2.0;
-const LOWER_BOUND     : usize =  4 * 1024 * 1024; // Use small model if <= 4 million nodes
-const UPPER_BOUND     : usize = 32 * 1024 * 1024; // Use big model if >= 32 million nodes
+// Default Allocator Parameters, which can be changed with an `EngineConfig`
+pub(crate) const SMALL_MODEL_SLOP: f64   = 8.0;
+pub(crate) const BIG_MODEL_SLOP  : f64   = 2.0;
+pub(crate) const LOWER_BOUND     : usize =  4 * 1024 * 1024; // Use small model if <= 4 million nodes
+pub(crate) const UPPER_BOUND     : usize = 32 * 1024 * 1024; // Use big model if >= 32 million nodes
 // It looks like Maude assumes DagNodes are 6 words in size, but ours are 3 words,
 // at least so far.
 pub(crate) const ARENA_SIZE: usize = 5460; // Arena size in nodes; 5460 * 6 + 1 + new/malloc_overhead <= 32768 words
-const RESERVE_SIZE         : usize = 256; // If fewer nodes left call GC when allowed
+pub(crate) const RESERVE_SIZE: usize = 256; // If fewer nodes left call GC when allowed
 
 
 pub(crate) static ACTIVE_NODE_COUNT: AtomicUsize = AtomicUsize::new(0);
-static GLOBAL_NODE_ALLOCATOR: Lazy<Mutex<NodeAllocator>> = Lazy::new(|| {
-  Mutex::new(NodeAllocator::new())
+/// The number of collections so far, whichever backend performed them
+static COLLECTION_COUNT: AtomicU64 = AtomicU64::new(0);
+static GLOBAL_NODE_ALLOCATOR: Lazy<Mutex<Box<dyn NodeAllocatorBackend>>> = Lazy::new(|| {
+  Mutex::new(AllocatorBackend::default().create())
 });
 
 /// Acquire the global node allocator. The `caller_msg` is for debugging purposes.
 #[inline(always)]
-pub fn acquire_node_allocator(caller_msg: &str) -> MutexGuard<'static, NodeAllocator> {
+pub fn acquire_node_allocator(caller_msg: &str) -> MutexGuard<'static, Box<dyn NodeAllocatorBackend>> {
   GLOBAL_NODE_ALLOCATOR.lock().expect(caller_msg)
 }
 
+/// Makes `backend` the global node allocator. Nodes can't move between backends, so this only succeeds while the
+/// installed backend has not allocated any node; otherwise `backend` is handed back.
+pub fn install_node_allocator(
+  backend: Box<dyn NodeAllocatorBackend>
+) -> Result<(), Box<dyn NodeAllocatorBackend>> {
+  let mut installed = acquire_node_allocator("install_node_allocator");
+  if installed.has_allocated() {
+    return Err(backend);
+  }
+
+  *installed = backend;
+  Ok(())
+}
+
+/// Collects garbage if the allocator wants to, and then logs the reports of collections (see `gc_log`) and tells the
+/// observers of the collection (see `observer`).
 #[inline(always)]
 pub fn ok_to_collect_garbage() {
-  acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
+  check_owner();
+  if !is_observed() {
+    acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
+    report_gc_events();
+    return;
+  }
+
+  let (collection, nodes_before, start) = (collection_count(), active_node_count(), Instant::now());
+  let arenas = { // Scope of `allocator`
+    let mut allocator = acquire_node_allocator("ok_to_collect_garbage");
+    allocator.ok_to_collect_garbage();
+    allocator.arena_count()
+  };
+  let pause = start.elapsed();
+  report_gc_events();
+
+  if collection_count() != collection {
+    let summary = GcSummary {
+      collection : collection_count(),
+      nodes_before,
+      nodes_after: active_node_count(),
+      arenas,
+      pause
+    };
+    notify(|observer| observer.garbage_collected(&summary));
+  }
 }
 
 #[inline(always)]
@@ -77,15 +124,40 @@ pub fn want_to_collect_garbage() -> bool {
   acquire_node_allocator("want_to_collect_garbage").want_to_collect_garbage()
 }
 
+/// Sets whether collections are reported, which backend allocates nodes, and how it sizes its capacity. See
+/// `EngineConfig`. The backend can only be changed before the first node is allocated, and nothing is changed if it
+/// can't be.
+pub(crate) fn configure_allocators(report_gc: bool, tuning: AllocatorConfig) -> Result<(), ConfigError> {
+  { // Scope of `installed`
+    let mut installed = acquire_node_allocator("configure_allocators");
+    if installed.kind() != tuning.backend {
+      if installed.has_allocated() {
+        return Err(ConfigError::AllocatorInUse);
+      }
+      *installed = tuning.backend.create();
+    }
+    installed.configure(report_gc, tuning);
+  }
+  let mut storage_allocator = acquire_storage_allocator();
+  storage_allocator.show_gc_statistics(report_gc);
+  storage_allocator.set_heap_storage(tuning.backend == AllocatorBackend::Malloc);
+
+  Ok(())
+}
+
 #[inline(always)]
 pub fn allocate_dag_node() -> ThinDagNodePtr {
-  acquire_node_allocator("want_to_collect_garbage").allocate_dag_node()
+  let node = acquire_node_allocator("want_to_collect_garbage").allocate_dag_node();
+  forget_debug_id(node);
+  advance_generation(node);
+  node
 }
 
 
-pub(crate) struct NodeAllocator {
+pub struct NodeAllocator {
   // General settings
   show_gc   : bool, // Do we report GC stats to user
+  tuning    : AllocatorConfig,
 
   need_to_collect_garbage        : bool,
 
@@ -105,30 +177,25 @@ pub(crate) struct NodeAllocator {
 unsafe impl Send for NodeAllocator {}
 // unsafe impl Sync for Allocator {}
 
-impl NodeAllocator {
-  pub fn new() -> Self {
-    NodeAllocator {
-      show_gc    : true,
-      arena_count: 0,
+impl NodeAllocatorBackend for NodeAllocator {
+  fn kind(&self) -> AllocatorBackend {
+    AllocatorBackend::Arena
+  }
 
-      current_arena_past_active_arena: true,
-      need_to_collect_garbage        : false,
+  fn has_allocated(&self) -> bool {
+    !self.first_arena.is_null()
+  }
 
-      first_arena      : std::ptr::null_mut(),
-      last_arena       : std::ptr::null_mut(),
-      current_arena    : std::ptr::null_mut(),
-      next_node        : std::ptr::null_mut(),
-      end_pointer      : std::ptr::null_mut(),
-      last_active_arena: std::ptr::null_mut(),
-      last_active_node : std::ptr::null_mut(),
-    }
+  fn configure(&mut self, report_gc: bool, tuning: AllocatorConfig) {
+    self.show_gc = report_gc;
+    self.tuning  = tuning;
   }
 
   /// Tell the garbage collect to collect garbage if it needs to.
   /// You can query whether it needs to by calling `want_to_collect_garbage`,
   /// but this isn't necessary.
   #[inline(always)]
-  pub fn ok_to_collect_garbage(&mut self) {
+  fn ok_to_collect_garbage(&mut self) {
     if self.need_to_collect_garbage
         || acquire_storage_allocator().want_to_collect_garbage()
     {
@@ -138,12 +205,12 @@ impl NodeAllocator {
 
   /// Query whether the allocator has any garbage to collect.
   #[inline(always)]
-  pub fn want_to_collect_garbage(&self) -> bool {
+  fn want_to_collect_garbage(&self) -> bool {
     self.need_to_collect_garbage
   }
 
   /// Allocates a new `DagNode`
-  pub fn allocate_dag_node(&mut self) -> *mut DagNodeCore {
+  fn allocate_dag_node(&mut self) -> *mut DagNodeCore {
     // ToDo: I think we can replace these pointers with indices into the current arena's data array.
     //       Includes next_node, end_pointer, end_node.
     let mut current_node = self.next_node;
@@ -157,17 +224,18 @@ impl NodeAllocator {
         }
 
         { // Scope of `current_node_mut: &mut DagNode`
-          let current_node_mut = current_node.as_mut_unchecked();
+          let current_node_mut = &mut *current_node;
           if current_node_mut.simple_reuse() {
             break;
           }
           if !current_node_mut.is_marked() {
             // Not marked, but needs destruction because it's not simple reuse.
-            drop_in_place(current_node_mut);
+            DagNodeCore::drop_in_place(current_node);
             break;
           }
-          // current_node_mut.flags.remove(DagNodeFlag::Marked);
-          current_node_mut.flags = DagNodeFlags::default();
+          // Only the mark is cleared. The node is live, so its other flags, in particular `NeedsDestruction`, must
+          // survive.
+          current_node_mut.remove_flags(DagNodeFlag::Marked.into());
         }
 
         current_node = current_node.add(1);
@@ -180,6 +248,36 @@ impl NodeAllocator {
     current_node
   }
 
+  fn arena_count(&self) -> usize {
+    self.arena_count as usize
+  }
+
+  #[cfg(all(feature = "gc_debug", feature = "std"))]
+  fn dump_memory_variables(&self) {
+    NodeAllocator::dump_memory_variables(self)
+  }
+}
+
+impl NodeAllocator {
+  pub fn new() -> Self {
+    NodeAllocator {
+      show_gc    : true,
+      tuning     : AllocatorConfig::default(),
+      arena_count: 0,
+
+      current_arena_past_active_arena: true,
+      need_to_collect_garbage        : false,
+
+      first_arena      : core::ptr::null_mut(),
+      last_arena       : core::ptr::null_mut(),
+      current_arena    : core::ptr::null_mut(),
+      next_node        : core::ptr::null_mut(),
+      end_pointer      : core::ptr::null_mut(),
+      last_active_arena: core::ptr::null_mut(),
+      last_active_node : core::ptr::null_mut(),
+    }
+  }
+
 
   /// Allocates a new arena, adding it to the linked list of arenas, and
   /// returns (a pointer to) the new arena.
@@ -219,10 +317,10 @@ impl NodeAllocator {
       if self.current_arena.is_null() {
         // Allocate the first arena
         self.current_arena = self.allocate_new_arena();
-        let arena          = self.current_arena.as_mut_unchecked();
+        let arena          = &mut *self.current_arena;
         let first_node     = arena.first_node();
         // The last arena in the linked list is given a reserve.
-        self.end_pointer   = first_node.add(ARENA_SIZE - RESERVE_SIZE);
+        self.end_pointer   = first_node.add(ARENA_SIZE - self.tuning.reserve_size);
 
         // These two members are initialized on first call to `NodeAllocator::sweep_arenas()`.
         // self.last_active_arena = arena;
@@ -232,7 +330,7 @@ impl NodeAllocator {
       }
 
       // Checked for null above.
-      let current_arena = self.current_arena.as_mut_unchecked();
+      let current_arena = &mut *self.current_arena;
       let arena         = current_arena.next_arena;
 
       if arena.is_null() {
@@ -250,7 +348,7 @@ impl NodeAllocator {
           }
 
           self.current_arena = self.allocate_new_arena();
-          let arena          = self.current_arena.as_mut_unchecked();
+          let arena          = &mut *self.current_arena;
           let first_node     = arena.first_node();
           self.end_pointer   = first_node.add(ARENA_SIZE); // ToDo: Why no reserve here?
 
@@ -264,13 +362,13 @@ impl NodeAllocator {
         }
 
         self.current_arena = arena;
-        let current_arena  = arena.as_mut_unchecked();
+        let current_arena  = &mut *arena;
         self.next_node     = current_arena.first_node();
 
         match current_arena.next_arena.is_null() {
           true => {
             // The last arena in the linked list is given a reserve.
-            self.end_pointer = self.next_node.add(ARENA_SIZE - RESERVE_SIZE);
+            self.end_pointer = self.next_node.add(ARENA_SIZE - self.tuning.reserve_size);
           }
           false => {
             self.end_pointer = self.next_node.add(ARENA_SIZE);
@@ -288,17 +386,17 @@ impl NodeAllocator {
       let mut cursor = self.next_node;
       // Loop over all nodes from self.next_node to self.end_pointer
       while cursor != end_node {
-        let cursor_mut = cursor.as_mut_unchecked();
+        let cursor_mut = &mut *cursor;
 
         if cursor_mut.simple_reuse(){
           return cursor;
         }
         if !cursor_mut.is_marked() {
-          drop_in_place(cursor_mut);
+          DagNodeCore::drop_in_place(cursor);
           return cursor;
         }
 
-        cursor_mut.flags.remove(DagNodeFlag::Marked);
+        cursor_mut.remove_flags(DagNodeFlag::Marked.into());
 
         cursor = cursor.add(1);
       } // end loop over all nodes
@@ -306,17 +404,14 @@ impl NodeAllocator {
   }
 
   unsafe fn collect_garbage(&mut self) {
-    static mut GC_COUNT: u64 = 0;
-
     if self.first_arena.is_null() {
       return;
     }
 
-    GC_COUNT += 1;
-    let gc_count = GC_COUNT; // To silence shared_mut_ref warning
+    let gc_count = next_collection_number();
     if self.show_gc {
       // We moved this up here so that it appears before the bucket storage statistics.
-      println!("Collection: {}", gc_count);
+      record_gc_event(GcEvent::Collection(gc_count));
     }
 
     self.sweep_arenas();
@@ -326,13 +421,7 @@ impl NodeAllocator {
     // Mark phase
 
     let old_active_node_count = active_node_count();
-    ACTIVE_NODE_COUNT.store(0, Relaxed); // to be updated during mark phase.
-
-    acquire_storage_allocator()._prepare_to_mark();
-
-    mark_roots();
-
-    acquire_storage_allocator()._sweep_garbage();
+    mark_live_nodes();
 
     // Garbage Collection for Arenas
     let active_node_count = active_node_count(); // updated during mark phase
@@ -340,57 +429,20 @@ impl NodeAllocator {
     let node_capacity = (self.arena_count as usize) * ARENA_SIZE;
 
     if self.show_gc {
-      // println!(
-      //   "Arenas: {}\tNodes: {} ({:.2} MB)\tCollected: {} ({:.2}) MB\tNow: {} ({:.2} MB)",
-      //   self.arena_count,
-      //   node_capacity,
-      //   ((node_capacity * size_of::<DagNode>()) as f64) / (1024.0 * 1024.0),
-      //   old_active_node_count - active_node_count,
-      //   (((old_active_node_count - active_node_count) * size_of::<DagNode>() ) as f64) / (1024.0 * 1024.0),
-      //   active_node_count,
-      //   ((active_node_count * size_of::<DagNode>()) as f64) / (1024.0 * 1024.0),
-      // );
-      info!(1,
-        "{:<10} {:<10} {:<10} {:<10} {:<13} {:<10} {:<10} {:<10} {:<10}",
-        "Arenas",
-        "Nodes",
-        "Size (MB)",
-        "In use",
-        "In use (MB)",
-        "Collected",
-        "Col. (MB)",
-        "Now",
-        "Now (MB)"
-      );
-      info!(1,
-        "{:<10} {:<10} {:<10.2} {:<10} {:<13.2} {:<10} {:<10.2} {:<10} {:<10.2}",
-        self.arena_count,
-        node_capacity,
-        ((node_capacity * size_of::<DagNodeCore>()) as f64) / (1024.0 * 1024.0),
-        old_active_node_count,
-        (((old_active_node_count) * size_of::<DagNodeCore>()) as f64) / (1024.0 * 1024.0),
-        old_active_node_count - active_node_count,
-        (((old_active_node_count - active_node_count) * size_of::<DagNodeCore>()) as f64) / (1024.0 * 1024.0),
-        active_node_count,
-        ((active_node_count * size_of::<DagNodeCore>()) as f64) / (1024.0 * 1024.0),
-      );
+      record_gc_event(GcEvent::Arenas {
+        arenas  : self.arena_count,
+        capacity: node_capacity,
+        before  : old_active_node_count,
+        after   : active_node_count,
+      });
     }
 
     // Calculate if we should allocate more arenas to avoid an early gc.
     // Compute slop factor
-    // Case: ACTIVE_NODE_COUNT >= UPPER_BOUND
-    let mut slop_factor: f64 = BIG_MODEL_SLOP;
-    if ACTIVE_NODE_COUNT.load(Relaxed) < LOWER_BOUND {
-      // Case: ACTIVE_NODE_COUNT < LOWER_BOUND
-      slop_factor = SMALL_MODEL_SLOP;
-    } else if ACTIVE_NODE_COUNT.load(Relaxed) < UPPER_BOUND {
-      // Case: LOWER_BOUND <= ACTIVE_NODE_COUNT < UPPER_BOUND
-      // Linearly interpolate between the two models.
-      slop_factor += ((UPPER_BOUND - active_node_count as usize) as f64 * (SMALL_MODEL_SLOP - BIG_MODEL_SLOP)) / (UPPER_BOUND - LOWER_BOUND) as f64;
-    }
+    let slop_factor = slop_factor(&self.tuning, active_node_count);
 
     // Allocate new arenas so that we have capacity for at least slop_factor times the actually used nodes.
-    let ideal_arena_count = (active_node_count as f64 * slop_factor / (ARENA_SIZE as f64)).ceil() as u32;
+    let ideal_arena_count = ceil(active_node_count as f64 * slop_factor / (ARENA_SIZE as f64)) as u32;
 
     #[cfg(feature = "gc_debug")]
     debug!(2, "ideal_arena_count: {}", ideal_arena_count);
@@ -402,12 +454,12 @@ impl NodeAllocator {
     self.current_arena_past_active_arena = false;
     self.current_arena = self.first_arena;
     { // Scope of current_arena
-      let current_arena = self.current_arena.as_mut_unchecked();
+      let current_arena = &mut *self.current_arena;
       self.next_node = current_arena.first_node();
       match current_arena.next_arena.is_null() {
         true => {
           // The last arena in the linked list is given a reserve.
-          self.end_pointer = self.next_node.add(ARENA_SIZE - RESERVE_SIZE);
+          self.end_pointer = self.next_node.add(ARENA_SIZE - self.tuning.reserve_size);
         },
         false => {
           self.end_pointer = self.next_node.add(ARENA_SIZE);
@@ -443,28 +495,28 @@ impl NodeAllocator {
       let mut arena_cursor: *mut Arena = self.current_arena;
 
       while arena_cursor != self.last_active_arena {
-        let end_node_ptr = arena_cursor.as_mut_unchecked().first_node().add(ARENA_SIZE);
+        let end_node_ptr = (*arena_cursor).first_node().add(ARENA_SIZE);
 
         while node_cursor_ptr != end_node_ptr {
-          let node_cursor_mut = node_cursor_ptr.as_mut_unchecked();
+          let node_cursor_mut = &mut *node_cursor_ptr;
 
           if node_cursor_mut.is_marked() {
             new_last_active_arena = arena_cursor;
             new_last_active_node  = node_cursor_ptr;
-            node_cursor_mut.flags.remove(DagNodeFlag::Marked);
+            node_cursor_mut.remove_flags(DagNodeFlag::Marked.into());
           }
           else {
             if node_cursor_mut.needs_destruction() {
-              drop_in_place(node_cursor_ptr);
+              DagNodeCore::drop_in_place(node_cursor_ptr);
             }
-            node_cursor_mut.flags = DagNodeFlags::empty();
+            node_cursor_mut.remove_flags(DagNodeFlags::all());
           }
 
           node_cursor_ptr = node_cursor_ptr.add(1);
         } // end loop over nodes
 
-        arena_cursor    = arena_cursor.as_mut_unchecked().next_arena;
-        node_cursor_ptr = arena_cursor.as_mut_unchecked().first_node();
+        arena_cursor    = (*arena_cursor).next_arena;
+        node_cursor_ptr = (*arena_cursor).first_node();
 
       } // end loop over arenas
 
@@ -472,18 +524,18 @@ impl NodeAllocator {
       let end_node_ptr = self.last_active_node;
 
       while node_cursor_ptr <= end_node_ptr {
-        let d_mut = node_cursor_ptr.as_mut_unchecked();
+        let d_mut = &mut *node_cursor_ptr;
 
         if d_mut.is_marked() {
           new_last_active_arena = arena_cursor;
           new_last_active_node  = node_cursor_ptr;
-          d_mut.flags.remove(DagNodeFlag::Marked);
+          d_mut.remove_flags(DagNodeFlag::Marked.into());
         }
         else {
           if d_mut.needs_destruction() {
-            drop_in_place(node_cursor_ptr);
+            DagNodeCore::drop_in_place(node_cursor_ptr);
           }
-          d_mut.flags = DagNodeFlags::empty();
+          d_mut.remove_flags(DagNodeFlags::all());
         }
 
         node_cursor_ptr = node_cursor_ptr.add(1);
@@ -501,7 +553,7 @@ impl NodeAllocator {
     let mut arena_idx = 0u32;
 
     while !arena.is_null() {
-      let arena_mut = arena.as_mut_unchecked();
+      let arena_mut = &mut *arena;
       let mut d     = arena_mut.first_node();
 
       let bound: usize =
@@ -516,7 +568,7 @@ impl NodeAllocator {
           };
 
       for node_idx in 0..bound {
-        if d.as_ref_unchecked().is_marked() {
+        if (*d).is_marked() {
           debug!(2, "check_invariant() : MARKED DagNode! arena = {} node = {}", arena_idx, node_idx);
         }
         d = d.add(1);
@@ -535,11 +587,11 @@ impl NodeAllocator {
     let mut arena_idx = 0u32;
 
     while !arena.is_null() {
-      let arena_mut = arena.as_mut_unchecked();
+      let arena_mut = &mut *arena;
       let mut d     = arena_mut.first_node();
 
       for node_idx in 0..ARENA_SIZE {
-        if d.as_ref_unchecked().is_marked() {
+        if (*d).is_marked() {
           debug!(2, "check_arenas() : MARKED DagNode! arena = {} node = {}", arena_idx, node_idx);
         }
         d = d.add(1);
@@ -552,8 +604,8 @@ impl NodeAllocator {
     } // end loop over arenas
   }
 
-  /// Prints the state of the allocator.
-  #[cfg(feature = "gc_debug")]
+  /// Prints the state of the allocator to standard error.
+  #[cfg(all(feature = "gc_debug", feature = "std"))]
   pub fn dump_memory_variables(&self) {
     let bucket_needs_collection = acquire_storage_allocator().want_to_collect_garbage();
 
@@ -649,6 +701,60 @@ impl NodeAllocator {
 
 
 
+/// Marks the nodes reachable from roots, counting them in `ACTIVE_NODE_COUNT`, and compacts the storage of their
+/// arguments. This is the mark phase of every backend; sweeping the unmarked nodes is up to the backend.
+///
+/// # Safety
+///
+/// Every node reachable from a root must be initialized, and no node may be marked already.
+pub(super) unsafe fn mark_live_nodes() {
+  ACTIVE_NODE_COUNT.store(0, Relaxed); // to be updated during mark phase.
+
+  acquire_storage_allocator()._prepare_to_mark();
+
+  mark_roots();
+
+  acquire_storage_allocator()._sweep_garbage();
+}
+
+/// The number of the collection about to start, counting from 1
+pub(super) fn next_collection_number() -> u64 {
+  COLLECTION_COUNT.fetch_add(1, Relaxed) + 1
+}
+
+/// The number of collections so far
+#[inline(always)]
+pub(crate) fn collection_count() -> u64 {
+  COLLECTION_COUNT.load(Relaxed)
+}
+
+/// The factor by which the capacity of a backend should exceed `active_node_count` after a collection. See
+/// `AllocatorConfig`.
+pub(super) fn slop_factor(tuning: &AllocatorConfig, active_node_count: usize) -> f64 {
+  let AllocatorConfig { small_model_slop, big_model_slop, small_model_bound, big_model_bound, .. } = *tuning;
+
+  // Case: active_node_count >= big_model_bound
+  let mut slop_factor: f64 = big_model_slop;
+  if active_node_count < small_model_bound {
+    // Case: active_node_count < small_model_bound
+    slop_factor = small_model_slop;
+  } else if active_node_count < big_model_bound {
+    // Case: small_model_bound <= active_node_count < big_model_bound
+    // Linearly interpolate between the two models.
+    slop_factor += ((big_model_bound - active_node_count) as f64 * (small_model_slop - big_model_slop))
+        / (big_model_bound - small_model_bound) as f64;
+  }
+
+  slop_factor
+}
+
+/// Rounds the nonnegative `value` up to an integer. `f64::ceil` needs `std`.
+#[inline(always)]
+pub(super) fn ceil(value: f64) -> f64 {
+  let truncated = value as u64 as f64;
+  if truncated < value { truncated + 1.0 } else { truncated }
+}
+
 #[inline(always)]
 pub(crate) fn increment_active_node_count() {
   ACTIVE_NODE_COUNT.fetch_add(1, Relaxed);
@@ -12,19 +12,30 @@ In the sweep phase, the allocator clears the old buckets, resetting their free s
 
 Because live objects are relocated during garbage collection to previously empty buckets, there is no fragmentation after garbage collection. What's more, copying occurs in depth-first order on the graph nodes, improving locality for certain access patterns.
 
+## Heap Storage
+
+With heap storage on, as it is whenever nodes are allocated by the malloc backend, each allocation is instead a heap
+allocation of its own, and the allocations that were live when the mark phase began are freed in the sweep phase, their
+contents having been copied. Memory checkers like AddressSanitizer and Valgrind then catch the use of an argument
+vector that was left behind by a collection, which bucket storage would silently hand out again.
+
 */
 
-use std::{
+use core::{
   cmp::max,
-  sync::{Mutex, MutexGuard},
   ptr::NonNull
 };
-
-use once_cell::sync::Lazy;
+use alloc::{boxed::Box, vec::Vec};
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
 
 use crate::{
+  abstractions::{Lazy, Mutex, MutexGuard},
   core::{
-    allocator::bucket::Bucket,
+    allocator::{
+      backend::AllocatorBackend,
+      bucket::Bucket,
+      gc_log::{record_gc_event, GcEvent}
+    },
     Void
   },
   log::{debug, info},
@@ -55,10 +66,16 @@ pub struct StorageAllocator {
   bucket_count  : u32,    // Total number of buckets
   bucket_list   : Option<NonNull<Bucket>>, // Linked list of "in use" buckets
   unused_list   : Option<NonNull<Bucket>>, // Linked list of unused buckets
+  old_list      : Option<NonNull<Bucket>>, // The buckets that were in use when the mark phase began
   storage_in_use: usize,  // Amount of bucket storage in use (bytes)
   total_bytes_allocated: usize,  // Total amount of bucket storage (bytes)
   old_storage_in_use   : usize, // A temporary to remember storage use prior to GC.
   target        : usize,  // Amount to use before GC (bytes)
+
+  // Heap storage. See the module documentation.
+  heap_storage        : bool,
+  heap_allocations    : Vec<(*mut Void, Layout)>, // Allocations made since the mark phase began
+  old_heap_allocations: Vec<(*mut Void, Layout)>, // Allocations that were live when the mark phase began
 }
 
 // Access is hidden behind a mutex.
@@ -66,6 +83,22 @@ unsafe impl Send for StorageAllocator {}
 // unsafe impl Sync for Allocator {}
 
 impl StorageAllocator {
+  pub fn show_gc_statistics(&mut self, show: bool) {
+    self.show_gc_statistics = show;
+  }
+
+  /// Sets whether each allocation is a heap allocation of its own. Allocations already made are kept until the next
+  /// collection, whichever way they were made.
+  pub fn set_heap_storage(&mut self, heap_storage: bool) {
+    self.heap_storage = heap_storage;
+  }
+
+  /// The number of heap allocations in use
+  #[inline(always)]
+  pub fn heap_allocation_count(&self) -> usize {
+    self.heap_allocations.len() + self.old_heap_allocations.len()
+  }
+
   pub fn new() -> Self {
     StorageAllocator {
       show_gc_statistics: true,
@@ -75,10 +108,15 @@ impl StorageAllocator {
       bucket_count  : 0,
       bucket_list   : None,
       unused_list   : None,
+      old_list      : None,
       storage_in_use: 0,
       total_bytes_allocated: 0,
       old_storage_in_use   : 0,
       target        : INITIAL_TARGET,
+
+      heap_storage        : AllocatorBackend::default() == AllocatorBackend::Malloc,
+      heap_allocations    : Vec::new(),
+      old_heap_allocations: Vec::new(),
     }
   }
 
@@ -97,6 +135,10 @@ impl StorageAllocator {
       self.need_to_collect_garbage = true;
     }
 
+    if self.heap_storage {
+      return self.allocate_heap_storage(bytes_needed);
+    }
+
     let mut b = self.bucket_list;
 
     while let Some(mut bucket) = b {
@@ -113,6 +155,19 @@ impl StorageAllocator {
     unsafe{ self.slow_allocate_storage(bytes_needed) }
   }
 
+  /// Allocates the given number of bytes as a heap allocation of its own.
+  fn allocate_heap_storage(&mut self, bytes_needed: usize) -> *mut Void {
+    // Empty vectors still get an allocation of their own, so that their pointers are distinct.
+    let layout     = Layout::from_size_align(bytes_needed.max(size_of::<usize>()), align_of::<usize>()).unwrap();
+    let allocation = unsafe{ alloc(layout) };
+    if allocation.is_null() {
+      handle_alloc_error(layout);
+    }
+
+    self.heap_allocations.push((allocation, layout));
+    allocation
+  }
+
   /// Allocates the given number of bytes by creating more bucket storage.
   unsafe fn slow_allocate_storage(&mut self, bytes_needed: usize) -> *mut u8 {
     #[cfg(feature = "gc_debug")]
@@ -167,16 +222,20 @@ impl StorageAllocator {
   /// Prepare bucket storage for mark phase of GC
   pub(crate) fn _prepare_to_mark(&mut self) {
     self.old_storage_in_use = self.storage_in_use;
+    self.old_list           = self.bucket_list;
     self.bucket_list        = self.unused_list;
     self.unused_list        = None;
     self.storage_in_use     = 0;
 
+    self.old_heap_allocations    = core::mem::take(&mut self.heap_allocations);
     self.need_to_collect_garbage = false;
   }
 
   /// Garbage Collection for Buckets, called after mark completes
   pub(crate) unsafe fn _sweep_garbage(&mut self) {
-    let mut maybe_bucket = self.bucket_list;
+    // Live storage was copied into `bucket_list` during the mark phase, so it is the formerly active buckets in
+    // `old_list` that are garbage.
+    let mut maybe_bucket = self.old_list.take();
 
     // Reset all formerly active buckets
     self.unused_list = maybe_bucket;
@@ -185,33 +244,18 @@ impl StorageAllocator {
       bucket_mut.reset();
       maybe_bucket = bucket_mut.next_bucket;
     }
+    for (allocation, layout) in self.old_heap_allocations.drain(..) {
+      dealloc(allocation, layout);
+    }
     self.target = max(self.target, TARGET_MULTIPLIER*self.storage_in_use);
 
     if self.show_gc_statistics {
-      info!(1,
-        "{:<10} {:<10} {:<10} {:<10} {:<13} {:<10} {:<10} {:<10} {:<10}",
-        "Buckets",
-        "Bytes",
-        "Size (MB)",
-        "In use",
-        "In use (MB)",
-        "Collected",
-        "Col. (MB)",
-        "Now",
-        "Now (MB)"
-      );
-      info!(1,
-        "{:<10} {:<10} {:<10.2} {:<10} {:<13.2} {:<10} {:<10.2} {:<10.2}  {:<10.2}",
-        self.bucket_count,
-        self.total_bytes_allocated,
-        (self.total_bytes_allocated as f64) / (1024.0 * 1024.0),
-        self.old_storage_in_use,
-        (self.old_storage_in_use as f64) / (1024.0 * 1024.0),
-        self.old_storage_in_use - self.storage_in_use,
-        ((self.old_storage_in_use - self.storage_in_use) as f64) / (1024.0 * 1024.0),
-        self.storage_in_use,
-        (self.storage_in_use as f64) / (1024.0 * 1024.0),
-      );
+      record_gc_event(GcEvent::Storage {
+        buckets: self.bucket_count,
+        bytes  : self.total_bytes_allocated,
+        before : self.old_storage_in_use,
+        after  : self.storage_in_use,
+      });
     }
 
   }
@@ -1,5 +1,3 @@
-use rand::Rng;
-
 use crate::{
   abstractions::IString,
   api::{
@@ -12,76 +10,26 @@ use crate::{
 };
 use crate::api::free_theory::FreeDagNode;
 use crate::api::symbol::SymbolPtr;
-use crate::core::dag_node_core::{DagNodeCore, DagNodeTheory};
-/*
-Recursively builds a random tree of `DagNode`s with a given height and arity rules.
-
-Because this function holds on to iterators of `NodeVec`s, the GC cannot run during
-the building of the tree. Run the GC before or after.
-
- - `symbols`: List of `Symbol` objects of each arity from 0 to `max_width`.
- - `parent`: Pointer to the current parent node.
- - `max_height`: Maximum allowed height for the tree.
-*/
-pub fn build_random_tree(
-  symbols   : &mut [Symbol],
-  parent    : DagNodePtr,
-  max_height: usize,
-  max_width : usize,
-  min_width : usize,
-) {
-  if max_height == 0 {
-    return; // Reached the maximum depth
-  }
-
-  // idiot-proof
-  let min_width = std::cmp::min(max_width, min_width);
-  let max_width = std::cmp::max(max_width, min_width);
-
-  let mut rng   = rand::thread_rng();
-
-  // Get the parent node's arity from its symbol
-  let parent_arity = if let Arity::Value(v) = unsafe { (*parent).arity() } { v as usize } else { 0 };
-
-  // For each child based on the parent's arity, create a new node
-  for i in 0..parent_arity as usize {
-    // Determine the arity of the child node
-    let child_arity = if max_height == 1 {
-      0 // Leaf nodes must have arity 0
-    } else {
-      rng.gen_range(min_width..=max_width) // Random arity between min_width and max_width
-    };
-
-    // Create the child node with the symbol corresponding to its arity
-    let child_symbol: SymbolPtr = &mut symbols[child_arity];
-    let child_node   = FreeDagNode::new(child_symbol);
-
-    // Insert the child into the parent node
-    let parent_mut = unsafe{ parent.as_mut_unchecked() };
-    if let Arity::Value(v) = parent_mut.arity(){
-      if i > v as usize {
-        panic!("Incorrect arity");
-      }
-
-
-
-    }
-    parent_mut.insert_child(child_node);
-
-    // Recursively build the subtree for the child
-    build_random_tree(symbols, child_node, max_height - 1, max_width, min_width);
-  }
+use crate::core::pretty::PrettyPrinter;
+use crate::testing::build_random_tree;
+use crate::core::dag_node_core::{DagNodeCore, DagNodeFlag, DagNodeFlags, DagNodeTheory, ThinDagNodePtr};
+use crate::core::format::{FormatStyle, Formattable};
+use crate::core::config::AllocatorConfig;
+use crate::core::sort::SpecialSort;
+use crate::core::allocator::storage_allocator::StorageAllocator;
+
+/// The symbols indexed by arity, as `build_random_tree` expects.
+fn symbol_ptrs(symbols: &mut [Symbol]) -> Vec<SymbolPtr> {
+  symbols.iter_mut().map(|symbol| symbol as SymbolPtr).collect()
+}
+/// Prints the tree rooted at `node`, checking that every node has as many arguments as its arity.
+pub fn print_tree(node: DagNodePtr) {
+  check_arity(node);
+  println!("{}", PrettyPrinter::default().with_width(0).dag_to_tree(node));
 }
 
-/// Recursively prints a tree structure using ASCII box-drawing symbols.
-///
-/// - `node`: The current node to print.
-/// - `prefix`: The string prefix to apply to the current node's line.
-/// - `is_tail`: Whether the current node is the last child of its parent.
-pub fn print_tree(node: DagNodePtr, prefix: String, is_tail: bool) {
+fn check_arity(node: DagNodePtr) {
   assert!(!node.is_null());
-
-  let is_head = prefix.is_empty();
   let node: &dyn DagNode = unsafe{ &*node };
 
   let arity = if let Arity::Value(v) = node.arity() {
@@ -93,38 +41,7 @@ pub fn print_tree(node: DagNodePtr, prefix: String, is_tail: bool) {
   if arity as usize != node.len() {
     panic!("Incorrect arity/len. arity: {}  len: {}", arity, node.len());
   }
-
-  // Print the current node
-  let new_prefix = if is_head {
-    ""
-  } else {
-    if is_tail { "╰──" } else { "├──" }
-  };
-  println!(
-    "{}{}{}",
-    prefix,
-    new_prefix,
-    node
-  );
-
-  // Determine the new prefix for children
-  let new_prefix = if is_tail {
-    format!("{}    ", prefix)
-  } else if is_head {
-    format!(" ")
-  }
-  else {
-    format!("{}│   ", prefix)
-  };
-
-  // Print each child
-  for (i, child_ptr) in node.iter_args().enumerate() {
-    print_tree(
-      child_ptr,
-      new_prefix.clone(),
-      i == node.len() - 1, // Is this the last child?
-    );
-  }
+  node.iter_args().for_each(check_arity);
 }
 
 
@@ -139,7 +56,25 @@ fn test_allocate_dag_node() {
     Some(node) => { node }
   };
 
-  node_mut.theory_tag = DagNodeTheory::Free;
+  node_mut.set_theory(DagNodeTheory::Free);
+}
+
+
+#[test]
+fn test_insert_child_checks_arity() {
+  let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+  let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+  let (f, a): (SymbolPtr, SymbolPtr) = (&mut f, &mut a);
+
+  let node     = FreeDagNode::new(f);
+  let node_mut = unsafe{ &mut *node };
+  assert!(node_mut.insert_child(FreeDagNode::new(a)).is_ok());
+  assert!(node_mut.insert_child(FreeDagNode::new(a)).is_ok());
+
+  let error = node_mut.insert_child(FreeDagNode::new(a)).unwrap_err();
+  assert_eq!(error.arity, 2);
+  assert_eq!(node_mut.len(), 2);
+  assert!(unsafe{ &mut *FreeDagNode::new(a) }.insert_child(node).is_err());
 }
 
 
@@ -161,8 +96,8 @@ fn test_dag_creation() {
   let max_width : usize = 3;
 
   // Recursively build the random tree
-  build_random_tree(&mut symbols, root, max_height, max_width, 0);
-  print_tree(root, String::new(), false);
+  build_random_tree(&mut rand::rng(), &symbol_ptrs(&mut symbols), root, max_height, max_width, 0);
+  print_tree(root);
   // println!("Symbols: {:?}", symbols);
   #[cfg(feature = "gc_debug")]
   acquire_node_allocator("dump_memory_variables").dump_memory_variables()
@@ -191,7 +126,7 @@ fn test_garbage_collection() {
       let max_width : usize = 4; // base
 
       // Recursively build the random tree
-      build_random_tree(&mut symbols, root, max_height, max_width, 0);
+      build_random_tree(&mut rand::rng(), &symbol_ptrs(&mut symbols), root, max_height, max_width, 0);
     }
     acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
 
@@ -202,6 +137,132 @@ fn test_garbage_collection() {
 }
 
 
+/// Counts the nodes of the tree below `node`, checking each node's arity against its length.
+fn count_nodes(node: DagNodePtr) -> usize {
+  let node: &dyn DagNode = unsafe{ &*node };
+  let arity = if let Arity::Value(v) = node.arity() { v } else { 0 };
+  assert_eq!(arity as usize, node.len());
+
+  1 + node.iter_args().map(count_nodes).sum::<usize>()
+}
+
+#[test]
+fn test_rooted_args_survive_collection() {
+  let mut symbols = (0..=4)
+      .map(|x| {
+        let name = IString::from(format!("sym({})", x).as_str());
+        Symbol::new(name, Arity::Value(x))
+      })
+      .collect::<Vec<_>>();
+  let symbol_ptrs = symbol_ptrs(&mut symbols);
+
+  let root: DagNodePtr = FreeDagNode::new(symbol_ptrs[4]);
+  let _root_container  = RootContainer::new(root);
+  build_random_tree(&mut rand::rng(), &symbol_ptrs, root, 4, 4, 2);
+  let node_count = count_nodes(root);
+
+  // Enough garbage to force several collections, each of which moves the argument vectors of the rooted tree.
+  for _ in 0..100 {
+    for _ in 0..10 {
+      let garbage: DagNodePtr = FreeDagNode::new(symbol_ptrs[4]);
+      build_random_tree(&mut rand::rng(), &symbol_ptrs, garbage, 5, 4, 0);
+    }
+    acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
+
+    assert_eq!(count_nodes(root), node_count);
+  }
+}
+
+
+#[test]
+fn test_live_nodes_keep_their_flags() {
+  let mut symbols = (0..=4)
+      .map(|x| {
+        let name = IString::from(format!("sym({})", x).as_str());
+        Symbol::new(name, Arity::Value(x))
+      })
+      .collect::<Vec<_>>();
+  let symbol_ptrs = symbol_ptrs(&mut symbols);
+
+  let root: DagNodePtr = FreeDagNode::new(symbol_ptrs[0]);
+  let _root_container  = RootContainer::new(root);
+  unsafe{ &mut *root }.core_mut().insert_flags(DagNodeFlag::Reduced.into());
+
+  // Enough garbage to force several collections, after each of which the lazy sweep passes over the root.
+  for _ in 0..100 {
+    for _ in 0..10 {
+      let garbage: DagNodePtr = FreeDagNode::new(symbol_ptrs[4]);
+      build_random_tree(&mut rand::rng(), &symbol_ptrs, garbage, 5, 4, 0);
+    }
+    acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
+
+    assert!(unsafe{ &*root }.core().flags().contains(DagNodeFlag::Reduced));
+  }
+}
+
+
+#[test]
+fn small_nodes_keep_their_arguments_inline() {
+  let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+  let mut g = Symbol::new(IString::from("g"), Arity::Variadic);
+  let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+  let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+  let (f, g, a, b): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut g, &mut a, &mut b);
+  let names = |node: DagNodePtr| {
+    unsafe{ &*node }.iter_args().map(|arg| unsafe{ &*arg }.symbol_ref().name.to_string()).collect::<Vec<_>>()
+  };
+
+  let binary = FreeDagNode::with_args(f, &mut vec![FreeDagNode::new(a), FreeDagNode::new(b)]);
+  let _root  = RootContainer::new(binary);
+  assert!(!unsafe{ &*binary }.core().needs_destruction());
+  assert_eq!(names(binary), ["a", "b"]);
+
+  // A node of variable arity moves its arguments to a vector when it is given a third.
+  let variadic = FreeDagNode::new(g);
+  let _root    = RootContainer::new(variadic);
+  for (count, symbol) in [a, b].into_iter().enumerate() {
+    unsafe{ &mut *variadic }.insert_child(FreeDagNode::new(symbol)).unwrap();
+    assert_eq!(unsafe{ &*variadic }.len(), count + 1);
+    assert!(!unsafe{ &*variadic }.core().needs_destruction());
+  }
+  let pair = FreeDagNode::with_args(g, &mut vec![FreeDagNode::new(a), FreeDagNode::new(b)]);
+  assert!(unsafe{ &*variadic }.equals(pair));
+  unsafe{ &mut *variadic }.insert_child(binary).unwrap();
+  assert!(unsafe{ &*variadic }.core().needs_destruction());
+  assert_eq!(names(variadic), ["a", "b", "f"]);
+  assert!(!unsafe{ &*variadic }.equals(pair));
+
+  // Inline arguments are marked like any others.
+  acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
+  assert_eq!(names(variadic), ["a", "b", "f"]);
+  assert_eq!(names(binary), ["a", "b"]);
+}
+
+
+#[test]
+fn the_symbol_word_keeps_the_sort_index_theory_and_flags() {
+  let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+  let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+  let (f, a): (SymbolPtr, SymbolPtr) = (&mut f, &mut a);
+
+  let node     = FreeDagNode::with_args(f, &mut vec![FreeDagNode::new(a), FreeDagNode::new(a)]);
+  let _root    = RootContainer::new(node);
+  let node_mut = unsafe{ &mut *node };
+  assert_eq!(node_mut.sort_index(), SpecialSort::Unknown as i8);
+
+  node_mut.set_sort_index(SpecialSort::ErrorSort as i8);
+  node_mut.set_flags(DagNodeFlag::Reduced | DagNodeFlag::GroundFlag);
+  acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
+
+  let core = node_mut.core();
+  assert!(core::ptr::addr_eq(core.symbol(), f));
+  assert_eq!(core.theory(), DagNodeTheory::Free);
+  assert_eq!(core.sort_index(), SpecialSort::ErrorSort as i8);
+  // The mark is cleared by the sweep, and the other flags survive it.
+  assert!(core.flags() == DagNodeFlag::Reduced | DagNodeFlag::GroundFlag);
+}
+
+
 #[test]
 fn test_arena_exhaustion() {
   let mut symbol = Symbol::new(IString::from("mysymbol"), Arity::Value(1));
@@ -223,13 +284,103 @@ fn test_arena_exhaustion() {
         node
       }
     };
-    node_mut.theory_tag = DagNodeTheory::Free;
+    // The storage may be that of a dead node, so everything the chain relies on is initialized.
+    node_mut.set_theory(DagNodeTheory::Free);
+    node_mut.remove_flags(DagNodeFlags::all());
+    node_mut.args       = core::ptr::null_mut();
+    node_mut.second_arg = core::ptr::null_mut();
     let node_ptr = DagNodeCore::upgrade(node_ptr);
     unsafe {
-      (&mut*last_node).insert_child(node_ptr);
+      (&mut*last_node).insert_child_unchecked(node_ptr);
     }
     last_node     = node_ptr;
   }
 
 }
 
+
+
+#[test]
+fn debug_ids_are_stable_across_collections() {
+  let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+  let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+  let (f, a): (SymbolPtr, SymbolPtr) = (&mut f, &mut a);
+
+  let root: DagNodePtr = FreeDagNode::with_args(f, &mut vec![FreeDagNode::new(a), FreeDagNode::new(a)]);
+  let _root_container  = RootContainer::new(root);
+  let root_ref = unsafe{ &*root };
+  let ids: Vec<u64> = root_ref.iter_args().map(|arg| unsafe{ &*arg }.debug_id()).collect();
+  let id = root_ref.debug_id();
+  assert_ne!(ids[0], ids[1]);
+  assert!(!ids.contains(&id));
+
+  // Collections move the argument vector of the root but not the nodes.
+  for _ in 0..10 {
+    for _ in 0..1000 {
+      FreeDagNode::with_args(f, &mut vec![FreeDagNode::new(a), FreeDagNode::new(a)]);
+    }
+    acquire_node_allocator("ok_to_collect_garbage").ok_to_collect_garbage();
+  }
+  assert_eq!(root_ref.debug_id(), id);
+  assert_eq!(root_ref.iter_args().map(|arg| unsafe{ &*arg }.debug_id()).collect::<Vec<_>>(), ids);
+  assert!(root_ref.repr(FormatStyle::Debug).starts_with(&format!("<f₂>:free@{}(", id)));
+
+  // A node allocated in the storage of a dead node gets a new id.
+  let thin = root_ref.core() as *const DagNodeCore as ThinDagNodePtr;
+  super::debug_id::forget_debug_id(thin);
+  assert_ne!(root_ref.debug_id(), id);
+}
+
+
+#[test]
+fn malloc_backend_frees_dead_nodes() {
+  let mut symbol = Symbol::new(IString::from("f"), Arity::Value(0));
+  let symbol_ptr: SymbolPtr = &mut symbol;
+  // Nodes come zeroed from the malloc backend, which is a valid empty free node once it has a symbol.
+  let new_node = |backend: &mut dyn NodeAllocatorBackend| {
+    let node = backend.allocate_dag_node();
+    unsafe{ &mut *node }.set_symbol(symbol_ptr);
+    DagNodeCore::upgrade(node)
+  };
+
+  let mut backend = MallocNodeAllocator::new();
+  backend.configure(false, AllocatorConfig::default());
+  let root = new_node(&mut backend);
+  let _root_container = RootContainer::new(root);
+  for _ in 0..ARENA_SIZE {
+    new_node(&mut backend);
+  }
+  assert!(backend.want_to_collect_garbage());
+  backend.ok_to_collect_garbage();
+  assert_eq!(backend.node_count(), 1);
+  assert!(!backend.want_to_collect_garbage());
+
+  let mut counting = CountingNodeAllocator::new(Box::new(MallocNodeAllocator::new()));
+  let counts       = counting.counts();
+  counting.configure(false, AllocatorConfig::default());
+  for _ in 0..=ARENA_SIZE {
+    new_node(&mut counting);
+  }
+  counting.ok_to_collect_garbage();
+  assert_eq!(counts.allocations.load(core::sync::atomic::Ordering::Relaxed), ARENA_SIZE + 1);
+  assert_eq!(counts.collections.load(core::sync::atomic::Ordering::Relaxed), 1);
+  assert_eq!(counting.kind(), AllocatorBackend::Malloc);
+}
+
+
+#[test]
+fn heap_storage_is_freed_by_the_collection_that_leaves_it() {
+  let mut storage = StorageAllocator::new();
+  storage.show_gc_statistics(false);
+  storage.set_heap_storage(true);
+  let old = [storage.allocate_storage(16), storage.allocate_storage(0)];
+  assert_ne!(old[0], old[1]);
+  assert_eq!(storage.heap_allocation_count(), 2);
+
+  // A collection copies live storage during the mark phase, and whatever was allocated before it is freed.
+  storage._prepare_to_mark();
+  let copy = storage.allocate_storage(16);
+  unsafe{ storage._sweep_garbage(); }
+  assert_eq!(storage.heap_allocation_count(), 1);
+  assert!(!copy.is_null());
+}
@@ -0,0 +1,377 @@
+/*!
+
+`ArcTerm`s are an alternative representation of ground terms for users who would rather not deal with the garbage
+collector. An `ArcTerm` is an immutable, reference-counted tree whose nodes are freed when the last reference to them is
+dropped, so it needs no `RootContainer`, and it is `Send` and `Sync`, so it can be shared between threads without a
+`ThreadBoundEngine` transfer. Nodes name their symbols rather than pointing at them, which is what lets them leave the
+module and the thread that made them.
+
+Subterms are shared structurally through an `ArcTermTable`, a hash-cons map: every term the table makes is the only
+term in the table with its symbol and arguments, so equal terms made by the same table are the same allocation and
+compare in constant time. The table holds a reference to each of its terms, and `purge` drops the terms nothing else
+refers to. `clear` drops all of them, so that later terms are no longer shared with earlier ones, which stay valid.
+`statistics` counts the terms made and how many of them were already in the table.
+
+Terms name their symbols, so a table can be shared by modules that share symbol names, and every module of a `Session`
+makes its terms in the session's table. See `Session::terms`.
+
+A session chooses its representation by the methods it calls. `RewritingContext::reduce_arc` takes and returns
+`ArcTerm`s:
+
+```ignore
+let table       = ArcTermTable::new();
+let two         = table.make("s".into(), vec![table.make("s".into(), vec![table.constant("0".into())])]);
+let normal_form = context.reduce_arc(&table.make("plus".into(), vec![two.clone(), two]), &table)?;
+assert_eq!(normal_form.to_string(), "s(s(s(s(0))))");
+```
+
+Rewriting itself still happens on DAG nodes: the term is converted to a DAG on the way in and back on the way out, with
+shared subterms staying shared both ways. The DAG nodes are never rooted, so they are garbage as soon as the call
+returns and are freed by the next collection, which trades throughput for the simplicity of never holding a node.
+
+Only terms of free symbols can be represented.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter},
+  hash::{BuildHasher, Hash, Hasher},
+  sync::atomic::{AtomicUsize, Ordering}
+};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+
+use crate::{
+  abstractions::{FixedState, HashMap, IString, Mutex, Set},
+  api::dag_node::DagNodePtr,
+  core::{
+    module::Module,
+    theory_table::build_node
+  }
+};
+
+/// Returned when an `ArcTerm` can't be converted to a DAG of a module.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ArcTermError {
+  /// The module has no symbol with the name.
+  UnknownSymbol(IString),
+  /// The module's symbol with the name is a variable or takes a different number of arguments.
+  WrongArity { symbol: IString, args: usize },
+}
+
+impl Display for ArcTermError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      ArcTermError::UnknownSymbol(symbol)       => write!(f, "the module has no symbol {}", symbol),
+      ArcTermError::WrongArity { symbol, args } => write!(f, "{} can't be applied to {} arguments", symbol, args),
+    }
+  }
+}
+
+impl Error for ArcTermError {}
+
+/// An immutable, reference-counted ground term. See the module documentation.
+#[derive(Clone)]
+pub struct ArcTerm(Arc<ArcTermNode>);
+
+struct ArcTermNode {
+  symbol: IString,
+  args  : Box<[ArcTerm]>,
+  /// The hash of the symbol and the arguments, computed once
+  hash  : u64,
+}
+
+impl ArcTerm {
+  fn new(symbol: IString, args: Vec<ArcTerm>) -> Self {
+    let mut hasher = FixedState::default().build_hasher();
+    symbol.hash(&mut hasher);
+    for arg in &args {
+      hasher.write_u64(arg.0.hash);
+    }
+
+    ArcTerm(Arc::new(ArcTermNode { symbol, args: args.into_boxed_slice(), hash: hasher.finish() }))
+  }
+
+  #[inline(always)]
+  pub fn symbol(&self) -> &IString {
+    &self.0.symbol
+  }
+
+  #[inline(always)]
+  pub fn args(&self) -> &[ArcTerm] {
+    &self.0.args
+  }
+
+  /// Whether `self` and `other` are the same allocation, which for terms made by the same table is whether they are
+  /// equal
+  #[inline(always)]
+  pub fn ptr_eq(&self, other: &ArcTerm) -> bool {
+    Arc::ptr_eq(&self.0, &other.0)
+  }
+
+  /// Converts the term to a DAG of `module`'s symbols, sharing the nodes of shared subterms. The nodes are not rooted.
+  pub fn to_dag(&self, module: &Module) -> Result<DagNodePtr, ArcTermError> {
+    to_dag(self, module, &mut HashMap::default())
+  }
+}
+
+fn to_dag(
+  term   : &ArcTerm,
+  module : &Module,
+  visited: &mut HashMap<*const ArcTermNode, DagNodePtr>
+) -> Result<DagNodePtr, ArcTermError>
+{
+  if let Some(&node) = visited.get(&Arc::as_ptr(&term.0)) {
+    return Ok(node);
+  }
+
+  let symbol = module.arena.symbol(term.symbol())
+      .ok_or_else(|| ArcTermError::UnknownSymbol(term.symbol().clone()))?;
+  let symbol_ref = unsafe{ &*symbol };
+  if symbol_ref.is_variable() || symbol_ref.arity.max_args() != Some(term.args().len()) {
+    return Err(ArcTermError::WrongArity { symbol: term.symbol().clone(), args: term.args().len() });
+  }
+
+  let mut args = term.args().iter().map(|arg| to_dag(arg, module, visited)).collect::<Result<Vec<_>, _>>()?;
+  let node     = build_node(symbol, &mut args)
+      .ok_or_else(|| ArcTermError::WrongArity { symbol: term.symbol().clone(), args: args.len() })?;
+
+  visited.insert(Arc::as_ptr(&term.0), node);
+  Ok(node)
+}
+
+fn list_bottom_up(term: &ArcTerm, visited: &mut Set<*const ArcTermNode>, listed: &mut Vec<ArcTerm>) {
+  if !visited.insert(Arc::as_ptr(&term.0)) {
+    return;
+  }
+  for arg in term.args() {
+    list_bottom_up(arg, visited, listed);
+  }
+  listed.push(term.clone());
+}
+
+impl PartialEq for ArcTerm {
+  fn eq(&self, other: &Self) -> bool {
+    self.ptr_eq(other)
+        || (self.0.hash == other.0.hash && self.0.symbol == other.0.symbol && self.0.args == other.0.args)
+  }
+}
+
+impl Eq for ArcTerm {}
+
+impl Hash for ArcTerm {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_u64(self.0.hash);
+  }
+}
+
+impl Display for ArcTerm {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.symbol())?;
+    if let Some((first, rest)) = self.args().split_first() {
+      write!(f, "({}", first)?;
+      for arg in rest {
+        write!(f, ", {}", arg)?;
+      }
+      write!(f, ")")?;
+    }
+    Ok(())
+  }
+}
+
+impl core::fmt::Debug for ArcTerm {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "ArcTerm({})", self)
+  }
+}
+
+/// How much sharing an `ArcTermTable` has found since it was made or last cleared
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct HashConsStatistics {
+  /// The number of terms made, counting subterms made on their own
+  pub made  : usize,
+  /// The number of terms made that were already in the table, and were shared instead
+  pub shared: usize,
+  /// The number of terms in the table
+  pub terms : usize,
+}
+
+impl HashConsStatistics {
+  /// The fraction of the terms made that were shared, or zero if none were made
+  pub fn dedup_rate(&self) -> f64 {
+    match self.made {
+      0    => 0.0,
+      made => self.shared as f64 / made as f64,
+    }
+  }
+}
+
+/// A hash-cons map of `ArcTerm`s. See the module documentation.
+#[derive(Default)]
+pub struct ArcTermTable {
+  terms : Mutex<Set<ArcTerm>>,
+  made  : AtomicUsize,
+  shared: AtomicUsize,
+}
+
+impl ArcTermTable {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The term with `symbol` applied to `args`, shared with every other such term the table has made.
+  pub fn make(&self, symbol: IString, args: Vec<ArcTerm>) -> ArcTerm {
+    let term      = ArcTerm::new(symbol, args);
+    let mut terms = self.terms.lock().expect("term table poisoned");
+    self.made.fetch_add(1, Ordering::Relaxed);
+    if let Some(existing) = terms.get(&term) {
+      self.shared.fetch_add(1, Ordering::Relaxed);
+      return existing.clone();
+    }
+
+    terms.insert(term.clone());
+    term
+  }
+
+  #[inline(always)]
+  pub fn constant(&self, symbol: IString) -> ArcTerm {
+    self.make(symbol, Vec::new())
+  }
+
+  /// Converts the DAG at `node` to a term, making shared nodes shared subterms.
+  pub fn term_for_dag(&self, node: DagNodePtr) -> ArcTerm {
+    self.term_for_dag_aux(node, &mut HashMap::default())
+  }
+
+  fn term_for_dag_aux(&self, node: DagNodePtr, visited: &mut HashMap<*mut u8, ArcTerm>) -> ArcTerm {
+    if let Some(term) = visited.get(&(node as *mut u8)) {
+      return term.clone();
+    }
+
+    let node_ref = unsafe{ &*node };
+    let args     = node_ref.iter_args().map(|arg| self.term_for_dag_aux(arg, visited)).collect();
+    let term     = self.make(node_ref.symbol_ref().name.clone(), args);
+
+    visited.insert(node as *mut u8, term.clone());
+    term
+  }
+
+  /// The terms in the table, each after its arguments, in an order that depends only on the terms. Arguments that
+  /// were dropped from the table by `clear` are listed as well.
+  pub(crate) fn terms_bottom_up(&self) -> Vec<ArcTerm> {
+    let mut terms: Vec<ArcTerm> = self.terms.lock().expect("term table poisoned").iter().cloned().collect();
+    terms.sort_by_key(|term| term.0.hash);
+
+    let mut listed  = Vec::with_capacity(terms.len());
+    let mut visited = Set::default();
+    for term in terms.iter() {
+      list_bottom_up(term, &mut visited, &mut listed);
+    }
+    listed
+  }
+
+  /// The number of terms in the table, counting subterms
+  pub fn len(&self) -> usize {
+    self.terms.lock().expect("term table poisoned").len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Drops the terms that are referred to only by the table, including subterms that become unreferenced as their
+  /// parents are dropped, and returns the number dropped.
+  pub fn purge(&self) -> usize {
+    let mut terms = self.terms.lock().expect("term table poisoned");
+    let before    = terms.len();
+    loop {
+      let count = terms.len();
+      terms.retain(|term| Arc::strong_count(&term.0) > 1);
+      if terms.len() == count {
+        break;
+      }
+    }
+
+    before - terms.len()
+  }
+
+  /// Drops every term from the table and resets its statistics. Terms held elsewhere stay valid, but terms made after
+  /// are no longer shared with them.
+  pub fn clear(&self) {
+    let mut terms = self.terms.lock().expect("term table poisoned");
+    terms.clear();
+    self.made.store(0, Ordering::Relaxed);
+    self.shared.store(0, Ordering::Relaxed);
+  }
+
+  /// See `HashConsStatistics`.
+  pub fn statistics(&self) -> HashConsStatistics {
+    let terms = self.terms.lock().expect("term table poisoned");
+    HashConsStatistics {
+      made  : self.made.load(Ordering::Relaxed),
+      shared: self.shared.load(Ordering::Relaxed),
+      terms : terms.len(),
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::{
+      arc_term::{ArcTerm, ArcTermError, ArcTermTable},
+      parser::parse_module,
+      rewriting_context::RewritingContext
+    }
+  };
+
+  fn is_send_and_sync<T: Send + Sync>() {}
+
+  #[test]
+  fn arc_terms_are_shared_and_reduce_without_roots() {
+    is_send_and_sync::<ArcTerm>();
+    is_send_and_sync::<ArcTermTable>();
+
+    let module = parse_module("
+      mod PEANO is
+        sort Nat .
+        ops 0 : -> Nat .
+        op s : Nat -> Nat .
+        op plus : Nat Nat -> Nat .
+        vars X Y : Nat .
+        eq plus(0, Y) = Y .
+        eq plus(s(X), Y) = s(plus(X, Y)) .
+      endm
+    ").unwrap();
+
+    let table = ArcTermTable::new();
+    let zero  = table.constant(IString::from("0"));
+    let one   = table.make(IString::from("s"), vec![zero.clone()]);
+    let two   = table.make(IString::from("s"), vec![table.make(IString::from("s"), vec![zero])]);
+    assert!(two.args()[0].ptr_eq(&one));
+    assert_eq!(table.len(), 3);
+
+    let subject     = table.make(IString::from("plus"), vec![two.clone(), two.clone()]);
+    let mut context = RewritingContext::new(&module);
+    let normal_form = context.reduce_arc(&subject, &table).unwrap();
+    assert_eq!(normal_form.to_string(), "s(s(s(s(0))))");
+    assert!(normal_form.args()[0].args()[0].ptr_eq(&two));
+
+    // Other threads see the same terms.
+    let shared = std::thread::scope(|scope| scope.spawn(|| normal_form.args()[0].clone()).join().unwrap());
+    assert!(shared.args()[0].ptr_eq(&two));
+
+    let unknown = table.constant(IString::from("nil"));
+    assert_eq!(context.reduce_arc(&unknown, &table).unwrap_err(), ArcTermError::UnknownSymbol(IString::from("nil")));
+
+    // Only the terms still held survive a purge.
+    drop((subject, one, two, shared, unknown));
+    assert_eq!(table.purge(), 2);
+    assert_eq!(table.len(), 5);
+    drop(normal_form);
+    table.purge();
+    assert!(table.is_empty());
+  }
+}
@@ -0,0 +1,142 @@
+use alloc::vec::Vec;
+
+use crate::{
+  api::{
+    dag_node::DagNodePtr,
+    free_theory::FreeDagNode,
+    symbol::SymbolPtr,
+    NONE
+  },
+  core::{
+    automata::{
+      duplication::{max_duplications, report_duplication_bound_hit},
+      match_all,
+      LHSAutomaton,
+      RcLHSAutomaton
+    },
+    collapse::Collapse,
+    substitution::Substitution
+  }
+};
+
+/// One way a pattern can collapse: the automata of the arguments that must be the identity, and the automaton of the
+/// argument that remains
+struct CollapseCase {
+  identities: Vec<RcLHSAutomaton>,
+  survivor  : RcLHSAutomaton,
+}
+
+/// Matches a subject against a pattern whose top symbol has an identity or is idempotent, first structurally, then,
+/// if no structural match is accepted, by each of the pattern's collapses in turn. For each surviving argument, the
+/// other arguments are matched against the identity, and the survivor against the whole subject. If the symbol is
+/// idempotent, every argument is matched against the whole subject, which takes a duplication. See `collapse` and
+/// `duplication`.
+pub struct CollapseLHSAutomaton {
+  symbol      : SymbolPtr,
+  structural  : RcLHSAutomaton,
+  identity    : Option<SymbolPtr>,
+  cases       : Vec<CollapseCase>,
+  idempotent  : bool,
+  arg_automata: Vec<RcLHSAutomaton>,
+  save_index  : i32,
+}
+
+impl CollapseLHSAutomaton {
+  /// Wraps `structural`, the automaton for the pattern with top symbol `symbol`, with the cases of `collapse`, whose
+  /// survivors index `arg_automata`, the automata for the pattern's arguments.
+  pub fn new(
+    symbol      : SymbolPtr,
+    structural  : RcLHSAutomaton,
+    collapse    : &Collapse,
+    arg_automata: &[RcLHSAutomaton],
+    save_index  : i32
+  ) -> Self
+  {
+    let cases = collapse
+        .survivors
+        .iter()
+        .map(
+          |&survivor| CollapseCase {
+            identities: arg_automata
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != survivor)
+                .map(|(_, automaton)| automaton.clone())
+                .collect(),
+            survivor  : arg_automata[survivor].clone(),
+          }
+        )
+        .collect();
+
+    CollapseLHSAutomaton {
+      symbol,
+      structural,
+      identity    : collapse.identity,
+      cases,
+      idempotent  : collapse.idempotent,
+      arg_automata: arg_automata.to_vec(),
+      save_index,
+    }
+  }
+
+  /// The subject is an instance of the pattern modulo the axioms, so the right-hand side can reuse it.
+  #[inline(always)]
+  fn save(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool {
+    if self.save_index != NONE {
+      solution.bind(self.save_index, Some(subject));
+    }
+    true
+  }
+}
+
+impl LHSAutomaton for CollapseLHSAutomaton {
+  fn match_(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool {
+    self.match_each(subject, solution, &mut |_| true)
+  }
+
+  fn match_each(
+    &self,
+    subject : DagNodePtr,
+    solution: &mut Substitution,
+    accept  : &mut dyn FnMut(&mut Substitution) -> bool
+  ) -> bool
+  {
+    if self.structural.match_each(subject, solution, accept) {
+      return true;
+    }
+
+    let mut accept_saved = |solution: &mut Substitution| self.save(subject, solution) && accept(solution);
+
+    if let Some(identity) = self.identity {
+      let identity = FreeDagNode::new(identity);
+      for case in self.cases.iter() {
+        let mut pairs: Vec<(&RcLHSAutomaton, DagNodePtr)>
+            = case.identities.iter().map(|automaton| (automaton, identity)).collect();
+        pairs.push((&case.survivor, subject));
+        if match_all(&pairs, solution, &mut accept_saved) {
+          return true;
+        }
+      }
+    }
+
+    if self.idempotent {
+      if max_duplications() == 0 {
+        report_duplication_bound_hit(self.symbol);
+      } else {
+        let pairs: Vec<(&RcLHSAutomaton, DagNodePtr)>
+            = self.arg_automata.iter().map(|automaton| (automaton, subject)).collect();
+        if match_all(&pairs, solution, &mut accept_saved) {
+          return true;
+        }
+      }
+    }
+
+    false
+  }
+
+  /// A subject can match structurally and by collapsing.
+  #[inline(always)]
+  fn is_deterministic(&self) -> bool {
+    false
+  }
+}
@@ -0,0 +1,56 @@
+/*!
+
+Demandedness analysis of patterns. To match a subject, a pattern must see the subject's symbol at every position at
+which the pattern has a non-variable subterm, and only there: variables match anything. These are the pattern's
+*demanded positions*.
+
+A subject subterm at a demanded position that an evaluation strategy left unreduced has to be reduced before the match
+can decide anything, while unreduced subterms at other positions can be left alone. Positions are listed in preorder,
+so that each comes after its ancestors. Once the subject disagrees with the pattern at a position, the match has failed,
+and nothing below the position needs to be reduced.
+
+A subterm that can collapse (see `collapse`) can match subjects with any of several symbols, so neither its position
+nor the positions below it are demanded. The arguments of a subterm with an associative top symbol are matched against
+the flattened arguments of the subject (see `extension`), so the positions below it aren't demanded either.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  api::{symbol::SymbolPtr, term::Term},
+  core::{automata::has_associative_top, collapse::can_collapse}
+};
+
+/// A position below the top of a pattern at which a match needs the subject to have `symbol`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DemandedPosition {
+  pub position: Vec<usize>,
+  pub symbol  : SymbolPtr,
+}
+
+/// The demanded positions of `pattern` below its top, in preorder. A pattern whose top symbol is associative is
+/// matched against the flattened arguments of the subject, and a pattern that can collapse may match a subject headed
+/// by another symbol, so the positions of either aren't the subject's, and none are demanded.
+pub fn demanded_positions(pattern: &dyn Term) -> Vec<DemandedPosition> {
+  let mut demanded = Vec::new();
+  if !has_associative_top(pattern) && !can_collapse(pattern) {
+    collect(pattern, &mut Vec::new(), &mut demanded);
+  }
+  demanded
+}
+
+fn collect(term: &dyn Term, position: &mut Vec<usize>, demanded: &mut Vec<DemandedPosition>) {
+  for (index, arg) in term.iter_args().enumerate() {
+    if arg.is_variable() || can_collapse(arg) {
+      continue;
+    }
+
+    position.push(index);
+    demanded.push(DemandedPosition { position: position.clone(), symbol: arg.symbol() });
+    if !has_associative_top(arg) {
+      collect(arg, position, demanded);
+    }
+    position.pop();
+  }
+}
@@ -0,0 +1,99 @@
+/*!
+
+Duplication control for idempotent symbols. Modulo idempotency, `f(a, a)` is `a`, so the pattern `f(X, Y)` matches
+`a` with both variables bound to `a`, and a single argument of an associative-commutative subject can match several
+arguments of the pattern. Each extra use of a subject or subject argument is a *duplication*.
+
+The number of ways to duplicate grows quickly with the number of pattern arguments, so a single match attempt makes at
+most `max_duplications()` duplications, `DEFAULT_MAX_DUPLICATIONS` unless configured otherwise with
+`EngineConfig::with_max_duplications`. Matches that need more are not found. Each failed match attempt that the bound
+cut short is counted in `Statistics::duplication_bound_hits` and logged at level 2.
+
+Duplications are made by the collapse automaton of an idempotent symbol (see `CollapseLHSAutomaton`) and by the
+matching of associative-commutative patterns (see `extension`). Idempotency of associative symbols that aren't
+commutative isn't taken into account.
+
+*/
+
+use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+use crate::{
+  api::symbol::SymbolPtr,
+  core::statistics::record_duplication_bound_hit,
+  warning
+};
+
+/// The number of duplications a match attempt may make unless configured otherwise
+pub const DEFAULT_MAX_DUPLICATIONS: usize = 4;
+
+static MAX_DUPLICATIONS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DUPLICATIONS);
+
+/// The number of duplications a single match attempt may make
+#[inline(always)]
+pub fn max_duplications() -> usize {
+  MAX_DUPLICATIONS.load(Relaxed)
+}
+
+#[inline(always)]
+pub(crate) fn set_max_duplications(max_duplications: usize) {
+  MAX_DUPLICATIONS.store(max_duplications, Relaxed);
+}
+
+/// Records that a match attempt against a subject with top symbol `symbol` failed after the bound cut it short.
+pub(crate) fn report_duplication_bound_hit(symbol: SymbolPtr) {
+  record_duplication_bound_hit();
+  warning!(
+    2,
+    "a match under {} may have been missed because it needs more than {} duplications",
+    unsafe{ &*symbol }.name,
+    max_duplications()
+  );
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    automata::duplication::{set_max_duplications, DEFAULT_MAX_DUPLICATIONS},
+    parser::{parse_module, parse_term},
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext,
+    statistics::statistics
+  };
+
+  const MODULE: &str = "
+    mod IDEMPOTENT is
+      sort Set .
+      ops a b c : -> Set .
+      op u : Set Set -> Set [assoc comm idem] .
+      op m : Set Set -> Set [idem] .
+      op h : Set -> Set .
+      var X : Set .
+      eq u(a, u(b, X)) = c .
+      eq h(m(X, a)) = c .
+    endm
+  ";
+
+  fn reduce(subject: &str) -> String {
+    let module      = parse_module(MODULE).unwrap();
+    let term        = parse_term(&module, subject).unwrap();
+    let mut context = RewritingContext::new(&module);
+    dag_to_string(context.reduce(term.term_to_dag(false)))
+  }
+
+  // The bound is global, and no other test uses idempotent symbols.
+  #[test]
+  fn duplications_are_bounded() {
+    // `X` is a duplicate of `a` or `b`, and `m(X, a)` is a duplicated `a`.
+    assert_eq!(reduce("u(a, b)"), "c");
+    assert_eq!(reduce("h(a)"), "c");
+    assert_eq!(reduce("h(b)"), "h(b)");
+
+    set_max_duplications(0);
+    let before = statistics();
+    assert_eq!(reduce("u(a, b)"), "u(a, b)");
+    assert_eq!(reduce("h(a)"), "h(a)");
+    assert!(statistics().duplication_bound_hits >= before.duplication_bound_hits + 2);
+    set_max_duplications(DEFAULT_MAX_DUPLICATIONS);
+  }
+}
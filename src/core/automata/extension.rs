@@ -0,0 +1,713 @@
+/*!
+
+Matching modulo associativity and commutativity. An associative symbol is binary, but modulo associativity a term
+`f(a, f(b, c))` is the list `a, b, c`, and modulo commutativity too, the multiset of `a`, `b`, and `c`. A pattern
+subterm with an associative top symbol is compiled into an `AssociativeLHSAutomaton`, wherever it occurs in the pattern,
+which matches its flattened arguments against the flattened arguments of the subject.
+
+Each argument of the flattened pattern that isn't a variable matches a single argument of the subject. A variable
+matches a run of them, bound to their nesting to the right: contiguous arguments if the symbol is associative, and any
+of them if it is also commutative. So `f(c, X)` matches `f(a, f(b, c))` modulo associativity and commutativity, with `X`
+bound to `f(a, b)`. If the symbol is also idempotent, a subject argument can match several pattern arguments, within the
+bound on duplications (see `duplication`). Idempotency of symbols that aren't commutative isn't taken into account.
+
+If the symbol has an identity, an argument of the flattened pattern that can be the identity, such as a variable, can
+also match no subject arguments at all, in which case it is bound to the identity, and a subject with another top
+symbol is a list of one argument. So `f(c, X)` also matches `c`, with `X` bound to the identity. An identity on one side
+only vanishes next to an argument on the other. The identity is the one found by the collapse analysis of the pattern
+(see `collapse`).
+
+A pattern can have several matches modulo the axioms, and the automaton tries them in turn until one is accepted by
+whoever asked for the match (see `LHSAutomaton::match_each`), which is how the constraints that structural matching
+can't see, and the rest of the pattern, get a say.
+
+## Matching with Extension
+
+A pattern whose top symbol is associative applies not only to a subject's whole list of arguments but to any part of
+it. A match with *extension* matches the pattern against part of the subject's flattened argument list and returns the
+rest as an `Extension`, so that the subject can be rebuilt around the instance of the right-hand side. If the symbol is
+associative, the matched part is a contiguous run of arguments, and the extension is the arguments before and after
+it. If the symbol is also commutative, the extension is the arguments left over.
+
+The subproblems and alternatives of a match are limited by the budget of the substitution it binds, if it has one (see
+`match_limits`).
+
+*/
+
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{
+  abstractions::NatSet,
+  api::{
+    dag_node::DagNodePtr,
+    free_theory::FreeDagNode,
+    symbol::{SymbolAttribute, SymbolPtr},
+    term::Term,
+    NONE
+  },
+  core::{
+    automata::{
+      duplication::{max_duplications, report_duplication_bound_hit},
+      EqualityConstraint,
+      LHSAutomaton,
+      RcLHSAutomaton,
+      SharedAutomata
+    },
+    collapse::can_be_identity,
+    format::FormatStyle,
+    match_limits::MatchAttempt,
+    substitution::Substitution,
+    VariableInfo
+  }
+};
+
+/// What a match left of the subject. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Extension {
+  /// The pattern matched the whole subject.
+  Whole,
+  /// The pattern matched part of the flattened arguments of a subject with top symbol `symbol`. The unmatched
+  /// arguments are those in `before` and `after`. For a commutative symbol, they are all in `after`.
+  Partial {
+    symbol: SymbolPtr,
+    before: Vec<DagNodePtr>,
+    after : Vec<DagNodePtr>,
+  },
+}
+
+impl Extension {
+  #[inline(always)]
+  pub fn is_whole(&self) -> bool {
+    matches!(self, Extension::Whole)
+  }
+
+  /// The subject with the part the pattern matched replaced by `replacement`, nested to the right.
+  pub fn rebuild(&self, replacement: DagNodePtr) -> DagNodePtr {
+    let Extension::Partial { symbol, before, after } = self else {
+      return replacement;
+    };
+
+    let mut args = before.clone();
+    args.push(replacement);
+    args.extend_from_slice(after);
+
+    nest_right(*symbol, &args)
+  }
+}
+
+/// Whether `pattern` is matched by an `AssociativeLHSAutomaton`
+#[inline(always)]
+pub(crate) fn has_associative_top(pattern: &dyn Term) -> bool {
+  !pattern.is_variable() && pattern.symbol_ref().attributes.contains(SymbolAttribute::Associative)
+}
+
+/// Matches the flattened arguments of a pattern with an associative top symbol against the flattened arguments of a
+/// subject, with or without extension. See the module documentation.
+pub struct AssociativeLHSAutomaton {
+  symbol        : SymbolPtr,
+  commutative   : bool,
+  /// Whether the symbol is also commutative and idempotent
+  idempotent    : bool,
+  /// The identity of the symbol, if the pattern's collapse analysis found it
+  identity      : Option<SymbolPtr>,
+  /// Whether the identity vanishes next to an argument on its right, as a left identity does. True for a commutative
+  /// symbol.
+  left_identity : bool,
+  /// Whether the identity vanishes next to an argument on its left, as a right identity does. True for a commutative
+  /// symbol.
+  right_identity: bool,
+  arg_automata  : Vec<RcLHSAutomaton>,
+  /// Whether each pattern argument is a variable, which matches a run of subject arguments
+  variables     : Vec<bool>,
+  /// Whether each pattern argument can be the identity, and so can match no subject arguments
+  vanishing     : Vec<bool>,
+  save_index    : i32,
+  /// The pattern, for reporting matches that reach a limit
+  pattern       : String,
+}
+
+/// The state of a commutative match: the subject arguments each pattern argument matched, which subject arguments are
+/// matched, and how many duplications were made
+struct Assignment {
+  runs        : Vec<Vec<usize>>,
+  used        : Vec<bool>,
+  duplications: usize,
+  extension   : bool,
+  /// Whether an assignment was skipped because it needed too many duplications
+  bound_hit   : bool,
+}
+
+/// The state of a match of an associative symbol that isn't commutative: the subject arguments, where the matched
+/// part of them starts, where it must end, if it must, and the length of the run each pattern argument matched
+struct Runs<'a> {
+  args   : &'a [DagNodePtr],
+  start  : usize,
+  end    : Option<usize>,
+  lengths: Vec<usize>,
+}
+
+impl AssociativeLHSAutomaton {
+  /// Compiles the flattened arguments of `pattern`, which must have an associative top symbol.
+  pub fn new(
+    pattern        : &dyn Term,
+    variable_info  : &mut VariableInfo,
+    bound_uniquely : &mut NatSet,
+    constraints    : &mut Vec<EqualityConstraint>,
+    shared_automata: &mut SharedAutomata
+  ) -> Self
+  {
+    let symbol = pattern.symbol();
+    let mut args = Vec::new();
+    flatten_pattern(pattern, symbol, &mut args);
+
+    let attributes  = pattern.symbol_ref().attributes;
+    let commutative = attributes.contains(SymbolAttribute::Commutative);
+    let identity    = find_identity(pattern, symbol);
+
+    let variables = args.iter().map(|arg| arg.is_variable()).collect();
+    let vanishing = args
+        .iter()
+        .map(|&arg| identity.is_some_and(|identity| can_be_identity(arg, identity)))
+        .collect();
+    let arg_automata = args
+        .into_iter()
+        .map(|arg| shared_automata.compile_lhs(arg, variable_info, bound_uniquely, constraints))
+        .collect();
+
+    AssociativeLHSAutomaton {
+      symbol,
+      commutative,
+      idempotent    : commutative && attributes.contains(SymbolAttribute::Idempotent),
+      identity,
+      left_identity : commutative || attributes.contains(SymbolAttribute::LeftIdentity),
+      right_identity: commutative || attributes.contains(SymbolAttribute::RightIdentity),
+      arg_automata,
+      variables,
+      vanishing,
+      save_index    : pattern.core().save_index,
+      pattern       : pattern.repr(FormatStyle::Default),
+    }
+  }
+
+  /// Matches `subject`, or part of it if `extension` is set, calling `accept` on each match until it returns `true`.
+  /// Returns what the accepted match left of the subject.
+  pub(crate) fn match_with(
+    &self,
+    subject  : DagNodePtr,
+    solution : &mut Substitution,
+    extension: bool,
+    accept   : &mut dyn FnMut(&mut Substitution) -> bool
+  ) -> Option<Extension>
+  {
+    let mut args = Vec::new();
+    if core::ptr::addr_eq(unsafe{ &*subject }.symbol(), self.symbol) {
+      flatten_subject(subject, self.symbol, &mut args);
+    } else if self.identity.is_some() || self.idempotent {
+      // Modulo the identity or idempotency, the subject is a list of one.
+      args.push(subject);
+    } else {
+      return None;
+    }
+
+    let width = self.arg_automata.len();
+    if !extension && !self.variables.contains(&true) && args.len() > width {
+      return None;
+    }
+
+    let mut accept = |solution: &mut Substitution| {
+      if self.save_index != NONE {
+        solution.bind(self.save_index, Some(subject));
+      }
+      accept(solution)
+    };
+    let mut attempt = MatchAttempt::new(solution);
+    let result      = match self.commutative {
+      true  => self.match_commutative(args, solution, extension, &mut accept, &mut attempt),
+      false => self.match_sequence(args, solution, extension, &mut accept, &mut attempt),
+    };
+    attempt.finish(&|| self.pattern.clone());
+
+    result
+  }
+
+  /// The subject the run `args` of subject arguments stands for
+  fn run(&self, args: &[DagNodePtr]) -> DagNodePtr {
+    match args {
+      []    => FreeDagNode::new(self.identity.expect("a run of no arguments without an identity")),
+      [arg] => *arg,
+      _     => nest_right(self.symbol, args)
+    }
+  }
+
+  /// The number of subject arguments the pattern arguments from `next` on need at least
+  #[inline(always)]
+  fn needed_from(&self, next: usize) -> usize {
+    self.vanishing[next..].iter().filter(|&&vanishing| !vanishing).count()
+  }
+
+  fn match_sequence(
+    &self,
+    args     : Vec<DagNodePtr>,
+    solution : &mut Substitution,
+    extension: bool,
+    accept   : &mut dyn FnMut(&mut Substitution) -> bool,
+    attempt  : &mut MatchAttempt
+  ) -> Option<Extension>
+  {
+    let starts = if extension { args.len() } else { 1 };
+    for start in 0..starts {
+      let mut matched_end = None;
+      let mut finish      = |end: usize, solution: &mut Substitution| {
+        matched_end = Some(end);
+        accept(solution)
+      };
+      let mut runs = Runs {
+        args   : &args,
+        start,
+        end    : (!extension).then_some(args.len()),
+        lengths: Vec::with_capacity(self.arg_automata.len()),
+      };
+
+      if self.match_runs(0, start, &mut runs, solution, &mut finish, attempt) {
+        let end = matched_end.unwrap();
+        if start == 0 && end == args.len() {
+          return Some(Extension::Whole);
+        }
+        return Some(Extension::Partial {
+          symbol: self.symbol,
+          before: args[..start].to_vec(),
+          after : args[end..].to_vec(),
+        });
+      }
+      if attempt.is_exhausted() {
+        break;
+      }
+    }
+
+    None
+  }
+
+  /// Matches the pattern arguments from `next` on against runs of subject arguments starting at `position`, shortest
+  /// runs first, backtracking on failure. Calls `finish` with the end of the matched part on each complete match.
+  fn match_runs(
+    &self,
+    next    : usize,
+    position: usize,
+    runs    : &mut Runs,
+    solution: &mut Substitution,
+    finish  : &mut dyn FnMut(usize, &mut Substitution) -> bool,
+    attempt : &mut MatchAttempt
+  ) -> bool
+  {
+    let Some(automaton) = self.arg_automata.get(next) else {
+      return runs.end.is_none_or(|end| end == position)
+          && position > runs.start
+          && self.identities_vanish(&runs.lengths)
+          && attempt.alternative()
+          && finish(position, solution);
+    };
+
+    let limit  = runs.end.unwrap_or(runs.args.len());
+    let needed = self.needed_from(next + 1);
+    if position + needed > limit {
+      return false;
+    }
+    let available = limit - position - needed;
+    let longest   = if self.variables[next] { available } else { available.min(1) };
+    let vanishing = self.vanishing[next].then_some(0);
+
+    for length in (1..=longest).chain(vanishing) {
+      if !attempt.subproblem() {
+        return false;
+      }
+      let subject = self.run(&runs.args[position..position + length]);
+      runs.lengths.push(length);
+      let matched = automaton.match_each(
+        subject,
+        solution,
+        &mut |solution| self.match_runs(next + 1, position + length, runs, solution, finish, attempt)
+      );
+      runs.lengths.pop();
+      if matched {
+        return true;
+      }
+      if attempt.is_exhausted() {
+        return false;
+      }
+    }
+
+    false
+  }
+
+  /// Whether each pattern argument that matched no subject arguments has an argument that did on the side where the
+  /// identity vanishes
+  fn identities_vanish(&self, lengths: &[usize]) -> bool {
+    if self.left_identity && self.right_identity {
+      return true;
+    }
+    lengths.iter().enumerate().all(
+      |(index, &length)| {
+        length > 0
+            || (self.right_identity && lengths[..index].iter().any(|&length| length > 0))
+            || (self.left_identity && lengths[index + 1..].iter().any(|&length| length > 0))
+      }
+    )
+  }
+
+  fn match_commutative(
+    &self,
+    args     : Vec<DagNodePtr>,
+    solution : &mut Substitution,
+    extension: bool,
+    accept   : &mut dyn FnMut(&mut Substitution) -> bool,
+    attempt  : &mut MatchAttempt
+  ) -> Option<Extension>
+  {
+    // Without idempotency, each pattern argument that can't vanish needs a subject argument of its own.
+    let shortfall = self.needed_from(0).saturating_sub(args.len());
+    if shortfall > 0 && (!self.idempotent || shortfall > max_duplications()) {
+      if self.idempotent {
+        report_duplication_bound_hit(self.symbol);
+      }
+      return None;
+    }
+
+    let mut assignment = Assignment {
+      runs        : vec![Vec::new(); self.arg_automata.len()],
+      used        : vec![false; args.len()],
+      duplications: 0,
+      extension,
+      bound_hit   : false,
+    };
+    if !self.assign(0, &args, &mut assignment, solution, accept, attempt) {
+      if assignment.bound_hit {
+        report_duplication_bound_hit(self.symbol);
+      }
+      return None;
+    }
+
+    if assignment.used.iter().all(|&used| used) {
+      return Some(Extension::Whole);
+    }
+    let after = args.iter().zip(assignment.used).filter(|(_, used)| !used).map(|(&arg, _)| arg).collect();
+    Some(Extension::Partial { symbol: self.symbol, before: vec![], after })
+  }
+
+  /// Assigns subject arguments to the pattern arguments from `next` on, backtracking on failure. Each pattern argument
+  /// is assigned one subject argument, or none if it can vanish, and the arguments that aren't variables are matched
+  /// as they are assigned. Subject arguments are distinct unless the symbol is idempotent. The subject arguments left
+  /// over are then shared out among the variables by `extend`. Every slot an automaton binds is bound again whenever
+  /// the automaton is retried, so bindings left by failed attempts are harmless. Gives up once `attempt` reaches a
+  /// limit.
+  fn assign(
+    &self,
+    next      : usize,
+    args      : &[DagNodePtr],
+    assignment: &mut Assignment,
+    solution  : &mut Substitution,
+    accept    : &mut dyn FnMut(&mut Substitution) -> bool,
+    attempt   : &mut MatchAttempt
+  ) -> bool
+  {
+    let Some(automaton) = self.arg_automata.get(next) else {
+      return self.extend(0, args, assignment, solution, accept, attempt);
+    };
+
+    // Subject arguments not yet matched are tried first, then matching none, then duplications.
+    let unused     = (0..args.len()).filter(|&index| !assignment.used[index]).map(Some);
+    let vanishing  = self.vanishing[next].then_some(None);
+    let duplicates = (0..args.len()).filter(|&index| self.idempotent && assignment.used[index]).map(Some);
+    let candidates: Vec<Option<usize>> = unused.chain(vanishing).chain(duplicates).collect();
+
+    for candidate in candidates {
+      let duplicate = candidate.is_some_and(|index| assignment.used[index]);
+      if duplicate && assignment.duplications == max_duplications() {
+        assignment.bound_hit = true;
+        continue;
+      }
+      if !attempt.subproblem() {
+        return false;
+      }
+
+      if duplicate {
+        assignment.duplications += 1;
+      }
+      let subject = match candidate {
+        Some(index) => {
+          assignment.used[index] = true;
+          assignment.runs[next].push(index);
+          args[index]
+        }
+        None => self.run(&[]),
+      };
+      let matched = match self.variables[next] {
+        // The variable is matched once its run is complete.
+        true  => self.assign(next + 1, args, assignment, solution, accept, attempt),
+        false => automaton.match_each(
+          subject,
+          solution,
+          &mut |solution| self.assign(next + 1, args, assignment, solution, accept, attempt)
+        ),
+      };
+      if matched {
+        return true;
+      }
+      if let Some(index) = candidate {
+        assignment.runs[next].pop();
+        assignment.used[index] = duplicate;
+      }
+      if duplicate {
+        assignment.duplications -= 1;
+      }
+      if attempt.is_exhausted() {
+        return false;
+      }
+    }
+
+    false
+  }
+
+  /// Shares out the subject arguments from `index` on that `assign` left over, each going to the extension, if there
+  /// is one, or to the run of a variable. A run only grows by arguments after its first, so that each way of sharing
+  /// out the arguments is tried once. Then matches the variables against their runs.
+  fn extend(
+    &self,
+    index     : usize,
+    args      : &[DagNodePtr],
+    assignment: &mut Assignment,
+    solution  : &mut Substitution,
+    accept    : &mut dyn FnMut(&mut Substitution) -> bool,
+    attempt   : &mut MatchAttempt
+  ) -> bool
+  {
+    let Some(leftover) = (index..args.len()).find(|&index| !assignment.used[index]) else {
+      return self.match_variables(0, args, assignment, solution, accept, attempt);
+    };
+
+    if assignment.extension && self.extend(leftover + 1, args, assignment, solution, accept, attempt) {
+      return true;
+    }
+
+    assignment.used[leftover] = true;
+    for next in 0..self.arg_automata.len() {
+      let grows = self.variables[next] && assignment.runs[next].first().is_some_and(|&first| first < leftover);
+      if !grows {
+        continue;
+      }
+      if !attempt.subproblem() {
+        break;
+      }
+      assignment.runs[next].push(leftover);
+      let matched = self.extend(leftover + 1, args, assignment, solution, accept, attempt);
+      assignment.runs[next].pop();
+      if matched {
+        return true;
+      }
+      if attempt.is_exhausted() {
+        break;
+      }
+    }
+    assignment.used[leftover] = false;
+
+    false
+  }
+
+  /// Matches the variables from the `next`th pattern argument on against their runs, then calls `accept`.
+  fn match_variables(
+    &self,
+    next      : usize,
+    args      : &[DagNodePtr],
+    assignment: &mut Assignment,
+    solution  : &mut Substitution,
+    accept    : &mut dyn FnMut(&mut Substitution) -> bool,
+    attempt   : &mut MatchAttempt
+  ) -> bool
+  {
+    let Some(variable) = (next..self.arg_automata.len()).find(|&next| self.variables[next]) else {
+      return attempt.alternative() && accept(solution);
+    };
+
+    let run: Vec<DagNodePtr> = assignment.runs[variable].iter().map(|&index| args[index]).collect();
+    let subject = self.run(&run);
+    self.arg_automata[variable].match_each(
+      subject,
+      solution,
+      &mut |solution| self.match_variables(variable + 1, args, assignment, solution, accept, attempt)
+    )
+  }
+}
+
+impl LHSAutomaton for AssociativeLHSAutomaton {
+  fn match_(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool {
+    self.match_with(subject, solution, false, &mut |_| true).is_some()
+  }
+
+  fn match_each(
+    &self,
+    subject : DagNodePtr,
+    solution: &mut Substitution,
+    accept  : &mut dyn FnMut(&mut Substitution) -> bool
+  ) -> bool
+  {
+    self.match_with(subject, solution, false, accept).is_some()
+  }
+
+  #[inline(always)]
+  fn is_deterministic(&self) -> bool {
+    false
+  }
+}
+
+/// The nesting to the right of `args`, of which there are at least two, under `symbol`
+fn nest_right(symbol: SymbolPtr, args: &[DagNodePtr]) -> DagNodePtr {
+  let (&last, rest) = args.split_last().unwrap();
+  rest.iter().rev().fold(last, |result, &arg| FreeDagNode::with_args(symbol, &mut vec![arg, result]))
+}
+
+/// The identity of `symbol` recorded by the collapse analysis of `pattern` or of one of the subterms flattened into it
+fn find_identity(pattern: &dyn Term, symbol: SymbolPtr) -> Option<SymbolPtr> {
+  if let Some(identity) = pattern.core().collapse.as_ref().and_then(|collapse| collapse.identity) {
+    return Some(identity);
+  }
+  pattern
+      .iter_args()
+      .filter(|arg| !arg.is_variable() && core::ptr::addr_eq(arg.symbol(), symbol))
+      .find_map(|arg| find_identity(arg, symbol))
+}
+
+fn flatten_pattern<'t>(term: &'t dyn Term, symbol: SymbolPtr, args: &mut Vec<&'t dyn Term>) {
+  for arg in term.iter_args() {
+    if !arg.is_variable() && core::ptr::addr_eq(arg.symbol(), symbol) {
+      flatten_pattern(arg, symbol, args);
+    } else {
+      args.push(arg);
+    }
+  }
+}
+
+fn flatten_subject(node: DagNodePtr, symbol: SymbolPtr, args: &mut Vec<DagNodePtr>) {
+  for arg in unsafe{ &*node }.iter_args() {
+    if core::ptr::addr_eq(unsafe{ &*arg }.symbol(), symbol) {
+      flatten_subject(arg, symbol, args);
+    } else {
+      args.push(arg);
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::{IString, NatSet},
+    api::{
+      Arity,
+      dag_node::{DagNode, DagNodePtr},
+      free_theory::{FreeDagNode, FreeTerm},
+      symbol::{Symbol, SymbolAttribute, SymbolPtr},
+      variable_theory::VariableTerm,
+    },
+    core::{
+      automata::{CompiledPattern, Extension},
+      parser::{parse_module, parse_term},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext,
+      substitution::Substitution,
+      RootContainer,
+      VariableInfo
+    }
+  };
+
+  fn reduce(module_source: &str, subject: &str) -> String {
+    let module      = parse_module(module_source).unwrap();
+    let term        = parse_term(&module, subject).unwrap();
+    let mut context = RewritingContext::new(&module);
+    dag_to_string(context.reduce(term.term_to_dag(false)))
+  }
+
+  #[test]
+  fn match_part_of_a_flattened_subject() {
+    let mut plus = Symbol::new(IString::from("plus"), Arity::Value(2));
+    plus.attributes = SymbolAttribute::Associative.into();
+    let mut zero = Symbol::new(IString::from("0"), Arity::Value(0));
+    let mut a    = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b    = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x    = Symbol::new_variable(IString::from("X"));
+    let (plus, zero, a, b, x): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut plus, &mut zero, &mut a, &mut b, &mut x);
+
+    // plus(X, 0) against plus(a, plus(0, b)) and plus(b, 0)
+    let zero_term   = FreeTerm::with_args(zero, vec![]);
+    let mut pattern = FreeTerm::with_args(plus, vec![Box::new(VariableTerm::new(x)), zero_term]);
+    let (a_node, zero_node, b_node) = (FreeDagNode::new(a), FreeDagNode::new(zero), FreeDagNode::new(b));
+    let nested  = FreeDagNode::with_args(plus, &mut vec![zero_node, b_node]);
+    let subject = FreeDagNode::with_args(plus, &mut vec![a_node, nested]);
+    let whole   = FreeDagNode::with_args(plus, &mut vec![b_node, zero_node]);
+    let _roots  = (RootContainer::new(subject), RootContainer::new(whole));
+
+    let mut variable_info = VariableInfo::new();
+    pattern.index_variables(&mut variable_info);
+    let compiled     = CompiledPattern::new(pattern.as_ref(), &mut variable_info, &mut NatSet::new());
+    let mut solution = Substitution::with_capacity(variable_info.protected_variable_count() as usize);
+
+    // Without extension, the pattern needs the whole list.
+    assert!(!compiled.match_(subject, &mut solution));
+    assert_eq!(compiled.match_with_extension(whole, &mut solution), Some(Extension::Whole));
+    assert!(std::ptr::addr_eq(solution.value(0).unwrap(), b_node));
+
+    let extension = compiled.match_with_extension(subject, &mut solution).unwrap();
+    assert_eq!(extension, Extension::Partial { symbol: plus, before: vec![], after: vec![b_node] });
+    assert!(std::ptr::addr_eq(solution.value(0).unwrap(), a_node));
+
+    // The rebuilt subject has the replacement in place of the matched part.
+    let rebuilt: &dyn DagNode = unsafe{ &*extension.rebuild(a_node) };
+    let args: Vec<DagNodePtr> = rebuilt.iter_args().collect();
+    assert!(std::ptr::addr_eq(rebuilt.symbol(), plus));
+    assert!(std::ptr::addr_eq(args[0], a_node) && std::ptr::addr_eq(args[1], b_node));
+  }
+
+  #[test]
+  fn match_modulo_axioms_below_the_top() {
+    let module = "
+      mod AC is
+        sort S .
+        ops a b c yes : -> S .
+        op u : S S -> S [assoc comm] .
+        op f : S -> S .
+        var X : S .
+        eq f(u(c, X)) = yes .
+      endm
+    ";
+    // `X` absorbs the arguments `c` leaves, however the subject is nested and ordered.
+    assert_eq!(reduce(module, "f(u(a, u(b, c)))"), "yes");
+    assert_eq!(reduce(module, "f(u(c, u(a, b)))"), "yes");
+    assert_eq!(reduce(module, "f(u(u(a, c), b))"), "yes");
+    assert_eq!(reduce(module, "f(u(c, a))"), "yes");
+    assert_eq!(reduce(module, "f(u(a, b))"), "f(u(a, b))");
+    // Without an identity, `X` can't match nothing.
+    assert_eq!(reduce(module, "f(c)"), "f(c)");
+  }
+
+  #[test]
+  fn variables_match_runs_of_arguments() {
+    let module = "
+      mod RUNS is
+        sort S .
+        ops a b c e yes : -> S .
+        op cat : S S -> S [assoc] .
+        op u : S S -> S [assoc comm id: e] .
+        ops g h : S -> S .
+        vars X Y : S .
+        eq g(cat(X, cat(c, Y))) = yes .
+        eq h(u(X, u(X, Y))) = Y .
+      endm
+    ";
+    // Runs of an associative symbol are contiguous, and have at least one argument without an identity.
+    assert_eq!(reduce(module, "g(cat(a, cat(b, cat(c, cat(a, b)))))"), "yes");
+    assert_eq!(reduce(module, "g(cat(c, cat(a, b)))"), "g(cat(c, cat(a, b)))");
+    // With an identity, a variable can match nothing, and repeated variables match equal runs.
+    assert_eq!(reduce(module, "h(u(a, a))"), "e");
+    assert_eq!(reduce(module, "h(u(a, u(a, c)))"), "c");
+    assert_eq!(reduce(module, "h(c)"), "c");
+  }
+}
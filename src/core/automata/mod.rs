@@ -0,0 +1,534 @@
+/*!
+
+Compiled matching automata. A pattern `Term` is compiled into a tree of `LHSAutomaton`s, one per subterm, which are
+then run against subject `DagNode`s, recording variable bindings in a `Substitution`.
+
+## Non-linear Variables
+
+A pattern is *linear* if no variable occurs in it more than once. Matching a linear pattern never needs to compare
+bindings, so the automata for variables simply bind the subject to the variable's slot.
+
+When a variable occurs more than once (or was already bound, say, by an earlier condition fragment), only its first
+occurrence binds the variable's own slot. Each repeated occurrence is compiled to bind a fresh protected slot, and an
+`EqualityConstraint` between the two slots is recorded. After structural matching succeeds, `CompiledPattern` checks
+the constraints. For linear patterns there are no constraints to check.
+
+## Constrained Variables
+
+Variables can carry side constraints (see `ConstrainedVariable`). These are delayed like equality constraints: after
+structural matching and the equality constraints succeed, `CompiledPattern` checks each constrained variable's binding.
+The exception is pattern tests, which the variable's automaton checks before it binds.
+
+## Demanded Positions
+
+A compiled pattern also records its demanded positions, the positions at which it needs to see the subject's symbol.
+Reduction uses them to reduce subterms left unreduced by evaluation strategies only when a match depends on them. See
+`demand`.
+
+## Matching Modulo Axioms
+
+A pattern subterm whose top symbol is associative is matched modulo associativity, and, if the symbol is also
+commutative, commutativity, against the flattened arguments of the subject, with variables matching runs of them. Such
+a match can have several solutions, which `LHSAutomaton::match_each` enumerates, so that a solution the rest of the
+pattern or the constraints reject is followed by the next. A pattern whose top symbol is associative can also be
+matched against part of the subject's arguments, leaving the rest as an `Extension`. See `extension`.
+
+## Collapse
+
+A pattern subterm whose top symbol has an identity can *collapse* to one of its arguments when the others are the
+identity, or, if the symbol is idempotent, when the arguments are equal. The automaton of such a subterm tries its
+collapses when structural matching fails. See `collapse`. The number of duplications idempotency can introduce in a
+match is bounded. See `duplication`.
+
+## Saved Subterms
+
+A left-hand side subterm whose instance is reused on the right-hand side (see `TermBag`) is given a `save_index`. Its
+automaton binds the matched subject to that slot so the right-hand side can refer to it without rebuilding it.
+
+*/
+
+mod collapse_automaton;
+mod demand;
+pub(crate) mod duplication;
+mod extension;
+mod rhs_builder;
+mod shared_automata;
+mod term_bag;
+
+use alloc::{rc::Rc, vec::Vec};
+
+use crate::{
+  abstractions::NatSet,
+  api::{
+    dag_node::DagNodePtr,
+    term::Term,
+    variable_theory::{ConstrainedVariable, VariableTerm}
+  },
+  core::{
+    binders::alpha_equal,
+    substitution::Substitution,
+    VariableInfo
+  }
+};
+
+pub use collapse_automaton::CollapseLHSAutomaton;
+pub use demand::{demanded_positions, DemandedPosition};
+pub use extension::{AssociativeLHSAutomaton, Extension};
+pub(crate) use extension::has_associative_top;
+pub use rhs_builder::{RHSAutomaton, RHSBuilder};
+pub use shared_automata::SharedAutomata;
+pub use term_bag::TermBag;
+
+/// Automata are reference counted so that identical subpatterns of different statements can share them. See
+/// `SharedAutomata`.
+pub type RcLHSAutomaton = Rc<dyn LHSAutomaton>;
+
+pub trait LHSAutomaton {
+  /// Attempts to match `subject`, recording bindings in `solution`. Returns `true` on success. On failure, `solution`
+  /// may contain partial bindings.
+  fn match_(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool;
+
+  /// Matches `subject`, calling `accept` on each match in turn until it returns `true`, and returns whether a match
+  /// was accepted. Only matching modulo axioms can find more than one match, so by default this is `match_` followed
+  /// by `accept`.
+  fn match_each(
+    &self,
+    subject : DagNodePtr,
+    solution: &mut Substitution,
+    accept  : &mut dyn FnMut(&mut Substitution) -> bool
+  ) -> bool
+  {
+    self.match_(subject, solution) && accept(solution)
+  }
+
+  /// Whether the automaton finds at most one match for any subject, so that `match_` is as good as `match_each`
+  #[inline(always)]
+  fn is_deterministic(&self) -> bool {
+    true
+  }
+}
+
+/// Matches each automaton against its subject in turn, backtracking over the matches of each, and calls `accept` on
+/// each combined match until it returns `true`.
+pub(crate) fn match_all(
+  pairs   : &[(&RcLHSAutomaton, DagNodePtr)],
+  solution: &mut Substitution,
+  accept  : &mut dyn FnMut(&mut Substitution) -> bool
+) -> bool
+{
+  match pairs.split_first() {
+    None                              => accept(solution),
+    Some(((automaton, subject), rest)) => {
+      automaton.match_each(*subject, solution, &mut |solution| match_all(rest, solution, accept))
+    }
+  }
+}
+
+/// Records that the binding in slot `duplicate` must equal the binding in slot `original` for a match to succeed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct EqualityConstraint {
+  pub original : i32,
+  pub duplicate: i32,
+}
+
+/// Records that the binding in slot `index` must satisfy `constraint` for a match to succeed.
+#[derive(Clone, Debug)]
+pub struct VariableConstraint {
+  pub index     : i32,
+  pub constraint: Rc<ConstrainedVariable>,
+}
+
+/// A pattern compiled into an automaton together with the equality constraints arising from its non-linear variables,
+/// the side constraints of its constrained variables, and its demanded positions. Clones share the automata.
+#[derive(Clone)]
+pub struct CompiledPattern {
+  automaton           : RcLHSAutomaton,
+  /// The automaton again if the top symbol is associative, for matching with extension
+  associative         : Option<Rc<AssociativeLHSAutomaton>>,
+  equality_constraints: Vec<EqualityConstraint>,
+  variable_constraints: Vec<VariableConstraint>,
+  demanded_positions  : Vec<DemandedPosition>,
+}
+
+impl CompiledPattern {
+  /// Compiles `pattern`, whose variables must already be indexed in `variable_info`. Variables in `bound_uniquely`
+  /// are treated as already bound; on return, `bound_uniquely` additionally contains every variable bound by the
+  /// pattern.
+  pub fn new(pattern: &dyn Term, variable_info: &mut VariableInfo, bound_uniquely: &mut NatSet) -> Self {
+    Self::with_shared_automata(pattern, variable_info, bound_uniquely, &mut SharedAutomata::new())
+  }
+
+  /// Like `new`, but reuses the automata in `shared_automata` for subpatterns identical to ones already compiled,
+  /// adding the automata for new subpatterns to it.
+  pub fn with_shared_automata(
+    pattern        : &dyn Term,
+    variable_info  : &mut VariableInfo,
+    bound_uniquely : &mut NatSet,
+    shared_automata: &mut SharedAutomata
+  ) -> Self
+  {
+    let mut equality_constraints = Vec::new();
+    let (automaton, associative): (RcLHSAutomaton, _) = match has_associative_top(pattern) {
+      true => {
+        let automaton = Rc::new(AssociativeLHSAutomaton::new(
+          pattern, variable_info, bound_uniquely, &mut equality_constraints, shared_automata
+        ));
+        (automaton.clone(), Some(automaton))
+      }
+      false => {
+        let automaton = pattern.compile_lhs(variable_info, bound_uniquely, &mut equality_constraints, shared_automata);
+        (automaton, None)
+      }
+    };
+
+    let mut variable_constraints = Vec::new();
+    collect_variable_constraints(pattern, &mut variable_constraints);
+
+    CompiledPattern {
+      automaton,
+      associative,
+      equality_constraints,
+      variable_constraints,
+      demanded_positions: demanded_positions(pattern),
+    }
+  }
+
+  /// Is the compiled pattern linear, i.e. free of equality constraints? Variable constraints don't affect linearity.
+  #[inline(always)]
+  pub fn is_linear(&self) -> bool {
+    self.equality_constraints.is_empty()
+  }
+
+  #[inline(always)]
+  pub fn equality_constraints(&self) -> &[EqualityConstraint] {
+    &self.equality_constraints
+  }
+
+  #[inline(always)]
+  pub fn variable_constraints(&self) -> &[VariableConstraint] {
+    &self.variable_constraints
+  }
+
+  /// The positions below the top at which the pattern needs to see the subject's symbol, in preorder
+  #[inline(always)]
+  pub fn demanded_positions(&self) -> &[DemandedPosition] {
+    &self.demanded_positions
+  }
+
+  /// Matches `subject` against the pattern. The `solution` must be large enough to hold every slot the pattern
+  /// binds, including the slots for repeated variable occurrences.
+  pub fn match_(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool {
+    if self.automaton.is_deterministic() {
+      return self.automaton.match_(subject, solution) && self.constraints_hold(solution);
+    }
+
+    self.automaton.match_each(subject, solution, &mut |solution| self.constraints_hold(solution))
+  }
+
+  /// Like `match_`, but skips the first `skip` solutions, so that the alternative matches of a pattern with associative
+  /// symbols can be enumerated one at a time without computing those after the one wanted. Matching starts over each
+  /// time. A pattern without associative symbols has at most one solution.
+  pub fn match_skipping(&self, subject: DagNodePtr, solution: &mut Substitution, skip: usize) -> bool {
+    if self.automaton.is_deterministic() {
+      return skip == 0 && self.match_(subject, solution);
+    }
+
+    let mut skipped = 0;
+    let mut accept  = |solution: &mut Substitution| {
+      if !self.constraints_hold(solution) {
+        return false;
+      }
+      skipped += 1;
+      skipped > skip
+    };
+    self.automaton.match_each(subject, solution, &mut accept)
+  }
+
+  /// Like `match_`, but a pattern whose top symbol is associative may match part of the subject's flattened arguments.
+  /// Returns what the match left of the subject, or `None` if the pattern doesn't match.
+  pub fn match_with_extension(&self, subject: DagNodePtr, solution: &mut Substitution) -> Option<Extension> {
+    match &self.associative {
+      Some(associative) => {
+        associative.match_with(subject, solution, true, &mut |solution| self.constraints_hold(solution))
+      }
+      None => self.match_(subject, solution).then_some(Extension::Whole)
+    }
+  }
+
+  /// Whether the bindings of a structural match satisfy the equality and variable constraints
+  fn constraints_hold(&self, solution: &Substitution) -> bool {
+    // Fast path for linear, unconstrained patterns
+    if self.is_linear() && self.variable_constraints.is_empty() {
+      return true;
+    }
+
+    self.equality_constraints.iter().all(
+      |constraint| {
+        match (solution.get(constraint.original), solution.get(constraint.duplicate)) {
+          (Some(original), Some(duplicate)) => alpha_equal(original, duplicate),
+          _ => false
+        }
+      }
+    ) && self.variable_constraints.iter().all(
+      |constraint| {
+        match solution.get(constraint.index) {
+          Some(binding) => constraint.constraint.is_satisfied_by(binding),
+          None          => false
+        }
+      }
+    )
+  }
+}
+
+/// Collects the constraints of the constrained variables in `pattern`, which must be indexed. An occurrence only
+/// contributes constraints not already collected for its variable, so repeated occurrences are checked once.
+fn collect_variable_constraints(pattern: &dyn Term, constraints: &mut Vec<VariableConstraint>) {
+  if let Some(variable) = pattern.as_any().downcast_ref::<VariableTerm>() {
+    if let Some(constraint) = variable.constraint() {
+      let seen = constraints.iter().any(
+        |seen| seen.index == variable.index() && seen.constraint.same_constraints(constraint)
+      );
+      if !seen {
+        constraints.push(VariableConstraint { index: variable.index(), constraint: constraint.clone() });
+      }
+    }
+    return;
+  }
+
+  for arg in pattern.iter_args() {
+    collect_variable_constraints(arg, constraints);
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::{cell::Cell, rc::Rc};
+
+  use crate::{
+    abstractions::{IString, NatSet},
+    api::{
+      Arity,
+      dag_node::DagNodePtr,
+      free_theory::{FreeDagNode, FreeTerm},
+      symbol::{Symbol, SymbolPtr},
+      variable_theory::{ConstrainedVariable, VariableTerm},
+    },
+    core::{
+      automata::{CompiledPattern, SharedAutomata},
+      sort::Sort,
+      substitution::Substitution,
+      RootContainer,
+      VariableInfo
+    }
+  };
+
+  #[test]
+  fn match_non_linear_pattern() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let mut y = Symbol::new_variable(IString::from("Y"));
+    let (f, a, b, x, y): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut f, &mut a, &mut b, &mut x, &mut y);
+
+    // f(X, X) and f(X, Y)
+    let mut non_linear = FreeTerm::with_args(f, vec![Box::new(VariableTerm::new(x)), Box::new(VariableTerm::new(x))]);
+    let mut linear     = FreeTerm::with_args(f, vec![Box::new(VariableTerm::new(x)), Box::new(VariableTerm::new(y))]);
+    assert!(!non_linear.is_linear());
+    assert!(linear.is_linear());
+
+    // Subjects f(a, a) and f(a, b)
+    let a_node: DagNodePtr = FreeDagNode::new(a);
+    let b_node: DagNodePtr = FreeDagNode::new(b);
+    let same      = FreeDagNode::with_args(f, &mut vec![a_node, a_node]);
+    let different = FreeDagNode::with_args(f, &mut vec![a_node, b_node]);
+    let _roots    = (RootContainer::new(same), RootContainer::new(different));
+
+    let mut variable_info = VariableInfo::new();
+    non_linear.index_variables(&mut variable_info);
+    let pattern = CompiledPattern::new(non_linear.as_ref(), &mut variable_info, &mut NatSet::new());
+    assert!(!pattern.is_linear());
+
+    let mut solution = Substitution::with_capacity(variable_info.protected_variable_count() as usize);
+    assert!(pattern.match_(same, &mut solution));
+    solution.clear_first_n(variable_info.protected_variable_count() as usize);
+    assert!(!pattern.match_(different, &mut solution));
+
+    let mut variable_info = VariableInfo::new();
+    linear.index_variables(&mut variable_info);
+    let pattern = CompiledPattern::new(linear.as_ref(), &mut variable_info, &mut NatSet::new());
+    assert!(pattern.is_linear());
+
+    let mut solution = Substitution::with_capacity(variable_info.protected_variable_count() as usize);
+    assert!(pattern.match_(different, &mut solution));
+    assert!(std::ptr::addr_eq(solution.value(1).unwrap(), b_node));
+  }
+
+  #[test]
+  fn share_identical_subpatterns() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let (f, g, a, x): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut g, &mut a, &mut x);
+
+    // f(g(a), X) and f(g(a), g(X))
+    let g_a        = FreeTerm::with_args(g, vec![FreeTerm::with_args(a, vec![])]);
+    let mut first  = FreeTerm::with_args(f, vec![g_a, Box::new(VariableTerm::new(x))]);
+    let mut second = FreeTerm::with_args(
+      f,
+      vec![
+        FreeTerm::with_args(g, vec![FreeTerm::with_args(a, vec![])]),
+        FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(x))])
+      ]
+    );
+
+    let mut shared_automata = SharedAutomata::new();
+    let mut first_info      = VariableInfo::new();
+    let mut second_info     = VariableInfo::new();
+    first.index_variables(&mut first_info);
+    second.index_variables(&mut second_info);
+    let first_pattern = CompiledPattern::with_shared_automata(
+      first.as_ref(), &mut first_info, &mut NatSet::new(), &mut shared_automata
+    );
+    let second_pattern = CompiledPattern::with_shared_automata(
+      second.as_ref(), &mut second_info, &mut NatSet::new(), &mut shared_automata
+    );
+
+    // g(a) is compiled once
+    assert_eq!(shared_automata.hit_count(), 1);
+
+    // Subject f(g(a), g(a))
+    let a_node: DagNodePtr = FreeDagNode::new(a);
+    let g_node = FreeDagNode::new(g);
+    unsafe{ &mut *g_node }.insert_child(a_node).unwrap();
+    let subject = FreeDagNode::with_args(f, &mut vec![g_node, g_node]);
+    let _root   = RootContainer::new(subject);
+
+    let mut solution = Substitution::with_capacity(1);
+    assert!(first_pattern.match_(subject, &mut solution));
+    assert!(std::ptr::addr_eq(solution.value(0).unwrap(), g_node));
+    solution.clear_first_n(1);
+    assert!(second_pattern.match_(subject, &mut solution));
+    assert!(std::ptr::addr_eq(solution.value(0).unwrap(), a_node));
+  }
+
+  #[test]
+  fn match_constrained_variables() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let (f, a, b, x): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut a, &mut b, &mut x);
+
+    // f(X, X) where X must be `a`. The predicate counts how often it is called.
+    let calls     = Rc::new(Cell::new(0));
+    let constraint = {
+      let calls = calls.clone();
+      ConstrainedVariable::new(x).with_predicate(
+        move |node| {
+          calls.set(calls.get() + 1);
+          std::ptr::addr_eq(node.symbol(), a)
+        }
+      )
+    };
+    let mut pattern = FreeTerm::with_args(f, vec![constraint.clone().into_term(), constraint.into_term()]);
+
+    let a_node: DagNodePtr = FreeDagNode::new(a);
+    let b_node: DagNodePtr = FreeDagNode::new(b);
+    let subjects = [
+      FreeDagNode::with_args(f, &mut vec![a_node, a_node]),
+      FreeDagNode::with_args(f, &mut vec![b_node, b_node]),
+      FreeDagNode::with_args(f, &mut vec![a_node, b_node]),
+    ];
+    let _roots: Vec<_> = subjects.iter().map(|subject| RootContainer::new(*subject)).collect();
+
+    let mut variable_info = VariableInfo::new();
+    pattern.index_variables(&mut variable_info);
+    let compiled = CompiledPattern::new(pattern.as_ref(), &mut variable_info, &mut NatSet::new());
+    assert_eq!(compiled.variable_constraints().len(), 1);
+
+    let size         = variable_info.protected_variable_count() as usize;
+    let mut solution = Substitution::with_capacity(size);
+    let results: Vec<bool> = subjects.iter().map(
+      |subject| {
+        solution.clear_first_n(size);
+        compiled.match_(*subject, &mut solution)
+      }
+    ).collect();
+    assert_eq!(results, vec![true, false, false]);
+    // The constraint is checked once per match, and not at all when the equality constraint fails.
+    assert_eq!(calls.get(), 2);
+
+    // A sort constraint is only satisfied by bindings known to be in the sort.
+    let mut sort = Sort::new(IString::from("Letter"));
+    sort.leq_sorts.insert(1);
+    let mut pattern = ConstrainedVariable::new(x).with_sort(&mut sort).into_term();
+    let mut variable_info = VariableInfo::new();
+    pattern.index_variables(&mut variable_info);
+    let compiled = CompiledPattern::new(pattern.as_ref(), &mut variable_info, &mut NatSet::new());
+
+    let mut solution = Substitution::with_capacity(1);
+    assert!(!compiled.match_(a_node, &mut solution));
+    unsafe{ &mut *a_node }.set_sort_index(1);
+    assert!(compiled.match_(a_node, &mut solution));
+  }
+
+  #[test]
+  fn pattern_tests_are_checked_during_matching() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let mut y = Symbol::new_variable(IString::from("Y"));
+    let (f, a, b): (SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut a, &mut b);
+    let (x, y): (SymbolPtr, SymbolPtr)               = (&mut x, &mut y);
+
+    // f(X?isA, Y) where Y also has a delayed predicate. Both count how often they are called.
+    let test_calls      = Rc::new(Cell::new(0));
+    let predicate_calls = Rc::new(Cell::new(0));
+    let x_term = {
+      let test_calls = test_calls.clone();
+      ConstrainedVariable::new(x).with_test(
+        move |node| {
+          test_calls.set(test_calls.get() + 1);
+          std::ptr::addr_eq(node.symbol(), a)
+        }
+      ).into_term()
+    };
+    let y_term = {
+      let predicate_calls = predicate_calls.clone();
+      ConstrainedVariable::new(y).with_predicate(
+        move |_| {
+          predicate_calls.set(predicate_calls.get() + 1);
+          true
+        }
+      ).into_term()
+    };
+    let mut pattern = FreeTerm::with_args(f, vec![x_term, y_term]);
+
+    let a_node: DagNodePtr = FreeDagNode::new(a);
+    let b_node: DagNodePtr = FreeDagNode::new(b);
+    let subjects = [
+      FreeDagNode::with_args(f, &mut vec![b_node, a_node]),
+      FreeDagNode::with_args(f, &mut vec![a_node, b_node]),
+    ];
+    let _roots: Vec<_> = subjects.iter().map(|subject| RootContainer::new(*subject)).collect();
+
+    let mut variable_info = VariableInfo::new();
+    pattern.index_variables(&mut variable_info);
+    let compiled = CompiledPattern::new(pattern.as_ref(), &mut variable_info, &mut NatSet::new());
+
+    let size         = variable_info.protected_variable_count() as usize;
+    let mut solution = Substitution::with_capacity(size);
+    assert!(!compiled.match_(subjects[0], &mut solution));
+    // The failing test stopped the match before the predicate ran.
+    assert_eq!((test_calls.get(), predicate_calls.get()), (1, 0));
+
+    solution.clear_first_n(size);
+    assert!(compiled.match_(subjects[1], &mut solution));
+    assert_eq!((test_calls.get(), predicate_calls.get()), (2, 1));
+  }
+}
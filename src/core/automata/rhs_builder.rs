@@ -0,0 +1,73 @@
+/*!
+
+Right-hand side construction. A right-hand side `Term` is compiled into a sequence of `RHSAutomaton`s, each of which
+builds one node from the bindings in a `Substitution` and stores it in a slot of the same substitution. Arguments are
+always compiled before the terms that contain them, so running the automata in order builds the whole right-hand side.
+
+Subterms that are available at runtime are not rebuilt. See `TermBag`.
+
+*/
+
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+  api::dag_node::DagNodePtr,
+  core::{
+    substitution::Substitution,
+    VariableInfo
+  }
+};
+
+pub type BxRHSAutomaton = Box<dyn RHSAutomaton>;
+
+pub trait RHSAutomaton {
+  /// Replaces construction indices with the slots assigned by `VariableInfo::compute_index_remapping`.
+  fn remap_indices(&mut self, variable_info: &VariableInfo);
+
+  /// Builds a node from the bindings in `matcher`, binds it to the automaton's destination slot, and returns it.
+  fn construct(&self, matcher: &mut Substitution) -> DagNodePtr;
+}
+
+#[derive(Default)]
+pub struct RHSBuilder {
+  automata: Vec<BxRHSAutomaton>,
+}
+
+impl RHSBuilder {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  #[inline(always)]
+  pub fn add_rhs_automaton(&mut self, automaton: BxRHSAutomaton) {
+    self.automata.push(automaton);
+  }
+
+  /// Is there nothing to construct, i.e. is the right-hand side available without construction?
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.automata.is_empty()
+  }
+
+  /// The number of nodes constructed by each run of the builder.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.automata.len()
+  }
+
+  pub fn remap_indices(&mut self, variable_info: &VariableInfo) {
+    for automaton in self.automata.iter_mut() {
+      automaton.remap_indices(variable_info);
+    }
+  }
+
+  /// Runs every automaton in order. Returns the last node constructed, if any.
+  pub fn construct(&self, matcher: &mut Substitution) -> Option<DagNodePtr> {
+    let mut last = None;
+    for automaton in self.automata.iter() {
+      last = Some(automaton.construct(matcher));
+    }
+    last
+  }
+}
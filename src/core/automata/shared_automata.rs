@@ -0,0 +1,138 @@
+/*!
+
+Sharing of compiled subpatterns across statements. Large (especially machine generated) modules often have many
+equations for the same symbol whose left-hand sides have identical subpatterns, e.g. `f(s(s(0)), X)` and
+`f(s(s(0)), g(X))`. A `SharedAutomata` cache is threaded through the compilation of all statements with the same top
+symbol, and identical subpatterns are compiled once and share a single automaton.
+
+A subpattern's automaton depends on more than the subpattern's structure. It also depends on the slots assigned to its
+variables, on the `save_index` of the subpattern and its subterms, and on which of its variables were already bound when
+it was compiled. Two subpatterns therefore share an automaton only if they agree on all of these. To keep the check
+simple, we only share subpatterns that are linear and none of whose variables were previously bound, so that they
+contribute no equality constraints.
+
+*/
+
+use alloc::{rc::Rc, vec::Vec};
+
+use crate::{
+  abstractions::{
+    HashMap,
+    NatSet
+  },
+  api::{
+    term::Term,
+    variable_theory::VariableTerm,
+    NONE
+  },
+  core::{
+    automata::{
+      EqualityConstraint,
+      RcLHSAutomaton
+    },
+    VariableInfo
+  }
+};
+
+#[derive(Default)]
+pub struct SharedAutomata {
+  /// Maps the semantic hash of a subpattern to the subpatterns with that hash and their automata. The subpatterns
+  /// are owned by the statements being compiled, which outlive the cache.
+  automata : HashMap<u32, Vec<(*const dyn Term, RcLHSAutomaton)>>,
+  hit_count: usize,
+}
+
+impl SharedAutomata {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The number of subpatterns whose compilation was avoided by reusing an existing automaton.
+  #[inline(always)]
+  pub fn hit_count(&self) -> usize {
+    self.hit_count
+  }
+
+  /// The number of distinct shareable subpatterns compiled so far.
+  pub fn len(&self) -> usize {
+    self.automata.values().map(|entries| entries.len()).sum()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.automata.is_empty()
+  }
+
+  /// Compiles `subpattern` as `Term::compile_lhs` does, reusing a previously compiled automaton for an identical
+  /// subpattern if there is one.
+  pub fn compile_lhs(
+    &mut self,
+    subpattern    : &dyn Term,
+    variable_info : &mut VariableInfo,
+    bound_uniquely: &mut NatSet,
+    constraints   : &mut Vec<EqualityConstraint>
+  ) -> RcLHSAutomaton
+  {
+    if !is_shareable(subpattern, bound_uniquely) {
+      return subpattern.compile_lhs(variable_info, bound_uniquely, constraints, self);
+    }
+
+    let hash = subpattern.semantic_hash();
+    if let Some(entries) = self.automata.get(&hash) {
+      let existing = entries
+          .iter()
+          .find(|(term, _)| compiles_identically(unsafe{ &**term }, subpattern));
+
+      if let Some((_, automaton)) = existing {
+        self.hit_count += 1;
+        bound_uniquely.union_in_place(subpattern.occurs_below());
+        return automaton.clone();
+      }
+    }
+
+    let automaton = subpattern.compile_lhs(variable_info, bound_uniquely, constraints, self);
+    self.automata.entry(hash).or_default().push((subpattern.as_ptr(), automaton.clone()));
+
+    automaton
+  }
+}
+
+/// Variables are cheap to compile and are never shared.
+fn is_shareable(subpattern: &dyn Term, bound_uniquely: &NatSet) -> bool {
+  !subpattern.is_variable()
+      && subpattern.core().save_index == NONE
+      && subpattern.is_linear()
+      && subpattern.occurs_below().is_disjoint(bound_uniquely)
+}
+
+/// Would `first` and `second` compile to the same automaton?
+fn compiles_identically(first: &dyn Term, second: &dyn Term) -> bool {
+  if !core::ptr::addr_eq(first.symbol(), second.symbol()) || first.core().save_index != second.core().save_index {
+    return false;
+  }
+
+  match (first.as_any().downcast_ref::<VariableTerm>(), second.as_any().downcast_ref::<VariableTerm>()) {
+    (Some(first), Some(second)) => {
+      first.index() == second.index()
+          && first.tests().len() == second.tests().len()
+          && first.tests().iter().zip(second.tests()).all(|(a, b)| Rc::ptr_eq(a, b))
+    }
+    (None, None) => {
+      let mut first_args  = first.iter_args();
+      let mut second_args = second.iter_args();
+      loop {
+        match (first_args.next(), second_args.next()) {
+          (Some(first_arg), Some(second_arg)) => {
+            if !compiles_identically(first_arg, second_arg) {
+              return false;
+            }
+          }
+          (None, None) => return true,
+          _ => return false,
+        }
+      }
+    }
+    _ => false
+  }
+}
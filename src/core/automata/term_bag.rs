@@ -0,0 +1,90 @@
+/*!
+
+A `TermBag` records the terms that are available at runtime when the right-hand side of a statement is constructed.
+There are two sources of available terms:
+
+ 1. *Matched terms*: subterms of the left-hand side (and the variables it binds). When the left-hand side matches, the
+    subject subterm matched by a pattern subterm is equal to that pattern subterm's instance, so it can be reused as-is.
+ 2. *Built terms*: subterms of the right-hand side that have already been compiled. Building an identical subterm a
+    second time is wasteful, so the first construction is reused.
+
+Terms are looked up up to equality (`Term::compare`), not identity. Since a lazily evaluated subterm may be left
+unreduced, terms are tracked separately for eager and lazy contexts: a matched term is usable in a lazy context
+regardless, but a term built in a lazy context is not usable in an eager one.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::HashMap,
+  api::term::Term
+};
+
+type TermPtr = *mut dyn Term;
+
+#[derive(Default)]
+struct TermSet(HashMap<u32, Vec<TermPtr>>);
+
+impl TermSet {
+  fn find(&self, term: &dyn Term) -> Option<TermPtr> {
+    self.0
+        .get(&term.semantic_hash())?
+        .iter()
+        .find(|candidate| unsafe{ &**(*candidate) }.compare(term).is_eq())
+        .copied()
+  }
+
+  /// Inserts `term` unless an equal term is already present. Returns `true` if `term` was inserted.
+  fn insert(&mut self, term: TermPtr) -> bool {
+    if self.find(unsafe{ &*term }).is_some() {
+      return false;
+    }
+    self.0.entry(unsafe{ &*term }.semantic_hash()).or_default().push(term);
+    true
+  }
+}
+
+/// The terms whose instances are available for reuse during right-hand side construction. The terms are owned by the
+/// statement being compiled, which outlives the `TermBag`.
+#[derive(Default)]
+pub struct TermBag {
+  terms_usable_in_eager_context: TermSet,
+  terms_usable_in_lazy_context : TermSet,
+}
+
+impl TermBag {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Records a subterm of a pattern. A new matched term never replaces an existing term, because existing built terms
+  /// are available at zero cost and existing matched terms are equally good.
+  pub fn insert_matched_term(&mut self, term: TermPtr, eager_context: bool) {
+    self.terms_usable_in_lazy_context.insert(term);
+    if eager_context {
+      self.terms_usable_in_eager_context.insert(term);
+    }
+  }
+
+  /// Records a subterm that has been compiled for construction. Built terms are only inserted if no equal term was
+  /// available, so the insertion always succeeds.
+  pub fn insert_built_term(&mut self, term: TermPtr, eager_context: bool) {
+    let inserted = if eager_context {
+      self.terms_usable_in_eager_context.insert(term)
+    } else {
+      self.terms_usable_in_lazy_context.insert(term)
+    };
+    debug_assert!(inserted, "built term should not already be available");
+  }
+
+  /// Finds an available term equal to `term`.
+  pub fn find_term(&self, term: &dyn Term, eager_context: bool) -> Option<TermPtr> {
+    if eager_context {
+      self.terms_usable_in_eager_context.find(term)
+    } else {
+      self.terms_usable_in_lazy_context.find(term)
+    }
+  }
+}
@@ -0,0 +1,444 @@
+/*!
+
+Support for terms with binders, for encoding calculi like the lambda calculus.
+
+A symbol with the `Binder` attribute binds the variable in its first argument in its remaining arguments, so that
+`lambda(X, f(X, Y))` binds `X` but not `Y`. Terms that differ only in the names of bound variables are
+*alpha-equivalent*, and this module provides the operations that respect alpha-equivalence:
+
+ - `alpha_equivalent` compares terms up to the renaming of bound variables;
+ - `alpha_hash` hashes terms so that alpha-equivalent terms hash alike, by hashing bound variables by their de Bruijn
+   index rather than their name;
+ - `substitute` replaces the free occurrences of a variable by a term, renaming bound variables where necessary to
+   avoid capturing the free variables of the replacement. The fresh variables are created by `FreshVariables`;
+ - `alpha_equal` compares DAGs up to the renaming of bound variables. Matching compares the bindings of a repeated
+   variable with it, and reduction the two sides of an equality condition, so `eq same(T, T) = yes` applies to
+   `same(lambda(X, X), lambda(Y, Y))`.
+
+Variables are identified by their symbols. A binder is declared with the `binder` attribute, as in
+`op lambda : Var Term -> Term [binder] .`
+
+*/
+
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+
+use crate::{
+  abstractions::{
+    hash::hash2,
+    IString
+  },
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::{Symbol, SymbolAttribute, SymbolPtr},
+    term::{BxTerm, Term},
+    variable_theory::VariableTerm
+  },
+  core::sort::SortPtr
+};
+
+/// Mixed into the hash of a bound variable to set it apart from free variables with the same hash.
+const BOUND_VARIABLE_TAG: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Whether `term` is headed by a binder whose first argument is a variable. Returns the bound variable if so.
+fn bound_variable(term: &dyn Term) -> Option<SymbolPtr> {
+  if term.is_variable() || !term.symbol_ref().attributes.contains(SymbolAttribute::Binder) {
+    return None;
+  }
+
+  term.iter_args().next().filter(|arg| arg.is_variable()).map(|arg| arg.symbol())
+}
+
+/// Whether `a` and `b` are equal up to the renaming of bound variables.
+pub fn alpha_equivalent(a: &dyn Term, b: &dyn Term) -> bool {
+  alpha_equivalent_aux(a, b, &mut Vec::new(), &mut Vec::new())
+}
+
+fn alpha_equivalent_aux(
+  a      : &dyn Term,
+  b      : &dyn Term,
+  bound_a: &mut Vec<SymbolPtr>,
+  bound_b: &mut Vec<SymbolPtr>
+) -> bool
+{
+  match (a.is_variable(), b.is_variable()) {
+    (true, true) => {
+      // Bound variables are equal if they were bound by corresponding binders, free variables if they are the same.
+      let index_a = bound_a.iter().rposition(|&bound| core::ptr::addr_eq(bound, a.symbol()));
+      let index_b = bound_b.iter().rposition(|&bound| core::ptr::addr_eq(bound, b.symbol()));
+      match (index_a, index_b) {
+        (None, None) => core::ptr::addr_eq(a.symbol(), b.symbol()),
+        (index_a, index_b) => index_a == index_b,
+      }
+    }
+
+    (false, false) => {
+      if !core::ptr::addr_eq(a.symbol(), b.symbol()) || a.iter_args().count() != b.iter_args().count() {
+        return false;
+      }
+
+      match (bound_variable(a), bound_variable(b)) {
+        (Some(variable_a), Some(variable_b)) => {
+          bound_a.push(variable_a);
+          bound_b.push(variable_b);
+          let equivalent = a.iter_args()
+                            .zip(b.iter_args())
+                            .skip(1)
+                            .all(|(arg_a, arg_b)| alpha_equivalent_aux(arg_a, arg_b, bound_a, bound_b));
+          bound_a.pop();
+          bound_b.pop();
+          equivalent
+        }
+
+        (None, None) => {
+          a.iter_args()
+           .zip(b.iter_args())
+           .all(|(arg_a, arg_b)| alpha_equivalent_aux(arg_a, arg_b, bound_a, bound_b))
+        }
+
+        _ => false
+      }
+    }
+
+    _ => false
+  }
+}
+
+/// Whether the DAGs `a` and `b` are equal up to the renaming of bound variables
+pub fn alpha_equal(a: DagNodePtr, b: DagNodePtr) -> bool {
+  alpha_equal_aux(a, b, &mut Vec::new(), &mut Vec::new())
+}
+
+/// Like `bound_variable`, for a DAG node
+fn bound_dag_variable(node: &dyn DagNode) -> Option<SymbolPtr> {
+  if !node.symbol_ref().attributes.contains(SymbolAttribute::Binder) {
+    return None;
+  }
+
+  node.iter_args().next().map(|arg| unsafe{ &*arg }.symbol()).filter(|&symbol| unsafe{ &*symbol }.is_variable())
+}
+
+fn alpha_equal_aux(
+  a      : DagNodePtr,
+  b      : DagNodePtr,
+  bound_a: &mut Vec<SymbolPtr>,
+  bound_b: &mut Vec<SymbolPtr>
+) -> bool
+{
+  // A shared node is bound alike on both sides unless a variable is bound by different binders.
+  if core::ptr::addr_eq(a, b) && bound_a == bound_b {
+    return true;
+  }
+
+  let (a_ref, b_ref) = unsafe{ (&*a, &*b) };
+  match (a_ref.symbol_ref().is_variable(), b_ref.symbol_ref().is_variable()) {
+    (true, true) => {
+      let index_a = bound_a.iter().rposition(|&bound| core::ptr::addr_eq(bound, a_ref.symbol()));
+      let index_b = bound_b.iter().rposition(|&bound| core::ptr::addr_eq(bound, b_ref.symbol()));
+      match (index_a, index_b) {
+        (None, None) => core::ptr::addr_eq(a_ref.symbol(), b_ref.symbol()),
+        (index_a, index_b) => index_a == index_b,
+      }
+    }
+
+    (false, false) => {
+      let arity = a_ref.iter_args().count();
+      if arity == 0 || a_ref.symbol_ref() != b_ref.symbol_ref() {
+        return a_ref.equals(b);
+      }
+      if b_ref.iter_args().count() != arity {
+        return false;
+      }
+
+      match (bound_dag_variable(a_ref), bound_dag_variable(b_ref)) {
+        (Some(variable_a), Some(variable_b)) => {
+          bound_a.push(variable_a);
+          bound_b.push(variable_b);
+          let equal = a_ref.iter_args()
+                           .zip(b_ref.iter_args())
+                           .skip(1)
+                           .all(|(arg_a, arg_b)| alpha_equal_aux(arg_a, arg_b, bound_a, bound_b));
+          bound_a.pop();
+          bound_b.pop();
+          equal
+        }
+
+        (None, None) => {
+          a_ref.iter_args()
+               .zip(b_ref.iter_args())
+               .all(|(arg_a, arg_b)| alpha_equal_aux(arg_a, arg_b, bound_a, bound_b))
+        }
+
+        _ => false
+      }
+    }
+
+    _ => false
+  }
+}
+
+/// A hash of `term` that is the same for alpha-equivalent terms. Bound variables are hashed by their de Bruijn index,
+/// the number of binders between the occurrence and its binder.
+pub fn alpha_hash(term: &dyn Term) -> u64 {
+  alpha_hash_aux(term, &mut Vec::new())
+}
+
+fn alpha_hash_aux(term: &dyn Term, bound: &mut Vec<SymbolPtr>) -> u64 {
+  if term.is_variable() {
+    return match bound.iter().rposition(|&variable| core::ptr::addr_eq(variable, term.symbol())) {
+      Some(index) => hash2(BOUND_VARIABLE_TAG, (bound.len() - 1 - index) as u64),
+      None        => term.symbol_ref().hash_value as u64,
+    };
+  }
+
+  let mut hash  = term.symbol_ref().hash_value as u64;
+  let binder    = bound_variable(term);
+  let mut args  = term.iter_args();
+  if let Some(variable) = binder {
+    // The bound variable's name is irrelevant.
+    args.next();
+    bound.push(variable);
+  }
+  for arg in args {
+    hash = hash2(hash, alpha_hash_aux(arg, bound));
+  }
+  if binder.is_some() {
+    bound.pop();
+  }
+
+  hash
+}
+
+/// The variables with free occurrences in `term`, each once, in the order they first occur
+pub fn free_variables(term: &dyn Term) -> Vec<SymbolPtr> {
+  fn collect(term: &dyn Term, bound: &mut Vec<SymbolPtr>, free: &mut Vec<SymbolPtr>) {
+    if term.is_variable() {
+      let symbol = term.symbol();
+      let known  = |variables: &Vec<SymbolPtr>| variables.iter().any(|&variable| core::ptr::addr_eq(variable, symbol));
+      if !known(bound) && !known(free) {
+        free.push(symbol);
+      }
+      return;
+    }
+
+    let binder   = bound_variable(term);
+    let mut args = term.iter_args();
+    if let Some(variable) = binder {
+      args.next();
+      bound.push(variable);
+    }
+    for arg in args {
+      collect(arg, bound, free);
+    }
+    if binder.is_some() {
+      bound.pop();
+    }
+  }
+
+  let mut free = Vec::new();
+  collect(term, &mut Vec::new(), &mut free);
+  free
+}
+
+/// Creates variables that are distinct from every existing variable, for renaming bound variables and for the
+/// variables unification and generalization introduce. The variables' symbols are owned by the `FreshVariables`, which
+/// must outlive every term that uses them.
+#[derive(Default)]
+#[allow(clippy::vec_box)]
+pub struct FreshVariables {
+  symbols: Vec<Box<Symbol>>,
+}
+
+impl FreshVariables {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// A new variable named after `base`. The name is only for display; the variable is distinct from every other
+  /// variable even if the names agree.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn fresh(&mut self, base: SymbolPtr) -> SymbolPtr {
+    self.create(format!("{}#{}", unsafe{ &*base }.name, self.symbols.len() + 1), None)
+  }
+
+  /// A new variable named `#n`, for a variable that stands for no variable in particular
+  pub fn anonymous(&mut self) -> SymbolPtr {
+    self.create(format!("#{}", self.symbols.len() + 1), None)
+  }
+
+  /// A new variable named `#n` of sort `sort`
+  pub fn anonymous_sorted(&mut self, sort: SortPtr) -> SymbolPtr {
+    self.create(format!("#{}", self.symbols.len() + 1), Some(sort))
+  }
+
+  fn create(&mut self, name: String, sort: Option<SortPtr>) -> SymbolPtr {
+    let name       = IString::from(name);
+    let mut symbol = Box::new(
+      match sort {
+        Some(sort) => Symbol::new_sorted_variable(name, sort),
+        None       => Symbol::new_variable(name),
+      }
+    );
+    let pointer: SymbolPtr = symbol.as_mut();
+    self.symbols.push(symbol);
+
+    pointer
+  }
+
+  /// The number of variables created
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.symbols.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.symbols.is_empty()
+  }
+}
+
+/// Replaces the free occurrences of `variable` in `term` by copies of `replacement`. Bound variables of `term` that
+/// would capture a free variable of `replacement` are renamed to variables created by `fresh`.
+pub fn substitute(term: &dyn Term, variable: SymbolPtr, replacement: &dyn Term, fresh: &mut FreshVariables) -> BxTerm {
+  substitute_aux(term, variable, replacement, &free_variables(replacement), fresh)
+}
+
+fn substitute_aux(
+  term           : &dyn Term,
+  variable       : SymbolPtr,
+  replacement    : &dyn Term,
+  replacement_fvs: &[SymbolPtr],
+  fresh          : &mut FreshVariables
+) -> BxTerm
+{
+  if term.is_variable() {
+    return match core::ptr::addr_eq(term.symbol(), variable) {
+      true  => replacement.deep_copy(),
+      false => term.deep_copy(),
+    };
+  }
+
+  let Some(bound) = bound_variable(term) else {
+    let args = term.iter_args()
+                   .map(|arg| substitute_aux(arg, variable, replacement, replacement_fvs, fresh))
+                   .collect();
+    return term.with_new_args(args);
+  };
+
+  let mut args   = term.iter_args();
+  let bound_term = args.next().unwrap();
+  let body: Vec<&dyn Term> = args.collect();
+
+  // The binder shadows `variable`, so there is nothing to replace below it.
+  let shadowed = core::ptr::addr_eq(bound, variable);
+  let occurs   = !shadowed
+      && body.iter().any(|arg| free_variables(*arg).iter().any(|&free| core::ptr::addr_eq(free, variable)));
+  if !occurs {
+    return term.deep_copy();
+  }
+
+  let captures = replacement_fvs.iter().any(|&free| core::ptr::addr_eq(free, bound));
+  if !captures {
+    let mut args = vec![bound_term.deep_copy()];
+    args.extend(body.iter().map(|arg| substitute_aux(*arg, variable, replacement, replacement_fvs, fresh)));
+    return term.with_new_args(args);
+  }
+
+  // Rename the bound variable before substituting.
+  let renamed      = fresh.fresh(bound);
+  let renamed_term = VariableTerm::new(renamed);
+  let mut args: Vec<BxTerm> = vec![Box::new(VariableTerm::new(renamed))];
+  for arg in body {
+    let arg = substitute_aux(arg, bound, &renamed_term, &[renamed], fresh);
+    args.push(substitute_aux(arg.as_ref(), variable, replacement, replacement_fvs, fresh));
+  }
+
+  term.with_new_args(args)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      free_theory::FreeTerm,
+      symbol::{Symbol, SymbolAttribute, SymbolPtr},
+      term::BxTerm,
+      variable_theory::VariableTerm
+    },
+    core::{
+      binders::{alpha_equivalent, alpha_hash, free_variables, substitute, FreshVariables},
+      parser::{parse_module, parse_term},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext
+    }
+  };
+
+  #[test]
+  fn alpha_equivalence_and_capture_avoiding_substitution() {
+    let mut lambda = Symbol::new(IString::from("lambda"), Arity::Value(2));
+    lambda.attributes = SymbolAttribute::Binder.into();
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let mut y = Symbol::new_variable(IString::from("Y"));
+    let mut z = Symbol::new_variable(IString::from("Z"));
+    let (lambda, f, x, y, z): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut lambda, &mut f, &mut x, &mut y, &mut z);
+
+    let var = |symbol| -> BxTerm { Box::new(VariableTerm::new(symbol)) };
+    // lambda(B, f(B, F))
+    let abstraction = |bound, free| {
+      FreeTerm::with_args(lambda, vec![var(bound), FreeTerm::with_args(f, vec![var(bound), var(free)])])
+    };
+
+    // lambda(X, f(X, Y)) ~ lambda(Z, f(Z, Y)), but not lambda(Y, f(Y, Y)) or lambda(X, f(X, Z))
+    let term = abstraction(x, y);
+    assert!(alpha_equivalent(term.as_ref(), abstraction(z, y).as_ref()));
+    assert!(!alpha_equivalent(term.as_ref(), abstraction(y, y).as_ref()));
+    assert!(!alpha_equivalent(term.as_ref(), abstraction(x, z).as_ref()));
+    assert_eq!(alpha_hash(term.as_ref()), alpha_hash(abstraction(z, y).as_ref()));
+    assert_ne!(alpha_hash(term.as_ref()), alpha_hash(abstraction(y, y).as_ref()));
+    assert_eq!(free_variables(term.as_ref()), vec![y]);
+
+    // lambda(X, f(X, Y))[Y := X] renames the bound X to avoid capturing the free X: lambda(X#1, f(X#1, X)).
+    let mut fresh  = FreshVariables::new();
+    let substituted = substitute(term.as_ref(), y, var(x).as_ref(), &mut fresh);
+    assert_eq!(fresh.len(), 1);
+    assert!(alpha_equivalent(substituted.as_ref(), abstraction(z, x).as_ref()));
+    assert!(!alpha_equivalent(substituted.as_ref(), abstraction(x, x).as_ref()));
+    assert_eq!(free_variables(substituted.as_ref()), vec![x]);
+
+    // Bound occurrences are not replaced: lambda(X, f(X, Y))[X := Z] = lambda(X, f(X, Y))
+    let substituted = substitute(term.as_ref(), x, var(z).as_ref(), &mut fresh);
+    assert!(alpha_equivalent(substituted.as_ref(), term.as_ref()));
+    assert_eq!(fresh.len(), 1);
+  }
+
+  #[test]
+  fn equations_match_alpha_renamed_subjects() {
+    let module = parse_module("
+      mod LAMBDA is
+        sorts Term Answer .
+        op lambda : Term Term -> Term [binder] .
+        op app : Term Term -> Term .
+        op a : -> Term .
+        op same : Term Term -> Answer .
+        op yes : -> Answer .
+        vars T X Y : Term .
+        eq same(T, T) = yes .
+      endm
+    ").unwrap();
+    let reduce = |term: &str| {
+      let subject     = parse_term(&module, term).unwrap().term_to_dag(false);
+      let mut context = RewritingContext::new(&module);
+      dag_to_string(context.reduce(subject))
+    };
+
+    assert_eq!(reduce("same(lambda(X, app(X, a)), lambda(Y, app(Y, a)))"), "yes");
+    // The second `X` is free, so the terms are not alpha-equivalent.
+    let unequal = "same(lambda(X, app(X, a)), lambda(Y, app(X, a)))";
+    assert_eq!(reduce(unequal), unequal);
+  }
+}
@@ -0,0 +1,302 @@
+/*!
+
+Canonical forms of terms modulo the structural axioms of their symbols—associativity, commutativity, idempotency, and
+identity—without applying any equations. Two terms that are equal modulo the axioms have the same canonical form, so
+canonical forms can be hashed, deduplicated, or handed to tools that know nothing about the axioms.
+
+Canonicalization works bottom up. At a symbol `f` with axioms,
+
+ 1. if `f` is associative, nested `f`-subterms are flattened into a single list of arguments;
+ 2. identity elements are removed, from any position if `f` is commutative or has both a left and a right identity,
+    otherwise only from positions where they are absorbed by a neighbor on the appropriate side;
+ 3. if `f` is commutative, the arguments are sorted by `Term::compare`;
+ 4. if `f` is idempotent, adjacent equal arguments are merged;
+ 5. the arguments are rebuilt, nesting to the right if `f` is associative. A single remaining argument replaces the
+    whole subterm, and no remaining arguments leave the identity element.
+
+Identity elements are constants, and are looked up in `Module::identities`.
+
+The order used for sorting depends on the order in which symbols were created, so canonical forms are canonical within
+a process but are not stable across processes.
+
+`canonical_node` takes the same steps for a single DAG node whose arguments are already canonical. The rewriting
+engine builds the instances of condition terms with it, and puts each node in canonical form once its arguments are
+reduced, so that a normal form never holds, say, an identity element its symbol absorbs, and equal normal forms modulo
+the axioms are equal.
+
+*/
+
+use core::cmp::Ordering;
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use crate::{
+  api::{
+    dag_node::DagNodePtr,
+    free_theory::{FreeDagNode, FreeTerm},
+    symbol::{SymbolAttribute, SymbolAttributes, SymbolPtr},
+    term::{BxTerm, Term},
+    variable_theory::VariableTerm
+  },
+  core::{
+    dag_node_core::DagNodeTheory,
+    module::Module
+  }
+};
+
+/// Returns the canonical form of `term` modulo the structural axioms of its symbols. Identity elements are taken from
+/// `module`.
+pub fn canonicalize(term: &dyn Term, module: &Module) -> BxTerm {
+  if term.is_variable() {
+    return Box::new(VariableTerm::new(term.symbol()));
+  }
+
+  let symbol     = term.symbol();
+  let attributes = term.symbol_ref().attributes;
+  let mut args: Vec<BxTerm> = term.iter_args().map(|arg| canonicalize(arg, module)).collect();
+
+  if !attributes.intersects(SymbolAttribute::Axioms) || args.len() < 2 {
+    return term.with_new_args(args);
+  }
+
+  let associative = attributes.contains(SymbolAttribute::Associative);
+  let commutative = attributes.contains(SymbolAttribute::Commutative);
+
+  if associative {
+    args = flatten(symbol, args);
+  }
+
+  let identity = module.identities.get(&symbol).copied();
+  if let Some(identity) = identity {
+    remove_identities(&mut args, attributes, |arg| is_constant(arg.as_ref(), identity));
+  }
+
+  if commutative {
+    args.sort_by(|a, b| a.compare(b.as_ref()));
+  }
+
+  if attributes.contains(SymbolAttribute::Idempotent) {
+    args.dedup_by(|a, b| a.compare(b.as_ref()) == Ordering::Equal);
+  }
+
+  match args.len() {
+    0 => FreeTerm::with_args(identity.expect("all arguments removed without an identity"), vec![]),
+    1 => args.pop().unwrap(),
+    _ if associative => nest_right(symbol, args),
+    _ => term.with_new_args(args)
+  }
+}
+
+/// The node `symbol(args)` in canonical form modulo the structural axioms of `symbol`, where `args` are already
+/// canonical. Identity elements are taken from `module`. See the module documentation.
+pub fn canonical_node(symbol: SymbolPtr, mut args: Vec<DagNodePtr>, module: &Module) -> DagNodePtr {
+  let attributes = unsafe{ &*symbol }.attributes;
+  if !attributes.intersects(SymbolAttribute::Axioms) || args.len() < 2 {
+    return FreeDagNode::with_args(symbol, &mut args);
+  }
+
+  let associative = attributes.contains(SymbolAttribute::Associative);
+  let commutative = attributes.contains(SymbolAttribute::Commutative);
+
+  if associative {
+    args = flatten_node(symbol, args);
+  }
+
+  let identity = module.identities.get(&symbol).copied();
+  if let Some(identity) = identity {
+    remove_identities(&mut args, attributes, |&arg| is_constant_node(arg, identity));
+  }
+
+  if commutative {
+    args.sort_by(|&a, &b| unsafe{ &*a }.compare(b));
+  }
+
+  if attributes.contains(SymbolAttribute::Idempotent) {
+    args.dedup_by(|&mut a, &mut b| core::ptr::addr_eq(a, b) || unsafe{ &*a }.equals(b));
+  }
+
+  match args.len() {
+    0 => FreeDagNode::with_args(identity.expect("all arguments removed without an identity"), &mut vec![]),
+    1 => args.pop().unwrap(),
+    _ if associative => {
+      let mut result = args.pop().unwrap();
+      while let Some(arg) = args.pop() {
+        result = FreeDagNode::with_args(symbol, &mut vec![arg, result]);
+      }
+      result
+    }
+    _ => FreeDagNode::with_args(symbol, &mut args)
+  }
+}
+
+/// Removes the identity elements among `args` that the symbol with `attributes` absorbs, from any position if the
+/// symbol is commutative or has both a left and a right identity.
+fn remove_identities<T>(args: &mut Vec<T>, attributes: SymbolAttributes, is_identity: impl Fn(&T) -> bool) {
+  let either_side = attributes.contains(SymbolAttribute::Commutative)
+      || attributes.contains(SymbolAttribute::LeftIdentity | SymbolAttribute::RightIdentity);
+  let left        = either_side || attributes.contains(SymbolAttribute::LeftIdentity);
+  let right       = either_side || attributes.contains(SymbolAttribute::RightIdentity);
+  let last        = args.len() - 1;
+  // A left identity is absorbed by the argument to its right, and a right identity by the argument to its left.
+  let mut index = 0;
+  args.retain(
+    |arg| {
+      let removable = is_identity(arg) && ((left && index < last) || (right && index > 0));
+      index += 1;
+      !removable
+    }
+  );
+}
+
+#[inline(always)]
+fn is_constant_node(node: DagNodePtr, constant: SymbolPtr) -> bool {
+  let node_ref = unsafe{ &*node };
+  core::ptr::addr_eq(node_ref.symbol(), constant) && node_ref.is_empty()
+}
+
+/// The DAG analogue of `flatten`.
+fn flatten_node(symbol: SymbolPtr, args: Vec<DagNodePtr>) -> Vec<DagNodePtr> {
+  let mut flattened = Vec::with_capacity(args.len());
+  let mut pending   = args;
+  pending.reverse();
+
+  while let Some(arg) = pending.pop() {
+    let arg_ref = unsafe{ &*arg };
+    if arg_ref.core().theory() == DagNodeTheory::Free && core::ptr::addr_eq(arg_ref.symbol(), symbol) {
+      pending.extend(arg_ref.iter_args().collect::<Vec<_>>().into_iter().rev());
+    } else {
+      flattened.push(arg);
+    }
+  }
+
+  flattened
+}
+
+#[inline(always)]
+fn is_constant(term: &dyn Term, constant: SymbolPtr) -> bool {
+  core::ptr::addr_eq(term.symbol(), constant) && term.iter_args().next().is_none()
+}
+
+/// Replaces arguments headed by `symbol`, which are already canonical and therefore nested to the right, by their
+/// arguments.
+fn flatten(symbol: SymbolPtr, args: Vec<BxTerm>) -> Vec<BxTerm> {
+  let mut flattened = Vec::with_capacity(args.len());
+  let mut pending   = args;
+  pending.reverse();
+
+  while let Some(mut arg) = pending.pop() {
+    if !arg.is_variable() && core::ptr::addr_eq(arg.symbol(), symbol) {
+      let nested = arg.as_any_mut().downcast_mut::<FreeTerm>().expect("associative symbol outside the free theory");
+      pending.extend(core::mem::take(&mut nested.args).into_iter().rev());
+    } else {
+      flattened.push(arg);
+    }
+  }
+
+  flattened
+}
+
+/// Builds `f(a₁, f(a₂, … f(aₙ₋₁, aₙ)))`.
+fn nest_right(symbol: SymbolPtr, mut args: Vec<BxTerm>) -> BxTerm {
+  let mut result = args.pop().unwrap();
+  while let Some(arg) = args.pop() {
+    result = FreeTerm::with_args(symbol, vec![arg, result]);
+  }
+
+  result
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::cmp::Ordering;
+
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      free_theory::FreeTerm,
+      symbol::{Symbol, SymbolAttribute, SymbolPtr},
+      term::BxTerm,
+      variable_theory::VariableTerm
+    },
+    core::{
+      canonicalize::canonicalize,
+      module::Module
+    }
+  };
+
+  fn assert_same(a: &BxTerm, b: &BxTerm, module: &Module) {
+    let (a, b) = (canonicalize(a.as_ref(), module), canonicalize(b.as_ref(), module));
+    assert_eq!(a.compare(b.as_ref()), Ordering::Equal, "{} != {}", a, b);
+  }
+
+  #[test]
+  fn equal_modulo_axioms_means_equal_canonical_forms() {
+    // An ACI union with identity `empty`, and a free symbol `g`
+    let mut union = Symbol::new(IString::from("union"), Arity::Value(2));
+    union.attributes = SymbolAttribute::Associative | SymbolAttribute::Commutative | SymbolAttribute::Idempotent
+        | SymbolAttribute::LeftIdentity | SymbolAttribute::RightIdentity;
+    let mut empty = Symbol::new(IString::from("empty"), Arity::Value(0));
+    let mut g     = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut a     = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b     = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x     = Symbol::new_variable(IString::from("X"));
+    let (union, empty, g, a, b, x): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut union, &mut empty, &mut g, &mut a, &mut b, &mut x);
+
+    let mut module = Module::default();
+    module.identities.insert(union, empty);
+    let constant = |symbol| FreeTerm::with_args(symbol, vec![]);
+    let variable = || -> BxTerm { Box::new(VariableTerm::new(x)) };
+    let union_of = |left, right| FreeTerm::with_args(union, vec![left, right]);
+
+    // union(union(a, X), b) = union(b, union(X, a))
+    assert_same(
+      &union_of(union_of(constant(a), variable()), constant(b)),
+      &union_of(constant(b), union_of(variable(), constant(a))),
+      &module
+    );
+
+    // g(union(a, union(empty, a))) = g(a)
+    assert_same(
+      &FreeTerm::with_args(g, vec![union_of(constant(a), union_of(constant(empty), constant(a)))]),
+      &FreeTerm::with_args(g, vec![constant(a)]),
+      &module
+    );
+
+    // union(empty, empty) = empty
+    assert_same(
+      &union_of(constant(empty), constant(empty)),
+      &constant(empty),
+      &module
+    );
+
+    // Canonical forms respect the arity of the symbol.
+    let canonical = canonicalize(union_of(constant(b), union_of(constant(a), variable())).as_ref(), &module);
+    assert_eq!(canonical.iter_args().count(), 2);
+  }
+
+  #[test]
+  fn free_and_associative_symbols_keep_their_argument_order() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut h = Symbol::new(IString::from("h"), Arity::Value(2));
+    h.attributes = SymbolAttribute::Associative.into();
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+    let (f, h, a, b): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut h, &mut a, &mut b);
+    let module = Module::default();
+
+    let constant = |symbol| FreeTerm::with_args(symbol, vec![]);
+    let h_of     = |left, right| FreeTerm::with_args(h, vec![left, right]);
+
+    let term = FreeTerm::with_args(f, vec![constant(b), constant(a)]);
+    assert_eq!(canonicalize(term.as_ref(), &module).compare(term.as_ref()), Ordering::Equal);
+
+    // h(h(b, a), b) = h(b, h(a, b))
+    assert_same(
+      &h_of(h_of(constant(b), constant(a)), constant(b)),
+      &h_of(constant(b), h_of(constant(a), constant(b))),
+      &module
+    );
+  }
+}
@@ -0,0 +1,197 @@
+/*!
+
+Collapse analysis of patterns. A symbol `f` with an identity element `e` makes terms *collapse*: modulo the identity
+axioms, `f(X, e)` is `X`. So, for a right identity `0`, the pattern `f(X, 0)` matches every subject, with `X` bound to
+the subject, and the pattern `f(X, a)` matches `a` if `f` has a left identity, with `X` bound to `0`. An idempotent
+symbol makes terms collapse too: `f(X, X)` is `X`, so `f(X, a)` matches `a` with `X` bound to `a`.
+
+`analyse_collapses` finds, for each subterm of a pattern with a top symbol with an identity, the arguments it can
+collapse to, that is, the arguments that can remain once every other argument is instantiated to the identity. An
+argument can be instantiated to the identity if it is a variable, the identity itself, or a subterm that can collapse to
+one of those. These *survivors* are recorded in the subterm's `Collapse`, and the symbols that can be on top of an
+instance of the subterm once it has collapsed are recorded in its `collapse_symbols`. The idempotent collapse of
+`f(p, q)` is recorded if `p` and `q` can have a common instance, as far as their top symbols tell.
+
+As in `canonicalize`, the identity of a commutative symbol is an identity on both sides. The analysis is conservative:
+it may record a collapse that no instance of the pattern has, which costs a failed match attempt, but it never misses
+one. A compiled pattern tries the collapses of a subterm when the subterm fails to match structurally. See
+`CollapseLHSAutomaton`.
+
+Subterms with an associative top symbol are matched modulo associativity instead, and their automata make their
+collapses themselves, by letting arguments that can be the identity match nothing (see `extension`).
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::HashMap,
+  api::{
+    symbol::{SymbolAttribute, SymbolPtr, SymbolSet},
+    term::Term
+  }
+};
+
+/// The ways a subterm can collapse. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Collapse {
+  /// The identity of the subterm's top symbol, if it has one
+  pub identity  : Option<SymbolPtr>,
+  /// The indices of the arguments the subterm can collapse to when the others are the identity
+  pub survivors : Vec<usize>,
+  /// Whether the subterm's top symbol is idempotent and its arguments can be instances of the same term
+  pub idempotent: bool,
+}
+
+/// Records the collapses of every subterm of `term`, bottom up, with the identities of the symbols taken from
+/// `identities`.
+pub fn analyse_collapses(term: &mut dyn Term, identities: &HashMap<SymbolPtr, SymbolPtr>) {
+  if term.is_variable() {
+    return;
+  }
+  for arg in term.args_mut() {
+    analyse_collapses(arg.as_mut(), identities);
+  }
+
+  let core = term.core_mut();
+  core.collapse = None;
+  core.collapse_symbols.clear();
+
+  let args: Vec<&dyn Term> = term.iter_args().collect();
+  let attributes = term.symbol_ref().attributes;
+  let identity   = identities.get(&term.symbol()).copied();
+  if args.len() != 2 || (identity.is_none() && !attributes.contains(SymbolAttribute::Idempotent)) {
+    return;
+  }
+
+  let mut collapse_symbols = SymbolSet::default();
+  let mut survivors        = Vec::new();
+  if let Some(identity) = identity {
+    let either_side = attributes.contains(SymbolAttribute::Commutative);
+    let left        = either_side || attributes.contains(SymbolAttribute::LeftIdentity);
+    let right       = either_side || attributes.contains(SymbolAttribute::RightIdentity);
+
+    // A left identity leaves the argument to its right, and a right identity the argument to its left.
+    if right && can_be_identity(args[1], identity) {
+      survivors.push(0);
+    }
+    if left && can_be_identity(args[0], identity) {
+      survivors.push(1);
+    }
+    for &survivor in survivors.iter() {
+      if !can_be_anything(args[survivor]) {
+        collapse_symbols.extend(possible_symbols(args[survivor]));
+      }
+    }
+  }
+
+  // An idempotent `f(p, q)` collapses to the common instances of `p` and `q`.
+  let mut idempotent = attributes.contains(SymbolAttribute::Idempotent);
+  if idempotent {
+    match (can_be_anything(args[0]), can_be_anything(args[1])) {
+      (true, true)   => {}
+      (true, false)  => collapse_symbols.extend(possible_symbols(args[1])),
+      (false, true)  => collapse_symbols.extend(possible_symbols(args[0])),
+      (false, false) => {
+        let common: SymbolSet = possible_symbols(args[0]).intersection(&possible_symbols(args[1])).copied().collect();
+        idempotent = !common.is_empty();
+        collapse_symbols.extend(common);
+      }
+    }
+  }
+
+  if survivors.is_empty() && !idempotent {
+    return;
+  }
+  let core = term.core_mut();
+  core.collapse_symbols = collapse_symbols;
+  core.collapse         = Some(Collapse { identity, survivors, idempotent });
+}
+
+/// Whether `term` has a collapse recorded by `analyse_collapses`
+#[inline(always)]
+pub fn can_collapse(term: &dyn Term) -> bool {
+  !term.is_variable() && term.core().collapse.is_some()
+}
+
+/// Whether an instance of `term` can collapse to a bare variable, and so can be anything at all
+pub fn can_collapse_to_variable(term: &dyn Term) -> bool {
+  if term.is_variable() {
+    return false;
+  }
+  let Some(collapse) = &term.core().collapse else {
+    return false;
+  };
+
+  let args: Vec<&dyn Term> = term.iter_args().collect();
+  collapse.survivors.iter().any(|&survivor| can_be_anything(args[survivor]))
+      || (collapse.idempotent && args.iter().all(|&arg| can_be_anything(arg)))
+}
+
+/// Whether an instance of `term` can have any top symbol
+#[inline(always)]
+fn can_be_anything(term: &dyn Term) -> bool {
+  term.is_variable() || can_collapse_to_variable(term)
+}
+
+/// The top symbols an instance of `term`, which can't be anything, can have
+fn possible_symbols(term: &dyn Term) -> SymbolSet {
+  let mut symbols = term.collapse_symbols().clone();
+  symbols.insert(term.symbol());
+  symbols
+}
+
+/// Whether an instance of `term` can be the constant `identity`
+pub(crate) fn can_be_identity(term: &dyn Term, identity: SymbolPtr) -> bool {
+  can_be_anything(term)
+      || (core::ptr::addr_eq(term.symbol(), identity) && term.iter_args().next().is_none())
+      || term.collapse_symbols().contains(&identity)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    api::symbol::SymbolPtr,
+    core::{
+      collapse::{can_collapse_to_variable, Collapse},
+      parser::{parse_module, parse_term},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext
+    }
+  };
+
+  const MODULE: &str = "
+    mod IDENTITY is
+      sort Nat .
+      ops 0 a b c : -> Nat .
+      op plus : Nat Nat -> Nat [id: 0] .
+      op g : Nat -> Nat .
+      var X : Nat .
+      eq g(plus(X, a)) = X .
+      eq plus(X, b) = c .
+    endm
+  ";
+
+  #[test]
+  fn collapses_are_found_and_matched() {
+    let module = parse_module(MODULE).unwrap();
+    let plus   = module.arena.symbol(&"plus".into()).unwrap();
+    let a      = module.arena.symbol(&"a".into()).unwrap();
+    let zero   = module.arena.symbol(&"0".into()).unwrap();
+
+    // plus(X, a) can only collapse to `a`, with `X` the identity.
+    let lhs  = module.equations[0].lhs_term.iter_args().next().unwrap();
+    let core = lhs.core();
+    assert_eq!(core.collapse, Some(Collapse { identity: Some(zero), survivors: vec![1], idempotent: false }));
+    assert_eq!(core.collapse_symbols.iter().copied().collect::<Vec<SymbolPtr>>(), vec![a]);
+    assert!(std::ptr::addr_eq(lhs.symbol(), plus));
+    assert!(!can_collapse_to_variable(lhs));
+
+    let mut context = RewritingContext::new(&module);
+    for (subject, expected) in [("g(a)", "0"), ("b", "c"), ("g(plus(b, a))", "c"), ("g(b)", "g(c)")] {
+      let term = parse_term(&module, subject).unwrap();
+      assert_eq!(dag_to_string(context.reduce(term.term_to_dag(false))), expected, "reducing {}", subject);
+    }
+  }
+}
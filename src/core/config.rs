@@ -0,0 +1,246 @@
+/*!
+
+An `EngineConfig` gathers the engine's switches in one place: the logging threshold, whether collections are reported,
+whether the build is deterministic, the depth of statement indices, the bound on duplications when matching modulo
+idempotency, the fuel for evaluating conditions, the limits on associative matches, whether sessions profile their
+statements, and the backend and tuning of the node allocator. A configuration is applied to the engine as a whole with
+`ThreadBoundEngine::configure`, and to a single reduction session with `RewritingContext::with_config`.
+
+```ignore
+let config = EngineConfig::default().with_report_gc(false).with_logging_threshold(1);
+let engine = ThreadBoundEngine::acquire()?;
+engine.configure(&config)?;
+let mut context = RewritingContext::with_config(&module, &config);
+```
+
+The logger, the allocators, and the duplication bound are shared by the whole process, so the settings for them stay in
+effect until the next configuration is applied. Everything else is read when a session is created, so sessions with
+different configurations can coexist.
+
+Determinism is chosen at compile time with the `deterministic` feature. A configuration asking for it is rejected by a
+build without the feature, rather than silently running nondeterministically.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+
+use crate::{
+  abstractions::{log::get_global_logging_threshold, DETERMINISTIC},
+  core::{
+    automata::duplication::DEFAULT_MAX_DUPLICATIONS,
+    allocator::{
+      AllocatorBackend,
+      ARENA_SIZE,
+      BIG_MODEL_SLOP,
+      LOWER_BOUND,
+      RESERVE_SIZE,
+      SMALL_MODEL_SLOP,
+      UPPER_BOUND
+    },
+    match_limits::MatchLimits,
+    rewriting_context::DEFAULT_CONDITION_FUEL,
+    statement_index::DEFAULT_INDEX_DEPTH
+  }
+};
+
+/// How the node allocator decides how many arenas to keep. After a collection, the allocator allocates arenas until its
+/// capacity is a *slop factor* times the number of live nodes. The slop factor is `small_model_slop` below
+/// `small_model_bound` live nodes, `big_model_slop` above `big_model_bound`, and interpolated linearly in between.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AllocatorConfig {
+  /// Where nodes are allocated, which can only be changed before the first node is. See `allocator::backend`.
+  pub backend          : AllocatorBackend,
+  pub small_model_slop : f64,
+  pub big_model_slop   : f64,
+  pub small_model_bound: usize,
+  pub big_model_bound  : usize,
+  /// The number of nodes at the end of the last arena kept in reserve, so that a collection can be put off until it is
+  /// safe to collect
+  pub reserve_size     : usize,
+}
+
+impl Default for AllocatorConfig {
+  fn default() -> Self {
+    AllocatorConfig {
+      backend          : AllocatorBackend::default(),
+      small_model_slop : SMALL_MODEL_SLOP,
+      big_model_slop   : BIG_MODEL_SLOP,
+      small_model_bound: LOWER_BOUND,
+      big_model_bound  : UPPER_BOUND,
+      reserve_size     : RESERVE_SIZE,
+    }
+  }
+}
+
+/// The engine's switches. See the module documentation.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct EngineConfig {
+  /// Messages at or below this level are logged.
+  pub logging_threshold: u8,
+  /// Whether the allocators report each collection
+  pub report_gc        : bool,
+  /// Whether the engine must be deterministic, which needs the `deterministic` feature
+  pub deterministic    : bool,
+  /// The number of levels below the top symbol that statement indices look at
+  pub index_depth      : usize,
+  /// The number of duplications a match modulo idempotency may make. See `automata::duplication`.
+  pub max_duplications : usize,
+  /// The number of rewrites the evaluation of a statement's conditions may take. See `rewriting_context`.
+  pub condition_fuel   : usize,
+  /// The limits on the subproblems and alternatives of each associative match of a session. See `match_limits`.
+  pub match_limits     : MatchLimits,
+  /// Whether sessions count how often each statement fires. See `coverage`.
+  pub profile          : bool,
+  pub allocator        : AllocatorConfig,
+}
+
+impl Default for EngineConfig {
+  /// The settings the engine has when it isn't configured
+  fn default() -> Self {
+    EngineConfig {
+      logging_threshold: get_global_logging_threshold(),
+      report_gc        : true,
+      deterministic    : DETERMINISTIC,
+      index_depth      : DEFAULT_INDEX_DEPTH,
+      max_duplications : DEFAULT_MAX_DUPLICATIONS,
+      condition_fuel   : DEFAULT_CONDITION_FUEL,
+      match_limits     : MatchLimits::default(),
+      profile          : false,
+      allocator        : AllocatorConfig::default(),
+    }
+  }
+}
+
+impl EngineConfig {
+  pub fn with_logging_threshold(mut self, logging_threshold: u8) -> Self {
+    self.logging_threshold = logging_threshold;
+    self
+  }
+
+  pub fn with_report_gc(mut self, report_gc: bool) -> Self {
+    self.report_gc = report_gc;
+    self
+  }
+
+  pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+    self.deterministic = deterministic;
+    self
+  }
+
+  pub fn with_index_depth(mut self, index_depth: usize) -> Self {
+    self.index_depth = index_depth;
+    self
+  }
+
+  pub fn with_max_duplications(mut self, max_duplications: usize) -> Self {
+    self.max_duplications = max_duplications;
+    self
+  }
+
+  pub fn with_condition_fuel(mut self, condition_fuel: usize) -> Self {
+    self.condition_fuel = condition_fuel;
+    self
+  }
+
+  pub fn with_match_limits(mut self, match_limits: MatchLimits) -> Self {
+    self.match_limits = match_limits;
+    self
+  }
+
+  pub fn with_profile(mut self, profile: bool) -> Self {
+    self.profile = profile;
+    self
+  }
+
+  pub fn with_allocator(mut self, allocator: AllocatorConfig) -> Self {
+    self.allocator = allocator;
+    self
+  }
+
+  /// Checks that the configuration can be applied to this build.
+  pub fn validate(&self) -> Result<(), ConfigError> {
+    if self.deterministic && !DETERMINISTIC {
+      return Err(ConfigError::DeterminismUnavailable);
+    }
+
+    let allocator = &self.allocator;
+    if allocator.small_model_slop < 1.0 || allocator.big_model_slop < 1.0 {
+      return Err(ConfigError::InvalidAllocator("slop factors must be at least 1"));
+    }
+    if allocator.small_model_bound >= allocator.big_model_bound {
+      return Err(ConfigError::InvalidAllocator("the small model bound must be below the big model bound"));
+    }
+    if allocator.reserve_size >= ARENA_SIZE {
+      return Err(ConfigError::InvalidAllocator("the reserve must be smaller than an arena"));
+    }
+
+    Ok(())
+  }
+}
+
+/// Returned for a configuration that can't be applied.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ConfigError {
+  /// Determinism was asked for, but the engine was built without the `deterministic` feature.
+  DeterminismUnavailable,
+  /// The allocator tuning is inconsistent.
+  InvalidAllocator(&'static str),
+  /// A different allocator backend was asked for, but nodes have already been allocated.
+  AllocatorInUse,
+}
+
+impl Display for ConfigError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      ConfigError::DeterminismUnavailable => {
+        write!(f, "determinism needs the engine to be built with the `deterministic` feature")
+      }
+      ConfigError::InvalidAllocator(problem) => write!(f, "invalid allocator configuration: {}", problem),
+      ConfigError::AllocatorInUse => {
+        write!(f, "the allocator backend can't be changed once nodes have been allocated")
+      }
+    }
+  }
+}
+
+impl Error for ConfigError {}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    config::{AllocatorConfig, ConfigError, EngineConfig},
+    module::Module,
+    parser::parse_term,
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext
+  };
+
+  #[test]
+  fn configurations_are_validated_and_reach_sessions() {
+    assert_eq!(EngineConfig::default().validate(), Ok(()));
+
+    let tuning = AllocatorConfig { small_model_slop: 0.5, ..AllocatorConfig::default() };
+    assert!(matches!(
+      EngineConfig::default().with_allocator(tuning).validate(),
+      Err(ConfigError::InvalidAllocator(_))
+    ));
+    let deterministic = EngineConfig::default().with_deterministic(true).validate();
+    assert_eq!(deterministic.is_ok(), cfg!(feature = "deterministic"));
+
+    // An index that only looks at top symbols filters nothing.
+    let module      = Module::prelude_module("NAT").unwrap();
+    let term        = parse_term(&module, "plus(s(0), 0)").unwrap();
+    let mut shallow = RewritingContext::with_config(&module, &EngineConfig::default().with_index_depth(0));
+    let mut deep    = RewritingContext::new(&module);
+    assert_eq!(
+      dag_to_string(shallow.reduce(term.term_to_dag(false))),
+      dag_to_string(deep.reduce(term.term_to_dag(false)))
+    );
+    assert_eq!(shallow.index_statistics().filtered, 0);
+    assert!(deep.index_statistics().filtered > 0);
+  }
+}
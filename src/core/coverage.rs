@@ -0,0 +1,228 @@
+/*!
+
+Statement coverage, for finding dead equations and rules in large specifications. A rewriting context created with
+profiling on, `EngineConfig::with_profile(true)`, counts how often each equation and rule of its module fires, that is,
+how often the context rewrites with it. `RewritingContext::coverage_report` then lists the statements of the module
+that never fired during the session, and the report can be exported to JSON with `to_json`:
+
+```json
+{
+  "module": "NAT",
+  "equations": 4,
+  "rules": 0,
+  "unused": [
+    {"kind": "eq", "index": 3, "label": "times-zero", "statement": "equation times(X, 0) = 0;"}
+  ]
+}
+```
+
+`equations` and `rules` are the numbers of statements of each kind in the module, and each unused statement is given by
+its kind, its index among the module's statements of that kind, its label, and the statement as written by `Display`.
+
+Every rewrite a context performs is counted, whether by reduction, `apply_rule`, or `rewrite_at`. Listing redexes
+doesn't fire anything, and neither does a match of a statement whose rewrite is cut off by a rewrite limit. Rolling
+back to a checkpoint doesn't undo the counts, as the statements did fire during the run.
+
+*/
+
+use core::fmt::{Display, Formatter, Write};
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+use crate::{
+  abstractions::IString,
+  core::{module::Module, proof_trace::json_string}
+};
+
+/// The kinds of statements a rewriting context fires
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum StatementKind {
+  Equation,
+  Rule,
+}
+
+impl Display for StatementKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      StatementKind::Equation => write!(f, "eq"),
+      StatementKind::Rule     => write!(f, "rl"),
+    }
+  }
+}
+
+/// How often each equation and rule of a module has fired. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub struct StatementProfile {
+  equations: Vec<usize>,
+  rules    : Vec<usize>,
+}
+
+impl StatementProfile {
+  /// A profile of the statements of `module` in which no statement has fired
+  pub fn new(module: &Module) -> Self {
+    StatementProfile {
+      equations: vec![0; module.equations.len()],
+      rules    : vec![0; module.rules.len()],
+    }
+  }
+
+  /// The number of times the statement of kind `kind` with index `index` has fired
+  pub fn fire_count(&self, kind: StatementKind, index: usize) -> usize {
+    self.counts(kind)[index]
+  }
+
+  #[inline(always)]
+  pub(crate) fn record(&mut self, kind: StatementKind, index: usize) {
+    match kind {
+      StatementKind::Equation => self.equations[index] += 1,
+      StatementKind::Rule     => self.rules[index] += 1,
+    }
+  }
+
+  fn counts(&self, kind: StatementKind) -> &[usize] {
+    match kind {
+      StatementKind::Equation => &self.equations,
+      StatementKind::Rule     => &self.rules,
+    }
+  }
+}
+
+/// A statement that never fired
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct UnusedStatement {
+  pub kind     : StatementKind,
+  /// The index of the statement among the module's statements of its kind
+  pub index    : usize,
+  pub label    : Option<IString>,
+  /// The statement as written by `Display`
+  pub statement: String,
+}
+
+/// The statements of a module that never fired during a profiled session. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct CoverageReport {
+  pub module   : IString,
+  /// The number of equations of the module
+  pub equations: usize,
+  /// The number of rules of the module
+  pub rules    : usize,
+  /// The statements that never fired, the equations first, each kind in the order of the module
+  pub unused   : Vec<UnusedStatement>,
+}
+
+impl CoverageReport {
+  /// The report on the statements of `module` given their fire counts in `profile`
+  pub fn new(module: &Module, profile: &StatementProfile) -> Self {
+    let statements = [(StatementKind::Equation, &module.equations), (StatementKind::Rule, &module.rules)];
+    let unused     = statements.into_iter()
+                               .flat_map(|(kind, statements)| {
+                                 statements.iter()
+                                           .enumerate()
+                                           .filter(move |&(index, _)| profile.fire_count(kind, index) == 0)
+                                           .map(move |(index, statement)| UnusedStatement {
+                                             kind,
+                                             index,
+                                             label    : statement.name.clone(),
+                                             statement: statement.to_string(),
+                                           })
+                               })
+                               .collect();
+
+    CoverageReport {
+      module   : module.name.clone(),
+      equations: module.equations.len(),
+      rules    : module.rules.len(),
+      unused,
+    }
+  }
+
+  /// The fraction of the module's statements that fired, or 1 for a module without statements
+  pub fn coverage(&self) -> f64 {
+    let total = self.equations + self.rules;
+    match total {
+      0 => 1.0,
+      _ => (total - self.unused.len()) as f64 / total as f64,
+    }
+  }
+
+  /// Writes the report as a JSON object with `module`, `equations`, `rules`, and `unused` members.
+  pub fn to_json(&self) -> String {
+    let mut out = String::new();
+
+    out.push_str("{\n");
+    writeln!(out, "  \"module\": {},", json_string(&self.module)).unwrap();
+    writeln!(out, "  \"equations\": {},", self.equations).unwrap();
+    writeln!(out, "  \"rules\": {},", self.rules).unwrap();
+    out.push_str("  \"unused\": [");
+
+    for (i, unused) in self.unused.iter().enumerate() {
+      out.push_str(if i == 0 { "\n" } else { ",\n" });
+      let label = match &unused.label {
+        Some(label) => json_string(label),
+        None        => "null".to_string(),
+      };
+      write!(
+        out,
+        "    {{\"kind\": \"{}\", \"index\": {}, \"label\": {}, \"statement\": {}}}",
+        unused.kind,
+        unused.index,
+        label,
+        json_string(&unused.statement)
+      ).unwrap();
+    }
+
+    if !self.unused.is_empty() {
+      out.push_str("\n  ");
+    }
+    out.push_str("]\n}\n");
+
+    out
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    config::EngineConfig,
+    coverage::StatementKind,
+    parser::{parse_module, parse_term},
+    rewriting_context::RewritingContext
+  };
+
+  #[test]
+  fn statements_that_never_fire_are_reported() {
+    let module = parse_module("
+      mod COVERAGE is
+        sort Nat .
+        op 0 : -> Nat .
+        op s : Nat -> Nat .
+        op plus : Nat Nat -> Nat .
+        op times : Nat Nat -> Nat .
+        vars X Y : Nat .
+        eq plus(X, 0) = X .
+        eq plus(X, s(Y)) = s(plus(X, Y)) .
+        eq [times-zero] : times(X, 0) = 0 .
+      endm
+    ").unwrap();
+    let subject = || parse_term(&module, "plus(s(0), s(0))").unwrap().term_to_dag(false);
+
+    let mut unprofiled = RewritingContext::new(&module);
+    unprofiled.reduce(subject());
+    assert!(unprofiled.coverage_report().is_none());
+
+    let mut context = RewritingContext::with_config(&module, &EngineConfig::default().with_profile(true));
+    context.reduce(subject());
+    let profile = context.profile().unwrap();
+    assert_eq!(profile.fire_count(StatementKind::Equation, 0), 1);
+    assert_eq!(profile.fire_count(StatementKind::Equation, 1), 1);
+
+    let report = context.coverage_report().unwrap();
+    assert_eq!(report.unused.len(), 1);
+    assert_eq!((report.unused[0].kind, report.unused[0].index), (StatementKind::Equation, 2));
+    assert!((report.coverage() - 2.0 / 3.0).abs() < 1e-9);
+
+    let json = report.to_json();
+    assert!(json.contains("\"module\": \"COVERAGE\""));
+    assert!(json.contains("{\"kind\": \"eq\", \"index\": 2, \"label\": \"times-zero\", \"statement\": \"equation"));
+  }
+}
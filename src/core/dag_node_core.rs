@@ -7,51 +7,56 @@ of transmute and bitflags.
 
 The following compares Maude's `DagNode` to our implementation here.
 
-|                | Maude                                        | mod2lib                  |
-|:---------------|:---------------------------------------------|:-------------------------|
-| size           | Fixed 3 word size                            | Fixed size struct        |
-| tag            | implicit via vtable pointer                  | enum variant             |
-| flags          | `MemoryInfo` in first word                   | `BitFlags` field         |
-| shared impl    | base class impl                              | enum impl                |
-| specialization | virtual function calls                       | match on variant in impl |
-| args           | `reinterpret_cast` of 2nd word based on flag | Nested enum              |
+|                | Maude                                        | mod2lib                            |
+|:---------------|:---------------------------------------------|:-----------------------------------|
+| size           | Fixed 3 word size                            | Fixed 3 word size                  |
+| tag            | implicit via vtable pointer                  | enum variant in the symbol word    |
+| flags          | `MemoryInfo` in first word                   | `BitFlags` in the symbol word      |
+| shared impl    | base class impl                              | enum impl                          |
+| specialization | virtual function calls                       | theory table vtables               |
+| args           | `reinterpret_cast` of 2nd word based on flag | Raw pointers and a flag            |
+
+A node stores up to two arguments inline, as thin pointers in `args` and `second_arg`, so the unary and binary
+operators that make up most terms need no argument vector. A node with more arguments stores them in a `DagNodeVector`
+pointed to by `args`, and has the `NeedsDestruction` flag, which tells the two representations apart. A node with fixed
+arity greater than two gets its vector when it is created, and a node of any other arity moves its arguments to a
+vector when it is given a third. Theories that store something else in `args`, like the payload of a data atom, also
+set `NeedsDestruction`, and leave `second_arg` null.
+
+With both arguments inline, the symbol, the sort index, the theory, and the flags share the first word, the `Header`.
+On 64-bit targets, addresses have 48 significant bits and a `Symbol` is aligned to 8 bytes, so the theory takes the
+three low bits of the symbol's address, the flags the eight bits above the address, and the sort index the top eight
+bits. A symbol whose address doesn't fit panics when it is given to a node. Other targets have no bits to spare, and
+their nodes take a fourth word.
 
 */
 
-use std::{
+use core::{
   fmt::{Display, Formatter},
-  marker::PhantomPinned
+  marker::PhantomPinned,
+  ptr::{addr_of_mut, null_mut}
 };
-use std::ptr::null_mut;
 use enumflags2::{bitflags, make_bitflags, BitFlags};
 
 use crate::{
   api::{
     Arity,
-    dag_node::{
-      DagNode,
-      DagNodePtr
-    },
+    dag_node::DagNodePtr,
     symbol::{Symbol, SymbolPtr},
-    free_theory::FreeDagNode,
   },
   core::{
     allocator::{
       allocate_dag_node,
-    }
+    },
+    sort::SpecialSort,
+    theory_table::theory_entry
   },
 };
-use crate::api::dag_node::DagNodeVector;
+use crate::api::dag_node::{arg_to_dag_node, arg_to_node_vec, DagNodeVector};
 
 pub type ThinDagNodePtr = *mut DagNodeCore; // A thin pointer to a `DagNodeCore` object.
 
-// ToDo: Isn't this just a tag for what concrete type of the `dyn Trait` that the `DagNodeCore` was created as?
-//       If so, can we have a map from `DagNodeKind` to the concrete type?
-// ```
-// let node: *mut DagNodeCore;
-// let dyn_node: *mut dyn DagNode = (&*node).to_dyn_trait();
-// let free_node: *mut FreeDagNode = match dyn_node.as_any().downcast_ref::<FreeDagNode>() { Some(n) => n ...}
-// ```
+/// The theory of a node, which determines its concrete type through the theory table in `core::theory_table`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Hash)]
 pub enum DagNodeTheory {
   #[default]
@@ -66,6 +71,19 @@ pub enum DagNodeTheory {
   // Float
 }
 
+impl DagNodeTheory {
+  /// The theory whose discriminant is `bits`, as a node's header stores it
+  #[inline(always)]
+  fn from_bits(bits: u8) -> DagNodeTheory {
+    match bits {
+      0 => DagNodeTheory::Free,
+      1 => DagNodeTheory::Variable,
+      2 => DagNodeTheory::Data,
+      _ => unreachable!("a node header holds an unknown theory"),
+    }
+  }
+}
+
 
 #[bitflags]
 #[repr(u8)]
@@ -109,8 +127,146 @@ impl DagNodeFlag {
 pub type DagNodeFlags = BitFlags<DagNodeFlag, u8>;
 
 
+// region Header
+
+/// The symbol of a node, packed with the node's sort index, theory, and flags. See the module documentation.
+#[cfg(target_pointer_width = "64")]
+#[derive(Copy, Clone)]
+struct Header(SymbolPtr);
+
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(align_of::<Symbol>() >= 8, "the theory is stored in the three low bits of the symbol's address");
+
+#[cfg(target_pointer_width = "64")]
+impl Header {
+  const THEORY_BITS : usize = 0b111;
+  const ADDRESS_BITS: usize = ((1 << 48) - 1) & !Header::THEORY_BITS;
+  const FLAGS_SHIFT : u32   = 48;
+  const SORT_SHIFT  : u32   = 56;
+
+  /// A header with a null symbol and no flags, which is what a node that was never allocated has
+  const EMPTY: Header = Header(null_mut());
+
+  #[inline(always)]
+  fn symbol(self) -> SymbolPtr {
+    self.0.map_addr(|address| address & Header::ADDRESS_BITS)
+  }
+
+  #[inline(always)]
+  fn set_symbol(&mut self, symbol: SymbolPtr) {
+    assert_eq!(symbol.addr() & !Header::ADDRESS_BITS, 0, "the symbol's address doesn't fit in a node header");
+    let rest = self.0.addr() & !Header::ADDRESS_BITS;
+    self.0   = symbol.map_addr(|address| address | rest);
+  }
+
+  #[inline(always)]
+  fn theory(self) -> DagNodeTheory {
+    DagNodeTheory::from_bits((self.0.addr() & Header::THEORY_BITS) as u8)
+  }
+
+  #[inline(always)]
+  fn set_theory(&mut self, theory: DagNodeTheory) {
+    self.0 = self.0.map_addr(|address| (address & !Header::THEORY_BITS) | theory as usize);
+  }
+
+  #[inline(always)]
+  fn sort_index(self) -> i8 {
+    self.byte(Header::SORT_SHIFT) as i8
+  }
+
+  #[inline(always)]
+  fn set_sort_index(&mut self, sort_index: i8) {
+    self.set_byte(Header::SORT_SHIFT, sort_index as u8);
+  }
+
+  #[inline(always)]
+  fn flags(self) -> DagNodeFlags {
+    DagNodeFlags::from_bits_truncate(self.byte(Header::FLAGS_SHIFT))
+  }
+
+  #[inline(always)]
+  fn set_flags(&mut self, flags: DagNodeFlags) {
+    self.set_byte(Header::FLAGS_SHIFT, flags.bits());
+  }
+
+  #[inline(always)]
+  fn byte(self, shift: u32) -> u8 {
+    (self.0.addr() >> shift) as u8
+  }
+
+  #[inline(always)]
+  fn set_byte(&mut self, shift: u32, value: u8) {
+    self.0 = self.0.map_addr(|address| (address & !(0xFF << shift)) | (value as usize) << shift);
+  }
+}
+
+/// The symbol of a node, along with the node's sort index, theory, and flags. See the module documentation.
+#[cfg(not(target_pointer_width = "64"))]
+#[derive(Copy, Clone)]
+struct Header {
+  symbol    : SymbolPtr,
+  sort_index: i8, // sort index within kind
+  theory    : DagNodeTheory,
+  flags     : DagNodeFlags,
+}
+
+#[cfg(not(target_pointer_width = "64"))]
+impl Header {
+  /// A header with a null symbol and no flags, which is what a node that was never allocated has
+  const EMPTY: Header = Header {
+    symbol    : null_mut(),
+    sort_index: 0,
+    theory    : DagNodeTheory::Free,
+    flags     : DagNodeFlags::EMPTY,
+  };
+
+  #[inline(always)]
+  fn symbol(self) -> SymbolPtr {
+    self.symbol
+  }
+
+  #[inline(always)]
+  fn set_symbol(&mut self, symbol: SymbolPtr) {
+    self.symbol = symbol;
+  }
+
+  #[inline(always)]
+  fn theory(self) -> DagNodeTheory {
+    self.theory
+  }
+
+  #[inline(always)]
+  fn set_theory(&mut self, theory: DagNodeTheory) {
+    self.theory = theory;
+  }
+
+  #[inline(always)]
+  fn sort_index(self) -> i8 {
+    self.sort_index
+  }
+
+  #[inline(always)]
+  fn set_sort_index(&mut self, sort_index: i8) {
+    self.sort_index = sort_index;
+  }
+
+  #[inline(always)]
+  fn flags(self) -> DagNodeFlags {
+    self.flags
+  }
+
+  #[inline(always)]
+  fn set_flags(&mut self, flags: DagNodeFlags) {
+    self.flags = flags;
+  }
+}
+
+// endregion Header
+
+
 pub struct DagNodeCore {
-  pub(crate) symbol    : SymbolPtr,
+  /// The symbol, the sort index within the symbol's kind, the theory, and the flags. See the module documentation.
+  header: Header,
   // ToDo: Figure out `args` representation at `DagNodeCore` level.
   /// Either null or a pointer to a `GCVector<T>`.
   ///
@@ -118,10 +274,11 @@ pub struct DagNodeCore {
   /// types in `args`, like `(DagNodePtr, Multiplicity)`. The low-level `args` details can be shifted to
   /// the theory node types, but then every theory would need to reimplement them. Likewise with `mark()` and
   /// the destructor.
+  ///
+  /// Without `NeedsDestruction`, `args` is instead null or the first argument inline. See the module documentation.
   pub(crate) args      : *mut u8,
-  pub(crate) sort_index: i8, // sort index within kind
-  pub(crate) theory_tag: DagNodeTheory,
-  pub(crate) flags     : DagNodeFlags,
+  /// The second argument of a node whose arguments are inline, or null
+  pub(crate) second_arg: *mut u8,
 
   // Opt out of `Unpin`
   _pin: PhantomPinned,
@@ -131,6 +288,7 @@ pub struct DagNodeCore {
 impl DagNodeCore {
   // region Constructors
 
+  #[allow(clippy::new_ret_no_self)]
   pub fn new(symbol: SymbolPtr) -> DagNodePtr {
     DagNodeCore::with_theory(symbol, DagNodeTheory::default())
   }
@@ -140,23 +298,37 @@ impl DagNodeCore {
     let node     = allocate_dag_node();
     let node_mut = unsafe { &mut *node };
 
-    node_mut.args  = null_mut();
-    node_mut.flags = DagNodeFlags::empty();
+    node_mut.header     = Header::EMPTY;
+    node_mut.args       = null_mut();
+    node_mut.second_arg = null_mut();
+    node_mut.header.set_sort_index(SpecialSort::Unknown as i8);
 
+    // Up to two arguments are inline.
     if let Arity::Value(arity) = unsafe{ &*symbol }.arity {
-      if arity > 1 {
+      if arity > 2 {
         let vec = DagNodeVector::with_capacity(arity as usize);
         node_mut.args = (vec as *mut DagNodeVector) as *mut u8;
-        node_mut.flags.insert(DagNodeFlag::NeedsDestruction);
+        node_mut.insert_flags(DagNodeFlag::NeedsDestruction.into());
       }
     };
 
-    node_mut.theory_tag = theory;
-    node_mut.symbol     = symbol;
+    node_mut.header.set_theory(theory);
+    node_mut.header.set_symbol(symbol);
 
     DagNodeCore::upgrade(node)
   }
 
+  /// Writes the header of a node that was never allocated, with a null symbol and no flags, to `node`, which may be
+  /// uninitialized, so that the allocator can sweep it as a free node that needs no destruction.
+  ///
+  /// # Safety
+  ///
+  /// `node` must be valid for writes.
+  #[inline(always)]
+  pub(crate) unsafe fn clear_header(node: ThinDagNodePtr) {
+    unsafe{ addr_of_mut!((*node).header).write(Header::EMPTY); }
+  }
+
   // endregion Constructors
 
   // region Accessors
@@ -164,13 +336,18 @@ impl DagNodeCore {
 
   #[inline(always)]
   pub fn symbol(&self) -> SymbolPtr {
-    self.symbol
+    self.header.symbol()
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_symbol(&mut self, symbol: SymbolPtr) {
+    self.header.set_symbol(symbol);
   }
 
   #[inline(always)]
   pub fn symbol_ref(&self) -> &Symbol {
     unsafe {
-      &*self.symbol
+      &*self.symbol()
     }
   }
 
@@ -179,6 +356,51 @@ impl DagNodeCore {
     self.symbol_ref().arity
   }
 
+  #[inline(always)]
+  pub fn theory(&self) -> DagNodeTheory {
+    self.header.theory()
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_theory(&mut self, theory: DagNodeTheory) {
+    self.header.set_theory(theory);
+  }
+
+  /// The index of the node's sort within its kind, or a `SpecialSort`
+  #[inline(always)]
+  pub fn sort_index(&self) -> i8 {
+    self.header.sort_index()
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_sort_index(&mut self, sort_index: i8) {
+    self.header.set_sort_index(sort_index);
+  }
+
+  #[inline(always)]
+  pub fn flags(&self) -> DagNodeFlags {
+    self.header.flags()
+  }
+
+  #[inline(always)]
+  pub(crate) fn insert_flags(&mut self, flags: DagNodeFlags) {
+    self.header.set_flags(self.header.flags() | flags);
+  }
+
+  #[inline(always)]
+  pub(crate) fn remove_flags(&mut self, flags: DagNodeFlags) {
+    self.header.set_flags(self.header.flags() & !flags);
+  }
+
+  /// The argument at `index`, which must be less than the number of arguments, of a node whose arguments are nodes
+  #[inline(always)]
+  pub(crate) fn arg(&self, index: usize) -> DagNodePtr {
+    match (self.needs_destruction(), index) {
+      (true, _)  => arg_to_node_vec(self.args)[index],
+      (false, 0) => arg_to_dag_node(self.args),
+      (false, _) => arg_to_dag_node(self.second_arg),
+    }
+  }
 
 
   // endregion
@@ -186,54 +408,65 @@ impl DagNodeCore {
   // region GC related methods
   #[inline(always)]
   pub fn is_marked(&self) -> bool {
-    self.flags.contains(DagNodeFlag::Marked)
+    self.flags().contains(DagNodeFlag::Marked)
   }
 
   #[inline(always)]
   pub fn needs_destruction(&self) -> bool {
-    self.flags.contains(DagNodeFlag::NeedsDestruction)
+    self.flags().contains(DagNodeFlag::NeedsDestruction)
   }
 
   #[inline(always)]
   pub fn simple_reuse(&self) -> bool {
-    !self.flags.contains(DagNodeFlag::Marked) && !self.needs_destruction()
+    !self.flags().contains(DagNodeFlag::Marked) && !self.needs_destruction()
   }
 
   //endregion
 
+  /// Overwrites `self` with a clone of `other`, so that every reference to `self` now refers to the term `other`
+  /// represents. The arguments are shared with `other`, but `self` gets its own argument vector. Used for in-place
+  /// (graph) rewriting. The theories of the nodes must agree, because existing fat pointers to `self` keep their vtable.
+  pub(crate) fn overwrite_with_clone(&mut self, other: &DagNodeCore) {
+    assert_eq!(self.theory(), other.theory(), "tried to overwrite a node with a node of a different theory");
+
+    // A marked node may not have been swept yet, and losing the mark would let the allocator reuse it.
+    let marked = self.flags() & DagNodeFlag::Marked;
+
+    self.header = other.header;
+    self.remove_flags(DagNodeFlag::Marked.into());
+    self.insert_flags(marked);
+    self.args       = match other.needs_destruction() {
+      true  => (arg_to_node_vec(other.args).copy() as *mut DagNodeVector) as *mut u8,
+      false => other.args,
+    };
+    self.second_arg = other.second_arg;
+  }
+
   /// Upgrades the thin pointer to a DagNodeCore object to a fat pointer to a concrete implementor of the `DagNode`
-  /// trait, returning a fat pointer to a `dyn DagNode` with the correct vtable. The concrete type is selected based
-  /// on `DagNodeCore::theory_tag`.
-  ///
-  /// This is a huge pain to do.
+  /// trait, returning a fat pointer to a `dyn DagNode` with the correct vtable. The concrete type is the one
+  /// registered in the theory table for the node's theory.
   #[inline(always)]
   pub fn upgrade(thin_dag_node_ptr: ThinDagNodePtr) -> DagNodePtr {
     assert!(!thin_dag_node_ptr.is_null());
-    match unsafe { thin_dag_node_ptr.as_ref_unchecked().theory_tag } {
-      DagNodeTheory::Free => {
-        // Step 1: Create a fake reference to MyStruct
-        let fake_ptr: *mut FreeDagNode = std::ptr::null_mut();
-        // Step 2: Cast the fake reference to a trait object pointer
-        let fake_trait_object: DagNodePtr = fake_ptr as DagNodePtr;
-        // Step 3: Extract the vtable from the trait object pointer
-        let vtable = std::ptr::metadata(fake_trait_object);
-        // Step 4: Combine the thin pointer and vtable pointer into a fat pointer
-        let fat_ptr: *mut dyn DagNode = std::ptr::from_raw_parts_mut(thin_dag_node_ptr, vtable);
-
-        fat_ptr
-      }
-      // DagNodeTheory::Variable => {}
-      // DagNodeTheory::Data => {}
-      _ => {
-        panic!("Thin DagNode has invalid theory tag")
-      }
-    }
+    let theory = unsafe { (*thin_dag_node_ptr).theory() };
+    (theory_entry(theory).upgrade)(thin_dag_node_ptr)
+  }
+
+  /// Runs the destructor of the node's concrete type, as registered in the theory table, on a dead node.
+  ///
+  /// # Safety
+  ///
+  /// `thin_dag_node_ptr` must point to an initialized node that is no longer reachable.
+  #[inline(always)]
+  pub unsafe fn drop_in_place(thin_dag_node_ptr: ThinDagNodePtr) {
+    let theory = unsafe { (*thin_dag_node_ptr).theory() };
+    unsafe { (theory_entry(theory).drop_in_place)(thin_dag_node_ptr) }
   }
 
 }
 
 impl Display for DagNodeCore {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    write!(f, "node<{}>", self.symbol_ref())
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "node<{}>:{}", self.symbol_ref(), theory_entry(self.theory()).name)
   }
 }
@@ -0,0 +1,165 @@
+/*!
+
+Structural differences between terms and DAGs, for reporting test failures and showing users what a step changed.
+`diff` compares two DAGs top down and reports each position at which they disagree, with the subterm of each at that
+position. Where both have the same symbol and number of arguments, the arguments are compared instead, so the
+differences are the smallest subterms that differ and no position is below another.
+
+Subterms that are the same node are equal without being looked at. DAGs built from terms share equal subterms, so
+comparing two large DAGs that differ in a few places costs time proportional to the few places and the nodes above
+them, not to the size of the DAGs. Pairs of distinct nodes found to be equal are remembered, so each pair is compared
+once however often it is shared. `diff_terms` does the same for terms, which have no sharing to exploit.
+
+```ignore
+for difference in diff(expected, actual) {
+  println!("{}", difference);  // at [1, 0]: s(0) vs 0
+}
+```
+
+*/
+
+use core::fmt::{Display, Formatter};
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::Set,
+  api::{
+    dag_node::DagNodePtr,
+    term::Term
+  },
+  core::{
+    format::FormatStyle,
+    proof_trace::dag_to_string,
+    rewriting_context::Position
+  }
+};
+
+/// A position at which two terms or DAGs disagree, with the subterm of each there
+#[derive(Clone, Debug)]
+pub struct Difference<T> {
+  pub position: Position,
+  pub left    : T,
+  pub right   : T,
+}
+
+impl Display for Difference<DagNodePtr> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "at {:?}: {} vs {}", self.position, dag_to_string(self.left), dag_to_string(self.right))
+  }
+}
+
+impl Display for Difference<&dyn Term> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "at {:?}: {} vs {}",
+      self.position,
+      self.left.repr(FormatStyle::Simple),
+      self.right.repr(FormatStyle::Simple)
+    )
+  }
+}
+
+/// The positions at which `left` and `right` disagree, in preorder. Empty if the DAGs are equal. See the module
+/// documentation.
+pub fn diff(left: DagNodePtr, right: DagNodePtr) -> Vec<Difference<DagNodePtr>> {
+  let mut differences = Vec::new();
+  diff_dags(left, right, &mut Vec::new(), &mut Set::default(), &mut differences);
+  differences
+}
+
+/// The positions at which `left` and `right` disagree, in preorder. Empty if the terms are equal.
+pub fn diff_terms<'t>(left: &'t dyn Term, right: &'t dyn Term) -> Vec<Difference<&'t dyn Term>> {
+  let mut differences = Vec::new();
+  diff_terms_aux(left, right, &mut Vec::new(), &mut differences);
+  differences
+}
+
+fn diff_dags(
+  left       : DagNodePtr,
+  right      : DagNodePtr,
+  position   : &mut Position,
+  equal      : &mut Set<(*const u8, *const u8)>,
+  differences: &mut Vec<Difference<DagNodePtr>>
+) {
+  let pair = (left as *const u8, right as *const u8);
+  if core::ptr::addr_eq(left, right) || equal.contains(&pair) {
+    return;
+  }
+
+  let (left_ref, right_ref) = unsafe{ (&*left, &*right) };
+  if !core::ptr::addr_eq(left_ref.symbol(), right_ref.symbol()) || left_ref.len() != right_ref.len() {
+    differences.push(Difference { position: position.clone(), left, right });
+    return;
+  }
+
+  let found = differences.len();
+  for (index, (left_arg, right_arg)) in left_ref.iter_args().zip(right_ref.iter_args()).enumerate() {
+    position.push(index);
+    diff_dags(left_arg, right_arg, position, equal, differences);
+    position.pop();
+  }
+  if differences.len() == found {
+    equal.insert(pair);
+  }
+}
+
+fn diff_terms_aux<'t>(
+  left       : &'t dyn Term,
+  right      : &'t dyn Term,
+  position   : &mut Position,
+  differences: &mut Vec<Difference<&'t dyn Term>>
+) {
+  let same_head = match (left.is_variable(), right.is_variable()) {
+    (false, false) => {
+      core::ptr::addr_eq(left.symbol(), right.symbol()) && left.iter_args().count() == right.iter_args().count()
+    }
+    (true, true)   => core::ptr::addr_eq(left.symbol(), right.symbol()),
+    _              => false,
+  };
+  if !same_head {
+    differences.push(Difference { position: position.clone(), left, right });
+    return;
+  }
+
+  for (index, (left_arg, right_arg)) in left.iter_args().zip(right.iter_args()).enumerate() {
+    position.push(index);
+    diff_terms_aux(left_arg, right_arg, position, differences);
+    position.pop();
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    diff::{diff, diff_terms},
+    parser::{parse_module, parse_term}
+  };
+
+  #[test]
+  fn differences_are_minimal() {
+    let module = parse_module("
+      mod DIFF is
+        sort Nat .
+        ops 0 a : -> Nat .
+        op s : Nat -> Nat .
+        op f : Nat Nat -> Nat .
+        var X : Nat .
+      endm
+    ").unwrap();
+    let dag = |text: &str| parse_term(&module, text).unwrap().term_to_dag(false);
+
+    let differences = diff(dag("f(s(0), f(a, s(0)))"), dag("f(s(0), f(s(a), 0))"));
+    let shown: Vec<String> = differences.iter().map(|difference| difference.to_string()).collect();
+    assert_eq!(shown, ["at [1, 0]: a vs s(a)", "at [1, 1]: s(0) vs 0"]);
+    assert!(diff(dag("f(a, s(0))"), dag("f(a, s(0))")).is_empty());
+
+    let left        = parse_term(&module, "f(X, s(0))").unwrap();
+    let right       = parse_term(&module, "f(X, s(a))").unwrap();
+    let differences = diff_terms(left.as_ref(), right.as_ref());
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].to_string(), "at [1, 0]: 0 vs a");
+    assert!(diff_terms(left.as_ref(), left.as_ref()).is_empty());
+  }
+}
@@ -0,0 +1,133 @@
+/*!
+
+Copy-on-write modules for trying out changes. `Module::clone_for_experiment` makes an `Experiment`, a module that starts
+out as a logical copy of the original but can be changed without affecting it: equations, rules, and membership axioms
+can be added, removed, or have their attributes changed, and reduction in the experiment sees the changes while the
+original module is unchanged.
+
+The copy is cheap. The experiment borrows the original's symbols and sorts instead of copying them (see `ModuleArena`),
+and its statements share their compiled matching automata and right-hand side builders with the original's, so nothing
+is recompiled until a statement is added or its compilation reset. Only the terms of the statements are copied.
+
+```ignore
+let mut experiment = module.clone_for_experiment();
+experiment.add_statement(equation)?;
+let mut context = RewritingContext::new(&experiment);
+```
+
+What is shared must not change: the experiment's symbols and sorts are the original's, so `add_subsort` panics on a
+shared sort, and symbol attributes must be left alone. Sorts and symbols created in the experiment are its own. The
+experiment borrows the original immutably, so the original can't change while the experiment exists. Rooted DAGs keep
+the experiment's own symbols alive past the experiment, as with any module. See `module_arena`.
+
+*/
+
+use core::ops::{Deref, DerefMut};
+
+use crate::core::module::Module;
+
+/// A module that starts out as a copy of another and can be changed without affecting it. See the module
+/// documentation.
+pub struct Experiment<'m> {
+  original: &'m Module,
+  module  : Module,
+}
+
+impl<'m> Experiment<'m> {
+  pub(crate) fn new(original: &'m Module) -> Self {
+    let module = Module {
+      name              : original.name.clone(),
+      status            : original.status,
+      imports           : original.imports.clone(),
+      transitive_imports: original.transitive_imports.clone(),
+      identities        : original.identities.clone(),
+      equations         : original.equations.iter().map(|statement| statement.shared_copy()).collect(),
+      rules             : original.rules.iter().map(|statement| statement.shared_copy()).collect(),
+      membership        : original.membership.iter().map(|statement| statement.shared_copy()).collect(),
+      sort_predicates   : original.sort_predicates.clone(),
+      graph_rewriting   : original.graph_rewriting,
+      arena             : original.arena.share(),
+      ..Module::default()
+    };
+
+    Experiment { original, module }
+  }
+
+  /// The module the experiment was copied from
+  #[inline(always)]
+  pub fn original(&self) -> &'m Module {
+    self.original
+  }
+}
+
+impl Deref for Experiment<'_> {
+  type Target = Module;
+
+  fn deref(&self) -> &Module {
+    &self.module
+  }
+}
+
+impl DerefMut for Experiment<'_> {
+  fn deref_mut(&mut self) -> &mut Module {
+    &mut self.module
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::{
+      module::Module,
+      parser::{parse_module, parse_term},
+      pre_equation::{PreEquation, PreEquationKind},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext
+    }
+  };
+
+  const MODULE: &str = "
+    mod EXPERIMENT is
+      sort Nat .
+      ops 0 1 2 : -> Nat .
+      op s : Nat -> Nat .
+      var X : Nat .
+      eq s(0) = 1 .
+      eq s(X) = X .
+    endm
+  ";
+
+  #[test]
+  fn experiments_leave_the_original_alone() {
+    let module = parse_module(MODULE).unwrap();
+    let reduce = |module: &Module, text: &str| {
+      let mut context = RewritingContext::new(module);
+      dag_to_string(context.reduce(parse_term(module, text).unwrap().term_to_dag(false)))
+    };
+
+    let mut experiment = module.clone_for_experiment();
+    assert!(std::ptr::eq(experiment.original(), &module));
+    // The copies share the compiled automata, so they work without being compiled again.
+    assert_eq!(reduce(&experiment, "s(s(0))"), "1");
+
+    let one = experiment.arena.symbol(&IString::from("1")).unwrap();
+    assert!(experiment.arena.is_shared_symbol(one) && !experiment.arena.owns_symbol(one));
+    let equation = PreEquation::new(
+      None,
+      parse_term(&experiment, "1").unwrap(),
+      PreEquationKind::Equation { rhs_term: parse_term(&experiment, "2").unwrap() },
+      vec![]
+    );
+    experiment.add_statement(equation).unwrap();
+    experiment.equations.remove(1);
+
+    assert_eq!(reduce(&experiment, "s(s(0))"), "s(2)");
+    assert_eq!(reduce(&module, "s(s(0))"), "1");
+    assert_eq!(module.equations.len(), 2);
+
+    drop(experiment);
+    assert_eq!(reduce(&module, "s(0)"), "1");
+  }
+}
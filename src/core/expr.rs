@@ -0,0 +1,570 @@
+/*!
+
+An expression layer in the style of the Wolfram Language. Available with the `expr` feature.
+
+An `Expr` is a symbol, a named blank like `x_`, a head applied to arguments, like `f[x, y]`, or a restricted blank.
+Expressions name their symbols, so they can be written without declaring anything. An `ExprEngine` holds the module
+they are evaluated in: it declares each symbol the first time an expression uses it, with the number of arguments of
+that use, turns definitions `lhs := rhs` made with `set_delayed` into equations, and evaluates an expression by reducing
+it with the rewriting engine.
+
+```ignore
+let mut engine = ExprEngine::new();
+engine.set_attributes("plus", &[ExprAttribute::Flat, ExprAttribute::Orderless])?;
+engine.set_delayed(&Expr::apply("plus", vec![Expr::blank("x"), Expr::symbol("zero")]), &Expr::blank("x"))?;
+let result = engine.evaluate(&Expr::apply("plus", vec![Expr::symbol("a"), Expr::symbol("zero"), Expr::symbol("b")]))?;
+assert_eq!(result.to_string(), "plus[a, b]");
+```
+
+## Attributes
+
+Attributes map to symbol attributes and strategies:
+
+| Attribute   | Symbol                                                                   |
+|:------------|:-------------------------------------------------------------------------|
+| `Flat`      | `Associative`. Applications to any number of arguments are nested pairs. |
+| `Orderless` | `Commutative`                                                            |
+| `HoldAll`   | The strategy `(0)`, so that arguments are not evaluated first            |
+
+A symbol that is `Flat` or `Orderless` is binary underneath. An application of a `Flat` symbol to more than two
+arguments is converted to nested binary applications, `f[a, b, c]` to `f(a, f(b, c))`, and nested applications are
+flattened again on the way back. Attributes must be set before the symbol is first used.
+
+## Restricted Blanks
+
+A blank can be restricted with a pattern test, `x_?test`, or a condition, `x_ /; condition`, and the restrictions nest.
+The test is either a Rust predicate on expressions registered with `register_test` or, failing that, a symbol, and a
+binding passes it if the predicate holds or if `test[binding]` evaluates to `True`. A binding satisfies the condition
+if the condition, with the binding in place of the blank, evaluates to `True`, so the condition can only use the blank
+it restricts. Both are checked while the blank is matched rather than after the whole pattern has matched (see
+`ConstrainedVariable::with_test`), so a binding that fails them stops the match early.
+
+```ignore
+engine.register_test("atomic", |expr| matches!(expr, Expr::Symbol(_)));
+engine.set_delayed(&Expr::apply("f", vec![Expr::pattern_test(Expr::blank("x"), "atomic")]), &Expr::symbol("yes"))?;
+// positive[s[x_]] := True; g[x_ /; positive[x]] := yes
+engine.set_delayed(
+  &Expr::apply("positive", vec![Expr::apply("s", vec![Expr::blank("x")])]),
+  &Expr::symbol("True")
+)?;
+let positive = Expr::apply("positive", vec![Expr::blank("x")]);
+engine.set_delayed(&Expr::apply("g", vec![Expr::condition(Expr::blank("x"), positive)]), &Expr::symbol("yes"))?;
+```
+
+Tests and conditions are evaluated in the engine's module, so the definitions they use can be made after them.
+
+## Limitations
+
+Blanks match a single expression; there are no sequence blanks. Only blanks can be restricted, not other patterns.
+Numbers and strings are data atoms, which terms can't hold yet, so expressions have none. Heads are symbols rather than
+arbitrary expressions.
+
+*/
+
+use core::{error::Error, fmt::{Display, Formatter}};
+use alloc::{boxed::Box, rc::Rc, vec, vec::Vec};
+
+use crate::{
+  abstractions::{HashMap, IString},
+  api::{
+    dag_node::{as_node_ptr, DagNode, DagNodePtr},
+    free_theory::{FreeDagNode, FreeTerm},
+    symbol::{Symbol, SymbolAttribute, SymbolAttributes, SymbolPtr},
+    term::{BxTerm, Term},
+    variable_theory::{ConstrainedVariable, VariableTerm},
+    Arity
+  },
+  core::{
+    module::Module,
+    pre_equation::{PreEquation, PreEquationKind, StatementError, UnboundVariableError},
+    rewriting_context::RewritingContext
+  }
+};
+
+/// A Wolfram Language style expression. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Expr {
+  /// A symbol, like `x`
+  Symbol(IString),
+  /// A named blank, like `x_`, which matches any expression
+  Blank(IString),
+  /// A head applied to arguments, like `f[x, y]`
+  Apply { head: IString, args: Vec<Expr> },
+  /// A restricted blank that only matches expressions passing a test, like `x_?test`
+  PatternTest { pattern: Box<Expr>, test: IString },
+  /// A restricted blank that only matches expressions satisfying a condition, like `x_ /; positive[x]`
+  Condition { pattern: Box<Expr>, condition: Box<Expr> },
+}
+
+/// A Rust predicate on expressions, used as a pattern test. See the module documentation.
+pub type ExprTest = Rc<dyn Fn(&Expr) -> bool>;
+
+impl Expr {
+  pub fn symbol(name: &str) -> Expr {
+    Expr::Symbol(IString::from(name))
+  }
+
+  pub fn blank(name: &str) -> Expr {
+    Expr::Blank(IString::from(name))
+  }
+
+  pub fn apply(head: &str, args: Vec<Expr>) -> Expr {
+    Expr::Apply { head: IString::from(head), args }
+  }
+
+  pub fn pattern_test(pattern: Expr, test: &str) -> Expr {
+    Expr::PatternTest { pattern: Box::new(pattern), test: IString::from(test) }
+  }
+
+  pub fn condition(pattern: Expr, condition: Expr) -> Expr {
+    Expr::Condition { pattern: Box::new(pattern), condition: Box::new(condition) }
+  }
+
+  /// The expression of `term`, with nested applications of associative symbols flattened
+  pub fn from_term(term: &dyn Term) -> Expr {
+    let symbol = term.symbol_ref();
+    if term.is_variable() {
+      return Expr::Blank(symbol.name.clone());
+    }
+
+    let mut args = Vec::new();
+    for arg in term.iter_args() {
+      push_flattened(&mut args, symbol, Expr::from_term(arg));
+    }
+    make_expr(symbol, args)
+  }
+
+  /// The expression of the DAG at `node`, with nested applications of associative symbols flattened
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn from_dag(node: DagNodePtr) -> Expr {
+    Expr::from_node(unsafe{ &*node })
+  }
+
+  fn from_node(node: &dyn DagNode) -> Expr {
+    let symbol = node.symbol_ref();
+    if symbol.is_variable() {
+      return Expr::Blank(symbol.name.clone());
+    }
+
+    let mut args = Vec::new();
+    for arg in node.iter_args() {
+      push_flattened(&mut args, symbol, Expr::from_dag(arg));
+    }
+    make_expr(symbol, args)
+  }
+}
+
+fn make_expr(symbol: &Symbol, args: Vec<Expr>) -> Expr {
+  match args.is_empty() {
+    true  => Expr::Symbol(symbol.name.clone()),
+    false => Expr::Apply { head: symbol.name.clone(), args },
+  }
+}
+
+/// Pushes `arg`, or the arguments of `arg` if it is an application of the associative symbol `symbol`.
+fn push_flattened(args: &mut Vec<Expr>, symbol: &Symbol, arg: Expr) {
+  match arg {
+    Expr::Apply { head, args: inner }
+      if head == symbol.name && symbol.attributes.contains(SymbolAttribute::Associative) => args.extend(inner),
+    arg => args.push(arg),
+  }
+}
+
+impl Display for Expr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Expr::Symbol(name) => write!(f, "{}", name),
+      Expr::Blank(name)  => write!(f, "{}_", name),
+      Expr::Apply { head, args } => {
+        write!(f, "{}[", head)?;
+        for (index, arg) in args.iter().enumerate() {
+          if index > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}", arg)?;
+        }
+        write!(f, "]")
+      }
+      Expr::PatternTest { pattern, test }    => write!(f, "{}?{}", pattern, test),
+      Expr::Condition { pattern, condition } => write!(f, "{} /; {}", pattern, condition),
+    }
+  }
+}
+
+/// Attributes of expression symbols. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ExprAttribute {
+  Flat,
+  Orderless,
+  HoldAll,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ExprError {
+  /// The symbol was first used with a different number of arguments.
+  WrongArity { head: IString, expected: usize, found: usize },
+  /// The name is used both as a symbol and as a blank.
+  NameClash(IString),
+  /// Attributes were set for a symbol that is already in use.
+  AlreadyInUse(IString),
+  /// A blank was used where it can't be, like on the right-hand side of a definition without occurring on its left.
+  UnboundVariable(UnboundVariableError),
+  /// A pattern test or condition restricts something other than a blank.
+  NotABlank(Expr),
+  /// The condition of a blank uses another blank.
+  ForeignBlank { blank: IString, other: IString },
+}
+
+impl Display for ExprError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      ExprError::WrongArity { head, expected, found } => {
+        write!(f, "{} takes {} arguments but was given {}", head, expected, found)
+      }
+      ExprError::NameClash(name)        => write!(f, "{} is used both as a symbol and as a blank", name),
+      ExprError::AlreadyInUse(name)     => write!(f, "the attributes of {} must be set before it is used", name),
+      ExprError::UnboundVariable(error) => write!(f, "{}", error),
+      ExprError::NotABlank(pattern)     => write!(f, "only blanks can be restricted, not {}", pattern),
+      ExprError::ForeignBlank { blank, other } => {
+        write!(f, "the condition of {}_ can only use {}_, not {}_", blank, blank, other)
+      }
+    }
+  }
+}
+
+impl Error for ExprError {}
+
+impl From<StatementError> for ExprError {
+  fn from(error: StatementError) -> Self {
+    match error {
+      StatementError::UnboundVariable(error) => ExprError::UnboundVariable(error),
+      // Definitions have no statement conditions; the conditions of blanks are constraints on their variables.
+      StatementError::UnsupportedCondition { .. } => unreachable!("a definition has a condition: {}", error),
+    }
+  }
+}
+
+/// A module of expression symbols and definitions. See the module documentation.
+pub struct ExprEngine {
+  /// Boxed so that pattern tests and conditions, which live in the module's statements, can refer to it
+  module: Box<Module>,
+  tests : HashMap<IString, ExprTest>,
+}
+
+impl Default for ExprEngine {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ExprEngine {
+  pub fn new() -> Self {
+    let mut module = Module::default();
+    // There are no sorts, so the closure is empty, but definitions are compiled as they are added once it is computed.
+    module.recompute_kind_closures().expect("an empty module has no statements");
+
+    ExprEngine { module: Box::new(module), tests: HashMap::default() }
+  }
+
+  /// The module expressions are evaluated in
+  #[inline(always)]
+  pub fn module(&self) -> &Module {
+    &self.module
+  }
+
+  /// Declares `head` with `attributes`. `Flat` and `Orderless` symbols are binary, and other symbols get the number of
+  /// arguments of their first use.
+  pub fn set_attributes(&mut self, head: &str, attributes: &[ExprAttribute]) -> Result<SymbolPtr, ExprError> {
+    let name = IString::from(head);
+    if self.module.arena.symbol(&name).is_some() {
+      return Err(ExprError::AlreadyInUse(name));
+    }
+
+    let mut axioms  = SymbolAttributes::empty();
+    let mut arity   = Arity::Unspecified;
+    let mut holding = false;
+    for attribute in attributes {
+      match attribute {
+        ExprAttribute::Flat      => axioms |= SymbolAttribute::Associative,
+        ExprAttribute::Orderless => axioms |= SymbolAttribute::Commutative,
+        ExprAttribute::HoldAll   => holding = true,
+      }
+    }
+    if !axioms.is_empty() {
+      arity = Arity::Value(2);
+    }
+
+    let mut symbol = Symbol::new(name, arity);
+    symbol.attributes.insert(axioms);
+    if holding {
+      // Only fixed arities have strategies, so a held symbol of unspecified arity gets one when it is first used.
+      if symbol.arity.is_fixed() {
+        symbol.set_strategy(vec![0]).expect("the strategy (0) is valid for any arity");
+      } else {
+        symbol.attributes.insert(SymbolAttribute::Strategy);
+      }
+    }
+
+    Ok(self.module.arena.add_symbol(symbol))
+  }
+
+  /// Registers `test` as the pattern test `name`, replacing the test registered with the name before. Patterns made
+  /// before are unaffected.
+  pub fn register_test(&mut self, name: &str, test: impl Fn(&Expr) -> bool + 'static) {
+    self.tests.insert(IString::from(name), Rc::new(test));
+  }
+
+  /// Defines `lhs := rhs`: an equation rewriting instances of `lhs` to the corresponding instances of `rhs`.
+  pub fn set_delayed(&mut self, lhs: &Expr, rhs: &Expr) -> Result<(), ExprError> {
+    let lhs_term  = self.term_of(lhs)?;
+    let rhs_term  = self.term_of(rhs)?;
+    let statement = PreEquation::new(None, lhs_term, PreEquationKind::Equation { rhs_term }, vec![]);
+    self.module.add_statement(statement)?;
+
+    Ok(())
+  }
+
+  /// Evaluates `expr` to normal form with the definitions made so far.
+  pub fn evaluate(&mut self, expr: &Expr) -> Result<Expr, ExprError> {
+    let subject     = self.term_of(expr)?.term_to_dag(false);
+    let mut context = RewritingContext::new(&self.module);
+
+    Ok(Expr::from_dag(context.reduce(subject)))
+  }
+
+  /// The term of `expr`, declaring the symbols it uses that aren't declared yet.
+  pub fn term_of(&mut self, expr: &Expr) -> Result<BxTerm, ExprError> {
+    match expr {
+      Expr::Symbol(name) => {
+        let symbol = self.operator(name, 0)?;
+        Ok(Box::new(FreeTerm::new(symbol)))
+      }
+
+      Expr::Blank(name) => {
+        let symbol = match self.module.arena.symbol(name) {
+          Some(symbol) if unsafe{ &*symbol }.is_variable() => symbol,
+          Some(_) => return Err(ExprError::NameClash(name.clone())),
+          None    => self.module.arena.add_symbol(Symbol::new_variable(name.clone())),
+        };
+        Ok(Box::new(VariableTerm::new(symbol)))
+      }
+
+      Expr::PatternTest { .. } | Expr::Condition { .. } => Ok(self.restricted_blank(expr)?.into_term()),
+
+      Expr::Apply { head, args } => {
+        let symbol = self.operator(head, args.len())?;
+        let args   = args.iter().map(|arg| self.term_of(arg)).collect::<Result<Vec<_>, _>>()?;
+
+        // A flat symbol is binary, so longer applications nest to the right.
+        let flat = unsafe{ &*symbol }.attributes.contains(SymbolAttribute::Associative);
+        if flat && args.len() > 2 {
+          let mut args = args.into_iter().rev();
+          let last     = args.next().unwrap();
+          return Ok(args.fold(last, |right, left| binary(symbol, left, right)));
+        }
+
+        Ok(FreeTerm::with_args(symbol, args))
+      }
+    }
+  }
+
+  /// The blank `expr` with its pattern tests and conditions, which are checked during matching
+  fn restricted_blank(&mut self, expr: &Expr) -> Result<ConstrainedVariable, ExprError> {
+    match expr {
+      Expr::Blank(_) => {
+        let term   = self.term_of(expr)?;
+        Ok(ConstrainedVariable::new(term.symbol()))
+      }
+
+      Expr::PatternTest { pattern, test } => {
+        let blank = self.restricted_blank(pattern)?;
+        if let Some(test) = self.tests.get(test).cloned() {
+          return Ok(blank.with_test(move |node| test(&Expr::from_node(node))));
+        }
+
+        let symbol = self.operator(test, 1)?;
+        let module = self.module_ptr();
+        Ok(blank.with_test(
+          move |node| {
+            let subject = FreeDagNode::new(symbol);
+            unsafe{ &mut *subject }.insert_child_unchecked(as_node_ptr(node));
+            is_true(module, subject)
+          }
+        ))
+      }
+
+      Expr::Condition { pattern, condition } => {
+        let blank     = self.restricted_blank(pattern)?;
+        let variable  = blank.symbol();
+        let condition = self.term_of(condition)?;
+        check_blanks(condition.as_ref(), variable)?;
+
+        let module = self.module_ptr();
+        Ok(blank.with_test(move |node| is_true(module, instantiate(condition.as_ref(), as_node_ptr(node)))))
+      }
+
+      _ => Err(ExprError::NotABlank(expr.clone())),
+    }
+  }
+
+  /// The module, for tests and conditions to evaluate in. The module is boxed and lives as long as the statements that
+  /// hold the tests.
+  #[inline(always)]
+  fn module_ptr(&self) -> *const Module {
+    self.module.as_ref()
+  }
+
+  /// The operator named `name`, declared with `arg_count` arguments if it isn't declared yet
+  fn operator(&mut self, name: &IString, arg_count: usize) -> Result<SymbolPtr, ExprError> {
+    let symbol = match self.module.arena.symbol(name) {
+      Some(symbol) => symbol,
+      None         => self.module.arena.add_symbol(Symbol::new(name.clone(), Arity::Unspecified)),
+    };
+
+    let symbol_ref = unsafe{ &mut *symbol };
+    if symbol_ref.is_variable() {
+      return Err(ExprError::NameClash(name.clone()));
+    }
+    if symbol_ref.arity == Arity::Unspecified {
+      symbol_ref.arity = Arity::new(arg_count).expect("expressions have few enough arguments");
+      if symbol_ref.attributes.contains(SymbolAttribute::Strategy) {
+        symbol_ref.set_strategy(vec![0]).expect("the strategy (0) is valid for any arity");
+      }
+    }
+
+    let flat = symbol_ref.attributes.contains(SymbolAttribute::Associative);
+    match symbol_ref.arity.max_args() {
+      Some(expected) if expected != arg_count && !(flat && arg_count > 2) => {
+        Err(ExprError::WrongArity { head: name.clone(), expected, found: arg_count })
+      }
+      _ => Ok(symbol),
+    }
+  }
+}
+
+/// Whether `subject` evaluates to `True` in `module`
+fn is_true(module: *const Module, subject: DagNodePtr) -> bool {
+  let mut context = RewritingContext::new(unsafe{ &*module });
+  unsafe{ &*context.reduce(subject) }.symbol_ref().name.as_ref() == "True"
+}
+
+/// Whether the blanks of `condition` are all `variable`
+fn check_blanks(condition: &dyn Term, variable: SymbolPtr) -> Result<(), ExprError> {
+  if condition.is_variable() && !core::ptr::addr_eq(condition.symbol(), variable) {
+    return Err(ExprError::ForeignBlank {
+      blank: unsafe{ &*variable }.name.clone(),
+      other: condition.symbol_ref().name.clone()
+    });
+  }
+
+  condition.iter_args().try_for_each(|arg| check_blanks(arg, variable))
+}
+
+/// The instance of `condition`, whose only blank is the one being matched, with `binding` for the blank
+fn instantiate(condition: &dyn Term, binding: DagNodePtr) -> DagNodePtr {
+  if condition.is_variable() {
+    return binding;
+  }
+
+  let node     = FreeDagNode::new(condition.symbol());
+  let node_mut = unsafe{ &mut *node };
+  for arg in condition.iter_args() {
+    node_mut.insert_child_unchecked(instantiate(arg, binding));
+  }
+
+  node
+}
+
+fn binary(symbol: SymbolPtr, left: BxTerm, right: BxTerm) -> BxTerm {
+  FreeTerm::with_args(symbol, vec![left, right])
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::expr::{Expr, ExprAttribute, ExprEngine, ExprError};
+
+  #[test]
+  fn expressions_evaluate_with_definitions_and_attributes() {
+    let mut engine = ExprEngine::new();
+    engine.set_attributes("plus", &[ExprAttribute::Flat, ExprAttribute::Orderless]).unwrap();
+    engine.set_attributes("hold", &[ExprAttribute::HoldAll]).unwrap();
+
+    // double[x_] := pair[x, x]; id[x_] := x
+    engine.set_delayed(
+      &Expr::apply("double", vec![Expr::blank("x")]),
+      &Expr::apply("pair", vec![Expr::blank("x"), Expr::blank("x")])
+    ).unwrap();
+    engine.set_delayed(&Expr::apply("id", vec![Expr::blank("x")]), &Expr::blank("x")).unwrap();
+
+    let result = engine.evaluate(&Expr::apply("double", vec![Expr::apply("id", vec![Expr::symbol("a")])])).unwrap();
+    assert_eq!(result.to_string(), "pair[a, a]");
+
+    // Held arguments are not evaluated.
+    let held = Expr::apply("hold", vec![Expr::apply("id", vec![Expr::symbol("a")])]);
+    assert_eq!(engine.evaluate(&held).unwrap(), held);
+
+    // Flat applications nest underneath and flatten on the way back.
+    let sum = Expr::apply("plus", vec![Expr::symbol("a"), Expr::symbol("b"), Expr::symbol("c")]);
+    assert_eq!(engine.term_of(&sum).unwrap().iter_args().nth(1).unwrap().symbol_ref().name.as_ref(), "plus");
+    assert_eq!(Expr::from_term(engine.term_of(&sum).unwrap().as_ref()), sum);
+    assert_eq!(engine.evaluate(&sum).unwrap(), sum);
+
+    assert_eq!(
+      engine.term_of(&Expr::apply("pair", vec![Expr::symbol("a")])).err(),
+      Some(ExprError::WrongArity { head: "pair".into(), expected: 2, found: 1 })
+    );
+    assert_eq!(engine.term_of(&Expr::blank("a")).err(), Some(ExprError::NameClash("a".into())));
+    assert_eq!(
+      engine.set_attributes("id", &[ExprAttribute::HoldAll]).err(),
+      Some(ExprError::AlreadyInUse("id".into()))
+    );
+    assert!(matches!(
+      engine.set_delayed(&Expr::symbol("b"), &Expr::blank("y")),
+      Err(ExprError::UnboundVariable(_))
+    ));
+  }
+
+  #[test]
+  fn restricted_blanks_are_checked_while_matching() {
+    let mut engine = ExprEngine::new();
+    engine.register_test("atomic", |expr| matches!(expr, Expr::Symbol(_)));
+
+    // f[x_?atomic] := yes
+    let atomic = Expr::pattern_test(Expr::blank("x"), "atomic");
+    assert_eq!(atomic.to_string(), "x_?atomic");
+    engine.set_delayed(&Expr::apply("f", vec![atomic]), &Expr::symbol("yes")).unwrap();
+    assert_eq!(engine.evaluate(&Expr::apply("f", vec![Expr::symbol("a")])).unwrap(), Expr::symbol("yes"));
+    let compound = Expr::apply("f", vec![Expr::apply("s", vec![Expr::symbol("a")])]);
+    assert_eq!(engine.evaluate(&compound).unwrap(), compound);
+
+    // positive[s[x_]] := True; g[x_?positive] := yes; h[x_ /; positive[x]] := yes
+    engine.set_delayed(
+      &Expr::apply("positive", vec![Expr::apply("s", vec![Expr::blank("x")])]),
+      &Expr::symbol("True")
+    ).unwrap();
+    engine.set_delayed(
+      &Expr::apply("g", vec![Expr::pattern_test(Expr::blank("x"), "positive")]),
+      &Expr::symbol("yes")
+    ).unwrap();
+    let condition = Expr::condition(Expr::blank("x"), Expr::apply("positive", vec![Expr::blank("x")]));
+    assert_eq!(condition.to_string(), "x_ /; positive[x_]");
+    engine.set_delayed(&Expr::apply("h", vec![condition]), &Expr::symbol("yes")).unwrap();
+
+    let one = Expr::apply("s", vec![Expr::symbol("zero")]);
+    for head in ["g", "h"] {
+      assert_eq!(engine.evaluate(&Expr::apply(head, vec![one.clone()])).unwrap(), Expr::symbol("yes"));
+      let zero = Expr::apply(head, vec![Expr::symbol("zero")]);
+      assert_eq!(engine.evaluate(&zero).unwrap(), zero);
+    }
+
+    assert_eq!(
+      engine.term_of(&Expr::pattern_test(Expr::symbol("a"), "atomic")).err(),
+      Some(ExprError::NotABlank(Expr::symbol("a")))
+    );
+    assert_eq!(
+      engine.term_of(&Expr::condition(Expr::blank("x"), Expr::apply("positive", vec![Expr::blank("y")]))).err(),
+      Some(ExprError::ForeignBlank { blank: "x".into(), other: "y".into() })
+    );
+  }
+}
@@ -3,10 +3,46 @@
 There are different text representations possible for terms, DAGs, and so forth, that we want depending on the context.
 This module provides a unified API for formatting objects across the project.
 
+## Print Settings
+
+A `PrintSettings` limits how much of a term or DAG is printed, so that huge DAGs can be displayed:
+
+| Setting        | Effect                                                                                    |
+|:---------------|:------------------------------------------------------------------------------------------|
+| `max_depth`    | The arguments of a node at this depth are elided as `…`, as in `f(g(…))` for depth 1       |
+| `max_width`    | Only the first `max_width` arguments of a node are printed, as in `f(a, b, …)` for width 2 |
+| `elide_shared` | A shared subterm is printed once, labeled as in `#1=g(a)`, and as `#1` where it recurs     |
+
+Under `FormatStyle::Debug`, each DAG node is annotated with its theory and debug id, as in `<f₁>:free@12`. See
+`DagNode::debug_id`.
+
+Only DAGs have shared subterms. Constants are never elided as shared, as the back-reference would be no shorter. The
+settings are honored by every style through `Formattable::repr_with`; `Formattable::repr` prints everything. A
+statement or module passes the settings on to its terms.
+
 */
 
 
-use std::fmt::Display;
+use core::fmt::Display;
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+use crate::{
+  abstractions::{HashMap, Set},
+  api::{
+    dag_node::DagNode,
+    symbol::Symbol,
+    term::Term,
+    variable_theory::VariableTerm
+  },
+  core::{
+    latex::tree_to_latex,
+    pretty::PrettyPrinter,
+    theory_table::theory_entry
+  }
+};
+
+/// Stands for the arguments left out by `PrintSettings`
+pub const ELISION: &str = "…";
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 pub enum FormatStyle {
@@ -15,12 +51,47 @@ pub enum FormatStyle {
   Simple, // Use a simplified formatting
   Input,  // Format the term as a valid input expression, if possible.
   Debug,  // Format with extra debugging information
+  Pretty, // Format as a multi-line tree with ANSI colors, as laid out by `core::pretty::PrettyPrinter`
+  Latex,  // Format as LaTeX for math mode, as written by `core::latex`
+}
+
+/// Limits on how much of a term or DAG is printed. See the module documentation. The default prints everything.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PrintSettings {
+  /// The depth below which arguments are elided, the top being at depth 0
+  pub max_depth   : Option<usize>,
+  /// The number of arguments printed of each node
+  pub max_width   : Option<usize>,
+  /// Whether to print shared subterms once and refer back to them
+  pub elide_shared: bool,
+}
+
+impl PrintSettings {
+  pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+    self.max_depth = Some(max_depth);
+    self
+  }
+
+  pub fn with_max_width(mut self, max_width: usize) -> Self {
+    self.max_width = Some(max_width);
+    self
+  }
+
+  pub fn with_elide_shared(mut self, elide_shared: bool) -> Self {
+    self.elide_shared = elide_shared;
+    self
+  }
 }
 
 pub trait Formattable {
+  /// Writes a text representation of `self` according to the given `FormatStyle`, within the limits of `settings`.
+  fn repr_with(&self, style: FormatStyle, settings: &PrintSettings) -> String;
+
   /// Writes a text representation of `self` according to the given `FormatStyle`.
   /// Use `format!` and friends to create a string.
-  fn repr(&self, style: FormatStyle) -> String;
+  fn repr(&self, style: FormatStyle) -> String {
+    self.repr_with(style, &PrintSettings::default())
+  }
 }
 
 /**
@@ -28,20 +99,329 @@ Any type that implements `Formattable` can trivially implement `Display` as in t
 
 ```ignore
 impl Display for FreeTerm {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     (self as &dyn Term).fmt(f)
   }
 }
 ```
 */
 impl Display for dyn Formattable {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     write!(f, "{}", Formattable::repr(self, FormatStyle::Default))
   }
 }
 
 // impl Display for Box<dyn Formattable> {
-//   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+//   fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
 //     write!(f, "{}", &**self)
 //   }
 // }
+
+
+// region Print trees
+
+/// What the printers need of a node of a term or DAG.
+pub(crate) trait TreeNode: Sized {
+  fn symbol(&self) -> &'static Symbol;
+  fn children(&self) -> Vec<Self>;
+  /// The node's address if it can be shared, as DAG nodes can
+  fn address(&self) -> Option<*const u8>;
+  /// The text of the node without its arguments in `style`
+  fn head(&self, style: FormatStyle) -> String;
+}
+
+impl<'a> TreeNode for &'a dyn DagNode {
+  fn symbol(&self) -> &'static Symbol {
+    unsafe{ &*DagNode::symbol(*self) }
+  }
+
+  fn children(&self) -> Vec<Self> {
+    self.iter_args().map(|arg| unsafe{ &*arg } as &'a dyn DagNode).collect()
+  }
+
+  fn address(&self) -> Option<*const u8> {
+    Some(*self as *const dyn DagNode as *const u8)
+  }
+
+  fn head(&self, style: FormatStyle) -> String {
+    match style {
+      FormatStyle::Debug => {
+        format!("<{}>:{}@{}", self.symbol_ref(), theory_entry(self.core().theory()).name, self.debug_id())
+      }
+      _                  => match self.data() {
+        Some(atom) => atom.to_string(),
+        None       => self.symbol_ref().name.to_string()
+      }
+    }
+  }
+}
+
+impl TreeNode for &dyn Term {
+  fn symbol(&self) -> &'static Symbol {
+    self.symbol_ref()
+  }
+
+  fn children(&self) -> Vec<Self> {
+    self.iter_args().collect()
+  }
+
+  fn address(&self) -> Option<*const u8> {
+    None
+  }
+
+  fn head(&self, style: FormatStyle) -> String {
+    let name = &self.symbol_ref().name;
+    match (style, self.as_any().downcast_ref::<VariableTerm>()) {
+      (FormatStyle::Debug, Some(variable))              => format!("var<{}>#{}", name, variable.index()),
+      (FormatStyle::Default | FormatStyle::Debug, None) => format!("free<{}>", name),
+      _                                                 => name.to_string()
+    }
+  }
+}
+
+/// A term or DAG cut down to what `PrintSettings` lets through, from which the printer of each style writes text.
+pub(crate) struct PrintTree {
+  pub node     : PrintNode,
+  pub args     : Vec<PrintTree>,
+  /// Whether arguments were elided after `args`
+  pub truncated: bool,
+}
+
+pub(crate) enum PrintNode {
+  /// A node that is printed, with a label if it is shared and referred to later
+  Node {
+    symbol: &'static Symbol,
+    head  : String,
+    label : Option<usize>,
+  },
+  /// A shared node printed earlier with the given label
+  Reference(usize),
+}
+
+impl PrintTree {
+  /// Cuts `root` down according to `settings`, computing the heads of nodes in `style`.
+  pub fn new<N: TreeNode>(root: &N, style: FormatStyle, settings: &PrintSettings) -> PrintTree {
+    let mut builder = PrintTreeBuilder {
+      style,
+      settings,
+      shared: if settings.elide_shared { shared_nodes(root) } else { Set::default() },
+      labels: HashMap::default(),
+    };
+    let mut tree = builder.build(root, 0);
+
+    // Only the labels that are referred to are kept, numbered in the order they are printed.
+    if !builder.labels.is_empty() {
+      let mut referenced = Set::default();
+      tree.collect_references(&mut referenced);
+      tree.renumber(&referenced, &mut HashMap::default());
+    }
+
+    tree
+  }
+
+  /// The tree in prefix notation, `f(a, g(b))`.
+  pub fn to_prefix_string(&self) -> String {
+    let mut out = String::new();
+    self.write_prefix(&mut out);
+    out
+  }
+
+  fn write_prefix(&self, out: &mut String) {
+    match &self.node {
+      PrintNode::Reference(label) => {
+        out.push_str(&format!("#{}", label));
+      }
+
+      PrintNode::Node { head, label, .. } => {
+        if let Some(label) = label {
+          out.push_str(&format!("#{}=", label));
+        }
+        out.push_str(head);
+        if self.args.is_empty() && !self.truncated {
+          return;
+        }
+
+        out.push('(');
+        for (index, arg) in self.args.iter().enumerate() {
+          if index > 0 {
+            out.push_str(", ");
+          }
+          arg.write_prefix(out);
+        }
+        if self.truncated {
+          if !self.args.is_empty() {
+            out.push_str(", ");
+          }
+          out.push_str(ELISION);
+        }
+        out.push(')');
+      }
+    }
+  }
+
+  fn collect_references(&self, referenced: &mut Set<usize>) {
+    if let PrintNode::Reference(label) = self.node {
+      referenced.insert(label);
+    }
+    self.args.iter().for_each(|arg| arg.collect_references(referenced));
+  }
+
+  fn renumber(&mut self, referenced: &Set<usize>, numbers: &mut HashMap<usize, usize>) {
+    match &mut self.node {
+      PrintNode::Reference(label) => {
+        *label = numbers[label];
+      }
+
+      PrintNode::Node { label, .. } => {
+        *label = label.filter(|old| referenced.contains(old))
+                      .map(
+                        |old| {
+                          let new = numbers.len() + 1;
+                          numbers.insert(old, new);
+                          new
+                        }
+                      );
+      }
+    }
+    self.args.iter_mut().for_each(|arg| arg.renumber(referenced, numbers));
+  }
+}
+
+struct PrintTreeBuilder<'s> {
+  style   : FormatStyle,
+  settings: &'s PrintSettings,
+  /// The nodes reachable along more than one path, if shared nodes are elided
+  shared  : Set<*const u8>,
+  /// The labels of the shared nodes printed so far
+  labels  : HashMap<*const u8, usize>,
+}
+
+impl PrintTreeBuilder<'_> {
+  fn build<N: TreeNode>(&mut self, node: &N, depth: usize) -> PrintTree {
+    let children = node.children();
+
+    let mut label = None;
+    if let Some(address) = node.address().filter(|address| !children.is_empty() && self.shared.contains(address)) {
+      if let Some(&label) = self.labels.get(&address) {
+        return PrintTree { node: PrintNode::Reference(label), args: vec![], truncated: false };
+      }
+      let next = self.labels.len() + 1;
+      self.labels.insert(address, next);
+      label = Some(next);
+    }
+
+    let mut shown = children.len();
+    if self.settings.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+      shown = 0;
+    }
+    if let Some(max_width) = self.settings.max_width {
+      shown = shown.min(max_width);
+    }
+
+    PrintTree {
+      node     : PrintNode::Node { symbol: node.symbol(), head: node.head(self.style), label },
+      args     : children[..shown].iter().map(|child| self.build(child, depth + 1)).collect(),
+      truncated: shown < children.len(),
+    }
+  }
+}
+
+/// The nodes reachable from `root` along more than one path
+fn shared_nodes<N: TreeNode>(root: &N) -> Set<*const u8> {
+  let mut visited = Set::default();
+  let mut shared  = Set::default();
+  let mut pending = root.children();
+
+  while let Some(node) = pending.pop() {
+    let Some(address) = node.address() else { continue };
+    if !visited.insert(address) {
+      shared.insert(address);
+      continue;
+    }
+    pending.extend(node.children());
+  }
+
+  shared
+}
+
+/// Writes `term` in `style` within the limits of `settings`, for the `Formattable` impls of terms.
+pub(crate) fn format_term(term: &dyn Term, style: FormatStyle, settings: &PrintSettings) -> String {
+  let tree = PrintTree::new(&term, style, settings);
+  write_tree(&tree, style)
+}
+
+/// Writes the DAG rooted at `node` in `style` within the limits of `settings`, for the `Formattable` impl of DAG
+/// nodes.
+pub(crate) fn format_dag(node: &dyn DagNode, style: FormatStyle, settings: &PrintSettings) -> String {
+  let tree = PrintTree::new(&node, style, settings);
+  write_tree(&tree, style)
+}
+
+fn write_tree(tree: &PrintTree, style: FormatStyle) -> String {
+  match style {
+    FormatStyle::Pretty => PrettyPrinter::default().write(tree),
+    FormatStyle::Latex  => tree_to_latex(tree),
+    _                   => tree.to_prefix_string()
+  }
+}
+
+// endregion Print trees
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      dag_node::DagNode,
+      free_theory::FreeDagNode
+    },
+    core::{
+      format::{FormatStyle, Formattable, PrintSettings},
+      module::Module,
+      parser::parse_term,
+      pretty::PrettyPrinter,
+      RootContainer
+    }
+  };
+
+  #[test]
+  fn print_settings_limit_output() {
+    let module = Module::prelude_module("NAT").unwrap();
+    let symbol = |name: &str| module.arena.symbol(&IString::from(name)).unwrap();
+
+    // plus(times(s(0), 0), times(s(0), 0)) with the `times` node shared
+    let one     = FreeDagNode::new(symbol("s"));
+    unsafe{ &mut *one }.insert_child(FreeDagNode::new(symbol("0"))).unwrap();
+    let product = FreeDagNode::with_args(symbol("times"), &mut vec![one, FreeDagNode::new(symbol("0"))]);
+    let sum_ptr = FreeDagNode::with_args(symbol("plus"), &mut vec![product, product]);
+    let _root   = RootContainer::new(sum_ptr);
+    let sum: &dyn DagNode = unsafe{ &*sum_ptr };
+
+    let elide = PrintSettings::default().with_elide_shared(true);
+    assert_eq!(sum.repr(FormatStyle::Default), "plus(times(s(0), 0), times(s(0), 0))");
+    assert_eq!(sum.repr_with(FormatStyle::Default, &elide), "plus(#1=times(s(0), 0), #1)");
+    assert_eq!(
+      sum.repr_with(FormatStyle::Default, &PrintSettings::default().with_max_depth(1)),
+      "plus(times(…), times(…))"
+    );
+    assert_eq!(
+      sum.repr_with(FormatStyle::Default, &PrintSettings::default().with_max_width(1)),
+      "plus(times(s(0), …), …)"
+    );
+    assert_eq!(
+      PrettyPrinter::default().with_color(false).with_settings(elide).with_width(0).dag_to_tree(sum_ptr),
+      "plus\n├── #1=times\n│   ├── s\n│   │   ╰── 0\n│   ╰── 0\n╰── #1"
+    );
+    assert_eq!(
+      sum.repr_with(FormatStyle::Latex, &elide.with_max_depth(1)),
+      "\\mathsf{plus}(\\#1{=}\\mathsf{times}(\\ldots), \\#1)"
+    );
+
+    // Constants are never labeled, and terms have no sharing.
+    let term = parse_term(&module, "plus(s(0), s(0))").unwrap();
+    assert_eq!(term.repr_with(FormatStyle::Simple, &elide), "plus(s(0), s(0))");
+    assert_eq!(term.repr_with(FormatStyle::Simple, &elide.with_max_depth(1)), "plus(s(…), s(…))");
+    assert_eq!(term.repr(FormatStyle::Default), "free<plus>(free<s>(free<0>), free<s>(free<0>))");
+  }
+}
@@ -0,0 +1,176 @@
+/*!
+
+Generalization, or anti-unification, the dual of unification. A *generalization* of two terms is a term that both are
+instances of, and the *least general generalization* is the one every other generalization is more general than. For
+`f(a, g(a))` and `f(b, g(b))` it is `f(#1, g(#1))`, with `#1` bound to `a` in the first term and to `b` in the second,
+where `f(#1, g(#2))` would also be a generalization, but a more general one. Tools that speculate lemmas or mine
+patterns from a collection of terms are built on it.
+
+`Term::generalize` computes the least general generalization of two terms together with the two substitutions that
+take it back to each term. The terms are compared top down: where they agree, the generalization agrees with them, and
+each distinct pair of disagreeing subterms is replaced by a fresh variable, the same one for every occurrence of the
+pair. The fresh variables are named `#1`, `#2`, and so on, and are created by a `FreshVariables`, which must outlive the
+generalization.
+
+A fresh variable is given the least sort of which both of its subterms are members, if the subsort relation has one,
+and no sort otherwise. The terms are generalized syntactically: symbols with axioms are treated as free, so the result
+is a generalization modulo axioms too, but not necessarily the least general one.
+
+*/
+
+use core::cmp::Ordering;
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+  api::{
+    free_theory::FreeTerm,
+    symbol::SymbolPtr,
+    term::{BxTerm, Term},
+    variable_theory::VariableTerm
+  },
+  core::{
+    binders::FreshVariables,
+    sort::{Sort, SortPtr}
+  }
+};
+
+/// The values of the fresh variables of a generalization, in the order the variables were created
+pub type Bindings = Vec<(SymbolPtr, BxTerm)>;
+
+impl dyn Term {
+  /// The least general generalization of `self` and `other`, with the bindings of its fresh variables that give back
+  /// `self` and `other`, in that order. The fresh variables are created by `fresh`. See `core::generalization`.
+  pub fn generalize(&self, other: &dyn Term, fresh: &mut FreshVariables) -> (BxTerm, Bindings, Bindings) {
+    let mut generalizer = Generalizer {
+      fresh,
+      pairs: Vec::new(),
+      left : Vec::new(),
+      right: Vec::new(),
+    };
+    let generalization = generalizer.generalize(self, other);
+
+    (generalization, generalizer.left, generalizer.right)
+  }
+}
+
+struct Generalizer<'t, 'f> {
+  fresh: &'f mut FreshVariables,
+  /// The pairs of disagreeing subterms seen so far, with the variable each was replaced by
+  pairs: Vec<(&'t dyn Term, &'t dyn Term, SymbolPtr)>,
+  left : Bindings,
+  right: Bindings,
+}
+
+impl<'t> Generalizer<'t, '_> {
+  fn generalize(&mut self, a: &'t dyn Term, b: &'t dyn Term) -> BxTerm {
+    if a.compare(b) == Ordering::Equal {
+      return a.deep_copy();
+    }
+
+    if !a.is_variable() && !b.is_variable()
+        && core::ptr::addr_eq(a.symbol(), b.symbol())
+        && a.iter_args().count() == b.iter_args().count()
+    {
+      let mut term = FreeTerm::new(a.symbol());
+      term.args    = a.iter_args().zip(b.iter_args()).map(|(a, b)| self.generalize(a, b)).collect();
+      return Box::new(term);
+    }
+
+    let seen = self.pairs.iter().find(|(left, right, _)| {
+      left.compare(a) == Ordering::Equal && right.compare(b) == Ordering::Equal
+    });
+    let variable = match seen {
+      Some(&(_, _, variable)) => variable,
+      None                    => {
+        let variable = match least_common_supersort(a, b) {
+          Some(sort) => self.fresh.anonymous_sorted(sort),
+          None       => self.fresh.anonymous(),
+        };
+        self.pairs.push((a, b, variable));
+        self.left.push((variable, a.deep_copy()));
+        self.right.push((variable, b.deep_copy()));
+        variable
+      }
+    };
+
+    Box::new(VariableTerm::new(variable))
+  }
+}
+
+/// The least sort of which the sorts of `a` and `b` are both subsorts, if both terms have sorts and there is one
+fn least_common_supersort(a: &dyn Term, b: &dyn Term) -> Option<SortPtr> {
+  let a = unsafe{ &*a.compute_sort()? };
+  let b = unsafe{ &*b.compute_sort()? };
+  if a.kind.is_null() || a.kind != b.kind {
+    return None;
+  }
+
+  let common: Vec<&Sort> = unsafe{ &*a.kind }.sorts
+                                             .iter()
+                                             .map(|&sort| unsafe{ &*sort })
+                                             .filter(|sort| a.leq(sort) && b.leq(sort))
+                                             .collect();
+  common.iter()
+        .find(|least| common.iter().all(|sort| least.leq(sort)))
+        .map(|&least| least as *const Sort as SortPtr)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    binders::FreshVariables,
+    format::FormatStyle,
+    generalization::Bindings,
+    parser::{parse_module, parse_term}
+  };
+
+  fn show(bindings: &Bindings) -> Vec<String> {
+    bindings.iter()
+            .map(|(variable, value)| {
+              format!("{} := {}", unsafe{ &**variable }.name, value.repr(FormatStyle::Simple))
+            })
+            .collect()
+  }
+
+  #[test]
+  fn least_general_generalizations() {
+    let module = parse_module("
+      mod GENERALIZE is
+        sorts Zero Nat .
+        subsort Zero < Nat .
+        ops a b : -> Nat .
+        op 0 : -> Zero .
+        op s : Nat -> Nat .
+        op g : Nat -> Nat .
+        op f : Nat Nat -> Nat .
+      endm
+    ").unwrap();
+    let mut fresh      = FreshVariables::new();
+    let mut generalize = |left: &str, right: &str| {
+      let left  = parse_term(&module, left).unwrap();
+      let right = parse_term(&module, right).unwrap();
+      left.as_ref().generalize(right.as_ref(), &mut fresh)
+    };
+
+    // Repeated disagreements share a variable.
+    let (generalization, left, right) = generalize("f(a, g(a))", "f(b, g(b))");
+    assert_eq!(generalization.repr(FormatStyle::Simple), "f(#1, g(#1))");
+    assert_eq!((show(&left), show(&right)), (vec!["#1 := a".to_string()], vec!["#1 := b".to_string()]));
+
+    let (generalization, left, right) = generalize("f(a, g(b))", "f(b, g(a))");
+    assert_eq!(generalization.repr(FormatStyle::Simple), "f(#2, g(#3))");
+    assert_eq!(show(&right), ["#2 := b", "#3 := a"]);
+    assert_eq!(left.len(), 2);
+
+    // A fresh variable has the least sort of its subterms.
+    let (generalization, left, _) = generalize("s(0)", "s(s(0))");
+    assert_eq!(generalization.repr(FormatStyle::Simple), "s(#4)");
+    let sort = unsafe{ &*left[0].0 }.variable_sort().unwrap();
+    assert_eq!(unsafe{ &*sort }.name.as_ref(), "Nat");
+
+    let (generalization, left, _) = generalize("g(a)", "g(a)");
+    assert_eq!(generalization.repr(FormatStyle::Simple), "g(a)");
+    assert!(left.is_empty());
+  }
+}
@@ -0,0 +1,296 @@
+/*!
+
+Parsing a module again after its source is edited, reusing the statements that didn't change, for editors that parse
+on every keystroke:
+
+```ignore
+let mut parser = IncrementalParser::new();
+parser.parse(source)?;
+let reparse = parser.parse(edited_source)?;
+for line in reparse.changed {
+  println!("recompiled the statement on line {}", line);
+}
+let module = parser.module().unwrap();
+```
+
+The source is split into declarations at their periods, and two versions of a declaration are the same if they have
+the same tokens, so edits to whitespace and comments change nothing. If the module's header and every declaration
+other than its equations and rules, like its sorts, operators, variables, and imports, are the same as in the previous
+source, the module is kept: the equations and rules that are gone are removed from it, the new or edited ones are
+parsed and compiled, and the others are kept as they were compiled. Otherwise the module is parsed from scratch, as it
+is the first time. `Reparse` says which statements were parsed.
+
+Each statement is parsed over the whole signature, so unlike `parse_module`, a statement can use an operator or
+variable declared after it. The on-the-fly variables of removed statements stay in the module's arena until the
+module is next parsed from scratch. If the source doesn't parse, the error is returned and the parser forgets the
+module, so the next source is parsed from scratch.
+
+*/
+
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+use mod2lib_syntax::{tokenize, Token};
+
+use crate::{
+  abstractions::{HashMap, IString},
+  core::{
+    module::Module,
+    parser::{parse_module_tokens, parse_statement, ParseError}
+  }
+};
+
+/// The tokens of a declaration paired with their lines
+type Declaration = Vec<(Token, usize)>;
+
+/// What `IncrementalParser::parse` parsed
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub struct Reparse {
+  /// Whether the module was parsed from scratch
+  pub full   : bool,
+  /// The lines of the source on which the equations and rules that were parsed and compiled begin, in order. After a
+  /// parse from scratch, these are all of them.
+  pub changed: Vec<usize>,
+  /// The lines of the previous source on which the equations and rules that were removed began, in order
+  pub removed: Vec<usize>,
+}
+
+/// An equation or rule of the last source parsed
+struct Statement {
+  /// The statement's tokens, without their lines
+  tokens     : Vec<Token>,
+  line       : usize,
+  is_rule    : bool,
+  /// Whether parsing the statement added it to the module, which it doesn't if it is a variant of an earlier one
+  contributed: bool,
+}
+
+/// Parses successive versions of the source of a module. See the module documentation.
+#[derive(Default)]
+pub struct IncrementalParser {
+  /// The sources of the modules that can be imported besides the prelude's, as for `parse_module_in`
+  library   : HashMap<IString, String>,
+  module    : Option<Module>,
+  /// The tokens of the header and the declarations other than statements of the last source parsed, in order
+  signature : Vec<Vec<Token>>,
+  /// The statements of the last source parsed, in order
+  statements: Vec<Statement>,
+}
+
+impl IncrementalParser {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// An incremental parser resolving imports in `library`, as `parse_module_in` does
+  pub fn with_library(library: HashMap<IString, String>) -> Self {
+    IncrementalParser { library, ..Self::default() }
+  }
+
+  /// The module parsed from the last source, unless that source failed to parse
+  #[inline(always)]
+  pub fn module(&self) -> Option<&Module> {
+    self.module.as_ref()
+  }
+
+  /// Parses `source`, which holds exactly one module, reusing what it can of the previous source. See the module
+  /// documentation.
+  pub fn parse(&mut self, source: &str) -> Result<Reparse, ParseError> {
+    let (signature, statements) = split_declarations(tokenize(source));
+    let signature_tokens: Vec<Vec<Token>> = signature.iter().map(|declaration| without_lines(declaration)).collect();
+
+    let result = match self.module.is_some() && signature_tokens == self.signature {
+      true  => self.reparse_statements(statements),
+      false => self.parse_from_scratch(signature, statements),
+    };
+    match result {
+      Ok(_)  => self.signature = signature_tokens,
+      Err(_) => {
+        self.module = None;
+        self.signature.clear();
+        self.statements.clear();
+      }
+    }
+
+    result
+  }
+
+  fn parse_from_scratch(
+    &mut self,
+    signature : Vec<Declaration>,
+    statements: Vec<Declaration>
+  ) -> Result<Reparse, ParseError> {
+    let removed = self.statements.drain(..).map(|statement| statement.line).collect();
+    self.module = None;
+
+    let mut module = parse_module_tokens(signature.into_iter().flatten().collect(), &self.library)?;
+    let mut parsed = Vec::with_capacity(statements.len());
+    for declaration in statements {
+      parsed.push(add_statement(&mut module, declaration)?);
+    }
+    module.compile_statements().expect("statements are checked as they are parsed");
+
+    let changed     = parsed.iter().map(|statement| statement.line).collect();
+    self.statements = parsed;
+    self.module     = Some(module);
+
+    Ok(Reparse { full: true, changed, removed })
+  }
+
+  /// Rebuilds the module's equations and rules from `declarations`, keeping the compiled statements whose tokens are
+  /// unchanged and parsing the others.
+  fn reparse_statements(&mut self, declarations: Vec<Declaration>) -> Result<Reparse, ParseError> {
+    let module = self.module.as_mut().unwrap();
+
+    // The module's own statements follow the imported ones, in the order of the source.
+    let own_equations = self.statements.iter().filter(|statement| statement.contributed && !statement.is_rule).count();
+    let own_rules     = self.statements.iter().filter(|statement| statement.contributed && statement.is_rule).count();
+    let mut equations = module.equations.split_off(module.equations.len() - own_equations).into_iter();
+    let mut rules     = module.rules.split_off(module.rules.len() - own_rules).into_iter();
+
+    // The old statements by their tokens, with their compiled forms, in order
+    let mut previous: HashMap<Vec<Token>, Vec<_>> = HashMap::default();
+    for statement in self.statements.drain(..) {
+      let compiled = match (statement.contributed, statement.is_rule) {
+        (false, _)    => None,
+        (true, false) => equations.next(),
+        (true, true)  => rules.next(),
+      };
+      previous.entry(statement.tokens.clone()).or_default().push(Some((statement, compiled)));
+    }
+
+    let mut reparse = Reparse::default();
+    for declaration in declarations {
+      let tokens = without_lines(&declaration);
+      let reused = previous.get_mut(&tokens).and_then(|matches| matches.iter_mut().find_map(Option::take));
+      match reused {
+        Some((statement, compiled)) => {
+          let line = declaration[0].1;
+          if let Some(compiled) = compiled {
+            match statement.is_rule {
+              true  => module.rules.push(compiled),
+              false => module.equations.push(compiled),
+            }
+          }
+          self.statements.push(Statement { line, ..statement });
+        }
+        None => {
+          let statement = add_statement(module, declaration)?;
+          reparse.changed.push(statement.line);
+          self.statements.push(statement);
+        }
+      }
+    }
+    module.compile_statements().expect("statements are checked as they are parsed");
+
+    reparse.removed = previous.into_values().flatten().flatten().map(|(statement, _)| statement.line).collect();
+    reparse.removed.sort_unstable();
+
+    Ok(reparse)
+  }
+}
+
+/// Parses the equation or rule `declaration` into `module`.
+fn add_statement(module: &mut Module, declaration: Declaration) -> Result<Statement, ParseError> {
+  let tokens  = without_lines(&declaration);
+  let line    = declaration[0].1;
+  let is_rule = tokens[0] == Token::Word("rl".to_string());
+  let count   = module.equations.len() + module.rules.len();
+  parse_statement(module, declaration)?;
+
+  Ok(Statement { tokens, line, is_rule, contributed: module.equations.len() + module.rules.len() > count })
+}
+
+/// Splits the tokens of a module into its signature, which is its header, its declarations other than equations and
+/// rules, and its end, and its equations and rules. A declaration runs to its period, or to the end of the source if
+/// the period is missing.
+fn split_declarations(tokens: Vec<(Token, usize)>) -> (Vec<Declaration>, Vec<Declaration>) {
+  let is_word        = |token: &Token, words: &[&str]| {
+    matches!(token, Token::Word(word) if words.contains(&word.as_str()))
+  };
+  let mut tokens     = tokens.into_iter().peekable();
+  let mut signature  = vec![tokens.by_ref().take(3).collect::<Declaration>()];
+  let mut statements = Vec::new();
+
+  while let Some((token, _)) = tokens.peek() {
+    if is_word(token, &["endm", "endfm"]) {
+      signature.push(tokens.by_ref().collect());
+      break;
+    }
+
+    let mut declaration = Vec::new();
+    for (token, line) in tokens.by_ref() {
+      let period = token == Token::Period;
+      declaration.push((token, line));
+      if period {
+        break;
+      }
+    }
+    match is_word(&declaration[0].0, &["eq", "rl"]) {
+      true  => statements.push(declaration),
+      false => signature.push(declaration),
+    }
+  }
+
+  (signature, statements)
+}
+
+fn without_lines(declaration: &[(Token, usize)]) -> Vec<Token> {
+  declaration.iter().map(|(token, _)| token.clone()).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    incremental_parser::{IncrementalParser, Reparse},
+    parser::parse_term,
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext
+  };
+
+  const PEANO: &str = "
+    mod PEANO is
+      sort Nat .
+      ops 0 : -> Nat [ctor] .
+      op s : Nat -> Nat [ctor] .
+      op plus : Nat Nat -> Nat .
+      vars X Y : Nat .
+      eq plus(0, Y) = Y .
+      eq plus(s(X), Y) = s(plus(X, Y)) .
+    endm
+  ";
+
+  fn reduce(parser: &IncrementalParser, term: &str) -> String {
+    let module      = parser.module().unwrap();
+    let subject     = parse_term(module, term).unwrap().term_to_dag(false);
+    let mut context = RewritingContext::new(module);
+    dag_to_string(context.reduce(subject))
+  }
+
+  #[test]
+  fn only_edited_statements_are_parsed() {
+    let mut parser = IncrementalParser::new();
+    assert_eq!(parser.parse(PEANO), Ok(Reparse { full: true, changed: vec![8, 9], removed: vec![] }));
+    assert_eq!(reduce(&parser, "plus(s(0), s(0))"), "s(s(0))");
+
+    // Comments and spacing change nothing.
+    let spaced = PEANO.replace("eq plus(0, Y) = Y .", "*** base case\n      eq plus(0,Y) = Y.");
+    assert_eq!(parser.parse(&spaced), Ok(Reparse { full: false, changed: vec![], removed: vec![] }));
+
+    // Adding on the right instead of the left replaces the second equation.
+    let edited = spaced.replace("eq plus(s(X), Y) = s(plus(X, Y)) .", "eq plus(X, s(Y)) = s(plus(X, Y)) .");
+    let edited = edited.replace("eq plus(0,Y) = Y.", "eq plus(Y, 0) = Y .");
+    assert_eq!(parser.parse(&edited), Ok(Reparse { full: false, changed: vec![9, 10], removed: vec![9, 10] }));
+    assert_eq!(reduce(&parser, "plus(s(0), s(s(0)))"), "s(s(s(0)))");
+    assert_eq!(parser.module().unwrap().equations.len(), 2);
+
+    // A new operator changes the signature.
+    let extended = edited.replace("op plus", "op double : Nat -> Nat .\n      op plus");
+    assert_eq!(parser.parse(&extended).map(|reparse| reparse.full), Ok(true));
+
+    let broken = extended.replace("eq plus(Y, 0) = Y .", "eq plus(Y, 0) = Z .");
+    assert_eq!(parser.parse(&broken).unwrap_err().line, 10);
+    assert!(parser.module().is_none());
+    assert_eq!(parser.parse(&extended).map(|reparse| reparse.full), Ok(true));
+  }
+}
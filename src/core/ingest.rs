@@ -0,0 +1,213 @@
+/*!
+
+Bulk construction of DAGs from flat arrays, for programs that generate terms at scale, like translators and machine
+learning pipelines. Rather than building a `Term` per node and converting it, the caller describes the term as an
+array of `NodeRecord`s in post-order, each node after its arguments, and `ingest` builds the DAG bottom-up with a stack,
+allocating nothing per node but the node itself and its argument vector.
+
+ - A `NodeRecord::Symbol` names its symbol by its index in a symbol array and takes the `arity` nodes before it, in
+   order, as its arguments.
+ - A `NodeRecord::Data` names a data atom by its index in a data atom array.
+ - A `NodeRecord::Shared` reuses the node built by an earlier record, so a DAG with shared subterms is described
+   without repeating them.
+
+The symbols and data atoms are borrowed, not copied. A `DataDagNode` owns its atom, and atoms can't be copied, so a
+data atom becomes a constant headed by the atom's symbol, without the atom's value.
+
+The nodes are not rooted. As with every DAG, protect the result with a `RootContainer` before anything can collect
+garbage.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+  abstractions::IString,
+  api::{
+    atom::DataAtom,
+    dag_node::DagNodePtr,
+    free_theory::FreeDagNode,
+    symbol::SymbolPtr
+  }
+};
+
+/// One node of a term in post-order. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NodeRecord {
+  /// A node headed by the symbol at index `symbol` with the `arity` nodes before it as its arguments
+  Symbol { symbol: u32, arity: u32 },
+  /// The data atom at this index
+  Data(u32),
+  /// The node built by the record at this earlier position
+  Shared(u32),
+}
+
+/// Returned by `ingest` for records that don't describe a term. Positions are indices into the records.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum IngestError {
+  UnknownSymbol { position: usize, symbol: u32 },
+  UnknownData { position: usize, index: u32 },
+  /// A `Shared` record refers to itself or a later record.
+  ForwardReference { position: usize, target: u32 },
+  /// The symbol doesn't take `arity` arguments.
+  WrongArity { position: usize, symbol: IString, arity: u32 },
+  /// Fewer nodes were built than the record takes as arguments.
+  MissingArguments { position: usize, arity: u32, available: usize },
+  /// The records describe `count` terms rather than one.
+  TermCount(usize),
+}
+
+impl Display for IngestError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      IngestError::UnknownSymbol { position, symbol } => {
+        write!(f, "record {} names symbol {}, which doesn't exist", position, symbol)
+      }
+      IngestError::UnknownData { position, index } => {
+        write!(f, "record {} names data atom {}, which doesn't exist", position, index)
+      }
+      IngestError::ForwardReference { position, target } => {
+        write!(f, "record {} shares record {}, which isn't built yet", position, target)
+      }
+      IngestError::WrongArity { position, symbol, arity } => {
+        write!(f, "record {} gives {} arguments to {}", position, arity, symbol)
+      }
+      IngestError::MissingArguments { position, arity, available } => {
+        write!(f, "record {} takes {} arguments, but only {} nodes precede it", position, arity, available)
+      }
+      IngestError::TermCount(count) => write!(f, "the records describe {} terms rather than one", count),
+    }
+  }
+}
+
+impl Error for IngestError {}
+
+/// Builds the DAG described by `records`, which must describe exactly one term. See the module documentation.
+pub fn ingest(symbols: &[SymbolPtr], data: &[Box<dyn DataAtom>], records: &[NodeRecord])
+    -> Result<DagNodePtr, IngestError>
+{
+  let roots = ingest_all(symbols, data, records)?;
+  match roots.as_slice() {
+    [root] => Ok(*root),
+    _      => Err(IngestError::TermCount(roots.len())),
+  }
+}
+
+/// Builds the DAGs described by `records`, which may describe any number of terms one after the other, returning
+/// their roots in order.
+pub fn ingest_all(symbols: &[SymbolPtr], data: &[Box<dyn DataAtom>], records: &[NodeRecord])
+    -> Result<Vec<DagNodePtr>, IngestError>
+{
+  let mut stack: Vec<DagNodePtr> = Vec::new();
+  // The node built by each record, for `Shared` records
+  let mut built: Vec<DagNodePtr> = Vec::with_capacity(records.len());
+
+  for (position, &record) in records.iter().enumerate() {
+    let node = match record {
+      NodeRecord::Symbol { symbol: id, arity } => {
+        let symbol = *symbols.get(id as usize).ok_or(IngestError::UnknownSymbol { position, symbol: id })?;
+        let symbol_ref = unsafe{ &*symbol };
+        if !symbol_ref.arity.accepts(arity as usize) {
+          return Err(IngestError::WrongArity { position, symbol: symbol_ref.name.clone(), arity });
+        }
+        if stack.len() < arity as usize {
+          return Err(IngestError::MissingArguments { position, arity, available: stack.len() });
+        }
+
+        let node = FreeDagNode::new(symbol);
+        for arg in stack.drain(stack.len() - arity as usize..) {
+          unsafe{ &mut *node }.insert_child_unchecked(arg);
+        }
+        node
+      }
+
+      NodeRecord::Data(index) => {
+        let atom = data.get(index as usize).ok_or(IngestError::UnknownData { position, index })?;
+        FreeDagNode::new(atom.symbol())
+      }
+
+      NodeRecord::Shared(target) => {
+        *built.get(target as usize).ok_or(IngestError::ForwardReference { position, target })?
+      }
+    };
+
+    built.push(node);
+    stack.push(node);
+  }
+
+  Ok(stack)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::any::Any;
+
+  use paste::paste;
+
+  use crate::{
+    abstractions::{IString, Lazy},
+    api::{
+      atom::{implement_data_atom, Atom, DataAtom},
+      symbol::{Symbol, SymbolAttribute, SymbolPtr, SymbolType},
+      Arity
+    },
+    core::{
+      ingest::{ingest, ingest_all, IngestError, NodeRecord},
+      parser::parse_module,
+      proof_trace::dag_to_string,
+      RootContainer
+    }
+  };
+
+  implement_data_atom!(Count, u32);
+
+  #[test]
+  fn dags_are_built_from_records() {
+    let module = parse_module("
+      mod INGEST is
+        sort S .
+        op a : -> S .
+        op g : S -> S .
+        op f : S S -> S .
+      endm
+    ").unwrap();
+    let symbols: Vec<SymbolPtr> = ["a", "g", "f"].iter()
+                                                  .map(|&name| module.arena.symbol(&name.into()).unwrap())
+                                                  .collect();
+    let data = vec![match CountAtom::new_atom(7) { Atom::Data(atom) => atom, _ => unreachable!() }];
+
+    // f(g(a), g(a)), with g(a) shared, then Count
+    let records = [
+      NodeRecord::Symbol { symbol: 0, arity: 0 },
+      NodeRecord::Symbol { symbol: 1, arity: 1 },
+      NodeRecord::Shared(1),
+      NodeRecord::Symbol { symbol: 2, arity: 2 },
+      NodeRecord::Data(0),
+    ];
+    let roots = ingest_all(&symbols, &data, &records).unwrap();
+    let _root = RootContainer::new(roots[0]);
+    assert_eq!(dag_to_string(roots[0]), "f(g(a), g(a))");
+    let args: Vec<_> = unsafe{ &*roots[0] }.iter_args().collect();
+    assert!(std::ptr::addr_eq(args[0], args[1]));
+    assert_eq!(dag_to_string(roots[1]), "Count");
+
+    assert_eq!(ingest(&symbols, &data, &records), Err(IngestError::TermCount(2)));
+    assert_eq!(
+      ingest(&symbols, &data, &[NodeRecord::Symbol { symbol: 2, arity: 1 }]),
+      Err(IngestError::WrongArity { position: 0, symbol: IString::from("f"), arity: 1 })
+    );
+    assert_eq!(
+      ingest(&symbols, &data, &[NodeRecord::Symbol { symbol: 1, arity: 1 }]),
+      Err(IngestError::MissingArguments { position: 0, arity: 1, available: 0 })
+    );
+    assert_eq!(
+      ingest(&symbols, &data, &[NodeRecord::Shared(0)]),
+      Err(IngestError::ForwardReference { position: 0, target: 0 })
+    );
+  }
+}
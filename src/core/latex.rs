@@ -0,0 +1,288 @@
+/*!
+
+LaTeX output for terms, statements, and modules, used for `FormatStyle::Latex`. The output is meant for math mode.
+
+By default, an operator is written in sans serif in prefix notation, $\mathsf{plus}(\mathsf{0}, N)$, and a variable in
+italics. A symbol can be given its own LaTeX with the `latex` attribute of its declaration, in which `#1`, `#2`, …
+stand for its arguments:
+
+```text
+op plus : Nat Nat -> Nat [latex "#1 + #2"] .
+op 0 : -> Zero [latex "0"] .
+```
+
+A macro without placeholders is written in place of the operator's name and followed by the arguments in parentheses.
+
+Arguments elided by `PrintSettings` are written as `\ldots`. A shared subterm elided by `PrintSettings` is labeled
+`\#1{=}` where it is printed and written `\#1` where it recurs.
+
+A module is written as an `array` with one declaration or statement per row, suitable for a `displaymath`
+environment. On-the-fly variables, like `N:Nat`, are not declared.
+
+*/
+
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+use crate::{
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::Symbol,
+    term::{BxTerm, Term}
+  },
+  core::{
+    format::{FormatStyle, PrintNode, PrintSettings, PrintTree},
+    module::Module,
+    pre_equation::{condition::Condition, PreEquation, PreEquationKind},
+    sort::{collection::SortId, SortPtr}
+  }
+};
+
+/// Stands for elided arguments
+const ELISION: &str = "\\ldots";
+
+/// Escapes the characters of `text` that are special to LaTeX.
+pub fn escape(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '\\'                                  => out.push_str("\\backslash{}"),
+      '{' | '}' | '_' | '#' | '$' | '%' | '&' => { out.push('\\'); out.push(c); }
+      '^'                                   => out.push_str("\\hat{}"),
+      '~'                                   => out.push_str("\\sim{}"),
+      c                                     => out.push(c),
+    }
+  }
+  out
+}
+
+/// The LaTeX for `symbol` on its own: its macro if it has one without placeholders, otherwise its name.
+pub fn symbol_to_latex(symbol: &Symbol) -> String {
+  match &symbol.latex {
+    Some(latex) if !latex.contains('#') => latex.to_string(),
+    _ if symbol.is_variable()           => format!("\\mathit{{{}}}", escape(&symbol.name)),
+    _                                   => format!("\\mathsf{{{}}}", escape(&symbol.name)),
+  }
+}
+
+/// The LaTeX for `term` within the limits of `settings`.
+pub fn term_to_latex(term: &dyn Term, settings: &PrintSettings) -> String {
+  tree_to_latex(&PrintTree::new(&term, FormatStyle::Latex, settings))
+}
+
+/// The LaTeX for the DAG rooted at `node` within the limits of `settings`, written as a tree.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn dag_to_latex(node: DagNodePtr, settings: &PrintSettings) -> String {
+  let node: &dyn DagNode = unsafe{ &*node };
+  tree_to_latex(&PrintTree::new(&node, FormatStyle::Latex, settings))
+}
+
+/// The LaTeX for a tree that has already been cut down to its print settings.
+pub(crate) fn tree_to_latex(tree: &PrintTree) -> String {
+  match &tree.node {
+    PrintNode::Reference(label) => format!("\\#{}", label),
+
+    PrintNode::Node { symbol, label, .. } => {
+      let mut args: Vec<String> = tree.args.iter().map(tree_to_latex).collect();
+      if tree.truncated {
+        args.push(ELISION.to_string());
+      }
+      let application = application(symbol, args);
+      match label {
+        Some(label) => format!("\\#{}{{=}}{}", label, application),
+        None        => application
+      }
+    }
+  }
+}
+
+/// The LaTeX for `statement` within the limits of `settings`, without its attributes.
+pub fn statement_to_latex(statement: &PreEquation, settings: &PrintSettings) -> String {
+  let conditional = !statement.conditions.is_empty();
+  let mut out = match (&statement.kind, conditional) {
+    (PreEquationKind::Equation { .. }, false)   => "\\mathbf{eq}\\ ".to_string(),
+    (PreEquationKind::Equation { .. }, true)    => "\\mathbf{ceq}\\ ".to_string(),
+    (PreEquationKind::Rule { .. }, false)       => "\\mathbf{rl}\\ ".to_string(),
+    (PreEquationKind::Rule { .. }, true)        => "\\mathbf{crl}\\ ".to_string(),
+    (PreEquationKind::Membership { .. }, false) => "\\mathbf{mb}\\ ".to_string(),
+    (PreEquationKind::Membership { .. }, true)  => "\\mathbf{cmb}\\ ".to_string(),
+  };
+
+  if let Some(name) = &statement.name {
+    out.push_str(&format!("[\\mathit{{{}}}] :\\ ", escape(name)));
+  }
+
+  out.push_str(&term_to_latex(statement.lhs_term.as_ref(), settings));
+  match &statement.kind {
+    PreEquationKind::Equation { rhs_term } => {
+      out.push_str(" = ");
+      out.push_str(&term_to_latex(rhs_term.as_ref(), settings));
+    }
+    PreEquationKind::Rule { rhs_term } => {
+      out.push_str(" \\Rightarrow ");
+      out.push_str(&term_to_latex(rhs_term.as_ref(), settings));
+    }
+    PreEquationKind::Membership { sort_spec } => {
+      out.push_str(&format!(" : \\mathsf{{{}}}", escape(&sort_spec.to_string())));
+    }
+  }
+
+  if conditional {
+    let conditions: Vec<String> = statement.conditions
+                                           .iter()
+                                           .map(|condition| condition_to_latex(condition, settings))
+                                           .collect();
+    out.push_str("\\ \\mathbf{if}\\ ");
+    out.push_str(&conditions.join(" \\wedge "));
+  }
+  out.push_str("\\ .");
+
+  out
+}
+
+/// The LaTeX for `module`, an `array` with a row for each sort, subsort, operator, and variable declaration and each
+/// statement. The terms of statements are written within the limits of `settings`.
+pub fn module_to_latex(module: &Module, settings: &PrintSettings) -> String {
+  let mut rows = vec![format!("\\mathbf{{mod}}\\ \\mathsf{{{}}}\\ \\mathbf{{is}}", escape(&module.name))];
+
+  let sorts: Vec<SortPtr> = (0..module.arena.sorts.len() as u32)
+      .map(|id| module.arena.sorts.get(SortId(id)))
+      .collect();
+  if !sorts.is_empty() {
+    let names: Vec<String> = sorts.iter().map(|&sort| sort_to_latex(sort)).collect();
+    let keyword = if sorts.len() == 1 { "sort" } else { "sorts" };
+    rows.push(format!("\\quad \\mathbf{{{}}}\\ {}\\ .", keyword, names.join("\\ ")));
+  }
+  for &sort in sorts.iter() {
+    for &subsort in unsafe{ &*sort }.subsorts.iter() {
+      rows.push(format!("\\quad \\mathbf{{subsort}}\\ {} < {}\\ .", sort_to_latex(subsort), sort_to_latex(sort)));
+    }
+  }
+
+  let mut symbols: Vec<&Symbol> = module.arena.symbols.values().map(|&symbol| unsafe{ &*symbol }).collect();
+  symbols.sort_by(|a, b| a.compare(b));
+  for symbol in symbols.iter().filter(|symbol| !symbol.is_variable()) {
+    for declaration in symbol.sort_table.declarations() {
+      let domain: Vec<String> = declaration.domain.iter().map(|&sort| sort_to_latex(sort)).collect();
+      let domain = match domain.is_empty() {
+        true  => String::new(),
+        false => format!("{}\\ ", domain.join("\\ ")),
+      };
+      let ctor   = if declaration.is_constructor { "\\ [\\mathrm{ctor}]" } else { "" };
+      rows.push(
+        format!(
+          "\\quad \\mathbf{{op}}\\ \\mathsf{{{}}} : {}\\to {}{}\\ .",
+          escape(&symbol.name), domain, sort_to_latex(declaration.range), ctor
+        )
+      );
+    }
+  }
+  for symbol in symbols.iter().filter(|symbol| symbol.is_variable() && !symbol.name.contains(':')) {
+    if let Some(sort) = symbol.variable_sort() {
+      rows.push(format!("\\quad \\mathbf{{var}}\\ {} : {}\\ .", symbol_to_latex(symbol), sort_to_latex(sort)));
+    }
+  }
+
+  for statement in module.membership.iter().chain(module.equations.iter()).chain(module.rules.iter()) {
+    rows.push(format!("\\quad {}", statement_to_latex(statement, settings)));
+  }
+  rows.push("\\mathbf{endm}".to_string());
+
+  format!("\\begin{{array}}{{l}}\n{}\n\\end{{array}}", rows.join("\\\\\n"))
+}
+
+/// The application of `symbol` to arguments already written in LaTeX.
+fn application(symbol: &Symbol, args: Vec<String>) -> String {
+  if let Some(latex) = symbol.latex.as_ref().filter(|latex| latex.contains('#')) {
+    return substitute(latex, &args);
+  }
+
+  let head = symbol_to_latex(symbol);
+  match args.is_empty() {
+    true  => head,
+    false => format!("{}({})", head, args.join(", "))
+  }
+}
+
+/// Replaces the placeholders `#1` through `#9` of `latex` with `args`. A placeholder without an argument, which was
+/// elided, is replaced with an ellipsis.
+fn substitute(latex: &str, args: &[String]) -> String {
+  let mut out   = String::with_capacity(latex.len());
+  let mut chars = latex.chars().peekable();
+  while let Some(c) = chars.next() {
+    let argument = match (c, chars.peek().and_then(|next| next.to_digit(10))) {
+      ('#', Some(digit)) if digit >= 1 => Some(args.get(digit as usize - 1).map_or(ELISION, String::as_str)),
+      _ => None
+    };
+    match argument {
+      Some(argument) => {
+        chars.next();
+        out.push_str(argument);
+      }
+      None => out.push(c)
+    }
+  }
+  out
+}
+
+fn condition_to_latex(condition: &Condition, settings: &PrintSettings) -> String {
+  let latex = |term: &BxTerm| term_to_latex(term.as_ref(), settings);
+  match condition {
+    Condition::Equality { lhs_term, rhs_term }   => format!("{} = {}", latex(lhs_term), latex(rhs_term)),
+    Condition::SortMembership { lhs_term, sort } => {
+      format!("{} : \\mathsf{{{}}}", latex(lhs_term), escape(&sort.to_string()))
+    }
+    Condition::Match { lhs_term, rhs_term }      => format!("{} := {}", latex(lhs_term), latex(rhs_term)),
+    Condition::Rewrite { lhs_term, rhs_term }    => format!("{} \\Rightarrow {}", latex(lhs_term), latex(rhs_term)),
+  }
+}
+
+fn sort_to_latex(sort: SortPtr) -> String {
+  format!("\\mathsf{{{}}}", escape(&unsafe{ &*sort }.name))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    format::{FormatStyle, Formattable},
+    latex::escape,
+    parser::{parse_module, parse_term}
+  };
+
+  #[test]
+  fn latex_macros_and_modules() {
+    let module = parse_module(r##"
+      mod PLUS is
+        sorts Nat .
+        op 0 : -> Nat [ctor latex "0"] .
+        op s : Nat -> Nat [ctor latex "#1 + 1"] .
+        op plus : Nat Nat -> Nat .
+        op half_of : Nat -> Nat .
+        vars N M : Nat .
+        eq [plus-s] : plus(s(N), M) = s(plus(N, M)) .
+      endm
+    "##).unwrap();
+
+    let term = parse_term(&module, "plus(s(0), half_of(M))").unwrap();
+    assert_eq!(term.repr(FormatStyle::Latex), "\\mathsf{plus}(0 + 1, \\mathsf{half\\_of}(\\mathit{M}))");
+    assert_eq!(
+      unsafe{ &*term.term_to_dag(false) }.repr(FormatStyle::Latex),
+      "\\mathsf{plus}(0 + 1, \\mathsf{half\\_of}(\\mathit{M}))"
+    );
+    assert_eq!(
+      module.equations[0].repr(FormatStyle::Latex),
+      "\\mathbf{eq}\\ [\\mathit{plus-s}] :\\ \\mathsf{plus}(\\mathit{N} + 1, \\mathit{M}) = \
+       \\mathsf{plus}(\\mathit{N}, \\mathit{M}) + 1\\ ."
+    );
+
+    let latex = module.repr(FormatStyle::Latex);
+    assert!(latex.starts_with("\\begin{array}{l}\n\\mathbf{mod}\\ \\mathsf{PLUS}\\ \\mathbf{is}\\\\\n"));
+    assert!(
+      latex.contains("\\quad \\mathbf{op}\\ \\mathsf{s} : \\mathsf{Nat}\\ \\to \\mathsf{Nat}\\ [\\mathrm{ctor}]\\ .")
+    );
+    assert!(latex.contains("\\quad \\mathbf{var}\\ \\mathit{N} : \\mathsf{Nat}\\ ."));
+    assert!(latex.ends_with("\\mathbf{endm}\n\\end{array}"));
+
+    assert_eq!(escape("a_b{c}^~\\"), "a\\_b\\{c\\}\\hat{}\\sim{}\\backslash{}");
+  }
+}
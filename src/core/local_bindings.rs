@@ -8,6 +8,8 @@ other validation is performed.
 */
 
 
+use alloc::vec::Vec;
+
 use crate::{core::substitution::Substitution, api::dag_node::DagNodePtr};
 
 pub struct Binding {
@@ -42,7 +44,7 @@ impl LocalBindings {
     for i in self.bindings.iter() {
       if let Some(d) = substitution.get(i.variable_index) {
         unsafe{
-          if d.as_ref_unchecked().equals(i.value) {
+          if (*d).equals(i.value) {
             return false;
           }
         }
@@ -52,7 +54,7 @@ impl LocalBindings {
     for i in self.bindings.iter_mut() {
       let index = i.variable_index;
       if substitution.get(index).is_none() {
-        substitution.bind(index, Some(i.value.clone()));
+        substitution.bind(index, Some(i.value));
         i.active = true;
       }
     }
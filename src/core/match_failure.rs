@@ -0,0 +1,270 @@
+/*!
+
+Explanations of failed matches. A compiled pattern only says whether it matched, which is little help when a big
+pattern unexpectedly doesn't. `CachedPattern::explain_failure` goes back over a failed match and reports the deepest
+point at which it went wrong as a `MatchFailure`: the position in the subject, and what the pattern expected there and
+found instead.
+
+```ignore
+let pattern = module.compile_pattern(parse_term(&module, "f(g(X:Nat), a)")?.as_ref());
+if let Some(failure) = pattern.explain_failure(subject) {
+  println!("{}", failure);  // at position [0, 0]: X expected a term of sort Nat but found true of sort Bool
+}
+```
+
+The explanation is found by walking the pattern and the subject together, separately from matching, so it costs
+nothing unless it is asked for. The walk compares symbols, checks the sorts and constraints of variables, and compares
+the bindings of repeated variables, and the failure reported is the one at the longest position, the first in preorder
+among those as deep. Subpatterns whose top symbol has axioms, like associativity, or that can collapse match modulo
+the axioms, which the walk doesn't redo: if it finds no other failure, it reports the deepest such subpattern as the
+point where matching modulo its axioms failed.
+
+*/
+
+use core::fmt::{Display, Formatter};
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+  abstractions::IString,
+  api::{
+    dag_node::DagNodePtr,
+    symbol::{SymbolAttribute, SymbolPtr},
+    term::Term,
+    variable_theory::VariableTerm
+  },
+  core::{
+    binders::alpha_equal,
+    proof_trace::dag_to_string,
+    rewriting_context::Position,
+    sort::SortPtr
+  }
+};
+
+/// Why a match failed at a position. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MatchFailureReason {
+  /// The pattern has `expected` on top at the position, and the subject has `found`.
+  SymbolMismatch { expected: IString, found: IString },
+  /// The variable only binds terms of sort `expected`, and the subterm's sort is `found`, or unknown if `None`.
+  SortMismatch { variable: IString, expected: IString, found: Option<IString> },
+  /// The subterm fails a predicate or pattern test of the variable.
+  ConstraintFailed { variable: IString },
+  /// The variable already bound a different subterm at `first`.
+  InconsistentBinding { variable: IString, first: Position },
+  /// The pattern applies `symbol` to `expected` arguments at the position, and the subject to `found`.
+  ArgumentCount { symbol: IString, expected: usize, found: usize },
+  /// The subterm doesn't match the subpattern with `symbol` on top modulo the symbol's axioms.
+  ModuloAxioms { symbol: IString },
+  /// The match failed, but the walk found no reason. This only happens if the walk and matching disagree.
+  Unexplained,
+}
+
+/// The deepest point at which a match failed. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MatchFailure {
+  /// The position of the subterm in the subject
+  pub position: Position,
+  /// The subterm at the position, as displayed
+  pub found   : String,
+  pub reason  : MatchFailureReason,
+}
+
+impl Display for MatchFailure {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "at position {:?}: ", self.position)?;
+    match &self.reason {
+      MatchFailureReason::SymbolMismatch { expected, found } => {
+        write!(f, "expected {} on top but found {}", expected, found)
+      }
+      MatchFailureReason::SortMismatch { variable, expected, found } => {
+        write!(f, "{} expected a term of sort {} but found {}", variable, expected, self.found)?;
+        match found {
+          Some(found) => write!(f, " of sort {}", found),
+          None        => write!(f, ", whose sort is unknown"),
+        }
+      }
+      MatchFailureReason::ConstraintFailed { variable } => {
+        write!(f, "{} does not satisfy the constraints of {}", self.found, variable)
+      }
+      MatchFailureReason::InconsistentBinding { variable, first } => {
+        write!(f, "{} is bound to {} here and to a different term at position {:?}", variable, self.found, first)
+      }
+      MatchFailureReason::ArgumentCount { symbol, expected, found } => {
+        write!(f, "expected {} applied to {} arguments but found {} arguments", symbol, expected, found)
+      }
+      MatchFailureReason::ModuloAxioms { symbol } => {
+        write!(f, "{} does not match modulo the axioms of {}", self.found, symbol)
+      }
+      MatchFailureReason::Unexplained => write!(f, "{} does not match", self.found),
+    }
+  }
+}
+
+/// Walks `pattern` and `subject` together as described in the module documentation. Returns the deepest failure, or
+/// `None` if the walk finds none. Variables are reported by the name `variable_name` gives them.
+pub(crate) fn find_match_failure(
+  pattern      : &dyn Term,
+  subject      : DagNodePtr,
+  variable_name: &dyn Fn(SymbolPtr) -> IString
+) -> Option<MatchFailure>
+{
+  let mut walk = FailureWalk { variable_name, bindings: Vec::new(), deepest: None, deepest_modulo: None };
+  walk.visit(pattern, subject, &mut Vec::new());
+
+  walk.deepest.or(walk.deepest_modulo)
+}
+
+struct FailureWalk<'a> {
+  variable_name : &'a dyn Fn(SymbolPtr) -> IString,
+  /// The variables bound so far, with their bindings and the positions of the bindings
+  bindings      : Vec<(SymbolPtr, DagNodePtr, Position)>,
+  deepest       : Option<MatchFailure>,
+  deepest_modulo: Option<MatchFailure>,
+}
+
+impl FailureWalk<'_> {
+  fn visit(&mut self, pattern: &dyn Term, subject: DagNodePtr, position: &mut Position) {
+    if let Some(variable) = pattern.as_any().downcast_ref::<VariableTerm>() {
+      if let Some(reason) = self.check_variable(variable, subject, position) {
+        record(&mut self.deepest, failure(position, subject, reason));
+      }
+      return;
+    }
+
+    let subject_ref = unsafe{ &*subject };
+    let symbol      = pattern.symbol_ref();
+    if symbol.attributes.intersects(SymbolAttribute::Axioms) || pattern.core().collapse.is_some() {
+      let reason = MatchFailureReason::ModuloAxioms { symbol: symbol.name.clone() };
+      record(&mut self.deepest_modulo, failure(position, subject, reason));
+      return;
+    }
+
+    if !core::ptr::addr_eq(pattern.symbol(), subject_ref.symbol()) {
+      let reason = MatchFailureReason::SymbolMismatch {
+        expected: symbol.name.clone(),
+        found   : subject_ref.symbol_ref().name.clone(),
+      };
+      record(&mut self.deepest, failure(position, subject, reason));
+      return;
+    }
+
+    let (expected, found) = (pattern.iter_args().count(), subject_ref.iter_args().count());
+    if expected != found {
+      let reason = MatchFailureReason::ArgumentCount { symbol: symbol.name.clone(), expected, found };
+      record(&mut self.deepest, failure(position, subject, reason));
+      return;
+    }
+
+    for (index, (arg, subject_arg)) in pattern.iter_args().zip(subject_ref.iter_args()).enumerate() {
+      position.push(index);
+      self.visit(arg, subject_arg, position);
+      position.pop();
+    }
+  }
+
+  fn check_variable(&mut self, variable: &VariableTerm, subject: DagNodePtr, position: &Position)
+      -> Option<MatchFailureReason>
+  {
+    let name  = (self.variable_name)(variable.symbol());
+    let sorts = [variable.symbol_ref().variable_sort(), variable.constraint().and_then(|constraint| constraint.sort())];
+    for sort in sorts.into_iter().flatten() {
+      let found = unsafe{ &mut *subject }.compute_sort();
+      if !found.is_some_and(|found| leq(found, sort)) {
+        return Some(MatchFailureReason::SortMismatch {
+          variable: name,
+          expected: unsafe{ &*sort }.name.clone(),
+          found   : found.map(|found| unsafe{ &*found }.name.clone()),
+        });
+      }
+    }
+
+    if let Some(constraint) = variable.constraint() {
+      let subject_ref = unsafe{ &*subject };
+      if !constraint.tests().iter().all(|test| test(subject_ref)) || !constraint.is_satisfied_by(subject) {
+        return Some(MatchFailureReason::ConstraintFailed { variable: name });
+      }
+    }
+
+    let bound = self.bindings.iter().find(|(symbol, _, _)| core::ptr::addr_eq(*symbol, variable.symbol()));
+    match bound {
+      Some((_, binding, first)) => {
+        let consistent = alpha_equal(*binding, subject);
+        (!consistent).then(|| MatchFailureReason::InconsistentBinding { variable: name, first: first.clone() })
+      }
+      None => {
+        self.bindings.push((variable.symbol(), subject, position.clone()));
+        None
+      }
+    }
+  }
+}
+
+fn failure(position: &Position, subject: DagNodePtr, reason: MatchFailureReason) -> MatchFailure {
+  MatchFailure { position: position.clone(), found: dag_to_string(subject), reason }
+}
+
+/// Keeps `candidate` if it is deeper than the failure kept so far.
+fn record(deepest: &mut Option<MatchFailure>, candidate: MatchFailure) {
+  if deepest.as_ref().is_none_or(|deepest| candidate.position.len() > deepest.position.len()) {
+    *deepest = Some(candidate);
+  }
+}
+
+#[inline(always)]
+fn leq(sort: SortPtr, other: SortPtr) -> bool {
+  core::ptr::eq(sort, other) || unsafe{ &*sort }.leq(unsafe{ &*other })
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::{
+      match_failure::MatchFailureReason,
+      parser::{parse_module, parse_term}
+    }
+  };
+
+  #[test]
+  fn failures_are_explained_at_the_deepest_position() {
+    let module = parse_module("
+      mod FAILURES is
+        sorts Zero Nat .
+        subsort Zero < Nat .
+        op 0 : -> Zero .
+        op s : Nat -> Nat .
+        op f : Nat Nat -> Nat .
+        vars X Y : Nat .
+        var Z : Zero .
+      endm
+    ").unwrap();
+    let explain = |pattern: &str, subject: &str| {
+      let pattern = module.compile_pattern(parse_term(&module, pattern).unwrap().as_ref());
+      pattern.explain_failure(parse_term(&module, subject).unwrap().term_to_dag(false))
+    };
+
+    assert_eq!(explain("f(X, s(Y))", "f(0, s(0))"), None);
+
+    let failure = explain("f(s(X), s(s(Y)))", "f(0, s(0))").unwrap();
+    assert_eq!((failure.position, failure.found.as_str()), (vec![1, 0], "0"));
+    assert_eq!(failure.reason, MatchFailureReason::SymbolMismatch { expected: "s".into(), found: "0".into() });
+
+    // Variables are reported by their own names rather than the canonical ones the pattern is compiled with.
+    let failure = explain("f(Y, s(Y))", "f(0, s(s(0)))").unwrap();
+    assert_eq!(
+      failure.to_string(),
+      "at position [1, 0]: Y is bound to s(0) here and to a different term at position [0]"
+    );
+
+    let failure = explain("s(Z)", "s(s(0))").unwrap();
+    assert_eq!(
+      failure.reason,
+      MatchFailureReason::SortMismatch {
+        variable: IString::from("Z"),
+        expected: IString::from("Zero"),
+        found   : Some(IString::from("Nat"))
+      }
+    );
+  }
+}
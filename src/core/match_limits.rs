@@ -0,0 +1,252 @@
+/*!
+
+Limits on associative matching. A pattern subterm with an associative top symbol is matched against the flattened
+arguments of the subject, trying the arguments of the pattern against runs of arguments of the subject in every
+combination that could match. For an associative-commutative symbol the number of combinations grows combinatorially
+with the number of arguments, so a single match against a large subject can take practically forever.
+
+Each attempt to match an argument of the pattern against an argument of the subject is a *subproblem*, and each
+assignment of subject arguments to the pattern arguments that matches structurally, and is then checked against the
+constraints structural matching can't see, is an *alternative*. A rewriting context limits the subproblems and
+alternatives of each associative match it performs by the `MatchLimits` of its configuration,
+`EngineConfig::with_match_limits`. A match that reaches a limit gives up, and so may miss a match that exists.
+
+The context counts the subproblems and alternatives of its matches, and the matches that gave up, in its
+`MatchStatistics`, given by `RewritingContext::match_statistics`. Each match that gives up also logs a warning at level
+1 naming the pattern responsible.
+
+```ignore
+let limits      = MatchLimits { max_subproblems: 10_000, ..MatchLimits::default() };
+let mut context = RewritingContext::with_config(&module, &EngineConfig::default().with_match_limits(limits));
+context.reduce(subject);
+if context.match_statistics().limit_hits() > 0 { … }
+```
+
+Matches outside a rewriting context, like those of compiled patterns from the `pattern_cache`, are not limited.
+
+*/
+
+use core::cell::Cell;
+use alloc::{rc::Rc, string::String};
+
+use crate::{
+  core::{
+    observer::{is_observed, notify, EngineLimit},
+    substitution::Substitution
+  },
+  warning
+};
+
+/// The number of subproblems an associative match may generate unless configured otherwise
+pub const DEFAULT_MAX_SUBPROBLEMS : usize = 1_000_000;
+/// The number of alternatives an associative match may try unless configured otherwise
+pub const DEFAULT_MAX_ALTERNATIVES: usize = 100_000;
+
+/// The limits on each associative match of a rewriting context. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MatchLimits {
+  pub max_subproblems : usize,
+  pub max_alternatives: usize,
+}
+
+impl Default for MatchLimits {
+  fn default() -> Self {
+    MatchLimits {
+      max_subproblems : DEFAULT_MAX_SUBPROBLEMS,
+      max_alternatives: DEFAULT_MAX_ALTERNATIVES,
+    }
+  }
+}
+
+/// Counts of the associative matches of a rewriting context
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct MatchStatistics {
+  pub subproblems           : usize,
+  pub alternatives          : usize,
+  /// The number of matches that gave up on reaching the limit on subproblems
+  pub subproblem_limit_hits : usize,
+  /// The number of matches that gave up on reaching the limit on alternatives
+  pub alternative_limit_hits: usize,
+}
+
+impl MatchStatistics {
+  /// The number of matches that gave up on reaching a limit
+  #[inline(always)]
+  pub fn limit_hits(&self) -> usize {
+    self.subproblem_limit_hits + self.alternative_limit_hits
+  }
+
+  /// The counts accumulated since the counts were `earlier`
+  pub fn since(&self, earlier: &MatchStatistics) -> MatchStatistics {
+    MatchStatistics {
+      subproblems           : self.subproblems - earlier.subproblems,
+      alternatives          : self.alternatives - earlier.alternatives,
+      subproblem_limit_hits : self.subproblem_limit_hits - earlier.subproblem_limit_hits,
+      alternative_limit_hits: self.alternative_limit_hits - earlier.alternative_limit_hits,
+    }
+  }
+}
+
+/// The limits of a context's matches and the counts of what they did. Shared by the copies of the context's
+/// substitution, so that matches made with a copy are counted too.
+#[derive(Default, Debug)]
+pub(crate) struct MatchBudget {
+  limits    : MatchLimits,
+  statistics: Cell<MatchStatistics>,
+}
+
+impl MatchBudget {
+  pub(crate) fn new(limits: MatchLimits) -> Self {
+    MatchBudget { limits, statistics: Cell::default() }
+  }
+
+  #[inline(always)]
+  pub(crate) fn statistics(&self) -> MatchStatistics {
+    self.statistics.get()
+  }
+}
+
+/// Which limit a match reached
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Exhausted {
+  Subproblems,
+  Alternatives,
+}
+
+/// The subproblems and alternatives of a single associative match, checked against the limits of the budget of the
+/// substitution the match binds, if it has one
+pub(crate) struct MatchAttempt {
+  budget      : Option<Rc<MatchBudget>>,
+  subproblems : usize,
+  alternatives: usize,
+  exhausted   : Option<Exhausted>,
+}
+
+impl MatchAttempt {
+  pub(crate) fn new(solution: &Substitution) -> Self {
+    MatchAttempt {
+      budget      : solution.budget().cloned(),
+      subproblems : 0,
+      alternatives: 0,
+      exhausted   : None,
+    }
+  }
+
+  /// Counts a subproblem. Returns `false`, and from then on always `false`, once a limit is reached.
+  #[inline(always)]
+  pub(crate) fn subproblem(&mut self) -> bool {
+    self.count(Exhausted::Subproblems)
+  }
+
+  /// Counts an alternative. Returns `false`, and from then on always `false`, once a limit is reached.
+  #[inline(always)]
+  pub(crate) fn alternative(&mut self) -> bool {
+    self.count(Exhausted::Alternatives)
+  }
+
+  /// Whether the match has reached a limit
+  #[inline(always)]
+  pub(crate) fn is_exhausted(&self) -> bool {
+    self.exhausted.is_some()
+  }
+
+  fn count(&mut self, kind: Exhausted) -> bool {
+    if self.is_exhausted() {
+      return false;
+    }
+    let Some(budget) = &self.budget else {
+      return true;
+    };
+
+    let (count, limit) = match kind {
+      Exhausted::Subproblems  => (&mut self.subproblems, budget.limits.max_subproblems),
+      Exhausted::Alternatives => (&mut self.alternatives, budget.limits.max_alternatives),
+    };
+    if *count == limit {
+      self.exhausted = Some(kind);
+      return false;
+    }
+    *count += 1;
+    true
+  }
+
+  /// Adds the counts of the match to the budget, reporting the match if it gave up. `pattern` describes the pattern.
+  pub(crate) fn finish(self, pattern: &dyn Fn() -> String) {
+    let Some(budget) = &self.budget else {
+      return;
+    };
+
+    let mut statistics = budget.statistics.get();
+    statistics.subproblems  += self.subproblems;
+    statistics.alternatives += self.alternatives;
+    match self.exhausted {
+      Some(Exhausted::Subproblems)  => statistics.subproblem_limit_hits += 1,
+      Some(Exhausted::Alternatives) => statistics.alternative_limit_hits += 1,
+      None                          => {}
+    }
+    budget.statistics.set(statistics);
+
+    if let Some(exhausted) = self.exhausted {
+      let (limit, what) = match exhausted {
+        Exhausted::Subproblems  => (budget.limits.max_subproblems, "subproblems"),
+        Exhausted::Alternatives => (budget.limits.max_alternatives, "alternatives"),
+      };
+      warning!(1, "gave up matching {} after reaching the limit of {} {}", pattern(), limit, what);
+
+      if is_observed() {
+        let pattern = pattern();
+        let limit   = match exhausted {
+          Exhausted::Subproblems  => EngineLimit::MatchSubproblems { pattern, limit },
+          Exhausted::Alternatives => EngineLimit::MatchAlternatives { pattern, limit },
+        };
+        notify(|observer| observer.limit_reached(&limit));
+      }
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    config::EngineConfig,
+    match_limits::MatchLimits,
+    parser::{parse_module, parse_term},
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext
+  };
+
+  #[test]
+  fn associative_matches_give_up_at_their_limits() {
+    let module = parse_module("
+      mod AC is
+        sort S .
+        ops a b c d : -> S .
+        op u : S S -> S [assoc comm] .
+        var X : S .
+        eq u(X, a) = X .
+      endm
+    ").unwrap();
+    let reduce = |limits: MatchLimits| {
+      let mut context = RewritingContext::with_config(&module, &EngineConfig::default().with_match_limits(limits));
+      let subject     = parse_term(&module, "u(b, u(c, u(d, a)))").unwrap().term_to_dag(false);
+      (dag_to_string(context.reduce(subject)), context.match_statistics())
+    };
+
+    let (result, statistics) = reduce(MatchLimits::default());
+    assert_eq!(result, "u(b, u(c, d))");
+    assert!(statistics.subproblems > 0 && statistics.alternatives > 0);
+    assert_eq!(statistics.limit_hits(), 0);
+
+    // Without a match, the subject is only put in canonical form, which sorts its arguments.
+    let (result, statistics) = reduce(MatchLimits { max_subproblems: 1, ..MatchLimits::default() });
+    assert_eq!(result, "u(a, u(b, u(c, d)))");
+    assert!(statistics.subproblem_limit_hits > 0);
+    assert_eq!(statistics.alternatives, 0);
+
+    let (result, statistics) = reduce(MatchLimits { max_alternatives: 0, ..MatchLimits::default() });
+    assert_eq!(result, "u(a, u(b, u(c, d)))");
+    assert!(statistics.alternative_limit_hits > 0);
+    assert_eq!(statistics.subproblem_limit_hits, 0);
+  }
+}
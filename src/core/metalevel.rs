@@ -0,0 +1,660 @@
+/*!
+
+The metalevel, where terms of one module, the *object module*, are represented as terms of another, the *metamodule*,
+so that a specification can compute with the terms and statements of another, as with Maude's `META-LEVEL`. The
+metamodule includes the prelude module `META-LEVEL`, whose constructors metarepresent terms:
+
+| Object level          | Metalevel                                                |
+|:----------------------|:---------------------------------------------------------|
+| the name `f`          | the quoted identifier `'f`, a constant of sort `Qid`     |
+| the variable `X`      | `variable('X)`                                           |
+| `f(t₁, …, tₙ)`        | `app('f, args(t̄₁, args(…, args(t̄ₙ, noArgs))))`           |
+| the constant `c`      | `app('c, noArgs)`                                        |
+| a substitution        | `bindings(bind('X, t̄), bindings(…, none))`               |
+
+Quoted identifiers are constants headed by symbols named with a leading `'`. The parser creates them the first time
+they are used in a module that declares the sort `Qid`, and `Module::add_object_module` creates those of the names
+of an object module: its own name, and the names of its symbols, sorts, and statement labels. A metamodule can only
+work in the object modules added to it, which it looks up by name.
+
+## Descent Functions
+
+The descent functions take a module, by its quoted name, and metarepresented terms, and do at the object level what
+their names say, giving the metarepresentation of the result:
+
+ - `metaReduce(M, T)` reduces `T` with the equations of `M`, giving `pair(T', S)`, where `T'` is the normal form and
+   `S` its sort.
+ - `metaMatch(M, P, T, N)` matches `T` against the pattern `P` modulo the axioms of its symbols, giving the `N`th match,
+   counting from `0`, as a substitution of the pattern's variables in the order of their first occurrences, or
+   `noMatch`. Neither term is reduced.
+ - `metaApply(M, T, L, N)` reduces `T`, rewrites it at the top with the `N`th match of the rules labeled `L`, and
+   reduces the result, giving `triple(T', S, B)`, where `B` binds the rule's variables, or `failure`.
+ - `metaSearch(M, T, P, D, N)` reduces `T` and searches breadth first through the terms its rules rewrite it to in at
+   most `D` steps, giving the `N`th match of the pattern `P` against them as `triple(T', S, B)`, or `failure`. Each
+   term is visited once, in the order of the steps needed to reach it.
+
+The descent functions are symbols with the `Descent` attribute, which the engine evaluates in Rust instead of with
+equations: a descent function whose arguments are reduced is replaced by its result. An application that can't be
+evaluated, because its arguments aren't metarepresentations or the module is unknown, is stuck, and stays as it is.
+From Rust, the same functions are methods of `MetaLevel`, which report such problems as `MetaError`s, along with
+`up_term` and `down_term`, which move terms between the levels.
+
+```ignore
+let mut meta = Module::prelude_module("META-LEVEL").unwrap();
+meta.add_object_module(parse_module(PEANO)?)?;
+let metalevel = MetaLevel::new(&meta)?;
+let term      = metalevel.up_term(object_term)?;
+let result    = metalevel.meta_reduce(metalevel.qid("PEANO")?, term)?;
+```
+
+Data atoms have no metarepresentation yet, and the search of `metaSearch` has no conditions and no unbounded depth.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+
+use crate::{
+  abstractions::{intern, IString},
+  api::{
+    Arity,
+    dag_node::DagNodePtr,
+    free_theory::{FreeDagNode, FreeTerm},
+    symbol::{Symbol, SymbolAttribute, SymbolPtr},
+    term::BxTerm,
+    variable_theory::VariableTerm
+  },
+  core::{
+    module::Module,
+    module_arena::ModuleArena,
+    prelude::NatView,
+    proof_trace::dag_to_string,
+    replacement::collect_variables,
+    rewriting_context::{Bindings, RewritingContext},
+    sort::SortPtr
+  }
+};
+
+/// The names of the descent functions, which are the only symbols that may have the `Descent` attribute
+pub const DESCENT_FUNCTIONS: [&str; 4] = ["metaReduce", "metaMatch", "metaApply", "metaSearch"];
+
+/// Returned by the methods of `MetaLevel` when an operation can't be carried out.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MetaError {
+  /// The metamodule has no symbol with the name: it doesn't include `META-LEVEL`, or has no quoted identifier for a
+  /// name of an object module.
+  MissingSymbol(IString),
+  /// No object module of the metamodule has the name.
+  UnknownModule(IString),
+  /// The object module has no operator or variable with the name.
+  UnknownSymbol(IString),
+  /// The object module's symbol with the name takes a different number of arguments.
+  WrongArity { symbol: IString, args: usize },
+  /// The term, as printed, isn't the metarepresentation it should be.
+  NotMetaTerm(String),
+  /// The term, as printed, is a data atom, which has no metarepresentation.
+  DataAtom(String),
+}
+
+impl Display for MetaError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      MetaError::MissingSymbol(name)          => write!(f, "the metamodule has no symbol {}", name),
+      MetaError::UnknownModule(name)          => write!(f, "the metamodule has no object module {}", name),
+      MetaError::UnknownSymbol(name)          => write!(f, "the object module has no symbol {}", name),
+      MetaError::WrongArity { symbol, args }  => write!(f, "{} can't be applied to {} arguments", symbol, args),
+      MetaError::NotMetaTerm(term)            => write!(f, "{} is not a metarepresentation", term),
+      MetaError::DataAtom(term)               => write!(f, "the data atom {} has no metarepresentation", term),
+    }
+  }
+}
+
+impl Error for MetaError {}
+
+/// The name of the quoted identifier of `name`, `'name`
+#[inline(always)]
+pub fn qid_name(name: &str) -> IString {
+  intern(format!("'{}", name))
+}
+
+/// The quoted identifier `'name` in `arena`, a constant of sort `qid`, created if it doesn't exist yet
+pub(crate) fn quoted_identifier(arena: &mut ModuleArena, qid: SortPtr, name: &str) -> SymbolPtr {
+  let name = qid_name(name);
+  if let Some(symbol) = arena.symbol(&name) {
+    return symbol;
+  }
+
+  let mut symbol    = Symbol::new(name, Arity::Value(0));
+  symbol.attributes = SymbolAttribute::Constructor.into();
+  symbol.sort_table.add_op_declaration(Vec::new(), qid, true);
+  arena.add_symbol(symbol)
+}
+
+impl Module {
+  /// Makes `object` an object module of this module, which must include `META-LEVEL`, so that the descent functions
+  /// can work in it under its name, and creates the quoted identifiers of the names of `object`. The object module
+  /// must be compiled, as for reduction. An object module with the same name is replaced.
+  pub fn add_object_module(&mut self, object: Module) -> Result<(), MetaError> {
+    let qid = self.arena
+                  .sorts
+                  .get_by_name(&IString::from("Qid"))
+                  .ok_or_else(|| MetaError::MissingSymbol(IString::from("Qid")))?;
+
+    let mut names: Vec<IString> = vec![object.name.clone()];
+    names.extend(object.arena.symbols.keys().cloned());
+    names.extend(object.arena.sorts.iter().map(|(_, sort)| unsafe{ &*sort }.name.clone()));
+    names.extend(object.arena.kinds.iter().map(|kind| unsafe{ &*kind.error_sort() }.name.clone()));
+    names.extend(
+      object.equations
+            .iter()
+            .chain(object.rules.iter())
+            .chain(object.membership.iter())
+            .filter_map(|statement| statement.name.clone())
+    );
+    for name in names {
+      quoted_identifier(&mut self.arena, qid, &name);
+    }
+
+    self.object_modules.insert(object.name.clone(), Box::new(object));
+    Ok(())
+  }
+}
+
+/// The descent functions of a module including `META-LEVEL`, and the conversions between the levels. See the module
+/// documentation.
+pub struct MetaLevel<'m> {
+  module   : &'m Module,
+  nat      : NatView,
+  variable : SymbolPtr,
+  app      : SymbolPtr,
+  no_args  : SymbolPtr,
+  args     : SymbolPtr,
+  bind     : SymbolPtr,
+  none     : SymbolPtr,
+  bindings : SymbolPtr,
+  no_match : SymbolPtr,
+  pair     : SymbolPtr,
+  triple   : SymbolPtr,
+  failure  : SymbolPtr,
+}
+
+impl<'m> MetaLevel<'m> {
+  /// The metalevel of `module`, which must include `META-LEVEL`.
+  pub fn new(module: &'m Module) -> Result<Self, MetaError> {
+    let symbol = |name: &str| {
+      let name = IString::from(name);
+      module.arena.symbol(&name).ok_or(MetaError::MissingSymbol(name))
+    };
+
+    Ok(
+      MetaLevel {
+        module,
+        nat     : NatView::new(module).ok_or_else(|| MetaError::MissingSymbol(IString::from("s")))?,
+        variable: symbol("variable")?,
+        app     : symbol("app")?,
+        no_args : symbol("noArgs")?,
+        args    : symbol("args")?,
+        bind    : symbol("bind")?,
+        none    : symbol("none")?,
+        bindings: symbol("bindings")?,
+        no_match: symbol("noMatch")?,
+        pair    : symbol("pair")?,
+        triple  : symbol("triple")?,
+        failure : symbol("failure")?,
+      }
+    )
+  }
+
+  #[inline(always)]
+  pub fn module(&self) -> &'m Module {
+    self.module
+  }
+
+  /// The quoted identifier `'name`, which must already exist in the module
+  pub fn qid(&self, name: &str) -> Result<DagNodePtr, MetaError> {
+    let name = qid_name(name);
+    match self.module.arena.symbol(&name) {
+      Some(symbol) => Ok(FreeDagNode::new(symbol)),
+      None         => Err(MetaError::MissingSymbol(name)),
+    }
+  }
+
+  // region Moving between levels
+
+  /// The metarepresentation of the term of an object module that `node` represents
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn up_term(&self, node: DagNodePtr) -> Result<DagNodePtr, MetaError> {
+    let node_ref = unsafe{ &*node };
+    if node_ref.data().is_some() {
+      return Err(MetaError::DataAtom(dag_to_string(node)));
+    }
+
+    let name = self.qid(&node_ref.symbol_ref().name)?;
+    if node_ref.symbol_ref().is_variable() {
+      return Ok(self.make(self.variable, vec![name]));
+    }
+
+    let args = node_ref.iter_args().map(|arg| self.up_term(arg)).collect::<Result<Vec<_>, _>>()?;
+    Ok(self.make(self.app, vec![name, self.list(self.args, self.no_args, args)]))
+  }
+
+  /// The term of `object` that `meta` metarepresents
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn down_term(&self, object: &Module, meta: DagNodePtr) -> Result<BxTerm, MetaError> {
+    let meta_ref = unsafe{ &*meta };
+    let args: Vec<DagNodePtr> = meta_ref.iter_args().collect();
+
+    if core::ptr::addr_eq(meta_ref.symbol(), self.variable) {
+      let name = self.down_name(args[0])?;
+      return match object.arena.symbol(&name) {
+        Some(symbol) if unsafe{ &*symbol }.is_variable() => Ok(Box::new(VariableTerm::new(symbol))),
+        _ => Err(MetaError::UnknownSymbol(name)),
+      };
+    }
+    if !core::ptr::addr_eq(meta_ref.symbol(), self.app) {
+      return Err(MetaError::NotMetaTerm(dag_to_string(meta)));
+    }
+
+    let name   = self.down_name(args[0])?;
+    let symbol = object.arena.symbol(&name).ok_or_else(|| MetaError::UnknownSymbol(name.clone()))?;
+    let args   = self.items(self.args, self.no_args, args[1])?
+                     .into_iter()
+                     .map(|arg| self.down_term(object, arg))
+                     .collect::<Result<Vec<_>, _>>()?;
+    let symbol_ref = unsafe{ &*symbol };
+    if symbol_ref.is_variable() || !symbol_ref.arity.accepts(args.len()) {
+      return Err(MetaError::WrongArity { symbol: name, args: args.len() });
+    }
+
+    Ok(FreeTerm::with_args(symbol, args))
+  }
+
+  /// The name `node` quotes
+  fn down_name(&self, node: DagNodePtr) -> Result<IString, MetaError> {
+    let node_ref = unsafe{ &*node };
+    match node_ref.symbol_ref().name.strip_prefix('\'') {
+      Some(name) if node_ref.is_empty() && !name.is_empty() => Ok(IString::from(name)),
+      _ => Err(MetaError::NotMetaTerm(dag_to_string(node))),
+    }
+  }
+
+  /// The object module `name` quotes
+  fn object(&self, name: DagNodePtr) -> Result<&'m Module, MetaError> {
+    let name = self.down_name(name)?;
+    match self.module.object_modules.get(&name) {
+      Some(object) => Ok(object),
+      None         => Err(MetaError::UnknownModule(name)),
+    }
+  }
+
+  /// The DAG of the term `meta` metarepresents in `object`
+  fn down_dag(&self, object: &Module, meta: DagNodePtr) -> Result<DagNodePtr, MetaError> {
+    Ok(self.down_term(object, meta)?.term_to_dag(false))
+  }
+
+  fn natural(&self, node: DagNodePtr) -> Result<usize, MetaError> {
+    match self.nat.to_u64(node) {
+      Some(value) => Ok(value as usize),
+      None        => Err(MetaError::NotMetaTerm(dag_to_string(node))),
+    }
+  }
+
+  // endregion Moving between levels
+
+  // region Descent functions
+
+  /// Reduces the term `term` metarepresents in the module `module` quotes. See the module documentation.
+  pub fn meta_reduce(&self, module: DagNodePtr, term: DagNodePtr) -> Result<DagNodePtr, MetaError> {
+    let object      = self.object(module)?;
+    let subject     = self.down_dag(object, term)?;
+    let mut context = RewritingContext::new(object);
+    let result      = context.reduce(subject);
+
+    Ok(self.make(self.pair, vec![self.up_term(result)?, self.sort_name(result)?]))
+  }
+
+  /// Gives the `solution`th match of `subject` against `pattern` in the module `module` quotes. See the module
+  /// documentation.
+  pub fn meta_match(
+    &self,
+    module  : DagNodePtr,
+    pattern : DagNodePtr,
+    subject : DagNodePtr,
+    solution: DagNodePtr
+  ) -> Result<DagNodePtr, MetaError> {
+    let object   = self.object(module)?;
+    let pattern  = self.down_term(object, pattern)?;
+    let subject  = self.down_dag(object, subject)?;
+    let solution = self.natural(solution)?;
+
+    let compiled = object.compile_pattern(pattern.as_ref());
+    let Some(values) = compiled.match_skipping(subject, solution) else {
+      return Ok(FreeDagNode::new(self.no_match));
+    };
+
+    // The cached pattern may have been compiled from a variant, but its bindings are in the order of first occurrence.
+    let mut variables = Vec::new();
+    collect_variables(pattern.as_ref(), &mut variables);
+    let bindings: Bindings = variables.into_iter()
+                                      .map(|variable| unsafe{ &*variable }.name.clone())
+                                      .zip(values)
+                                      .collect();
+    self.substitution(&bindings)
+  }
+
+  /// Rewrites the term `term` metarepresents at the top with the `solution`th match of the rules labeled `label` in
+  /// the module `module` quotes. See the module documentation.
+  pub fn meta_apply(
+    &self,
+    module  : DagNodePtr,
+    term    : DagNodePtr,
+    label   : DagNodePtr,
+    solution: DagNodePtr
+  ) -> Result<DagNodePtr, MetaError> {
+    let object   = self.object(module)?;
+    let subject  = self.down_dag(object, term)?;
+    let label    = self.down_name(label)?;
+    let solution = self.natural(solution)?;
+
+    let mut context = RewritingContext::new(object);
+    let subject     = context.reduce(subject);
+    let found       = context.solutions(subject)
+                             .filter(|found| {
+                               found.redex.position.is_empty()
+                                   && object.rules[found.redex.rule].name.as_ref() == Some(&label)
+                             })
+                             .nth(solution);
+
+    match found {
+      Some(found) => self.result_triple(found.result, &found.redex.bindings),
+      None        => Ok(FreeDagNode::new(self.failure)),
+    }
+  }
+
+  /// Searches breadth first from the term `term` metarepresents for the `solution`th match of `pattern` among the terms
+  /// reached in at most `depth` rule steps in the module `module` quotes. See the module documentation.
+  pub fn meta_search(
+    &self,
+    module  : DagNodePtr,
+    term    : DagNodePtr,
+    pattern : DagNodePtr,
+    depth   : DagNodePtr,
+    solution: DagNodePtr
+  ) -> Result<DagNodePtr, MetaError> {
+    let object   = self.object(module)?;
+    let subject  = self.down_dag(object, term)?;
+    let pattern  = self.down_term(object, pattern)?;
+    let depth    = self.natural(depth)?;
+    let solution = self.natural(solution)?;
+
+    let compiled      = object.compile_pattern(pattern.as_ref());
+    let mut variables = Vec::new();
+    collect_variables(pattern.as_ref(), &mut variables);
+
+    let mut context = RewritingContext::new(object);
+    let subject     = context.reduce(subject);
+    // The states found so far, in the order they were found, and sorted, to tell whether a state is new
+    let mut states  = vec![(subject, 0)];
+    let mut visited = vec![subject];
+    let mut skipped = 0;
+    let mut next    = 0;
+    while let Some(&(state, steps)) = states.get(next) {
+      next += 1;
+
+      let mut matches = 0;
+      while let Some(values) = compiled.match_skipping(state, matches) {
+        if skipped == solution {
+          let bindings: Bindings = variables.iter()
+                                            .map(|&variable| unsafe{ &*variable }.name.clone())
+                                            .zip(values)
+                                            .collect();
+          return self.result_triple(state, &bindings);
+        }
+        matches += 1;
+        skipped += 1;
+      }
+
+      if steps == depth {
+        continue;
+      }
+      for found in context.solutions(state) {
+        if let Err(index) = visited.binary_search_by(|probe| unsafe{ &**probe }.compare(found.result)) {
+          visited.insert(index, found.result);
+          states.push((found.result, steps + 1));
+        }
+      }
+    }
+
+    Ok(FreeDagNode::new(self.failure))
+  }
+
+  // endregion Descent functions
+
+  // region Building results
+
+  /// `triple(T, S, B)` for the object term `result`, its sort, and `bindings`
+  fn result_triple(&self, result: DagNodePtr, bindings: &Bindings) -> Result<DagNodePtr, MetaError> {
+    let args = vec![self.up_term(result)?, self.sort_name(result)?, self.substitution(bindings)?];
+    Ok(self.make(self.triple, args))
+  }
+
+  /// The quoted name of the sort of the reduced object term `node`
+  fn sort_name(&self, node: DagNodePtr) -> Result<DagNodePtr, MetaError> {
+    match unsafe{ &mut *node }.compute_sort() {
+      Some(sort) => self.qid(&unsafe{ &*sort }.name),
+      None       => Err(MetaError::NotMetaTerm(dag_to_string(node))),
+    }
+  }
+
+  fn substitution(&self, bindings: &Bindings) -> Result<DagNodePtr, MetaError> {
+    let assignments = bindings.iter()
+                              .map(|(name, value)| {
+                                Ok(self.make(self.bind, vec![self.qid(name)?, self.up_term(*value)?]))
+                              })
+                              .collect::<Result<Vec<_>, MetaError>>()?;
+    Ok(self.list(self.bindings, self.none, assignments))
+  }
+
+  /// The list `cons(item₁, cons(…, cons(itemₙ, nil)))` of `items`
+  fn list(&self, cons: SymbolPtr, nil: SymbolPtr, items: Vec<DagNodePtr>) -> DagNodePtr {
+    items.into_iter()
+         .rev()
+         .fold(FreeDagNode::new(nil), |list, item| self.make(cons, vec![item, list]))
+  }
+
+  /// The items of the list `node` built with `cons` and `nil`
+  fn items(&self, cons: SymbolPtr, nil: SymbolPtr, node: DagNodePtr) -> Result<Vec<DagNodePtr>, MetaError> {
+    let mut items = Vec::new();
+    let mut list  = node;
+    while core::ptr::addr_eq(unsafe{ &*list }.symbol(), cons) {
+      let mut args = unsafe{ &*list }.iter_args();
+      items.push(args.next().unwrap());
+      list = args.next().unwrap();
+    }
+
+    match core::ptr::addr_eq(unsafe{ &*list }.symbol(), nil) {
+      true  => Ok(items),
+      false => Err(MetaError::NotMetaTerm(dag_to_string(node))),
+    }
+  }
+
+  #[inline(always)]
+  fn make(&self, symbol: SymbolPtr, mut args: Vec<DagNodePtr>) -> DagNodePtr {
+    FreeDagNode::with_args(symbol, &mut args)
+  }
+
+  // endregion Building results
+}
+
+/// The result of the descent function at the top of `node`, if `node` is headed by one whose arguments are
+/// metarepresentations, which is what reducing `node` gives. See the module documentation.
+pub(crate) fn descend(module: &Module, node: DagNodePtr) -> Option<DagNodePtr> {
+  let node_ref = unsafe{ &*node };
+  if !node_ref.symbol_ref().attributes.contains(SymbolAttribute::Descent) {
+    return None;
+  }
+
+  let metalevel = MetaLevel::new(module).ok()?;
+  let args: Vec<DagNodePtr> = node_ref.iter_args().collect();
+  let result = match (node_ref.symbol_ref().name.as_ref(), args.as_slice()) {
+    ("metaReduce", &[module, term]) => metalevel.meta_reduce(module, term),
+    ("metaMatch", &[module, pattern, subject, solution]) => {
+      metalevel.meta_match(module, pattern, subject, solution)
+    }
+    ("metaApply", &[module, term, label, solution]) => {
+      metalevel.meta_apply(module, term, label, solution)
+    }
+    ("metaSearch", &[module, term, pattern, depth, solution]) => {
+      metalevel.meta_search(module, term, pattern, depth, solution)
+    }
+    _ => return None,
+  };
+
+  result.ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::{
+      metalevel::{MetaError, MetaLevel},
+      module::Module,
+      parser::{parse_module, parse_term},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext
+    }
+  };
+
+  const PEANO: &str = "
+    mod PEANO is
+      sorts Zero NzNat Nat .
+      subsorts Zero NzNat < Nat .
+      op 0 : -> Zero [ctor] .
+      op s : Nat -> NzNat [ctor] .
+      op plus : Nat Nat -> Nat .
+      op coin : -> Nat .
+      vars X Y : Nat .
+      eq plus(0, Y) = Y .
+      eq plus(s(X), Y) = s(plus(X, Y)) .
+      rl [flip] : coin => 0 .
+      rl [flip] : coin => s(0) .
+      rl [grow] : s(X) => s(s(X)) .
+    endm
+  ";
+
+  fn metamodule() -> Module {
+    let mut meta = Module::prelude_module("META-LEVEL").unwrap();
+    meta.add_object_module(parse_module(PEANO).unwrap()).unwrap();
+    meta
+  }
+
+  /// The metarepresentation of the term `source` of `PEANO`
+  fn up(metalevel: &MetaLevel, source: &str) -> crate::api::dag_node::DagNodePtr {
+    let object = metalevel.module().object_modules.get(&IString::from("PEANO")).unwrap();
+    metalevel.up_term(parse_term(object, source).unwrap().term_to_dag(false)).unwrap()
+  }
+
+  fn nat(metalevel: &MetaLevel, value: &str) -> crate::api::dag_node::DagNodePtr {
+    parse_term(metalevel.module(), value).unwrap().term_to_dag(false)
+  }
+
+  #[test]
+  fn terms_move_between_levels() {
+    let meta      = metamodule();
+    let metalevel = MetaLevel::new(&meta).unwrap();
+    let object    = meta.object_modules.get(&IString::from("PEANO")).unwrap();
+
+    let term = up(&metalevel, "plus(s(0), Y)");
+    assert_eq!(
+      dag_to_string(term),
+      "app('plus, args(app('s, args(app('0, noArgs), noArgs)), args(variable('Y), noArgs)))"
+    );
+    assert_eq!(dag_to_string(metalevel.down_term(object, term).unwrap().term_to_dag(false)), "plus(s(0), Y)");
+
+    let bad = parse_term(&meta, "app('s, noArgs)").unwrap().term_to_dag(false);
+    assert_eq!(
+      metalevel.down_term(object, bad).err(),
+      Some(MetaError::WrongArity { symbol: IString::from("s"), args: 0 })
+    );
+    assert_eq!(metalevel.qid("NAT").err(), Some(MetaError::MissingSymbol(IString::from("'NAT"))));
+    assert!(MetaLevel::new(object).is_err());
+  }
+
+  #[test]
+  fn descent_functions_from_rust() {
+    let meta      = metamodule();
+    let metalevel = MetaLevel::new(&meta).unwrap();
+    let peano     = metalevel.qid("PEANO").unwrap();
+
+    let sum = up(&metalevel, "plus(s(0), s(0))");
+    assert_eq!(
+      dag_to_string(metalevel.meta_reduce(peano, sum).unwrap()),
+      "pair(app('s, args(app('s, args(app('0, noArgs), noArgs)), noArgs)), 'NzNat)"
+    );
+
+    let pattern = up(&metalevel, "plus(X, s(Y))");
+    assert_eq!(
+      dag_to_string(metalevel.meta_match(peano, pattern, sum, nat(&metalevel, "0")).unwrap()),
+      "bindings(bind('X, app('s, args(app('0, noArgs), noArgs))), bindings(bind('Y, app('0, noArgs)), none))"
+    );
+    let second = metalevel.meta_match(peano, pattern, sum, nat(&metalevel, "s(0)")).unwrap();
+    assert_eq!(dag_to_string(second), "noMatch");
+
+    let coin = up(&metalevel, "coin");
+    let flip = metalevel.qid("flip").unwrap();
+    assert_eq!(
+      dag_to_string(metalevel.meta_apply(peano, coin, flip, nat(&metalevel, "s(0)")).unwrap()),
+      "triple(app('s, args(app('0, noArgs), noArgs)), 'NzNat, none)"
+    );
+    let third = metalevel.meta_apply(peano, coin, flip, nat(&metalevel, "s(s(0))")).unwrap();
+    assert_eq!(dag_to_string(third), "failure");
+
+    // From coin, flip gives 0 and s(0) in one step, and grow gives s(s(0)) from s(0) in a second.
+    let pattern = up(&metalevel, "s(s(X))");
+    let search  = |depth: &str| {
+      dag_to_string(metalevel.meta_search(peano, coin, pattern, nat(&metalevel, depth), nat(&metalevel, "0")).unwrap())
+    };
+    assert_eq!(search("s(0)"), "failure");
+    assert_eq!(
+      search("s(s(0))"),
+      "triple(app('s, args(app('s, args(app('0, noArgs), noArgs)), noArgs)), 'NzNat, \
+       bindings(bind('X, app('0, noArgs)), none))"
+    );
+
+    let nat_qid = metalevel.qid("Nat").unwrap();
+    assert_eq!(metalevel.meta_reduce(nat_qid, sum).err(), Some(MetaError::UnknownModule(IString::from("Nat"))));
+  }
+
+  #[test]
+  fn descent_functions_in_reflective_modules() {
+    let mut meta = parse_module("
+      mod REFLECTIVE is
+        including META-LEVEL .
+        op result : ResultPair -> Term .
+        op double : Term -> Term .
+        var T : Term .
+        var Q : Qid .
+        eq result(pair(T, Q)) = T .
+        eq double(T) = result(metaReduce('PEANO, app('plus, args(T, args(T, noArgs))))) .
+      endm
+    ").unwrap();
+    meta.add_object_module(parse_module(PEANO).unwrap()).unwrap();
+
+    let reduce = |source: &str| {
+      let subject     = parse_term(&meta, source).unwrap().term_to_dag(false);
+      let mut context = RewritingContext::new(&meta);
+      dag_to_string(context.reduce(subject))
+    };
+    assert_eq!(
+      reduce("double(app('s, args(app('0, noArgs), noArgs)))"),
+      "app('s, args(app('s, args(app('0, noArgs), noArgs)), noArgs))"
+    );
+    // An application that can't be evaluated, here for want of an object module named `Nat`, is stuck.
+    assert_eq!(reduce("metaReduce('Nat, app('0, noArgs))"), "metaReduce('Nat, app('0, noArgs))");
+  }
+}
@@ -0,0 +1,211 @@
+/*!
+
+Engine counters for the [`metrics`](https://docs.rs/metrics) facade, with the `metrics` feature. A `MetricsObserver`
+is an `EngineObserver` that reports what it is told through whichever recorder the embedding service installs, a
+Prometheus exporter for instance:
+
+```ignore
+PrometheusBuilder::new().install()?;
+MetricsObserver::install();
+```
+
+The metrics are:
+
+| Name                                | Kind      | Labels   | What                                          |
+|:------------------------------------|:----------|:---------|:----------------------------------------------|
+| `mod2lib_rewrites_total`            | counter   | `module` | Equations applied by reductions               |
+| `mod2lib_reductions_total`          | counter   | `module` | Reductions performed                          |
+| `mod2lib_reduction_seconds`         | histogram | `module` | Time taken by each reduction                  |
+| `mod2lib_match_subproblems_total`   | counter   |          | Subproblems of associative matches            |
+| `mod2lib_match_alternatives_total`  | counter   |          | Alternatives tried by associative matches     |
+| `mod2lib_limits_reached_total`      | counter   | `limit`  | Limits reached, by the kind of limit          |
+| `mod2lib_gc_collections_total`      | counter   |          | Garbage collections                           |
+| `mod2lib_gc_pause_seconds`          | histogram |          | Time the engine was paused by each collection |
+| `mod2lib_live_nodes`                | gauge     |          | Live nodes after the last collection          |
+| `mod2lib_arenas`                    | gauge     |          | Arenas held by the node allocator             |
+| `mod2lib_modules_loaded_total`      | counter   |          | Modules loaded by sessions                    |
+| `mod2lib_modules_compiled_total`    | counter   |          | Modules whose statements were compiled        |
+
+Rates, like rewrites per second, are left to the monitoring system, as in `rate(mod2lib_rewrites_total[1m])`. The
+match counters count the matches of reductions only. See `observer` for which events are observed.
+
+*/
+
+use std::sync::Arc;
+
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit};
+
+use crate::{
+  abstractions::IString,
+  core::observer::{add_observer, EngineLimit, EngineObserver, GcSummary, ObserverId, ReductionSummary}
+};
+
+/// Reports the engine's events as metrics. See the module documentation.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct MetricsObserver;
+
+impl MetricsObserver {
+  /// Describes the metrics to the installed recorder and adds a `MetricsObserver` to the engine's observers, returning
+  /// the id that removes it.
+  pub fn install() -> ObserverId {
+    MetricsObserver::describe();
+    add_observer(Arc::new(MetricsObserver))
+  }
+
+  /// Gives the installed recorder the units and descriptions of the metrics.
+  pub fn describe() {
+    describe_counter!("mod2lib_rewrites_total", Unit::Count, "Equations applied by reductions");
+    describe_counter!("mod2lib_reductions_total", Unit::Count, "Reductions performed");
+    describe_histogram!("mod2lib_reduction_seconds", Unit::Seconds, "Time taken by each reduction");
+    describe_counter!("mod2lib_match_subproblems_total", Unit::Count, "Subproblems of associative matches");
+    describe_counter!("mod2lib_match_alternatives_total", Unit::Count, "Alternatives tried by associative matches");
+    describe_counter!("mod2lib_limits_reached_total", Unit::Count, "Limits reached, by the kind of limit");
+    describe_counter!("mod2lib_gc_collections_total", Unit::Count, "Garbage collections");
+    describe_histogram!("mod2lib_gc_pause_seconds", Unit::Seconds, "Time the engine was paused by each collection");
+    describe_gauge!("mod2lib_live_nodes", Unit::Count, "Live nodes after the last collection");
+    describe_gauge!("mod2lib_arenas", Unit::Count, "Arenas held by the node allocator");
+    describe_counter!("mod2lib_modules_loaded_total", Unit::Count, "Modules loaded by sessions");
+    describe_counter!("mod2lib_modules_compiled_total", Unit::Count, "Modules whose statements were compiled");
+  }
+}
+
+impl EngineObserver for MetricsObserver {
+  fn module_loaded(&self, _module: &IString) {
+    counter!("mod2lib_modules_loaded_total").increment(1);
+  }
+
+  fn module_compiled(&self, _module: &IString, _statements: usize) {
+    counter!("mod2lib_modules_compiled_total").increment(1);
+  }
+
+  fn garbage_collected(&self, summary: &GcSummary) {
+    counter!("mod2lib_gc_collections_total").increment(1);
+    histogram!("mod2lib_gc_pause_seconds").record(summary.pause.as_secs_f64());
+    gauge!("mod2lib_live_nodes").set(summary.nodes_after as f64);
+    gauge!("mod2lib_arenas").set(summary.arenas as f64);
+  }
+
+  fn reduction_finished(&self, module: &IString, summary: &ReductionSummary) {
+    let module = module.to_string();
+    counter!("mod2lib_rewrites_total", "module" => module.clone()).increment(summary.rewrites as u64);
+    counter!("mod2lib_reductions_total", "module" => module.clone()).increment(1);
+    histogram!("mod2lib_reduction_seconds", "module" => module).record(summary.elapsed.as_secs_f64());
+    counter!("mod2lib_match_subproblems_total").increment(summary.matches.subproblems as u64);
+    counter!("mod2lib_match_alternatives_total").increment(summary.matches.alternatives as u64);
+  }
+
+  fn limit_reached(&self, limit: &EngineLimit) {
+    let kind = match limit {
+      EngineLimit::Rewrites { .. }          => "rewrites",
+      EngineLimit::MatchSubproblems { .. }  => "match_subproblems",
+      EngineLimit::MatchAlternatives { .. } => "match_alternatives",
+      EngineLimit::ConditionFuel(_)         => "condition_fuel",
+    };
+    counter!("mod2lib_limits_reached_total", "limit" => kind).increment(1);
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{
+    atomic::{AtomicU64, Ordering::Relaxed},
+    Arc,
+    Mutex
+  };
+
+  use metrics::{
+    with_local_recorder,
+    Counter,
+    Gauge,
+    Histogram,
+    Key,
+    KeyName,
+    Metadata,
+    Recorder,
+    SharedString,
+    Unit
+  };
+
+  use crate::core::{
+    metrics::MetricsObserver,
+    observer::remove_observer,
+    parser::{parse_module, parse_term},
+    rewriting_context::RewritingContext
+  };
+
+  /// Keeps the counters registered with it by their keys
+  #[derive(Default)]
+  struct CounterRecorder {
+    counters: Mutex<Vec<(Key, Arc<AtomicU64>)>>,
+  }
+
+  impl CounterRecorder {
+    fn value(&self, name: &str, labels: &[(&'static str, &'static str)]) -> u64 {
+      let key = Key::from_parts(name.to_string(), labels);
+      self.counters
+          .lock()
+          .unwrap()
+          .iter()
+          .find(|(counter, _)| *counter == key)
+          .map_or(0, |(_, value)| value.load(Relaxed))
+    }
+  }
+
+  impl Recorder for CounterRecorder {
+    fn describe_counter(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_gauge(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_histogram(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+      let mut counters = self.counters.lock().unwrap();
+      let value = match counters.iter().find(|(counter, _)| counter == key) {
+        Some((_, value)) => value.clone(),
+        None => {
+          let value = Arc::new(AtomicU64::new(0));
+          counters.push((key.clone(), value.clone()));
+          value
+        }
+      };
+      Counter::from_arc(value)
+    }
+
+    fn register_gauge(&self, _key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+      Gauge::noop()
+    }
+
+    fn register_histogram(&self, _key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+      Histogram::noop()
+    }
+  }
+
+  #[test]
+  fn reductions_are_counted_per_module() {
+    let module = parse_module("
+      mod METERED is
+        sort S .
+        ops a b c : -> S .
+        eq a = b .
+        eq b = c .
+      endm
+    ").unwrap();
+
+    // Events are reported on the thread they happen on, so the local recorder sees only this test's.
+    let recorder = CounterRecorder::default();
+    let id       = MetricsObserver::install();
+    with_local_recorder(&recorder, || {
+      let mut context = RewritingContext::new(&module);
+      for _ in 0..2 {
+        context.reduce(parse_term(&module, "a").unwrap().term_to_dag(false));
+      }
+      context.reduce_bounded(parse_term(&module, "a").unwrap().term_to_dag(false), 1).unwrap_err();
+    });
+    remove_observer(id);
+
+    assert_eq!(recorder.value("mod2lib_rewrites_total", &[("module", "METERED")]), 5);
+    assert_eq!(recorder.value("mod2lib_reductions_total", &[("module", "METERED")]), 3);
+    assert_eq!(recorder.value("mod2lib_limits_reached_total", &[("limit", "rewrites")]), 1);
+  }
+}
@@ -9,7 +9,7 @@ The following compares Maude's `DagNode` to our implementation here.
 
 |                | Maude                                        | mod2lib                     |
 |:---------------|:---------------------------------------------|:----------------------------|
-| size           | Fixed 3 word size (or 6 words?)              | Fixed size struct (4 words) |
+| size           | Fixed 3 word size (or 6 words?)              | Fixed size struct (3 words) |
 | tag            | implicit via vtable pointer                  | enum variant                |
 | flags          | `MemoryInfo` in first word                   | `BitFlags` field            |
 | shared impl    | base class impl                              | enum impl                   |
@@ -19,12 +19,65 @@ The following compares Maude's `DagNode` to our implementation here.
 */
 
 mod root_container;
+pub mod arc_term;
 pub(crate) mod allocator;
+pub(crate) mod automata;
 pub mod sort;
+pub mod config;
+pub mod coverage;
+pub mod experiment;
+pub mod match_failure;
+pub mod match_limits;
+pub mod metalevel;
 pub mod module;
+pub mod module_arena;
+pub mod module_graph;
+#[cfg(feature = "std")]
+pub mod module_resolver;
+pub mod observer;
+pub mod pattern_cache;
+pub mod pattern_constructs;
 pub mod pre_equation;
+pub mod replacement;
+pub mod retract;
+pub mod rewriting_context;
+pub mod rule_selection;
+#[cfg(feature = "std")]
+pub mod session;
+pub mod signature;
+pub mod simulation;
+pub mod solution_cursor;
+pub mod statement_index;
 pub mod term_core;
+pub mod term_match;
 pub mod format;
+pub mod latex;
+pub mod pretty;
+#[cfg(feature = "std")]
+pub mod snapshot;
+pub mod source_index;
+pub mod statistics;
+pub mod ingest;
+pub mod incremental_parser;
+pub mod parser;
+pub mod prelude;
+pub mod proof_trace;
+pub mod canonicalize;
+pub mod collapse;
+pub mod binders;
+pub mod diff;
+pub mod generalization;
+pub mod renaming;
+pub mod subsumption;
+pub mod unification;
+pub mod thread_bound;
+pub(crate) mod theory_table;
+#[cfg(feature = "smt")]
+pub mod smt;
+#[cfg(feature = "expr")]
+pub mod expr;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub(crate) mod dag_node_core;
 pub(crate) mod substitution;
 pub(crate) mod local_bindings;
@@ -34,6 +87,7 @@ pub(crate) mod variable_info;
 
 // Reexports to flatten some of the smaller modules
 pub(crate) use local_bindings::LocalBindings;
+#[cfg(feature = "std")]
 pub(crate) use narrowing_variable_info::NarrowingVariableInfo;
 pub(crate) use variable_info::VariableInfo;
 
@@ -63,6 +117,8 @@ mod tests {
     println!("size of DagNodeKind: {}", size_of::<DagNodeTheory>());
     println!("size of DagNodeFlags: {}", size_of::<DagNodeFlags>());
     println!("size of DagNode: {}", size_of::<DagNodeCore>());
+    // The symbol, packed with the sort index, the theory, and the flags, and two inline arguments
+    #[cfg(target_pointer_width = "64")]
     assert_eq!(size_of::<DagNodeCore>(), 3 * size_of::<usize>());
   }
 }
@@ -13,33 +13,57 @@ same statuses as Maude, but it's not clear to me if I'll need them.
 The connected components of the lattice of sorts (the "kinds") is computed by computing the transitive closure of the
 subsort relation.
 
+A closed module can still be edited, as in a REPL: sorts, subsorts, and statements added with `add_sort`,
+`add_subsort`, and `add_statement` are taken into account by `recompute_kind_closures`, which rebuilds only the kinds
+the edits touched and recompiles only the statements that depend on them.
+
+To try out changes without touching the module at all, edit a copy made by `clone_for_experiment` instead.
+
 ## See Also...
 
  * The module system section of the [Design Notes](doc/DesignNotes.md).
 
 */
 
-use std::fmt::{Debug, Display, Formatter};
-use std::ops::Deref;
+use core::cell::{Ref, RefCell};
+use core::fmt::{Debug, Display, Formatter};
+use core::ops::Deref;
+use alloc::{boxed::Box, format, rc::Rc, string::{String, ToString}, vec, vec::Vec};
+use rand::Rng;
 use crate::{
   abstractions::{
     HashMap,
     IString,
+    Set,
     join_iter
   },
-  api::symbol::SymbolPtr,
+  api::{
+    atom::DataAtom,
+    dag_node::DagNodePtr,
+    data_theory::DataDagNode,
+    symbol::{SymbolPtr, SymbolType},
+    term::{BxTerm, Term}
+  },
   core::{
-    pre_equation::PreEquation,
+    automata::SharedAutomata,
+    collapse::analyse_collapses,
+    experiment::Experiment,
+    format::{FormatStyle, Formattable, PrintSettings},
+    latex::module_to_latex,
+    module_arena::ModuleArena,
+    observer::notify,
+    pattern_cache::{CachedPattern, PatternCache},
+    pre_equation::{PreEquation, PreEquationAttribute, PreEquationKind, StatementError},
     sort::{
-      kind::{
-        BxKind,
-        Kind
-      },
-      collection::SortCollection,
-      kind_error::KindError
-    }
+      kind::{Kind, KindPtr},
+      kind_error::KindError,
+      predicate::SortPredicates,
+      SortPtr,
+      SpecialSort
+    },
+    statistics::SymbolStatistics
   },
-  heap_destroy,
+  testing::{module_terms::ModuleTermGenerator, Choices},
   warning,
 };
 
@@ -64,21 +88,44 @@ pub struct Module {
   pub submodules: Vec<BxModule>,
   pub status    : ModuleStatus,
 
-  // ToDo: Why not just have the sorts in `kinds`? Do we need `kinds` after construction?
-  pub sorts     : SortCollection,
-  pub kinds     : Vec<BxKind>,
-  pub symbols   : HashMap<IString, SymbolPtr>,
+  /// The names of the modules imported by the module's own import declarations, in order
+  pub(crate) imports           : Vec<IString>,
+  /// The names of every module imported, directly or not, in the order they were first imported
+  pub(crate) transitive_imports: Vec<IString>,
+  /// The sorts added or given subsorts since the kinds were last computed
+  pub(crate) dirty_sorts       : Vec<SortPtr>,
+
+  /// The identity element, a constant, of each symbol with the `LeftIdentity` or `RightIdentity` attribute
+  pub identities: HashMap<SymbolPtr, SymbolPtr>,
   pub equations : Vec<PreEquation>,
   pub rules     : Vec<PreEquation>,
   pub membership: Vec<PreEquation>,
+  /// The sorts of data atoms given by Rust predicates. See `sort::predicate`.
+  pub sort_predicates: SortPredicates,
   // pub strategies: Vec<PreEquation>, // Unimplemented
 
+  // Reduction options
+  /// Whether reduction rewrites shared subterms in place. A subterm whose normal form is of another theory, like a data
+  /// atom or a variable, can't be overwritten; it is rewritten once per reduction, and the caller's references to it
+  /// still see it unreduced. See `RewritingContext`.
+  pub graph_rewriting: bool,
+
   // ProfileModule members (performance profiling)
   // symbol_info: Vec<SymbolProfile>,
   // mb_info    : Vec<StatementProfile>, // Membership
   // eq_info    : Vec<StatementProfile>, // Equation
   // rl_info    : Vec<StatementProfile>, // Rule
   // sd_info    : Vec<StatementProfile>, // Strategy Definition
+
+  /// Patterns compiled by `compile_pattern`. See `pattern_cache`.
+  pub(crate) pattern_cache: RefCell<PatternCache>,
+
+  /// The modules the descent functions of the metalevel work in, by name. See `metalevel`.
+  pub object_modules: HashMap<IString, BxModule>,
+
+  /// Owns the symbols, sorts, and kinds of the module. It must be the last field, so that it is dropped after
+  /// everything referring to its contents. See `ModuleArena`.
+  pub arena: ModuleArena,
 }
 
 impl Module {
@@ -93,56 +140,346 @@ impl Module {
   `ModuleStatus::SortSetClosed`, so at any point after this method call, a module will have
   `status >= ModuleStatus::SortSetClosed`.
 
-  ToDo: It would be nice if this method were idempotent. Low priority.
+  To add sorts and subsorts to a module whose sort set is already closed, use `add_sort`, `add_subsort`, and
+  `recompute_kind_closures`.
+
+  # Safety
+
+  Every sort in the module's arena must be live. The sorts the module creates stay live until it is dropped.
   */
   pub unsafe fn compute_kind_closures(&mut self) {
     assert_eq!(self.status, ModuleStatus::Open, "tried to compute kind closure when module status is not open");
 
-    for (_, sort) in
-        self.sorts
-            .iter()
-            .filter(|(_, sort_ptr)| (**sort_ptr).kind.is_null())
-    {
-      let kind = unsafe { Kind::new(sort) };
-      let kind = kind.unwrap_or_else(
-        | kind_error | {
-          let msg = kind_error.to_string();
-          match kind_error {
-
-            KindError::NoMaximalSort { kind, .. }
-            | KindError::CycleDetected { kind, .. } => {
-              warning!(1, "{}", msg.as_str());
-              // Box::into_raw(kind)
-              kind
-            }
+    let sorts: Vec<SortPtr> = self.arena.sorts.iter().map(|(_, sort)| sort).collect();
+    for sort in sorts {
+      if (*sort).kind.is_null() {
+        self.close_kind(sort);
+      }
+    }
+    self.dirty_sorts.clear();
+    self.status = ModuleStatus::SortSetClosed
+  }
 
+  /// Constructs the kind of `sort` and every sort connected to it, which must not have kinds yet.
+  unsafe fn close_kind(&mut self, sort: SortPtr) {
+    let kind = unsafe { Kind::new(sort) };
+    let kind = kind.unwrap_or_else(
+      | kind_error | {
+        let msg = kind_error.to_string();
+        match kind_error {
+
+          KindError::NoMaximalSort { kind, .. }
+          | KindError::CycleDetected { kind, .. } => {
+            warning!(1, "{}", msg.as_str());
+            // Box::into_raw(kind)
+            kind
           }
+
         }
-      );
+      }
+    );
 
-      // Maude sets the index_in_parent of the kind here.
-      self.kinds.push(kind);
+    // Maude sets the index_in_parent of the kind here.
+    self.arena.kinds.push(kind);
+  }
+
+  // region Incremental editing
+
+  /// Adds a sort named `name`, or returns the existing sort of that name. If the sort set is already closed, the sort
+  /// has no kind until `recompute_kind_closures` is called.
+  pub fn add_sort(&mut self, name: IString) -> SortPtr {
+    self.invalidate_pattern_cache();
+    let existing = self.arena.sorts.get_by_name(&name);
+    let sort     = self.arena.sorts.get_or_create_sort(name);
+    if existing.is_none() {
+      self.dirty_sorts.push(sort);
+    }
+
+    sort
+  }
+
+  /// Declares `smaller` a subsort of `larger`. If the sort set is already closed, the subsort relation is out of date
+  /// until `recompute_kind_closures` is called.
+  ///
+  /// In an `Experiment`, neither sort may be shared with the original module.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn add_subsort(&mut self, smaller: SortPtr, larger: SortPtr) {
+    assert!(
+      !self.arena.sorts.is_shared(smaller) && !self.arena.sorts.is_shared(larger),
+      "tried to change the subsorts of a sort shared with the original module"
+    );
+    self.invalidate_pattern_cache();
+    unsafe{ (*larger).insert_subsort(smaller); }
+    self.dirty_sorts.push(smaller);
+    self.dirty_sorts.push(larger);
+  }
+
+  /// Adds `statement` to the equations, rules, or membership axioms, as its kind says. If the module's sort set is
+  /// closed, the statement is given its retracts and compiled, and, like `compile_statements`, a statement that can't
+  /// be compiled is added but marked `Bad`, and its error returned.
+  pub fn add_statement(&mut self, mut statement: PreEquation) -> Result<(), StatementError> {
+    self.invalidate_pattern_cache();
+    let mut result = Ok(());
+    if self.status >= ModuleStatus::SortSetClosed {
+      statement.insert_retracts(&mut self.arena);
+      analyse_collapses(statement.lhs_term.as_mut(), &self.identities);
+      result = statement.compile();
+      if let Err(error) = &result {
+        warning!(1, "{}", error);
+      }
+    }
+
+    match statement.kind {
+      PreEquationKind::Equation { .. }   => self.equations.push(statement),
+      PreEquationKind::Rule { .. }       => self.rules.push(statement),
+      PreEquationKind::Membership { .. } => self.membership.push(statement),
+    }
+
+    result
+  }
+
+  /**
+  Brings the closure of the subsort relation up to date after sorts and subsorts were added with `add_sort` and
+  `add_subsort`. Only the kinds of the edited sorts are rebuilt, along with the sorts now connected to them, and only
+  the statements that use a symbol declared with a sort of a rebuilt kind are recompiled. Returns the number of
+  statements recompiled, or the error of the first recompiled statement that can't be compiled. Unlike
+  `compute_kind_closures`, this may be called any number of times. On a module whose sort set is still open, it
+  computes the closure for the first time.
+
+  The sorts of existing DAGs in a rebuilt kind are out of date, as is the statement index of any `RewritingContext`
+  created before the edits, so reduce with a new context and invalidate the sorts of existing DAGs. The replaced kinds
+  are kept in `ModuleArena::retired_kinds`, so the sorts stale DAGs point to stay valid.
+  */
+  pub fn recompute_kind_closures(&mut self) -> Result<usize, StatementError> {
+    self.invalidate_pattern_cache();
+    if self.status == ModuleStatus::Open {
+      unsafe{ self.compute_kind_closures(); }
+      return Ok(0);
+    }
+
+    let dirty_sorts = core::mem::take(&mut self.dirty_sorts);
+    let mut stale_kinds: Vec<KindPtr> = Vec::new();
+    let mut affected: Vec<SortPtr>    = Vec::new();
+    for &sort in dirty_sorts.iter() {
+      let kind = unsafe{ &*sort }.kind;
+      match kind.is_null() {
+        true  => affected.push(sort),
+        false => {
+          if !stale_kinds.contains(&kind) {
+            stale_kinds.push(kind);
+          }
+        }
+      }
+    }
+
+    // The error sort of a stale kind is a supersort of the kind's maximal sorts and must be unlinked from them.
+    for kind in stale_kinds {
+      let index = self.arena.kinds.iter().position(|owned| core::ptr::addr_eq(owned.as_ref(), kind)).unwrap();
+      let kind  = self.arena.kinds.remove(index);
+      let error_sort = kind.error_sort();
+      for &sort in kind.sorts.iter().skip(SpecialSort::FirstUserSort as usize) {
+        let sort = unsafe{ &mut *sort };
+        sort.supersorts.retain(|&supersort| !core::ptr::addr_eq(supersort, error_sort));
+        sort.reset_closure();
+        affected.push(sort);
+      }
+      self.arena.retired_kinds.push(kind);
+    }
+
+    for &sort in affected.iter() {
+      if unsafe{ &*sort }.kind.is_null() {
+        unsafe{ self.close_kind(sort); }
+      }
+    }
+
+    // Recompile the statements that use a symbol with a sort in a rebuilt kind.
+    let uses_affected_sort = |term: &dyn Term| {
+      let mut uses = false;
+      for_each_symbol(term, &mut |symbol| {
+        uses |= unsafe{ &*symbol }.sort_table.declarations().iter().any(
+          |declaration| core::iter::once(&declaration.range)
+              .chain(declaration.domain.iter())
+              .any(|sort| affected.iter().any(|&affected| core::ptr::addr_eq(affected, *sort)))
+        );
+      });
+      uses
+    };
+    let mut recompiled = 0;
+    for statement in self.equations.iter_mut().chain(self.rules.iter_mut()).chain(self.membership.iter_mut()) {
+      let mut terms: Vec<&dyn Term> = vec![statement.lhs_term.as_ref()];
+      for condition in statement.conditions.iter() {
+        terms.extend(condition.terms().into_iter().map(|term| term.as_ref()));
+      }
+      if let PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } = &statement.kind {
+        terms.push(rhs_term.as_ref());
+      }
+
+      if terms.into_iter().any(uses_affected_sort) {
+        statement.reset_compilation();
+        recompiled += 1;
+      }
+    }
+
+    self.compile_statements().map(|_| recompiled)
+  }
+
+  /// The compiled form of `pattern`, from the module's pattern cache if a variant of it was compiled since the cache
+  /// was last invalidated. The sorts of the module must be closed, and the module must outlive the result. See
+  /// `pattern_cache`.
+  pub fn compile_pattern(&self, pattern: &dyn Term) -> Rc<CachedPattern> {
+    self.pattern_cache.borrow_mut().get_or_compile(pattern, &self.identities)
+  }
+
+  /// Forgets the patterns compiled by `compile_pattern`. The editing methods of the module call this, but code that
+  /// changes the module's sorts or identities directly must call it itself.
+  #[inline(always)]
+  pub fn invalidate_pattern_cache(&self) {
+    self.pattern_cache.borrow_mut().clear();
+  }
+
+  /// The module's cache of compiled patterns, with its counts of hits and misses
+  #[inline(always)]
+  pub fn pattern_cache(&self) -> Ref<'_, PatternCache> {
+    self.pattern_cache.borrow()
+  }
+
+  /// Makes the data atoms `predicate` accepts members of `sort`. See `sort::predicate`.
+  pub fn add_sort_predicate(&mut self, sort: SortPtr, predicate: impl Fn(&dyn DataAtom) -> bool + 'static) {
+    self.sort_predicates.add(sort, Rc::new(predicate));
+  }
+
+  /// The least sort of `atom`, from the declarations of its symbol and the module's sort predicates
+  #[inline(always)]
+  pub fn data_atom_sort(&self, atom: &dyn DataAtom) -> Option<SortPtr> {
+    self.sort_predicates.sort_of(atom)
+  }
+
+  /// Gives each `DataDagNode` in the DAG rooted at `node` its least sort in this module, by `data_atom_sort`, and makes
+  /// the nodes above them forget their sorts. See `sort::predicate`.
+  pub fn sort_data_atoms(&self, node: DagNodePtr) {
+    self.sort_data_atoms_aux(node, &mut Set::default());
+  }
+
+  /// Returns whether the DAG rooted at `node` has a data node. `visited` holds the nodes without one.
+  fn sort_data_atoms_aux(&self, node: DagNodePtr, visited: &mut Set<*const u8>) -> bool {
+    if visited.contains(&(node as *const u8)) {
+      return false;
+    }
+
+    let node_mut = unsafe{ &mut *node };
+    if let Some(data_node) = node_mut.as_any_mut().downcast_mut::<DataDagNode>() {
+      let sort = self.data_atom_sort(data_node.atom());
+      data_node.set_sort(sort);
+      return true;
+    }
+
+    let mut has_data = false;
+    for arg in node_mut.iter_args() {
+      has_data |= self.sort_data_atoms_aux(arg, visited);
+    }
+    match has_data {
+      true  => node_mut.invalidate_sort(),
+      false => { visited.insert(node as *const u8); }
+    }
+
+    has_data
+  }
+
+  /// A random ground term of sort `sort` and depth at most `depth` over the operators of the module, or `None` if there
+  /// is none. To generate many terms, make a `ModuleTermGenerator` once instead. See `testing::module_terms`.
+  pub fn generate_random_term<R: Rng + ?Sized>(&self, sort: SortPtr, depth: usize, rng: &mut R) -> Option<BxTerm> {
+    ModuleTermGenerator::new(self).generate(&mut Choices::from_seed(rng.random()), sort, depth)
+  }
+
+  /// A cheap logical copy of the module for trying out changes, such as new statements or statement attributes,
+  /// without affecting the module. The copy shares the module's symbols, sorts, and compiled automata. See
+  /// `experiment`.
+  #[inline(always)]
+  pub fn clone_for_experiment(&self) -> Experiment<'_> {
+    Experiment::new(self)
+  }
+
+  // endregion Incremental editing
+
+  /// Compiles every statement of the module. Identical left-hand side subpatterns of statements with the same top
+  /// symbol share a single compiled automaton. Returns the number of subpatterns whose automata were shared.
+  /// Statements that are already compiled are left alone. Retracts are first inserted where the right-hand side of a
+  /// statement would otherwise be ill-sorted (see `retract`), and the left-hand sides are analysed for the ways they
+  /// can collapse modulo `identities` and idempotency.
+  ///
+  /// A statement with an unbound variable, or with a matching or rewrite condition, which reduction doesn't support
+  /// yet, is marked `Bad` and never applied. The other statements are compiled regardless, and the error of the first
+  /// bad statement is returned. Redundant statements, which are common in machine-generated modules, can be flagged or
+  /// removed beforehand. See `subsumption`.
+  pub fn compile_statements(&mut self) -> Result<usize, StatementError> {
+    let mut shared_automata: HashMap<SymbolPtr, SharedAutomata> = HashMap::default();
+    let mut first_error = None;
+
+    for statement in self.equations.iter_mut().chain(self.rules.iter_mut()).chain(self.membership.iter_mut()) {
+      if !statement.attributes.contains(PreEquationAttribute::Compiled) {
+        statement.insert_retracts(&mut self.arena);
+      }
+      analyse_collapses(statement.lhs_term.as_mut(), &self.identities);
+      let symbol = statement.lhs_term.symbol();
+      if let Err(error) = statement.compile_with_shared_automata(shared_automata.entry(symbol).or_default()) {
+        warning!(1, "{}", error);
+        first_error.get_or_insert(error);
+      }
+    }
+
+    let statements = self.equations.len() + self.rules.len() + self.membership.len();
+    notify(|observer| observer.module_compiled(&self.name, statements));
+
+    match first_error {
+      None        => Ok(shared_automata.values().map(|shared| shared.hit_count()).sum()),
+      Some(error) => Err(error)
+    }
+  }
+
+  /// The names of the modules the module imports directly, in the order of its import declarations
+  #[inline(always)]
+  pub fn imports(&self) -> &[IString] {
+    &self.imports
+  }
+
+  /// The names of every module the module imports, directly or through other imports, in the order they were first
+  /// imported. See `module_graph` for the dependencies of modules that haven't been parsed.
+  #[inline(always)]
+  pub fn transitive_imports(&self) -> &[IString] {
+    &self.transitive_imports
+  }
+
+  /// Counts the symbols, sorts, kinds, and statements of the module, not including those of its submodules.
+  pub fn symbol_statistics(&self) -> SymbolStatistics {
+    let symbols = self.arena.symbols.values().map(|&symbol| unsafe{ &*symbol });
+
+    SymbolStatistics {
+      symbols   : self.arena.symbols.len(),
+      variables : symbols.clone().filter(|symbol| symbol.symbol_type == SymbolType::Variable).count(),
+      data      : symbols.filter(|symbol| symbol.symbol_type == SymbolType::Data).count(),
+      sorts     : self.arena.sorts.len(),
+      kinds     : self.arena.kinds.len(),
+      statements: self.equations.len() + self.rules.len() + self.membership.len(),
     }
-    self.status = ModuleStatus::SortSetClosed
   }
 
 
   /// Formats the module for display with `prefix` for each line. The `Debug` impl defers to this method. Interior
   /// indentation is affixed to `prefix`.
-  fn debug_fmt(&self, f: &mut Formatter<'_>, prefix: &String) -> std::fmt::Result {
+  fn debug_fmt(&self, f: &mut Formatter<'_>, prefix: &String) -> core::fmt::Result {
     let inner_prefix = format!("{}{}", prefix, " ".repeat(DISPLAY_INDENT));
     writeln!(f, "{}Module {{", prefix)?;
     writeln!(f, "{}name: {}", inner_prefix, self.name)?;
     writeln!(f, "{}status: {:?}", inner_prefix, self.status)?;
     //sorts (as kinds)
-    if !self.kinds.is_empty()  {
-      format_named_list(f, inner_prefix.as_str(), "sorts", &self.kinds)?
+    if !self.arena.kinds.is_empty()  {
+      format_named_list(f, inner_prefix.as_str(), "sorts", &self.arena.kinds)?
       // let sort_vec = join_iter(self.sorts.iter().map(|(name, _)| name.as_str()), |_| ", ",).collect::<String>();
       // writeln!(f, "{}sorts: [{}]", inner_prefix, sort_vec)?;
     }
     //symbols
-    if !self.symbols.is_empty() {
-      let iter = self.symbols.iter().map(|(n, _)| n.deref());
+    if !self.arena.symbols.is_empty() {
+      let iter = self.arena.symbols.keys().map(|n| n.deref());
       let sep = ", ";
       writeln!(
         f,
@@ -173,21 +510,30 @@ impl Module {
 
 }
 
-impl Drop for Module {
-  fn drop(&mut self) {
-    for (_, &symbol_ptr) in self.symbols.iter() {
-      heap_destroy!(symbol_ptr);
-    }
+/// Calls `visit` with the symbol of each subterm of `term`, in preorder.
+fn for_each_symbol(term: &dyn Term, visit: &mut dyn FnMut(SymbolPtr)) {
+  visit(term.symbol());
+  for arg in term.iter_args() {
+    for_each_symbol(arg, visit);
   }
 }
 
 impl Debug for Module {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     let prefix = "".to_string();
     self.debug_fmt(f, &prefix)
   }
 }
 
+impl Formattable for Module {
+  fn repr_with(&self, style: FormatStyle, settings: &PrintSettings) -> String {
+    match style {
+      FormatStyle::Latex => module_to_latex(self, settings),
+      _                  => format!("{:?}", self)
+    }
+  }
+}
+
 
 /// Helper function to format a named list of something:
 /// ```txt
@@ -197,8 +543,8 @@ impl Debug for Module {
 ///   thing3
 /// ]
 /// ```
-fn format_named_list<T: Display>(f: &mut Formatter<'_>, prefix: &str, name: &str, list: &Vec<T>)
-  -> std::fmt::Result
+fn format_named_list<T: Display>(f: &mut Formatter<'_>, prefix: &str, name: &str, list: &[T])
+  -> core::fmt::Result
 {
   let tab = " ".repeat(DISPLAY_INDENT);
   writeln!(f, "{}{}: [", prefix, name)?;
@@ -252,3 +598,70 @@ mod tests {
   }
 }
 */
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      symbol::Symbol
+    },
+    core::{
+      module::Module,
+      parser::{parse_module, parse_term},
+      pre_equation::{PreEquation, PreEquationKind},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext
+    }
+  };
+
+  const MODULE: &str = "
+    mod EDIT is
+      sorts A B .
+      op a : -> A .
+      op f : B -> B .
+      ops g h : A -> A .
+      var X : B .
+      var Y : A .
+      eq f(X) = X .
+      eq g(Y) = Y .
+    endm
+  ";
+
+  fn reduce(module: &Module, term: &str) -> String {
+    let subject     = parse_term(module, term).unwrap().term_to_dag(false);
+    let mut context = RewritingContext::new(module);
+    dag_to_string(context.reduce(subject))
+  }
+
+  #[test]
+  fn closed_modules_can_be_extended() {
+    let mut module = parse_module(MODULE).unwrap();
+    let (a, b)     = (module.add_sort("A".into()), module.add_sort("B".into()));
+    assert_eq!(reduce(&module, "f(a)"), "f(a)");
+
+    // Both statements use a sort of the merged kind.
+    module.add_subsort(a, b);
+    assert_eq!(module.recompute_kind_closures(), Ok(2));
+    assert_eq!(module.recompute_kind_closures(), Ok(0));
+    assert!(unsafe{ (*a).leq(&*b) });
+    assert_eq!(reduce(&module, "f(a)"), "a");
+
+    let c = module.add_sort("C".into());
+    module.add_subsort(c, a);
+    let mut constant = Symbol::new(IString::from("c"), Arity::Value(0));
+    constant.sort_table.add_op_declaration(vec![], c, true);
+    module.arena.add_symbol(constant);
+    assert_eq!(module.recompute_kind_closures(), Ok(2));
+    assert_eq!(module.arena.retired_kinds.len(), 3);
+    assert!(unsafe{ (*c).leq(&*b) });
+    assert_eq!(reduce(&module, "f(g(c))"), "c");
+
+    let lhs = parse_term(&module, "h(Y)").unwrap();
+    let rhs = parse_term(&module, "a").unwrap();
+    module.add_statement(PreEquation::new(None, lhs, PreEquationKind::Equation { rhs_term: rhs }, vec![])).unwrap();
+    assert_eq!(reduce(&module, "h(c)"), "a");
+  }
+}
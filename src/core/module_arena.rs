@@ -0,0 +1,279 @@
+/*!
+
+A `ModuleArena` owns the symbols, sorts, and kinds of a `Module` and frees them together, in a fixed order, when it is
+dropped. Everything else in the module, and every DAG built from its symbols, refers to these items by raw pointer, so
+the arena must outlive all of them.
+
+## Teardown Order
+
+The arena is the last field of `Module`, so the module's statements, whose terms point to its symbols, are dropped
+before the arena. The arena then frees
+
+ 1. its kinds, including retired kinds, which own the error sorts of the kinds,
+ 2. its symbols, whose sort tables point to its sorts, and
+ 3. its sorts.
+
+## Symbol Lifetime
+
+DAG nodes are not owned by any module, and a DAG may outlive the module whose symbols it uses. Rather than leave such a
+DAG with dangling symbol pointers, dropping an arena that a DAG reachable from a root still refers to *retires* the
+arena instead of freeing it: its symbols, sorts, and kinds are kept, and a warning is logged. Retired arenas are freed
+by `collect_retired_arenas` once no rooted DAG refers to their symbols. It is called whenever an arena is dropped and
+can be called directly, for example after dropping the roots of the DAGs in question. `retired_arena_count` says how
+many are waiting. A module replaced on purpose, as by `Session::reload`, is retired the same way by `retire`, but
+without the warning.
+
+Only roots keep symbols alive, just as only roots keep DAG nodes alive. A DAG that isn't reachable from a root may be
+collected by the next garbage collection anyway and must not be used after its module is dropped.
+
+## Shared Arenas
+
+The arena of an `Experiment` is made by `share` and borrows the symbols and sorts of the original module's arena rather
+than owning them. It never frees them, and they must not be changed through it. Symbols, sorts, and kinds created in
+the experiment belong to its arena as usual. See `Module::clone_for_experiment`.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::{heap_construct, heap_destroy, HashMap, IString, Mutex, Set, DETERMINISTIC},
+  api::{
+    dag_node::DagNodePtr,
+    symbol::{Symbol, SymbolPtr}
+  },
+  core::sort::{
+    collection::SortCollection,
+    kind::BxKind
+  },
+  warning
+};
+
+/// The arenas dropped while rooted DAGs still referred to their symbols. See the module documentation.
+static RETIRED_ARENAS: Mutex<RetiredArenas> = Mutex::new(RetiredArenas(Vec::new()));
+
+struct RetiredArenas(Vec<ModuleArena>);
+
+// SAFETY: A `ModuleArena` holds unsynchronized raw pointers to its symbols, sorts, and kinds, so the arenas may only be
+// touched by the thread using the engine. An arena is retired when its module is dropped, and is only touched again by
+// `collect_retired_arenas`, to look for rooted DAGs that refer to its symbols and to free it. Dropping a module and
+// collecting garbage, which calls `collect_retired_arenas`, are both uses of the engine, which is used by one thread
+// at a time: that is checked by the entry points while a `ThreadBoundEngine` binds the engine, and is up to the
+// program otherwise (see `thread_bound`). The mutex orders the uses by successive threads.
+unsafe impl Send for RetiredArenas {}
+
+/// Frees the retired arenas that no DAG reachable from a root refers to any longer, returning how many were freed.
+pub fn collect_retired_arenas() -> usize {
+  let released: Vec<ModuleArena> = {
+    let mut retired = RETIRED_ARENAS.lock().unwrap();
+    if retired.0.is_empty() {
+      return 0;
+    }
+    let (released, kept) = core::mem::take(&mut retired.0)
+                               .into_iter()
+                               .partition(|arena| arena.live_reference().is_none());
+    retired.0 = kept;
+    released
+  };
+
+  // Dropping the released arenas frees them, as nothing refers to them.
+  released.len()
+}
+
+/// The number of arenas waiting to be freed by `collect_retired_arenas`
+pub fn retired_arena_count() -> usize {
+  RETIRED_ARENAS.lock().unwrap().0.len()
+}
+
+#[derive(Default)]
+pub struct ModuleArena {
+  pub sorts        : SortCollection,
+  pub kinds        : Vec<BxKind>,
+  /// Kinds replaced when the subsort relation changed. They are kept, with their error sorts, because the sorts of
+  /// existing terms and DAGs may still point to them. See `Module::recompute_kind_closures`.
+  pub retired_kinds: Vec<BxKind>,
+  pub symbols      : HashMap<IString, SymbolPtr>,
+  /// The symbols borrowed from the arena this one was shared from, which this arena doesn't free
+  shared_symbols   : Set<SymbolPtr>,
+}
+
+impl ModuleArena {
+  /// An arena with the symbols and sorts of this one, but no kinds, that shares rather than owns them, so this arena
+  /// must outlive it. See the module documentation.
+  pub(crate) fn share(&self) -> ModuleArena {
+    ModuleArena {
+      sorts         : self.sorts.share(),
+      symbols       : self.symbols.clone(),
+      kinds         : Vec::new(),
+      retired_kinds : Vec::new(),
+      shared_symbols: self.symbols.values().copied().collect(),
+    }
+  }
+
+  /// Takes ownership of `symbol`, registering it under its name. With the `deterministic` feature, the symbol is
+  /// renumbered with its position in the order symbols were added to the arena.
+  pub fn add_symbol(&mut self, mut symbol: Symbol) -> SymbolPtr {
+    let name = symbol.name.clone();
+    assert!(!self.symbols.contains_key(&name), "tried to add a second symbol named {}", name);
+    if DETERMINISTIC {
+      symbol.renumber(self.symbols.len() as u32 + 1);
+    }
+
+    let symbol = heap_construct!(symbol);
+    self.symbols.insert(name, symbol);
+    symbol
+  }
+
+  #[inline(always)]
+  pub fn symbol(&self, name: &IString) -> Option<SymbolPtr> {
+    self.symbols.get(name).copied()
+  }
+
+  /// Whether `symbol` is owned by this arena.
+  pub fn owns_symbol(&self, symbol: SymbolPtr) -> bool {
+    !self.shared_symbols.contains(&symbol) && self.symbols.values().any(|&owned| core::ptr::addr_eq(owned, symbol))
+  }
+
+  /// Whether `symbol` is borrowed from the arena this one was shared from.
+  #[inline(always)]
+  pub fn is_shared_symbol(&self, symbol: SymbolPtr) -> bool {
+    self.shared_symbols.contains(&symbol)
+  }
+
+  /// Retires the arena, leaving it empty, if a DAG reachable from a root refers to its symbols, like dropping it does
+  /// but without the warning. For arenas whose modules are replaced on purpose. See the module documentation.
+  pub(crate) fn retire(&mut self) {
+    if self.live_reference().is_some() {
+      let arena = core::mem::take(self);
+      RETIRED_ARENAS.lock().unwrap().0.push(arena);
+    }
+  }
+
+  /// The name of a symbol of the arena that a DAG reachable from a root refers to, if there is one
+  fn live_reference(&self) -> Option<IString> {
+    if self.symbols.len() == self.shared_symbols.len() {
+      return None;
+    }
+
+    let owned: Set<*mut Symbol>   = self.symbols
+                                            .values()
+                                            .copied()
+                                            .filter(|symbol| !self.shared_symbols.contains(symbol))
+                                            .collect();
+    let mut visited: Set<*mut u8> = Set::default();
+    let mut pending: Vec<DagNodePtr>  = Vec::new();
+
+    crate::core::root_container::for_each_root(|root| pending.push(root));
+    while let Some(node) = pending.pop() {
+      if !visited.insert(node as *mut u8) {
+        continue;
+      }
+
+      let node = unsafe{ &*node };
+      if owned.contains(&node.symbol()) {
+        return Some(node.symbol_ref().name.clone());
+      }
+      pending.extend(node.iter_args());
+    }
+
+    None
+  }
+
+  /// Frees the kinds, symbols, and sorts of the arena in teardown order.
+  fn free(&mut self) {
+    self.kinds.clear();
+    self.retired_kinds.clear();
+    for (_, &symbol) in self.symbols.iter() {
+      if !self.shared_symbols.contains(&symbol) {
+        heap_destroy!(symbol);
+      }
+    }
+    self.symbols.clear();
+    drop(core::mem::take(&mut self.sorts));
+  }
+}
+
+impl Drop for ModuleArena {
+  fn drop(&mut self) {
+    if let Some(name) = self.live_reference() {
+      warning!(
+        1,
+        "dropped a module while a live DAG refers to its symbol {}; keeping its symbols until no root refers to them",
+        name
+      );
+      let arena = core::mem::take(self);
+      RETIRED_ARENAS.lock().unwrap().0.push(arena);
+      return;
+    }
+
+    self.free();
+    collect_retired_arenas();
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      free_theory::FreeDagNode,
+      symbol::Symbol
+    },
+    core::{
+      module_arena::{collect_retired_arenas, ModuleArena},
+      RootContainer
+    }
+  };
+  #[cfg(feature = "deterministic")]
+  use crate::core::{parser::parse_module, prelude::NAT};
+
+  #[test]
+  fn arena_owns_its_symbols() {
+    let mut arena = ModuleArena::default();
+    let f         = arena.add_symbol(Symbol::new(IString::from("f"), Arity::Value(0)));
+
+    assert_eq!(arena.symbol(&IString::from("f")), Some(f));
+    assert!(arena.owns_symbol(f));
+
+    // Dropping the roots of DAGs before the arena is fine.
+    let root = RootContainer::new(FreeDagNode::new(f));
+    drop(root);
+    drop(arena);
+  }
+
+  #[test]
+  fn dropping_an_arena_in_use_retires_it() {
+    let mut arena = ModuleArena::default();
+    let f         = arena.add_symbol(Symbol::new(IString::from("f"), Arity::Value(1)));
+    let a         = arena.add_symbol(Symbol::new(IString::from("a"), Arity::Value(0)));
+
+    let node = FreeDagNode::new(f);
+    unsafe{ &mut *node }.insert_child(FreeDagNode::new(a)).unwrap();
+    let root = RootContainer::new(node);
+    drop(arena);
+
+    // The symbols outlive the arena as long as the DAG is rooted.
+    let child = unsafe{ &*node }.iter_args().next().unwrap();
+    assert_eq!(unsafe{ &*child }.symbol_ref().name.as_ref(), "a");
+
+    drop(root);
+    collect_retired_arenas();
+  }
+
+  #[test]
+  #[cfg(feature = "deterministic")]
+  fn deterministic_modules_are_reproducible() {
+    let first  = parse_module(NAT).unwrap();
+    // Symbols created in between don't change the numbering of the next module's symbols.
+    let _other = Symbol::new(IString::from("other"), Arity::Value(0));
+    let second = parse_module(NAT).unwrap();
+
+    for (name, &symbol) in first.arena.symbols.iter() {
+      let twin = second.arena.symbol(name).unwrap();
+      assert_eq!(unsafe{ &*symbol }.hash_value, unsafe{ &*twin }.hash_value);
+    }
+    assert_eq!(format!("{:?}", first), format!("{:?}", second));
+  }
+}
@@ -0,0 +1,196 @@
+/*!
+
+The dependency graph of a collection of modules, in which each module points to the modules it imports. The graph is
+built from the import declarations of the modules' sources, read with `parser::parse_imports`, without parsing the
+modules themselves, so it can be built for modules that don't parse yet, or whose imports can't be resolved, as build
+tooling over large code bases needs. For a module that has been parsed, `Module::imports` and
+`Module::transitive_imports` give the same information.
+
+ - `imports` and `transitive_imports` give the direct and the transitive dependencies of a module.
+ - `import_cycles` finds the groups of modules that import each other, directly or not. The parser only imports a module
+   once, so a cycle doesn't keep a module from parsing, but it is almost always a mistake.
+ - `to_dot` writes the graph in the DOT language of Graphviz.
+
+A module may import modules that aren't in the graph. They appear in the graph's edges and DOT output, but have no
+imports of their own.
+
+*/
+
+use core::fmt::Write;
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+use crate::{
+  abstractions::{HashMap, IString},
+  core::{
+    parser::{parse_imports, ParseError},
+    prelude
+  }
+};
+
+/// The dependency graph of a collection of modules. See the module documentation.
+#[derive(Clone, Default, Debug)]
+pub struct ModuleGraph {
+  /// The modules in the order they were added, with their direct imports
+  modules: Vec<(IString, Vec<IString>)>,
+  /// The index of each module in `modules`
+  indices: HashMap<IString, usize>,
+}
+
+impl ModuleGraph {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The graph of the modules of the prelude
+  pub fn prelude() -> Self {
+    Self::from_sources(prelude::MODULE_NAMES.iter().filter_map(|&name| prelude::source(name)))
+        .expect("the prelude failed to parse")
+  }
+
+  /// The graph of the modules whose sources are `sources`, each holding exactly one module
+  pub fn from_sources<'s>(sources: impl IntoIterator<Item=&'s str>) -> Result<Self, ParseError> {
+    let mut graph = ModuleGraph::new();
+    for source in sources {
+      let (name, imports) = parse_imports(source)?;
+      graph.add_module(name, imports);
+    }
+
+    Ok(graph)
+  }
+
+  /// Adds the module `name` with the given direct imports, replacing the imports of a module of the same name.
+  pub fn add_module(&mut self, name: IString, imports: Vec<IString>) {
+    match self.indices.get(&name) {
+      Some(&index) => self.modules[index].1 = imports,
+      None         => {
+        self.indices.insert(name.clone(), self.modules.len());
+        self.modules.push((name, imports));
+      }
+    }
+  }
+
+  /// The names of the modules in the graph, in the order they were added
+  pub fn modules(&self) -> impl Iterator<Item=&IString> {
+    self.modules.iter().map(|(name, _)| name)
+  }
+
+  #[inline(always)]
+  pub fn contains(&self, name: &IString) -> bool {
+    self.indices.contains_key(name)
+  }
+
+  /// The modules `name` imports directly, in order, or `None` if `name` isn't in the graph
+  pub fn imports(&self, name: &IString) -> Option<&[IString]> {
+    self.indices.get(name).map(|&index| self.modules[index].1.as_slice())
+  }
+
+  /// The modules `name` imports, directly or not, in the order the parser would first import them, not including
+  /// `name` itself unless it is on an import cycle. Empty if `name` isn't in the graph.
+  pub fn transitive_imports(&self, name: &IString) -> Vec<IString> {
+    let mut imported = Vec::new();
+    self.collect_imports(name, &mut imported);
+    imported
+  }
+
+  fn collect_imports(&self, name: &IString, imported: &mut Vec<IString>) {
+    for import in self.imports(name).unwrap_or_default() {
+      if !imported.contains(import) {
+        imported.push(import.clone());
+        self.collect_imports(import, imported);
+      }
+    }
+  }
+
+  /// The groups of modules that import each other, directly or not, each in the order the modules were added. A
+  /// module that imports itself is a group of one. The groups are ordered by their first modules.
+  pub fn import_cycles(&self) -> Vec<Vec<IString>> {
+    let mut cycles: Vec<Vec<IString>> = Vec::new();
+    let mut grouped = vec![false; self.modules.len()];
+
+    for (index, (name, _)) in self.modules.iter().enumerate() {
+      if grouped[index] {
+        continue;
+      }
+      let reachable = self.transitive_imports(name);
+      if !reachable.contains(name) {
+        continue;
+      }
+
+      // The modules on a cycle through `name` are those that reach `name` back.
+      let mut cycle = Vec::new();
+      for (other, (other_name, _)) in self.modules.iter().enumerate() {
+        if other == index || (reachable.contains(other_name) && self.transitive_imports(other_name).contains(name)) {
+          grouped[other] = true;
+          cycle.push(other_name.clone());
+        }
+      }
+      cycles.push(cycle);
+    }
+
+    cycles
+  }
+
+  /// The graph in the DOT language of Graphviz, with an edge from each module to each module it imports
+  pub fn to_dot(&self) -> String {
+    let mut dot = String::from("digraph modules {\n");
+    for (name, imports) in self.modules.iter() {
+      writeln!(dot, "  \"{}\";", escape(name)).unwrap();
+      for import in imports {
+        writeln!(dot, "  \"{}\" -> \"{}\";", escape(name), escape(import)).unwrap();
+      }
+    }
+    dot.push_str("}\n");
+
+    dot
+  }
+}
+
+/// Escapes `name` for use in a quoted DOT identifier.
+fn escape(name: &IString) -> String {
+  name.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::{
+      module_graph::ModuleGraph,
+      parser::parse_module
+    }
+  };
+
+  fn names(names: &[&str]) -> Vec<IString> {
+    names.iter().map(|&name| IString::from(name)).collect()
+  }
+
+  #[test]
+  fn module_dependencies() {
+    let module = parse_module("
+      mod COLLECTIONS is
+        including LIST .
+        including NAT .
+        sort Bag .
+      endm
+    ").unwrap();
+    assert_eq!(module.imports(), names(&["LIST", "NAT"]));
+    assert_eq!(module.transitive_imports(), names(&["LIST", "NAT", "ELT"]));
+
+    let prelude = ModuleGraph::prelude();
+    assert_eq!(prelude.imports(&"LIST".into()).unwrap(), names(&["NAT", "ELT"]));
+    assert_eq!(prelude.transitive_imports(&"PRELUDE".into()), names(&["NAT", "LIST", "ELT", "SET"]));
+    assert!(prelude.import_cycles().is_empty());
+    assert!(prelude.to_dot().contains("  \"SET\" -> \"ELT\";\n"));
+
+    let graph = ModuleGraph::from_sources([
+      "mod A is including B . endm",
+      "mod B is sort S . including C . including EXTERNAL . endm",
+      "mod C is including A . endm",
+      "mod D is including D . including A . endm",
+    ]).unwrap();
+    assert_eq!(graph.transitive_imports(&"A".into()), names(&["B", "C", "A", "EXTERNAL"]));
+    assert_eq!(graph.import_cycles(), vec![names(&["A", "B", "C"]), names(&["D"])]);
+  }
+}
@@ -0,0 +1,93 @@
+/*!
+
+Module resolvers virtualize where the sources of modules come from. A `Session` asks its resolvers, in the order they
+were added, for the source of each module it is asked to load or that a module it loads imports but that it doesn't
+have yet. An embedder can serve sources from a database, an archive, or a generator by implementing `ModuleResolver`,
+and three resolvers are provided:
+
+ - `IncludePath` looks for a file named after the module in a directory, as `NAT.mod2` or `nat.mod2`, with any of the
+   extensions in `MODULE_FILE_EXTENSIONS`.
+ - A `HashMap` from module names to sources serves the sources in it.
+ - A closure `Fn(&IString) -> Option<String>` serves whatever it returns.
+
+```ignore
+let mut session = Session::new();
+session.add_resolver(IncludePath::new("specs/lib"));
+session.add_resolver(|name: &IString| generated_source(name));
+session.load_module(&"MAIN".into())?;
+```
+
+*/
+
+use std::{
+  collections::HashMap as StdHashMap,
+  hash::BuildHasher,
+  path::PathBuf
+};
+
+use crate::{
+  abstractions::IString,
+  core::session::{LoadError, MODULE_FILE_EXTENSIONS}
+};
+
+/// The source of a module, as a resolver found it
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ModuleSource {
+  /// Where the source came from, like the path of its file, for error messages
+  pub origin: String,
+  pub text  : String,
+}
+
+/// A source of module sources. See the module documentation.
+pub trait ModuleResolver {
+  /// The source of the module `name`, or `None` if the resolver doesn't have it. An error stops the load that asked.
+  fn resolve(&self, name: &IString) -> Result<Option<ModuleSource>, LoadError>;
+}
+
+/// Resolves modules to the files named after them in a directory. See the module documentation.
+#[derive(Clone, Debug)]
+pub struct IncludePath {
+  directory: PathBuf,
+}
+
+impl IncludePath {
+  pub fn new(directory: impl Into<PathBuf>) -> Self {
+    IncludePath { directory: directory.into() }
+  }
+}
+
+impl ModuleResolver for IncludePath {
+  fn resolve(&self, name: &IString) -> Result<Option<ModuleSource>, LoadError> {
+    let lowercase = name.to_lowercase();
+    for stem in [name.as_ref(), lowercase.as_str()] {
+      for extension in MODULE_FILE_EXTENSIONS {
+        let path = self.directory.join(format!("{}.{}", stem, extension));
+        if !path.is_file() {
+          continue;
+        }
+
+        let text = std::fs::read_to_string(&path)
+            .map_err(|error| LoadError::Io { path: path.clone(), message: error.to_string() })?;
+        return Ok(Some(ModuleSource { origin: path.display().to_string(), text }));
+      }
+    }
+
+    Ok(None)
+  }
+}
+
+impl<S: BuildHasher> ModuleResolver for StdHashMap<IString, String, S> {
+  fn resolve(&self, name: &IString) -> Result<Option<ModuleSource>, LoadError> {
+    Ok(self.get(name).map(|text| in_memory(name, text.clone())))
+  }
+}
+
+impl<F: Fn(&IString) -> Option<String>> ModuleResolver for F {
+  fn resolve(&self, name: &IString) -> Result<Option<ModuleSource>, LoadError> {
+    Ok(self(name).map(|text| in_memory(name, text)))
+  }
+}
+
+fn in_memory(name: &IString, text: String) -> ModuleSource {
+  ModuleSource { origin: format!("the resolved source of {}", name), text }
+}
@@ -1,9 +1,13 @@
 /*!
 
+A `NarrowingVariableInfo` assigns indices to the variables occurring in the subject of a narrowing or unification
+problem, which, unlike the variables of a pattern, are DAG nodes rather than terms.
 
 */
 
 
+use alloc::{boxed::Box, vec::Vec};
+
 use crate::{core::substitution::MaybeDagNode, api::dag_node::DagNodePtr};
 
 
@@ -20,7 +24,7 @@ impl NarrowingVariableInfo {
   #[inline(always)]
   pub(crate) fn index_to_variable(&self, index: usize) -> MaybeDagNode {
     if let Some(d) = self.variables.get(index) {
-      d.clone()
+      *d
     } else {
       None
     }
@@ -32,7 +36,7 @@ impl NarrowingVariableInfo {
     match idx {
       Some(i) => i,
       None => {
-        self.variables.push(Some(variable.clone()));
+        self.variables.push(Some(variable));
         (self.variables.len() - 1) as i32
       }
     }
@@ -40,7 +44,7 @@ impl NarrowingVariableInfo {
 
   #[inline(always)]
   pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = (usize, DagNodePtr)> + '_> {
-    Box::new(self.variables.iter().filter_map(|v| (*v).clone()).enumerate())
+    Box::new(self.variables.iter().filter_map(|v| *v).enumerate())
   }
 
   #[inline(always)]
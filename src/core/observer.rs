@@ -0,0 +1,258 @@
+/*!
+
+Structured events for the lifecycle of the engine. An `EngineObserver` is told when a session loads a module, when a
+module's statements are compiled, when garbage is collected, when a reduction starts and finishes, and when a reduction
+or match reaches a limit. It is the single point at which to hook up a metrics exporter or an IDE protocol, rather than
+scraping the log.
+
+```ignore
+struct RewriteCounter(AtomicUsize);
+
+impl EngineObserver for RewriteCounter {
+  fn reduction_finished(&self, _module: &IString, summary: &ReductionSummary) {
+    self.0.fetch_add(summary.rewrites, Relaxed);
+  }
+}
+
+let counter = Arc::new(RewriteCounter(AtomicUsize::new(0)));
+let id      = add_observer(counter.clone());
+context.reduce(subject);
+remove_observer(id);
+```
+
+Every callback has a default implementation that does nothing, so an observer implements only the ones it cares about.
+
+## Delivery
+
+Observers are global, like the allocator, and are told of events on every thread in the order they were added. The
+events carry plain data, never DAGs or other pointers into the engine, so an observer can keep them or send them to
+another thread.
+
+Each callback is made with no lock of the engine held, so an observer may use the engine, though events its use
+causes are delivered to it in turn. Adding or removing an observer from a callback takes effect from the next event.
+With no observers, an event costs a single atomic load.
+
+Reductions are observed at the public entry points of `RewritingContext`, `reduce` and `reduce_bounded` and the methods
+built on them, and not at each of the nested reductions that evaluate conditions or reduce arguments. A collection is
+observed when it is performed by `ok_to_collect_garbage`, which is how the engine collects.
+
+*/
+
+use core::{
+  sync::atomic::{AtomicUsize, Ordering::Relaxed},
+  time::Duration
+};
+use alloc::{string::String, sync::Arc, vec::Vec};
+
+use crate::{
+  abstractions::{IString, Mutex},
+  core::{
+    match_limits::MatchStatistics,
+    rewriting_context::ConditionTimedOut
+  }
+};
+
+/// The observers, with their ids, in the order they were added
+static OBSERVERS: Mutex<Vec<(ObserverId, Arc<dyn EngineObserver>)>> = Mutex::new(Vec::new());
+/// The number of observers, read without taking the lock
+static OBSERVER_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// The id of the next observer added
+static NEXT_OBSERVER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Identifies an observer to `remove_observer`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ObserverId(usize);
+
+/// What a garbage collection did
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct GcSummary {
+  /// The number of the collection, counting from 1
+  pub collection  : u64,
+  /// The number of live nodes before and after the collection
+  pub nodes_before: usize,
+  pub nodes_after : usize,
+  /// The number of arenas the node allocator holds after the collection, zero if it doesn't use arenas
+  pub arenas      : usize,
+  /// How long the engine was paused for the collection
+  pub pause       : Duration,
+}
+
+/// What a reduction did
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ReductionSummary {
+  /// The number of equations applied
+  pub rewrites : usize,
+  /// The associative matches performed, and those that gave up on reaching a limit. See `match_limits`.
+  pub matches  : MatchStatistics,
+  /// Whether the reduction reached a normal form, rather than its rewrite limit
+  pub completed: bool,
+  pub elapsed  : Duration,
+}
+
+/// A limit reached by a reduction or match
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum EngineLimit {
+  /// A bounded reduction gave up after `limit` rewrites.
+  Rewrites { limit: usize },
+  /// An associative match of `pattern` gave up after `limit` subproblems.
+  MatchSubproblems { pattern: String, limit: usize },
+  /// An associative match of `pattern` gave up after `limit` alternatives.
+  MatchAlternatives { pattern: String, limit: usize },
+  /// The evaluation of a statement's conditions ran out of fuel.
+  ConditionFuel(ConditionTimedOut),
+}
+
+/// Told of the events of the engine's lifecycle. See the module documentation.
+pub trait EngineObserver: Send + Sync {
+  /// A session loaded, or reloaded, the module. See `session`.
+  fn module_loaded(&self, _module: &IString) {}
+
+  /// The statements of the module were compiled. `statements` counts its equations, rules, and membership axioms.
+  fn module_compiled(&self, _module: &IString, _statements: usize) {}
+
+  fn garbage_collected(&self, _summary: &GcSummary) {}
+
+  /// A reduction with the module's statements started.
+  fn reduction_started(&self, _module: &IString) {}
+
+  /// The reduction with the module's statements that started last finished.
+  fn reduction_finished(&self, _module: &IString, _summary: &ReductionSummary) {}
+
+  fn limit_reached(&self, _limit: &EngineLimit) {}
+}
+
+/// Adds `observer` after the observers already added, returning the id that removes it.
+pub fn add_observer(observer: Arc<dyn EngineObserver>) -> ObserverId {
+  let id            = ObserverId(NEXT_OBSERVER_ID.fetch_add(1, Relaxed));
+  let mut observers = OBSERVERS.lock().unwrap();
+  observers.push((id, observer));
+  OBSERVER_COUNT.store(observers.len(), Relaxed);
+
+  id
+}
+
+/// Removes the observer `id`, returning whether it was there to remove.
+pub fn remove_observer(id: ObserverId) -> bool {
+  let mut observers = OBSERVERS.lock().unwrap();
+  let count         = observers.len();
+  observers.retain(|(observer, _)| *observer != id);
+  OBSERVER_COUNT.store(observers.len(), Relaxed);
+
+  observers.len() < count
+}
+
+/// Whether any observer would be told of an event, for events that cost something to describe
+#[inline(always)]
+pub(crate) fn is_observed() -> bool {
+  OBSERVER_COUNT.load(Relaxed) > 0
+}
+
+/// Tells every observer of an event by calling `event` with it, with the lock on the observers released.
+#[inline(always)]
+pub(crate) fn notify(event: impl Fn(&dyn EngineObserver)) {
+  if !is_observed() {
+    return;
+  }
+
+  let observers: Vec<Arc<dyn EngineObserver>> = OBSERVERS.lock()
+                                                         .unwrap()
+                                                         .iter()
+                                                         .map(|(_, observer)| observer.clone())
+                                                         .collect();
+  for observer in observers {
+    event(observer.as_ref());
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    sync::{Arc, Mutex},
+    thread::{self, ThreadId}
+  };
+
+  use crate::{
+    abstractions::IString,
+    core::{
+      observer::{add_observer, remove_observer, EngineLimit, EngineObserver, ReductionSummary},
+      parser::parse_term,
+      rewriting_context::RewritingContext,
+      session::Session
+    }
+  };
+
+  /// Records the events of one thread, as tests run concurrently
+  struct Recorder {
+    thread: ThreadId,
+    events: Mutex<Vec<String>>,
+  }
+
+  impl Recorder {
+    fn record(&self, event: String) {
+      if thread::current().id() == self.thread {
+        self.events.lock().unwrap().push(event);
+      }
+    }
+  }
+
+  impl EngineObserver for Recorder {
+    fn module_loaded(&self, module: &IString) {
+      self.record(format!("loaded {}", module));
+    }
+
+    fn module_compiled(&self, module: &IString, statements: usize) {
+      self.record(format!("compiled {} with {} statements", module, statements));
+    }
+
+    fn reduction_started(&self, module: &IString) {
+      self.record(format!("started {}", module));
+    }
+
+    fn reduction_finished(&self, module: &IString, summary: &ReductionSummary) {
+      self.record(format!("finished {} after {} rewrites: {}", module, summary.rewrites, summary.completed));
+    }
+
+    fn limit_reached(&self, limit: &EngineLimit) {
+      self.record(format!("reached {:?}", limit));
+    }
+  }
+
+  #[test]
+  fn observers_are_told_of_lifecycle_events() {
+    let recorder = Arc::new(Recorder { thread: thread::current().id(), events: Mutex::default() });
+    let id       = add_observer(recorder.clone());
+
+    let mut session = Session::new();
+    session.add_resolver(|name: &IString| {
+      (name.as_ref() == "LOOP").then(|| "
+        mod LOOP is
+          sort S .
+          ops a b : -> S .
+          eq a = b .
+          eq b = a .
+        endm
+      ".to_string())
+    });
+    session.load_module(&"LOOP".into()).unwrap();
+    let module      = session.module(&"LOOP".into()).unwrap();
+    let mut context = RewritingContext::new(module);
+    let subject     = parse_term(module, "a").unwrap().term_to_dag(false);
+    assert!(context.reduce_bounded(subject, 3).is_err());
+
+    assert!(remove_observer(id));
+    assert!(!remove_observer(id));
+    context.reduce_bounded(subject, 3).unwrap_err();
+
+    assert_eq!(
+      *recorder.events.lock().unwrap(),
+      [
+        "compiled LOOP with 2 statements",
+        "loaded LOOP",
+        "started LOOP",
+        "reached Rewrites { limit: 3 }",
+        "finished LOOP after 3 rewrites: false",
+      ]
+    );
+  }
+}
@@ -0,0 +1,795 @@
+/*!
+
+A parser for a small module language modeled on Maude's. Terms are written in prefix notation.
+
+```text
+mod PEANO is
+  sort Nat .
+  op 0 : -> Nat [ctor] .
+  op s : Nat -> Nat [ctor] .
+  op plus : Nat Nat -> Nat .
+  vars X Y : Nat .
+  eq plus(0, Y) = Y .
+  eq [plus-s] : plus(s(X), Y) = s(plus(X, Y)) .
+endm
+```
+
+A module declares, in any order but each before its first use,
+
+ - sorts, with `sort` or `sorts`,
+ - the subsort relation, with `subsort` or `subsorts`, as in `subsorts NzNat Zero < Nat .`,
+ - operators, with `op` or `ops`, whose only supported attributes are `ctor`, `latex "…"`, as described in
+   `core::latex`, evaluation strategies, as in `strat (1 0 2)`, as described in `Symbol::set_strategy`, `descent`,
+   for the descent functions of `core::metalevel`, and, for binary operators, `assoc`, `comm`, `idem`, and
+   identities, with `id: c`, `left id: c`, or `right id: c` for a constant `c` declared before the operator,
+ - variables, with `var` or `vars`,
+ - equations and rules, with `eq` and `rl`, optionally labeled as in `rl [label] : lhs => rhs .`, with rules
+   optionally followed by a priority and weight, as in `rl a => b [prio 1 weight 3] .`, as described in
+   `core::rule_selection`, and
+ - imports of other modules, with `including` or `protecting`, as in `including NAT .`.
+
+Every declaration and statement ends with a period. Comments begin with `***` or `---` and run to the end of the line.
+Declaring an operator again with a different domain adds an overloaded declaration to the same symbol; the number of
+arguments must agree. Declaring a variable again with the same sort has no effect.
+
+A variable only matches terms whose sort is the variable's sort or one of its subsorts. Besides the declared variables,
+a term may use variables declared on the fly by their sort, as in `eq plus(0, N:Nat) = N:Nat .`. A declared variable
+`N` of sort `Nat` and `N:Nat` are the same variable. On-the-fly variables in terms parsed by `parse_term` must also
+occur in the module.
+
+The sort of a variable in an equation or rule may also be left to be inferred: a name that is neither declared nor
+annotated with a sort, and occurs without arguments, is the on-the-fly variable of the most general sort that the
+domains of the operators it is an argument of allow, as in `eq plus(0, N) = N .`, where `N` is `N:Nat`. A name whose
+occurrences allow no sort, or more than one most general sort, is an error, as is one that is never an argument of
+an operator. Terms parsed by `parse_term` don't infer sorts.
+
+A term may use the retract from a sort to another sort of its kind, usually a subsort, as in `r:Nat>NzNat(t)`, which
+is created on first use. Retracts are also inserted where the right-hand side of a statement would otherwise be
+ill-sorted. See `core::retract`. Likewise, in a module that declares the sort `Qid`, as those including `META-LEVEL`
+do, a name beginning with `'` is a quoted identifier, a constant of sort `Qid` created on first use. See
+`core::metalevel`.
+
+An import copies the declarations and statements of the imported module into the importing module, as if they were
+written in place of the import. A module imported more than once, directly or through other imports, is only copied
+once. The modules of the prelude can always be imported (see `core::prelude`), and a module parsed with
+`parse_module_in` can also import the modules of a library of sources, which take precedence over the prelude's. See
+`core::session`. A statement that is a variant of an earlier one, equal to it up to the names of its variables (see
+`core::renaming`), is dropped, so that a statement several imports share is only added once. The module keeps the
+names of the modules it imports. See `Module::imports` and `core::module_graph`.
+
+The module language's tokenizer and parser live in the `mod2lib_syntax` crate, which the `module!` and `term!` macros
+also parse their input with, so that the macros accept exactly what this parser accepts. It reads a syntax tree, and
+this module builds the module from the tree, checking what depends on the signature: that sorts, operators, and
+variables are declared, that arities agree, and so on. Building a module closes its sort set and compiles its
+statements, so the module is ready for reduction.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+use alloc::{boxed::Box, format, string::{String, ToString}, vec, vec::Vec};
+
+use mod2lib_syntax::{
+  Declaration,
+  IdentitySide,
+  ModuleSyntax,
+  OperatorAttribute,
+  OperatorSyntax,
+  StatementSyntax,
+  SyntaxError,
+  TermSyntax,
+  Token
+};
+
+use crate::{
+  abstractions::{intern, HashMap, IString, Set},
+  api::{
+    Arity,
+    free_theory::FreeTerm,
+    symbol::{Symbol, SymbolAttribute, SymbolAttributes, SymbolPtr},
+    term::BxTerm,
+    variable_theory::VariableTerm
+  },
+  core::{
+    metalevel::{quoted_identifier, DESCENT_FUNCTIONS},
+    module::Module,
+    pre_equation::{PreEquation, PreEquationKind},
+    prelude,
+    retract::{parse_retract_name, retract},
+    rule_selection::Selection,
+    sort::SortPtr,
+    thread_bound::check_owner
+  }
+};
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ParseError {
+  /// The line of the source on which the error was found, starting at 1
+  pub line   : usize,
+  pub message: String,
+}
+
+impl Display for ParseError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "parse error at line {}: {}", self.line, self.message)
+  }
+}
+
+impl Error for ParseError {}
+
+impl From<SyntaxError> for ParseError {
+  fn from(error: SyntaxError) -> Self {
+    ParseError { line: error.line, message: error.message }
+  }
+}
+
+/// Parses a module from `source`, which holds exactly one module. Malformed source is reported as a `ParseError`, never
+/// by panicking, which the `parse` fuzz target checks.
+pub fn parse_module(source: &str) -> Result<Module, ParseError> {
+  parse_module_in(source, &HashMap::default())
+}
+
+/// Parses a module from `source`, which holds exactly one module, resolving its imports in `library`, the sources of
+/// modules by name, before the prelude. Panics if the engine is bound to another thread (see `thread_bound`).
+pub fn parse_module_in(source: &str, library: &HashMap<IString, String>) -> Result<Module, ParseError> {
+  check_owner();
+  let mut module = build_module(&mod2lib_syntax::parse_module(source)?, library)?;
+  module.compile_statements().expect("statements are checked as they are parsed");
+
+  Ok(module)
+}
+
+/// Parses a module from its tokens, as `parse_module_in` does, and closes its sort set without compiling its
+/// statements.
+pub(crate) fn parse_module_tokens(
+  tokens : Vec<(Token, usize)>,
+  library: &HashMap<IString, String>
+) -> Result<Module, ParseError> {
+  build_module(&mod2lib_syntax::parse_module_tokens(tokens)?, library)
+}
+
+/// Builds the module `syntax` over the signature it declares, resolving its imports in `library` before the prelude,
+/// and closes its sort set without compiling its statements.
+pub(crate) fn build_module(syntax: &ModuleSyntax, library: &HashMap<IString, String>) -> Result<Module, ParseError> {
+  let mut builder = Builder::new(Module::default());
+  builder.library = Some(library);
+  builder.module.name = intern(syntax.name.as_str());
+  builder.declarations(&syntax.declarations, true)?;
+
+  let mut module = builder.module;
+  unsafe{ module.compute_kind_closures(); }
+
+  Ok(module)
+}
+
+/// Parses the equation or rule whose tokens, from its keyword to its period, are `tokens`, and adds it to `module`,
+/// uncompiled, unless it is a variant of one of the module's statements.
+pub(crate) fn parse_statement(module: &mut Module, tokens: Vec<(Token, usize)>) -> Result<(), ParseError> {
+  let syntax       = mod2lib_syntax::parse_statement_tokens(tokens)?;
+  let mut builder  = Builder::new(core::mem::take(module));
+  builder.variants = builder.module
+                            .equations
+                            .iter()
+                            .chain(builder.module.rules.iter())
+                            .map(|statement| statement.variant_form().stable_hash())
+                            .collect();
+
+  let result = builder.statement(&syntax);
+  *module    = builder.module;
+
+  result
+}
+
+/// Reads the name of the module in `source` and the names of the modules it imports directly, in order, without
+/// parsing the rest of the module or resolving the imports. See `core::module_graph`.
+pub fn parse_imports(source: &str) -> Result<(IString, Vec<IString>), ParseError> {
+  let (name, imports) = mod2lib_syntax::parse_imports(source)?;
+
+  Ok((intern(name), imports.into_iter().map(intern).collect()))
+}
+
+/// Parses a term over the symbols of `module` from `source`.
+pub fn parse_term(module: &Module, source: &str) -> Result<BxTerm, ParseError> {
+  build_term(module, &mod2lib_syntax::parse_term(source)?)
+}
+
+/// Builds the term `syntax` over the symbols of `module`, as `parse_term` does.
+pub(crate) fn build_term(module: &Module, syntax: &TermSyntax) -> Result<BxTerm, ParseError> {
+  // The builder's own module stays empty. Symbols are looked up in `module`.
+  let mut builder = Builder::new(Module::default());
+  builder.symbols = Some(module);
+
+  builder.term(syntax)
+}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, ParseError> {
+  Err(ParseError { line, message: message.into() })
+}
+
+/// Builds a module, a statement, or a term from its syntax tree.
+struct Builder<'m> {
+  /// The module being built
+  module  : Module,
+  /// The module whose symbols terms are built over, if not `module`
+  symbols : Option<&'m Module>,
+  /// The sources of the modules that can be imported besides the prelude's
+  library : Option<&'m HashMap<IString, String>>,
+  /// The names of the modules imported so far
+  imported: Set<String>,
+  /// The stable hashes of the variant forms of the statements added so far
+  variants: Set<u64>,
+  /// The inferred sorts of the variables of the statement being built that are neither declared nor annotated
+  inferred: HashMap<String, SortPtr>,
+}
+
+impl<'m> Builder<'m> {
+  fn new(module: Module) -> Self {
+    Builder {
+      module,
+      symbols : None,
+      library : None,
+      imported: Set::default(),
+      variants: Set::default(),
+      inferred: HashMap::default(),
+    }
+  }
+
+  // region Declarations
+
+  /// Adds `declarations` to the module, in order. They are the module's own if `own` is set, and otherwise those of
+  /// an imported module.
+  fn declarations(&mut self, declarations: &[Declaration], own: bool) -> Result<(), ParseError> {
+    for declaration in declarations {
+      match declaration {
+        Declaration::Sorts { line, names }             => self.sorts(*line, names)?,
+        Declaration::Subsorts { line, groups }         => self.subsorts(*line, groups)?,
+        Declaration::Operators(operators)              => self.operators(operators)?,
+        Declaration::Variables { line, names, sort }   => self.variables(*line, names, sort)?,
+        Declaration::Statement(statement)              => self.statement(statement)?,
+        Declaration::Import { line, name }             => self.import(*line, name, own)?,
+      }
+    }
+
+    Ok(())
+  }
+
+  fn sorts(&mut self, line: usize, names: &[String]) -> Result<(), ParseError> {
+    for name in names {
+      let name = intern(name.as_str());
+      if self.module.arena.sorts.get_by_name(&name).is_some() {
+        return error(line, format!("sort {} is declared twice", name));
+      }
+      self.module.arena.sorts.get_or_create_sort(name);
+    }
+
+    Ok(())
+  }
+
+  fn sort(&self, name: &str, line: usize) -> Result<SortPtr, ParseError> {
+    let module = self.symbols.unwrap_or(&self.module);
+    match module.arena.sorts.get_by_name(&intern(name)) {
+      Some(sort) => Ok(sort),
+      None       => error(line, format!("undeclared sort {}", name)),
+    }
+  }
+
+  /// Makes each sort in a group a subsort of each sort in the next group.
+  fn subsorts(&mut self, line: usize, groups: &[Vec<String>]) -> Result<(), ParseError> {
+    for pair in groups.windows(2) {
+      for smaller in pair[0].iter() {
+        let smaller = self.sort(smaller, line)?;
+        for larger in pair[1].iter() {
+          let larger = self.sort(larger, line)?;
+          unsafe{ (*larger).insert_subsort(smaller); }
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn operators(&mut self, syntax: &OperatorSyntax) -> Result<(), ParseError> {
+    let line   = syntax.line;
+    let domain = syntax.domain
+                       .iter()
+                       .map(|name| self.sort(name, line))
+                       .collect::<Result<Vec<_>, _>>()?;
+    let range  = self.sort(syntax.range.as_str(), line)?;
+
+    let mut is_constructor = false;
+    let mut is_descent     = false;
+    let mut is_binder      = false;
+    let mut latex          = None;
+    let mut strategy       = None;
+    let mut axioms         = SymbolAttributes::empty();
+    let mut identity       = None;
+    for attribute in syntax.attributes.iter() {
+      match attribute {
+        OperatorAttribute::Constructor => is_constructor = true,
+        OperatorAttribute::Descent     => is_descent = true,
+        OperatorAttribute::Binder      => is_binder = true,
+        OperatorAttribute::Latex(text) => latex = Some(intern(text.as_str())),
+        OperatorAttribute::Strategy(s) => strategy = Some(s.clone()),
+        OperatorAttribute::Associative => axioms |= SymbolAttribute::Associative,
+        OperatorAttribute::Commutative => axioms |= SymbolAttribute::Commutative,
+        OperatorAttribute::Idempotent  => axioms |= SymbolAttribute::Idempotent,
+        OperatorAttribute::Identity { side, constant } => {
+          axioms |= match side {
+            IdentitySide::Left  => SymbolAttribute::LeftIdentity.into(),
+            IdentitySide::Right => SymbolAttribute::RightIdentity.into(),
+            IdentitySide::Both  => SymbolAttribute::LeftIdentity | SymbolAttribute::RightIdentity,
+          };
+          identity = Some(self.identity(constant, line)?);
+        }
+      }
+    }
+
+    let arity = match Arity::new(domain.len()) {
+      Ok(arity)  => arity,
+      Err(error) => return self::error(line, error.to_string()),
+    };
+    if !axioms.is_empty() && arity != Arity::Value(2) {
+      return error(line, "only binary operators can be associative, commutative, idempotent, or have an identity");
+    }
+    if is_binder && domain.len() < 2 {
+      return error(line, "a binder needs a bound variable and a body");
+    }
+    for name in syntax.names.iter() {
+      let name   = intern(name.as_str());
+      let symbol = match self.module.arena.symbol(&name) {
+        Some(symbol) if unsafe{ &*symbol }.arity == arity => symbol,
+        Some(_) => return error(line, format!("{} is already declared with a different number of arguments", name)),
+        None    => self.module.arena.add_symbol(Symbol::new(name, arity)),
+      };
+
+      let symbol = unsafe{ &mut *symbol };
+      if symbol.is_variable() {
+        return error(line, format!("{} is already declared as a variable", symbol.name));
+      }
+      if is_constructor {
+        symbol.attributes.insert(SymbolAttribute::Constructor);
+      }
+      if is_descent {
+        if !DESCENT_FUNCTIONS.contains(&symbol.name.as_ref()) {
+          return error(line, format!("{} is not a descent function", symbol.name));
+        }
+        symbol.attributes.insert(SymbolAttribute::Descent);
+      }
+      if is_binder {
+        symbol.attributes.insert(SymbolAttribute::Binder);
+      }
+      symbol.attributes.insert(axioms);
+      if let Some(identity) = identity {
+        if self.module.identities.get(&(symbol as *mut Symbol)).is_some_and(|&existing| existing != identity) {
+          return error(line, format!("{} is already declared with a different identity", symbol.name));
+        }
+        self.module.identities.insert(symbol, identity);
+      }
+      if let Some(latex) = &latex {
+        if symbol.latex.as_ref().is_some_and(|existing| existing != latex) {
+          return error(line, format!("{} is already declared with a different latex attribute", symbol.name));
+        }
+        symbol.set_latex(latex.clone());
+      }
+      if let Some(strategy) = &strategy {
+        let previous = core::mem::take(&mut symbol.strategy);
+        if let Err(message) = symbol.set_strategy(strategy.clone()) {
+          return error(line, format!("{}: {}", symbol.name, message));
+        }
+        if !previous.is_empty() && previous != symbol.strategy {
+          return error(line, format!("{} is already declared with a different strategy", symbol.name));
+        }
+      }
+      symbol.sort_table.add_op_declaration(domain.clone(), range, is_constructor);
+    }
+
+    Ok(())
+  }
+
+  /// The constant of an identity attribute
+  fn identity(&self, name: &str, line: usize) -> Result<SymbolPtr, ParseError> {
+    let name = intern(name);
+    match self.module.arena.symbol(&name) {
+      Some(symbol) if unsafe{ &*symbol }.arity == Arity::Value(0) && !unsafe{ &*symbol }.is_variable() => Ok(symbol),
+      _ => error(line, format!("the identity {} is not a declared constant", name)),
+    }
+  }
+
+  fn variables(&mut self, line: usize, names: &[String], sort: &str) -> Result<(), ParseError> {
+    let sort = self.sort(sort, line)?;
+
+    for name in names {
+      let name = intern(name.as_str());
+      match self.module.arena.symbol(&name) {
+        Some(symbol) if unsafe{ &*symbol }.variable_sort() == Some(sort) => {}
+        Some(symbol) if unsafe{ &*symbol }.is_variable() => {
+          return error(line, format!("variable {} is already declared with a different sort", name));
+        }
+        Some(_) => return error(line, format!("{} is already declared as an operator", name)),
+        None    => { self.module.arena.add_symbol(Symbol::new_sorted_variable(name, sort)); }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Adds the declarations and statements of the library or prelude module `name` in place of the import, unless the
+  /// module has been imported already. The import is one of the module's own if `own` is set.
+  fn import(&mut self, line: usize, name: &str, own: bool) -> Result<(), ParseError> {
+    let library_source = self.library.and_then(|library| library.get(&intern(name)));
+    let source = match library_source.map(String::as_str).or_else(|| prelude::source(name)) {
+      Some(source) => source,
+      None         => return error(line, format!("unknown module {}", name)),
+    };
+    let interned = intern(name);
+    if own && !self.module.imports.contains(&interned) {
+      self.module.imports.push(interned.clone());
+    }
+    if !self.imported.insert(name.to_string()) {
+      return Ok(());
+    }
+    self.module.transitive_imports.push(interned);
+
+    let imported = mod2lib_syntax::parse_module(source)?;
+    self.declarations(&imported.declarations, false)
+  }
+
+  /// Adds the equation or rule `syntax` to the module, unless it is a variant of an earlier one. Every variable of the
+  /// right-hand side must occur in the left-hand side.
+  fn statement(&mut self, syntax: &StatementSyntax) -> Result<(), ParseError> {
+    let line      = syntax.line;
+    self.inferred = self.infer_variable_sorts(syntax)?;
+    let lhs_term  = self.term(&syntax.lhs)?;
+    let rhs_term  = self.term(&syntax.rhs)?;
+
+    if lhs_term.is_variable() {
+      return error(line, "the left-hand side of a statement can't be a variable");
+    }
+
+    let kind = match syntax.is_rule {
+      true  => PreEquationKind::Rule { rhs_term },
+      false => PreEquationKind::Equation { rhs_term },
+    };
+    let name          = syntax.label.as_deref().map(intern);
+    let mut statement = PreEquation::new(name, lhs_term, kind, vec![]);
+    statement.selection = syntax.selection.map(|selection| {
+      let default = Selection::default();
+      Selection {
+        priority: selection.priority.unwrap_or(default.priority),
+        weight  : selection.weight.unwrap_or(default.weight),
+      }
+    });
+    if let Err(message) = statement.check() {
+      return error(line, message.to_string());
+    }
+    // Only a statement whose hash has been seen before can be a variant of an earlier statement.
+    let statements = match syntax.is_rule {
+      true  => &mut self.module.rules,
+      false => &mut self.module.equations,
+    };
+    if self.variants.insert(statement.variant_form().stable_hash())
+        || !statements.iter().any(|existing| existing.is_variant_of(&statement))
+    {
+      statements.push(statement);
+    }
+
+    Ok(())
+  }
+
+  /**
+  Infers the sorts of the names in `statement` that are neither declared nor annotated with a sort and occur without
+  arguments, which are taken for variables. Each occurrence of such a name as an argument of an operator allows the
+  sorts of the operator's domains at that position and their subsorts, and the name's sort is the most general sort
+  every occurrence allows. It is an error if there is no such sort or if there is more than one. A name that is never
+  an argument of a declared operator has no inferred sort.
+  */
+  fn infer_variable_sorts(&self, statement: &StatementSyntax) -> Result<HashMap<String, SortPtr>, ParseError> {
+    // The sorts each occurrence of a name allows, by name in the order in which the names first occur
+    let mut occurrences: Vec<(&str, Vec<Vec<SortPtr>>)> = Vec::new();
+    self.occurrences(&statement.lhs, None, &mut occurrences);
+    self.occurrences(&statement.rhs, None, &mut occurrences);
+
+    let mut inferred = HashMap::default();
+    for (name, allowed) in occurrences {
+      if allowed.is_empty() {
+        continue;
+      }
+      let agrees     = |sort: &SortPtr| {
+        allowed.iter().all(|domains| domains.iter().any(|&domain| is_subsort(*sort, domain)))
+      };
+      let consistent: Vec<SortPtr>   = self.module.arena.sorts.iter().map(|(_, sort)| sort).filter(agrees).collect();
+      let is_maximal = |sort: &SortPtr| {
+        !consistent.iter().any(|&other| !core::ptr::addr_eq(other, *sort) && is_subsort(*sort, other))
+      };
+      let most_general: Vec<SortPtr> = consistent.iter().copied().filter(is_maximal).collect();
+      match most_general.as_slice() {
+        [sort] => { inferred.insert(name.to_string(), *sort); }
+        []     => return error(statement.line, format!("no sort of variable {} agrees with every occurrence", name)),
+        sorts  => {
+          let sorts = sorts.iter().map(|&sort| unsafe{ &*sort }.name.to_string()).collect::<Vec<_>>().join(", ");
+          return error(statement.line, format!("the sort of variable {} is ambiguous between {}", name, sorts));
+        }
+      }
+    }
+
+    Ok(inferred)
+  }
+
+  /// Records the sorts the occurrences in `term` of names to infer the sorts of allow, where `parent` is the operator
+  /// `term` is an argument of, if it is declared, and the position of the argument.
+  fn occurrences<'t>(
+    &self,
+    term       : &'t TermSyntax,
+    parent     : Option<(SymbolPtr, usize)>,
+    occurrences: &mut Vec<(&'t str, Vec<Vec<SortPtr>>)>
+  ) {
+    let name = term.name.as_str();
+    if !term.args.is_empty() {
+      let operator = self.symbol(name, term.line).ok().filter(|&symbol| !unsafe{ &*symbol }.is_variable());
+      for (argument, arg) in term.args.iter().enumerate() {
+        self.occurrences(arg, operator.map(|operator| (operator, argument)), occurrences);
+      }
+      return;
+    }
+
+    let annotated = name.split_once(':').is_some_and(|(name, sort)| !name.is_empty() && !sort.is_empty());
+    if annotated || self.is_quoted_identifier(name) || self.symbol(name, term.line).is_ok() {
+      return;
+    }
+    let index = match occurrences.iter().position(|(occurring, _)| *occurring == name) {
+      Some(index) => index,
+      None        => {
+        occurrences.push((name, Vec::new()));
+        occurrences.len() - 1
+      }
+    };
+    if let Some((parent, argument)) = parent {
+      let domains = unsafe{ &*parent }.sort_table
+                                      .declarations()
+                                      .iter()
+                                      .filter_map(|declaration| declaration.domain.get(argument).copied())
+                                      .collect();
+      occurrences[index].1.push(domains);
+    }
+  }
+
+  // endregion Declarations
+
+  // region Terms
+
+  fn symbol(&self, name: &str, line: usize) -> Result<SymbolPtr, ParseError> {
+    let module = self.symbols.unwrap_or(&self.module);
+    match module.arena.symbol(&intern(name)) {
+      Some(symbol) => Ok(symbol),
+      None         => error(line, format!("undeclared operator or variable {}", name)),
+    }
+  }
+
+  /// The symbol of the on-the-fly variable `X:S`, which is the declared variable `X` if it has the sort `S`, and
+  /// otherwise a variable named `X:S`, created on first use.
+  fn sorted_variable(&mut self, name: &str, sort_name: &str, line: usize) -> Result<SymbolPtr, ParseError> {
+    let sort = self.sort(sort_name, line)?;
+    if let Ok(symbol) = self.symbol(name, line) {
+      if unsafe{ &*symbol }.variable_sort() == Some(sort) {
+        return Ok(symbol);
+      }
+    }
+
+    let full_name = format!("{}:{}", name, sort_name);
+    match self.symbol(full_name.as_str(), line) {
+      Ok(symbol) => Ok(symbol),
+      // Terms built by `build_term` can't add symbols to the module they are built over.
+      Err(error) if self.symbols.is_some() => Err(error),
+      Err(_) => Ok(self.module.arena.add_symbol(Symbol::new_sorted_variable(intern(full_name), sort))),
+    }
+  }
+
+  /// The symbol of the retract `r:A>B`, created on first use
+  fn retract(&mut self, name: &str, from: &str, to: &str, line: usize) -> Result<SymbolPtr, ParseError> {
+    // Terms built by `build_term` can't add symbols to the module they are built over.
+    if self.symbols.is_some() {
+      return self.symbol(name, line);
+    }
+    let (from, to) = (self.sort(from, line)?, self.sort(to, line)?);
+    Ok(retract(&mut self.module.arena, from, to))
+  }
+
+  /// Whether `name` is a quoted identifier, which it is if it begins with `'` and the module declares the sort `Qid`
+  fn is_quoted_identifier(&self, name: &str) -> bool {
+    name.len() > 1 && name.starts_with('\'') && self.sort("Qid", 0).is_ok()
+  }
+
+  /// The symbol of the quoted identifier `name`, created on first use
+  fn quoted_identifier(&mut self, name: &str, line: usize) -> Result<SymbolPtr, ParseError> {
+    // Terms built by `build_term` can't add symbols to the module they are built over.
+    if self.symbols.is_some() {
+      return self.symbol(name, line);
+    }
+    let qid = self.sort("Qid", line)?;
+    Ok(quoted_identifier(&mut self.module.arena, qid, &name[1..]))
+  }
+
+  fn term(&mut self, syntax: &TermSyntax) -> Result<BxTerm, ParseError> {
+    let name   = syntax.name.as_str();
+    let line   = syntax.line;
+    let symbol = match name.split_once(':') {
+      _ if self.symbol(name, line).is_err() && parse_retract_name(name).is_some() => {
+        let (from, to) = parse_retract_name(name).unwrap();
+        self.retract(name, from, to, line)?
+      }
+      _ if self.is_quoted_identifier(name) && self.symbol(name, line).is_err() => {
+        self.quoted_identifier(name, line)?
+      }
+      Some((variable, sort)) if !variable.is_empty() && !sort.is_empty() && self.symbol(name, line).is_err() => {
+        self.sorted_variable(variable, sort, line)?
+      }
+      _ => match self.inferred.get(name) {
+        Some(&sort) => {
+          let sort_name = unsafe{ &*sort }.name.clone();
+          self.sorted_variable(name, &sort_name, line)?
+        }
+        None => self.symbol(name, line)?
+      }
+    };
+
+    let args = syntax.args
+                     .iter()
+                     .map(|arg| self.term(arg))
+                     .collect::<Result<Vec<BxTerm>, _>>()?;
+
+    let symbol_ref = unsafe{ &*symbol };
+    if symbol_ref.is_variable() {
+      if !args.is_empty() {
+        return error(line, format!("variable {} has arguments", name));
+      }
+      return Ok(Box::new(VariableTerm::new(symbol)));
+    }
+    if !symbol_ref.arity.accepts(args.len()) {
+      return error(line, format!("{} takes {} arguments but is given {}", name, symbol_ref.arity, args.len()));
+    }
+
+    Ok(FreeTerm::with_args(symbol, args))
+  }
+
+  // endregion Terms
+}
+
+/// Whether `sort` is `other` or one of its subsorts, by the declared subsorts, which don't have to be closed yet
+fn is_subsort(sort: SortPtr, other: SortPtr) -> bool {
+  let mut visited = vec![other];
+  let mut pending = vec![other];
+  while let Some(next) = pending.pop() {
+    if core::ptr::addr_eq(next, sort) {
+      return true;
+    }
+    for &subsort in unsafe{ &*next }.subsorts.iter() {
+      if !visited.iter().any(|&seen| core::ptr::addr_eq(seen, subsort)) {
+        visited.push(subsort);
+        pending.push(subsort);
+      }
+    }
+  }
+
+  false
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::parser::{parse_module, parse_term, ParseError},
+    reduce
+  };
+
+  const PEANO: &str = "
+    mod PEANO is
+      sorts Zero NzNat Nat .
+      subsorts Zero NzNat < Nat .
+      op 0 : -> Zero [ctor] .
+      op s : Nat -> NzNat [ctor] .
+      op plus : Nat Nat -> Nat .
+      vars X Y : Nat .
+      *** Addition on the first argument
+      eq plus(0, Y) = Y .
+      eq [plus-s] : plus(s(X), Y) = s(plus(X, Y)).
+    endm
+  ";
+
+  #[test]
+  fn parse_peano() {
+    let module = parse_module(PEANO).unwrap();
+    assert_eq!(module.name, IString::from("PEANO"));
+    assert_eq!(module.arena.sorts.len(), 3);
+    assert_eq!(module.arena.symbols.len(), 5);
+    assert_eq!(module.equations.len(), 2);
+    assert_eq!(module.equations[1].name, Some(IString::from("plus-s")));
+
+    let term = parse_term(&module, "plus(s(0), s(0))").unwrap();
+    assert_eq!(term.symbol_ref().name, IString::from("plus"));
+  }
+
+  #[test]
+  fn parse_errors_have_lines() {
+    let error = parse_module("mod M is\n  sort A .\n  op f : B -> A .\nendm").unwrap_err();
+    assert_eq!(error, ParseError { line: 3, message: "undeclared sort B".to_string() });
+
+    let module = parse_module(PEANO).unwrap();
+    assert!(parse_term(&module, "s(0, 0)").is_err());
+    assert!(parse_term(&module, "plus(0, s(0)").is_err());
+    assert!(parse_term(&module, "zero").is_err());
+
+    let error = parse_module("mod M is\n  sort A .\n  op f : A -> A .\n  vars X Y : A .\n  eq f(X) = Y .\nendm");
+    assert_eq!(error.unwrap_err().line, 5);
+  }
+
+  #[test]
+  fn sorted_variables_match_their_sort() {
+    let source = "
+      mod SORTED is
+        sorts Zero NzNat Nat .
+        subsorts Zero NzNat < Nat .
+        op 0 : -> Zero [ctor] .
+        op s : Nat -> NzNat [ctor] .
+        op pred : Nat -> Nat .
+        var Z : Zero .
+        eq pred(Z) = Z .
+        eq pred(s(N:Nat)) = N:Nat .
+        op nonzero : Nat -> Nat .
+        eq nonzero(N:NzNat) = s(0) .
+      endm
+    ";
+    assert_eq!(reduce(source, "pred(s(s(0)))").unwrap(), "s(0)");
+    assert_eq!(reduce(source, "pred(0)").unwrap(), "0");
+    assert_eq!(reduce(source, "nonzero(s(0))").unwrap(), "s(0)");
+    // `0` has sort `Zero`, which is not below `NzNat`.
+    assert_eq!(reduce(source, "nonzero(0)").unwrap(), "nonzero(0)");
+    assert_eq!(reduce(source, "nonzero(pred(s(s(0))))").unwrap(), "s(0)");
+
+    let module = parse_module(source).unwrap();
+    assert!(parse_term(&module, "pred(N:Nat)").is_ok());
+    assert!(parse_term(&module, "pred(M:Nat)").is_err());
+
+    let error = parse_module("mod M is\n  sorts A B .\n  var X : A .\n  var X : B .\nendm").unwrap_err();
+    assert_eq!(error.line, 4);
+  }
+
+  #[test]
+  fn variable_sorts_are_inferred() {
+    let source = "
+      mod INFERRED is
+        sorts Zero NzNat Nat .
+        subsorts Zero NzNat < Nat .
+        op 0 : -> Zero [ctor] .
+        op s : Nat -> NzNat [ctor] .
+        op p : NzNat -> Nat .
+        op plus : Nat Nat -> Nat .
+        eq p(s(N)) = N .
+        eq plus(N, 0) = N .
+        eq plus(N, s(M)) = s(plus(N, M)) .
+        eq plus(0, p(N)) = N .
+      endm
+    ";
+    assert_eq!(reduce(source, "plus(s(0), s(s(0)))").unwrap(), "s(s(s(0)))");
+    let module = parse_module(source).unwrap();
+    let lhs    = module.equations[3].lhs_term.as_ref();
+    let n      = lhs.iter_args().nth(1).unwrap().iter_args().next().unwrap();
+    assert_eq!(n.symbol_ref().name, IString::from("N:NzNat"));
+
+    let error = parse_module("
+      mod AMBIGUOUS is
+        sorts A B C D .
+        subsorts A D < B .
+        subsorts A D < C .
+        op f : B C -> B .
+        eq f(X, X) = X .
+      endm
+    ");
+    assert_eq!(error.unwrap_err().message, "the sort of variable X is ambiguous between A, D");
+    let error = parse_module("mod M is\n  sorts A B .\n  op f : A B -> A .\n  eq f(X, X) = X .\nendm");
+    let message = "no sort of variable X agrees with every occurrence".to_string();
+    assert_eq!(error.unwrap_err(), ParseError { line: 4, message });
+  }
+}
@@ -0,0 +1,283 @@
+/*!
+
+A cache of patterns compiled for matching outside any statement, as tools matching ad hoc patterns against DAGs do.
+`Module::compile_pattern` compiles a pattern the first time it is asked for and returns the same `CachedPattern` every
+time after, so compiling the same pattern over and over costs a hash lookup.
+
+Patterns are keyed by the stable hash of their `VariantForm` (see `renaming`), so patterns that differ only in the
+names of their variables share an entry. A cached pattern is compiled from a copy of the pattern with its variables
+renamed canonically, and reports the bindings of a match in the order in which the pattern's variables first occur,
+which is the same for every variant.
+
+Compilation depends on the sorts, subsorts, and identities of the module, so the editing methods of `Module` invalidate
+the cache. Code that changes the module's public fields directly must call `Module::invalidate_pattern_cache` itself.
+Invalidation only forgets the cached patterns: a `CachedPattern` already handed out stays usable, as compiled.
+
+`DagNode::count_occurrences` and `DagNode::contains` look for a cached pattern throughout a DAG. They match modulo the
+axioms of the pattern's symbols, as compiled patterns do, and count occurrences in the term the DAG represents, so a
+shared subterm counts once per occurrence. Each node is matched once however often it is shared.
+
+*/
+
+use alloc::{rc::Rc, vec::Vec};
+
+use crate::{
+  abstractions::{HashMap, NatSet, Set},
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::SymbolPtr,
+    term::{BxTerm, Term}
+  },
+  core::{
+    automata::CompiledPattern,
+    collapse::analyse_collapses,
+    match_failure::{find_match_failure, MatchFailure, MatchFailureReason},
+    proof_trace::dag_to_string,
+    renaming::{VariableRenaming, VariantForm},
+    substitution::Substitution,
+    VariableInfo
+  }
+};
+
+/// A pattern compiled on its own. See the module documentation.
+pub struct CachedPattern {
+  pattern      : CompiledPattern,
+  variable_info: VariableInfo,
+  form         : VariantForm,
+  /// The pattern with its variables renamed canonically. The compiled pattern and `variable_info` refer to it.
+  term         : BxTerm,
+  /// Owns the variables of `term`
+  renaming     : VariableRenaming,
+}
+
+impl CachedPattern {
+  /// Compiles `pattern`, analysing its collapses modulo `identities`. The sorts of the module must be closed.
+  pub fn new(pattern: &dyn Term, identities: &HashMap<SymbolPtr, SymbolPtr>) -> Self {
+    let mut renaming = VariableRenaming::new();
+    let mut term     = pattern.rename_variables(&mut renaming);
+    analyse_collapses(term.as_mut(), identities);
+
+    let mut variable_info = VariableInfo::new();
+    term.index_variables(&mut variable_info);
+    term.determine_context_variables();
+    term.compute_sort();
+    let pattern = CompiledPattern::new(term.as_ref(), &mut variable_info, &mut NatSet::new());
+
+    CachedPattern {
+      pattern,
+      variable_info,
+      form: VariantForm::of_term(term.as_ref()),
+      term,
+      renaming,
+    }
+  }
+
+  /// The pattern as compiled, with its variables renamed canonically
+  #[inline(always)]
+  pub fn term(&self) -> &dyn Term {
+    self.term.as_ref()
+  }
+
+  /// The number of distinct variables of the pattern
+  #[inline(always)]
+  pub fn variable_count(&self) -> usize {
+    self.renaming.len()
+  }
+
+  /// Matches `subject` against the pattern. Returns the bindings of the pattern's variables, in the order in which
+  /// they first occur in the pattern, or `None` if the pattern doesn't match.
+  pub fn match_(&self, subject: DagNodePtr) -> Option<Vec<DagNodePtr>> {
+    self.match_skipping(subject, 0)
+  }
+
+  /// Like `match_`, but skips the first `skip` matches, so that the alternative matches of a pattern whose top symbol
+  /// is associative can be had one at a time. See `CompiledPattern::match_skipping`.
+  pub fn match_skipping(&self, subject: DagNodePtr, skip: usize) -> Option<Vec<DagNodePtr>> {
+    let mut solution = Substitution::with_capacity(self.variable_info.protected_variable_count() as usize);
+    if !self.pattern.match_skipping(subject, &mut solution, skip) {
+      return None;
+    }
+
+    Some(
+      (0..self.variable_count())
+          .map(|index| solution.value(index).expect("a successful match left a variable unbound"))
+          .collect()
+    )
+  }
+
+  /// Why `subject` doesn't match the pattern, or `None` if it does. Variables are reported by their names in the
+  /// pattern the cached pattern was compiled from. See `match_failure`.
+  pub fn explain_failure(&self, subject: DagNodePtr) -> Option<MatchFailure> {
+    if self.match_(subject).is_some() {
+      return None;
+    }
+
+    let variable_name = |canonical: SymbolPtr| {
+      let variable = self.renaming.original(canonical).unwrap_or(canonical);
+      unsafe{ &*variable }.name.clone()
+    };
+    let failure = find_match_failure(self.term(), subject, &variable_name);
+
+    Some(failure.unwrap_or_else(
+      || MatchFailure { position: Vec::new(), found: dag_to_string(subject), reason: MatchFailureReason::Unexplained }
+    ))
+  }
+}
+
+impl dyn DagNode {
+  /// The number of positions of the term the DAG represents at which `pattern` matches. See `pattern_cache`.
+  pub fn count_occurrences(&self, pattern: &CachedPattern) -> usize {
+    count_occurrences(self as *const dyn DagNode as DagNodePtr, pattern, &mut HashMap::default())
+  }
+
+  /// Whether `pattern` matches the DAG or any of its subterms. See `pattern_cache`.
+  pub fn contains(&self, pattern: &CachedPattern) -> bool {
+    contains(self as *const dyn DagNode as DagNodePtr, pattern, &mut Set::default())
+  }
+}
+
+/// `counts` holds the number of occurrences below each node already visited.
+fn count_occurrences(node: DagNodePtr, pattern: &CachedPattern, counts: &mut HashMap<*const u8, usize>) -> usize {
+  if let Some(&count) = counts.get(&(node as *const u8)) {
+    return count;
+  }
+
+  let mut count = pattern.match_(node).is_some() as usize;
+  for arg in unsafe{ &*node }.iter_args() {
+    count += count_occurrences(arg, pattern, counts);
+  }
+  counts.insert(node as *const u8, count);
+
+  count
+}
+
+/// `absent` holds the nodes already visited, none of which contains an occurrence.
+fn contains(node: DagNodePtr, pattern: &CachedPattern, absent: &mut Set<*const u8>) -> bool {
+  if absent.contains(&(node as *const u8)) {
+    return false;
+  }
+  if pattern.match_(node).is_some() || unsafe{ &*node }.iter_args().any(|arg| contains(arg, pattern, absent)) {
+    return true;
+  }
+  absent.insert(node as *const u8);
+
+  false
+}
+
+/// The compiled patterns of a module, by the stable hashes of their forms. See the module documentation.
+#[derive(Default)]
+pub struct PatternCache {
+  entries: HashMap<u64, Vec<Rc<CachedPattern>>>,
+  /// The number of lookups that found their pattern already compiled
+  pub hits  : usize,
+  /// The number of lookups that compiled their pattern
+  pub misses: usize,
+}
+
+impl PatternCache {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The compiled `pattern`, compiled now if no variant of it is cached
+  pub fn get_or_compile(&mut self, pattern: &dyn Term, identities: &HashMap<SymbolPtr, SymbolPtr>)
+      -> Rc<CachedPattern>
+  {
+    let form    = VariantForm::of_term(pattern);
+    let entries = self.entries.entry(form.stable_hash()).or_default();
+    if let Some(cached) = entries.iter().find(|cached| cached.form == form) {
+      self.hits += 1;
+      return cached.clone();
+    }
+
+    self.misses += 1;
+    let cached = Rc::new(CachedPattern::new(pattern, identities));
+    entries.push(cached.clone());
+
+    cached
+  }
+
+  /// The number of patterns cached
+  pub fn len(&self) -> usize {
+    self.entries.values().map(|entries| entries.len()).sum()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Forgets every cached pattern. The counts of hits and misses are kept.
+  #[inline(always)]
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::rc::Rc;
+
+  use crate::core::parser::{parse_module, parse_term};
+
+  const MODULE: &str = "
+    mod PATTERNS is
+      sorts Zero Nat .
+      subsort Zero < Nat .
+      op 0 : -> Zero .
+      op s : Nat -> Nat .
+      op f : Nat Nat -> Nat .
+      vars X Y : Nat .
+      var Z : Zero .
+    endm
+  ";
+
+  #[test]
+  fn patterns_are_compiled_once() {
+    let mut module = parse_module(MODULE).unwrap();
+
+    let pattern = module.compile_pattern(parse_term(&module, "f(X, s(Y))").unwrap().as_ref());
+    let variant = module.compile_pattern(parse_term(&module, "f(Y, s(X))").unwrap().as_ref());
+    let other   = module.compile_pattern(parse_term(&module, "f(X, s(X))").unwrap().as_ref());
+    assert!(Rc::ptr_eq(&pattern, &variant));
+    assert!(!Rc::ptr_eq(&pattern, &other));
+    assert_eq!((module.pattern_cache().hits, module.pattern_cache().misses, module.pattern_cache().len()), (1, 2, 2));
+
+    let subject  = parse_term(&module, "f(0, s(s(0)))").unwrap().term_to_dag(false);
+    let bindings = pattern.match_(subject).unwrap();
+    assert_eq!(bindings.len(), 2);
+    assert_eq!(unsafe{ &*bindings[1] }.symbol_ref().name.as_ref(), "s");
+    assert!(other.match_(subject).is_none());
+
+    let sorted = module.compile_pattern(parse_term(&module, "s(Z)").unwrap().as_ref());
+    assert!(sorted.match_(parse_term(&module, "s(0)").unwrap().term_to_dag(false)).is_some());
+
+    // Editing the module forgets the compiled patterns.
+    module.add_sort("Pos".into());
+    assert!(module.pattern_cache().is_empty());
+    let recompiled = module.compile_pattern(parse_term(&module, "f(X, s(Y))").unwrap().as_ref());
+    assert!(!Rc::ptr_eq(&pattern, &recompiled));
+  }
+
+  #[test]
+  fn occurrences_are_counted_per_position() {
+    let module  = parse_module(MODULE).unwrap();
+    let pattern = module.compile_pattern(parse_term(&module, "s(X)").unwrap().as_ref());
+    let zero    = module.compile_pattern(parse_term(&module, "s(Z)").unwrap().as_ref());
+    let pair    = module.compile_pattern(parse_term(&module, "f(X, X)").unwrap().as_ref());
+
+    // The two occurrences of s(s(0)) are one shared node.
+    let subject = parse_term(&module, "f(s(s(0)), s(s(0)))").unwrap().term_to_dag(false);
+    let subject = unsafe{ &*subject };
+    assert_eq!(subject.count_occurrences(&pattern), 4);
+    assert_eq!(subject.count_occurrences(&zero), 2);
+    assert_eq!(subject.count_occurrences(&pair), 1);
+    assert!(subject.contains(&zero));
+
+    let zero_only = unsafe{ &*parse_term(&module, "f(0, 0)").unwrap().term_to_dag(false) };
+    assert!(!zero_only.contains(&pattern));
+    assert_eq!(zero_only.count_occurrences(&pattern), 0);
+  }
+}
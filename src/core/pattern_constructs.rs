@@ -0,0 +1,309 @@
+/*!
+
+Pattern constructs beyond plain variables, for rule sets in the style of Mathematica:
+
+ - *Alternatives*, `x:(a|f[_])`, restrict a variable to the bindings matched by at least one of several patterns. See
+   `ConstrainedVariable::with_alternatives`.
+ - *Exceptions*, `x:Except[a]`, restrict a variable to the bindings not matched by a pattern. See
+   `ConstrainedVariable::except`.
+ - *Optional arguments*, `f[x_, y_:0]`, may be left out of a subject, in which case their variable is bound to a
+   default. See `expand_optional_arguments`.
+
+The patterns of alternatives and exceptions are compiled on their own, with `Module::compile_pattern`, and are checked
+like any other constraint on a variable, once structural matching has succeeded. Their variables are local: they match
+anything consistent within the pattern and bind nothing in the enclosing pattern, so `x:(f(Y)|g(Y))` doesn't bind `Y`.
+
+An optional argument is an argument of a symbol taking a variable number of arguments, as free symbols with a fixed
+arity always have every argument. A statement with optional arguments is expanded into one statement for every way of
+leaving some of them out, with the default in place of each variable left out on the right-hand side and in the
+conditions. The expansions that keep more arguments come first, so that a subject with an argument binds it rather
+than the default.
+
+```ignore
+// f(X, Y:0) = g(X, Y)
+let statements = expand_optional_arguments(statement, &[OptionalArgument { variable: y, default: zero_term }])?;
+// f(X, Y) = g(X, Y) and f(X) = g(X, 0)
+```
+
+## Sequence Variables
+
+The matcher has no sequence variables: each argument of a pattern matches exactly one argument of the subject, in
+flattened associative argument lists too. An optional argument therefore stands for zero or one arguments, never a
+run of them, and alternatives and exceptions are tried against the single argument their variable binds.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+  abstractions::IString,
+  api::{
+    free_theory::FreeTerm,
+    symbol::{Symbol, SymbolPtr},
+    term::{BxTerm, Term}
+  },
+  core::{
+    pre_equation::{PreEquation, PreEquationAttribute, PreEquationKind}
+  }
+};
+
+/// A variable of a left-hand side that may be left out, with the term it stands for when it is.
+pub struct OptionalArgument {
+  pub variable: SymbolPtr,
+  pub default : BxTerm,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum OptionalArgumentError {
+  /// The variable doesn't occur exactly once in the left-hand side.
+  NotOnce(IString),
+  /// The variable is an argument of a symbol with a fixed number of arguments.
+  FixedArity { variable: IString, symbol: IString },
+}
+
+impl Display for OptionalArgumentError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      OptionalArgumentError::NotOnce(variable) => {
+        write!(f, "the optional argument {} must occur exactly once in the left-hand side", variable)
+      }
+      OptionalArgumentError::FixedArity { variable, symbol } => {
+        write!(f, "the optional argument {} is an argument of {}, which has a fixed arity", variable, symbol)
+      }
+    }
+  }
+}
+
+impl Error for OptionalArgumentError {}
+
+/// Expands `statement`, whose left-hand side has the `optionals` as arguments, into a statement for every way of
+/// leaving some of them out. See the module documentation.
+pub fn expand_optional_arguments(statement: PreEquation, optionals: &[OptionalArgument])
+    -> Result<Vec<PreEquation>, OptionalArgumentError>
+{
+  for optional in optionals {
+    check_optional(statement.lhs_term.as_ref(), optional.variable)?;
+  }
+
+  // Subsets of the optionals to leave out, fewest first
+  let mut subsets = (0..1usize << optionals.len()).collect::<Vec<_>>();
+  subsets.sort_by_key(|subset| subset.count_ones());
+
+  let mut statements = Vec::with_capacity(subsets.len());
+  for subset in subsets {
+    let omitted = optionals.iter()
+                           .enumerate()
+                           .filter(|(index, _)| subset & (1 << index) != 0)
+                           .map(|(_, optional)| optional)
+                           .collect::<Vec<_>>();
+    statements.push(without_arguments(&statement, &omitted));
+  }
+
+  Ok(statements)
+}
+
+/// Checks that `variable` occurs once in `lhs`, as an argument of a symbol of variable arity.
+fn check_optional(lhs: &dyn Term, variable: SymbolPtr) -> Result<(), OptionalArgumentError> {
+  let name = unsafe{ &*variable }.name.clone();
+  let mut parents = Vec::new();
+  find_parents(lhs, variable, &mut parents);
+
+  match parents.as_slice() {
+    [parent] if !parent.arity.is_fixed() => Ok(()),
+    [parent] => Err(OptionalArgumentError::FixedArity { variable: name, symbol: parent.name.clone() }),
+    _        => Err(OptionalArgumentError::NotOnce(name)),
+  }
+}
+
+/// Pushes the symbol of the parent of every occurrence of `variable` in `term`, including at the top, where the
+/// variable is its own parent.
+fn find_parents(term: &dyn Term, variable: SymbolPtr, parents: &mut Vec<&'static Symbol>) {
+  if term.is_variable() {
+    if core::ptr::addr_eq(term.symbol(), variable) {
+      parents.push(term.symbol_ref());
+    }
+    return;
+  }
+
+  for arg in term.iter_args() {
+    match arg.is_variable() && core::ptr::addr_eq(arg.symbol(), variable) {
+      true  => parents.push(term.symbol_ref()),
+      false => find_parents(arg, variable, parents),
+    }
+  }
+}
+
+/// A copy of `statement` without the arguments `omitted`, whose defaults take their place elsewhere.
+fn without_arguments(statement: &PreEquation, omitted: &[&OptionalArgument]) -> PreEquation {
+  let lhs_term = remove_arguments(statement.lhs_term.as_ref(), omitted);
+  let kind     = match &statement.kind {
+    PreEquationKind::Equation { rhs_term } => {
+      PreEquationKind::Equation { rhs_term: replace(rhs_term.as_ref(), omitted) }
+    }
+    PreEquationKind::Rule { rhs_term } => PreEquationKind::Rule { rhs_term: replace(rhs_term.as_ref(), omitted) },
+    PreEquationKind::Membership { sort_spec } => PreEquationKind::Membership { sort_spec: sort_spec.clone() },
+  };
+  let mut conditions = statement.conditions
+                                .iter()
+                                .map(|condition| Box::new(condition.deep_copy()))
+                                .collect::<Vec<_>>();
+  for condition in conditions.iter_mut() {
+    for term in condition.terms_mut() {
+      *term = replace(term.as_ref(), omitted);
+    }
+  }
+
+  let mut copy    = PreEquation::new(statement.name.clone(), lhs_term, kind, conditions);
+  copy.attributes = statement.attributes;
+  copy.attributes.remove(PreEquationAttribute::Compiled | PreEquationAttribute::Bad);
+  copy.selection  = statement.selection;
+  copy
+}
+
+fn is_omitted(term: &dyn Term, omitted: &[&OptionalArgument]) -> bool {
+  term.is_variable() && omitted.iter().any(|optional| core::ptr::addr_eq(term.symbol(), optional.variable))
+}
+
+/// A copy of `term` without the arguments that are `omitted` variables
+fn remove_arguments(term: &dyn Term, omitted: &[&OptionalArgument]) -> BxTerm {
+  if term.is_variable() {
+    return term.deep_copy();
+  }
+
+  let mut copy = FreeTerm::new(term.symbol());
+  copy.args    = term.iter_args()
+                     .filter(|arg| !is_omitted(*arg, omitted))
+                     .map(|arg| remove_arguments(arg, omitted))
+                     .collect();
+  Box::new(copy)
+}
+
+/// A copy of `term` with the defaults of the `omitted` variables in their place
+fn replace(term: &dyn Term, omitted: &[&OptionalArgument]) -> BxTerm {
+  if term.is_variable() {
+    return match omitted.iter().find(|optional| core::ptr::addr_eq(term.symbol(), optional.variable)) {
+      Some(optional) => optional.default.deep_copy(),
+      None           => term.deep_copy(),
+    };
+  }
+
+  let mut copy = FreeTerm::new(term.symbol());
+  copy.args    = term.iter_args().map(|arg| replace(arg, omitted)).collect();
+  Box::new(copy)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      free_theory::{FreeDagNode, FreeTerm},
+      symbol::{Symbol, SymbolPtr},
+      term::BxTerm,
+      variable_theory::{ConstrainedVariable, VariableTerm},
+      Arity
+    },
+    core::{
+      module::Module,
+      parser::{parse_module, parse_term},
+      pattern_constructs::{expand_optional_arguments, OptionalArgument, OptionalArgumentError},
+      pre_equation::{PreEquation, PreEquationKind},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext,
+      RootContainer
+    }
+  };
+
+  #[test]
+  fn alternatives_and_exceptions_constrain_bindings() {
+    let module = parse_module("
+      mod M is
+        sort S .
+        ops a b c : -> S .
+        ops f h : S -> S .
+        vars X Z : S .
+      endm
+    ").unwrap();
+    let symbol = |name: &str| module.arena.symbol(&IString::from(name)).unwrap();
+
+    // f(X) with X matching a or h(Z) but not h(c)
+    let alternatives = vec![
+      module.compile_pattern(parse_term(&module, "a").unwrap().as_ref()),
+      module.compile_pattern(parse_term(&module, "h(Z)").unwrap().as_ref()),
+    ];
+    let excluded = module.compile_pattern(parse_term(&module, "h(c)").unwrap().as_ref());
+    let x        = ConstrainedVariable::new(symbol("X")).with_alternatives(alternatives).except(excluded);
+    let pattern  = module.compile_pattern(FreeTerm::with_args(symbol("f"), vec![x.into_term()]).as_ref());
+
+    for (subject, matches) in [("f(a)", true), ("f(h(b))", true), ("f(b)", false), ("f(h(c))", false)] {
+      let subject = parse_term(&module, subject).unwrap().term_to_dag(false);
+      let _root   = RootContainer::new(subject);
+      assert_eq!(pattern.match_(subject).is_some(), matches, "{}", dag_to_string(subject));
+    }
+  }
+
+  #[test]
+  fn optional_arguments_expand_into_statements() {
+    let mut f    = Symbol::new(IString::from("f"), Arity::Variadic);
+    let mut g    = Symbol::new(IString::from("g"), Arity::Value(2));
+    let mut a    = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b    = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut zero = Symbol::new(IString::from("0"), Arity::Value(0));
+    let mut x    = Symbol::new_variable(IString::from("X"));
+    let mut y    = Symbol::new_variable(IString::from("Y"));
+    let (f, g, a, b, zero, x, y): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut f, &mut g, &mut a, &mut b, &mut zero, &mut x, &mut y);
+    let variable = |symbol| -> BxTerm { Box::new(VariableTerm::new(symbol)) };
+
+    // f(X, Y:0) = g(X, Y)
+    let statement = || PreEquation::new(
+      None,
+      FreeTerm::with_args(f, vec![variable(x), variable(y)]),
+      PreEquationKind::Equation { rhs_term: FreeTerm::with_args(g, vec![variable(x), variable(y)]) },
+      vec![]
+    );
+    let optional = |variable| OptionalArgument { variable, default: FreeTerm::with_args(zero, vec![]) };
+
+    let statements = expand_optional_arguments(statement(), &[optional(y)]).unwrap();
+    assert_eq!(statements.len(), 2);
+    assert_eq!(statements[1].lhs_term.iter_args().count(), 1);
+
+    let mut module    = Module::default();
+    module.equations.extend(statements);
+    module.compile_statements().unwrap();
+    let mut context   = RewritingContext::new(&module);
+    let a_node        = FreeDagNode::new(a);
+    let b_node        = FreeDagNode::new(b);
+    let with_both     = FreeDagNode::with_args(f, &mut vec![a_node, b_node]);
+    let with_one      = FreeDagNode::with_args(f, &mut vec![a_node]);
+    let _roots        = (RootContainer::new(with_both), RootContainer::new(with_one));
+    assert_eq!(dag_to_string(context.reduce(with_both)), "g(a, b)");
+    assert_eq!(dag_to_string(context.reduce(with_one)), "g(a, 0)");
+
+    // g has a fixed arity, so its arguments can't be left out.
+    let statement = PreEquation::new(
+      None,
+      FreeTerm::with_args(g, vec![variable(x), variable(y)]),
+      PreEquationKind::Equation { rhs_term: variable(x) },
+      vec![]
+    );
+    assert_eq!(
+      expand_optional_arguments(statement, &[optional(y)]).err(),
+      Some(OptionalArgumentError::FixedArity { variable: IString::from("Y"), symbol: IString::from("g") })
+    );
+    assert_eq!(
+      expand_optional_arguments(PreEquation::new(
+        None,
+        FreeTerm::with_args(f, vec![variable(x), variable(y), variable(y)]),
+        PreEquationKind::Equation { rhs_term: variable(x) },
+        vec![]
+      ), &[optional(y)]).err(),
+      Some(OptionalArgumentError::NotOnce(IString::from("Y")))
+    );
+  }
+}
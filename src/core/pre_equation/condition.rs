@@ -6,9 +6,13 @@ apply. Conditions are like a "lite" version of `PreEquation`.
 
 */
 
-use std::fmt::Display;
+use core::fmt::Display;
+use alloc::{boxed::Box, vec, vec::Vec};
+use crate::abstractions::NatSet;
 use crate::api::term::BxTerm;
 use crate::core::sort::sort_spec::BxSortSpec;
+use crate::core::renaming::VariableRenaming;
+use crate::core::VariableInfo;
 
 pub type Conditions  = Vec<BxCondition>;
 pub type BxCondition = Box<Condition>;
@@ -41,8 +45,101 @@ pub enum Condition {
   },
 }
 
+impl Condition {
+  /// The terms of the condition, left to right
+  pub fn terms(&self) -> Vec<&BxTerm> {
+    match self {
+      Condition::SortMembership { lhs_term, .. } => vec![lhs_term],
+      Condition::Equality { lhs_term, rhs_term }
+      | Condition::Match { lhs_term, rhs_term }
+      | Condition::Rewrite { lhs_term, rhs_term } => vec![lhs_term, rhs_term],
+    }
+  }
+
+  pub(crate) fn terms_mut(&mut self) -> Vec<&mut BxTerm> {
+    match self {
+      Condition::SortMembership { lhs_term, .. } => vec![lhs_term],
+      Condition::Equality { lhs_term, rhs_term }
+      | Condition::Match { lhs_term, rhs_term }
+      | Condition::Rewrite { lhs_term, rhs_term } => vec![lhs_term, rhs_term],
+    }
+  }
+
+  /// The symbol between the condition's terms
+  pub fn operator(&self) -> &'static str {
+    match self {
+      Condition::Equality { .. }       => "=",
+      Condition::SortMembership { .. } => "::",
+      Condition::Match { .. }          => ":=",
+      Condition::Rewrite { .. }        => "=>",
+    }
+  }
+
+  /// A copy of the condition with its variables renamed by `renaming`. See `renaming`.
+  pub(crate) fn rename_variables(&self, renaming: &mut VariableRenaming) -> Condition {
+    match self {
+      Condition::Equality { lhs_term, rhs_term } => Condition::Equality {
+        lhs_term: lhs_term.rename_variables(renaming),
+        rhs_term: rhs_term.rename_variables(renaming),
+      },
+      Condition::SortMembership { lhs_term, sort } => Condition::SortMembership {
+        lhs_term: lhs_term.rename_variables(renaming),
+        sort    : sort.clone(),
+      },
+      Condition::Match { lhs_term, rhs_term } => Condition::Match {
+        lhs_term: lhs_term.rename_variables(renaming),
+        rhs_term: rhs_term.rename_variables(renaming),
+      },
+      Condition::Rewrite { lhs_term, rhs_term } => Condition::Rewrite {
+        lhs_term: lhs_term.rename_variables(renaming),
+        rhs_term: rhs_term.rename_variables(renaming),
+      },
+    }
+  }
+
+  /// A copy of the condition with copies of its terms as compiled. See `Term::deep_copy`.
+  pub(crate) fn deep_copy(&self) -> Condition {
+    match self {
+      Condition::Equality { lhs_term, rhs_term } => Condition::Equality {
+        lhs_term: lhs_term.deep_copy(),
+        rhs_term: rhs_term.deep_copy(),
+      },
+      Condition::SortMembership { lhs_term, sort } => Condition::SortMembership {
+        lhs_term: lhs_term.deep_copy(),
+        sort    : sort.clone(),
+      },
+      Condition::Match { lhs_term, rhs_term } => Condition::Match {
+        lhs_term: lhs_term.deep_copy(),
+        rhs_term: rhs_term.deep_copy(),
+      },
+      Condition::Rewrite { lhs_term, rhs_term } => Condition::Rewrite {
+        lhs_term: lhs_term.deep_copy(),
+        rhs_term: rhs_term.deep_copy(),
+      },
+    }
+  }
+
+  /// Indexes the variables of the condition's terms in the `VariableInfo` of the statement the condition belongs to,
+  /// so that a variable has the same index wherever it occurs in the statement.
+  pub(crate) fn index_variables(&mut self, variable_info: &mut VariableInfo) {
+    for term in self.terms_mut() {
+      term.index_variables(variable_info);
+    }
+  }
+
+  /// The indices of the variables occurring in the condition. The condition must have been indexed.
+  pub fn occurs_below(&self) -> NatSet {
+    let mut occurs = NatSet::new();
+    for term in self.terms() {
+      occurs.union_in_place(term.occurs_below());
+    }
+
+    occurs
+  }
+}
+
 impl Display for Condition {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
 
       Condition::Equality { lhs_term, rhs_term } => {
@@ -7,18 +7,41 @@ implemented.) The subclass is implemented as enum `PreEquationKind`.
 
 pub mod condition;
 
-use std::fmt::{Display, Formatter};
+use core::{error::Error, fmt::{Display, Formatter}};
+use alloc::{boxed::Box, format, rc::Rc, string::{String, ToString}, vec::Vec};
 
 use enumflags2::{bitflags, BitFlags};
 
 use crate::{
-  abstractions::IString,
+  abstractions::{
+    IString,
+    NatSet
+  },
   core::{
-    pre_equation::condition::Conditions,
+    automata::{
+      CompiledPattern,
+      DemandedPosition,
+      Extension,
+      RHSBuilder,
+      SharedAutomata,
+      TermBag
+    },
+    format::{FormatStyle, Formattable, PrintSettings},
+    latex::statement_to_latex,
+    pre_equation::condition::{Condition, Conditions},
+    renaming::{VariableRenaming, VariantForm},
+    rule_selection::Selection,
+    substitution::Substitution,
+    VariableInfo
+  },
+  api::{
+    dag_node::DagNodePtr,
+    term::{BxTerm, Term},
+    NONE
   },
-  api::term::BxTerm,
 };
 use crate::abstractions::join_string;
+use crate::warning;
 use crate::core::sort::sort_spec::BxSortSpec;
 
 
@@ -33,11 +56,12 @@ pub enum PreEquationAttribute {
   Print,        // StatementAttributeInfo--not a `PreEquation`
   Narrowing,    // Rule
   Bad,          // A malformed pre-equation
+  Subsumed,     // Redundant, see `subsumption`
 }
 pub type PreEquationAttributes = BitFlags<PreEquationAttribute>;
 
 impl Display for PreEquationAttribute {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     match self {
       PreEquationAttribute::Compiled   => write!(f, "compiled"),
       PreEquationAttribute::NonExecute => write!(f, "nonexecute"),
@@ -46,10 +70,59 @@ impl Display for PreEquationAttribute {
       PreEquationAttribute::Print      => write!(f, "print"),
       PreEquationAttribute::Narrowing  => write!(f, "narrowing"),
       PreEquationAttribute::Bad        => write!(f, "bad"),
+      PreEquationAttribute::Subsumed   => write!(f, "subsumed"),
+    }
+  }
+}
+
+/// A statement uses a variable that neither its left-hand side nor an earlier matching condition binds.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct UnboundVariableError {
+  /// The offending statement, as displayed
+  pub statement: String,
+  pub variable : IString,
+}
+
+impl Display for UnboundVariableError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "variable {} is unbound in {}", self.variable, self.statement)
+  }
+}
+
+impl Error for UnboundVariableError {}
+
+/// A statement that can't be compiled
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum StatementError {
+  UnboundVariable(UnboundVariableError),
+  /// The statement has a matching condition, `p := t`, or a rewrite condition, `t => p`, which reduction doesn't
+  /// support yet.
+  UnsupportedCondition {
+    /// The offending statement, as displayed
+    statement: String,
+    condition: String,
+  },
+}
+
+impl Display for StatementError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      StatementError::UnboundVariable(error) => write!(f, "{}", error),
+      StatementError::UnsupportedCondition { statement, condition } => {
+        write!(f, "the condition {} of {} is not supported", condition, statement)
+      }
     }
   }
 }
 
+impl Error for StatementError {}
+
+impl From<UnboundVariableError> for StatementError {
+  fn from(error: UnboundVariableError) -> Self {
+    StatementError::UnboundVariable(error)
+  }
+}
+
 pub struct PreEquation {
   pub name      : Option<IString>,
   pub attributes: PreEquationAttributes,
@@ -57,6 +130,17 @@ pub struct PreEquation {
 
   pub lhs_term  : BxTerm,
   pub kind      : PreEquationKind,
+  /// The priority and weight of a rule, if given. See `rule_selection`.
+  pub selection : Option<Selection>,
+
+  // Compilation
+  pub(crate) variable_info    : VariableInfo,
+  pub(crate) lhs_automaton    : Option<CompiledPattern>,
+  /// Shared by the copies made by `shared_copy`
+  pub(crate) rhs_builder      : Rc<RHSBuilder>,
+  /// The slot holding the instantiated right-hand side after `rhs_builder` has run
+  pub(crate) rhs_index        : i32,
+  pub(crate) substitution_size: usize,
 }
 
 
@@ -78,8 +162,364 @@ pub enum PreEquationKind {
   // StrategyDefinition
 }
 
+impl PreEquation {
+  pub fn new(name: Option<IString>, lhs_term: BxTerm, kind: PreEquationKind, conditions: Conditions) -> Self {
+    PreEquation {
+      name,
+      attributes       : PreEquationAttributes::default(),
+      conditions,
+      lhs_term,
+      kind,
+      selection        : None,
+      variable_info    : VariableInfo::default(),
+      lhs_automaton    : None,
+      rhs_builder      : Rc::new(RHSBuilder::new()),
+      rhs_index        : NONE,
+      substitution_size: 0,
+    }
+  }
+
+  /// Indexes the variables of the pre-equation and compiles its left-hand side into a matching automaton and its
+  /// right-hand side, if any, into an `RHSBuilder`. Compiling an already compiled pre-equation does nothing.
+  ///
+  /// Variables are indexed across the whole statement, so every occurrence of a variable in the left-hand side, the
+  /// conditions, and the right-hand side has the same index. The variables of the conditions and the right-hand side
+  /// form the context of the left-hand side.
+  ///
+  /// The right-hand side is compiled before the left-hand side, because reusing a matched subterm on the right-hand
+  /// side assigns the subterm a `save_index` that its left-hand side automaton must know about.
+  ///
+  /// A statement that fails `check`, or that has a matching or rewrite condition, is marked `Bad` and left
+  /// uncompiled.
+  #[inline(always)]
+  pub fn compile(&mut self) -> Result<(), StatementError> {
+    self.compile_with_shared_automata(&mut SharedAutomata::new())
+  }
+
+  /// Like `compile`, but shares the automata of left-hand side subpatterns through `shared_automata`. Statements
+  /// compiled with the same `SharedAutomata` should have the same top symbol.
+  pub fn compile_with_shared_automata(&mut self, shared_automata: &mut SharedAutomata)
+      -> Result<(), StatementError>
+  {
+    if self.attributes.contains(PreEquationAttribute::Compiled) {
+      return Ok(());
+    }
+
+    self.check()?;
+    // ToDo: Matching conditions need their patterns compiled, and rewrite conditions need search.
+    for condition in self.conditions.iter() {
+      if let Condition::Match { .. } | Condition::Rewrite { .. } = condition.as_ref() {
+        let statement = self.to_string();
+        self.attributes.insert(PreEquationAttribute::Bad);
+        return Err(StatementError::UnsupportedCondition { statement, condition: condition.to_string() });
+      }
+    }
+    self.compute_sorts();
+
+    // Only compiled statements share their builders.
+    let rhs_builder = Rc::get_mut(&mut self.rhs_builder).expect("an uncompiled statement shares its RHSBuilder");
+    if let PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } = &mut self.kind {
+      let mut available_terms = TermBag::new();
+      self.lhs_term.find_available_terms(&mut available_terms, true, true);
+
+      let index = rhs_term.compile_rhs(rhs_builder, &mut self.variable_info, &mut available_terms, true);
+      self.variable_info.use_index(index);
+      self.variable_info.end_of_fragment();
+      self.rhs_index = index;
+    }
+
+    let mut bound_uniquely = NatSet::new();
+    self.lhs_automaton = Some(
+      CompiledPattern::with_shared_automata(
+        self.lhs_term.as_ref(),
+        &mut self.variable_info,
+        &mut bound_uniquely,
+        shared_automata
+      )
+    );
+
+    self.substitution_size = self.variable_info.compute_index_remapping() as usize;
+    rhs_builder.remap_indices(&self.variable_info);
+    if self.rhs_index != NONE {
+      self.rhs_index = self.variable_info.remap_index(self.rhs_index);
+    }
+
+    self.attributes.insert(PreEquationAttribute::Compiled);
+    Ok(())
+  }
+
+  /// Forgets the compilation of the pre-equation, so that the next `compile` compiles it afresh, with the sorts of its
+  /// terms computed again. Used when the subsort relation changes. See `Module::recompute_kind_closures`.
+  pub(crate) fn reset_compilation(&mut self) {
+    reset_term(self.lhs_term.as_mut());
+    for condition in self.conditions.iter_mut() {
+      for term in condition.terms_mut() {
+        reset_term(term.as_mut());
+      }
+    }
+    if let PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } = &mut self.kind {
+      reset_term(rhs_term.as_mut());
+    }
+
+    self.variable_info     = VariableInfo::default();
+    self.lhs_automaton     = None;
+    self.rhs_builder       = Rc::new(RHSBuilder::new());
+    self.rhs_index         = NONE;
+    self.substitution_size = 0;
+    self.attributes.remove(PreEquationAttribute::Compiled);
+  }
+
+  /// Checks that every variable the statement uses is bound where it is used. The left-hand side binds its variables.
+  /// The conditions are then taken in order: the pattern of a matching condition, `p := t`, and the result of a rewrite
+  /// condition, `t => p`, bind their variables, while every other condition term may only use bound variables. Finally,
+  /// the right-hand side may only use bound variables.
+  ///
+  /// The unbound variables are recorded in the statement's `VariableInfo`. A statement with unbound variables is marked
+  /// `Bad`, and the error reports its first unbound variable.
+  pub fn check(&mut self) -> Result<(), UnboundVariableError> {
+    self.index_variables();
+
+    let mut bound   = self.lhs_term.occurs_below().clone();
+    let mut unbound = NatSet::new();
+    let mut uses    = |term: &dyn Term, bound: &NatSet| unbound.union_in_place(&term.occurs_below().difference(bound));
+
+    for condition in self.conditions.iter() {
+      match condition.as_ref() {
+        Condition::Equality { lhs_term, rhs_term } => {
+          uses(lhs_term.as_ref(), &bound);
+          uses(rhs_term.as_ref(), &bound);
+        }
+        Condition::SortMembership { lhs_term, .. } => uses(lhs_term.as_ref(), &bound),
+        Condition::Match { lhs_term: pattern, rhs_term: subject }
+        | Condition::Rewrite { lhs_term: subject, rhs_term: pattern } => {
+          uses(subject.as_ref(), &bound);
+          bound.union_in_place(pattern.occurs_below());
+        }
+      }
+    }
+    if let PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } = &self.kind {
+      uses(rhs_term.as_ref(), &bound);
+    }
+
+    self.variable_info.add_unbound_variables(&unbound);
+    match unbound.min_value() {
+      None        => Ok(()),
+      Some(index) => {
+        self.attributes.insert(PreEquationAttribute::Bad);
+        Err(
+          UnboundVariableError {
+            statement: self.to_string(),
+            variable : self.variable_info.variable_name(index),
+          }
+        )
+      }
+    }
+  }
+
+  /// Indexes the variables of the left-hand side, then of the conditions in order, then of the right-hand side, and
+  /// computes the context sets of the left-hand side.
+  fn index_variables(&mut self) {
+    self.lhs_term.index_variables(&mut self.variable_info);
+
+    let mut context = NatSet::new();
+    for condition in self.conditions.iter_mut() {
+      condition.index_variables(&mut self.variable_info);
+      let occurs = condition.occurs_below();
+      self.variable_info.add_condition_variables(&occurs);
+      context.union_in_place(&occurs);
+    }
+
+    if let PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } = &mut self.kind {
+      rhs_term.index_variables(&mut self.variable_info);
+      context.union_in_place(rhs_term.occurs_below());
+    }
+
+    *self.lhs_term.occurs_in_context_mut() = context;
+    self.lhs_term.determine_context_variables();
+  }
+
+  /// Computes the sorts of the terms of the statement, which needs the sorts of the module to be closed, and warns if
+  /// the two sides of an equation or rule are in different kinds.
+  fn compute_sorts(&self) {
+    let lhs_sort = self.lhs_term.compute_sort();
+    for condition in self.conditions.iter() {
+      for term in condition.terms() {
+        term.compute_sort();
+      }
+    }
+
+    if let PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } = &self.kind {
+      if let (Some(lhs_sort), Some(rhs_sort)) = (lhs_sort, rhs_term.compute_sort()) {
+        if unsafe{ (*lhs_sort).kind != (*rhs_sort).kind } {
+          warning!(1, "the left- and right-hand sides of {} are in different kinds", self);
+        }
+      }
+    }
+  }
+
+  /// The number of slots a `Substitution` needs to match the compiled left-hand side and construct the right-hand
+  /// side.
+  #[inline(always)]
+  pub fn substitution_size(&self) -> usize {
+    self.substitution_size
+  }
+
+  /// Matches `subject` against the compiled left-hand side, recording bindings in `solution`. The pre-equation must
+  /// have been compiled.
+  pub fn match_lhs(&self, subject: DagNodePtr, solution: &mut Substitution) -> bool {
+    let automaton = self.lhs_automaton.as_ref().expect("tried to match an uncompiled pre-equation");
+    solution.clear_first_n(self.substitution_size());
+    automaton.match_(subject, solution)
+  }
+
+  /// Like `match_lhs`, but skips the first `skip` matches. See `CompiledPattern::match_skipping`.
+  pub fn match_lhs_skipping(&self, subject: DagNodePtr, solution: &mut Substitution, skip: usize) -> bool {
+    let automaton = self.lhs_automaton.as_ref().expect("tried to match an uncompiled pre-equation");
+    solution.clear_first_n(self.substitution_size());
+    automaton.match_skipping(subject, solution, skip)
+  }
+
+  /// Like `match_lhs`, but a left-hand side whose top symbol is associative may match part of the subject's flattened
+  /// arguments. Returns what the match left of the subject. See `Extension`.
+  pub fn match_lhs_with_extension(&self, subject: DagNodePtr, solution: &mut Substitution) -> Option<Extension> {
+    let automaton = self.lhs_automaton.as_ref().expect("tried to match an uncompiled pre-equation");
+    solution.clear_first_n(self.substitution_size());
+    automaton.match_with_extension(subject, solution)
+  }
+
+  /// The positions below the top of the left-hand side at which a match needs to see the subject's symbol. The
+  /// pre-equation must have been compiled.
+  pub(crate) fn demanded_positions(&self) -> &[DemandedPosition] {
+    self.lhs_automaton.as_ref().expect("tried to analyze an uncompiled pre-equation").demanded_positions()
+  }
+
+  /// Constructs the instance of the right-hand side under the bindings of a successful `match_lhs`. Subterms the
+  /// left-hand side matched are reused rather than rebuilt.
+  pub fn construct_rhs(&self, solution: &mut Substitution) -> DagNodePtr {
+    assert_ne!(self.rhs_index, NONE, "tried to construct the right-hand side of a pre-equation without one");
+    self.rhs_builder.construct(solution);
+    solution.get(self.rhs_index).expect("right-hand side slot is unbound")
+  }
+
+  /// A copy of the pre-equation, as compiled, that shares its compiled automata and right-hand side builder with it
+  /// rather than compiling them again. The copy's terms are its own, so its attributes can be changed, and it can be
+  /// recompiled, without affecting the original. Until it is recompiled, its variable information refers to the
+  /// variables of the original, which must outlive it. See `Module::clone_for_experiment`.
+  pub(crate) fn shared_copy(&self) -> PreEquation {
+    let kind = match &self.kind {
+      PreEquationKind::Equation { rhs_term }    => PreEquationKind::Equation { rhs_term: rhs_term.deep_copy() },
+      PreEquationKind::Rule { rhs_term }        => PreEquationKind::Rule { rhs_term: rhs_term.deep_copy() },
+      PreEquationKind::Membership { sort_spec } => PreEquationKind::Membership { sort_spec: sort_spec.clone() },
+    };
+
+    let mut copy = PreEquation {
+      name             : self.name.clone(),
+      attributes       : self.attributes,
+      conditions       : self.conditions.iter().map(|condition| Box::new(condition.deep_copy())).collect(),
+      lhs_term         : self.lhs_term.deep_copy(),
+      kind,
+      selection        : self.selection,
+      variable_info    : self.variable_info.clone(),
+      lhs_automaton    : self.lhs_automaton.clone(),
+      rhs_builder      : self.rhs_builder.clone(),
+      rhs_index        : self.rhs_index,
+      substitution_size: self.substitution_size,
+    };
+    // There is nothing to share with a statement that isn't compiled, and its copy must be free to compile.
+    if !self.attributes.contains(PreEquationAttribute::Compiled) {
+      copy.reset_compilation();
+    }
+
+    copy
+  }
+
+  /// An uncompiled copy of the pre-equation with its variables renamed canonically by `renaming`. See `renaming`.
+  pub fn rename_variables(&self, renaming: &mut VariableRenaming) -> PreEquation {
+    let lhs_term   = self.lhs_term.rename_variables(renaming);
+    let conditions = self.conditions
+                         .iter()
+                         .map(|condition| Box::new(condition.rename_variables(renaming)))
+                         .collect();
+    let kind = match &self.kind {
+      PreEquationKind::Equation { rhs_term } => {
+        PreEquationKind::Equation { rhs_term: rhs_term.rename_variables(renaming) }
+      }
+      PreEquationKind::Rule { rhs_term } => PreEquationKind::Rule { rhs_term: rhs_term.rename_variables(renaming) },
+      PreEquationKind::Membership { sort_spec } => PreEquationKind::Membership { sort_spec: sort_spec.clone() },
+    };
+
+    let mut renamed = PreEquation::new(self.name.clone(), lhs_term, kind, conditions);
+    renamed.attributes = self.attributes;
+    renamed.selection  = self.selection;
+    renamed.attributes.remove(PreEquationAttribute::Compiled);
+
+    renamed
+  }
+
+  /// The form the pre-equation has in common with its variants, the pre-equations that differ from it only in the
+  /// names of their variables. See `renaming`.
+  pub fn variant_form(&self) -> VariantForm {
+    let mut form = VariantForm::new();
+    form.add_tag(
+      match self.kind {
+        PreEquationKind::Equation { .. }   => "eq",
+        PreEquationKind::Rule { .. }       => "rl",
+        PreEquationKind::Membership { .. } => "mb",
+      }
+    );
+    if let Some(name) = &self.name {
+      form.add_tag(format!("[{}]", name));
+    }
+    if let Some(selection) = &self.selection {
+      form.add_tag(selection.to_string());
+    }
+
+    form.add_term(self.lhs_term.as_ref());
+    for condition in self.conditions.iter() {
+      form.add_tag(condition.operator());
+      for term in condition.terms() {
+        form.add_term(term.as_ref());
+      }
+      if let Condition::SortMembership { sort, .. } = condition.as_ref() {
+        form.add_tag(sort.to_string());
+      }
+    }
+    match &self.kind {
+      PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } => form.add_term(rhs_term.as_ref()),
+      PreEquationKind::Membership { sort_spec } => form.add_tag(sort_spec.to_string()),
+    }
+
+    form
+  }
+
+  /// The priority and weight of the rule, which are the defaults unless given. See `rule_selection`.
+  #[inline(always)]
+  pub fn selection(&self) -> Selection {
+    self.selection.unwrap_or_default()
+  }
+
+  /// Whether `self` and `other` differ only in the names of their variables
+  #[inline(always)]
+  pub fn is_variant_of(&self, other: &PreEquation) -> bool {
+    self.variant_form() == other.variant_form()
+  }
+}
+
+/// Forgets what compiling `term` recorded in it and its subterms: their sorts, save indices, and variables.
+fn reset_term(term: &mut dyn Term) {
+  for arg in term.args_mut() {
+    reset_term(arg.as_mut());
+  }
+
+  let core = term.core_mut();
+  core.sort.set(None);
+  core.save_index  = NONE;
+  core.occurs_set  = NatSet::default();
+  core.context_set = NatSet::default();
+}
+
 impl Display for PreEquation {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     match &self.kind {
 
       PreEquationKind::Equation { rhs_term } => {
@@ -108,14 +548,166 @@ impl Display for PreEquation {
     }
 
     // attributes
-    if !self.attributes.is_empty() {
+    let mut attributes: Vec<String> = self.attributes.iter().map(|attribute| attribute.to_string()).collect();
+    if let Some(selection) = &self.selection {
+      attributes.push(selection.to_string());
+    }
+    if !attributes.is_empty() {
       write!(
         f,
         " [{}]",
-        join_string(self.attributes.iter(), ", ")
+        attributes.join(", ")
       )?;
     }
 
     write!(f, ";")
   }
 }
+
+impl Formattable for PreEquation {
+  fn repr_with(&self, style: FormatStyle, settings: &PrintSettings) -> String {
+    match style {
+      FormatStyle::Latex => statement_to_latex(self, settings),
+      _                  => self.to_string()
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      dag_node::DagNodePtr,
+      free_theory::{FreeDagNode, FreeTerm},
+      symbol::{Symbol, SymbolPtr},
+      term::BxTerm,
+      variable_theory::VariableTerm,
+    },
+    core::{
+      pre_equation::{condition::Condition, PreEquation, PreEquationAttribute, PreEquationKind, StatementError},
+      substitution::Substitution,
+      RootContainer
+    }
+  };
+
+  #[test]
+  fn rhs_reuses_matched_subterms() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut h = Symbol::new(IString::from("h"), Arity::Value(2));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let mut y = Symbol::new_variable(IString::from("Y"));
+    let (f, g, h, a, x, y): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut f, &mut g, &mut h, &mut a, &mut x, &mut y);
+
+    // f(g(X), Y) = h(g(X), h(g(Y), g(Y)))
+    let g_x = FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(x))]);
+    let lhs = FreeTerm::with_args(f, vec![g_x, Box::new(VariableTerm::new(y))]);
+    let rhs = FreeTerm::with_args(
+      h,
+      vec![
+        FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(x))]),
+        FreeTerm::with_args(
+          h,
+          vec![
+            FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(y))]),
+            FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(y))])
+          ]
+        )
+      ]
+    );
+    let mut equation = PreEquation::new(None, lhs, PreEquationKind::Equation { rhs_term: rhs }, vec![]);
+    equation.compile().unwrap();
+
+    // Only g(Y) and the two h(…) nodes need to be built.
+    assert_eq!(equation.rhs_builder.len(), 3);
+
+    // Subject f(g(a), a)
+    let a_node: DagNodePtr = FreeDagNode::new(a);
+    let g_node = FreeDagNode::new(g);
+    unsafe{ &mut *g_node }.insert_child(a_node).unwrap();
+    let subject = FreeDagNode::with_args(f, &mut vec![g_node, a_node]);
+    let _root   = RootContainer::new(subject);
+
+    let mut solution = Substitution::new();
+    assert!(equation.match_lhs(subject, &mut solution));
+    let result = equation.construct_rhs(&mut solution);
+
+    let args: Vec<DagNodePtr> = unsafe{ &*result }.iter_args().collect();
+    assert!(std::ptr::addr_eq(args[0], g_node));
+    let inner: Vec<DagNodePtr> = unsafe{ &*args[1] }.iter_args().collect();
+    assert!(std::ptr::addr_eq(inner[0], inner[1]));
+    assert!(!std::ptr::addr_eq(inner[0], g_node));
+  }
+
+  #[test]
+  fn variables_are_indexed_across_the_statement() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(3));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut h = Symbol::new(IString::from("h"), Arity::Value(2));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let mut y = Symbol::new_variable(IString::from("Y"));
+    let mut z = Symbol::new_variable(IString::from("Z"));
+    let (f, g, h, x, y, z): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut f, &mut g, &mut h, &mut x, &mut y, &mut z);
+    let var = |symbol: SymbolPtr| -> BxTerm { Box::new(VariableTerm::new(symbol)) };
+
+    // f(X, g(Y), Z) = h(X, Y) if Y = X
+    let lhs       = FreeTerm::with_args(f, vec![var(x), FreeTerm::with_args(g, vec![var(y)]), var(z)]);
+    let rhs       = FreeTerm::with_args(h, vec![var(x), var(y)]);
+    let condition = Box::new(Condition::Equality { lhs_term: var(y), rhs_term: var(x) });
+    let mut equation = PreEquation::new(None, lhs, PreEquationKind::Equation { rhs_term: rhs }, vec![condition]);
+    equation.compile().unwrap();
+
+    // X, Y, and Z are 0, 1, and 2 wherever they occur.
+    assert_eq!(equation.variable_info.real_variable_count(), 3);
+    let condition_variables = equation.conditions[0].occurs_below();
+    assert_eq!(condition_variables.iter().collect::<Vec<_>>(), vec![0, 1]);
+    assert_eq!(equation.variable_info.get_condition_variables(), &condition_variables);
+
+    // The context of an argument holds the variables of its siblings, the condition, and the right-hand side.
+    let args: Vec<_> = equation.lhs_term.iter_args().collect();
+    assert_eq!(args[2].occurs_in_context().iter().collect::<Vec<_>>(), vec![0, 1]);
+    assert_eq!(args[0].occurs_in_context().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+    let inner = args[1].iter_args().next().unwrap();
+    assert_eq!(inner.occurs_in_context().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  }
+
+  #[test]
+  fn unbound_variables_are_reported() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(1));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let mut y = Symbol::new_variable(IString::from("Y"));
+    let (f, g, x, y): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut g, &mut x, &mut y);
+    let var = |symbol: SymbolPtr| -> BxTerm { Box::new(VariableTerm::new(symbol)) };
+
+    // f(X) = Y
+    let mut equation = PreEquation::new(
+      None, FreeTerm::with_args(f, vec![var(x)]), PreEquationKind::Equation { rhs_term: var(y) }, vec![]
+    );
+    let error = equation.compile().unwrap_err();
+    assert!(matches!(error, StatementError::UnboundVariable(error) if error.variable.as_ref() == "Y"));
+    assert!(equation.attributes.contains(PreEquationAttribute::Bad));
+    assert!(equation.lhs_automaton.is_none());
+
+    // f(X) = Y if g(Y) := X binds Y before the right-hand side uses it.
+    let condition    = Box::new(Condition::Match { lhs_term: FreeTerm::with_args(g, vec![var(y)]), rhs_term: var(x) });
+    let mut equation = PreEquation::new(
+      None, FreeTerm::with_args(f, vec![var(x)]), PreEquationKind::Equation { rhs_term: var(y) }, vec![condition]
+    );
+    assert!(equation.check().is_ok());
+
+    // f(X) = X if Y = X uses Y before anything binds it.
+    let condition    = Box::new(Condition::Equality { lhs_term: var(y), rhs_term: var(x) });
+    let mut equation = PreEquation::new(
+      None, FreeTerm::with_args(f, vec![var(x)]), PreEquationKind::Equation { rhs_term: var(x) }, vec![condition]
+    );
+    assert_eq!(equation.check().unwrap_err().variable, IString::from("Y"));
+    assert!(equation.variable_info.get_unbound_variables().contains(1));
+  }
+}
@@ -0,0 +1,327 @@
+/*!
+
+The prelude is a library of standard modules written in the module language of `core::parser`. Each can be loaded on
+its own with `Module::prelude_module`, all of them but `META-LEVEL` together with `Module::prelude`, or imported into
+another module with `including NAME .`.
+
+| Module       | Contents                                                                         |
+|:-------------|:---------------------------------------------------------------------------------|
+| `NAT`        | Peano naturals `0`, `s` with `plus`, `times`, `pred`, and `monus`                |
+| `ELT`        | The sort `Elt` of the elements of lists and sets                                 |
+| `LIST`       | Lists of `Elt` built from `nil` and `cons`, with `append`, `reverse`, `length`   |
+| `SET`        | Sets of `Elt` built from `empty` and `union`, with `insert` and `size`           |
+| `PRELUDE`    | All of the above                                                                 |
+| `META-LEVEL` | The metarepresentation of terms, and the descent functions (see `metalevel`)     |
+
+Lists and sets are generic in the sense that their elements have the sort `Elt`, which a module using them extends
+with subsorts, as in `subsort Nat < Elt .`.
+
+A `NatView` converts between Peano naturals and machine integers.
+
+## Sets
+
+An element is a singleton set, and `union` is associative, commutative, and idempotent, with identity `empty`. The
+engine keeps subjects in canonical form modulo these axioms (see `canonicalize`), so a set reduces to the union of its
+distinct elements, nested to the right in a fixed order, and `insert(a, insert(b, insert(a, empty)))` reduces to
+`union(a, b)` whatever the order of the insertions. `size` peels off one element at a time: its pattern
+`union(E, S)` is matched modulo the axioms, with `E` matching one element of the set and `S` the rest, however the
+union is nested, and `S` bound to `empty` for a single element.
+
+*/
+
+use crate::{
+  abstractions::IString,
+  api::{
+    dag_node::DagNodePtr,
+    free_theory::FreeDagNode,
+    symbol::SymbolPtr
+  },
+  core::{
+    module::Module,
+    parser::parse_module
+  }
+};
+
+pub const NAT: &str = "
+mod NAT is
+  sorts Zero NzNat Nat .
+  subsorts Zero NzNat < Nat .
+
+  op 0 : -> Zero [ctor] .
+  op s : Nat -> NzNat [ctor] .
+  op plus : Nat Nat -> Nat .
+  op plus : NzNat Nat -> NzNat .
+  op times : Nat Nat -> Nat .
+  op pred : NzNat -> Nat .
+  op monus : Nat Nat -> Nat .
+
+  vars N M : Nat .
+  eq plus(0, M) = M .
+  eq plus(s(N), M) = s(plus(N, M)) .
+  eq times(0, M) = 0 .
+  eq times(s(N), M) = plus(M, times(N, M)) .
+  eq pred(s(N)) = N .
+  eq monus(N, 0) = N .
+  eq monus(0, M) = 0 .
+  eq monus(s(N), s(M)) = monus(N, M) .
+endm
+";
+
+pub const ELT: &str = "
+mod ELT is
+  sort Elt .
+endm
+";
+
+pub const LIST: &str = "
+mod LIST is
+  including NAT .
+  including ELT .
+  sort List .
+
+  op nil : -> List [ctor] .
+  op cons : Elt List -> List [ctor] .
+  op append : List List -> List .
+  op reverse : List -> List .
+  op length : List -> Nat .
+
+  var E : Elt .
+  vars L L' : List .
+  eq append(nil, L') = L' .
+  eq append(cons(E, L), L') = cons(E, append(L, L')) .
+  eq reverse(nil) = nil .
+  eq reverse(cons(E, L)) = append(reverse(L), cons(E, nil)) .
+  eq length(nil) = 0 .
+  eq length(cons(E, L)) = s(length(L)) .
+endm
+";
+
+pub const SET: &str = "
+mod SET is
+  including NAT .
+  including ELT .
+  sort Set .
+  subsort Elt < Set .
+
+  op empty : -> Set [ctor] .
+  op union : Set Set -> Set [ctor assoc comm idem id: empty] .
+  op insert : Elt Set -> Set .
+  op size : Set -> Nat .
+
+  var E : Elt .
+  var S : Set .
+  eq insert(E, S) = union(E, S) .
+  eq size(empty) = 0 .
+  eq size(union(E, S)) = s(size(S)) .
+endm
+";
+
+pub const PRELUDE: &str = "
+mod PRELUDE is
+  including NAT .
+  including LIST .
+  including SET .
+endm
+";
+
+pub const META_LEVEL: &str = "
+mod META-LEVEL is
+  including NAT .
+  sorts Qid Term TermList Assignment Substitution Substitution? ResultPair ResultTriple ResultTriple? .
+  subsort Substitution < Substitution? .
+  subsort ResultTriple < ResultTriple? .
+
+  op variable : Qid -> Term [ctor] .
+  op app : Qid TermList -> Term [ctor] .
+  op noArgs : -> TermList [ctor] .
+  op args : Term TermList -> TermList [ctor] .
+  op bind : Qid Term -> Assignment [ctor] .
+  op none : -> Substitution [ctor] .
+  op bindings : Assignment Substitution -> Substitution [ctor] .
+  op noMatch : -> Substitution? [ctor] .
+  op pair : Term Qid -> ResultPair [ctor] .
+  op triple : Term Qid Substitution -> ResultTriple [ctor] .
+  op failure : -> ResultTriple? [ctor] .
+
+  op metaReduce : Qid Term -> ResultPair [descent] .
+  op metaMatch : Qid Term Term Nat -> Substitution? [descent] .
+  op metaApply : Qid Term Qid Nat -> ResultTriple? [descent] .
+  op metaSearch : Qid Term Term Nat Nat -> ResultTriple? [descent] .
+endm
+";
+
+/// The names of the modules of the prelude, each of which can be loaded with `Module::prelude_module`.
+pub const MODULE_NAMES: [&str; 6] = ["NAT", "ELT", "LIST", "SET", "PRELUDE", "META-LEVEL"];
+
+/// The source of the prelude module named `name`, if there is one.
+pub fn source(name: &str) -> Option<&'static str> {
+  match name {
+    "NAT"        => Some(NAT),
+    "ELT"        => Some(ELT),
+    "LIST"       => Some(LIST),
+    "SET"        => Some(SET),
+    "PRELUDE"    => Some(PRELUDE),
+    "META-LEVEL" => Some(META_LEVEL),
+    _            => None
+  }
+}
+
+impl Module {
+  /// A fresh copy of the module `PRELUDE`, which includes every module of the prelude.
+  pub fn prelude() -> Module {
+    Module::prelude_module("PRELUDE").unwrap()
+  }
+
+  /// A fresh copy of the prelude module named `name`, or `None` if there is no such module.
+  pub fn prelude_module(name: &str) -> Option<Module> {
+    let source = source(name)?;
+    Some(parse_module(source).expect("the prelude failed to parse"))
+  }
+}
+
+/// Converts between the Peano naturals of a module including `NAT` and machine integers.
+#[derive(Copy, Clone, Debug)]
+pub struct NatView {
+  zero     : SymbolPtr,
+  successor: SymbolPtr,
+}
+
+impl NatView {
+  /// A view of the naturals of `module`, or `None` if the module does not include `NAT`.
+  pub fn new(module: &Module) -> Option<NatView> {
+    Some(
+      NatView {
+        zero     : module.arena.symbol(&IString::from("0"))?,
+        successor: module.arena.symbol(&IString::from("s"))?,
+      }
+    )
+  }
+
+  /// The value of the natural `node`, or `None` if `node` is not of the form `s(…s(0)…)`.
+  pub fn to_u64(self, node: DagNodePtr) -> Option<u64> {
+    let mut node  = unsafe{ &*node };
+    let mut value = 0u64;
+
+    while core::ptr::addr_eq(node.symbol(), self.successor) {
+      node   = unsafe{ &*node.iter_args().next()? };
+      value += 1;
+    }
+
+    match core::ptr::addr_eq(node.symbol(), self.zero) {
+      true  => Some(value),
+      false => None
+    }
+  }
+
+  /// Builds the natural `s(…s(0)…)` with `value` successors. The DAG has `value + 1` nodes, so this is only suitable
+  /// for small values.
+  pub fn to_dag(self, value: u64) -> DagNodePtr {
+    let mut node = FreeDagNode::new(self.zero);
+    for _ in 0..value {
+      let successor = FreeDagNode::new(self.successor);
+      unsafe{ &mut *successor }.insert_child_unchecked(node);
+      node = successor;
+    }
+
+    node
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::{
+      module::Module,
+      parser::{parse_module, parse_term},
+      prelude::{NatView, MODULE_NAMES},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext
+    }
+  };
+
+  fn reduce(module: &Module, term: &str) -> String {
+    let subject     = parse_term(module, term).unwrap().term_to_dag(false);
+    let mut context = RewritingContext::new(module);
+    dag_to_string(context.reduce(subject))
+  }
+
+  #[test]
+  fn prelude_modules_load() {
+    for name in MODULE_NAMES {
+      let module = Module::prelude_module(name).unwrap();
+      assert_eq!(module.name, IString::from(name));
+    }
+    assert!(Module::prelude_module("INT").is_none());
+
+    let prelude = Module::prelude();
+    assert!(prelude.arena.symbol(&IString::from("times")).is_some());
+    assert!(prelude.arena.symbol(&IString::from("union")).is_some());
+  }
+
+  #[test]
+  fn nat_view_round_trips() {
+    let module = Module::prelude_module("NAT").unwrap();
+    let view   = NatView::new(&module).unwrap();
+
+    let product = parse_term(&module, "times(s(s(0)), s(s(s(0))))").unwrap().term_to_dag(false);
+    let mut context = RewritingContext::new(&module);
+    assert_eq!(view.to_u64(context.reduce(product)), Some(6));
+
+    let seven = view.to_dag(7);
+    assert_eq!(view.to_u64(seven), Some(7));
+    let difference = parse_term(&module, "monus(s(s(0)), s(s(s(0))))").unwrap().term_to_dag(false);
+    assert_eq!(view.to_u64(context.reduce(difference)), Some(0));
+  }
+
+  #[test]
+  fn lists_and_sets_of_naturals() {
+    let module = parse_module("
+      mod NAT-COLLECTIONS is
+        including PRELUDE .
+        including NAT .
+        subsort Nat < Elt .
+      endm
+    ").unwrap();
+
+    assert_eq!(reduce(&module, "reverse(cons(0, cons(s(0), nil)))"), "cons(s(0), cons(0, nil))");
+    assert_eq!(reduce(&module, "length(append(cons(0, nil), cons(0, nil)))"), "s(s(0))");
+    assert_eq!(
+      reduce(&module, "size(union(insert(0, empty), insert(0, insert(0, empty))))"),
+      "s(0)"
+    );
+    // Duplicates are removed wherever they are, and the order of insertion doesn't matter.
+    let set = "insert(s(0), insert(0, insert(s(0), empty)))";
+    assert_eq!(reduce(&module, &format!("size({})", set)), "s(s(0))");
+    assert_eq!(reduce(&module, set), reduce(&module, "union(0, union(empty, s(0)))"));
+    assert_eq!(reduce(&module, "size(empty)"), "0");
+  }
+
+  #[test]
+  fn set_operations_on_nested_unions() {
+    let module = parse_module("
+      mod NAT-SETS is
+        including SET .
+        subsort Nat < Elt .
+        sort Answer .
+        op yes : -> Answer .
+        op member : Nat Set -> Answer .
+        var N : Nat .
+        var S : Set .
+        eq member(N, union(N, S)) = yes .
+      endm
+    ").unwrap();
+
+    // The unions are nested to the left, with repeated elements and identities, and out of order.
+    let set = "union(union(union(s(s(0)), empty), union(0, s(0))), union(s(s(0)), 0))";
+    assert_eq!(reduce(&module, &format!("size({})", set)), "s(s(s(0)))");
+    // `N` matches any element, not just the first of the canonical form.
+    assert_eq!(reduce(&module, &format!("member(s(0), {})", set)), "yes");
+    assert_eq!(reduce(&module, &format!("member(s(s(0)), {})", set)), "yes");
+    assert_eq!(reduce(&module, "member(s(0), s(0))"), "yes");
+    assert_eq!(reduce(&module, "member(s(0), union(0, s(s(0))))"), "member(s(0), union(0, s(s(0))))");
+    assert_eq!(reduce(&module, &format!("size(insert(s(0), {}))", set)), "s(s(s(0)))");
+    assert_eq!(reduce(&module, &format!("size(union({}, {}))", set, set)), "s(s(s(0)))");
+  }
+}
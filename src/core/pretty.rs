@@ -0,0 +1,259 @@
+/*!
+
+Pretty-printing of terms and DAGs for the terminal, used for `FormatStyle::Pretty`.
+
+A `PrettyPrinter` lays a term out as an indented tree drawn with box-drawing characters. A subterm that fits on the
+rest of its line is written on one line in prefix notation; one that doesn't is broken into its top symbol and a
+branch for each argument:
+
+```text
+plus
+├── s(s(0))
+╰── times
+    ├── s(0)
+    ╰── monus(s(s(s(0))), s(0))
+```
+
+Symbols, variables, and data are given different ANSI colors unless color is turned off, as it is by default when the
+`NO_COLOR` environment variable is set, and always without `std`. A width of zero breaks every subterm with arguments,
+drawing the full tree.
+
+DAGs are printed as trees, so a shared subterm is printed at each of its occurrences unless the printer's
+`PrintSettings` elide shared subterms.
+
+```ignore
+let printer = PrettyPrinter::default().with_width(40).with_color(false);
+println!("{}", printer.dag_to_tree(node));
+```
+
+*/
+
+use alloc::{format, string::String};
+
+use crate::{
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::SymbolType,
+    term::Term
+  },
+  core::format::{FormatStyle, PrintNode, PrintSettings, PrintTree, ELISION}
+};
+
+/// The width used by `FormatStyle::Pretty`
+pub const DEFAULT_WIDTH: usize = 80;
+
+const SYMBOL_COLOR  : &str = "\x1b[1;34m";
+const VARIABLE_COLOR: &str = "\x1b[33m";
+const DATA_COLOR    : &str = "\x1b[32m";
+const RESET         : &str = "\x1b[0m";
+
+/// Lays out terms and DAGs as indented trees. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PrettyPrinter {
+  /// The number of columns a line may take before a subterm on it is broken up
+  pub width   : usize,
+  /// Whether to color symbols, variables, and data with ANSI escape sequences
+  pub color   : bool,
+  /// The limits on how much of a term is printed
+  pub settings: PrintSettings,
+}
+
+/// Whether to color output unless told otherwise: when `NO_COLOR` isn't set
+#[cfg(feature = "std")]
+fn color_by_default() -> bool {
+  std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Without `std` there is no environment to read `NO_COLOR` from, and no terminal to color.
+#[cfg(not(feature = "std"))]
+fn color_by_default() -> bool {
+  false
+}
+
+impl Default for PrettyPrinter {
+  fn default() -> Self {
+    PrettyPrinter {
+      width   : DEFAULT_WIDTH,
+      color   : color_by_default(),
+      settings: PrintSettings::default(),
+    }
+  }
+}
+
+impl PrettyPrinter {
+  pub fn with_width(mut self, width: usize) -> Self {
+    self.width = width;
+    self
+  }
+
+  pub fn with_color(mut self, color: bool) -> Self {
+    self.color = color;
+    self
+  }
+
+  pub fn with_settings(mut self, settings: PrintSettings) -> Self {
+    self.settings = settings;
+    self
+  }
+
+  /// Lays out the DAG rooted at `node` as a tree.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn dag_to_tree(&self, node: DagNodePtr) -> String {
+    let node: &dyn DagNode = unsafe{ &*node };
+    self.write(&PrintTree::new(&node, FormatStyle::Pretty, &self.settings))
+  }
+
+  /// Lays out `term` as a tree.
+  pub fn term_to_tree(&self, term: &dyn Term) -> String {
+    self.write(&PrintTree::new(&term, FormatStyle::Pretty, &self.settings))
+  }
+
+  /// Lays out a tree that has already been cut down to its print settings.
+  pub(crate) fn write(&self, tree: &PrintTree) -> String {
+    let mut out = String::new();
+    self.write_tree(tree, "", "", "", &mut out);
+    out
+  }
+
+  /// Writes `tree` on a line beginning with `prefix` and `connector`, and its arguments, if it is broken up, on lines
+  /// beginning with `child_prefix`.
+  fn write_tree(&self, tree: &PrintTree, prefix: &str, connector: &str, child_prefix: &str, out: &mut String) {
+    if !out.is_empty() {
+      out.push('\n');
+    }
+    out.push_str(prefix);
+    out.push_str(connector);
+
+    let used = prefix.chars().count() + connector.chars().count();
+    if tree.args.is_empty() || flat_width(tree, self.width.saturating_sub(used)).is_some() {
+      self.write_flat(tree, out);
+      return;
+    }
+
+    self.write_head(tree, out);
+    let prefix = format!("{}{}", prefix, child_prefix);
+    let last   = if tree.truncated { tree.args.len() } else { tree.args.len() - 1 };
+    for (index, arg) in tree.args.iter().enumerate() {
+      match index == last {
+        true  => self.write_tree(arg, &prefix, "╰── ", "    ", out),
+        false => self.write_tree(arg, &prefix, "├── ", "│   ", out),
+      }
+    }
+    if tree.truncated {
+      out.push('\n');
+      out.push_str(&prefix);
+      out.push_str("╰── ");
+      out.push_str(ELISION);
+    }
+  }
+
+  /// Writes `tree` on one line in prefix notation.
+  fn write_flat(&self, tree: &PrintTree, out: &mut String) {
+    self.write_head(tree, out);
+    if tree.args.is_empty() && !tree.truncated {
+      return;
+    }
+
+    out.push('(');
+    for (index, arg) in tree.args.iter().enumerate() {
+      if index > 0 {
+        out.push_str(", ");
+      }
+      self.write_flat(arg, out);
+    }
+    if tree.truncated {
+      if !tree.args.is_empty() {
+        out.push_str(", ");
+      }
+      out.push_str(ELISION);
+    }
+    out.push(')');
+  }
+
+  /// Writes the node at the top of `tree` without its arguments.
+  fn write_head(&self, tree: &PrintTree, out: &mut String) {
+    let (symbol, head, label) = match &tree.node {
+      PrintNode::Reference(label) => {
+        out.push_str(&format!("#{}", label));
+        return;
+      }
+      PrintNode::Node { symbol, head, label } => (symbol, head, label)
+    };
+
+    if let Some(label) = label {
+      out.push_str(&format!("#{}=", label));
+    }
+    if !self.color {
+      out.push_str(head);
+      return;
+    }
+
+    let color = match symbol.symbol_type {
+      SymbolType::Variable => VARIABLE_COLOR,
+      SymbolType::Data     => DATA_COLOR,
+      _                    => SYMBOL_COLOR,
+    };
+    out.push_str(color);
+    out.push_str(head);
+    out.push_str(RESET);
+  }
+}
+
+/// The number of columns `tree` takes on one line, or `None` if it takes more than `budget`.
+fn flat_width(tree: &PrintTree, budget: usize) -> Option<usize> {
+  let mut width = match &tree.node {
+    PrintNode::Reference(label)         => format!("#{}", label).len(),
+    PrintNode::Node { head, label, .. } => {
+      head.chars().count() + label.map_or(0, |label| format!("#{}=", label).len())
+    }
+  };
+  let arg_count = tree.args.len() + tree.truncated as usize;
+  if arg_count > 0 {
+    // The parentheses, the separators between arguments, and the elision
+    width += 2 * arg_count + tree.truncated as usize;
+  }
+  if width > budget {
+    return None;
+  }
+
+  for arg in tree.args.iter() {
+    width += flat_width(arg, budget - width)?;
+  }
+
+  Some(width)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    parser::parse_term,
+    module::Module,
+    pretty::PrettyPrinter
+  };
+
+  #[test]
+  fn subterms_are_broken_up_when_they_do_not_fit() {
+    let module  = Module::prelude_module("NAT").unwrap();
+    let term    = parse_term(&module, "plus(s(s(0)), times(s(0), monus(s(s(s(0))), s(0))))").unwrap();
+    let printer = PrettyPrinter::default().with_color(false);
+
+    assert_eq!(printer.term_to_tree(term.as_ref()), "plus(s(s(0)), times(s(0), monus(s(s(s(0))), s(0))))");
+    assert_eq!(
+      printer.with_width(32).term_to_tree(term.as_ref()),
+      "plus\n├── s(s(0))\n╰── times\n    ├── s(0)\n    ╰── monus(s(s(s(0))), s(0))"
+    );
+
+    let dag = term.term_to_dag(false);
+    assert_eq!(
+      printer.with_width(0).dag_to_tree(dag),
+      "plus\n├── s\n│   ╰── s\n│       ╰── 0\n╰── times\n    ├── s\n    │   ╰── 0\n    ╰── monus\n        \
+      ├── s\n        │   ╰── s\n        │       ╰── s\n        │           ╰── 0\n        ╰── s\n            ╰── 0"
+    );
+
+    // Colors don't count toward the width.
+    let colored = printer.with_color(true).with_width(32).term_to_tree(term.as_ref());
+    assert_eq!(colored.lines().count(), 5);
+    assert!(colored.contains("\x1b[1;34mplus\x1b[0m"));
+  }
+}
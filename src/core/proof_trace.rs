@@ -0,0 +1,156 @@
+/*!
+
+A `ProofTrace` records the rewrites performed by a reduction so that external tools can replay or certify the
+derivation. A traced reduction is requested with `RewritingContext::reduce_traced`.
+
+Each `RewriteStep` records which equation was applied, where, and under which substitution, together with the redex and
+its replacement. Terms are written in prefix notation, `f(a, g(b))`.
+
+Positions are paths of argument indices from the top of the term being reduced, with the empty path denoting the top.
+Reduction is innermost, so positions refer to the term as it is after the steps before it. Reduction works on a DAG in
+which a shared subterm is reduced only once; its position is that of the occurrence reduced first.
+
+A trace can be exported to JSON with `to_json`:
+
+```json
+{
+  "subject": "plus(s(0), 0)",
+  "result": "s(0)",
+  "steps": [
+    {
+      "statement": 1,
+      "label": null,
+      "position": [],
+      "substitution": {"X": "0", "Y": "0"},
+      "redex": "plus(s(0), 0)",
+      "contractum": "s(plus(0, 0))"
+    },
+    ...
+  ]
+}
+```
+
+*/
+
+use core::fmt::Write;
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+use crate::{
+  abstractions::IString,
+  api::dag_node::DagNodePtr
+};
+
+/// A single application of an equation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RewriteStep {
+  /// The index of the equation in its module's `equations`
+  pub statement   : usize,
+  /// The equation's label, if it has one
+  pub label       : Option<IString>,
+  /// The path of argument indices from the top of the term to the redex
+  pub position    : Vec<usize>,
+  /// The bindings of the equation's variables, in the order the equation's variables were indexed
+  pub substitution: Vec<(IString, String)>,
+  pub redex       : String,
+  pub contractum  : String,
+}
+
+/// The rewrites performed by a reduction, in the order they were performed.
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub struct ProofTrace {
+  pub subject: String,
+  pub result : String,
+  pub steps  : Vec<RewriteStep>,
+}
+
+impl ProofTrace {
+  pub(crate) fn new(subject: DagNodePtr) -> Self {
+    ProofTrace {
+      subject: dag_to_string(subject),
+      result : String::new(),
+      steps  : Vec::new(),
+    }
+  }
+
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.steps.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.steps.is_empty()
+  }
+
+  /// Writes the trace as a JSON object with `subject`, `result`, and `steps` members.
+  pub fn to_json(&self) -> String {
+    let mut out = String::new();
+
+    out.push_str("{\n");
+    writeln!(out, "  \"subject\": {},", json_string(&self.subject)).unwrap();
+    writeln!(out, "  \"result\": {},", json_string(&self.result)).unwrap();
+    out.push_str("  \"steps\": [");
+
+    for (i, step) in self.steps.iter().enumerate() {
+      out.push_str(if i == 0 { "\n" } else { ",\n" });
+      out.push_str("    {\n");
+      writeln!(out, "      \"statement\": {},", step.statement).unwrap();
+      match &step.label {
+        Some(label) => writeln!(out, "      \"label\": {},", json_string(label)).unwrap(),
+        None        => out.push_str("      \"label\": null,\n"),
+      }
+
+      let position: Vec<String> = step.position.iter().map(|index| index.to_string()).collect();
+      writeln!(out, "      \"position\": [{}],", position.join(", ")).unwrap();
+
+      let bindings: Vec<String> = step.substitution
+          .iter()
+          .map(|(variable, value)| format!("{}: {}", json_string(variable), json_string(value)))
+          .collect();
+      writeln!(out, "      \"substitution\": {{{}}},", bindings.join(", ")).unwrap();
+
+      writeln!(out, "      \"redex\": {},", json_string(&step.redex)).unwrap();
+      writeln!(out, "      \"contractum\": {}", json_string(&step.contractum)).unwrap();
+      out.push_str("    }");
+    }
+
+    if !self.steps.is_empty() {
+      out.push_str("\n  ");
+    }
+    out.push_str("]\n}\n");
+
+    out
+  }
+}
+
+/// Writes the term rooted at `node` in prefix notation.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn dag_to_string(node: DagNodePtr) -> String {
+  let node = unsafe{ &*node };
+  let args: Vec<String> = node.iter_args().map(dag_to_string).collect();
+  match args.is_empty() {
+    true  => node.symbol_ref().name.to_string(),
+    false => format!("{}({})", node.symbol_ref().name, args.join(", "))
+  }
+}
+
+/// Quotes and escapes `string` as a JSON string.
+pub(crate) fn json_string(string: &str) -> String {
+  let mut out = String::with_capacity(string.len() + 2);
+
+  out.push('"');
+  for c in string.chars() {
+    match c {
+      '"'  => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+      c    => out.push(c),
+    }
+  }
+  out.push('"');
+
+  out
+}
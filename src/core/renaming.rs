@@ -0,0 +1,232 @@
+/*!
+
+Canonical renaming of variables. Terms or statements that differ only in the names of their variables are *variants*
+of each other. Renaming the variables canonically, the first distinct variable in preorder to `X0`, the next to `X1`,
+and so on, each keeping its sort, gives variants the same form, so that statements can be compared for redundancy,
+deduplicated, and hashed independently of the names of their variables.
+
+ - `Term::rename_variables` copies a term with its variables renamed by a `VariableRenaming`, which owns the new
+   variables. Renaming several terms with the same `VariableRenaming`, like the parts of a statement, renames them
+   consistently. `PreEquation::rename_variables` renames a whole statement.
+ - A `VariantForm` records a term or statement as it would be after renaming, without creating any variables. Two terms
+   or statements are variants of each other exactly when their forms are equal. See `PreEquation::variant_form`.
+ - `VariantForm::stable_hash` hashes the names of symbols and sorts rather than their pointers or creation order, so
+   the hash of a statement is the same in every process.
+
+The variables of a statement are numbered in the order of its left-hand side, its conditions, and its right-hand side.
+
+*/
+
+use core::hash::Hasher;
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+
+use crate::{
+  abstractions::{hash::FastHasher, IString},
+  api::{
+    symbol::{Symbol, SymbolPtr},
+    term::Term
+  }
+};
+
+/// Renames variables canonically. See the module documentation. The new variables are owned by the `VariableRenaming`,
+/// which must outlive every term that uses them.
+#[derive(Default)]
+#[allow(clippy::vec_box)]
+pub struct VariableRenaming {
+  originals: Vec<SymbolPtr>,
+  symbols  : Vec<Box<Symbol>>,
+}
+
+impl VariableRenaming {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The canonical variable for `variable`: `Xn` with the sort of `variable`, where `n` is the number of distinct
+  /// variables renamed before `variable` was first renamed.
+  pub fn rename(&mut self, variable: SymbolPtr) -> SymbolPtr {
+    if let Some(index) = self.originals.iter().position(|&original| core::ptr::addr_eq(original, variable)) {
+      return self.symbols[index].as_mut();
+    }
+
+    let name       = IString::from(format!("X{}", self.originals.len()));
+    let mut symbol = Box::new(
+      match unsafe{ &*variable }.variable_sort() {
+        Some(sort) => Symbol::new_sorted_variable(name, sort),
+        None       => Symbol::new_variable(name),
+      }
+    );
+    let pointer: SymbolPtr = symbol.as_mut();
+    self.originals.push(variable);
+    self.symbols.push(symbol);
+
+    pointer
+  }
+
+  /// The variable renamed to `canonical`, if `canonical` is one of the variables of this renaming
+  pub fn original(&self, canonical: SymbolPtr) -> Option<SymbolPtr> {
+    self.symbols
+        .iter()
+        .position(|symbol| core::ptr::addr_eq(symbol.as_ref() as *const Symbol, canonical))
+        .map(|index| self.originals[index])
+  }
+
+  /// The number of distinct variables renamed
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.originals.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.originals.is_empty()
+  }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Item {
+  Symbol {
+    name : IString,
+    arity: usize,
+  },
+  /// The canonical number of a variable, and the name of its sort, if it has one
+  Variable {
+    number: usize,
+    sort  : Option<IString>,
+  },
+  /// Anything else that distinguishes statements, like the kind of a statement or condition or a label
+  Tag(String),
+}
+
+/// A term or statement with its variables numbered canonically. See the module documentation.
+#[derive(Clone, Default, Debug)]
+pub struct VariantForm {
+  items    : Vec<Item>,
+  variables: Vec<SymbolPtr>,
+}
+
+impl VariantForm {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The form of a single term
+  pub fn of_term(term: &dyn Term) -> Self {
+    let mut form = VariantForm::new();
+    form.add_term(term);
+    form
+  }
+
+  /// Appends `term` in preorder, numbering its variables after those already added.
+  pub fn add_term(&mut self, term: &dyn Term) {
+    if !term.is_variable() {
+      self.items.push(Item::Symbol { name: term.symbol_ref().name.clone(), arity: term.iter_args().count() });
+      for arg in term.iter_args() {
+        self.add_term(arg);
+      }
+      return;
+    }
+
+    let variable = term.symbol();
+    let number   = match self.variables.iter().position(|&seen| core::ptr::addr_eq(seen, variable)) {
+      Some(number) => number,
+      None         => {
+        self.variables.push(variable);
+        self.variables.len() - 1
+      }
+    };
+    let sort = term.symbol_ref().variable_sort().map(|sort| unsafe{ &*sort }.name.clone());
+    self.items.push(Item::Variable { number, sort });
+  }
+
+  /// Appends a tag, like the kind of a statement, that must also agree for forms to be equal.
+  pub fn add_tag(&mut self, tag: impl Into<String>) {
+    self.items.push(Item::Tag(tag.into()));
+  }
+
+  /// A hash of the form that depends only on names, and so is the same in every process
+  pub fn stable_hash(&self) -> u64 {
+    let mut hasher = FastHasher::new();
+    for item in self.items.iter() {
+      match item {
+        Item::Symbol { name, arity } => {
+          hasher.write_u32(0);
+          hasher.write(name.as_ref().as_bytes());
+          hasher.write_usize(*arity);
+        }
+        Item::Variable { number, sort } => {
+          hasher.write_u32(1);
+          hasher.write_usize(*number);
+          if let Some(sort) = sort {
+            hasher.write(sort.as_ref().as_bytes());
+          }
+        }
+        Item::Tag(tag) => {
+          hasher.write_u32(2);
+          hasher.write(tag.as_bytes());
+        }
+      }
+    }
+
+    hasher.finish()
+  }
+}
+
+/// Forms are equal if the items are, whatever the names of the variables.
+impl PartialEq for VariantForm {
+  fn eq(&self, other: &Self) -> bool {
+    self.items == other.items
+  }
+}
+
+impl Eq for VariantForm {}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    format::FormatStyle,
+    parser::{parse_module, parse_term},
+    renaming::{VariableRenaming, VariantForm}
+  };
+
+  const MODULE: &str = "
+    mod RENAMING is
+      sort Nat .
+      ops 0 a : -> Nat .
+      op plus : Nat Nat -> Nat .
+      vars X Y Z : Nat .
+      eq plus(X, plus(Y, X)) = Y .
+      eq plus(Z, plus(X, Z)) = X .
+      eq plus(Y, plus(Y, X)) = Y .
+    endm
+  ";
+
+  #[test]
+  fn variants_have_the_same_form() {
+    let module = parse_module(MODULE).unwrap();
+
+    // The second equation is a variant of the first, so the parser drops it.
+    assert_eq!(module.equations.len(), 2);
+    let (first, third) = (&module.equations[0], &module.equations[1]);
+    assert!(!first.is_variant_of(third));
+    assert_ne!(first.variant_form().stable_hash(), third.variant_form().stable_hash());
+
+    let mut renaming = VariableRenaming::new();
+    let renamed      = first.rename_variables(&mut renaming);
+    assert!(renamed.is_variant_of(first));
+    assert_eq!(renamed.variant_form().stable_hash(), first.variant_form().stable_hash());
+    assert_eq!(renamed.lhs_term.repr(FormatStyle::Simple), "plus(X0, plus(X1, X0))");
+
+    let mut renaming = VariableRenaming::new();
+    let term         = parse_term(&module, "plus(Z, plus(a, X))").unwrap();
+    let renamed      = term.rename_variables(&mut renaming);
+    assert_eq!(renamed.repr(FormatStyle::Simple), "plus(X0, plus(a, X1))");
+    assert_eq!(renaming.len(), 2);
+    let x1 = renamed.iter_args().nth(1).unwrap().iter_args().nth(1).unwrap().symbol();
+    assert_eq!(unsafe{ &*renaming.original(x1).unwrap() }.name.as_ref(), "X");
+    assert_eq!(VariantForm::of_term(term.as_ref()), VariantForm::of_term(renamed.as_ref()));
+  }
+}
@@ -0,0 +1,204 @@
+/*!
+
+Rewrite rules detached from any module, for scripting-style use where building a module to apply a few rules is
+overkill. A `ReplacementRule` is a left-hand side compiled on its own (see `pattern_cache`) together with a right-hand
+side, and `replace_all` applies a list of them to a DAG the way the Wolfram Language's `ReplaceAll` does:
+
+```ignore
+let rules = [
+  ReplacementRule::new(f_of_x.as_ref(), g_of_x_x)?,  // f(X) -> g(X, X)
+  ReplacementRule::new(a.as_ref(), b)?,              // a -> b
+];
+let result = subject.replace_all(&rules);            // h(f(a), a) becomes h(g(a, a), b)
+```
+
+The DAG is traversed from the top. At each position the rules are tried in order, and the first that matches replaces
+the subterm there. The replacement is not traversed further, so each part of the subject is rewritten at most once:
+unlike reduction, there is no normal form to reach, and the rules need not terminate. Positions no rule matches are
+traversed into. Shared subterms are replaced once, and nodes with nothing replaced below them are reused rather than
+copied, so the result shares them with the subject.
+
+There is no module, so the rules have no equations to reduce their instances with, and their left-hand sides are
+matched without identities, as if no symbol had one. Their other axioms are matched as usual.
+
+*/
+
+use alloc::{format, vec::Vec};
+
+use crate::{
+  abstractions::HashMap,
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::SymbolPtr,
+    term::{BxTerm, Term}
+  },
+  core::{
+    format::FormatStyle,
+    pattern_cache::CachedPattern,
+    pre_equation::UnboundVariableError,
+    theory_table::{build_node, theory_entry}
+  }
+};
+
+/// A rewrite rule `lhs -> rhs` that belongs to no module. See the module documentation.
+pub struct ReplacementRule {
+  lhs      : CachedPattern,
+  rhs      : BxTerm,
+  /// The variables of the left-hand side in the order in which they first occur, which is the order of the bindings
+  /// `lhs` reports
+  variables: Vec<SymbolPtr>,
+}
+
+impl ReplacementRule {
+  /// Compiles the rule `lhs -> rhs`. Every variable of `rhs` must occur in `lhs`.
+  pub fn new(lhs: &dyn Term, rhs: BxTerm) -> Result<Self, UnboundVariableError> {
+    let mut variables = Vec::new();
+    collect_variables(lhs, &mut variables);
+
+    let mut rhs_variables = Vec::new();
+    collect_variables(rhs.as_ref(), &mut rhs_variables);
+    if let Some(&unbound) = rhs_variables.iter().find(|variable| !variables.contains(variable)) {
+      return Err(
+        UnboundVariableError {
+          statement: format!("{} -> {}", lhs.repr(FormatStyle::Simple), rhs.repr(FormatStyle::Simple)),
+          variable : unsafe{ &*unbound }.name.clone(),
+        }
+      );
+    }
+
+    Ok(ReplacementRule { lhs: CachedPattern::new(lhs, &HashMap::default()), rhs, variables })
+  }
+
+  /// The instance of the right-hand side if the rule matches `subject`
+  pub fn apply(&self, subject: DagNodePtr) -> Option<DagNodePtr> {
+    let bindings = self.lhs.match_(subject)?;
+    Some(self.instantiate(self.rhs.as_ref(), &bindings))
+  }
+
+  fn instantiate(&self, term: &dyn Term, bindings: &[DagNodePtr]) -> DagNodePtr {
+    if term.is_variable() {
+      let index = self.variables.iter().position(|&variable| core::ptr::addr_eq(variable, term.symbol()));
+      return bindings[index.expect("right-hand side variables are checked on construction")];
+    }
+
+    let mut args = term.iter_args().map(|arg| self.instantiate(arg, bindings)).collect();
+    build_node(term.symbol(), &mut args).expect("the right-hand side is a term of the signature")
+  }
+}
+
+/// Pushes the variables of `term` not already in `variables`, in the order in which they first occur.
+pub(crate) fn collect_variables(term: &dyn Term, variables: &mut Vec<SymbolPtr>) {
+  if term.is_variable() {
+    if !variables.iter().any(|&variable| core::ptr::addr_eq(variable, term.symbol())) {
+      variables.push(term.symbol());
+    }
+    return;
+  }
+
+  for arg in term.iter_args() {
+    collect_variables(arg, variables);
+  }
+}
+
+impl dyn DagNode {
+  /// Replaces each outermost subterm matched by one of `rules` with the instance of the first such rule. The DAG is
+  /// not modified. See `replacement`.
+  pub fn replace_all(&self, rules: &[ReplacementRule]) -> DagNodePtr {
+    replace_all(self as *const dyn DagNode as DagNodePtr, rules, &mut HashMap::default())
+  }
+}
+
+/// `replaced` holds the result for each node already visited.
+fn replace_all(
+  node    : DagNodePtr,
+  rules   : &[ReplacementRule],
+  replaced: &mut HashMap<*const u8, DagNodePtr>
+) -> DagNodePtr
+{
+  if let Some(&result) = replaced.get(&(node as *const u8)) {
+    return result;
+  }
+
+  let result = match rules.iter().find_map(|rule| rule.apply(node)) {
+    Some(instance) => instance,
+    None => {
+      let node_ref = unsafe{ &*node };
+      let mut args: Vec<DagNodePtr> = node_ref.iter_args().map(|arg| replace_all(arg, rules, replaced)).collect();
+      match node_ref.iter_args().zip(args.iter()).all(|(arg, &new_arg)| core::ptr::addr_eq(arg, new_arg)) {
+        true  => node,
+        false => {
+          (theory_entry(node_ref.core().theory()).with_args)(node_ref.symbol(), &mut args)
+              .expect("a node of the same theory with the same number of arguments exists")
+        }
+      }
+    }
+  };
+  replaced.insert(node as *const u8, result);
+
+  result
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      dag_node::DagNodePtr,
+      free_theory::{FreeDagNode, FreeTerm},
+      symbol::{Symbol, SymbolPtr},
+      term::BxTerm,
+      variable_theory::VariableTerm
+    },
+    core::{
+      proof_trace::dag_to_string,
+      replacement::ReplacementRule,
+      root_container::RootContainer
+    }
+  };
+
+  #[test]
+  fn rules_replace_outermost_matches_once() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(1));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(2));
+    let mut h = Symbol::new(IString::from("h"), Arity::Value(3));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let mut y = Symbol::new_variable(IString::from("Y"));
+    let (f, g, h, a, b): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut f, &mut g, &mut h, &mut a, &mut b);
+    let (x, y): (SymbolPtr, SymbolPtr) = (&mut x, &mut y);
+    let variable = |symbol: SymbolPtr| -> BxTerm { Box::new(VariableTerm::new(symbol)) };
+
+    // f(X) -> g(X, X), a -> b, and b -> a
+    let rules = [
+      ReplacementRule::new(
+        FreeTerm::with_args(f, vec![variable(x)]).as_ref(),
+        FreeTerm::with_args(g, vec![variable(x), variable(x)])
+      ),
+      ReplacementRule::new(FreeTerm::with_args(a, vec![]).as_ref(), FreeTerm::with_args(b, vec![])),
+      ReplacementRule::new(FreeTerm::with_args(b, vec![]).as_ref(), FreeTerm::with_args(a, vec![])),
+    ].map(Result::unwrap);
+
+    // h(f(a), a, b), which is rewritten once at each outermost match
+    let a_node: DagNodePtr = FreeDagNode::new(a);
+    let b_node: DagNodePtr = FreeDagNode::new(b);
+    let f_node: DagNodePtr = FreeDagNode::new(f);
+    unsafe{ &mut *f_node }.insert_child_unchecked(a_node);
+    let subject = FreeDagNode::with_args(h, &mut vec![f_node, a_node, b_node]);
+    let _root   = RootContainer::new(subject);
+
+    let result = unsafe{ &*subject }.replace_all(&rules);
+    assert_eq!(dag_to_string(result), "h(g(a, a), b, a)");
+    assert_eq!(dag_to_string(subject), "h(f(a), a, b)");
+
+    // A node with nothing to replace below it is reused.
+    assert_eq!(dag_to_string(unsafe{ &*f_node }.replace_all(&rules[1..2])), "f(b)");
+    assert!(std::ptr::addr_eq(unsafe{ &*b_node }.replace_all(&rules[..2]), b_node));
+
+    let unbound = ReplacementRule::new(FreeTerm::with_args(a, vec![]).as_ref(), variable(y));
+    assert_eq!(unbound.err().map(|error| error.variable), Some(IString::from("Y")));
+  }
+}
@@ -0,0 +1,224 @@
+/*!
+
+Retracts, which let a term of a sort stand where a subsort is expected. The retract `r:A>B` is an operator `A -> B`
+with the `Retract` attribute, for sorts `A` and `B` in the same kind. Its argument must have the sort `A`, which names
+the kind being retracted from, and the retract claims the sort `B` for it. Reduction removes the retract as soon as
+its argument has been reduced to a term whose sort is `B` or one of its subsorts:
+
+```text
+op p : NzNat -> Nat .
+eq half(X:Nat) = p(r:Nat>NzNat(X:Nat)) .
+```
+
+Retracts are written in terms like any other operator, and created the first time they are used. Before the module
+compiles a statement, it inserts them where the right-hand side would otherwise be ill-sorted: an argument whose sort
+is in the kind of the operator's domain but isn't below the domain sort is wrapped in the retract from its sort to the
+domain sort, so that the right-hand side is well-sorted. An operator with several declarations uses the declaration
+needing the fewest retracts. Arguments in the wrong kind are left alone, as no retract can fix them.
+
+A retract whose argument never gets a small enough sort stays, and the term it heads keeps the sort `B`, though its
+argument doesn't have it. Such a term is stuck, as in Maude, and `retracts_of` finds the retracts in a normal form.
+
+*/
+
+use alloc::{boxed::Box, format, vec, vec::Vec};
+
+use crate::{
+  abstractions::{intern, IString},
+  api::{
+    Arity,
+    dag_node::DagNodePtr,
+    free_theory::FreeTerm,
+    symbol::{Symbol, SymbolAttribute, SymbolPtr},
+    term::BxTerm
+  },
+  core::{
+    module_arena::ModuleArena,
+    pre_equation::{PreEquation, PreEquationKind},
+    sort::{SortPtr, SpecialSort}
+  }
+};
+
+/// The name of the retract from `from` to `to`, `r:from>to`
+pub fn retract_name(from: &str, to: &str) -> IString {
+  intern(format!("r:{}>{}", from, to))
+}
+
+/// The names of the sorts a retract is from and to, if `name` is the name of a retract
+pub fn parse_retract_name(name: &str) -> Option<(&str, &str)> {
+  let (from, to) = name.strip_prefix("r:")?.split_once('>')?;
+  match from.is_empty() || to.is_empty() {
+    true  => None,
+    false => Some((from, to)),
+  }
+}
+
+/// The retract from `from` to `to` in `arena`, created if it doesn't exist yet
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn retract(arena: &mut ModuleArena, from: SortPtr, to: SortPtr) -> SymbolPtr {
+  let name = retract_name(&unsafe{ &*from }.name, &unsafe{ &*to }.name);
+  if let Some(symbol) = arena.symbol(&name) {
+    return symbol;
+  }
+
+  let mut symbol    = Symbol::new(name, Arity::Value(1));
+  symbol.attributes = SymbolAttribute::Retract.into();
+  symbol.sort_table.add_op_declaration(vec![from], to, false);
+  arena.add_symbol(symbol)
+}
+
+/// Whether `symbol` is a retract
+#[inline(always)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn is_retract(symbol: SymbolPtr) -> bool {
+  unsafe{ &*symbol }.attributes.contains(SymbolAttribute::Retract)
+}
+
+/// The argument of `node` if `node` is headed by a retract whose argument has the sort it retracts to or a subsort,
+/// which is what reducing `node` gives.
+pub(crate) fn retracted(node: DagNodePtr) -> Option<DagNodePtr> {
+  let node_ref = unsafe{ &*node };
+  if !is_retract(node_ref.symbol()) {
+    return None;
+  }
+
+  let argument = node_ref.iter_args().next()?;
+  let sort     = unsafe{ &mut *argument }.compute_sort()?;
+  let to       = node_ref.symbol_ref().sort_table.declarations().first()?.range;
+  unsafe{ &*sort }.leq(unsafe{ &*to }).then_some(argument)
+}
+
+/// The positions of the retracts in `node`, in preorder. A normal form has retracts only where they are stuck.
+pub fn retracts_of(node: DagNodePtr) -> Vec<Vec<usize>> {
+  fn visit(node: DagNodePtr, position: &mut Vec<usize>, positions: &mut Vec<Vec<usize>>) {
+    if is_retract(unsafe{ &*node }.symbol()) {
+      positions.push(position.clone());
+    }
+    for (index, arg) in unsafe{ &*node }.iter_args().enumerate() {
+      position.push(index);
+      visit(arg, position, positions);
+      position.pop();
+    }
+  }
+
+  let mut positions = Vec::new();
+  visit(node, &mut Vec::new(), &mut positions);
+  positions
+}
+
+impl PreEquation {
+  /// Inserts retracts where the right-hand side would otherwise be ill-sorted, creating them in `arena`. Returns the
+  /// number inserted. The sorts of the module must be closed. See `retract`.
+  pub(crate) fn insert_retracts(&mut self, arena: &mut ModuleArena) -> usize {
+    match &mut self.kind {
+      PreEquationKind::Equation { rhs_term } | PreEquationKind::Rule { rhs_term } => insert_retracts(rhs_term, arena),
+      PreEquationKind::Membership { .. } => 0,
+    }
+  }
+}
+
+/// Inserts retracts in `term` bottom up, so that the sorts of the arguments of each subterm are those with retracts.
+fn insert_retracts(term: &mut BxTerm, arena: &mut ModuleArena) -> usize {
+  if term.is_variable() {
+    return 0;
+  }
+  let mut inserted = term.args_mut().iter_mut().map(|arg| insert_retracts(arg, arena)).sum();
+
+  let arg_sorts: Option<Vec<SortPtr>> = term.iter_args().map(|arg| arg.compute_sort()).collect();
+  let Some(arg_sorts) = arg_sorts else {
+    return inserted;
+  };
+  let sort_table = &term.symbol_ref().sort_table;
+  let optional: Vec<Option<SortPtr>> = arg_sorts.iter().copied().map(Some).collect();
+  if sort_table.compute_sort_index(&optional) != SpecialSort::ErrorSort as i8 {
+    return inserted;
+  }
+
+  // The declaration in the kinds of the arguments needing the fewest retracts
+  let is_below    = |sort: SortPtr, domain: SortPtr| unsafe{ (*sort).leq(&*domain) };
+  let declaration = sort_table.declarations()
+                              .iter()
+                              .filter(|declaration| {
+                                declaration.domain.iter().zip(arg_sorts.iter()).all(|(&domain, &sort)| {
+                                  let sort = unsafe{ &*sort };
+                                  let is_error_sort = sort.index_within_kind == SpecialSort::ErrorSort as u8;
+                                  sort.kind == unsafe{ &*domain }.kind && !is_error_sort
+                                })
+                              })
+                              .min_by_key(|declaration| {
+                                declaration.domain
+                                           .iter()
+                                           .zip(arg_sorts.iter())
+                                           .filter(|&(&domain, &sort)| !is_below(sort, domain))
+                                           .count()
+                              });
+  let Some(domain) = declaration.map(|declaration| declaration.domain.clone()) else {
+    return inserted;
+  };
+
+  for ((arg, sort), domain) in term.args_mut().iter_mut().zip(arg_sorts).zip(domain) {
+    if is_below(sort, domain) {
+      continue;
+    }
+    let argument      = core::mem::replace(arg, Box::new(FreeTerm::new(retract(arena, sort, domain))));
+    let retract_term  = arg.as_any_mut().downcast_mut::<FreeTerm>().unwrap();
+    retract_term.args = vec![argument];
+    inserted += 1;
+  }
+  term.core().sort.set(None);
+
+  inserted
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    parser::{parse_module, parse_term},
+    pre_equation::PreEquationKind,
+    proof_trace::dag_to_string,
+    retract::retracts_of,
+    rewriting_context::RewritingContext
+  };
+
+  const SOURCE: &str = "
+    mod HALF is
+      sorts Zero NzNat Nat .
+      subsorts Zero NzNat < Nat .
+      op 0 : -> Zero [ctor] .
+      op s : Nat -> NzNat [ctor] .
+      op p : NzNat -> Nat .
+      op half : Nat -> Nat .
+      eq p(s(N:Nat)) = N:Nat .
+      eq half(0) = 0 .
+      eq half(s(0)) = 0 .
+      eq half(s(s(N:Nat))) = s(half(N:Nat)) .
+      op down : Nat -> Nat .
+      eq down(N:Nat) = p(half(N:Nat)) .
+      op up : Nat -> Nat .
+      eq up(N:Nat) = p(r:Nat>NzNat(s(N:Nat))) .
+    endm
+  ";
+
+  #[test]
+  fn retracts_are_inserted_and_removed() {
+    let module = parse_module(SOURCE).unwrap();
+    let PreEquationKind::Equation { rhs_term } = &module.equations[4].kind else {
+      unreachable!()
+    };
+    assert_eq!(dag_to_string(rhs_term.term_to_dag(false)), "p(r:Nat>NzNat(half(N:Nat)))");
+
+    let reduce = |term: &str| {
+      let subject     = parse_term(&module, term).unwrap().term_to_dag(false);
+      let mut context = RewritingContext::new(&module);
+      context.reduce(subject)
+    };
+    // `half(s(s(s(s(0)))))` reduces to `s(s(0))`, a `NzNat`, so the retract is removed.
+    assert_eq!(dag_to_string(reduce("down(s(s(s(s(0)))))")), "s(0)");
+    assert_eq!(dag_to_string(reduce("up(0)")), "0");
+    // `half(s(0))` reduces to `0`, which is not a `NzNat`, so the retract is stuck.
+    let stuck = reduce("down(s(0))");
+    assert_eq!(dag_to_string(stuck), "p(r:Nat>NzNat(0))");
+    assert_eq!(retracts_of(stuck), vec![vec![0]]);
+  }
+}
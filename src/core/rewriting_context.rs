@@ -0,0 +1,1982 @@
+/*!
+
+A `RewritingContext` reduces subjects to normal form with respect to the equations of a module.
+
+Reduction is innermost: the arguments of a node are reduced before equations are tried at the node itself. A symbol
+with an evaluation strategy, set with `Symbol::set_strategy`, instead has its arguments reduced and its equations tried
+in the order its strategy gives, and arguments its strategy doesn't list are left unreduced.
+
+Unreduced arguments are reduced on demand: before an equation is tried, the unreduced subterms at the positions where
+its left-hand side has a non-variable subterm are reduced, in preorder, stopping at the first that can't match. So an
+equation like `eq if(true, X, Y) = X` for a lazy `if` reduces the condition to decide the match but not the branches.
+
+An equation whose left-hand side has an associative top symbol is matched with extension: it applies to any run of
+arguments of a flattened subject, or, if the symbol is also commutative, to any of its arguments, and the subject is
+rebuilt with the instance of the right-hand side in place of the arguments matched. So `eq plus(X, 0) = X` applies to
+`plus(a, plus(0, b))`.
+
+Candidate equations are found with a `StatementIndex`, so equations whose left-hand sides cannot match the subject are
+never attempted. Reduced nodes are flagged so that shared subterms are only reduced once.
+
+## Sharing
+
+Subjects are DAGs, and by default reduction has tree semantics: the subject is never modified, and the nodes that
+change are rebuilt. A subterm that is shared but not yet reduced is reduced at each of its occurrences. Only reduced
+flags, which hold regardless of context, are recorded in the subject.
+
+If the module sets `graph_rewriting`, reduction has term graph semantics instead: a node is overwritten in place with
+its normal form, so a shared subterm is rewritten once and every occurrence sees the result. The subject itself is
+overwritten with its normal form. This saves work on highly shared subjects, but callers holding other references into
+the subject will see it change.
+
+A node can only be overwritten with a node of its own theory, as the pointers to a node carry the vtable of its theory's
+node type, and a data node can't be overwritten at all. A node whose normal form is a data atom or a variable is
+therefore left as it is, and reduction forwards it to its normal form instead: every occurrence of the node met in the
+same reduction is replaced by the normal form without being rewritten again. Outside the reduction, as in references
+held by the caller, the node is still unreduced.
+
+## Tracing
+
+Reductions can be traced with `reduce_traced`, which records each rewrite in a `ProofTrace`.
+
+Reduction can be bounded by a number of rewrites with `reduce_bounded`, which is how the fuzz targets guard against
+nonterminating equations.
+
+## Conditions
+
+A statement with conditions applies only if its conditions hold under the bindings of its match. An equality condition
+`t = u` holds if the instances of `t` and `u` have equal normal forms, and a sort test `t :: s` holds if the normal form
+of the instance of `t` has sort `s` or one of its subsorts. Match and rewrite conditions are not supported yet: a
+statement with one is rejected when it is compiled. See `PreEquation::compile`.
+
+Evaluating conditions can fail to terminate even where reduction of the subject would not. The rewrites performed while
+evaluating the conditions of a statement, including those of the conditional statements applied along the way, draw on
+a budget of their own, the *condition fuel* of `EngineConfig::condition_fuel`, separate from the rewrite limit of
+`reduce_bounded`. When the fuel runs out, the statement is treated as not applying, and a `ConditionTimedOut`
+identifying the statement, the condition, and the bindings of the match is logged and kept for `condition_timeouts`.
+
+## Profiling
+
+A context created with profiling on counts how often each statement fires, and `coverage_report` lists the statements
+that never did. See `coverage`.
+
+Associative matches are limited in the subproblems and alternatives they may try by the `MatchLimits` of the
+configuration, and counted in `match_statistics`. See `match_limits`.
+
+Reductions, and the limits they reach, are reported to the engine's observers. See `observer`.
+
+A context can also hold a current term, set with `set_term` and reduced with `reduce_term`. `checkpoint` saves the
+current term and the context's counters, and `rollback` restores them, so that backtracking search strategies can try
+an alternative and undo it. With tree semantics checkpoints are cheap: reduction never modifies a node other than to
+flag it reduced, so a checkpoint shares the DAG of the current term instead of copying it. Nodes are copied only when
+they change, and the copies belong to the new term. With `graph_rewriting`, reduction overwrites the nodes of the
+current term, so a checkpoint saves a copy of its DAG instead, and a rollback makes a fresh copy of the saved DAG the
+current term. Checkpoints form a stack; rolling back to a checkpoint discards the checkpoints taken after it.
+
+## Rules
+
+Equations are applied by reduction, while rules are applied one step at a time, at a redex the caller picks from those
+listed by `rule_redexes` or that `choose_rule_redex` chooses by the priorities and weights of the rules, with
+`apply_rule`. See `rule_selection`. `solutions` instead gives the rewrites of a term by every rule redex lazily, one
+at a time, as a `SolutionCursor`. See `solution_cursor`.
+
+## Descent Functions
+
+A descent function of the metalevel, a symbol with the `Descent` attribute, has no equations. Once its arguments are
+reduced, it is evaluated in Rust, with a context of its own for the object module it names. See `metalevel`.
+
+## Positions
+
+Tools that drive the engine one step at a time, like proof assistants, address subterms by `Position`. `rewrite_at`
+rewrites the subterm at a position with the rule or equation of a given label, without reducing the result, and the
+free function `replace_subterm` puts a given DAG at a position. Both check the position and return a new root that
+shares every node of the old term outside the path to the position, leaving the old term intact.
+
+## Redexes
+
+`redexes` lists the redexes of a term without rewriting it: every position at which an equation matches, with the
+equation and the bindings of its variables. This is meant for tools that show the user what could be rewritten, like
+editor hovers and interactive provers.
+
+The garbage collector is not run during reduction, because intermediate nodes are not rooted. Callers may call
+`ok_to_collect_garbage` between reductions, provided the nodes they want to keep are held in `RootContainer`s.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter},
+  ops::Range
+};
+use alloc::{boxed::Box, format, rc::Rc, string::String, vec::Vec};
+
+use rand::Rng;
+
+use crate::{
+  abstractions::{HashMap, Instant, IString},
+  api::{
+    dag_node::DagNodePtr,
+    free_theory::FreeDagNode,
+    symbol::SymbolAttribute,
+    term::Term,
+    variable_theory::VariableTerm
+  },
+  core::{
+    arc_term::{ArcTerm, ArcTermError, ArcTermTable},
+    module::Module,
+    automata::{DemandedPosition, Extension},
+    binders::alpha_equal,
+    canonicalize::canonical_node,
+    config::EngineConfig,
+    coverage::{CoverageReport, StatementKind, StatementProfile},
+    dag_node_core::{DagNodeFlag, DagNodeTheory},
+    match_limits::{MatchBudget, MatchStatistics},
+    metalevel::descend,
+    observer::{is_observed, notify, EngineLimit, ReductionSummary},
+    pre_equation::{condition::Condition, PreEquation},
+    proof_trace::{dag_to_string, ProofTrace, RewriteStep},
+    retract::retracted,
+    rule_selection::{choose_weighted, order_by_priority, RuleRedex},
+    solution_cursor::SolutionCursor,
+    sort::{predicate::ActiveSortPredicates, sort_spec::SortSpec, SortPtr},
+    statement_index::{IndexStatistics, StatementIndex},
+    substitution::Substitution,
+    thread_bound::check_owner,
+    RootContainer
+  },
+  warning
+};
+#[cfg(feature = "smt")]
+use crate::core::smt::{SmtResult, SmtSignature, SmtSolver};
+
+/// The number of rewrites the evaluation of a statement's conditions may take if the configuration doesn't say
+pub const DEFAULT_CONDITION_FUEL: usize = 100_000;
+
+/// A path of argument indices from the top of a term to a subterm, with the empty path denoting the top
+pub type Position = Vec<usize>;
+
+/// The bindings of a statement's variables, in the order the statement's variables were indexed
+pub type Bindings = Vec<(IString, DagNodePtr)>;
+
+/// Returned by `RewritingContext::reduce_bounded` when the rewrite limit is reached before a normal form.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RewriteLimitReached;
+
+impl Display for RewriteLimitReached {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "rewrite limit reached before a normal form")
+  }
+}
+
+impl Error for RewriteLimitReached {}
+
+/// A token for a saved state of a `RewritingContext`, returned by `RewritingContext::checkpoint`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Checkpoint(usize);
+
+/// Returned by `RewritingContext::rollback` and `RewritingContext::discard` for a checkpoint that was already
+/// discarded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct UnknownCheckpoint(pub Checkpoint);
+
+impl Display for UnknownCheckpoint {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "checkpoint {} was discarded", self.0.0)
+  }
+}
+
+impl Error for UnknownCheckpoint {}
+
+/// Returned by `replace_subterm` for a position that isn't in the term.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct InvalidPosition(pub Position);
+
+impl Display for InvalidPosition {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "the term has no subterm at position {:?}", self.0)
+  }
+}
+
+impl Error for InvalidPosition {}
+
+/// Returned by `RewritingContext::rewrite_at` when the step can't be taken.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum RewriteAtError {
+  InvalidPosition(Position),
+  /// No rule or equation of the module has the label.
+  UnknownLabel(IString),
+  /// No statement with the label matches the subterm at the position with its conditions holding.
+  NoMatch { label: IString, position: Position },
+}
+
+impl Display for RewriteAtError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      RewriteAtError::InvalidPosition(position) => write!(f, "the term has no subterm at position {:?}", position),
+      RewriteAtError::UnknownLabel(label)       => write!(f, "no rule or equation is labeled {}", label),
+      RewriteAtError::NoMatch { label, position } => {
+        write!(f, "no statement labeled {} matches at position {:?}", label, position)
+      }
+    }
+  }
+}
+
+impl Error for RewriteAtError {}
+
+/// Recorded when the evaluation of a statement's conditions runs out of fuel. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ConditionTimedOut {
+  pub kind     : StatementKind,
+  /// The index of the statement among the module's statements of its kind
+  pub statement: usize,
+  pub label    : Option<IString>,
+  /// The index of the condition being evaluated when the fuel ran out
+  pub condition: usize,
+  /// The bindings of the statement's variables when the fuel ran out
+  pub bindings : Vec<(IString, String)>,
+}
+
+impl Display for ConditionTimedOut {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "condition {} of {} {}", self.condition, self.kind, self.statement)?;
+    if let Some(label) = &self.label {
+      write!(f, " [{}]", label)?;
+    }
+    write!(f, " ran out of fuel")?;
+    if !self.bindings.is_empty() {
+      let bindings: Vec<String> = self.bindings.iter().map(|(name, value)| format!("{} = {}", name, value)).collect();
+      write!(f, " with {}", bindings.join(", "))?;
+    }
+    Ok(())
+  }
+}
+
+impl Error for ConditionTimedOut {}
+
+impl From<InvalidPosition> for RewriteAtError {
+  fn from(error: InvalidPosition) -> Self {
+    RewriteAtError::InvalidPosition(error.0)
+  }
+}
+
+/// The state of a context saved by a checkpoint
+struct SavedState {
+  checkpoint    : Checkpoint,
+  term          : Option<DagNodePtr>,
+  /// Keeps `term` alive for as long as the checkpoint exists
+  _root         : Option<Box<RootContainer>>,
+  equation_count: usize,
+  rule_count    : usize,
+  statistics    : IndexStatistics,
+}
+
+pub struct RewritingContext<'m> {
+  module          : &'m Module,
+  equation_index  : StatementIndex,
+  rule_index      : StatementIndex,
+  substitution    : Substitution,
+  /// Scratch space for the candidates returned by `equation_index`
+  candidates      : Vec<usize>,
+  /// The number of equational rewrites performed
+  equation_count  : usize,
+  /// The number of rule rewrites performed
+  rule_count      : usize,
+  /// Reduction stops when `equation_count` reaches this value.
+  rewrite_limit   : usize,
+  /// Whether the module has symbols with evaluation strategies, whose unreduced arguments equations may demand
+  on_demand       : bool,
+  /// Under graph rewriting, the normal forms of the nodes of the current reduction that couldn't be overwritten with
+  /// them, by the address of the node. See the module documentation.
+  forwarded       : HashMap<*const u8, DagNodePtr>,
+
+  // Conditions
+  /// The number of rewrites the evaluation of a statement's conditions may take
+  condition_fuel    : usize,
+  /// The fuel left while conditions are being evaluated, and `None` otherwise
+  fuel              : Option<usize>,
+  condition_timeouts: Vec<ConditionTimedOut>,
+  /// The solver asked about equality conditions before they are evaluated by rewriting, and its signature
+  #[cfg(feature = "smt")]
+  smt               : Option<(SmtSignature, Box<dyn SmtSolver>)>,
+
+  /// The limits and counts of associative matches, shared with `substitution`
+  match_budget    : Rc<MatchBudget>,
+
+  // Tracing
+  /// The trace of the current reduction, if it is traced
+  trace           : Option<ProofTrace>,
+  /// The position of the subterm being reduced
+  position        : Vec<usize>,
+  /// How often each statement has fired, if the context profiles
+  profile         : Option<StatementProfile>,
+
+  // Backtracking
+  /// The current term
+  term            : Option<DagNodePtr>,
+  term_root       : Option<Box<RootContainer>>,
+  checkpoints     : Vec<SavedState>,
+  /// The number of checkpoints taken, used to tell checkpoints apart
+  checkpoint_count: usize,
+}
+
+impl<'m> RewritingContext<'m> {
+  /// Creates a context for reducing with the equations of `module`, which must already be compiled.
+  #[inline(always)]
+  pub fn new(module: &'m Module) -> Self {
+    Self::with_config(module, &EngineConfig::default())
+  }
+
+  /// Like `new`, but with the session settings of `config`, like the depth of the equation index. Panics if the engine
+  /// is bound to another thread (see `thread_bound`).
+  pub fn with_config(module: &'m Module, config: &EngineConfig) -> Self {
+    check_owner();
+    let match_budget     = Rc::new(MatchBudget::new(config.match_limits));
+    let mut substitution = Substitution::new();
+    substitution.set_budget(match_budget.clone());
+
+    RewritingContext {
+      module,
+      equation_index  : StatementIndex::with_depth(&module.equations, config.index_depth),
+      rule_index      : StatementIndex::with_depth(&module.rules, config.index_depth),
+      substitution,
+      candidates      : Vec::new(),
+      equation_count  : 0,
+      rule_count      : 0,
+      rewrite_limit   : usize::MAX,
+      on_demand       : module.arena.symbols.values().any(|&symbol| !unsafe{ &*symbol }.strategy.is_empty()),
+      forwarded       : HashMap::default(),
+      condition_fuel    : config.condition_fuel,
+      fuel              : None,
+      condition_timeouts: Vec::new(),
+      #[cfg(feature = "smt")]
+      smt               : None,
+      match_budget,
+      trace           : None,
+      position        : Vec::new(),
+      profile         : config.profile.then(|| StatementProfile::new(module)),
+      term            : None,
+      term_root       : None,
+      checkpoints     : Vec::new(),
+      checkpoint_count: 0,
+    }
+  }
+
+  #[inline(always)]
+  pub fn module(&self) -> &'m Module {
+    self.module
+  }
+
+  /// The number of equational rewrites performed by this context.
+  #[inline(always)]
+  pub fn equation_count(&self) -> usize {
+    self.equation_count
+  }
+
+  /// The number of rule rewrites performed by this context.
+  #[inline(always)]
+  pub fn rule_count(&self) -> usize {
+    self.rule_count
+  }
+
+  /// The statements whose conditions ran out of fuel in this context, in the order they did. See the module
+  /// documentation.
+  #[inline(always)]
+  pub fn condition_timeouts(&self) -> &[ConditionTimedOut] {
+    &self.condition_timeouts
+  }
+
+  /// Makes `solver` decide the equality conditions whose instances translate to ground formulas by `signature`, in
+  /// place of rewriting. See `smt`.
+  #[cfg(feature = "smt")]
+  pub fn set_smt_solver(&mut self, signature: SmtSignature, solver: impl SmtSolver + 'static) {
+    self.smt = Some((signature, Box::new(solver)));
+  }
+
+  /// Counts of the associative matches performed by this context, and of those that gave up on reaching a limit. See
+  /// `match_limits`.
+  #[inline(always)]
+  pub fn match_statistics(&self) -> MatchStatistics {
+    self.match_budget.statistics()
+  }
+
+  /// How often each statement has fired, or `None` if the context doesn't profile.
+  #[inline(always)]
+  pub fn profile(&self) -> Option<&StatementProfile> {
+    self.profile.as_ref()
+  }
+
+  /// The statements of the module that have not fired in this context, or `None` if the context doesn't profile. See
+  /// `coverage`.
+  pub fn coverage_report(&self) -> Option<CoverageReport> {
+    self.profile.as_ref().map(|profile| CoverageReport::new(self.module, profile))
+  }
+
+  /// Statistics on how well the equation index filters candidate equations.
+  #[inline(always)]
+  pub fn index_statistics(&self) -> &IndexStatistics {
+    self.equation_index.statistics()
+  }
+
+  // region Current term and checkpoints
+
+  /// The current term, if one has been set.
+  #[inline(always)]
+  pub fn term(&self) -> Option<DagNodePtr> {
+    self.term
+  }
+
+  /// Makes `term` the current term. The context keeps the current term alive across garbage collections.
+  pub fn set_term(&mut self, term: DagNodePtr) {
+    self.term_root = Some(RootContainer::new(term));
+    self.term      = Some(term);
+  }
+
+  /// Reduces the current term to normal form, making the normal form the current term, and returns it.
+  pub fn reduce_term(&mut self) -> Option<DagNodePtr> {
+    let normal_form = self.reduce(self.term?);
+    self.set_term(normal_form);
+
+    Some(normal_form)
+  }
+
+  /// Saves the current term and the rewrite and index counters. The returned checkpoint can be passed to `rollback` to
+  /// return to this state, as many times as needed, until the checkpoint is discarded.
+  pub fn checkpoint(&mut self) -> Checkpoint {
+    let checkpoint = Checkpoint(self.checkpoint_count);
+    self.checkpoint_count += 1;
+
+    let term = self.term.map(|term| self.unshared(term));
+    self.checkpoints.push(
+      SavedState {
+        checkpoint,
+        term,
+        _root         : term.map(RootContainer::new),
+        equation_count: self.equation_count,
+        rule_count    : self.rule_count,
+        statistics    : *self.equation_index.statistics(),
+      }
+    );
+
+    checkpoint
+  }
+
+  /// Restores the state saved by `checkpoint`, discarding the checkpoints taken after it. The checkpoint itself
+  /// remains valid.
+  pub fn rollback(&mut self, checkpoint: Checkpoint) -> Result<(), UnknownCheckpoint> {
+    let position = self.position_of(checkpoint)?;
+    self.checkpoints.truncate(position + 1);
+
+    let saved = &self.checkpoints[position];
+    self.term           = saved.term.map(|term| self.unshared(term));
+    self.term_root      = self.term.map(RootContainer::new);
+    self.equation_count = saved.equation_count;
+    self.rule_count     = saved.rule_count;
+    self.equation_index.set_statistics(saved.statistics);
+
+    Ok(())
+  }
+
+  /// Discards `checkpoint` and the checkpoints taken after it without changing the current state.
+  pub fn discard(&mut self, checkpoint: Checkpoint) -> Result<(), UnknownCheckpoint> {
+    let position = self.position_of(checkpoint)?;
+    self.checkpoints.truncate(position);
+
+    Ok(())
+  }
+
+  /// The number of checkpoints that have not been discarded.
+  #[inline(always)]
+  pub fn checkpoint_depth(&self) -> usize {
+    self.checkpoints.len()
+  }
+
+  /// `term`, or, with `graph_rewriting`, a copy of it that reduction of `term` can't overwrite.
+  fn unshared(&self, term: DagNodePtr) -> DagNodePtr {
+    match self.module.graph_rewriting {
+      true  => copy_graph(term, &mut HashMap::default()),
+      false => term,
+    }
+  }
+
+  fn position_of(&self, checkpoint: Checkpoint) -> Result<usize, UnknownCheckpoint> {
+    // Checkpoints are taken in increasing order, so the stack is sorted.
+    self.checkpoints
+        .binary_search_by_key(&checkpoint.0, |saved| saved.checkpoint.0)
+        .map_err(|_| UnknownCheckpoint(checkpoint))
+  }
+
+  // endregion Current term and checkpoints
+
+  /// Reduces `subject` to normal form, returning the normal form. Unless the module uses graph rewriting, the subject
+  /// itself is never modified; nodes that change are rebuilt. Every node of the normal form has its sort computed.
+  pub fn reduce(&mut self, subject: DagNodePtr) -> DagNodePtr {
+    self.rewrite_limit = usize::MAX;
+    self.reduce_observed(subject).expect("unbounded reduction hit a rewrite limit")
+  }
+
+  /// Like `reduce`, but gives up after `max_rewrites` rewrites, so that nonterminating equations can't hang the
+  /// caller. Subterms that reached normal form before the limit keep their reduced flags.
+  pub fn reduce_bounded(&mut self, subject: DagNodePtr, max_rewrites: usize) -> Result<DagNodePtr, RewriteLimitReached> {
+    self.rewrite_limit = self.equation_count.saturating_add(max_rewrites);
+    let result = self.reduce_observed(subject);
+    self.rewrite_limit = usize::MAX;
+
+    result
+  }
+
+  /// Like `reduce`, but for an `ArcTerm`, whose normal form is made by `table`. See `arc_term`.
+  pub fn reduce_arc(&mut self, subject: &ArcTerm, table: &ArcTermTable) -> Result<ArcTerm, ArcTermError> {
+    let subject = subject.to_dag(self.module)?;
+    Ok(table.term_for_dag(self.reduce(subject)))
+  }
+
+  /// Like `reduce`, but also returns a trace of the rewrites that were performed.
+  pub fn reduce_traced(&mut self, subject: DagNodePtr) -> (DagNodePtr, ProofTrace) {
+    self.trace = Some(ProofTrace::new(subject));
+    self.position.clear();
+    let result = self.reduce(subject);
+
+    let mut trace = self.trace.take().unwrap();
+    trace.result  = dag_to_string(result);
+
+    (result, trace)
+  }
+
+  /// Reduces `subject` like `reduce_aux`, telling the observers of the reduction. See `observer`.
+  fn reduce_observed(&mut self, subject: DagNodePtr) -> Result<DagNodePtr, RewriteLimitReached> {
+    // Data nodes are sorted by the module's predicates, including those sorted in another module before.
+    let _predicates = ActiveSortPredicates::new(&self.module.sort_predicates);
+    // Nodes may be collected between reductions, so their addresses may be reused.
+    self.forwarded.clear();
+    if !self.module.sort_predicates.is_empty() {
+      self.module.sort_data_atoms(subject);
+    }
+
+    if !is_observed() {
+      return self.reduce_aux(subject);
+    }
+
+    let module = &self.module.name;
+    notify(|observer| observer.reduction_started(module));
+    let (rewrites, matches, start) = (self.equation_count, self.match_statistics(), Instant::now());
+    let result  = self.reduce_aux(subject);
+    let summary = ReductionSummary {
+      rewrites : self.equation_count - rewrites,
+      matches  : self.match_statistics().since(&matches),
+      completed: result.is_ok(),
+      elapsed  : start.elapsed(),
+    };
+
+    if result.is_err() {
+      let limit = EngineLimit::Rewrites { limit: self.rewrite_limit - rewrites };
+      notify(|observer| observer.limit_reached(&limit));
+    }
+    notify(|observer| observer.reduction_finished(module, &summary));
+
+    result
+  }
+
+  fn reduce_aux(&mut self, subject: DagNodePtr) -> Result<DagNodePtr, RewriteLimitReached> {
+    if unsafe{ &*subject }.is_reduced() {
+      return Ok(subject);
+    }
+    if let Some(&normal_form) = self.forwarded.get(&(subject as *const u8)) {
+      return Ok(normal_form);
+    }
+
+    let normal_form = self.normalize(subject)?;
+    if self.module.graph_rewriting && !core::ptr::addr_eq(normal_form, subject) {
+      let subject_core     = unsafe{ &mut *subject }.core_mut();
+      let normal_form_core = unsafe{ &*normal_form }.core();
+      // A data node can't be overwritten, as its atom can't be shared or copied.
+      if subject_core.theory() == normal_form_core.theory() && subject_core.theory() != DagNodeTheory::Data {
+        subject_core.overwrite_with_clone(normal_form_core);
+        return Ok(subject);
+      }
+      self.forwarded.insert(subject as *const u8, normal_form);
+    }
+
+    Ok(normal_form)
+  }
+
+  /// Rewrites `subject` to normal form, following the evaluation strategy of each top symbol.
+  fn normalize(&mut self, subject: DagNodePtr) -> Result<DagNodePtr, RewriteLimitReached> {
+    let mut node = subject;
+    loop {
+      let symbol    = unsafe{ &*(*node).symbol() };
+      let rewritten = match symbol.strategy.is_empty() {
+        true => {
+          node = self.reduce_arguments(node, 0..unsafe{ &*node }.len())?;
+          self.rewrite_at_top(&mut node)?
+        }
+        false => self.follow_strategy(&mut node, &symbol.strategy)?
+      };
+
+      match rewritten {
+        Some(result) if unsafe{ &*result }.is_reduced() => return Ok(result),
+        Some(result) => node = result,
+        None         => break
+      }
+    }
+
+    // A sort cached before the arguments were reduced is stale: under graph rewriting, the arguments are overwritten
+    // in place, so `node` is the same node it was. The reduced arguments have their sorts, so this only looks at the
+    // top symbol's declarations. An argument left unreduced by the strategy may not have a sort, in which case neither
+    // does the node.
+    let node_mut = unsafe{ &mut *node };
+    node_mut.invalidate_sort();
+    node_mut.compute_sort();
+    node_mut.set_reduced();
+    Ok(node)
+  }
+
+  /// Follows `strategy`, reducing the arguments it lists and trying the equations at each `0`, until an equation
+  /// applies. Returns the result of the equation, or `None` if none applied, in which case `node` is left with the
+  /// arguments the strategy reduced.
+  fn follow_strategy(
+    &mut self,
+    node    : &mut DagNodePtr,
+    strategy: &[usize]
+  ) -> Result<Option<DagNodePtr>, RewriteLimitReached> {
+    for &step in strategy {
+      match step {
+        0 => {
+          if let Some(result) = self.rewrite_at_top(node)? {
+            return Ok(Some(result));
+          }
+        }
+        argument => *node = self.reduce_arguments(*node, argument - 1..argument)?
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// Reduces the arguments of `subject` with indices in `indices`, returning either `subject` or, if an argument
+  /// changed, a copy of `subject` with the reduced arguments. If every argument was reduced, the copy is in canonical
+  /// form modulo the axioms of its symbol, and a subject whose symbol has axioms is copied into canonical form even if
+  /// no argument changed. Otherwise the copy keeps the arguments in place, as the later steps of an evaluation
+  /// strategy refer to them by position.
+  fn reduce_arguments(
+    &mut self,
+    subject: DagNodePtr,
+    indices: Range<usize>
+  ) -> Result<DagNodePtr, RewriteLimitReached> {
+    let subject_ref = unsafe{ &*subject };
+    let mut changed = false;
+    let mut args    = Vec::with_capacity(subject_ref.len());
+    for (index, arg) in subject_ref.iter_args().enumerate() {
+      if !indices.contains(&index) {
+        args.push(arg);
+        continue;
+      }
+
+      self.position.push(index);
+      let reduced = self.reduce_aux(arg);
+      self.position.pop();
+      let reduced = reduced?;
+      changed |= !core::ptr::addr_eq(reduced, arg);
+      args.push(reduced);
+    }
+
+    let has_axioms = subject_ref.symbol_ref().attributes.intersects(SymbolAttribute::Axioms);
+    match (changed || has_axioms, indices.len() == args.len()) {
+      (false, _)    => Ok(subject),
+      (true, true)  => Ok(canonical_node(subject_ref.symbol(), args, self.module)),
+      (true, false) => Ok(FreeDagNode::with_args(subject_ref.symbol(), &mut args))
+    }
+  }
+
+  /// Applies the first equation that matches `subject` at the top, returning the instance of its right-hand side. If
+  /// the module has symbols with evaluation strategies, the subterms an equation demands are reduced before it is
+  /// tried, and `subject` is left with them reduced. A retract whose argument has the sort it retracts to gives its
+  /// argument instead, and a descent function gives its result (see `metalevel`).
+  fn rewrite_at_top(&mut self, subject: &mut DagNodePtr) -> Result<Option<DagNodePtr>, RewriteLimitReached> {
+    if let Some(argument) = retracted(*subject) {
+      return Ok(Some(argument));
+    }
+    if let Some(result) = descend(self.module, *subject) {
+      return Ok(Some(result));
+    }
+    if self.on_demand {
+      return self.rewrite_on_demand(subject);
+    }
+
+    self.equation_index.find_candidates(*subject, &mut self.candidates);
+    // Evaluating conditions reduces, which reuses `self.candidates`.
+    let candidates  = core::mem::take(&mut self.candidates);
+    let result      = self.rewrite_with(*subject, &candidates);
+    self.candidates = candidates;
+
+    result
+  }
+
+  /// Rewrites `subject` at the top with the first of the equations `candidates` that applies.
+  fn rewrite_with(
+    &mut self,
+    subject   : DagNodePtr,
+    candidates: &[usize]
+  ) -> Result<Option<DagNodePtr>, RewriteLimitReached> {
+    let module = self.module;
+    for &statement in candidates {
+      let equation  = &module.equations[statement];
+      let extension = equation.match_lhs_with_extension(subject, &mut self.substitution);
+      self.equation_index.record_match(extension.is_some());
+      if let Some(extension) = extension {
+        if self.conditions_hold(equation, StatementKind::Equation, statement)? {
+          return self.apply(statement, subject, &extension).map(Some);
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// Like `rewrite_at_top`, but reduces the unreduced subterms each candidate equation demands before trying it. The
+  /// index can't rule out candidates by their fingerprints, as the symbols of unreduced subterms may change.
+  fn rewrite_on_demand(&mut self, subject: &mut DagNodePtr) -> Result<Option<DagNodePtr>, RewriteLimitReached> {
+    let module = self.module;
+    // Reducing demanded subterms reuses `self.candidates`.
+    let mut candidates = Vec::new();
+    self.equation_index.find_unfiltered_candidates(*subject, &mut candidates);
+
+    for statement in candidates {
+      let equation  = &module.equations[statement];
+      let extension = match self.demand(subject, equation.demanded_positions())? {
+        true  => equation.match_lhs_with_extension(*subject, &mut self.substitution),
+        false => None
+      };
+      self.equation_index.record_match(extension.is_some());
+      if let Some(extension) = extension {
+        if self.conditions_hold(equation, StatementKind::Equation, statement)? {
+          return self.apply(statement, *subject, &extension).map(Some);
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// Reduces the unreduced subterms of `subject` at the `demanded` positions, in order, rebuilding `subject` above the
+  /// subterms that change. Returns `false` as soon as a subterm's symbol differs from the demanded symbol, as the match
+  /// can't succeed, leaving the subterms below it alone.
+  fn demand(&mut self, subject: &mut DagNodePtr, demanded: &[DemandedPosition]) -> Result<bool, RewriteLimitReached> {
+    for DemandedPosition { position, symbol } in demanded {
+      let mut subterm = subterm_at(*subject, position).expect("demanded position is missing");
+      if !unsafe{ &*subterm }.is_reduced() {
+        let depth = self.position.len();
+        self.position.extend_from_slice(position);
+        let reduced = self.reduce_aux(subterm);
+        self.position.truncate(depth);
+        let reduced = reduced?;
+
+        if !core::ptr::addr_eq(reduced, subterm) {
+          *subject = replace_unchecked(*subject, position, reduced);
+          subterm  = reduced;
+        }
+      }
+
+      if !core::ptr::addr_eq(unsafe{ &*subterm }.symbol(), *symbol) {
+        return Ok(false);
+      }
+    }
+
+    Ok(true)
+  }
+
+  /// Constructs the instance of the right-hand side of the equation `statement`, which has just matched `subject`, and
+  /// puts it in place of the part of `subject` the equation matched.
+  fn apply(
+    &mut self,
+    statement: usize,
+    subject  : DagNodePtr,
+    extension: &Extension
+  ) -> Result<DagNodePtr, RewriteLimitReached> {
+    // Within conditions, running out of fuel stops reduction the way reaching the rewrite limit does.
+    match &mut self.fuel {
+      Some(0)    => return Err(RewriteLimitReached),
+      Some(fuel) => *fuel -= 1,
+      None       => {
+        if self.equation_count >= self.rewrite_limit {
+          return Err(RewriteLimitReached);
+        }
+      }
+    }
+    self.equation_count += 1;
+    if let Some(profile) = &mut self.profile {
+      profile.record(StatementKind::Equation, statement);
+    }
+
+    let result = extension.rebuild(self.module.equations[statement].construct_rhs(&mut self.substitution));
+    if self.trace.is_some() {
+      self.record_step(statement, subject, result);
+    }
+    Ok(result)
+  }
+
+  /// Whether the conditions of `statement`, whose left-hand side has just matched with the bindings in
+  /// `self.substitution`, hold. The bindings are left as they were. An evaluation nested within the evaluation of
+  /// another statement's conditions shares its fuel and passes running out of it on, while the outermost evaluation
+  /// records the timeout and fails. See the module documentation.
+  fn conditions_hold(
+    &mut self,
+    statement: &'m PreEquation,
+    kind     : StatementKind,
+    index    : usize
+  ) -> Result<bool, RewriteLimitReached> {
+    if statement.conditions.is_empty() {
+      return Ok(true);
+    }
+
+    let bindings  = self.substitution.clone();
+    let trace     = self.trace.take();
+    let outermost = self.fuel.is_none();
+    if outermost {
+      self.fuel = Some(self.condition_fuel);
+    }
+
+    let mut result    = Ok(true);
+    let mut timed_out = None;
+    for (position, condition) in statement.conditions.iter().enumerate() {
+      let holds = match self.solver_decides(condition, &bindings) {
+        Some(holds) => Ok(holds),
+        None        => self.condition_holds(condition, &bindings),
+      };
+      match holds {
+        Ok(true)   => continue,
+        Ok(false)  => result = Ok(false),
+        Err(error) => {
+          result    = Err(error);
+          timed_out = Some(position);
+        }
+      }
+      break;
+    }
+
+    self.substitution = bindings;
+    self.trace        = trace;
+    if outermost {
+      self.fuel = None;
+      if let Some(condition) = timed_out {
+        self.record_timeout(statement, kind, index, condition);
+        result = Ok(false);
+      }
+    }
+
+    result
+  }
+
+  /// Whether the SMT solver says the instance of `condition` holds, or `None` if there is no solver, the instance has
+  /// uninterpreted symbols or variables, or the solver gives up
+  #[cfg(feature = "smt")]
+  fn solver_decides(&mut self, condition: &Condition, bindings: &Substitution) -> Option<bool> {
+    let (signature, solver) = self.smt.as_mut()?;
+    let Condition::Equality { lhs_term, rhs_term } = condition else {
+      return None;
+    };
+    let lhs     = instantiate(lhs_term.as_ref(), bindings, self.module);
+    let rhs     = instantiate(rhs_term.as_ref(), bindings, self.module);
+    let formula = signature.translate_equality(lhs, rhs).ok().filter(|formula| formula.variables().is_empty())?;
+
+    match solver.check_sat(&formula) {
+      SmtResult::Sat     => Some(true),
+      SmtResult::Unsat   => Some(false),
+      SmtResult::Unknown => None,
+    }
+  }
+
+  #[cfg(not(feature = "smt"))]
+  #[inline(always)]
+  fn solver_decides(&mut self, _condition: &Condition, _bindings: &Substitution) -> Option<bool> {
+    None
+  }
+
+  fn condition_holds(&mut self, condition: &Condition, bindings: &Substitution) -> Result<bool, RewriteLimitReached> {
+    match condition {
+      Condition::Equality { lhs_term, rhs_term } => {
+        let lhs = self.reduce_aux(instantiate(lhs_term.as_ref(), bindings, self.module))?;
+        let rhs = self.reduce_aux(instantiate(rhs_term.as_ref(), bindings, self.module))?;
+        Ok(alpha_equal(lhs, rhs))
+      }
+
+      Condition::SortMembership { lhs_term, sort } => {
+        let node = self.reduce_aux(instantiate(lhs_term.as_ref(), bindings, self.module))?;
+        let holds = match sort.as_ref() {
+          SortSpec::Sort(sort) => unsafe{ &*node }.get_sort().is_some_and(|node_sort| leq(node_sort, *sort)),
+          SortSpec::Any        => true,
+          SortSpec::None
+          | SortSpec::Functor { .. } => false,
+        };
+        Ok(holds)
+      }
+
+      Condition::Match { .. } | Condition::Rewrite { .. } => {
+        unreachable!("the condition {} is rejected when its statement is compiled", condition)
+      }
+    }
+  }
+
+  fn record_timeout(&mut self, statement: &PreEquation, kind: StatementKind, index: usize, condition: usize) {
+    let timeout = ConditionTimedOut {
+      kind,
+      statement: index,
+      label    : statement.name.clone(),
+      condition,
+      bindings : self.bindings(statement)
+                     .into_iter()
+                     .map(|(name, value)| (name, dag_to_string(value)))
+                     .collect(),
+    };
+    warning!(1, "{}", timeout);
+    if is_observed() {
+      let limit = EngineLimit::ConditionFuel(timeout.clone());
+      notify(|observer| observer.limit_reached(&limit));
+    }
+    self.condition_timeouts.push(timeout);
+  }
+
+  fn record_step(&mut self, statement: usize, redex: DagNodePtr, contractum: DagNodePtr) {
+    let equation     = &self.module.equations[statement];
+    let substitution = self.bindings(equation)
+                           .into_iter()
+                           .map(|(name, value)| (name, dag_to_string(value)))
+                           .collect();
+
+    let step = RewriteStep {
+      statement,
+      label     : equation.name.clone(),
+      position  : self.position.clone(),
+      substitution,
+      redex     : dag_to_string(redex),
+      contractum: dag_to_string(contractum),
+    };
+    self.trace.as_mut().unwrap().steps.push(step);
+  }
+
+  /// The bindings of the variables of `equation` after a successful match.
+  fn bindings(&self, equation: &PreEquation) -> Bindings {
+    let variable_info = &equation.variable_info;
+    (0..variable_info.real_variable_count())
+        .filter_map(
+          |index| {
+            let variable = variable_info.index_to_variable(index as i8)?;
+            let value    = self.substitution.value(index)?;
+            Some((variable.symbol_ref().name.clone(), value))
+          }
+        )
+        .collect()
+  }
+
+  // region Redexes
+
+  /// Lists the redexes of `subject` without rewriting it: for each position at which an equation matches and its
+  /// conditions hold, in preorder, the position, the equation, and the bindings of its variables. A shared subterm is
+  /// listed at each of its occurrences, while subterms flagged reduced are skipped, as no equation applies within them.
+  /// The index statistics are left unchanged.
+  pub fn redexes(&mut self, subject: DagNodePtr) -> impl Iterator<Item=(Position, &'m PreEquation, Bindings)> {
+    let statistics  = *self.equation_index.statistics();
+    let mut redexes = Vec::new();
+    self.collect_redexes(subject, &mut Vec::new(), &mut redexes);
+    self.equation_index.set_statistics(statistics);
+
+    redexes.into_iter()
+  }
+
+  fn collect_redexes(
+    &mut self,
+    subject : DagNodePtr,
+    position: &mut Position,
+    redexes : &mut Vec<(Position, &'m PreEquation, Bindings)>
+  ) {
+    let subject_ref = unsafe{ &*subject };
+    if subject_ref.is_reduced() {
+      return;
+    }
+
+    let module = self.module;
+    self.equation_index.find_candidates(subject, &mut self.candidates);
+    // Evaluating conditions reduces, which reuses `self.candidates`.
+    let candidates = core::mem::take(&mut self.candidates);
+    for &statement in candidates.iter() {
+      let equation = &module.equations[statement];
+      if equation.match_lhs_with_extension(subject, &mut self.substitution).is_some()
+          && self.conditions_hold(equation, StatementKind::Equation, statement) == Ok(true)
+      {
+        redexes.push((position.clone(), equation, self.bindings(equation)));
+      }
+    }
+    self.candidates = candidates;
+
+    for (index, arg) in subject_ref.iter_args().enumerate() {
+      position.push(index);
+      self.collect_redexes(arg, position, redexes);
+      position.pop();
+    }
+  }
+
+  // endregion Redexes
+
+  // region Rules
+
+  /// Lists the rule redexes of `subject`, a term in normal form with respect to the equations, in the order
+  /// exploration should visit them: by the priorities of their rules, highest first, and in preorder among redexes of
+  /// equal priority. A rule with an associative top symbol is only tried at the top of a flattened subterm, against
+  /// the whole subterm. See `rule_selection`.
+  pub fn rule_redexes(&mut self, subject: DagNodePtr) -> Vec<RuleRedex> {
+    let mut redexes = Vec::new();
+    self.collect_rule_redexes(subject, &mut Vec::new(), &mut redexes);
+    order_by_priority(&mut redexes, &self.module.rules);
+
+    redexes
+  }
+
+  fn collect_rule_redexes(&mut self, subject: DagNodePtr, position: &mut Position, redexes: &mut Vec<RuleRedex>) {
+    let module = self.module;
+    self.rule_index.find_candidates(subject, &mut self.candidates);
+    // Evaluating conditions reduces, which reuses `self.candidates`.
+    let candidates = core::mem::take(&mut self.candidates);
+    for &rule in candidates.iter() {
+      let statement = &module.rules[rule];
+      if statement.match_lhs(subject, &mut self.substitution)
+          && self.conditions_hold(statement, StatementKind::Rule, rule) == Ok(true)
+      {
+        redexes.push(RuleRedex { position: position.clone(), rule, bindings: self.bindings(statement) });
+      }
+    }
+    self.candidates = candidates;
+
+    for (index, arg) in unsafe{ &*subject }.iter_args().enumerate() {
+      position.push(index);
+      self.collect_rule_redexes(arg, position, redexes);
+      position.pop();
+    }
+  }
+
+  /// Chooses a rule redex of `subject` among those of the highest priority, with probability proportional to the
+  /// weights of their rules, using `rng`. Returns `None` if no rule of positive weight applies at that priority.
+  pub fn choose_rule_redex<R: Rng + ?Sized>(&mut self, subject: DagNodePtr, rng: &mut R) -> Option<RuleRedex> {
+    let redexes = self.rule_redexes(subject);
+    choose_weighted(&redexes, &self.module.rules, rng).cloned()
+  }
+
+  /// Rewrites `subject` with the rule of `redex`, one of the redexes `rule_redexes` lists for `subject`, and reduces
+  /// the result with the equations. The nodes above the redex are rebuilt.
+  pub fn apply_rule(&mut self, subject: DagNodePtr, redex: &RuleRedex) -> DagNodePtr {
+    let rule   = &self.module.rules[redex.rule];
+    let target = subterm_at(subject, &redex.position).expect("the redex position is missing");
+    assert!(rule.match_lhs(target, &mut self.substitution), "the rule no longer matches at the redex");
+
+    self.rewrite_with_match(subject, &redex.position, redex.rule)
+  }
+
+  /// Lazily rewrites `subject`, a term in normal form with respect to the equations, one rule step at a time, giving
+  /// a solution for each rule redex, including each alternative match of a rule with an associative top symbol. See
+  /// `solution_cursor`.
+  pub fn solutions(&mut self, subject: DagNodePtr) -> SolutionCursor<'_, 'm> {
+    SolutionCursor::new(self, subject)
+  }
+
+  /// The rules that may match `node`
+  pub(crate) fn rule_candidates(&mut self, node: DagNodePtr) -> Vec<usize> {
+    self.rule_index.find_candidates(node, &mut self.candidates);
+    self.candidates.clone()
+  }
+
+  /// Matches the rule of index `rule` against `node`, skipping the first `*skip` matches, and counting in `skip` the
+  /// matches tried, up to and including the first whose conditions hold. Returns the bindings of that match, which
+  /// the substitution holds until `rewrite_with_match` is called, or `None` if there is none.
+  pub(crate) fn match_rule(&mut self, node: DagNodePtr, rule: usize, skip: &mut usize) -> Option<Bindings> {
+    let statement = &self.module.rules[rule];
+    while statement.match_lhs_skipping(node, &mut self.substitution, *skip) {
+      *skip += 1;
+      if self.conditions_hold(statement, StatementKind::Rule, rule) == Ok(true) {
+        return Some(self.bindings(statement));
+      }
+    }
+
+    None
+  }
+
+  /// Rewrites the subterm of `subject` at `position` with the rule of index `rule`, whose match is in the
+  /// substitution, and reduces the result with the equations. The nodes above the position are rebuilt.
+  pub(crate) fn rewrite_with_match(&mut self, subject: DagNodePtr, position: &[usize], rule: usize) -> DagNodePtr {
+    self.rule_count += 1;
+    if let Some(profile) = &mut self.profile {
+      profile.record(StatementKind::Rule, rule);
+    }
+
+    let rhs    = self.module.rules[rule].construct_rhs(&mut self.substitution);
+    let result = replace_unchecked(subject, position, rhs);
+    self.reduce(result)
+  }
+
+  /// Takes one rewrite step in `term` with the rule or equation labeled `label` at `position`, without reducing the
+  /// result. Rules with the label are tried before equations, each in the order of the module. Only the nodes above
+  /// the position are rebuilt; the rest of the result shares the nodes of `term`. See the module documentation.
+  pub fn rewrite_at(&mut self, term: DagNodePtr, position: &[usize], label: &str)
+      -> Result<DagNodePtr, RewriteAtError>
+  {
+    let target = subterm_at(term, position).ok_or_else(|| RewriteAtError::InvalidPosition(position.to_vec()))?;
+    let module = self.module;
+    let labeled = |statement: &&PreEquation| statement.name.as_ref().is_some_and(|name| name.as_ref() == label);
+
+    let mut found = false;
+    let rules     = module.rules.iter().enumerate().map(|(index, rule)| (StatementKind::Rule, index, rule));
+    let equations = module.equations
+                          .iter()
+                          .enumerate()
+                          .map(|(index, equation)| (StatementKind::Equation, index, equation));
+    for (kind, index, statement) in rules.chain(equations).filter(|(_, _, statement)| labeled(statement)) {
+      found = true;
+      if !statement.match_lhs(target, &mut self.substitution)
+          || self.conditions_hold(statement, kind, index) != Ok(true)
+      {
+        continue;
+      }
+
+      match kind {
+        StatementKind::Rule     => self.rule_count += 1,
+        StatementKind::Equation => self.equation_count += 1,
+      }
+      if let Some(profile) = &mut self.profile {
+        profile.record(kind, index);
+      }
+      return Ok(replace_unchecked(term, position, statement.construct_rhs(&mut self.substitution)));
+    }
+
+    match found {
+      true  => Err(RewriteAtError::NoMatch { label: IString::from(label), position: position.to_vec() }),
+      false => Err(RewriteAtError::UnknownLabel(IString::from(label))),
+    }
+  }
+
+  // endregion Rules
+}
+
+/// The instance of `term` under `bindings`, which bind every variable of `term`, in canonical form modulo the
+/// structural axioms of its symbols.
+fn instantiate(term: &dyn Term, bindings: &Substitution, module: &Module) -> DagNodePtr {
+  if let Some(variable) = term.as_any().downcast_ref::<VariableTerm>() {
+    return bindings.get(variable.index()).expect("a condition uses an unbound variable");
+  }
+
+  let args = term.iter_args().map(|arg| instantiate(arg, bindings, module)).collect();
+  canonical_node(term.symbol(), args, module)
+}
+
+/// A copy of the DAG `node` sharing none of its free nodes, the only nodes graph rewriting overwrites. Sharing within
+/// the DAG is kept, as are the sorts and rewriting flags of the nodes.
+fn copy_graph(node: DagNodePtr, copies: &mut HashMap<*const u8, DagNodePtr>) -> DagNodePtr {
+  let node_ref = unsafe{ &*node };
+  if node_ref.core().theory() != DagNodeTheory::Free {
+    return node;
+  }
+  if let Some(&copy) = copies.get(&(node as *const u8)) {
+    return copy;
+  }
+
+  let mut args  = node_ref.iter_args().map(|arg| copy_graph(arg, copies)).collect::<Vec<_>>();
+  let copy      = FreeDagNode::with_args(node_ref.symbol(), &mut args);
+  let copy_core = unsafe{ &mut *copy }.core_mut();
+  copy_core.set_sort_index(node_ref.core().sort_index());
+  copy_core.insert_flags(node_ref.core().flags() & DagNodeFlag::RewritingFlags);
+  copies.insert(node as *const u8, copy);
+
+  copy
+}
+
+#[inline(always)]
+fn leq(sort: SortPtr, other: SortPtr) -> bool {
+  core::ptr::eq(sort, other) || unsafe{ &*sort }.leq(unsafe{ &*other })
+}
+
+/// The subterm of `node` at `position`, or `None` if there is none.
+pub fn subterm_at(node: DagNodePtr, position: &[usize]) -> Option<DagNodePtr> {
+  position.iter().try_fold(node, |node, &index| unsafe{ &*node }.iter_args().nth(index))
+}
+
+/// A copy of `term` in which the subterm at `position` is `replacement`, or `term` itself if the subterm already is
+/// `replacement`. Only the nodes above the position are copied; the rest are shared with `term`.
+pub fn replace_subterm(term: DagNodePtr, position: &[usize], replacement: DagNodePtr)
+    -> Result<DagNodePtr, InvalidPosition>
+{
+  let subterm = subterm_at(term, position).ok_or_else(|| InvalidPosition(position.to_vec()))?;
+  match core::ptr::addr_eq(subterm, replacement) {
+    true  => Ok(term),
+    false => Ok(replace_unchecked(term, position, replacement)),
+  }
+}
+
+/// A copy of `node` in which the subterm at `position`, which must exist, is `replacement`. Only the nodes above the
+/// position are copied. The copies keep the arguments of the nodes they copy in place, even under symbols with
+/// axioms, as positions address the term as it is; they are put in canonical form when they are next reduced.
+fn replace_unchecked(node: DagNodePtr, position: &[usize], replacement: DagNodePtr) -> DagNodePtr {
+  let Some((&index, rest)) = position.split_first() else {
+    return replacement;
+  };
+
+  let node_ref = unsafe{ &*node };
+  let mut args = node_ref.iter_args()
+                         .enumerate()
+                         .map(|(i, arg)| if i == index { replace_unchecked(arg, rest, replacement) } else { arg })
+                         .collect();
+  FreeDagNode::with_args(node_ref.symbol(), &mut args)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      dag_node::{DagNode, DagNodePtr},
+      free_theory::{FreeDagNode, FreeTerm},
+      symbol::{Symbol, SymbolPtr},
+      variable_theory::VariableTerm,
+    },
+    core::{
+      config::EngineConfig,
+      coverage::StatementKind,
+      module::Module,
+      parser::{parse_module, parse_term},
+      pre_equation::{condition::Condition, PreEquation, PreEquationKind, StatementError},
+      proof_trace::dag_to_string,
+      rewriting_context::{
+        replace_subterm, InvalidPosition, RewriteAtError, RewriteLimitReached, RewritingContext, UnknownCheckpoint
+      },
+      sort::{sort_spec::SortSpec, SortPtr},
+      RootContainer
+    }
+  };
+
+  #[test]
+  fn reduce_with_indexed_equations() {
+    // Peano addition: plus(0, Y) = Y; plus(s(X), Y) = s(plus(X, Y)), plus a batch of equations for other constants
+    // that the index should rule out.
+    let mut plus  = Symbol::new(IString::from("plus"), Arity::Value(2));
+    let mut s     = Symbol::new(IString::from("s"), Arity::Value(1));
+    let mut zero  = Symbol::new(IString::from("0"), Arity::Value(0));
+    let mut x     = Symbol::new_variable(IString::from("X"));
+    let mut y     = Symbol::new_variable(IString::from("Y"));
+    let mut other = (0..10)
+        .map(|i| Symbol::new(IString::from(format!("c{}", i)), Arity::Value(0)))
+        .collect::<Vec<_>>();
+    let (plus, s, zero, x, y): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut plus, &mut s, &mut zero, &mut x, &mut y);
+
+    let mut module = Module::default();
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(plus, vec![FreeTerm::with_args(zero, vec![]), Box::new(VariableTerm::new(y))]),
+      PreEquationKind::Equation { rhs_term: Box::new(VariableTerm::new(y)) },
+      vec![]
+    ));
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(
+        plus,
+        vec![FreeTerm::with_args(s, vec![Box::new(VariableTerm::new(x))]), Box::new(VariableTerm::new(y))]
+      ),
+      PreEquationKind::Equation {
+        rhs_term: FreeTerm::with_args(
+          s,
+          vec![FreeTerm::with_args(plus, vec![Box::new(VariableTerm::new(x)), Box::new(VariableTerm::new(y))])]
+        )
+      },
+      vec![]
+    ));
+    for c in other.iter_mut() {
+      let c: SymbolPtr = c;
+      module.equations.push(PreEquation::new(
+        None,
+        FreeTerm::with_args(plus, vec![FreeTerm::with_args(c, vec![]), Box::new(VariableTerm::new(y))]),
+        PreEquationKind::Equation { rhs_term: FreeTerm::with_args(c, vec![]) },
+        vec![]
+      ));
+    }
+    module.compile_statements().unwrap();
+
+    // plus(s(s(0)), s(0))
+    let zero_node = FreeDagNode::new(zero);
+    let one       = FreeDagNode::new(s);
+    unsafe{ &mut *one }.insert_child(zero_node).unwrap();
+    let two       = FreeDagNode::new(s);
+    unsafe{ &mut *two }.insert_child(one).unwrap();
+    let subject   = FreeDagNode::with_args(plus, &mut vec![two, one]);
+    let _root     = RootContainer::new(subject);
+
+    let mut context = RewritingContext::new(&module);
+    let mut result  = context.reduce(subject);
+    let _result_root = RootContainer::new(result);
+
+    // s(s(s(0)))
+    for _ in 0..3 {
+      let node: &dyn DagNode = unsafe{ &*result };
+      assert!(std::ptr::addr_eq(node.symbol(), s));
+      result = node.iter_args().next().unwrap();
+    }
+    assert!(std::ptr::addr_eq(unsafe{ &*result }.symbol(), zero));
+    assert_eq!(context.equation_count(), 3);
+
+    // Every lookup rules out all but the one equation that applies.
+    let statistics = context.index_statistics();
+    assert_eq!(statistics.hits, 3);
+    assert_eq!(statistics.misses, 0);
+    assert_eq!(statistics.filtered, 3 * 11);
+  }
+
+  #[test]
+  fn bounded_reduction_stops_nonterminating_equations() {
+    // f(X) = f(g(X))
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(1));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let (f, g, a, x): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut g, &mut a, &mut x);
+
+    let mut module = Module::default();
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(f, vec![Box::new(VariableTerm::new(x))]),
+      PreEquationKind::Equation {
+        rhs_term: FreeTerm::with_args(f, vec![FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(x))])])
+      },
+      vec![]
+    ));
+    module.compile_statements().unwrap();
+
+    let subject = FreeDagNode::new(f);
+    unsafe{ &mut *subject }.insert_child(FreeDagNode::new(a)).unwrap();
+    let _root   = RootContainer::new(subject);
+
+    let mut context = RewritingContext::new(&module);
+    assert_eq!(context.reduce_bounded(subject, 100), Err(RewriteLimitReached));
+    assert_eq!(context.equation_count(), 100);
+
+    // The limit is relative to the rewrites already performed.
+    assert_eq!(context.reduce_bounded(subject, 10), Err(RewriteLimitReached));
+    assert_eq!(context.equation_count(), 110);
+  }
+
+  #[test]
+  fn rollback_restores_term_and_counters() {
+    // f(X) = g(X); a = b; g(X) => f(X)
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(1));
+    let mut g = Symbol::new(IString::from("g"), Arity::Value(1));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+    let (f, g, a, b, x): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut f, &mut g, &mut a, &mut b, &mut x);
+
+    let mut module = Module::default();
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(f, vec![Box::new(VariableTerm::new(x))]),
+      PreEquationKind::Equation { rhs_term: FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(x))]) },
+      vec![]
+    ));
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(a, vec![]),
+      PreEquationKind::Equation { rhs_term: FreeTerm::with_args(b, vec![]) },
+      vec![]
+    ));
+    module.rules.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(g, vec![Box::new(VariableTerm::new(x))]),
+      PreEquationKind::Rule { rhs_term: FreeTerm::with_args(f, vec![Box::new(VariableTerm::new(x))]) },
+      vec![]
+    ));
+    module.compile_statements().unwrap();
+
+    let mut context = RewritingContext::new(&module);
+    assert_eq!(context.reduce_term(), None);
+
+    // f(a)
+    let subject = FreeDagNode::new(f);
+    unsafe{ &mut *subject }.insert_child(FreeDagNode::new(a)).unwrap();
+    context.set_term(subject);
+    let start = context.checkpoint();
+
+    // g(b)
+    let normal_form = context.reduce_term().unwrap();
+    assert!(std::ptr::addr_eq(unsafe{ &*normal_form }.symbol(), g));
+    assert_eq!(context.equation_count(), 2);
+    let reduced = context.checkpoint();
+
+    // Try an alternative and undo it.
+    let alternative = FreeDagNode::new(a);
+    context.set_term(alternative);
+    context.reduce_term();
+    assert_eq!(context.equation_count(), 3);
+    let redex = context.rule_redexes(normal_form).remove(0);
+    context.apply_rule(normal_form, &redex);
+    assert_eq!(context.rule_count(), 1);
+
+    context.rollback(reduced).unwrap();
+    assert!(std::ptr::addr_eq(context.term().unwrap(), normal_form));
+    assert_eq!(context.equation_count(), 2);
+    assert_eq!(context.rule_count(), 0);
+
+    // The saved subject is unchanged by the reduction and can be reduced again.
+    context.rollback(start).unwrap();
+    let term = context.term().unwrap();
+    assert!(std::ptr::addr_eq(term, subject));
+    assert!(std::ptr::addr_eq(unsafe{ &*term }.symbol(), f));
+    assert!(!unsafe{ &*term }.is_reduced());
+    assert_eq!(context.equation_count(), 0);
+    assert_eq!(context.index_statistics().hits, 0);
+    assert_eq!(context.checkpoint_depth(), 1);
+
+    // Rolling back to `start` discarded `reduced`.
+    assert_eq!(context.rollback(reduced), Err(UnknownCheckpoint(reduced)));
+    context.discard(start).unwrap();
+    assert_eq!(context.checkpoint_depth(), 0);
+    assert_eq!(context.rollback(start), Err(UnknownCheckpoint(start)));
+  }
+
+  #[test]
+  fn traced_reduction_records_each_rewrite() {
+    // plus(0, Y) = Y; plus(s(X), Y) = s(plus(X, Y))
+    let mut plus = Symbol::new(IString::from("plus"), Arity::Value(2));
+    let mut s    = Symbol::new(IString::from("s"), Arity::Value(1));
+    let mut zero = Symbol::new(IString::from("0"), Arity::Value(0));
+    let mut x    = Symbol::new_variable(IString::from("X"));
+    let mut y    = Symbol::new_variable(IString::from("Y"));
+    let (plus, s, zero, x, y): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut plus, &mut s, &mut zero, &mut x, &mut y);
+
+    let mut module = Module::default();
+    module.equations.push(PreEquation::new(
+      Some(IString::from("plus-zero")),
+      FreeTerm::with_args(plus, vec![FreeTerm::with_args(zero, vec![]), Box::new(VariableTerm::new(y))]),
+      PreEquationKind::Equation { rhs_term: Box::new(VariableTerm::new(y)) },
+      vec![]
+    ));
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(
+        plus,
+        vec![FreeTerm::with_args(s, vec![Box::new(VariableTerm::new(x))]), Box::new(VariableTerm::new(y))]
+      ),
+      PreEquationKind::Equation {
+        rhs_term: FreeTerm::with_args(
+          s,
+          vec![FreeTerm::with_args(plus, vec![Box::new(VariableTerm::new(x)), Box::new(VariableTerm::new(y))])]
+        )
+      },
+      vec![]
+    ));
+    module.compile_statements().unwrap();
+
+    // plus(s(0), 0)
+    let one = FreeDagNode::new(s);
+    unsafe{ &mut *one }.insert_child(FreeDagNode::new(zero)).unwrap();
+    let subject = FreeDagNode::with_args(plus, &mut vec![one, FreeDagNode::new(zero)]);
+    let _root   = RootContainer::new(subject);
+
+    let mut context      = RewritingContext::new(&module);
+    let (result, trace)  = context.reduce_traced(subject);
+    let _result_root     = RootContainer::new(result);
+
+    assert_eq!(trace.subject, "plus(s(0), 0)");
+    assert_eq!(trace.result, "s(0)");
+    assert_eq!(trace.len(), 2);
+
+    let first = &trace.steps[0];
+    assert_eq!(first.statement, 1);
+    assert_eq!(first.label, None);
+    assert!(first.position.is_empty());
+    assert_eq!(
+      first.substitution,
+      vec![(IString::from("X"), "0".to_string()), (IString::from("Y"), "0".to_string())]
+    );
+    assert_eq!(first.redex, "plus(s(0), 0)");
+    assert_eq!(first.contractum, "s(plus(0, 0))");
+
+    let second = &trace.steps[1];
+    assert_eq!(second.statement, 0);
+    assert_eq!(second.label, Some(IString::from("plus-zero")));
+    assert_eq!(second.position, vec![0]);
+    assert_eq!(second.redex, "plus(0, 0)");
+    assert_eq!(second.contractum, "0");
+
+    let json = trace.to_json();
+    assert!(json.contains("\"label\": \"plus-zero\""));
+    assert!(json.contains("\"position\": [0]"));
+    assert!(json.contains("\"substitution\": {\"X\": \"0\", \"Y\": \"0\"}"));
+  }
+
+  #[test]
+  fn graph_rewriting_rewrites_shared_subterms_once() {
+    // c = d
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut c = Symbol::new(IString::from("c"), Arity::Value(0));
+    let mut d = Symbol::new(IString::from("d"), Arity::Value(0));
+    let (f, c, d): (SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut c, &mut d);
+
+    let mut module = Module::default();
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(c, vec![]),
+      PreEquationKind::Equation { rhs_term: FreeTerm::with_args(d, vec![]) },
+      vec![]
+    ));
+    module.compile_statements().unwrap();
+
+    // f(c, c) with c shared
+    let shared_subject = || {
+      let shared  = FreeDagNode::new(c);
+      let subject = FreeDagNode::with_args(f, &mut vec![shared, shared]);
+      (subject, shared, RootContainer::new(subject))
+    };
+
+    // Tree semantics: each occurrence is rewritten, and the subject is unchanged.
+    let (subject, shared, _root) = shared_subject();
+    let mut context  = RewritingContext::new(&module);
+    let result       = context.reduce(subject);
+    let _result_root = RootContainer::new(result);
+    assert_eq!(context.equation_count(), 2);
+    assert!(!std::ptr::addr_eq(result, subject));
+    assert!(std::ptr::addr_eq(unsafe{ &*shared }.symbol(), c));
+
+    // Graph semantics: the shared node is rewritten once, in place.
+    module.graph_rewriting = true;
+    let (subject, shared, _root) = shared_subject();
+    let mut context = RewritingContext::new(&module);
+    let result      = context.reduce(subject);
+    assert_eq!(context.equation_count(), 1);
+    assert!(std::ptr::addr_eq(result, subject));
+    assert!(std::ptr::addr_eq(unsafe{ &*shared }.symbol(), d));
+    for arg in unsafe{ &*result }.iter_args() {
+      assert!(std::ptr::addr_eq(arg, shared));
+    }
+  }
+
+  #[test]
+  fn graph_rewriting_rolls_back_to_the_unreduced_term() {
+    // c = d
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(1));
+    let mut c = Symbol::new(IString::from("c"), Arity::Value(0));
+    let mut d = Symbol::new(IString::from("d"), Arity::Value(0));
+    let (f, c, d): (SymbolPtr, SymbolPtr, SymbolPtr) = (&mut f, &mut c, &mut d);
+
+    let mut module = Module::default();
+    module.equations.push(PreEquation::new(
+      None,
+      FreeTerm::with_args(c, vec![]),
+      PreEquationKind::Equation { rhs_term: FreeTerm::with_args(d, vec![]) },
+      vec![]
+    ));
+    module.compile_statements().unwrap();
+    module.graph_rewriting = true;
+
+    // f(c)
+    let mut context = RewritingContext::new(&module);
+    context.set_term(FreeDagNode::with_args(f, &mut vec![FreeDagNode::new(c)]));
+    let start = context.checkpoint();
+
+    // Reducing the current term overwrites it, but not the checkpoint's copy, however often it is restored.
+    for _ in 0..2 {
+      assert_eq!(dag_to_string(context.reduce_term().unwrap()), "f(d)");
+      context.rollback(start).unwrap();
+      assert_eq!(dag_to_string(context.term().unwrap()), "f(c)");
+      assert!(!unsafe{ &*context.term().unwrap() }.is_reduced());
+    }
+  }
+
+  #[test]
+  fn graph_rewriting_forwards_nodes_with_normal_forms_of_another_theory() {
+    let mut module = parse_module("
+      mod FORWARD is
+        sort Elt .
+        op id : Elt -> Elt .
+        op pair : Elt Elt -> Elt .
+        vars X Y : Elt .
+        eq id(X) = X .
+      endm
+    ").unwrap();
+    module.graph_rewriting = true;
+
+    // pair(id(Y), id(Y)) with id(Y) shared. Its normal form, the variable Y, can't overwrite it.
+    let subject = parse_term(&module, "pair(id(Y), id(Y))").unwrap().term_to_dag(false);
+    let _root   = RootContainer::new(subject);
+    let shared  = unsafe{ &*subject }.iter_args().next().unwrap();
+    assert!(std::ptr::addr_eq(shared, unsafe{ &*subject }.iter_args().nth(1).unwrap()));
+
+    let mut context = RewritingContext::new(&module);
+    let result      = context.reduce(subject);
+    assert_eq!(dag_to_string(result), "pair(Y, Y)");
+    assert_eq!(context.equation_count(), 1);
+    assert_eq!(dag_to_string(shared), "id(Y)");
+    assert!(!unsafe{ &*shared }.is_reduced());
+  }
+
+  #[test]
+  fn normal_forms_have_sorts() {
+    let module = parse_module("
+      mod SORTS is
+        sorts Zero NzNat Nat .
+        subsorts Zero NzNat < Nat .
+        op 0 : -> Zero [ctor] .
+        op s : Nat -> NzNat [ctor] .
+        op pred : NzNat -> Nat .
+        eq pred(s(N:Nat)) = N:Nat .
+      endm
+    ").unwrap();
+    let sort_name = |sort: Option<SortPtr>| sort.map(|sort| unsafe{ &*sort }.name.clone());
+
+    // The sorts of statement terms are computed when the module is compiled.
+    let rhs = match &module.equations[0].kind {
+      PreEquationKind::Equation { rhs_term } => rhs_term,
+      _ => unreachable!()
+    };
+    assert_eq!(sort_name(module.equations[0].lhs_term.sort()), Some(IString::from("Nat")));
+    assert_eq!(sort_name(rhs.sort()), Some(IString::from("Nat")));
+
+    let term = parse_term(&module, "pred(s(s(0)))").unwrap();
+    assert_eq!(sort_name(term.compute_sort()), Some(IString::from("Nat")));
+    let subject = term.term_to_dag(true);
+    assert_eq!(sort_name(unsafe{ &*subject }.get_sort()), Some(IString::from("Nat")));
+
+    let mut context = RewritingContext::new(&module);
+    let result      = context.reduce(subject);
+    assert_eq!(sort_name(unsafe{ &*result }.get_sort()), Some(IString::from("NzNat")));
+
+    // `pred(0)` is ill-sorted and stays so.
+    let result = context.reduce(parse_term(&module, "pred(0)").unwrap().term_to_dag(false));
+    assert!(unsafe{ &*result }.is_error_sorted());
+  }
+
+  #[test]
+  fn sorts_cached_before_reduction_are_recomputed() {
+    let mut module = parse_module("
+      mod STALE is
+        sorts Zero NzNat Nat .
+        subsorts Zero NzNat < Nat .
+        op 0 : -> Zero [ctor] .
+        op s : Nat -> NzNat [ctor] .
+        op pred : NzNat -> Nat .
+        op double : Nat -> Nat .
+        op double : Zero -> Zero .
+        eq pred(s(N:Nat)) = N:Nat .
+      endm
+    ").unwrap();
+    module.graph_rewriting = true;
+    let sort_name = |sort: Option<SortPtr>| sort.map(|sort| unsafe{ &*sort }.name.clone());
+
+    // Before reduction, `double(pred(s(0)))` only has sort `Nat`.
+    let subject = parse_term(&module, "double(pred(s(0)))").unwrap().term_to_dag(false);
+    let _root   = RootContainer::new(subject);
+    assert_eq!(sort_name(unsafe{ &mut *subject }.compute_sort()), Some(IString::from("Nat")));
+
+    // The argument is overwritten with `0` in place, so the subject is its own normal form and must forget its sort.
+    let mut context = RewritingContext::new(&module);
+    let result      = context.reduce(subject);
+    assert!(std::ptr::addr_eq(result, subject));
+    assert_eq!(sort_name(unsafe{ &*result }.get_sort()), Some(IString::from("Zero")));
+  }
+
+  #[test]
+  fn redexes_are_listed_without_rewriting() {
+    let module  = Module::prelude_module("NAT").unwrap();
+    let subject = parse_term(&module, "plus(pred(s(0)), times(0, plus(0, 0)))").unwrap().term_to_dag(false);
+    let _root   = RootContainer::new(subject);
+
+    let mut context = RewritingContext::new(&module);
+    let redexes: Vec<_> = context.redexes(subject).collect();
+    let bindings = |index: usize| -> Vec<String> {
+      redexes[index].2.iter().map(|(name, value)| format!("{} = {}", name, dag_to_string(*value))).collect()
+    };
+
+    assert_eq!(redexes.len(), 3);
+    assert_eq!(redexes[0].0, vec![0]);
+    assert!(std::ptr::eq(redexes[0].1, &module.equations[4]));
+    assert_eq!(bindings(0), vec!["N = 0"]);
+    assert_eq!(redexes[1].0, vec![1]);
+    assert!(std::ptr::eq(redexes[1].1, &module.equations[2]));
+    assert_eq!(bindings(1), vec!["M = plus(0, 0)"]);
+    assert_eq!(redexes[2].0, vec![1, 1]);
+    assert!(std::ptr::eq(redexes[2].1, &module.equations[0]));
+    assert_eq!(bindings(2), vec!["M = 0"]);
+
+    // Nothing was rewritten or counted.
+    assert_eq!(dag_to_string(subject), "plus(pred(s(0)), times(0, plus(0, 0)))");
+    assert_eq!(context.equation_count(), 0);
+    assert_eq!(context.index_statistics().candidates, 0);
+
+    // A normal form has no redexes.
+    let normal_form = context.reduce(subject);
+    assert_eq!(context.redexes(normal_form).count(), 0);
+  }
+
+  #[test]
+  fn evaluation_strategies_control_argument_reduction() {
+    let module = parse_module("
+      mod LAZY is
+        sorts Bool Nat .
+        ops true false : -> Bool .
+        op not : Bool -> Bool .
+        op 0 : -> Nat .
+        op s : Nat -> Nat .
+        op loop : -> Nat .
+        op if : Bool Nat Nat -> Nat [strat (1 0)] .
+        op first : Nat Nat -> Nat [strat (1 0 2)] .
+        op delay : Nat -> Nat [strat (0)] .
+        vars X Y : Nat .
+        eq not(false) = true .
+        eq loop = s(loop) .
+        eq if(true, X, Y) = X .
+        eq if(false, X, Y) = Y .
+        eq first(0, Y) = 0 .
+      endm
+    ").unwrap();
+    let mut context = RewritingContext::new(&module);
+    let mut reduce  = |source: &str| {
+      let subject = parse_term(&module, source).unwrap().term_to_dag(false);
+      context.reduce_bounded(subject, 100).map(dag_to_string)
+    };
+
+    // The branches of `if` are only reduced once the condition has selected one.
+    assert_eq!(reduce("if(not(false), s(0), loop)"), Ok("s(0)".to_string()));
+    assert_eq!(reduce("if(true, loop, 0)"), Err(RewriteLimitReached));
+    // The equation for `first` is tried before the second argument is reduced.
+    assert_eq!(reduce("first(0, loop)"), Ok("0".to_string()));
+    assert_eq!(reduce("first(s(0), if(true, 0, loop))"), Ok("first(s(0), 0)".to_string()));
+    // The argument of a lazy symbol is left alone.
+    assert_eq!(reduce("delay(if(true, 0, loop))"), Ok("delay(if(true, 0, loop))".to_string()));
+
+    let error = parse_module("mod M is sort A . op f : A -> A [strat (2 0)] . endm").unwrap_err();
+    assert!(error.to_string().contains("argument 2"));
+  }
+
+  #[test]
+  fn demanded_arguments_are_reduced_on_demand() {
+    let module = parse_module("
+      mod ON-DEMAND is
+        sorts Bool Nat .
+        ops true false : -> Bool .
+        op not : Bool -> Bool .
+        op 0 : -> Nat .
+        op s : Nat -> Nat .
+        op loop : -> Nat .
+        op cond : Bool Nat Nat -> Nat [strat (0)] .
+        op delay : Nat -> Nat [strat (0)] .
+        op force : Nat -> Nat .
+        vars X Y : Nat .
+        eq not(false) = true .
+        eq loop = s(loop) .
+        eq cond(true, X, Y) = X .
+        eq force(delay(s(X))) = X .
+      endm
+    ").unwrap();
+    let mut context = RewritingContext::new(&module);
+    let mut reduce  = |source: &str| {
+      let subject = parse_term(&module, source).unwrap().term_to_dag(false);
+      context.reduce_bounded(subject, 100).map(dag_to_string)
+    };
+
+    // The condition is reduced to decide the match, but the branch that isn't taken is not.
+    assert_eq!(reduce("cond(not(false), s(0), loop)"), Ok("s(0)".to_string()));
+    // No equation applies, and reduction stops once the condition fails to match.
+    assert_eq!(reduce("cond(false, 0, loop)"), Ok("cond(false, 0, loop)".to_string()));
+    // The equation for `force` demands the argument of the lazy `delay`, which is left reduced if the match fails.
+    assert_eq!(reduce("force(delay(cond(true, s(0), loop)))"), Ok("0".to_string()));
+    assert_eq!(reduce("force(delay(cond(true, 0, loop)))"), Ok("force(delay(0))".to_string()));
+  }
+
+  #[test]
+  fn equations_apply_inside_larger_sums() {
+    let module = parse_module("
+      mod SUMS is
+        sort Nat .
+        ops 0 a b : -> Nat .
+        op plus : Nat Nat -> Nat [assoc] .
+        op times : Nat Nat -> Nat [assoc comm] .
+        var X : Nat .
+        eq plus(X, 0) = X .
+        eq times(0, X) = 0 .
+      endm
+    ").unwrap();
+    let mut context = RewritingContext::new(&module);
+    let mut reduce  = |source: &str| {
+      let subject = parse_term(&module, source).unwrap().term_to_dag(false);
+      dag_to_string(context.reduce(subject))
+    };
+
+    // The zeros are matched inside the flattened arguments, in order for an associative symbol.
+    assert_eq!(reduce("plus(a, plus(0, b))"), "plus(a, b)");
+    assert_eq!(reduce("plus(plus(a, 0), plus(b, 0))"), "plus(a, b)");
+    assert_eq!(reduce("plus(0, a)"), "plus(0, a)");
+    // In any order for an associative and commutative one
+    assert_eq!(reduce("times(a, times(b, 0))"), "0");
+
+    let error = parse_module("mod M is sort A . op f : A -> A [assoc] . endm").unwrap_err();
+    assert!(error.to_string().contains("binary"));
+  }
+
+  #[test]
+  fn steps_are_taken_at_positions() {
+    let module = parse_module("
+      mod STEPS is
+        sort Nat .
+        ops 0 1 : -> Nat .
+        op s : Nat -> Nat .
+        op f : Nat Nat -> Nat .
+        var X : Nat .
+        eq [zero] : s(0) = 1 .
+        rl [peel] : s(X) => X .
+      endm
+    ").unwrap();
+    let mut context = RewritingContext::new(&module);
+    let term = parse_term(&module, "f(s(0), s(s(0)))").unwrap().term_to_dag(false);
+    let args = |node: DagNodePtr| unsafe{ &*node }.iter_args().collect::<Vec<_>>();
+
+    let step = context.rewrite_at(term, &[1], "peel").unwrap();
+    assert_eq!(dag_to_string(step), "f(s(0), s(0))");
+    // The result isn't reduced, and the argument off the path is shared.
+    assert!(std::ptr::addr_eq(args(step)[0], args(term)[0]));
+    assert_eq!(dag_to_string(term), "f(s(0), s(s(0)))");
+
+    let step = context.rewrite_at(step, &[1], "zero").unwrap();
+    assert_eq!(dag_to_string(step), "f(s(0), 1)");
+    assert_eq!((context.rule_count(), context.equation_count()), (1, 1));
+
+    assert_eq!(context.rewrite_at(term, &[2], "peel"), Err(RewriteAtError::InvalidPosition(vec![2])));
+    assert_eq!(context.rewrite_at(term, &[], "skip"), Err(RewriteAtError::UnknownLabel("skip".into())));
+    assert_eq!(
+      context.rewrite_at(term, &[], "zero"),
+      Err(RewriteAtError::NoMatch { label: "zero".into(), position: vec![] })
+    );
+
+    let one      = parse_term(&module, "1").unwrap().term_to_dag(false);
+    let replaced = replace_subterm(term, &[1, 0], one).unwrap();
+    assert_eq!(dag_to_string(replaced), "f(s(0), s(1))");
+    assert!(std::ptr::addr_eq(args(replaced)[0], args(term)[0]));
+    assert!(std::ptr::addr_eq(replace_subterm(term, &[0], args(term)[0]).unwrap(), term));
+    assert_eq!(replace_subterm(term, &[0, 0, 0], term), Err(InvalidPosition(vec![0, 0, 0])));
+  }
+
+  #[test]
+  fn conditions_run_on_fuel() {
+    let mut module = parse_module("
+      mod CONDITIONS is
+        sorts Zero Nat .
+        subsort Zero < Nat .
+        op 0 : -> Zero .
+        ops a b : -> Nat .
+        op s : Nat -> Nat .
+        ops f g h loop : Nat -> Nat .
+        var X : Nat .
+        eq loop(X) = loop(s(X)) .
+      endm
+    ").unwrap();
+    let conditional = |module: &Module, label: &str, lhs: &str, rhs: &str, condition: Condition| {
+      let term = |text: &str| parse_term(module, text).unwrap();
+      let kind = PreEquationKind::Equation { rhs_term: term(rhs) };
+      PreEquation::new(Some(IString::from(label)), term(lhs), kind, vec![Box::new(condition)])
+    };
+    let zero = module.arena.sorts.get_by_name(&"Zero".into()).unwrap();
+
+    let condition = Condition::Equality {
+      lhs_term: parse_term(&module, "X").unwrap(),
+      rhs_term: parse_term(&module, "0").unwrap()
+    };
+    let equation = conditional(&module, "zero", "f(X)", "a", condition);
+    module.add_statement(equation).unwrap();
+    let condition = Condition::SortMembership {
+      lhs_term: parse_term(&module, "X").unwrap(),
+      sort    : Box::new(SortSpec::Sort(zero))
+    };
+    let equation = conditional(&module, "sorted", "g(X)", "b", condition);
+    module.add_statement(equation).unwrap();
+    let condition = Condition::Equality {
+      lhs_term: parse_term(&module, "loop(X)").unwrap(),
+      rhs_term: parse_term(&module, "a").unwrap()
+    };
+    let equation = conditional(&module, "diverge", "h(X)", "b", condition);
+    module.add_statement(equation).unwrap();
+
+    let mut context = RewritingContext::with_config(&module, &EngineConfig::default().with_condition_fuel(50));
+    let mut reduce  = |text: &str| dag_to_string(context.reduce(parse_term(&module, text).unwrap().term_to_dag(false)));
+    assert_eq!(reduce("f(0)"), "a");
+    assert_eq!(reduce("f(s(0))"), "f(s(0))");
+    assert_eq!(reduce("g(0)"), "b");
+    assert_eq!(reduce("g(a)"), "g(a)");
+    assert_eq!(reduce("h(0)"), "h(0)");
+
+    let timeouts = context.condition_timeouts();
+    assert_eq!(timeouts.len(), 1);
+    assert_eq!((timeouts[0].kind, timeouts[0].statement), (StatementKind::Equation, 3));
+    assert_eq!(timeouts[0].to_string(), "condition 0 of eq 3 [diverge] ran out of fuel with X = 0");
+  }
+
+  #[test]
+  fn redexes_need_their_conditions_to_hold() {
+    let mut module = parse_module("
+      mod CONDITIONS is
+        sort Nat .
+        ops 0 a : -> Nat .
+        op s : Nat -> Nat .
+        op f : Nat -> Nat .
+        var X : Nat .
+      endm
+    ").unwrap();
+    let term      = |module: &Module, text: &str| parse_term(module, text).unwrap();
+    // f(X) = a if X = 0
+    let condition = Condition::Equality { lhs_term: term(&module, "X"), rhs_term: term(&module, "0") };
+    let kind      = PreEquationKind::Equation { rhs_term: term(&module, "a") };
+    module.add_statement(PreEquation::new(None, term(&module, "f(X)"), kind, vec![Box::new(condition)])).unwrap();
+
+    // f(X) = a if s(X) := X is rejected.
+    let condition = Condition::Match { lhs_term: term(&module, "s(X)"), rhs_term: term(&module, "X") };
+    let kind      = PreEquationKind::Equation { rhs_term: term(&module, "a") };
+    let equation  = PreEquation::new(None, term(&module, "f(X)"), kind, vec![Box::new(condition)]);
+    assert!(matches!(module.add_statement(equation), Err(StatementError::UnsupportedCondition { .. })));
+
+    let mut context = RewritingContext::new(&module);
+    let subject     = term(&module, "f(f(s(0)))").term_to_dag(false);
+    let _root       = RootContainer::new(subject);
+    // Both occurrences of f match, but neither has 0 for its argument.
+    assert_eq!(context.redexes(subject).count(), 0);
+
+    let subject = term(&module, "f(f(0))").term_to_dag(false);
+    let _root   = RootContainer::new(subject);
+    let redexes = context.redexes(subject).map(|(position, ..)| position).collect::<Vec<_>>();
+    assert_eq!(redexes, vec![vec![0]]);
+  }
+
+  #[test]
+  fn built_nodes_are_canonical_modulo_axioms() {
+    let mut module = parse_module("
+      mod SETS is
+        sorts Elt Set .
+        subsort Elt < Set .
+        ops a b : -> Elt .
+        op empty : -> Set .
+        op u : Set Set -> Set [comm id: empty] .
+        ops g h : Set -> Set .
+        var S : Set .
+        eq h(a) = empty .
+      endm
+    ").unwrap();
+    let term      = |module: &Module, text: &str| parse_term(module, text).unwrap();
+    // g(S) = S if u(S, b) = u(b, a)
+    let condition = Condition::Equality { lhs_term: term(&module, "u(S, b)"), rhs_term: term(&module, "u(b, a)") };
+    let kind      = PreEquationKind::Equation { rhs_term: term(&module, "S") };
+    module.add_statement(PreEquation::new(None, term(&module, "g(S)"), kind, vec![Box::new(condition)])).unwrap();
+    let mut context = RewritingContext::new(&module);
+
+    // The instances of the condition's sides are put in the same argument order.
+    let subject = term(&module, "g(a)").term_to_dag(false);
+    let _root   = RootContainer::new(subject);
+    assert_eq!(dag_to_string(context.reduce(subject)), "a");
+
+    // The identity an argument reduces to is absorbed.
+    let subject = term(&module, "u(h(a), b)").term_to_dag(false);
+    let _root   = RootContainer::new(subject);
+    assert_eq!(dag_to_string(context.reduce(subject)), "b");
+  }
+}
@@ -4,20 +4,20 @@ A `RootContainer` is a linked list of roots of garbage collected objects.
 
 */
 
-use std::{
+use core::{
   ptr::NonNull,
-  sync::{
-    atomic::{
-      AtomicPtr,
-      Ordering
-    },
-    Mutex
-  },
-  sync::MutexGuard
+  sync::atomic::{
+    AtomicPtr,
+    Ordering
+  }
+};
+use alloc::boxed::Box;
+use crate::{
+  abstractions::{Mutex, MutexGuard},
+  api::dag_node::{DagNode, DagNodePtr}
 };
-use crate::api::dag_node::{DagNode, DagNodePtr};
 
-static LIST_HEAD: Mutex<AtomicPtr<RootContainer>> = Mutex::new(AtomicPtr::new(std::ptr::null_mut()));
+static LIST_HEAD: Mutex<AtomicPtr<RootContainer>> = Mutex::new(AtomicPtr::new(core::ptr::null_mut()));
 
 pub fn acquire_root_list() -> MutexGuard<'static, AtomicPtr<RootContainer>> {
   match LIST_HEAD.try_lock() {
@@ -85,7 +85,7 @@ impl RootContainer {
     } else if let Some(next) = self.next {
       list_head.store(next.as_ptr(), Ordering::Relaxed);
     } else {
-      list_head.store(std::ptr::null_mut(), Ordering::Relaxed);
+      list_head.store(core::ptr::null_mut(), Ordering::Relaxed);
     }
   }
 
@@ -112,3 +112,15 @@ pub fn mark_roots() {
     root = root_ref.next;
   }
 }
+
+/// Calls `f` on the node of each root in the linked list of `RootContainer`s.
+pub(crate) fn for_each_root(mut f: impl FnMut(DagNodePtr)) {
+  let list_head = acquire_root_list();
+  let mut root  = NonNull::new(list_head.load(Ordering::Relaxed));
+
+  while let Some(root_ptr) = root {
+    let root_ref = unsafe{ root_ptr.as_ref() };
+    f(root_ref.node.as_ptr());
+    root = root_ref.next;
+  }
+}
@@ -0,0 +1,147 @@
+/*!
+
+Priorities and weights of rules. A rule may be given a `Selection`, written as the statement attributes
+`[prio 2 weight 3]` after the rule, that controls which of its redexes are rewritten when several rules apply:
+
+ - Only the redexes of the rules of the highest priority among those that apply are eligible. Priorities may be
+   negative, and rules without a selection have priority `DEFAULT_PRIORITY`.
+ - Among the eligible redexes, one is chosen at random with probability proportional to the weight of its rule. Rules
+   without a selection have weight `DEFAULT_WEIGHT`. A rule of weight zero is never chosen at random, but its
+   redexes are still listed, so that exploration can reach them.
+
+`RewritingContext::rule_redexes` lists the rule redexes of a term in the order exploration should visit them, highest
+priority first, and `RewritingContext::choose_rule_redex` makes the weighted choice with a caller-supplied random number
+generator, so that seeded runs are reproducible. `RewritingContext::apply_rule` rewrites the chosen redex. A
+`Simulation` does all three repeatedly. See `simulation`.
+
+*/
+
+use core::fmt::{Display, Formatter};
+
+use rand::Rng;
+
+use crate::core::{
+  pre_equation::PreEquation,
+  rewriting_context::{Bindings, Position}
+};
+
+/// The priority of a rule without a `Selection`
+pub const DEFAULT_PRIORITY: i32 = 0;
+/// The weight of a rule without a `Selection`
+pub const DEFAULT_WEIGHT: u32 = 1;
+
+/// The priority of a rule and its weight among the rules of the same priority. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Selection {
+  pub priority: i32,
+  pub weight  : u32,
+}
+
+impl Default for Selection {
+  fn default() -> Self {
+    Selection {
+      priority: DEFAULT_PRIORITY,
+      weight  : DEFAULT_WEIGHT,
+    }
+  }
+}
+
+impl Display for Selection {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "prio {} weight {}", self.priority, self.weight)
+  }
+}
+
+/// A position at which a rule matches
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RuleRedex {
+  pub position: Position,
+  /// The index of the rule among the rules of the module
+  pub rule    : usize,
+  /// The bindings of the rule's variables
+  pub bindings: Bindings,
+}
+
+/// Orders `redexes` by the priority of their rules, highest first, keeping the order of redexes of equal priority.
+pub(crate) fn order_by_priority(redexes: &mut [RuleRedex], rules: &[PreEquation]) {
+  redexes.sort_by_key(|redex| core::cmp::Reverse(rules[redex.rule].selection().priority));
+}
+
+/// Chooses one of `redexes`, which are ordered by priority, among those of the highest priority, with probability
+/// proportional to the weights of their rules. Returns `None` if there are none or all their weights are zero.
+pub(crate) fn choose_weighted<'r, R: Rng + ?Sized>(
+  redexes: &'r [RuleRedex],
+  rules  : &[PreEquation],
+  rng    : &mut R
+) -> Option<&'r RuleRedex>
+{
+  let priority = rules[redexes.first()?.rule].selection().priority;
+  let eligible = redexes.iter().take_while(|redex| rules[redex.rule].selection().priority == priority);
+  let total: u64 = eligible.clone().map(|redex| rules[redex.rule].selection().weight as u64).sum();
+  if total == 0 {
+    return None;
+  }
+
+  let mut choice = rng.random_range(0..total);
+  for redex in eligible {
+    let weight = rules[redex.rule].selection().weight as u64;
+    if choice < weight {
+      return Some(redex);
+    }
+    choice -= weight;
+  }
+
+  unreachable!("the choice is less than the total weight")
+}
+
+
+#[cfg(test)]
+mod tests {
+  use rand::{rngs::StdRng, SeedableRng};
+
+  use crate::core::{
+    parser::{parse_module, parse_term},
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext,
+    rule_selection::Selection
+  };
+
+  const MODULE: &str = "
+    mod COIN is
+      sort Coin .
+      ops coin heads tails edge : -> Coin .
+      rl [edge] : coin => edge [prio -1 weight 100] .
+      rl [heads] : coin => heads [weight 3] .
+      rl [tails] : coin => tails .
+    endm
+  ";
+
+  #[test]
+  fn rules_are_chosen_by_priority_and_weight() {
+    let module = parse_module(MODULE).unwrap();
+    assert!(module.rules[0].to_string().ends_with("prio -1 weight 100];"));
+    assert_eq!(module.rules[1].selection(), Selection { priority: 0, weight: 3 });
+    assert_eq!(module.rules[2].selection, None);
+
+    let mut context = RewritingContext::new(&module);
+    let subject     = parse_term(&module, "coin").unwrap().term_to_dag(false);
+    let redexes     = context.rule_redexes(subject);
+    // The low priority rule comes last.
+    assert_eq!(redexes.iter().map(|redex| redex.rule).collect::<Vec<_>>(), vec![1, 2, 0]);
+
+    // The low priority rule is never chosen while the others apply, and heads is about three times as likely as tails.
+    let mut rng    = StdRng::seed_from_u64(7);
+    let mut counts = [0; 3];
+    for _ in 0..400 {
+      let redex = context.choose_rule_redex(subject, &mut rng).unwrap();
+      counts[redex.rule] += 1;
+    }
+    assert_eq!(counts[0], 0);
+    assert!(counts[1] > 2 * counts[2], "{:?}", counts);
+
+    let redex = context.choose_rule_redex(subject, &mut rng).unwrap();
+    let result = context.apply_rule(subject, &redex);
+    assert!(["heads", "tails"].contains(&dag_to_string(result).as_str()));
+    assert_eq!(context.rule_count(), 1);
+  }
+}
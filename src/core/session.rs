@@ -0,0 +1,614 @@
+/*!
+
+A `Session` holds the modules of a specification project, by name. `Session::load_directory` loads a project organized
+across files:
+
+```ignore
+let mut session = Session::new();
+let loaded      = session.load_directory("specs")?;  // The names of the modules loaded, in dependency order
+let module      = session.module(&"MAIN".into()).unwrap();
+```
+
+Loading discovers the module files in the directory and its subdirectories, the files with the extensions in
+`MODULE_FILE_EXTENSIONS`, each of which must hold exactly one module. It reads the name and imports of each module
+(see `module_graph`), resolves the imports by name among the directory's modules, the modules loaded before, and the
+prelude, in that order of precedence, and parses the modules in dependency order, each after the modules it imports.
+An import splices in the source of the imported module (see `parser`), so each module is self-contained once parsed.
+
+Imports that resolve to none of these are looked up with the session's module resolvers, which are asked before the
+prelude, and `Session::load_module` loads a single module from the resolvers along with its imports. See
+`module_resolver`. The modules the resolvers find are loaded as the session's own.
+
+Nothing is loaded unless every module can be: loading fails on the first file that can't be read, on two files
+holding modules of the same name, on an import that resolves to no module, on a resolved source holding a module of
+another name, on a cycle of imports, or on the first module in dependency order that fails to parse. A module loaded
+again replaces the module of the same name.
+
+## Reloading
+
+`Session::reload` rereads a module from the file it was loaded from, or from the resolvers if it wasn't loaded from a
+file, and reparses it together with the modules that import it, directly or not, so that they pick up the change. The
+reload fails, changing nothing, on the same errors as loading.
+
+A replaced module is dropped with everything compiled from it, like its pattern cache, but DAGs built from it stay
+valid as long as they are reachable from a root: its symbols are kept until no root refers to them (see
+`module_arena`). Such DAGs are stale rather than wrong. They still print and compare as before, but they belong to the
+old version of the module, and reducing them with the new one would be meaningless. `Session::is_stale` tells them
+apart. Patterns compiled by a replaced module must be compiled again with the new one.
+
+## Shared terms
+
+A session holds an `ArcTermTable` that lasts as long as the session, `Session::terms`, in which the modules of the
+session make their `ArcTerm`s, so identical subterms are shared across modules and across reductions:
+
+```ignore
+let table  = session.terms();
+let two    = table.make("s".into(), vec![table.make("s".into(), vec![table.constant("0".into())])]);
+let first  = RewritingContext::new(session.module(&"ARITH".into()).unwrap()).reduce_arc(&two, table)?;
+let second = RewritingContext::new(session.module(&"MAIN".into()).unwrap()).reduce_arc(&two, table)?;
+println!("{:.0}% of the terms made were shared", 100.0 * table.statistics().dedup_rate());
+```
+
+Terms name their symbols, so a term is shared by the modules that have symbols of its names, and replacing a module
+leaves its terms valid. The table keeps its terms until `ArcTermTable::purge` drops those nothing else refers to, or
+`ArcTermTable::clear` drops all of them. See `arc_term`.
+
+*/
+
+use std::{
+  collections::HashSet,
+  error::Error,
+  fmt::{Display, Formatter},
+  path::{Path, PathBuf}
+};
+
+use crate::{
+  abstractions::{HashMap, IString},
+  api::{
+    dag_node::DagNodePtr,
+    symbol::SymbolType
+  },
+  core::{
+    arc_term::ArcTermTable,
+    module::Module,
+    module_graph::ModuleGraph,
+    module_resolver::{ModuleResolver, ModuleSource},
+    observer::notify,
+    parser::{parse_imports, parse_module_in, ParseError},
+    prelude
+  }
+};
+
+/// The extensions of the files `Session::load_directory` loads
+pub const MODULE_FILE_EXTENSIONS: [&str; 2] = ["mod2", "maude"];
+
+/// Returned by `Session::load_directory`, `Session::load_module`, and `Session::reload` when the modules can't be
+/// loaded. See the module documentation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum LoadError {
+  /// The file or directory couldn't be read.
+  Io { path: PathBuf, message: String },
+  /// The module failed to parse. The `origin` is the file or resolver the source came from.
+  Parse { origin: String, error: ParseError },
+  /// Two files hold modules of the same name.
+  DuplicateModule { name: IString, first: PathBuf, second: PathBuf },
+  /// The module imports a module that is neither in the directory, nor loaded before, nor resolved, nor in the prelude.
+  UnknownImport { module: IString, import: IString },
+  /// No resolver has the module, or the module to reload isn't loaded.
+  UnknownModule(IString),
+  /// A resolver asked for `expected` gave the source of `found`.
+  WrongModule { origin: String, expected: IString, found: IString },
+  /// The modules import each other, directly or not.
+  ImportCycle(Vec<IString>),
+}
+
+impl Display for LoadError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LoadError::Io { path, message }      => write!(f, "can't read {}: {}", path.display(), message),
+      LoadError::Parse { origin, error }   => write!(f, "{}: {}", origin, error),
+      LoadError::DuplicateModule { name, first, second } => {
+        write!(f, "module {} is in both {} and {}", name, first.display(), second.display())
+      }
+      LoadError::UnknownImport { module, import } => write!(f, "module {} imports unknown module {}", module, import),
+      LoadError::UnknownModule(name)              => write!(f, "unknown module {}", name),
+      LoadError::WrongModule { origin, expected, found } => {
+        write!(f, "{} holds module {} rather than {}", origin, found, expected)
+      }
+      LoadError::ImportCycle(cycle) => {
+        let names: Vec<String> = cycle.iter().map(|name| name.to_string()).collect();
+        write!(f, "modules {} import each other", names.join(", "))
+      }
+    }
+  }
+}
+
+impl Error for LoadError {}
+
+/// The modules of a specification project. See the module documentation.
+#[derive(Default)]
+pub struct Session {
+  modules  : HashMap<IString, Module>,
+  /// The sources of the modules, for the modules loaded later to import
+  sources  : HashMap<IString, String>,
+  /// The names of the modules in the order they were loaded
+  order    : Vec<IString>,
+  /// The files the modules loaded from files were loaded from, for reloading
+  files    : HashMap<IString, PathBuf>,
+  resolvers: Vec<Box<dyn ModuleResolver>>,
+  /// The hash-cons table of the terms of every module
+  terms    : ArcTermTable,
+}
+
+/// The modules being loaded
+struct Batch {
+  graph  : ModuleGraph,
+  /// The sources of the modules being loaded and the modules loaded before
+  library: HashMap<IString, String>,
+  /// Where the source of each module being loaded came from
+  origins: HashMap<IString, String>,
+  /// The files of the modules being loaded from files
+  files  : HashMap<IString, PathBuf>,
+}
+
+impl Batch {
+  fn add(&mut self, name: IString, imports: Vec<IString>, source: String, origin: String) {
+    self.graph.add_module(name.clone(), imports);
+    self.library.insert(name.clone(), source);
+    self.origins.insert(name, origin);
+  }
+}
+
+impl Session {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The module named `name`
+  #[inline(always)]
+  pub fn module(&self, name: &IString) -> Option<&Module> {
+    self.modules.get(name)
+  }
+
+  /// The names of the modules, in the order they were loaded
+  pub fn modules(&self) -> impl Iterator<Item=&IString> {
+    self.order.iter()
+  }
+
+  /// The table in which the modules of the session make their terms. See the module documentation.
+  #[inline(always)]
+  pub fn terms(&self) -> &ArcTermTable {
+    &self.terms
+  }
+
+  /// Adds `resolver` after the resolvers added before. See `module_resolver`.
+  pub fn add_resolver(&mut self, resolver: impl ModuleResolver + 'static) {
+    self.resolvers.push(Box::new(resolver));
+  }
+
+  /// Loads the modules of the files in `path` and its subdirectories, returning their names in the order they were
+  /// loaded, which is dependency order. See the module documentation.
+  pub fn load_directory(&mut self, path: impl AsRef<Path>) -> Result<Vec<IString>, LoadError> {
+    let mut files = Vec::new();
+    discover(path.as_ref(), &mut files)?;
+
+    // Read the names and imports of the modules.
+    let mut batch = self.batch();
+    let mut paths: HashMap<IString, PathBuf> = HashMap::default();
+    for file in files {
+      let source = std::fs::read_to_string(&file)
+          .map_err(|error| LoadError::Io { path: file.clone(), message: error.to_string() })?;
+      let origin          = file.display().to_string();
+      let (name, imports) = parse_imports(&source).map_err(|error| LoadError::Parse { origin: origin.clone(), error })?;
+      if let Some(first) = paths.get(&name) {
+        return Err(LoadError::DuplicateModule { name, first: first.clone(), second: file });
+      }
+
+      batch.add(name.clone(), imports, source, origin);
+      batch.files.insert(name.clone(), file.clone());
+      paths.insert(name, file);
+    }
+
+    self.load(batch)
+  }
+
+  /// Loads the module `name` from the resolvers, with the modules it imports that aren't loaded yet, returning their
+  /// names in the order they were loaded, which is dependency order. Loads nothing if the module is loaded already.
+  pub fn load_module(&mut self, name: &IString) -> Result<Vec<IString>, LoadError> {
+    if self.modules.contains_key(name) {
+      return Ok(Vec::new());
+    }
+
+    let mut batch = self.batch();
+    match self.resolve(name)? {
+      Some(source) => self.add_resolved(&mut batch, name, source)?,
+      None         => return Err(LoadError::UnknownModule(name.clone())),
+    }
+
+    self.load(batch)
+  }
+
+  /// Rereads the module `name` and reparses it with the modules that import it, returning their names in the order
+  /// they were reloaded, which is dependency order, along with any new imports loaded with them. See the module
+  /// documentation.
+  pub fn reload(&mut self, name: &IString) -> Result<Vec<IString>, LoadError> {
+    if !self.modules.contains_key(name) {
+      return Err(LoadError::UnknownModule(name.clone()));
+    }
+
+    let mut batch = self.batch();
+    match self.files.get(name) {
+      Some(file) => {
+        let text = std::fs::read_to_string(file)
+            .map_err(|error| LoadError::Io { path: file.clone(), message: error.to_string() })?;
+        self.add_resolved(&mut batch, name, ModuleSource { origin: file.display().to_string(), text })?;
+        batch.files.insert(name.clone(), file.clone());
+      }
+      None => match self.resolve(name)? {
+        Some(source) => self.add_resolved(&mut batch, name, source)?,
+        None         => return Err(LoadError::UnknownModule(name.clone())),
+      }
+    }
+
+    // The dependents are reparsed from the sources they were loaded from, splicing in the new source.
+    for dependent in self.order.iter() {
+      let module = &self.modules[dependent];
+      if module.transitive_imports().contains(name) {
+        let origin = match self.files.get(dependent) {
+          Some(file) => file.display().to_string(),
+          None       => format!("the loaded source of {}", dependent),
+        };
+        batch.add(dependent.clone(), module.imports().to_vec(), self.sources[dependent].clone(), origin);
+        if let Some(file) = self.files.get(dependent) {
+          batch.files.insert(dependent.clone(), file.clone());
+        }
+      }
+    }
+
+    self.load(batch)
+  }
+
+  /// Whether the DAG refers to a symbol of a module this session replaced, that is, to a symbol that is neither a
+  /// symbol of a module of the session nor the symbol of a data atom. The DAG must be reachable from a root if a module
+  /// it was built from was replaced. See the module documentation.
+  pub fn is_stale(&self, dag: DagNodePtr) -> bool {
+    let current: HashSet<*const u8> = self.modules
+                                          .values()
+                                          .flat_map(|module| module.arena.symbols.values())
+                                          .map(|&symbol| symbol as *const u8)
+                                          .collect();
+    let mut visited: HashSet<*const u8> = HashSet::new();
+    let mut pending: Vec<DagNodePtr>    = vec![dag];
+    while let Some(node) = pending.pop() {
+      if !visited.insert(node as *const u8) {
+        continue;
+      }
+
+      let node = unsafe{ &*node };
+      if !current.contains(&(node.symbol() as *const u8)) && node.symbol_ref().symbol_type != SymbolType::Data {
+        return true;
+      }
+      pending.extend(node.iter_args());
+    }
+
+    false
+  }
+
+  fn batch(&self) -> Batch {
+    Batch {
+      graph  : ModuleGraph::new(),
+      library: self.sources.clone(),
+      origins: HashMap::default(),
+      files  : HashMap::default(),
+    }
+  }
+
+  /// The source of `name` from the first resolver that has it
+  fn resolve(&self, name: &IString) -> Result<Option<ModuleSource>, LoadError> {
+    for resolver in self.resolvers.iter() {
+      if let Some(source) = resolver.resolve(name)? {
+        return Ok(Some(source));
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn add_resolved(&self, batch: &mut Batch, name: &IString, source: ModuleSource) -> Result<(), LoadError> {
+    let (found, imports) = parse_imports(&source.text)
+        .map_err(|error| LoadError::Parse { origin: source.origin.clone(), error })?;
+    if found != *name {
+      return Err(LoadError::WrongModule { origin: source.origin, expected: name.clone(), found });
+    }
+
+    batch.add(found, imports, source.text, source.origin);
+    Ok(())
+  }
+
+  /// Resolves the imports of `batch` that aren't in it, checks it for cycles, and parses and adds its modules.
+  fn load(&mut self, mut batch: Batch) -> Result<Vec<IString>, LoadError> {
+    // Resolving an import can add modules with imports of their own, which are checked in turn.
+    let mut checked = 0;
+    while checked < batch.graph.modules().count() {
+      let name    = batch.graph.modules().nth(checked).unwrap().clone();
+      let imports = batch.graph.imports(&name).unwrap_or_default().to_vec();
+      for import in imports {
+        if batch.library.contains_key(&import) {
+          continue;
+        }
+        match self.resolve(&import)? {
+          Some(source) => self.add_resolved(&mut batch, &import, source)?,
+          None if prelude::source(import.as_ref()).is_some() => {}
+          None => return Err(LoadError::UnknownImport { module: name.clone(), import }),
+        }
+      }
+      checked += 1;
+    }
+    if let Some(cycle) = batch.graph.import_cycles().into_iter().next() {
+      return Err(LoadError::ImportCycle(cycle));
+    }
+
+    // Parse the modules in dependency order.
+    let mut loaded = Vec::new();
+    for name in batch.graph.modules() {
+      visit(name, &batch.graph, &mut loaded);
+    }
+    let mut modules = Vec::with_capacity(loaded.len());
+    for name in loaded.iter() {
+      let module = parse_module_in(&batch.library[name], &batch.library)
+          .map_err(|error| LoadError::Parse { origin: batch.origins[name].clone(), error })?;
+      modules.push(module);
+    }
+
+    for (name, module) in loaded.iter().zip(modules) {
+      self.order.retain(|loaded| loaded != name);
+      self.order.push(name.clone());
+      if let Some(mut replaced) = self.modules.insert(name.clone(), module) {
+        replaced.arena.retire();
+      }
+      self.sources.insert(name.clone(), batch.library[name].clone());
+      match batch.files.get(name) {
+        Some(file) => self.files.insert(name.clone(), file.clone()),
+        None       => self.files.remove(name),
+      };
+      notify(|observer| observer.module_loaded(name));
+    }
+
+    Ok(loaded)
+  }
+}
+
+/// Pushes the module files in `path` and its subdirectories onto `files`, in order of their paths.
+fn discover(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), LoadError> {
+  let io_error = |error: std::io::Error| LoadError::Io { path: path.to_path_buf(), message: error.to_string() };
+  let mut entries = std::fs::read_dir(path)
+      .map_err(io_error)?
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(io_error)?;
+  entries.sort();
+
+  for entry in entries {
+    if entry.is_dir() {
+      discover(&entry, files)?;
+    } else if entry.extension().is_some_and(|extension| MODULE_FILE_EXTENSIONS.iter().any(|&ext| extension == ext)) {
+      files.push(entry);
+    }
+  }
+
+  Ok(())
+}
+
+/// Pushes the modules of the graph `name` imports, and then `name`, onto `loaded` if they aren't there yet. The graph
+/// has no cycles.
+fn visit(name: &IString, graph: &ModuleGraph, loaded: &mut Vec<IString>) {
+  if loaded.contains(name) || !graph.contains(name) {
+    return;
+  }
+  for import in graph.imports(name).unwrap_or_default() {
+    visit(import, graph, loaded);
+  }
+  loaded.push(name.clone());
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    collections::HashMap,
+    path::PathBuf
+  };
+
+  use crate::{
+    abstractions::IString,
+    core::{
+      module_resolver::IncludePath,
+      parser::parse_term,
+      rewriting_context::RewritingContext,
+      proof_trace::dag_to_string,
+      root_container::RootContainer,
+      session::{LoadError, Session}
+    }
+  };
+
+  /// A fresh directory holding `files`, given by their paths relative to it and their contents
+  fn directory(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("mod2lib-{}-{}", name, std::process::id()));
+    let _    = std::fs::remove_dir_all(&root);
+    for (path, contents) in files {
+      let path = root.join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, contents).unwrap();
+    }
+    root
+  }
+
+  fn names(names: &[&str]) -> Vec<IString> {
+    names.iter().map(|&name| IString::from(name)).collect()
+  }
+
+  #[test]
+  fn directories_load_in_dependency_order() {
+    let root = directory("session", &[
+      ("main.mod2", "mod MAIN is including ARITH . op four : -> Nat . eq four = plus(two, two) . endm"),
+      ("lib/arith.mod2", "
+        mod ARITH is
+          including NUMBERS .
+          op plus : Nat Nat -> Nat .
+          vars X Y : Nat .
+          eq plus(0, Y) = Y .
+          eq plus(s(X), Y) = s(plus(X, Y)) .
+        endm
+      "),
+      ("lib/numbers.maude", "mod NUMBERS is including NAT . op two : -> Nat . eq two = s(s(0)) . endm"),
+      ("notes.txt", "not a module"),
+    ]);
+
+    let mut session = Session::new();
+    assert_eq!(session.load_directory(&root).unwrap(), names(&["NUMBERS", "ARITH", "MAIN"]));
+    let main        = session.module(&"MAIN".into()).unwrap();
+    let mut context = RewritingContext::new(main);
+    let four        = context.reduce(parse_term(main, "four").unwrap().term_to_dag(false));
+    assert_eq!(dag_to_string(four), "s(s(s(s(0))))");
+
+    // Later loads can import the modules loaded before.
+    let more = directory("session-more", &[("more.mod2", "mod MORE is including ARITH . endm")]);
+    assert_eq!(session.load_directory(&more).unwrap(), names(&["MORE"]));
+    assert_eq!(session.modules().cloned().collect::<Vec<_>>(), names(&["NUMBERS", "ARITH", "MAIN", "MORE"]));
+
+    let cyclic = directory("session-cycle", &[
+      ("a.mod2", "mod A is including B . endm"),
+      ("b.mod2", "mod B is including A . endm"),
+    ]);
+    assert_eq!(Session::new().load_directory(&cyclic), Err(LoadError::ImportCycle(names(&["A", "B"]))));
+
+    let unknown = directory("session-unknown", &[("a.mod2", "mod A is including MISSING . endm")]);
+    assert_eq!(
+      Session::new().load_directory(&unknown),
+      Err(LoadError::UnknownImport { module: "A".into(), import: "MISSING".into() })
+    );
+
+    for path in [root, more, cyclic, unknown] {
+      std::fs::remove_dir_all(path).unwrap();
+    }
+  }
+
+  #[test]
+  fn imports_are_resolved_with_resolvers_in_order() {
+    let include = directory("session-include", &[
+      ("numbers.mod2", "mod NUMBERS is including NAT . op two : -> Nat . eq two = s(s(0)) . endm"),
+      ("WRONG.mod2", "mod RIGHT is endm"),
+    ]);
+    let memory = HashMap::from([
+      (IString::from("MAIN"), "mod MAIN is including NUMBERS . including GENERATED . endm".to_string()),
+      (IString::from("NUMBERS"), "mod NUMBERS is including NAT . endm".to_string()),
+    ]);
+
+    let generated = |name: &IString| {
+      (name.as_ref() == "GENERATED").then(|| "mod GENERATED is including NAT . op three : -> Nat . endm".to_string())
+    };
+
+    let mut session = Session::new();
+    session.add_resolver(IncludePath::new(&include));
+    session.add_resolver(memory);
+    session.add_resolver(generated);
+
+    // NUMBERS comes from the include path, which was added before the map.
+    assert_eq!(session.load_module(&"MAIN".into()).unwrap(), names(&["NUMBERS", "GENERATED", "MAIN"]));
+    assert!(parse_term(session.module(&"MAIN".into()).unwrap(), "two").is_ok());
+    assert_eq!(session.load_module(&"NUMBERS".into()).unwrap(), names(&[]));
+
+    // Directories import modules from the resolvers as well.
+    let root = directory("session-resolved", &[("user.mod2", "mod USER is including GENERATED . endm")]);
+    let mut fresh = Session::new();
+    assert_eq!(fresh.load_directory(&root), Err(LoadError::UnknownImport {
+      module: "USER".into(),
+      import: "GENERATED".into()
+    }));
+    fresh.add_resolver(generated);
+    assert_eq!(fresh.load_directory(&root).unwrap(), names(&["GENERATED", "USER"]));
+
+    assert_eq!(session.load_module(&"MISSING".into()), Err(LoadError::UnknownModule("MISSING".into())));
+    assert!(matches!(
+      session.load_module(&"WRONG".into()),
+      Err(LoadError::WrongModule { expected, found, .. }) if expected.as_ref() == "WRONG" && found.as_ref() == "RIGHT"
+    ));
+
+    for path in [include, root] {
+      std::fs::remove_dir_all(path).unwrap();
+    }
+  }
+
+  #[test]
+  fn reloading_reparses_dependents_and_leaves_old_dags_stale() {
+    let root = directory("session-reload", &[
+      ("numbers.mod2", "mod NUMBERS is including NAT . op two : -> Nat . eq two = s(s(0)) . endm"),
+      ("main.mod2", "mod MAIN is including NUMBERS . op four : -> Nat . eq four = s(s(two)) . endm"),
+      ("other.mod2", "mod OTHER is including NAT . endm"),
+    ]);
+    let mut session = Session::new();
+    session.load_directory(&root).unwrap();
+
+    let main        = session.module(&"MAIN".into()).unwrap();
+    let mut context = RewritingContext::new(main);
+    let old_four    = context.reduce(parse_term(main, "four").unwrap().term_to_dag(false));
+    let _root       = RootContainer::new(old_four);
+    assert!(!session.is_stale(old_four));
+
+    std::fs::write(root.join("numbers.mod2"), "mod NUMBERS is including NAT . op two : -> Nat . eq two = 0 . endm")
+        .unwrap();
+    assert_eq!(session.reload(&"NUMBERS".into()).unwrap(), names(&["NUMBERS", "MAIN"]));
+
+    // The old result is still valid, but stale, and the new module reduces with the new equation.
+    assert!(session.is_stale(old_four));
+    assert_eq!(dag_to_string(old_four), "s(s(s(s(0))))");
+    let main        = session.module(&"MAIN".into()).unwrap();
+    let mut context = RewritingContext::new(main);
+    let new_four    = context.reduce(parse_term(main, "four").unwrap().term_to_dag(false));
+    assert_eq!(dag_to_string(new_four), "s(s(0))");
+    assert!(!session.is_stale(new_four));
+
+    // A failed reload changes nothing.
+    std::fs::write(root.join("numbers.mod2"), "mod NUMBERS is including MISSING . endm").unwrap();
+    assert_eq!(
+      session.reload(&"NUMBERS".into()),
+      Err(LoadError::UnknownImport { module: "NUMBERS".into(), import: "MISSING".into() })
+    );
+    assert!(parse_term(session.module(&"MAIN".into()).unwrap(), "two").is_ok());
+    assert_eq!(session.reload(&"UNLOADED".into()), Err(LoadError::UnknownModule("UNLOADED".into())));
+
+    std::fs::remove_dir_all(root).unwrap();
+  }
+
+  #[test]
+  fn modules_share_the_terms_of_the_session() {
+    let mut session = Session::new();
+    session.add_resolver(HashMap::from([
+      ("DOUBLE", "mod DOUBLE is including NAT . op double : Nat -> Nat . eq double(N) = plus(N, N) . endm"),
+      ("SQUARE", "mod SQUARE is including NAT . op square : Nat -> Nat . eq square(N) = times(N, N) . endm"),
+    ].map(|(name, source)| (IString::from(name), source.to_string()))));
+    session.load_module(&"DOUBLE".into()).unwrap();
+    session.load_module(&"SQUARE".into()).unwrap();
+
+    let table  = session.terms();
+    let two    = table.make("s".into(), vec![table.make("s".into(), vec![table.constant("0".into())])]);
+    let reduce = |module: &str, operator: &str| {
+      let module = session.module(&module.into()).unwrap();
+      RewritingContext::new(module).reduce_arc(&table.make(operator.into(), vec![two.clone()]), table).unwrap()
+    };
+    let doubled = reduce("DOUBLE", "double");
+    let squared = reduce("SQUARE", "square");
+
+    // Both are `s(s(s(s(0))))`, made once, and `two` is shared with them.
+    assert!(doubled.ptr_eq(&squared));
+    assert!(doubled.args()[0].args()[0].ptr_eq(&two));
+    let statistics = table.statistics();
+    assert_eq!(statistics.terms, 7);
+    assert!(statistics.shared > 0 && statistics.dedup_rate() > 0.0);
+
+    table.clear();
+    assert_eq!(table.statistics(), Default::default());
+    assert!(!table.make("s".into(), vec![table.constant("0".into())]).ptr_eq(&two.args()[0]));
+  }
+}
@@ -0,0 +1,230 @@
+/*!
+
+Reflection on the signature of a module, for documentation generators and tools that lint signatures.
+`Module::signature` lists the module's sorts and operators, ordered by name, with what was declared about each: the
+immediate subsorts and supersorts of a sort, and the arity, attributes, declarations, strategy, and identity of an
+operator. Variables are not part of the signature and are left out.
+
+The listing is a snapshot that refers to the module by name only, except for the `sort` and `symbol` pointers, which
+are valid for as long as the module is. The kinds of sorts are known once the module is closed and are `None` before.
+
+Counts of the module's symbols and of interned names are in `core::statistics`.
+
+```ignore
+for op in module.signature().symbols {
+  println!("op {} : {} [{}] .", op.name, op.declarations[0], op.attribute_names().join(" "));
+}
+```
+
+*/
+
+use core::fmt::{Display, Formatter};
+use alloc::{string::{String, ToString}, vec::Vec};
+
+use crate::{
+  abstractions::{join_string, IString},
+  api::{
+    symbol::{SymbolAttributes, SymbolPtr, SymbolType},
+    Arity
+  },
+  core::{
+    module::Module,
+    sort::{sort_table::OpDeclaration, SortPtr}
+  }
+};
+
+/// The sorts and operators of a module. See the module documentation.
+#[derive(Clone, Default, Debug)]
+pub struct Signature {
+  /// The sorts, ordered by name
+  pub sorts  : Vec<SortInfo>,
+  /// The operators, ordered by name
+  pub symbols: Vec<SymbolInfo>,
+}
+
+impl Signature {
+  /// The sort named `name`, if there is one
+  pub fn sort(&self, name: &str) -> Option<&SortInfo> {
+    self.sorts.iter().find(|sort| sort.name.as_ref() == name)
+  }
+
+  /// The operator named `name`, if there is one
+  pub fn symbol(&self, name: &str) -> Option<&SymbolInfo> {
+    self.symbols.iter().find(|symbol| symbol.name.as_ref() == name)
+  }
+}
+
+/// A sort of a module
+#[derive(Clone, Debug)]
+pub struct SortInfo {
+  pub name      : IString,
+  pub sort      : SortPtr,
+  /// The sorts declared immediately below the sort, ordered by name
+  pub subsorts  : Vec<IString>,
+  /// The sorts declared immediately above the sort, ordered by name, not including the error sort of its kind
+  pub supersorts: Vec<IString>,
+  /// The kind of the sort, written as its sorts, as in `{Zero, Nat}`, or `None` if kinds have not been computed
+  pub kind      : Option<String>,
+}
+
+/// An operator declaration `f : domain -> range`, with the sorts by name
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DeclarationInfo {
+  pub domain        : Vec<IString>,
+  pub range         : IString,
+  pub is_constructor: bool,
+}
+
+impl Display for DeclarationInfo {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self.domain.is_empty() {
+      true  => write!(f, "-> {}", self.range),
+      false => write!(f, "{} -> {}", join_string(self.domain.iter(), " "), self.range),
+    }
+  }
+}
+
+/// An operator of a module
+#[derive(Clone, Debug)]
+pub struct SymbolInfo {
+  pub name        : IString,
+  pub symbol      : SymbolPtr,
+  pub arity       : Arity,
+  pub symbol_type : SymbolType,
+  pub attributes  : SymbolAttributes,
+  /// The declarations of the operator, in the order they were given
+  pub declarations: Vec<DeclarationInfo>,
+  /// The evaluation strategy, or empty for the default strategy. See `Symbol::set_strategy`.
+  pub strategy    : Vec<usize>,
+  /// The name of the identity element of an operator with an identity attribute
+  pub identity    : Option<IString>,
+  pub latex       : Option<IString>,
+}
+
+impl SymbolInfo {
+  /// The attributes as they are written in Maude, as in `["assoc", "comm"]`
+  pub fn attribute_names(&self) -> Vec<&'static str> {
+    self.attributes.iter().map(|attribute| attribute.name()).collect()
+  }
+}
+
+impl Module {
+  /// The sorts and operators of the module. See `core::signature`.
+  pub fn signature(&self) -> Signature {
+    let mut sorts: Vec<SortInfo> = self.arena.sorts.iter().map(|(_, sort)| sort_info(sort)).collect();
+    sorts.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut symbols: Vec<SymbolInfo> = self.arena
+                                           .symbols
+                                           .values()
+                                           .filter(|&&symbol| !unsafe{ &*symbol }.is_variable())
+                                           .map(|&symbol| self.symbol_info(symbol))
+                                           .collect();
+    symbols.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Signature { sorts, symbols }
+  }
+
+  fn symbol_info(&self, symbol_ptr: SymbolPtr) -> SymbolInfo {
+    let symbol = unsafe{ &*symbol_ptr };
+
+    SymbolInfo {
+      name        : symbol.name.clone(),
+      symbol      : symbol_ptr,
+      arity       : symbol.arity,
+      symbol_type : symbol.symbol_type,
+      attributes  : symbol.attributes,
+      declarations: symbol.sort_table.declarations().iter().map(declaration_info).collect(),
+      strategy    : symbol.strategy.clone(),
+      identity    : self.identities.get(&symbol_ptr).map(|&identity| unsafe{ &*identity }.name.clone()),
+      latex       : symbol.latex.clone(),
+    }
+  }
+}
+
+fn sort_info(sort_ptr: SortPtr) -> SortInfo {
+  let sort  = unsafe{ &*sort_ptr };
+  let names = |sorts: &[SortPtr]| {
+    let mut names: Vec<IString> = sorts.iter()
+                                       .filter(|&&sort| !is_error_sort(sort))
+                                       .map(|&sort| sort_name(sort))
+                                       .collect();
+    names.sort();
+    names
+  };
+
+  SortInfo {
+    name      : sort.name.clone(),
+    sort      : sort_ptr,
+    subsorts  : names(&sort.subsorts),
+    supersorts: names(&sort.supersorts),
+    kind      : (!sort.kind.is_null()).then(|| unsafe{ &*sort.kind }.to_string()),
+  }
+}
+
+fn declaration_info(declaration: &OpDeclaration) -> DeclarationInfo {
+  DeclarationInfo {
+    domain        : declaration.domain.iter().map(|&sort| sort_name(sort)).collect(),
+    range         : sort_name(declaration.range),
+    is_constructor: declaration.is_constructor,
+  }
+}
+
+/// Whether `sort` is the error sort of its kind, which is a supersort of the kind's maximal sorts but isn't declared
+fn is_error_sort(sort: SortPtr) -> bool {
+  let kind = unsafe{ &*sort }.kind;
+  !kind.is_null() && core::ptr::eq(unsafe{ &*kind }.error_sort(), sort)
+}
+
+#[inline(always)]
+fn sort_name(sort: SortPtr) -> IString {
+  unsafe{ &*sort }.name.clone()
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{symbol::SymbolAttribute, Arity},
+    core::parser::parse_module
+  };
+
+  #[test]
+  fn signatures_list_declarations() {
+    let module = parse_module("
+      mod SIGNATURE is
+        sorts Zero NzNat Nat .
+        subsorts Zero NzNat < Nat .
+        op 0 : -> Zero [ctor] .
+        op s : Nat -> NzNat [ctor] .
+        op plus : Nat Nat -> Nat [assoc comm id: 0] .
+        var X : Nat .
+      endm
+    ").unwrap();
+    let signature = module.signature();
+
+    let names = |names: &[IString]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+    let sorts: Vec<IString> = signature.sorts.iter().map(|sort| sort.name.clone()).collect();
+    assert_eq!(names(&sorts), ["Nat", "NzNat", "Zero"]);
+    let nat = signature.sort("Nat").unwrap();
+    assert_eq!(names(&nat.subsorts), ["NzNat", "Zero"]);
+    assert!(nat.supersorts.is_empty());
+    assert_eq!(names(&signature.sort("Zero").unwrap().supersorts), ["Nat"]);
+
+    let symbols: Vec<IString> = signature.symbols.iter().map(|symbol| symbol.name.clone()).collect();
+    assert_eq!(names(&symbols), ["0", "plus", "s"]);
+    let s = signature.symbol("s").unwrap();
+    assert_eq!(s.declarations[0].to_string(), "Nat -> NzNat");
+    assert!(s.declarations[0].is_constructor);
+    assert_eq!(s.attribute_names(), ["ctor"]);
+
+    let plus = signature.symbol("plus").unwrap();
+    assert_eq!(plus.arity, Arity::Value(2));
+    assert!(plus.attributes.contains(SymbolAttribute::Associative | SymbolAttribute::Commutative));
+    assert_eq!(plus.attribute_names(), ["assoc", "comm", "left id", "right id"]);
+    assert_eq!(plus.identity.as_ref().map(|identity| identity.as_ref()), Some("0"));
+    assert!(signature.symbol("X").is_none());
+  }
+}
@@ -0,0 +1,202 @@
+/*!
+
+Monte Carlo simulation with rules. A `Simulation` runs a term forward by rewriting it, step after step, with a rule
+redex chosen at random by the priorities and weights of the rules (see `rule_selection`), reducing with the equations
+after each step. A run stops when no rule of positive weight applies, a *deadlock*, or after `max_steps` steps. Every
+run starts from the same initial term, and the random choices come from a generator seeded with `seed`, so a simulation
+with the same seed is reproducible.
+
+```ignore
+let report = Simulation::new(&module).with_seed(42).with_runs(1000).with_max_steps(50).run(initial);
+println!("{:.3} of runs deadlocked", report.deadlocks as f64 / report.runs as f64);
+```
+
+The `SimulationReport` counts the visits to each state, keyed by the state as printed, the states the runs ended in,
+and the applications of each rule. The initial term and the state after each step are visited, so a run of `n` steps
+visits `n + 1` states.
+
+*/
+
+use alloc::{string::String, vec, vec::Vec};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+  abstractions::HashMap,
+  api::dag_node::DagNodePtr,
+  core::{
+    module::Module,
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext,
+    RootContainer
+  }
+};
+
+/// The number of runs of a `Simulation` unless configured otherwise
+pub const DEFAULT_RUNS: usize = 100;
+/// The number of steps of each run of a `Simulation` unless configured otherwise
+pub const DEFAULT_MAX_STEPS: usize = 1000;
+
+/// Simulates the rules of a module. See the module documentation.
+#[derive(Copy, Clone, Debug)]
+pub struct Simulation<'m> {
+  module   : &'m Module,
+  seed     : u64,
+  runs     : usize,
+  max_steps: usize,
+}
+
+/// What the runs of a `Simulation` did
+#[derive(Clone, Default, Debug)]
+pub struct SimulationReport {
+  /// The number of runs
+  pub runs        : usize,
+  /// The number of rule rewrites, summed over the runs
+  pub steps       : usize,
+  /// The number of runs that ended because no rule applied
+  pub deadlocks   : usize,
+  /// The number of times each state was visited
+  pub visits      : HashMap<String, usize>,
+  /// The number of runs that ended in each state
+  pub final_states: HashMap<String, usize>,
+  /// The number of times each rule was applied, indexed like the rules of the module
+  pub rule_counts : Vec<usize>,
+}
+
+impl SimulationReport {
+  /// The number of distinct states visited
+  #[inline(always)]
+  pub fn distinct_states(&self) -> usize {
+    self.visits.len()
+  }
+
+  /// The fraction of all visits that were to `state`, as printed
+  pub fn visit_frequency(&self, state: &str) -> f64 {
+    let total: usize = self.visits.values().sum();
+    match total {
+      0     => 0.0,
+      total => self.visits.get(state).copied().unwrap_or(0) as f64 / total as f64,
+    }
+  }
+
+  /// The fraction of runs that ended in `state`, as printed
+  pub fn final_frequency(&self, state: &str) -> f64 {
+    match self.runs {
+      0    => 0.0,
+      runs => self.final_states.get(state).copied().unwrap_or(0) as f64 / runs as f64,
+    }
+  }
+
+  /// The `n` most visited states with their visits, most visited first, ties broken by the states' printed forms
+  pub fn most_visited(&self, n: usize) -> Vec<(&str, usize)> {
+    let mut states: Vec<(&str, usize)> = self.visits.iter().map(|(state, &count)| (state.as_str(), count)).collect();
+    states.sort_by(|(state, count), (other_state, other_count)| other_count.cmp(count).then(state.cmp(other_state)));
+    states.truncate(n);
+
+    states
+  }
+}
+
+impl<'m> Simulation<'m> {
+  /// A simulation of the rules of `module`, which must be compiled, with seed zero, `DEFAULT_RUNS` runs, and
+  /// `DEFAULT_MAX_STEPS` steps.
+  pub fn new(module: &'m Module) -> Self {
+    Simulation {
+      module,
+      seed     : 0,
+      runs     : DEFAULT_RUNS,
+      max_steps: DEFAULT_MAX_STEPS,
+    }
+  }
+
+  pub fn with_seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    self
+  }
+
+  pub fn with_runs(mut self, runs: usize) -> Self {
+    self.runs = runs;
+    self
+  }
+
+  pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+    self.max_steps = max_steps;
+    self
+  }
+
+  /// Runs the simulation from `initial`, which is first reduced with the equations.
+  pub fn run(&self, initial: DagNodePtr) -> SimulationReport {
+    let mut rng     = StdRng::seed_from_u64(self.seed);
+    let mut context = RewritingContext::new(self.module);
+    let mut report  = SimulationReport {
+      runs       : self.runs,
+      rule_counts: vec![0; self.module.rules.len()],
+      ..SimulationReport::default()
+    };
+
+    let initial = context.reduce(initial);
+    let _root   = RootContainer::new(initial);
+    for _ in 0..self.runs {
+      let mut state = initial;
+      *report.visits.entry(dag_to_string(state)).or_default() += 1;
+
+      for _ in 0..self.max_steps {
+        let Some(redex) = context.choose_rule_redex(state, &mut rng) else {
+          report.deadlocks += 1;
+          break;
+        };
+        state = context.apply_rule(state, &redex);
+        report.steps += 1;
+        report.rule_counts[redex.rule] += 1;
+        *report.visits.entry(dag_to_string(state)).or_default() += 1;
+      }
+
+      *report.final_states.entry(dag_to_string(state)).or_default() += 1;
+    }
+
+    report
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    parser::{parse_module, parse_term},
+    simulation::Simulation
+  };
+
+  const MODULE: &str = "
+    mod WALK is
+      sort Pos .
+      ops start left right home : -> Pos .
+      rl start => left [weight 3] .
+      rl start => right .
+      rl left => home .
+      rl right => start .
+    endm
+  ";
+
+  #[test]
+  fn simulations_are_reproducible() {
+    let module  = parse_module(MODULE).unwrap();
+    let initial = || parse_term(&module, "start").unwrap().term_to_dag(false);
+
+    let report = Simulation::new(&module).with_seed(3).with_runs(200).with_max_steps(10).run(initial());
+    // Every run ends at home, having visited start once more for each trip right.
+    assert_eq!(report.deadlocks, 200);
+    assert_eq!(report.final_frequency("home"), 1.0);
+    assert_eq!(report.distinct_states(), 4);
+    assert_eq!(report.rule_counts[2], 200);
+    assert_eq!(report.rule_counts[1], report.rule_counts[3]);
+    assert!(report.rule_counts[1] < 200, "{:?}", report.rule_counts);
+    assert_eq!(report.most_visited(1)[0].0, "start");
+
+    let again = Simulation::new(&module).with_seed(3).with_runs(200).with_max_steps(10).run(initial());
+    assert_eq!(again.visits, report.visits);
+
+    let bounded = Simulation::new(&module).with_runs(5).with_max_steps(0).run(initial());
+    assert_eq!((bounded.steps, bounded.deadlocks), (0, 0));
+    assert_eq!(bounded.final_frequency("start"), 1.0);
+  }
+}
@@ -0,0 +1,540 @@
+/*!
+
+A solver-agnostic bridge for discharging conditions with an SMT solver. Available with the `smt` feature.
+
+Conditions over integer, real, and Boolean data—`X > 0 and X + Y = 10`—are translated into `SmtTerm`s and handed to an
+`SmtSolver`, so that they can be decided by the solver instead of by rewriting. No solver is bundled. Users implement
+`SmtSolver` for the solver of their choice, either through its API or by writing the SMT-LIB 2 script that
+`SmtTerm`'s `Display` implementation produces and running the solver on it.
+
+An `SmtSignature` says which module symbols are interpreted by the solver: operators like `+` and `<`, literal
+constants like `10` and `true`, and the SMT sort of each variable.
+
+```ignore
+let mut signature = SmtSignature::new();
+signature.operator(plus, SmtOperator::Add);
+signature.operator(greater, SmtOperator::Gt);
+signature.literal(ten, SmtTerm::Int(10));
+signature.variable(x, SmtSort::Int);
+
+match signature.check_conditions(&equation.conditions, &mut solver)? {
+  SmtResult::Sat     => { /* the conditions can be satisfied */ }
+  SmtResult::Unsat   => { /* the conditions can never hold */ }
+  SmtResult::Unknown => { /* the solver gave up */ }
+}
+```
+
+A `RewritingContext` given a solver by `RewritingContext::set_smt_solver` asks it about the equality conditions of the
+statements it tries. The instance of a condition under the bindings of a match is translated by `translate_dag`, which
+also translates integer and float data atoms to their values. If the instance is a formula over interpreted symbols
+without variables, the solver's answer decides the condition, and otherwise, or if the solver gives up, the condition
+is evaluated by rewriting.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+use alloc::{boxed::Box, format, string::{String, ToString}, vec, vec::Vec};
+
+use crate::{
+  abstractions::{HashMap, IString},
+  api::{
+    dag_node::DagNodePtr,
+    float::FloatAtom,
+    integer::IntegerAtom,
+    symbol::SymbolPtr,
+    term::Term
+  },
+  core::pre_equation::condition::Condition
+};
+
+/// The sorts understood by the bridge
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SmtSort {
+  Bool,
+  Int,
+  Real,
+}
+
+impl Display for SmtSort {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      SmtSort::Bool => write!(f, "Bool"),
+      SmtSort::Int  => write!(f, "Int"),
+      SmtSort::Real => write!(f, "Real"),
+    }
+  }
+}
+
+/// Interpreted operators, named after their SMT-LIB 2 counterparts
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SmtOperator {
+  // Arithmetic
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Neg,
+
+  // Comparison
+  Eq,
+  Distinct,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+
+  // Logic
+  Not,
+  And,
+  Or,
+  Implies,
+  Ite,
+}
+
+impl SmtOperator {
+  /// The SMT-LIB 2 name of the operator
+  pub fn name(&self) -> &'static str {
+    match self {
+      SmtOperator::Add      => "+",
+      SmtOperator::Sub      => "-",
+      SmtOperator::Mul      => "*",
+      SmtOperator::Div      => "div",
+      SmtOperator::Neg      => "-",
+      SmtOperator::Eq       => "=",
+      SmtOperator::Distinct => "distinct",
+      SmtOperator::Lt       => "<",
+      SmtOperator::Le       => "<=",
+      SmtOperator::Gt       => ">",
+      SmtOperator::Ge       => ">=",
+      SmtOperator::Not      => "not",
+      SmtOperator::And      => "and",
+      SmtOperator::Or       => "or",
+      SmtOperator::Implies  => "=>",
+      SmtOperator::Ite      => "ite",
+    }
+  }
+}
+
+/// A solver-independent formula or expression
+#[derive(Clone, PartialEq, Debug)]
+pub enum SmtTerm {
+  Bool(bool),
+  Int(i64),
+  Real(f64),
+  Variable(IString, SmtSort),
+  Apply(SmtOperator, Vec<SmtTerm>),
+}
+
+impl SmtTerm {
+  /// The variables occurring in `self`, each once, in the order they first occur
+  pub fn variables(&self) -> Vec<(IString, SmtSort)> {
+    fn collect(term: &SmtTerm, variables: &mut Vec<(IString, SmtSort)>) {
+      match term {
+        SmtTerm::Variable(name, sort) if !variables.iter().any(|(seen, _)| seen == name) => {
+          variables.push((name.clone(), *sort));
+        }
+        SmtTerm::Apply(_, args) => {
+          for arg in args {
+            collect(arg, variables);
+          }
+        }
+        _ => {}
+      }
+    }
+
+    let mut variables = Vec::new();
+    collect(self, &mut variables);
+    variables
+  }
+
+  /// An SMT-LIB 2 script that declares the variables of `self`, asserts it, and checks satisfiability.
+  pub fn to_smt_lib(&self) -> String {
+    let mut script = String::new();
+    for (name, sort) in self.variables() {
+      script.push_str(format!("(declare-const |{}| {})\n", name, sort).as_str());
+    }
+    script.push_str(format!("(assert {})\n(check-sat)\n", self).as_str());
+
+    script
+  }
+}
+
+/// Writes the term as an SMT-LIB 2 expression.
+impl Display for SmtTerm {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      SmtTerm::Bool(value)           => write!(f, "{}", value),
+      SmtTerm::Int(value) if *value < 0 => write!(f, "(- {})", value.unsigned_abs()),
+      SmtTerm::Int(value)            => write!(f, "{}", value),
+      SmtTerm::Real(value) if *value < 0.0 => write!(f, "(- {:?})", -value),
+      SmtTerm::Real(value)           => write!(f, "{:?}", value),
+      SmtTerm::Variable(name, _)     => write!(f, "|{}|", name),
+      SmtTerm::Apply(operator, args) => {
+        write!(f, "({}", operator.name())?;
+        for arg in args {
+          write!(f, " {}", arg)?;
+        }
+        write!(f, ")")
+      }
+    }
+  }
+}
+
+/// The answer to a satisfiability query
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SmtResult {
+  Sat,
+  Unsat,
+  Unknown,
+}
+
+/// Implemented by SMT solvers, or by adapters for them.
+pub trait SmtSolver {
+  /// Decides whether `formula` is satisfiable.
+  fn check_sat(&mut self, formula: &SmtTerm) -> SmtResult;
+}
+
+pub enum SmtError {
+  /// The term has a symbol the signature doesn't interpret.
+  UninterpretedSymbol(IString),
+  /// The condition is of a kind that has no SMT translation, like a rewrite condition.
+  UnsupportedCondition(String),
+}
+
+impl Display for SmtError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      SmtError::UninterpretedSymbol(name)     => write!(f, "the symbol {} has no SMT interpretation", name),
+      SmtError::UnsupportedCondition(condition) => write!(f, "the condition {} has no SMT translation", condition),
+    }
+  }
+}
+
+impl core::fmt::Debug for SmtError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    Display::fmt(self, f)
+  }
+}
+
+impl Error for SmtError {}
+
+/// The interpretation of module symbols in the SMT theories
+#[derive(Default)]
+pub struct SmtSignature {
+  operators: HashMap<SymbolPtr, SmtOperator>,
+  literals : HashMap<SymbolPtr, SmtTerm>,
+  variables: HashMap<SymbolPtr, SmtSort>,
+  /// The Boolean constant `true`, for translating conditions of the form `expr = true`
+  true_symbol: Option<SymbolPtr>,
+}
+
+impl SmtSignature {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Interprets `symbol` as `operator`.
+  pub fn operator(&mut self, symbol: SymbolPtr, operator: SmtOperator) {
+    self.operators.insert(symbol, operator);
+  }
+
+  /// Interprets the constant `symbol` as `value`. If `value` is `SmtTerm::Bool(true)`, an equality condition with
+  /// `symbol` on the right-hand side is translated as its left-hand side alone.
+  pub fn literal(&mut self, symbol: SymbolPtr, value: SmtTerm) {
+    if value == SmtTerm::Bool(true) {
+      self.true_symbol = Some(symbol);
+    }
+    self.literals.insert(symbol, value);
+  }
+
+  /// Gives the variable `symbol` the SMT sort `sort`.
+  pub fn variable(&mut self, symbol: SymbolPtr, sort: SmtSort) {
+    self.variables.insert(symbol, sort);
+  }
+
+  /// Translates `term`, all of whose symbols must be interpreted.
+  pub fn translate(&self, term: &dyn Term) -> Result<SmtTerm, SmtError> {
+    let symbol = term.symbol();
+
+    if term.is_variable() {
+      return match self.variables.get(&symbol) {
+        Some(sort) => Ok(SmtTerm::Variable(term.symbol_ref().name.clone(), *sort)),
+        None       => Err(SmtError::UninterpretedSymbol(term.symbol_ref().name.clone())),
+      };
+    }
+
+    if let Some(literal) = self.literals.get(&symbol) {
+      return Ok(literal.clone());
+    }
+
+    match self.operators.get(&symbol) {
+      Some(operator) => {
+        let args = term.iter_args().map(|arg| self.translate(arg)).collect::<Result<Vec<_>, _>>()?;
+        Ok(SmtTerm::Apply(*operator, args))
+      }
+      None => Err(SmtError::UninterpretedSymbol(term.symbol_ref().name.clone())),
+    }
+  }
+
+  /// Translates the DAG rooted at `node`, all of whose symbols must be interpreted. Integer and float data atoms
+  /// translate to their values.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn translate_dag(&self, node: DagNodePtr) -> Result<SmtTerm, SmtError> {
+    let node_ref = unsafe{ &*node };
+    if let Some(integer) = node_ref.as_data::<IntegerAtom>() {
+      return Ok(SmtTerm::Int(integer.0));
+    }
+    if let Some(float) = node_ref.as_data::<FloatAtom>() {
+      return Ok(SmtTerm::Real(float.0));
+    }
+
+    let symbol = node_ref.symbol();
+    if node_ref.symbol_ref().is_variable() {
+      return match self.variables.get(&symbol) {
+        Some(sort) => Ok(SmtTerm::Variable(node_ref.symbol_ref().name.clone(), *sort)),
+        None       => Err(SmtError::UninterpretedSymbol(node_ref.symbol_ref().name.clone())),
+      };
+    }
+
+    if let Some(literal) = self.literals.get(&symbol) {
+      return Ok(literal.clone());
+    }
+
+    match self.operators.get(&symbol) {
+      Some(operator) => {
+        let args = node_ref.iter_args().map(|arg| self.translate_dag(arg)).collect::<Result<Vec<_>, _>>()?;
+        Ok(SmtTerm::Apply(*operator, args))
+      }
+      None => Err(SmtError::UninterpretedSymbol(node_ref.symbol_ref().name.clone())),
+    }
+  }
+
+  /// Translates an equality condition. Boolean conditions, `expr = true`, translate to `expr`.
+  pub fn translate_condition(&self, condition: &Condition) -> Result<SmtTerm, SmtError> {
+    match condition {
+      Condition::Equality { lhs_term, rhs_term } => {
+        let lhs = || self.translate(lhs_term.as_ref());
+        self.equality(rhs_term.symbol(), lhs, || self.translate(rhs_term.as_ref()))
+      }
+
+      _ => Err(SmtError::UnsupportedCondition(condition.to_string())),
+    }
+  }
+
+  /// Translates the equality of the DAGs `lhs` and `rhs`, like `translate_condition`.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
+  pub fn translate_equality(&self, lhs: DagNodePtr, rhs: DagNodePtr) -> Result<SmtTerm, SmtError> {
+    self.equality(unsafe{ &*rhs }.symbol(), || self.translate_dag(lhs), || self.translate_dag(rhs))
+  }
+
+  fn equality(
+    &self,
+    rhs_symbol: SymbolPtr,
+    lhs       : impl FnOnce() -> Result<SmtTerm, SmtError>,
+    rhs       : impl FnOnce() -> Result<SmtTerm, SmtError>
+  ) -> Result<SmtTerm, SmtError>
+  {
+    if self.true_symbol.is_some_and(|symbol| core::ptr::addr_eq(rhs_symbol, symbol)) {
+      return lhs();
+    }
+
+    Ok(SmtTerm::Apply(SmtOperator::Eq, vec![lhs()?, rhs()?]))
+  }
+
+  /// Decides whether the conjunction of `conditions` is satisfiable.
+  pub fn check_conditions<'c>(
+    &self,
+    conditions: impl IntoIterator<Item = &'c Box<Condition>>,
+    solver    : &mut dyn SmtSolver
+  ) -> Result<SmtResult, SmtError> {
+    let conjuncts = conditions
+        .into_iter()
+        .map(|condition| self.translate_condition(condition))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let formula = match conjuncts.len() {
+      0 => SmtTerm::Bool(true),
+      1 => conjuncts.into_iter().next().unwrap(),
+      _ => SmtTerm::Apply(SmtOperator::And, conjuncts),
+    };
+
+    Ok(solver.check_sat(&formula))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      data_theory::DataDagNode,
+      float::FloatAtom,
+      free_theory::{FreeDagNode, FreeTerm},
+      integer::{integer, IntegerAtom},
+      symbol::{Symbol, SymbolPtr},
+      term::BxTerm,
+      variable_theory::VariableTerm
+    },
+    core::{
+      parser::{parse_module, parse_term},
+      pre_equation::{condition::Condition, PreEquation, PreEquationKind},
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext,
+      smt::{SmtError, SmtOperator, SmtResult, SmtSignature, SmtSolver, SmtSort, SmtTerm},
+      RootContainer
+    }
+  };
+
+  /// Decides linear integer formulas by trying every assignment of small values to the variables.
+  struct BoundedSolver;
+
+  impl BoundedSolver {
+    fn eval(term: &SmtTerm, assignment: &[(IString, i64)]) -> i64 {
+      let args = |args: &[SmtTerm]| args.iter().map(|arg| Self::eval(arg, assignment)).collect::<Vec<_>>();
+      match term {
+        SmtTerm::Bool(value)       => *value as i64,
+        SmtTerm::Int(value)        => *value,
+        SmtTerm::Variable(name, _) => assignment.iter().find(|(variable, _)| variable == name).unwrap().1,
+        SmtTerm::Apply(operator, operands) => {
+          let values = args(operands);
+          match operator {
+            SmtOperator::Add => values.iter().sum(),
+            SmtOperator::Gt  => (values[0] > values[1]) as i64,
+            SmtOperator::Eq  => (values[0] == values[1]) as i64,
+            SmtOperator::And => values.iter().all(|value| *value != 0) as i64,
+            _                => unimplemented!(),
+          }
+        }
+        SmtTerm::Real(_)           => unimplemented!(),
+      }
+    }
+  }
+
+  impl SmtSolver for BoundedSolver {
+    fn check_sat(&mut self, formula: &SmtTerm) -> SmtResult {
+      let variables = formula.variables();
+      let count     = variables.len() as u32;
+      for index in 0..11i64.pow(count) {
+        let assignment: Vec<(IString, i64)> = variables
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.clone(), index / 11i64.pow(i as u32) % 11))
+            .collect();
+        if Self::eval(formula, &assignment) != 0 {
+          return SmtResult::Sat;
+        }
+      }
+      SmtResult::Unsat
+    }
+  }
+
+  #[test]
+  fn conditions_are_discharged_by_the_solver() {
+    let mut plus    = Symbol::new(IString::from("+"), Arity::Value(2));
+    let mut greater = Symbol::new(IString::from(">"), Arity::Value(2));
+    let mut zero    = Symbol::new(IString::from("0"), Arity::Value(0));
+    let mut ten     = Symbol::new(IString::from("10"), Arity::Value(0));
+    let mut twenty  = Symbol::new(IString::from("25"), Arity::Value(0));
+    let mut true_   = Symbol::new(IString::from("true"), Arity::Value(0));
+    let mut x       = Symbol::new_variable(IString::from("X"));
+    let mut y       = Symbol::new_variable(IString::from("Y"));
+    let mut list    = Symbol::new(IString::from("list"), Arity::Value(0));
+    let (plus, greater, zero, ten, twenty, true_, x, y, list):
+        (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr)
+        = (&mut plus, &mut greater, &mut zero, &mut ten, &mut twenty, &mut true_, &mut x, &mut y, &mut list);
+
+    let mut signature = SmtSignature::new();
+    signature.operator(plus, SmtOperator::Add);
+    signature.operator(greater, SmtOperator::Gt);
+    signature.literal(zero, SmtTerm::Int(0));
+    signature.literal(ten, SmtTerm::Int(10));
+    signature.literal(twenty, SmtTerm::Int(25));
+    signature.literal(true_, SmtTerm::Bool(true));
+    signature.variable(x, SmtSort::Int);
+    signature.variable(y, SmtSort::Int);
+
+    let var = |symbol| -> BxTerm { Box::new(VariableTerm::new(symbol)) };
+    // X > 0 = true /\ X + Y = `sum`
+    let conditions = |sum| vec![
+      Box::new(Condition::Equality {
+        lhs_term: FreeTerm::with_args(greater, vec![var(x), FreeTerm::with_args(zero, vec![])]),
+        rhs_term: FreeTerm::with_args(true_, vec![])
+      }),
+      Box::new(Condition::Equality {
+        lhs_term: FreeTerm::with_args(plus, vec![var(x), var(y)]),
+        rhs_term: FreeTerm::with_args(sum, vec![])
+      }),
+    ];
+
+    let satisfiable = conditions(ten);
+    assert_eq!(signature.check_conditions(&satisfiable, &mut BoundedSolver).unwrap(), SmtResult::Sat);
+    assert_eq!(
+      signature.translate_condition(&satisfiable[0]).unwrap().to_smt_lib(),
+      "(declare-const |X| Int)\n(assert (> |X| 0))\n(check-sat)\n"
+    );
+
+    // Within the solver's bounds, X + Y is at most 20.
+    assert_eq!(signature.check_conditions(&conditions(twenty), &mut BoundedSolver).unwrap(), SmtResult::Unsat);
+
+    // Uninterpreted symbols are reported rather than guessed at.
+    let list_term     = FreeTerm::with_args(list, vec![]);
+    let uninterpreted = vec![Box::new(Condition::Equality { lhs_term: var(x), rhs_term: list_term })];
+    assert!(matches!(
+      signature.check_conditions(&uninterpreted, &mut BoundedSolver),
+      Err(SmtError::UninterpretedSymbol(name)) if name.as_ref() == "list"
+    ));
+  }
+
+  #[test]
+  fn reduction_asks_the_solver_about_conditions() {
+    let mut module = parse_module("
+      mod POSITIVE is
+        sorts Int Bool Answer .
+        op gt : Int Int -> Bool .
+        op zero : -> Int .
+        op true : -> Bool .
+        op positive : Int -> Answer .
+        op yes : -> Answer .
+        var X : Int .
+      endm
+    ").unwrap();
+    let int = module.arena.sorts.get_by_name(&"Int".into()).unwrap();
+    module.add_sort_predicate(int, |atom| atom.as_any().is::<IntegerAtom>());
+    let term      = |text: &str| parse_term(&module, text).unwrap();
+    let condition = Condition::Equality { lhs_term: term("gt(X, zero)"), rhs_term: term("true") };
+    let kind      = PreEquationKind::Equation { rhs_term: term("yes") };
+    let equation  = PreEquation::new(None, term("positive(X)"), kind, vec![Box::new(condition)]);
+    module.add_statement(equation).unwrap();
+
+    let symbol = |name: &str| module.arena.symbol(&name.into()).unwrap();
+    let mut signature = SmtSignature::new();
+    signature.operator(symbol("gt"), SmtOperator::Gt);
+    signature.literal(symbol("zero"), SmtTerm::Int(0));
+    signature.literal(symbol("true"), SmtTerm::Bool(true));
+
+    let positive = |value: i64| {
+      let node = FreeDagNode::new(symbol("positive"));
+      unsafe{ &mut *node }.insert_child(DataDagNode::new(integer(value))).unwrap();
+      node
+    };
+    let subject = positive(5);
+    let _root   = RootContainer::new(subject);
+    assert_eq!(signature.translate_dag(unsafe{ &*subject }.iter_args().next().unwrap()).unwrap(), SmtTerm::Int(5));
+    assert_eq!(signature.translate_dag(DataDagNode::new(Box::new(FloatAtom(0.5)))).unwrap(), SmtTerm::Real(0.5));
+
+    // Without a solver, nothing reduces `gt(5, zero)`, so the condition fails.
+    let mut context = RewritingContext::new(&module);
+    assert_ne!(dag_to_string(context.reduce(subject)), "yes");
+
+    let mut context = RewritingContext::new(&module);
+    context.set_smt_solver(signature, BoundedSolver);
+    assert_eq!(dag_to_string(context.reduce(positive(5))), "yes");
+    assert_ne!(dag_to_string(context.reduce(positive(-3))), "yes");
+  }
+}
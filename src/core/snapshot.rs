@@ -0,0 +1,1153 @@
+/*!
+
+Snapshots save the state of a rewriting session—a module, the term being worked on, and the hash-cons table of
+`ArcTerm`s—to disk so that a long-running session can be restored after a restart.
+
+## Format
+
+A snapshot is line-oriented text. The first line identifies the format and its version. Sorts and symbols are written
+once, in tables, and referred to everywhere else by their position in their table. Names are quoted with Rust string
+escapes.
+
+```text
+mod2lib snapshot 2
+module "NAT"
+sort "Zero"
+sort "Nat"
+subsort 0 1
+symbol op 0 0 "0"
+symbol op 2 0 "plus"
+symbol var 0 0 "X"
+symbol op 1 0 "s"
+declaration 0 1 0
+declaration 1 0 1 1 1
+declaration 2 0 1
+declaration 3 1 1 1
+equation 0 - 1(0 2) 2
+equation 0 - 1(2 3(0)) 0 if = 2 0
+membership 0 - 3(0) 1
+node 0 r
+node 3 r 0
+term 1
+arcterm "0"
+arcterm "s" 0
+```
+
+After the sorts and the subsort relation come the symbols and their declarations, which give the symbol, whether the
+declaration is a constructor (`1`) or not (`0`), the range, and the domain. A variable's sort is its only declaration.
+Symbols may also have `strategy`, `identity`, and `latex` lines.
+
+Statement lines give the statement's attributes, its label (`-` if it has none), its left-hand side, and its
+right-hand side or, for membership axioms, its sort. Conditions follow `if`, each one an operator (`=`, `:`, `:=`, or
+`=>`) and its two sides. Terms are written as a symbol index followed by the parenthesized arguments, if any. The
+current term is written as a table of DAG nodes in which every node comes after its arguments, so that shared subterms
+stay shared when the snapshot is restored. A node's flags are `r` if the node is reduced and `-` otherwise. The terms of
+the hash-cons table come last, each after its arguments, which are given by their positions among the `arcterm` lines.
+
+Restoring a snapshot allocates fresh sorts and symbols, which the restored module owns, closes the module's sort set,
+and compiles its statements. Snapshots of version 1, which predate sorts, can still be restored.
+
+## Limitations
+
+Only free and variable terms are supported, and sort tests only of sorts, not of kinds or functors. Data atoms, sort
+predicates, and submodules are not captured yet; a module or term that has any of them cannot be saved rather than
+being saved incompletely.
+
+*/
+
+use std::{
+  error::Error,
+  fmt::{Display, Formatter, Write as FmtWrite},
+  fs::File,
+  io::{BufRead, BufReader, BufWriter, Read, Write},
+  path::Path
+};
+
+use crate::{
+  abstractions::{
+    intern,
+    HashMap,
+    IString
+  },
+  api::{
+    Arity,
+    dag_node::DagNodePtr,
+    free_theory::FreeTerm,
+    symbol::{Symbol, SymbolAttributes, SymbolPtr, SymbolType},
+    term::{BxTerm, Term},
+    variable_theory::VariableTerm
+  },
+  core::{
+    arc_term::{ArcTerm, ArcTermTable},
+    module::Module,
+    pre_equation::{
+      condition::{Condition, Conditions},
+      PreEquation,
+      PreEquationAttribute,
+      PreEquationAttributes,
+      PreEquationKind
+    },
+    sort::{sort_spec::SortSpec, SortPtr},
+    theory_table::build_node,
+    RootContainer
+  }
+};
+
+/// The first line of every snapshot
+const HEADER : &str  = "mod2lib snapshot";
+/// The version of the format written by this version of the library
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+  Io(std::io::Error),
+  /// The module has something snapshots can't capture yet.
+  Unsupported(&'static str),
+  /// The snapshot could not be parsed.
+  Malformed {
+    line   : usize,
+    message: String
+  },
+}
+
+impl Display for SnapshotError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SnapshotError::Io(error)                  => write!(f, "snapshot I/O failed: {}", error),
+      SnapshotError::Unsupported(what)          => write!(f, "snapshots do not support {} yet", what),
+      SnapshotError::Malformed { line, message } => write!(f, "malformed snapshot at line {}: {}", line, message),
+    }
+  }
+}
+
+impl Error for SnapshotError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      SnapshotError::Io(error) => Some(error),
+      _                        => None
+    }
+  }
+}
+
+impl From<std::io::Error> for SnapshotError {
+  fn from(error: std::io::Error) -> Self {
+    SnapshotError::Io(error)
+  }
+}
+
+/// A restored session: a compiled module, the term that was being worked on, if any, and the hash-cons table. The
+/// term is protected from garbage collection for as long as the snapshot lives.
+pub struct Snapshot {
+  // The root must be dropped before the module that owns the symbols.
+  root      : Option<Box<RootContainer>>,
+  term      : Option<DagNodePtr>,
+  pub module: Module,
+  pub terms : ArcTermTable,
+}
+
+impl Snapshot {
+  #[inline(always)]
+  pub fn term(&self) -> Option<DagNodePtr> {
+    self.term
+  }
+
+  /// Saves `module` and `term` to the file at `path`, replacing it if it exists.
+  pub fn save(path: impl AsRef<Path>, module: &Module, term: Option<DagNodePtr>) -> Result<(), SnapshotError> {
+    Snapshot::save_with_terms(path, module, term, &ArcTermTable::new())
+  }
+
+  /// Saves `module`, `term`, and the terms of `terms` to the file at `path`, replacing it if it exists.
+  pub fn save_with_terms(
+    path  : impl AsRef<Path>,
+    module: &Module,
+    term  : Option<DagNodePtr>,
+    terms : &ArcTermTable
+  ) -> Result<(), SnapshotError>
+  {
+    let mut writer = BufWriter::new(File::create(path)?);
+    Snapshot::write_with_terms(&mut writer, module, term, terms)?;
+    writer.flush()?;
+
+    Ok(())
+  }
+
+  /// Writes `module` and `term` to `writer`.
+  pub fn write(writer: &mut impl Write, module: &Module, term: Option<DagNodePtr>) -> Result<(), SnapshotError> {
+    Snapshot::write_with_terms(writer, module, term, &ArcTermTable::new())
+  }
+
+  /// Writes `module`, `term`, and the terms of `terms` to `writer`.
+  pub fn write_with_terms(
+    writer: &mut impl Write,
+    module: &Module,
+    term  : Option<DagNodePtr>,
+    terms : &ArcTermTable
+  ) -> Result<(), SnapshotError>
+  {
+    writer.write_all(SnapshotWriter::new(module)?.write(module, term, terms)?.as_bytes())?;
+    Ok(())
+  }
+
+  /// Restores the snapshot in the file at `path`.
+  pub fn load(path: impl AsRef<Path>) -> Result<Snapshot, SnapshotError> {
+    Snapshot::read(File::open(path)?)
+  }
+
+  /// Restores a snapshot from `reader`.
+  pub fn read(reader: impl Read) -> Result<Snapshot, SnapshotError> {
+    let mut restorer = SnapshotReader::default();
+    for (index, line) in BufReader::new(reader).lines().enumerate() {
+      restorer.read_line(index + 1, line?.as_str())?;
+    }
+
+    restorer.finish()
+  }
+}
+
+// region Writing
+
+/// Assigns sorts and symbols their indices in their tables and renders the snapshot.
+struct SnapshotWriter {
+  sorts  : HashMap<SortPtr, usize>,
+  symbols: Vec<SymbolPtr>,
+  indices: HashMap<SymbolPtr, usize>,
+}
+
+impl SnapshotWriter {
+  fn new(module: &Module) -> Result<Self, SnapshotError> {
+    if !module.submodules.is_empty() {
+      return Err(SnapshotError::Unsupported("submodules"));
+    }
+    if !module.sort_predicates.is_empty() {
+      return Err(SnapshotError::Unsupported("sort predicates"));
+    }
+
+    Ok(
+      SnapshotWriter {
+        sorts  : module.arena.sorts.iter().enumerate().map(|(index, (_, sort))| (sort, index)).collect(),
+        symbols: Vec::new(),
+        indices: HashMap::default(),
+      }
+    )
+  }
+
+  fn symbol_index(&mut self, symbol: SymbolPtr) -> usize {
+    *self.indices.entry(symbol).or_insert_with(
+      || {
+        self.symbols.push(symbol);
+        self.symbols.len() - 1
+      }
+    )
+  }
+
+  fn sort_index(&self, sort: SortPtr) -> Result<usize, SnapshotError> {
+    self.sorts.get(&sort).copied().ok_or(SnapshotError::Unsupported("sorts the module doesn't declare"))
+  }
+
+  fn write_sort_spec(&self, sort_spec: &SortSpec, out: &mut String) -> Result<(), SnapshotError> {
+    match sort_spec {
+      SortSpec::Sort(sort) => write!(out, "{}", self.sort_index(*sort)?).unwrap(),
+      _                    => return Err(SnapshotError::Unsupported("sort tests of anything but sorts")),
+    }
+    Ok(())
+  }
+
+  fn write_term(&mut self, term: &dyn Term, out: &mut String) -> Result<(), SnapshotError> {
+    if term.as_any().downcast_ref::<FreeTerm>().is_none() && !term.is_variable() {
+      return Err(SnapshotError::Unsupported("terms outside the free theory"));
+    }
+
+    let index = self.symbol_index(term.symbol());
+    write!(out, "{}", index).unwrap();
+    if term.iter_args().next().is_some() {
+      out.push('(');
+      for (i, arg) in term.iter_args().enumerate() {
+        if i > 0 {
+          out.push(' ');
+        }
+        self.write_term(arg, out)?;
+      }
+      out.push(')');
+    }
+
+    Ok(())
+  }
+
+  fn write_condition(&mut self, condition: &Condition, out: &mut String) -> Result<(), SnapshotError> {
+    let (operator, lhs_term) = match condition {
+      Condition::Equality { lhs_term, .. }       => ("=", lhs_term),
+      Condition::SortMembership { lhs_term, .. } => (":", lhs_term),
+      Condition::Match { lhs_term, .. }          => (":=", lhs_term),
+      Condition::Rewrite { lhs_term, .. }        => ("=>", lhs_term),
+    };
+
+    write!(out, " {} ", operator).unwrap();
+    self.write_term(lhs_term.as_ref(), out)?;
+    out.push(' ');
+    match condition {
+      Condition::SortMembership { sort, .. } => self.write_sort_spec(sort, out),
+      Condition::Equality { rhs_term, .. }
+      | Condition::Match { rhs_term, .. }
+      | Condition::Rewrite { rhs_term, .. }  => self.write_term(rhs_term.as_ref(), out),
+    }
+  }
+
+  fn write_statement(&mut self, statement: &PreEquation, out: &mut String) -> Result<(), SnapshotError> {
+    let keyword = match &statement.kind {
+      PreEquationKind::Equation { .. }   => "equation",
+      PreEquationKind::Rule { .. }       => "rule",
+      PreEquationKind::Membership { .. } => "membership",
+    };
+
+    let mut attributes = statement.attributes;
+    attributes.remove(PreEquationAttribute::Compiled);
+    write!(out, "{} {} ", keyword, attributes.bits()).unwrap();
+    match &statement.name {
+      Some(name) => write!(out, "{:?} ", name.as_ref()).unwrap(),
+      None       => out.push_str("- "),
+    }
+    self.write_term(statement.lhs_term.as_ref(), out)?;
+    out.push(' ');
+    match &statement.kind {
+      PreEquationKind::Equation { rhs_term }
+      | PreEquationKind::Rule { rhs_term }      => self.write_term(rhs_term.as_ref(), out)?,
+      PreEquationKind::Membership { sort_spec } => self.write_sort_spec(sort_spec, out)?,
+    }
+
+    if !statement.conditions.is_empty() {
+      out.push_str(" if");
+      for condition in statement.conditions.iter() {
+        self.write_condition(condition, out)?;
+      }
+    }
+    out.push('\n');
+
+    Ok(())
+  }
+
+  /// Writes the nodes of the DAG below `node` that aren't in `nodes` yet, arguments first. Returns the index of `node`.
+  fn write_node(
+    &mut self,
+    node : DagNodePtr,
+    nodes: &mut HashMap<*const (), usize>,
+    out  : &mut String
+  ) -> Result<usize, SnapshotError>
+  {
+    if let Some(&index) = nodes.get(&(node as *const ())) {
+      return Ok(index);
+    }
+
+    let node_ref = unsafe{ &*node };
+    if node_ref.symbol_ref().symbol_type == SymbolType::Data {
+      return Err(SnapshotError::Unsupported("data atoms"));
+    }
+    let args = node_ref.iter_args().map(|arg| self.write_node(arg, nodes, out)).collect::<Result<Vec<_>, _>>()?;
+    let symbol = self.symbol_index(node_ref.symbol());
+    let flags  = if node_ref.is_reduced() { "r" } else { "-" };
+
+    write!(out, "node {} {}", symbol, flags).unwrap();
+    for arg in args {
+      write!(out, " {}", arg).unwrap();
+    }
+    out.push('\n');
+
+    let index = nodes.len();
+    nodes.insert(node as *const (), index);
+    Ok(index)
+  }
+
+  fn write(mut self, module: &Module, term: Option<DagNodePtr>, terms: &ArcTermTable) -> Result<String, SnapshotError> {
+    // Symbols are only known after the statements and nodes that use them have been visited, so the body is rendered
+    // first.
+    let mut body = String::new();
+    for statement in module.equations.iter().chain(module.rules.iter()).chain(module.membership.iter()) {
+      self.write_statement(statement, &mut body)?;
+    }
+    if let Some(term) = term {
+      let mut nodes = HashMap::default();
+      let index     = self.write_node(term, &mut nodes, &mut body)?;
+      writeln!(body, "term {}", index).unwrap();
+    }
+
+    // Symbols the module declares but no statement uses, in a deterministic order
+    let mut unused: Vec<(&IString, SymbolPtr)> = module.arena.symbols
+        .iter()
+        .filter(|(_, symbol)| !self.indices.contains_key(*symbol))
+        .map(|(name, symbol)| (name, *symbol))
+        .collect();
+    unused.sort_by_key(|(name, _)| *name);
+    for (_, symbol) in unused {
+      self.symbol_index(symbol);
+    }
+    // Identities are referred to by index, so symbols outside the arena need indices before the table is written.
+    let mut identities: Vec<(SymbolPtr, SymbolPtr)> =
+        module.identities.iter().map(|(&symbol, &identity)| (symbol, identity)).collect();
+    identities.sort_by_key(|&(symbol, _)| unsafe{ &*symbol }.name.clone());
+    for &(symbol, identity) in identities.iter() {
+      self.symbol_index(symbol);
+      self.symbol_index(identity);
+    }
+
+    let mut out = String::new();
+    writeln!(out, "{} {}", HEADER, SNAPSHOT_VERSION).unwrap();
+    writeln!(out, "module {:?}", module.name.as_ref()).unwrap();
+
+    for (name, _) in module.arena.sorts.iter() {
+      writeln!(out, "sort {:?}", name.as_ref()).unwrap();
+    }
+    for (index, (_, sort)) in module.arena.sorts.iter().enumerate() {
+      for &subsort in unsafe{ &*sort }.subsorts.iter() {
+        writeln!(out, "subsort {} {}", self.sort_index(subsort)?, index).unwrap();
+      }
+    }
+
+    let mut names: HashMap<IString, SymbolPtr> = HashMap::default();
+    for &symbol in self.symbols.iter() {
+      let symbol_ref = unsafe{ &*symbol };
+      if names.insert(symbol_ref.name.clone(), symbol).is_some() {
+        return Err(SnapshotError::Unsupported("distinct symbols with the same name"));
+      }
+
+      let symbol_type = match symbol_ref.symbol_type {
+        SymbolType::Standard => "op",
+        SymbolType::Variable => "var",
+        SymbolType::Operator => "operator",
+        SymbolType::Data     => "data",
+      };
+      writeln!(
+        out,
+        "symbol {} {} {} {:?}",
+        symbol_type,
+        symbol_ref.arity,
+        symbol_ref.attributes.bits(),
+        symbol_ref.name.as_ref()
+      ).unwrap();
+    }
+
+    for (index, &symbol) in self.symbols.iter().enumerate() {
+      let symbol_ref = unsafe{ &*symbol };
+      for declaration in symbol_ref.sort_table.declarations() {
+        let range = self.sort_index(declaration.range)?;
+        write!(out, "declaration {} {} {}", index, declaration.is_constructor as u8, range).unwrap();
+        for &sort in declaration.domain.iter() {
+          write!(out, " {}", self.sort_index(sort)?).unwrap();
+        }
+        out.push('\n');
+      }
+      if !symbol_ref.strategy.is_empty() {
+        write!(out, "strategy {}", index).unwrap();
+        for step in symbol_ref.strategy.iter() {
+          write!(out, " {}", step).unwrap();
+        }
+        out.push('\n');
+      }
+      if let Some(latex) = &symbol_ref.latex {
+        writeln!(out, "latex {} {:?}", index, latex.as_ref()).unwrap();
+      }
+    }
+    for (symbol, identity) in identities {
+      writeln!(out, "identity {} {}", self.indices[&symbol], self.indices[&identity]).unwrap();
+    }
+
+    out.push_str(body.as_str());
+
+    let mut arc_terms: HashMap<ArcTerm, usize> = HashMap::default();
+    for term in terms.terms_bottom_up() {
+      write!(out, "arcterm {:?}", term.symbol().as_ref()).unwrap();
+      for arg in term.args() {
+        write!(out, " {}", arc_terms[arg]).unwrap();
+      }
+      out.push('\n');
+      let index = arc_terms.len();
+      arc_terms.insert(term, index);
+    }
+
+    Ok(out)
+  }
+}
+
+// endregion Writing
+
+// region Reading
+
+/// Splits a line into tokens: quoted strings (with `\\`, `\"`, `\n`, and `\t` escapes), parentheses, and words.
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+  let mut tokens = Vec::new();
+  let mut chars  = line.chars().peekable();
+
+  while let Some(&c) = chars.peek() {
+    match c {
+      ' ' | '\t' => { chars.next(); }
+
+      '(' | ')' => {
+        chars.next();
+        tokens.push(if c == '(' { Token::Open } else { Token::Close });
+      }
+
+      '"' => {
+        chars.next();
+        let mut string = String::new();
+        loop {
+          match chars.next() {
+            None       => return Err("unterminated string".to_string()),
+            Some('"')  => break,
+            Some('\\') => {
+              match chars.next() {
+                Some('\\') => string.push('\\'),
+                Some('"')  => string.push('"'),
+                Some('n')  => string.push('\n'),
+                Some('t')  => string.push('\t'),
+                other      => return Err(format!("unsupported escape {:?}", other)),
+              }
+            }
+            Some(c)    => string.push(c),
+          }
+        }
+        tokens.push(Token::String(string));
+      }
+
+      _ => {
+        let mut word = String::new();
+        while let Some(&c) = chars.peek() {
+          if matches!(c, ' ' | '\t' | '(' | ')' | '"') {
+            break;
+          }
+          word.push(c);
+          chars.next();
+        }
+        tokens.push(Token::Word(word));
+      }
+    }
+  }
+
+  Ok(tokens)
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Token {
+  Word(String),
+  String(String),
+  Open,
+  Close,
+}
+
+#[derive(Default)]
+#[allow(clippy::vec_box)]
+struct SnapshotReader {
+  seen_header: bool,
+  module     : Module,
+  sorts      : Vec<SortPtr>,
+  symbols    : Vec<SymbolPtr>,
+  nodes      : Vec<DagNodePtr>,
+  term       : Option<DagNodePtr>,
+  terms      : ArcTermTable,
+  arc_terms  : Vec<ArcTerm>,
+  /// Nodes are unrooted while the snapshot is read, so they are rooted here as they are created. The GC never runs
+  /// while reading, but the roots keep the invariant simple.
+  roots      : Vec<Box<RootContainer>>,
+  line       : usize,
+}
+
+impl SnapshotReader {
+  fn error<T>(&self, message: impl Into<String>) -> Result<T, SnapshotError> {
+    Err(SnapshotError::Malformed { line: self.line, message: message.into() })
+  }
+
+  fn read_line(&mut self, line_number: usize, line: &str) -> Result<(), SnapshotError> {
+    self.line = line_number;
+    if line.trim().is_empty() {
+      return Ok(());
+    }
+
+    if !self.seen_header {
+      let version = line.strip_prefix(HEADER).map(|version| version.trim().parse::<u32>());
+      return match version {
+        // Version 1 is version 2 without sorts, conditions, membership axioms, and the hash-cons table.
+        Some(Ok(1 | SNAPSHOT_VERSION)) => {
+          self.seen_header = true;
+          Ok(())
+        }
+        Some(Ok(version)) => self.error(format!("unsupported snapshot version {}", version)),
+        _                 => self.error("not a snapshot"),
+      };
+    }
+
+    let tokens = match tokenize(line) {
+      Ok(tokens)   => tokens,
+      Err(message) => return self.error(message),
+    };
+    let mut tokens = tokens.into_iter();
+
+    match tokens.next() {
+      Some(Token::Word(keyword)) => {
+        match keyword.as_str() {
+          "module"      => self.read_module(&mut tokens),
+          "sort"        => self.read_sort(&mut tokens),
+          "subsort"     => self.read_subsort(&mut tokens),
+          "symbol"      => self.read_symbol(&mut tokens),
+          "declaration" => self.read_declaration(&mut tokens),
+          "strategy"    => self.read_strategy(&mut tokens),
+          "latex"       => self.read_latex(&mut tokens),
+          "identity"    => self.read_identity(&mut tokens),
+          "equation"
+          | "rule"
+          | "membership"  => self.read_statement(&mut tokens, keyword.as_str()),
+          "node"        => self.read_node(&mut tokens),
+          "term"        => self.read_term_line(&mut tokens),
+          "arcterm"     => self.read_arc_term(&mut tokens),
+          _             => self.error(format!("unknown entry {}", keyword)),
+        }
+      }
+      _ => self.error("expected an entry keyword"),
+    }
+  }
+
+  fn word(&self, tokens: &mut impl Iterator<Item=Token>) -> Result<String, SnapshotError> {
+    match tokens.next() {
+      Some(Token::Word(word)) => Ok(word),
+      other                   => self.error(format!("expected a word, found {:?}", other)),
+    }
+  }
+
+  fn string(&self, tokens: &mut impl Iterator<Item=Token>) -> Result<String, SnapshotError> {
+    match tokens.next() {
+      Some(Token::String(string)) => Ok(string),
+      other                       => self.error(format!("expected a string, found {:?}", other)),
+    }
+  }
+
+  fn number<T: std::str::FromStr>(&self, word: &str) -> Result<T, SnapshotError> {
+    match word.parse::<T>() {
+      Ok(number) => Ok(number),
+      Err(_)     => self.error(format!("expected a number, found {}", word)),
+    }
+  }
+
+  fn symbol(&self, word: &str) -> Result<SymbolPtr, SnapshotError> {
+    let index: usize = self.number(word)?;
+    match self.symbols.get(index) {
+      Some(&symbol) => Ok(symbol),
+      None          => self.error(format!("undefined symbol {}", index)),
+    }
+  }
+
+  fn sort(&self, word: &str) -> Result<SortPtr, SnapshotError> {
+    let index: usize = self.number(word)?;
+    match self.sorts.get(index) {
+      Some(&sort) => Ok(sort),
+      None        => self.error(format!("undefined sort {}", index)),
+    }
+  }
+
+  fn end_of_line(&self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    match tokens.next() {
+      None  => Ok(()),
+      Some(token) => self.error(format!("unexpected {:?} at end of line", token)),
+    }
+  }
+
+  fn read_module(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    self.module.name = intern(self.string(tokens)?);
+    self.end_of_line(tokens)
+  }
+
+  fn read_sort(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let name = intern(self.string(tokens)?);
+    self.end_of_line(tokens)?;
+
+    if self.module.arena.sorts.get_by_name(&name).is_some() {
+      return self.error(format!("duplicate sort {}", name));
+    }
+    let sort = self.module.arena.sorts.get_or_create_sort(name);
+    self.sorts.push(sort);
+
+    Ok(())
+  }
+
+  fn read_subsort(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let subsort   = self.sort(self.word(tokens)?.as_str())?;
+    let supersort = self.sort(self.word(tokens)?.as_str())?;
+    self.end_of_line(tokens)?;
+
+    unsafe{ &mut *supersort }.insert_subsort(subsort);
+    Ok(())
+  }
+
+  fn read_symbol(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let symbol_type = match self.word(tokens)?.as_str() {
+      "op"       => SymbolType::Standard,
+      "var"      => SymbolType::Variable,
+      "operator" => SymbolType::Operator,
+      "data"     => SymbolType::Data,
+      other      => return self.error(format!("unknown symbol type {}", other)),
+    };
+    let arity = match self.word(tokens)?.parse::<Arity>() {
+      Ok(arity)  => arity,
+      Err(error) => return self.error(error.to_string()),
+    };
+    let attributes = match SymbolAttributes::from_bits(self.number(self.word(tokens)?.as_str())?) {
+      Ok(attributes) => attributes,
+      Err(_)         => return self.error("unknown symbol attributes"),
+    };
+    let name = intern(self.string(tokens)?);
+    self.end_of_line(tokens)?;
+
+    if self.module.arena.symbols.contains_key(&name) {
+      return self.error(format!("duplicate symbol {}", name));
+    }
+
+    let mut symbol         = Symbol::new(name.clone(), arity);
+    symbol.symbol_type     = symbol_type;
+    symbol.attributes      = attributes;
+    let symbol             = self.module.arena.add_symbol(symbol);
+    self.symbols.push(symbol);
+
+    Ok(())
+  }
+
+  fn read_declaration(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let symbol         = self.symbol(self.word(tokens)?.as_str())?;
+    let is_constructor = match self.word(tokens)?.as_str() {
+      "0"   => false,
+      "1"   => true,
+      other => return self.error(format!("expected 0 or 1, found {}", other)),
+    };
+    let range  = self.sort(self.word(tokens)?.as_str())?;
+    let domain = tokens.map(
+      |token| match token {
+        Token::Word(word) => self.sort(word.as_str()),
+        other             => self.error(format!("expected a sort index, found {:?}", other)),
+      }
+    ).collect::<Result<Vec<_>, _>>()?;
+
+    let symbol_ref = unsafe{ &mut *symbol };
+    let arity      = match symbol_ref.is_variable() {
+      true  => Some(0),
+      false => symbol_ref.arity.max_args(),
+    };
+    let first_domain = symbol_ref.sort_table.declarations().first().map(|declaration| declaration.domain.len());
+    if arity.is_some_and(|arity| arity != domain.len()) || first_domain.is_some_and(|len| len != domain.len()) {
+      return self.error(format!("{} declared with {} arguments", symbol_ref, domain.len()));
+    }
+    symbol_ref.sort_table.add_op_declaration(domain, range, is_constructor);
+
+    Ok(())
+  }
+
+  fn read_strategy(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let symbol   = self.symbol(self.word(tokens)?.as_str())?;
+    let strategy = tokens.map(
+      |token| match token {
+        Token::Word(word) => self.number(word.as_str()),
+        other             => self.error(format!("expected an argument index, found {:?}", other)),
+      }
+    ).collect::<Result<Vec<usize>, _>>()?;
+
+    match unsafe{ &mut *symbol }.set_strategy(strategy) {
+      Ok(())     => Ok(()),
+      Err(error) => self.error(error.to_string()),
+    }
+  }
+
+  fn read_latex(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let symbol = self.symbol(self.word(tokens)?.as_str())?;
+    let latex  = intern(self.string(tokens)?);
+    self.end_of_line(tokens)?;
+
+    unsafe{ &mut *symbol }.set_latex(latex);
+    Ok(())
+  }
+
+  fn read_identity(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let symbol   = self.symbol(self.word(tokens)?.as_str())?;
+    let identity = self.symbol(self.word(tokens)?.as_str())?;
+    self.end_of_line(tokens)?;
+
+    self.module.identities.insert(symbol, identity);
+    Ok(())
+  }
+
+  /// Reads a term, whose first token has already been taken from `tokens`.
+  fn read_term(&self, first: Option<Token>, tokens: &mut std::iter::Peekable<impl Iterator<Item=Token>>) -> Result<BxTerm, SnapshotError> {
+    let symbol = match first {
+      Some(Token::Word(word)) => self.symbol(word.as_str())?,
+      other                   => return self.error(format!("expected a term, found {:?}", other)),
+    };
+
+    let mut args = Vec::new();
+    if tokens.peek() == Some(&Token::Open) {
+      tokens.next();
+      loop {
+        match tokens.next() {
+          Some(Token::Close) => break,
+          token              => args.push(self.read_term(token, tokens)?),
+        }
+      }
+    }
+
+    let symbol_ref = unsafe{ &*symbol };
+    if symbol_ref.is_variable() {
+      if !args.is_empty() {
+        return self.error(format!("variable {} has arguments", symbol_ref.name));
+      }
+      return Ok(Box::new(VariableTerm::new(symbol)));
+    }
+    if symbol_ref.arity.max_args() != Some(args.len()) {
+      return self.error(format!("{} applied to {} arguments", symbol_ref, args.len()));
+    }
+
+    Ok(FreeTerm::with_args(symbol, args))
+  }
+
+  fn read_statement(&mut self, tokens: &mut impl Iterator<Item=Token>, keyword: &str) -> Result<(), SnapshotError> {
+    let attributes = match PreEquationAttributes::from_bits(self.number(self.word(tokens)?.as_str())?) {
+      Ok(attributes) => attributes,
+      Err(_)         => return self.error("unknown statement attributes"),
+    };
+    let mut tokens = tokens.peekable();
+    let name = match tokens.next() {
+      Some(Token::Word(word)) if word == "-" => None,
+      Some(Token::String(name))              => Some(intern(name)),
+      other                                  => return self.error(format!("expected a label, found {:?}", other)),
+    };
+
+    let first    = tokens.next();
+    let lhs_term = self.read_term(first, &mut tokens)?;
+    let kind     = match keyword {
+      "membership" => PreEquationKind::Membership { sort_spec: self.read_sort_spec(&mut tokens)? },
+      "rule"       => PreEquationKind::Rule { rhs_term: self.read_next_term(&mut tokens)? },
+      _            => PreEquationKind::Equation { rhs_term: self.read_next_term(&mut tokens)? },
+    };
+    let conditions = self.read_conditions(&mut tokens)?;
+
+    let mut statement     = PreEquation::new(name, lhs_term, kind, conditions);
+    statement.attributes  = attributes;
+    match keyword {
+      "membership" => self.module.membership.push(statement),
+      "rule"       => self.module.rules.push(statement),
+      _            => self.module.equations.push(statement),
+    }
+
+    Ok(())
+  }
+
+  fn read_next_term(
+    &self,
+    tokens: &mut std::iter::Peekable<impl Iterator<Item=Token>>
+  ) -> Result<BxTerm, SnapshotError>
+  {
+    let first = tokens.next();
+    self.read_term(first, tokens)
+  }
+
+  fn read_sort_spec(&self, tokens: &mut impl Iterator<Item=Token>) -> Result<Box<SortSpec>, SnapshotError> {
+    Ok(Box::new(SortSpec::Sort(self.sort(self.word(tokens)?.as_str())?)))
+  }
+
+  /// Reads the conditions of a statement, if it has any, up to the end of the line.
+  fn read_conditions(
+    &self,
+    tokens: &mut std::iter::Peekable<impl Iterator<Item=Token>>
+  ) -> Result<Conditions, SnapshotError>
+  {
+    let mut conditions = Conditions::new();
+    match tokens.next() {
+      None                                    => return Ok(conditions),
+      Some(Token::Word(word)) if word == "if" => {}
+      Some(token)                             => {
+        return self.error(format!("unexpected {:?} at end of line", token));
+      }
+    }
+
+    while tokens.peek().is_some() {
+      let operator  = self.word(tokens)?;
+      let lhs_term  = self.read_next_term(tokens)?;
+      let condition = match operator.as_str() {
+        "="   => Condition::Equality { lhs_term, rhs_term: self.read_next_term(tokens)? },
+        ":"   => Condition::SortMembership { lhs_term, sort: self.read_sort_spec(tokens)? },
+        ":="  => Condition::Match { lhs_term, rhs_term: self.read_next_term(tokens)? },
+        "=>"  => Condition::Rewrite { lhs_term, rhs_term: self.read_next_term(tokens)? },
+        other => return self.error(format!("unknown condition {}", other)),
+      };
+      conditions.push(Box::new(condition));
+    }
+    if conditions.is_empty() {
+      return self.error("expected a condition");
+    }
+
+    Ok(conditions)
+  }
+
+  fn read_node(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let symbol  = self.symbol(self.word(tokens)?.as_str())?;
+    let reduced = match self.word(tokens)?.as_str() {
+      "r" => true,
+      "-" => false,
+      other => return self.error(format!("unknown node flags {}", other)),
+    };
+
+    let mut args = Vec::new();
+    for token in tokens {
+      let index: usize = match token {
+        Token::Word(word) => self.number(word.as_str())?,
+        other             => return self.error(format!("expected a node index, found {:?}", other)),
+      };
+      match self.nodes.get(index) {
+        Some(&arg) => args.push(arg),
+        None       => return self.error(format!("node {} used before it is defined", index)),
+      }
+    }
+
+    let symbol_ref = unsafe{ &*symbol };
+    if symbol_ref.is_variable() || symbol_ref.arity.max_args() != Some(args.len()) {
+      return self.error(format!("{} applied to {} arguments", symbol_ref, args.len()));
+    }
+
+    let Some(node) = build_node(symbol, &mut args) else {
+      return self.error(format!("no node of {} with {} arguments", symbol_ref, args.len()));
+    };
+    let node_mut = unsafe{ &mut *node };
+    if reduced {
+      node_mut.set_reduced();
+    }
+
+    self.roots.push(RootContainer::new(node));
+    self.nodes.push(node);
+    Ok(())
+  }
+
+  fn read_term_line(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let index: usize = self.number(self.word(tokens)?.as_str())?;
+    self.end_of_line(tokens)?;
+
+    match self.nodes.get(index) {
+      Some(&node) => {
+        self.term = Some(node);
+        Ok(())
+      }
+      None => self.error(format!("undefined node {}", index)),
+    }
+  }
+
+  fn read_arc_term(&mut self, tokens: &mut impl Iterator<Item=Token>) -> Result<(), SnapshotError> {
+    let symbol = intern(self.string(tokens)?);
+    let args   = tokens.map(
+      |token| {
+        let index: usize = match token {
+          Token::Word(word) => self.number(word.as_str())?,
+          other             => return self.error(format!("expected a term index, found {:?}", other)),
+        };
+        match self.arc_terms.get(index) {
+          Some(arg) => Ok(arg.clone()),
+          None      => self.error(format!("term {} used before it is defined", index)),
+        }
+      }
+    ).collect::<Result<Vec<_>, _>>()?;
+
+    let term = self.terms.make(symbol, args);
+    self.arc_terms.push(term);
+    Ok(())
+  }
+
+  fn finish(mut self) -> Result<Snapshot, SnapshotError> {
+    if !self.seen_header {
+      return self.error("empty snapshot");
+    }
+
+    // The sorts were just created by the reader and are owned by the module.
+    unsafe{ self.module.compute_kind_closures(); }
+    if let Err(error) = self.module.compile_statements() {
+      return self.error(error.to_string());
+    }
+    // Only the current term needs to stay rooted; the other nodes are reachable from it or are garbage.
+    let root = self.term.map(RootContainer::new);
+    self.roots.clear();
+
+    Ok(
+      Snapshot {
+        root,
+        term  : self.term,
+        module: self.module,
+        terms : self.terms,
+      }
+    )
+  }
+}
+
+// endregion Reading
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      dag_node::DagNodePtr,
+      free_theory::{FreeDagNode, FreeTerm},
+      symbol::Symbol,
+      term::BxTerm,
+      variable_theory::VariableTerm
+    },
+    core::{
+      arc_term::ArcTermTable,
+      module::Module,
+      parser::{parse_module, parse_term},
+      pre_equation::{condition::Condition, PreEquation, PreEquationKind},
+      rewriting_context::RewritingContext,
+      snapshot::{Snapshot, SnapshotError},
+      sort::sort_spec::SortSpec,
+      RootContainer
+    }
+  };
+
+  fn peano() -> Module {
+    let mut module = Module {
+      name: IString::from("PEANO"),
+      ..Module::default()
+    };
+    let mut symbol = |symbol: Symbol| module.arena.add_symbol(symbol);
+    let zero = symbol(Symbol::new(IString::from("0"), Arity::Value(0)));
+    let s    = symbol(Symbol::new(IString::from("s"), Arity::Value(1)));
+    let plus = symbol(Symbol::new(IString::from("plus"), Arity::Value(2)));
+    let x    = symbol(Symbol::new_variable(IString::from("X")));
+    let y    = symbol(Symbol::new_variable(IString::from("Y")));
+    // Declared but unused by any statement
+    symbol(Symbol::new(IString::from("unused \"quoted\""), Arity::Value(0)));
+
+    let app = FreeTerm::with_args;
+    let var = |symbol| -> BxTerm { Box::new(VariableTerm::new(symbol)) };
+
+    module.equations.push(PreEquation::new(
+      Some(IString::from("plus-zero")),
+      app(plus, vec![app(zero, vec![]), var(y)]),
+      PreEquationKind::Equation { rhs_term: var(y) },
+      vec![]
+    ));
+    module.equations.push(PreEquation::new(
+      None,
+      app(plus, vec![app(s, vec![var(x)]), var(y)]),
+      PreEquationKind::Equation { rhs_term: app(s, vec![app(plus, vec![var(x), var(y)])]) },
+      vec![]
+    ));
+    module.compile_statements().unwrap();
+
+    module
+  }
+
+  #[test]
+  fn snapshot_round_trip() {
+    let module = peano();
+    let symbol = |name: &str| module.arena.symbol(&IString::from(name)).unwrap();
+
+    // plus(s(0), s(0)) with the argument shared
+    let one = FreeDagNode::new(symbol("s"));
+    unsafe{ &mut *one }.insert_child(FreeDagNode::new(symbol("0"))).unwrap();
+    let subject = FreeDagNode::with_args(symbol("plus"), &mut vec![one, one]);
+    let _root   = RootContainer::new(subject);
+
+    let mut saved = Vec::new();
+    Snapshot::write(&mut saved, &module, Some(subject)).unwrap();
+    let restored = Snapshot::read(saved.as_slice()).unwrap();
+
+    // Writing the restored session reproduces the snapshot exactly.
+    let mut resaved = Vec::new();
+    Snapshot::write(&mut resaved, &restored.module, restored.term()).unwrap();
+    assert_eq!(String::from_utf8(saved).unwrap(), String::from_utf8(resaved).unwrap());
+
+    // Sharing survives.
+    let term = restored.term().unwrap();
+    let args: Vec<DagNodePtr> = unsafe{ &*term }.iter_args().collect();
+    assert!(std::ptr::addr_eq(args[0], args[1]));
+
+    // The restored module is compiled and reduces the restored term: s(0) + s(0) = s(s(0)).
+    let mut context = RewritingContext::new(&restored.module);
+    let result      = context.reduce(term);
+    assert_eq!(context.equation_count(), 2);
+    assert_eq!(unsafe{ &*result }.symbol_ref().name, IString::from("s"));
+    assert_eq!(restored.module.equations[0].name, Some(IString::from("plus-zero")));
+  }
+
+  #[test]
+  fn parsed_modules_round_trip_with_their_sorts_conditions_and_terms() {
+    let mut module = parse_module("
+      mod NAT-SET is
+        sorts Bool Zero NzNat Nat Set .
+        subsorts Zero NzNat < Nat .
+        subsort Nat < Set .
+        ops true false : -> Bool [ctor] .
+        op 0 : -> Zero [ctor] .
+        op s : Nat -> NzNat [ctor latex \"\\\\operatorname{s}\"] .
+        op empty : -> Set [ctor] .
+        op union : Set Set -> Set [assoc comm id: empty] .
+        op nonzero : Nat -> Bool .
+        op dec : Nat -> Nat .
+        op choose : Bool Nat Nat -> Nat [strat (1 0)] .
+        vars N M : Nat .
+        eq nonzero(0) = false .
+        eq nonzero(s(N)) = true .
+        eq dec(s(N)) = N .
+        eq choose(true, N, M) = N .
+        eq choose(false, N, M) = M .
+      endm
+    ").unwrap();
+    let term = |module: &Module, text: &str| parse_term(module, text).unwrap();
+    let nat  = module.arena.sorts.get_by_name(&IString::from("Nat")).unwrap();
+
+    // dec(N) = 0 if nonzero(N) = false, and s(N) : Nat
+    let condition = Condition::Equality { lhs_term: term(&module, "nonzero(N)"), rhs_term: term(&module, "false") };
+    let equation  = PreEquation::new(
+      None,
+      term(&module, "dec(N)"),
+      PreEquationKind::Equation { rhs_term: term(&module, "0") },
+      vec![Box::new(condition)]
+    );
+    module.add_statement(equation).unwrap();
+    let membership = PreEquation::new(
+      Some(IString::from("s-nat")),
+      term(&module, "s(N)"),
+      PreEquationKind::Membership { sort_spec: Box::new(SortSpec::Sort(nat)) },
+      vec![]
+    );
+    module.add_statement(membership).unwrap();
+
+    let table = ArcTermTable::new();
+    let zero  = table.constant(IString::from("0"));
+    table.make(IString::from("union"), vec![table.make(IString::from("s"), vec![zero.clone()]), zero]);
+
+    let mut saved = Vec::new();
+    Snapshot::write_with_terms(&mut saved, &module, None, &table).unwrap();
+    let restored = Snapshot::read(saved.as_slice()).unwrap();
+
+    let mut resaved = Vec::new();
+    Snapshot::write_with_terms(&mut resaved, &restored.module, None, &restored.terms).unwrap();
+    assert_eq!(String::from_utf8(saved).unwrap(), String::from_utf8(resaved).unwrap());
+    assert_eq!(restored.terms.len(), 3);
+    assert_eq!(restored.module.membership.len(), 1);
+
+    // The restored module sorts, reduces under its strategy and its conditions the way the original does.
+    for text in ["dec(0)", "dec(s(s(0)))", "choose(nonzero(s(0)), dec(0), s(0))", "union(s(0), empty)"] {
+      let reduce = |module: &Module| {
+        let subject     = term(module, text).term_to_dag(false);
+        let _root       = RootContainer::new(subject);
+        let mut context = RewritingContext::new(module);
+        let result      = unsafe{ &*context.reduce(subject) };
+        (result.to_string(), result.get_sort().map(|sort| unsafe{ &*sort }.name.clone()))
+      };
+      assert_eq!(reduce(&module), reduce(&restored.module), "{}", text);
+    }
+  }
+
+  #[test]
+  fn malformed_snapshots_are_rejected() {
+    let read = |text: &str| Snapshot::read(text.as_bytes()).err().unwrap();
+
+    assert!(matches!(read(""), SnapshotError::Malformed { line: 0, .. }));
+    assert!(matches!(read("mod2lib snapshot 99"), SnapshotError::Malformed { line: 1, .. }));
+    assert!(matches!(
+      read("mod2lib snapshot 1\nsymbol op 1 0 \"s\"\nnode 0 -"),
+      SnapshotError::Malformed { line: 3, .. }
+    ));
+    assert!(matches!(read("mod2lib snapshot 1\nterm 0"), SnapshotError::Malformed { line: 2, .. }));
+  }
+}
@@ -0,0 +1,189 @@
+/*!
+
+Lazy one-step rewriting with rules. `RewritingContext::solutions` gives the rewrites of a term by its rule redexes as a
+`SolutionCursor`, an iterator that finds the next redex and rewrites it only when asked for the next solution, so the
+consumer sets the pace and pays only for the solutions it takes:
+
+```ignore
+let mut context = RewritingContext::new(&module);
+for solution in context.solutions(state).take(10) {
+  println!("{} at {:?} gives {}", solution.redex.rule, solution.redex.position, dag_to_string(solution.result));
+}
+```
+
+Dropping the cursor abandons the search. Nothing is computed ahead: the cursor holds only the positions of the term
+still to be visited and the candidate rules at the current one.
+
+The solutions come in the order `RewritingContext::rule_redexes` lists the redexes, highest priority first and in
+preorder among redexes of equal priority, each rewritten as by `RewritingContext::apply_rule` and reduced with the
+equations. Unlike `rule_redexes`, which takes the first match of each rule at each position, the cursor gives every
+alternative match of a rule with associative symbols, as the ways a commutative pattern can be assigned the arguments
+of the subject, in the order the matcher finds them. Each alternative is found by matching again and skipping those
+already given, so the alternatives at a redex cost time quadratic in their number but no memory.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  api::dag_node::DagNodePtr,
+  core::{
+    rewriting_context::{Position, RewritingContext},
+    rule_selection::RuleRedex
+  }
+};
+
+/// A rewrite of a term by one match of a rule
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RuleSolution {
+  /// Where the rule matched, and how
+  pub redex : RuleRedex,
+  /// The term rewritten at the redex and reduced with the equations
+  pub result: DagNodePtr,
+}
+
+/// The position being visited, with its candidate rules
+struct Visit {
+  position  : Position,
+  node      : DagNodePtr,
+  /// The candidate rules at `node`, in the order of the rule index
+  candidates: Vec<usize>,
+  /// The index in `candidates` of the rule being matched
+  next      : usize,
+  /// The number of matches of that rule already tried
+  skip      : usize,
+}
+
+/// The solutions of one rule step from a term, computed on demand. See the module documentation.
+pub struct SolutionCursor<'c, 'm> {
+  context   : &'c mut RewritingContext<'m>,
+  subject   : DagNodePtr,
+  /// The priorities of the module's rules not yet gone through, lowest first
+  priorities: Vec<i32>,
+  /// The positions still to be visited at the current priority, with their subterms, the next last
+  pending   : Vec<(Position, DagNodePtr)>,
+  visit     : Option<Visit>,
+  /// The number of solutions given so far
+  given     : usize,
+}
+
+impl<'c, 'm> SolutionCursor<'c, 'm> {
+  pub(crate) fn new(context: &'c mut RewritingContext<'m>, subject: DagNodePtr) -> Self {
+    let mut priorities: Vec<i32> = context.module().rules.iter().map(|rule| rule.selection().priority).collect();
+    priorities.sort_unstable();
+    priorities.dedup();
+
+    SolutionCursor {
+      context,
+      subject,
+      priorities,
+      pending: Vec::new(),
+      visit  : None,
+      given  : 0,
+    }
+  }
+
+  /// The number of solutions the cursor has given
+  #[inline(always)]
+  pub fn given(&self) -> usize {
+    self.given
+  }
+
+  /// The next solution at the position being visited, if any is left there
+  fn next_at_visit(&mut self) -> Option<RuleSolution> {
+    let priority = *self.priorities.last()?;
+    let visit    = self.visit.as_mut()?;
+    while let Some(&rule) = visit.candidates.get(visit.next) {
+      if self.context.module().rules[rule].selection().priority == priority {
+        if let Some(bindings) = self.context.match_rule(visit.node, rule, &mut visit.skip) {
+          let result = self.context.rewrite_with_match(self.subject, &visit.position, rule);
+          let redex  = RuleRedex { position: visit.position.clone(), rule, bindings };
+          return Some(RuleSolution { redex, result });
+        }
+      }
+      visit.next += 1;
+      visit.skip  = 0;
+    }
+
+    None
+  }
+}
+
+impl Iterator for SolutionCursor<'_, '_> {
+  type Item = RuleSolution;
+
+  fn next(&mut self) -> Option<RuleSolution> {
+    loop {
+      if let Some(solution) = self.next_at_visit() {
+        self.given += 1;
+        return Some(solution);
+      }
+
+      // Visit the next position in preorder, or start over at the next priority.
+      match self.pending.pop() {
+        Some((position, node)) => {
+          for (index, arg) in unsafe{ &*node }.iter_args().enumerate().collect::<Vec<_>>().into_iter().rev() {
+            let mut arg_position = position.clone();
+            arg_position.push(index);
+            self.pending.push((arg_position, arg));
+          }
+          let candidates = self.context.rule_candidates(node);
+          self.visit     = Some(Visit { position, node, candidates, next: 0, skip: 0 });
+        }
+        None => {
+          // The first priority starts with no visit, and each later one after the last position of the previous.
+          if self.visit.take().is_some() {
+            self.priorities.pop();
+          }
+          if self.priorities.is_empty() {
+            return None;
+          }
+          self.pending.push((Vec::new(), self.subject));
+        }
+      }
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    parser::{parse_module, parse_term},
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext
+  };
+
+  #[test]
+  fn solutions_are_computed_on_demand() {
+    let module = parse_module("
+      mod CHOICE is
+        sort S .
+        ops a b c : -> S .
+        op f : S S -> S [assoc comm] .
+        vars X Y : S .
+        rl [pick] : f(X, Y) => X .
+        rl [swap] : a => c [prio 1] .
+      endm
+    ").unwrap();
+    let subject     = parse_term(&module, "f(a, b)").unwrap().term_to_dag(false);
+    let mut context = RewritingContext::new(&module);
+
+    let results: Vec<(String, Vec<usize>)> = context.solutions(subject)
+                                                    .map(|solution| {
+                                                      (dag_to_string(solution.result), solution.redex.position)
+                                                    })
+                                                    .collect();
+    // Results are reduced, and so sorted under the commutative `f`.
+    let expected = [("f(b, c)", vec![0]), ("a", vec![]), ("b", vec![])];
+    assert_eq!(results, expected.map(|(result, position)| (result.to_string(), position)));
+    assert_eq!(context.rule_count(), 3);
+
+    // Abandoning the cursor leaves the rest uncomputed.
+    let mut cursor = context.solutions(subject);
+    assert!(cursor.next().is_some());
+    assert_eq!(cursor.given(), 1);
+    drop(cursor);
+    assert_eq!(context.rule_count(), 4);
+  }
+}
@@ -1,13 +1,34 @@
-use std::collections::{HashMap, HashSet};
-use std::collections::hash_map::{Entry, Iter};
-use std::iter::Map;
-use crate::abstractions::{IString, heap_construct};
-use crate::core::sort::{Sort, SortPtr};
+/*!
 
-/// A set of unique sorts with helper methods for creating new sorts. Helper collection only used during module construction.
+A `SortCollection` owns the sorts of a module. Sorts are created by name and live until the collection is dropped, so a
+`SortPtr` to a sort in the collection is valid for the lifetime of the collection.
+
+Each sort is identified by a `SortId`, its index in the order in which sorts were created. `SortId`s are stable: they
+don't change as sorts are added, and iteration is in `SortId` order.
+
+A collection made by `share` starts with the sorts of another collection, which it doesn't own and never frees. Sorts
+created afterward are its own.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::{heap_construct, heap_destroy, Entry, HashMap, IString, Set},
+  core::sort::{Sort, SortPtr}
+};
+
+/// Identifies a sort within its `SortCollection`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct SortId(pub u32);
+
+/// A set of unique sorts with helper methods for creating new sorts.
 #[derive(Default)]
 pub struct SortCollection {
-  sorts: HashMap<IString, SortPtr>
+  sorts : Vec<SortPtr>,
+  ids   : HashMap<IString, SortId>,
+  /// The number of leading sorts borrowed from another collection
+  shared: usize,
 }
 
 impl SortCollection {
@@ -15,32 +36,111 @@ impl SortCollection {
     Self::default()
   }
 
-  pub fn get_or_create_sort(&mut self, name: IString) -> SortPtr {
-    match self.sorts.entry(name.clone()) {
-      Entry::Occupied(s) => s.get().clone(),
-      Entry::Vacant(v) => {
-        let s = heap_construct!(Sort::new(name));
-        v.insert(s);
-        s
+  /// A collection with the sorts of this one, which it shares rather than owns, so this collection must outlive it.
+  pub(crate) fn share(&self) -> Self {
+    SortCollection {
+      sorts : self.sorts.clone(),
+      ids   : self.ids.clone(),
+      shared: self.sorts.len(),
+    }
+  }
+
+  /// Whether `sort` is one of the sorts borrowed from the collection this one was shared from
+  pub fn is_shared(&self, sort: SortPtr) -> bool {
+    self.sorts[..self.shared].iter().any(|&shared| core::ptr::addr_eq(shared, sort))
+  }
+
+  /// Returns the id of the sort named `name`, creating the sort if it doesn't exist.
+  pub fn get_or_create_sort_id(&mut self, name: IString) -> SortId {
+    match self.ids.entry(name.clone()) {
+      Entry::Occupied(entry) => *entry.get(),
+      Entry::Vacant(entry) => {
+        let id = SortId(self.sorts.len() as u32);
+        self.sorts.push(heap_construct!(Sort::new(name)));
+        entry.insert(id);
+        id
       }
     }
   }
 
+  /// Returns the sort named `name`, creating it if it doesn't exist.
+  pub fn get_or_create_sort(&mut self, name: IString) -> SortPtr {
+    let id = self.get_or_create_sort_id(name);
+    self.sorts[id.0 as usize]
+  }
+
   /// Given a list of sort names, inserts or creates a sort for each name.
-  pub fn create_implicit_sorts(&mut self, sort_names: &mut HashSet<IString>) {
+  pub fn create_implicit_sorts(&mut self, sort_names: &mut Set<IString>) {
     for sort_name in sort_names.drain() {
       self.get_or_create_sort(sort_name);
     }
   }
 
+  #[inline(always)]
+  pub fn get(&self, id: SortId) -> SortPtr {
+    self.sorts[id.0 as usize]
+  }
+
+  #[inline(always)]
+  pub fn sort_id(&self, name: &IString) -> Option<SortId> {
+    self.ids.get(name).copied()
+  }
+
+  #[inline(always)]
+  pub fn get_by_name(&self, name: &IString) -> Option<SortPtr> {
+    self.sort_id(name).map(|id| self.get(id))
+  }
+
   #[inline(always)]
   pub fn len(&self) -> usize {
     self.sorts.len()
   }
-  /// Creates and returns an iterator over the `SortCollection`.
-  // Can we just stop to appreciate how stupid the return type of this method is? And how obnoxious it is to have to
-  // specify it?
-  pub(crate) fn iter(&self) -> Map<Iter<'_, IString, SortPtr>, fn((&IString, &SortPtr)) -> (IString, SortPtr)> {
-    self.sorts.iter().map(|(istr, rcs)| (istr.clone(), *rcs))
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.sorts.is_empty()
+  }
+
+  /// Iterates over the names and sorts in the collection in `SortId` order.
+  pub fn iter(&self) -> impl Iterator<Item = (IString, SortPtr)> + '_ {
+    self.sorts.iter().map(|&sort| (unsafe{ &*sort }.name.clone(), sort))
+  }
+
+  /// Iterates over the ids of the sorts in the collection in order.
+  pub fn ids(&self) -> impl Iterator<Item = SortId> {
+    (0..self.sorts.len() as u32).map(SortId)
+  }
+}
+
+impl Drop for SortCollection {
+  fn drop(&mut self) {
+    for &sort in self.sorts.iter().skip(self.shared) {
+      heap_destroy!(sort);
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    core::sort::collection::{SortCollection, SortId}
+  };
+
+  #[test]
+  fn sort_ids_are_stable() {
+    let mut sorts = SortCollection::new();
+    let nat       = sorts.get_or_create_sort_id(IString::from("Nat"));
+    let int       = sorts.get_or_create_sort_id(IString::from("Int"));
+
+    assert_eq!((nat, int), (SortId(0), SortId(1)));
+    assert_eq!(sorts.get_or_create_sort_id(IString::from("Nat")), nat);
+    assert_eq!(sorts.get_by_name(&IString::from("Int")), Some(sorts.get(int)));
+    assert_eq!(sorts.sort_id(&IString::from("Bool")), None);
+
+    let names: Vec<String> = sorts.iter().map(|(name, _)| name.to_string()).collect();
+    assert_eq!(names, vec!["Nat", "Int"]);
+    assert_eq!(sorts.ids().collect::<Vec<_>>(), vec![nat, int]);
   }
 }
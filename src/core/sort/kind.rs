@@ -48,26 +48,29 @@ We report two kinds of errors during construction of a kind:
 */
 
 
-use std::{
+use core::{
   fmt::{
     Debug,
     Display
   }
 };
-use std::fmt::Formatter;
-use std::ops::Deref;
+use alloc::{boxed::Box, format, string::String, vec};
+use core::fmt::Formatter;
+use core::ops::Deref;
 use crate::{
+  abstractions::{heap_construct, heap_destroy, join_iter, IString},
   core::{
     sort::{
       sort::{
+        Sort,
         SortPtr,
-        SortPtrs
+        SortPtrs,
+        SpecialSort
       },
       kind_error::KindError
     }
   }
 };
-use crate::abstractions::join_iter;
 
 // Convenience types
 /// Each `Sort` holds a `KindPtr` to its `Kind`. However, it isn't clear if the `KindPtr` is ever dereferenced,
@@ -89,6 +92,10 @@ pub struct Kind {
 
 impl Kind {
   /// Returns a boxed Kind.
+  ///
+  /// # Safety
+  ///
+  /// `initial_sort` and every sort connected to it by subsort relations must be valid.
   pub unsafe fn new(initial_sort: SortPtr) -> Result<BxKind, KindError> {
 
     let mut kind: BxKind = Box::new(
@@ -99,18 +106,11 @@ impl Kind {
         sorts             : vec![],
       }
     );
-    /*
-    It's not clear how error sorts are used. They have the same name as `initial_sort`,
-    and there is one for each Kind. They are registered as a sort in the `Kind`. It does
-    increment `Kind.sort_count`. It is a supersort of every sort in the kind.
-
-    The `ERROR_SORT` is a `SpecialSorts` enum variant, not a `Sort`.
-
-      // Save initial sort so that we have a name for the component and its error sort.
-      // The error sort of each component is added to the module.
-      let error_sort = Sort::new((*sort).name);
-
-    */
+    // The error sort of the kind, named after `initial_sort`, has index `SpecialSort::ErrorSort` and is a supersort of
+    // every sort in the kind. Ill-sorted terms are given the error sort of their kind. It is owned by the kind.
+    let error_sort = heap_construct!(Sort::new(IString::from(format!("[{}]", (*initial_sort).name))));
+    (*error_sort).kind = &mut *kind;
+    kind.append_sort(error_sort);
 
     /*
     We walk the sorts graph, as determined by the adjacency lists in the sorts,
@@ -141,21 +141,23 @@ impl Kind {
     }
 
     // Make every sort in the kind a subsort of the error sort.
-    // for i in 1..=kind.maximal_sort_count as usize {
-    //   error_sort.insert_subsort(kind.sorts[i]);
-    // }
+    kind.maximal_sort_count = kind.sorts.len() as u32 - 1;
+    for i in 1..=kind.maximal_sort_count as usize {
+      (*error_sort).insert_subsort(kind.sorts[i]);
+    }
 
-    // Process subsorts. Length of `kind.sorts` may increase.
+    // Process subsorts, skipping the error sort, as the maximal sorts are already in the kind. Length of `kind.sorts`
+    // may increase.
     {
-      let mut i = 0;
+      let mut i = SpecialSort::FirstUserSort as usize;
       loop {
         if i >= kind.sorts.len() { break; }
-        (*kind).process_subsorts((*kind).sorts[i]);
+        kind.process_subsorts(kind.sorts[i]);
         i += 1
       }
     }
 
-    if kind.sorts.len() != visited_sort_count as usize {
+    if kind.sorts.len() != visited_sort_count as usize + 1 {
       kind.error_free = false;
       return Err(
         KindError::CycleDetected {
@@ -167,8 +169,8 @@ impl Kind {
 
     // Now that the entire connected component is included in the Kind, complete the
     // transitive closure of the subsort relation.
-    for i in (0..visited_sort_count).rev() {
-      (*kind.sorts[i as usize]).compute_leq_sorts();
+    for i in (0..kind.sorts.len()).rev() {
+      (*kind.sorts[i]).compute_leq_sorts();
     }
 
     Ok(kind)
@@ -182,7 +184,7 @@ impl Kind {
     { // Visit subsorts
       let subsort_count = (*sort).subsorts.len();
       for i in 0..subsort_count {
-        let s = (*sort).subsorts[i];
+        let s = (&(*sort).subsorts)[i];
         if (*s).kind.is_null() {
           self.register_connected_sorts(s, visited_sort_count);
         }
@@ -219,6 +221,12 @@ impl Kind {
     }
   }
 
+  /// The error sort of the kind, a supersort of every sort in the kind.
+  #[inline(always)]
+  pub fn error_sort(&self) -> SortPtr {
+    self.sorts[SpecialSort::ErrorSort as usize]
+  }
+
   /// Pushes the sort onto `self.sorts`, returning the index of the sort in `self.sorts`.
   pub fn append_sort(&mut self, sort: SortPtr) -> u8 {
     self.sorts.push(sort);
@@ -227,9 +235,17 @@ impl Kind {
 
 }
 
+impl Drop for Kind {
+  fn drop(&mut self) {
+    if let Some(&error_sort) = self.sorts.first() {
+      heap_destroy!(error_sort);
+    }
+  }
+}
+
 impl Display for Kind {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    let iter = self.sorts.iter().map(|s_ptr| unsafe{ (**s_ptr).name.deref() });
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    let iter = self.sorts.iter().skip(SpecialSort::FirstUserSort as usize).map(|s_ptr| unsafe{ (**s_ptr).name.deref() });
     write!(f, "{{{}}}", join_iter(iter, |_| ", ").collect::<String>())
   }
 }
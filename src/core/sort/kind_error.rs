@@ -4,8 +4,8 @@ When computing the closure of the subsort relation, encountering a cycle is an e
 
 */
 
-use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
 use crate::core::sort::kind::BxKind;
 use crate::core::sort::SortPtr;
 
@@ -21,7 +21,7 @@ pub enum KindError {
 }
 
 impl Display for KindError {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     match self{
 
       KindError::CycleDetected { problem_sort, .. } => {
@@ -46,7 +46,7 @@ impl Display for KindError {
 }
 
 impl Debug for KindError {
-  fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
     Display::fmt(self, f)
   }
 }
@@ -16,6 +16,9 @@ responsibility of destroying them (and freeing their memory). The subsort relati
 is represented by adjacency lists stored in the sorts themselves: each sort stores
 a list of (pointers to) its subsorts and a list of (pointers to) its supersorts.
 
+The exception is a module edited after its sort set was closed: `Module::recompute_kind_closures` rebuilds the kinds
+of the edited sorts, and the replaced kinds are retired rather than destroyed.
+
 ## The Subsort Relation
 
 Any two sorts are either incomparable, or equal, or one sort is a subsort of the other.
@@ -83,9 +86,12 @@ To compare two sorts `A` and `B` during runtime:
  */
 
 pub mod kind;
+#[allow(clippy::module_inception)]
 pub mod sort;
 pub mod sort_spec;
 pub mod collection;
+pub mod sort_table;
+pub mod predicate;
 pub(crate) mod kind_error;
 
 pub use sort::*;
@@ -0,0 +1,229 @@
+/*!
+
+Sorts of data atoms defined by Rust predicates. Data ranges like the nonzero naturals would otherwise need a membership
+axiom for every value; instead a module can register a predicate on data atoms for a sort, and a data atom is a member
+of every sort whose predicate accepts it, as well as of the declared ranges of its symbol.
+
+The sort of a data atom is the least of the sorts it is a member of. If those sorts have no least element, as when
+predicates of incomparable sorts both accept the atom, the atom has no sort, and a warning is logged. Predicates are
+called on atoms of every type, so a predicate should reject atoms of types it doesn't know, which `DataAtom::as_any`
+tells apart.
+
+```ignore
+module.add_sort_predicate(nz_nat, |atom| {
+  atom.as_any().downcast_ref::<IntegerAtom>().is_some_and(|integer| integer.0 > 0)
+});
+```
+
+A `DataDagNode` doesn't know its module. `Module::sort_data_atoms` gives the data nodes of a DAG their sorts in a
+module, as `Module::match_term` does before matching and `RewritingContext` before reducing. A data node without a sort
+computes one on demand in `compute_sort`, by the predicates of the module being reduced, which `RewritingContext` makes
+active for the duration of a reduction. With no reduction under way, only the declared ranges of the atom's symbol
+count.
+
+*/
+
+use core::cell::RefCell;
+use alloc::{rc::Rc, string::{String, ToString}, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+use crate::abstractions::LocalKey;
+use crate::{
+  api::atom::DataAtom,
+  core::sort::SortPtr,
+  warning
+};
+
+// The sort predicates of the modules being reduced on this thread, innermost last. See `ActiveSortPredicates`.
+#[cfg(feature = "std")]
+thread_local! {
+  static ACTIVE_PREDICATES: RefCell<Vec<SortPredicates>> = const { RefCell::new(Vec::new()) };
+}
+#[cfg(not(feature = "std"))]
+static ACTIVE_PREDICATES: LocalKey<RefCell<Vec<SortPredicates>>> = LocalKey::new(|| RefCell::new(Vec::new()));
+
+/// Whether a data atom is a member of a sort
+pub type SortPredicate = Rc<dyn Fn(&dyn DataAtom) -> bool>;
+
+/// The sort predicates of a module. See the module documentation.
+#[derive(Clone, Default)]
+pub struct SortPredicates {
+  predicates: Vec<(SortPtr, SortPredicate)>,
+}
+
+impl SortPredicates {
+  #[inline(always)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Makes the data atoms `predicate` accepts members of `sort`, in addition to those of earlier predicates for `sort`.
+  pub fn add(&mut self, sort: SortPtr, predicate: SortPredicate) {
+    self.predicates.push((sort, predicate));
+  }
+
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.predicates.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.predicates.is_empty()
+  }
+
+  /// The least sort `atom` is a member of, or `None` if it is a member of no sort or of sorts without a least one.
+  pub fn sort_of(&self, atom: &dyn DataAtom) -> Option<SortPtr> {
+    let declared = unsafe{ &*atom.symbol() }.sort_table
+                                            .declarations()
+                                            .iter()
+                                            .filter(|declaration| declaration.domain.is_empty())
+                                            .map(|declaration| declaration.range);
+    let accepted = self.predicates
+                       .iter()
+                       .filter(|(_, predicate)| predicate(atom))
+                       .map(|(sort, _)| *sort);
+    let sorts: Vec<SortPtr> = declared.chain(accepted).collect();
+
+    let least = sorts.iter().copied().find(|&least| sorts.iter().all(|&sort| leq(least, sort)));
+    if least.is_none() && !sorts.is_empty() {
+      let names: Vec<String> = sorts.iter().map(|&sort| unsafe{ &*sort }.to_string()).collect();
+      warning!(1, "the data atom {} is a member of sorts without a least one: {}", atom, names.join(", "));
+    }
+
+    least
+  }
+}
+
+/// Makes the sort predicates of a module the ones `active_sort_of` uses until it is dropped. `RewritingContext` holds
+/// one while it reduces, so that nested reductions in other modules, as by descent functions, use their own.
+pub(crate) struct ActiveSortPredicates(());
+
+impl ActiveSortPredicates {
+  pub(crate) fn new(predicates: &SortPredicates) -> Self {
+    ACTIVE_PREDICATES.with_borrow_mut(|active| active.push(predicates.clone()));
+    ActiveSortPredicates(())
+  }
+}
+
+impl Drop for ActiveSortPredicates {
+  fn drop(&mut self) {
+    ACTIVE_PREDICATES.with_borrow_mut(|active| active.pop());
+  }
+}
+
+/// The least sort of `atom` by the innermost active sort predicates, or by the declarations of its symbol alone if no
+/// reduction is under way
+pub(crate) fn active_sort_of(atom: &dyn DataAtom) -> Option<SortPtr> {
+  ACTIVE_PREDICATES.with_borrow(|active| {
+    match active.last() {
+      Some(predicates) => predicates.sort_of(atom),
+      None             => SortPredicates::new().sort_of(atom),
+    }
+  })
+}
+
+#[inline(always)]
+fn leq(sort: SortPtr, other: SortPtr) -> bool {
+  core::ptr::eq(sort, other) || unsafe{ &*sort }.leq(unsafe{ &*other })
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::any::Any;
+
+  use paste::paste;
+
+  use crate::{
+    abstractions::{IString, Lazy},
+    api::{
+      atom::{implement_data_atom, Atom, DataAtom},
+      symbol::{Symbol, SymbolAttribute, SymbolPtr, SymbolType},
+      Arity
+    },
+    api::{data_theory::DataDagNode, free_theory::FreeDagNode},
+    core::{
+      module::Module,
+      parser::parse_module,
+      proof_trace::dag_to_string,
+      rewriting_context::RewritingContext,
+      RootContainer
+    }
+  };
+
+  implement_data_atom!(Integer, i64);
+
+  fn integer(value: i64) -> Box<dyn DataAtom> {
+    match IntegerAtom::new_atom(value) {
+      Atom::Data(atom) => atom,
+      _                => unreachable!()
+    }
+  }
+
+  fn value(atom: &dyn DataAtom) -> Option<i64> {
+    atom.as_any().downcast_ref::<IntegerAtom>().map(|integer| integer.0)
+  }
+
+  #[test]
+  fn data_atoms_get_the_least_accepting_sort() {
+    let mut module = parse_module("
+      mod PREDICATES is
+        sorts Zero NzNat Nat Int Neg .
+        subsorts Zero NzNat < Nat < Int .
+        subsort Neg < Int .
+      endm
+    ").unwrap();
+    let sort = |module: &Module, name: &str| module.arena.sorts.get_by_name(&name.into()).unwrap();
+    let name = |module: &Module, atom: Box<dyn DataAtom>| {
+      module.data_atom_sort(atom.as_ref()).map(|sort| unsafe{ &*sort }.name.to_string())
+    };
+
+    assert_eq!(name(&module, integer(5)), None);
+    let [zero, nz_nat, nat, int] = ["Zero", "NzNat", "Nat", "Int"].map(|name| sort(&module, name));
+    module.add_sort_predicate(int, |atom| value(atom).is_some());
+    module.add_sort_predicate(nat, |atom| value(atom).is_some_and(|value| value >= 0));
+    module.add_sort_predicate(nz_nat, |atom| value(atom).is_some_and(|value| value > 0));
+    module.add_sort_predicate(zero, |atom| value(atom) == Some(0));
+
+    assert_eq!(name(&module, integer(5)), Some("NzNat".to_string()));
+    assert_eq!(name(&module, integer(0)), Some("Zero".to_string()));
+    assert_eq!(name(&module, integer(-3)), Some("Int".to_string()));
+
+    // Incomparable sorts leave the atom without a sort.
+    let neg = sort(&module, "Neg");
+    module.add_sort_predicate(neg, |atom| value(atom).is_some_and(|value| value < 0 || value == 7));
+    assert_eq!(name(&module, integer(-3)), Some("Neg".to_string()));
+    assert_eq!(name(&module, integer(7)), None);
+  }
+
+  #[test]
+  fn predicate_sorts_select_equations() {
+    let mut module = parse_module("
+      mod SIGNS is
+        sorts NzNat Nat Answer .
+        subsort NzNat < Nat .
+        op sign : Nat -> Answer .
+        op positive : -> Answer .
+        var N : NzNat .
+        eq sign(N) = positive .
+      endm
+    ").unwrap();
+    let sort = |name: &str| module.arena.sorts.get_by_name(&name.into()).unwrap();
+    let (nat, nz_nat) = (sort("Nat"), sort("NzNat"));
+    module.add_sort_predicate(nat, |atom| value(atom).is_some_and(|value| value >= 0));
+    module.add_sort_predicate(nz_nat, |atom| value(atom).is_some_and(|value| value > 0));
+
+    let sign   = module.arena.symbol(&"sign".into()).unwrap();
+    let reduce = |value: i64| {
+      let subject = FreeDagNode::new(sign);
+      unsafe{ &mut *subject }.insert_child(DataDagNode::new(integer(value))).unwrap();
+      let _root   = RootContainer::new(subject);
+      dag_to_string(RewritingContext::new(&module).reduce(subject))
+    };
+
+    // Only the predicate makes 5 a `NzNat`, so the equation applies to `sign(5)` but not to `sign(0)`.
+    assert_eq!(reduce(5), "positive");
+    assert_ne!(reduce(0), "positive");
+  }
+}
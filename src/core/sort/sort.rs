@@ -26,7 +26,8 @@ section titled, "Optimizations for Computing a Subsort Relation at Runtime."
 
 */
 
-use std::fmt::Display;
+use core::fmt::Display;
+use alloc::vec::Vec;
 
 use crate::{
   abstractions::{
@@ -46,9 +47,11 @@ pub type SortPtrs = Vec<SortPtr>;
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(i8)]
 pub enum SpecialSort {
+  /// The index of the error sort of a kind, which is a supersort of every sort in the kind
   Kind          =  0,
-  // ErrorSort     = 0,
+  /// The index of the first sort of a kind that is not the error sort
   FirstUserSort =  1,
+  /// The sort has not been computed
   Unknown       = -1,
 }
 
@@ -57,6 +60,64 @@ impl SpecialSort {
   //    `SpecialSort::Kind==SpecialSort::ErrorSort`
   #[allow(non_upper_case_globals)]
   pub const ErrorSort: SpecialSort = SpecialSort::Kind;
+
+  /// The `SpecialSort` with the given index, if there is one.
+  pub fn from_index(index: i8) -> Option<SpecialSort> {
+    match index {
+      0  => Some(SpecialSort::Kind),
+      1  => Some(SpecialSort::FirstUserSort),
+      -1 => Some(SpecialSort::Unknown),
+      _  => None
+    }
+  }
+}
+
+impl PartialEq<i8> for SpecialSort {
+  #[inline(always)]
+  fn eq(&self, other: &i8) -> bool {
+    *self as i8 == *other
+  }
+}
+
+impl PartialEq<SpecialSort> for i8 {
+  #[inline(always)]
+  fn eq(&self, other: &SpecialSort) -> bool {
+    *self == *other as i8
+  }
+}
+
+/// Sorts with a meaning to the engine, which every module may refer to by name.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum BuiltinSort {
+  /// The sort of any term, of arity `Arity::Any`
+  Any,
+  /// The sort of no term, of arity `Arity::None`
+  None,
+}
+
+impl BuiltinSort {
+  pub const ALL: [BuiltinSort; 2] = [BuiltinSort::Any, BuiltinSort::None];
+
+  #[inline(always)]
+  pub fn name(&self) -> &'static str {
+    match self {
+      BuiltinSort::Any  => "Any",
+      BuiltinSort::None => "None",
+    }
+  }
+
+  /// The builtin sort named `name`, if there is one.
+  pub fn from_name(name: &str) -> Option<BuiltinSort> {
+    BuiltinSort::ALL.into_iter().find(|builtin| builtin.name() == name)
+  }
+
+  #[inline(always)]
+  pub fn arity(&self) -> Arity {
+    match self {
+      BuiltinSort::Any  => Arity::Any,
+      BuiltinSort::None => Arity::None,
+    }
+  }
 }
 
 
@@ -87,6 +148,9 @@ pub struct Sort {
 
   // The connected component this sort belongs to.
   pub kind: KindPtr, // This should be a weak reference
+
+  /// Which builtin sort this is, if any. Determined by the name of the sort when it is created.
+  pub builtin: Option<BuiltinSort>,
 }
 
 impl Default for Sort {
@@ -94,11 +158,13 @@ impl Default for Sort {
     Sort {
       name                      : IString::default(),
       index_within_kind         : 0, // Also used for `unresolved_supersort_count` during kind construction
-      fast_compare_index        : 0,
+      // No index is a subsort until `compute_leq_sorts` says so.
+      fast_compare_index        : u8::MAX,
       subsorts                  : SortPtrs::default(),
       supersorts                : SortPtrs::default(),
       leq_sorts                 : NatSet::default(),
-      kind                      : std::ptr::null_mut(),
+      kind                      : core::ptr::null_mut(),
+      builtin                   : None,
     }
   }
 }
@@ -106,22 +172,47 @@ impl Default for Sort {
 impl Sort {
   pub fn new(name: IString) -> Sort {
     Sort{
+      builtin: BuiltinSort::from_name(&name),
       name,
       ..Self::default()
     }
   }
 
-  /// Returns `Arity::Any` for special sort Any, `Arity::None` for special sort None, and `0` otherwise.
+  /// Creates the builtin sort `builtin`.
+  pub fn builtin(builtin: BuiltinSort) -> Sort {
+    Sort::new(IString::from(builtin.name()))
+  }
+
+  #[inline(always)]
+  pub fn is_builtin(&self, builtin: BuiltinSort) -> bool {
+    self.builtin == Some(builtin)
+  }
+
+  /// Returns `Arity::Any` for builtin sort `Any`, `Arity::None` for builtin sort `None`, and `0` otherwise.
   pub fn arity(&self) -> Arity {
-    match &self.name  {
-      v if *v == IString::from("Any")  => Arity::Any,
-      v if *v == IString::from("None") => Arity::None,
-      _ =>  Arity::Value(0)
+    match self.builtin {
+      Some(builtin) => builtin.arity(),
+      None          => Arity::Value(0)
     }
   }
 
 
+  /// Whether `self` is `other` or one of its subsorts. Sorts in different kinds are incomparable.
+  #[inline(always)]
+  pub fn leq(&self, other: &Sort) -> bool {
+    self.kind == other.kind && other.geq_index(self.index_within_kind as usize)
+  }
+
+  /// Whether the sort with index `index` within this sort's kind is `self` or one of its subsorts. Indices at or above
+  /// `fast_compare_index` are answered without looking at `leq_sorts`. Until `compute_leq_sorts` is called, only
+  /// `leq_sorts` is looked at.
+  #[inline(always)]
+  pub fn geq_index(&self, index: usize) -> bool {
+    index >= self.fast_compare_index as usize || self.leq_sorts.contains(index)
+  }
+
   /// Antisymmetrically inserts `other` as a subsort of `self` and `self` as a supersort of `other`.
+  #[allow(clippy::not_unsafe_ptr_arg_deref)]
   pub fn insert_subsort(&mut self, other: SortPtr) {
     assert!(!other.is_null(), "other sort is null pointer");
     self.subsorts.push(other);
@@ -130,6 +221,15 @@ impl Sort {
     }
   }
 
+  /// Forgets the sort's kind and its place in the closure of the subsort relation, so that the closure can be computed
+  /// again after the relation changed. See `Module::recompute_kind_closures`.
+  pub(crate) fn reset_closure(&mut self) {
+    self.kind               = core::ptr::null_mut();
+    self.index_within_kind  = 0;
+    self.fast_compare_index = u8::MAX;
+    self.leq_sorts          = NatSet::default();
+  }
+
   /// Compute the transitive closure of the subsort relation as stored in `self.leq_sorts`.
   ///
   /// This only works if this method is called on each sort in the connected component in increasing order. This is
@@ -146,7 +246,7 @@ impl Sort {
     self.fast_compare_index = self.index_within_kind;
     let total_sort_count    = unsafe {(*self.kind).sorts.len() as u8};
     for i in (self.index_within_kind..total_sort_count).rev() {
-      if !self.leq_sorts.contains(i) {
+      if !self.leq_sorts.contains(i as usize) {
         self.fast_compare_index = i + 1;
         break;
       }
@@ -155,7 +255,31 @@ impl Sort {
 }
 
 impl Display for Sort {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     write!(f, "{}", self.name)
   }
 }
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::Arity,
+    core::sort::{BuiltinSort, Sort, SpecialSort}
+  };
+
+  #[test]
+  fn builtin_and_special_sorts() {
+    let any = Sort::builtin(BuiltinSort::Any);
+    assert!(any.is_builtin(BuiltinSort::Any));
+    assert_eq!(any.arity(), Arity::Any);
+    assert_eq!(Sort::new(IString::from("None")).builtin, Some(BuiltinSort::None));
+    assert_eq!(Sort::new(IString::from("Nat")).arity(), Arity::Value(0));
+
+    assert_eq!(SpecialSort::ErrorSort, 0i8);
+    assert_eq!(-1i8, SpecialSort::Unknown);
+    assert_eq!(SpecialSort::from_index(1), Some(SpecialSort::FirstUserSort));
+    assert_eq!(SpecialSort::from_index(2), None);
+  }
+}
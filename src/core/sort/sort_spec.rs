@@ -11,8 +11,9 @@ functors. `SortSpec`s are not named.
 
 */
 
-use std::fmt::Display;
-use crate::core::sort::SortPtr;
+use core::fmt::Display;
+use alloc::{boxed::Box, vec::Vec};
+use crate::core::sort::{BuiltinSort, SortPtr};
 use crate::abstractions::join_string;
 use crate::api::Arity;
 
@@ -20,6 +21,7 @@ use crate::api::Arity;
 pub type BxSortSpec = Box<SortSpec>;
 
 /// A generalization of a `Sort` that additionally permits functors.
+#[derive(Clone)]
 pub enum SortSpec {
   Sort(SortPtr),
   // arg1_sort arg2_sort -> result_sort
@@ -27,8 +29,8 @@ pub enum SortSpec {
     arg_sorts: Vec<BxSortSpec>,
     sort_spec: BxSortSpec
   },
-  Any,  // Shortcut for `SortSpec::Sort(Rc::new(Sort::any()))`
-  None, // Shortcut for `SortSpec::Sort(Rc::new(Sort::none()))`
+  Any,  // Shortcut for `SortSpec::Sort(Sort::builtin(BuiltinSort::Any))`
+  None, // Shortcut for `SortSpec::Sort(Sort::builtin(BuiltinSort::None))`
 }
 
 impl SortSpec {
@@ -42,11 +44,11 @@ impl SortSpec {
         }
       },
 
-      SortSpec::Functor { arg_sorts, ..} => Arity::Value(arg_sorts.len() as u16),
+      SortSpec::Functor { arg_sorts, ..} => Arity::new(arg_sorts.len()).expect("too many argument sorts"),
 
-      SortSpec::Any => Arity::Any,
+      SortSpec::Any => BuiltinSort::Any.arity(),
 
-      SortSpec::None => Arity::Unspecified
+      SortSpec::None => BuiltinSort::None.arity()
 
     }
   }
@@ -54,7 +56,7 @@ impl SortSpec {
 
 
 impl Display for SortSpec {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
 
       SortSpec::Sort(sort) => {
@@ -67,11 +69,11 @@ impl Display for SortSpec {
       }
 
       SortSpec::Any => {
-        write!(f, "any")
+        write!(f, "{}", BuiltinSort::Any.name())
       }
 
       SortSpec::None => {
-        write!(f, "none")
+        write!(f, "{}", BuiltinSort::None.name())
       }
 
     }
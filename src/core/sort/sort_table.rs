@@ -0,0 +1,189 @@
+/*!
+
+A `SortTable` holds the operator declarations of a symbol, `f : S₁ … Sₙ -> S`, and computes the sort of a term headed
+by the symbol from the sorts of its arguments.
+
+The sort of `f(t₁, …, tₙ)` is the least range among the declarations whose domain contains the sorts of `t₁, …, tₙ`.
+If there is no such declaration, the term is ill-sorted. It still has a kind, the kind of the ranges of `f`, and is
+given that kind's error sort, which has index `SpecialSort::ErrorSort` and is a supersort of every sort in the kind. A
+term with an ill-sorted subterm is itself ill-sorted, as no declaration has an error sort in its domain.
+
+Ill-sorted terms are not an error as far as reduction is concerned: they are matched and rewritten like any other term,
+and reduction may well produce a well-sorted result. Use `DagNode::is_error_sorted` and `ill_sorted_subterms` to
+detect and inspect them.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::core::sort::{
+  kind::KindPtr,
+  SortPtr,
+  SortPtrs,
+  SpecialSort
+};
+
+/// An operator declaration `f : domain -> range`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct OpDeclaration {
+  pub domain        : SortPtrs,
+  pub range         : SortPtr,
+  pub is_constructor: bool,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct SortTable {
+  declarations: Vec<OpDeclaration>,
+}
+
+// SAFETY: A `SortTable` never writes to the sorts it points to, and is itself only changed through `&mut`, so sharing
+// one shares only reads of its sorts. Sorts are written only while the module owning them is built, and a module is
+// neither `Send` nor `Sync`. So a table is only read on another thread if it belongs to a symbol shared on purpose: the
+// static symbol of a data atom, whose table has no declarations, or a symbol moved along with its module by
+// `ThreadBoundEngine::transfer`, whose contract keeps the sending thread from using either again. See `thread_bound`.
+unsafe impl Send for SortTable {}
+unsafe impl Sync for SortTable {}
+
+impl SortTable {
+  pub fn add_op_declaration(&mut self, domain: SortPtrs, range: SortPtr, is_constructor: bool) {
+    assert!(!range.is_null(), "tried to declare an operator with a null range");
+    if let Some(first) = self.declarations.first() {
+      assert_eq!(first.domain.len(), domain.len(), "tried to declare an operator with a different arity");
+    }
+
+    self.declarations.push(OpDeclaration { domain, range, is_constructor });
+  }
+
+  #[inline(always)]
+  pub fn declarations(&self) -> &[OpDeclaration] {
+    &self.declarations
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.declarations.is_empty()
+  }
+
+  /// The kind of the ranges of the declarations, or `None` if there are no declarations or the kinds have not been
+  /// computed yet.
+  pub fn range_kind(&self) -> Option<KindPtr> {
+    let range = self.declarations.first()?.range;
+    let kind  = unsafe{ &*range }.kind;
+
+    match kind.is_null() {
+      true  => None,
+      false => Some(kind)
+    }
+  }
+
+  /// The sort with index `sort_index` in the range kind, or `None` if the index is `SpecialSort::Unknown` or the kind
+  /// is not known.
+  pub fn sort_with_index(&self, sort_index: i8) -> Option<SortPtr> {
+    if sort_index == SpecialSort::Unknown as i8 {
+      return None;
+    }
+
+    let kind = self.range_kind()?;
+    unsafe{ &*kind }.sorts.get(sort_index as usize).copied()
+  }
+
+  /// Computes the index within the range kind of the sort of a term whose arguments have the sorts `arg_sorts`. Returns
+  /// `SpecialSort::ErrorSort` if the term is ill-sorted and `SpecialSort::Unknown` if the sort can't be determined,
+  /// because there are no declarations, the kinds have not been computed, or an argument's sort is unknown.
+  pub fn compute_sort_index(&self, arg_sorts: &[Option<SortPtr>]) -> i8 {
+    if self.range_kind().is_none() || arg_sorts.iter().any(Option::is_none) {
+      return SpecialSort::Unknown as i8;
+    }
+
+    let mut least: Option<SortPtr> = None;
+    for declaration in self.declarations.iter().filter(|declaration| accepts(&declaration.domain, arg_sorts)) {
+      match least {
+        Some(sort) if !leq(declaration.range, sort) => {}
+        _ => least = Some(declaration.range)
+      }
+    }
+
+    match least {
+      Some(sort) => unsafe{ &*sort }.index_within_kind as i8,
+      None       => SpecialSort::ErrorSort as i8
+    }
+  }
+}
+
+/// Whether `sort` is `other` or one of its subsorts.
+#[inline(always)]
+fn leq(sort: SortPtr, other: SortPtr) -> bool {
+  unsafe{ (*sort).leq(&*other) }
+}
+
+fn accepts(domain: &SortPtrs, arg_sorts: &[Option<SortPtr>]) -> bool {
+  domain.len() == arg_sorts.len()
+      && domain.iter().zip(arg_sorts.iter()).all(|(&domain_sort, arg_sort)| leq(arg_sort.unwrap(), domain_sort))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      dag_node::ill_sorted_subterms,
+      free_theory::FreeDagNode,
+      symbol::{Symbol, SymbolPtr}
+    },
+    core::{
+      module::Module,
+      sort::{SortPtr, SpecialSort}
+    }
+  };
+
+  #[test]
+  fn ill_sorted_terms_get_the_error_sort() {
+    let mut module = Module::default();
+    let nat: SortPtr    = module.arena.sorts.get_or_create_sort(IString::from("Nat"));
+    let nz_nat: SortPtr = module.arena.sorts.get_or_create_sort(IString::from("NzNat"));
+    unsafe {
+      (*nat).insert_subsort(nz_nat);
+      module.compute_kind_closures();
+    }
+
+    let mut zero = Symbol::new(IString::from("zero"), Arity::Value(0));
+    zero.sort_table.add_op_declaration(vec![], nat, true);
+    let mut one = Symbol::new(IString::from("one"), Arity::Value(0));
+    one.sort_table.add_op_declaration(vec![], nz_nat, true);
+    let mut p = Symbol::new(IString::from("p"), Arity::Value(1));
+    p.sort_table.add_op_declaration(vec![nz_nat], nat, false);
+    let mut plus = Symbol::new(IString::from("plus"), Arity::Value(2));
+    plus.sort_table.add_op_declaration(vec![nat, nat], nat, false);
+    plus.sort_table.add_op_declaration(vec![nz_nat, nz_nat], nz_nat, false);
+    let (zero, one, p, plus): (SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr) = (&mut zero, &mut one, &mut p, &mut plus);
+
+    // The least sort among the applicable declarations
+    let term = FreeDagNode::with_args(plus, &mut vec![FreeDagNode::new(one), FreeDagNode::new(one)]);
+    assert_eq!(unsafe{ &mut *term }.compute_sort(), Some(nz_nat));
+    let term = FreeDagNode::with_args(plus, &mut vec![FreeDagNode::new(one), FreeDagNode::new(zero)]);
+    assert_eq!(unsafe{ &mut *term }.compute_sort(), Some(nat));
+    assert!(ill_sorted_subterms(term).is_empty());
+
+    // `p(zero)` is ill-sorted, and so is `plus(one, p(zero))`.
+    let bad  = FreeDagNode::new(p);
+    unsafe{ &mut *bad }.insert_child(FreeDagNode::new(zero)).unwrap();
+    let term = FreeDagNode::with_args(plus, &mut vec![FreeDagNode::new(one), bad]);
+    let kind = unsafe{ &*nat }.kind;
+    assert_eq!(unsafe{ &mut *term }.compute_sort(), Some(unsafe{ &*kind }.error_sort()));
+    assert!(unsafe{ &*term }.is_error_sorted());
+    assert_eq!(unsafe{ &*bad }.sort_index(), SpecialSort::ErrorSort);
+
+    let found = ill_sorted_subterms(term);
+    assert_eq!(found.len(), 1);
+    assert!(std::ptr::addr_eq(found[0], bad));
+
+    // A symbol without declarations has no sort.
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(1));
+    let term  = FreeDagNode::new(&mut f);
+    unsafe{ &mut *term }.insert_child(FreeDagNode::new(zero)).unwrap();
+    assert_eq!(unsafe{ &mut *term }.compute_sort(), None);
+    assert!(!unsafe{ &*term }.is_error_sorted());
+  }
+}
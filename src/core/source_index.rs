@@ -0,0 +1,607 @@
+/*!
+
+What a language server for the module language needs to know about a source: where each sort, operator, variable,
+statement label, and module is declared, where each is referred to, where each statement is, and which kind of token
+each token is, for highlighting.
+
+```ignore
+let index = SourceIndex::new(source);
+if let Some(definition) = index.definition_at(Position { line: 7, column: 12 }) {
+  println!("{} is declared at {:?}", definition.name, definition.span);
+}
+let occurrences = index.references_at(Position { line: 7, column: 12 });
+```
+
+The index is built from the tokens of the parser (see `mod2lib_syntax`) without parsing the module, so it is built for
+sources that don't parse, as a source being edited usually doesn't, and a declaration is indexed whether or not the
+parser would accept it. Names declared in imported modules have no definition in the source, and references to them have
+none. A name occurring in a term is a variable if a variable of that name is declared anywhere in the source, a
+retract referring to its two sorts if it has the form `r:A>B`, an on-the-fly variable if it has the form `X:S`, and
+otherwise an operator.
+
+Lines count from 1, as in `ParseError`, and columns count characters from 0. A language server speaking UTF-16
+offsets must convert the columns of lines with characters outside the basic multilingual plane.
+
+Overloaded operators are declared more than once. Each declaration is a definition, and `definition_at` gives the
+first. An on-the-fly variable `X:S` is defined by its first occurrence, unless a variable `X` of sort `S` is declared,
+in which case it is that variable.
+
+*/
+
+use alloc::{string::{String, ToString}, vec::Vec};
+
+use mod2lib_syntax::{lex, Token};
+pub use mod2lib_syntax::{Position, Span};
+
+use crate::{
+  abstractions::HashMap,
+  core::{
+    coverage::StatementKind,
+    retract::parse_retract_name
+  }
+};
+
+/// What a name names
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum NameKind {
+  Module,
+  Sort,
+  Operator,
+  Variable,
+  Label,
+}
+
+/// A declaration of a name
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Definition {
+  pub name: String,
+  pub kind: NameKind,
+  pub span: Span,
+}
+
+/// An occurrence of a name other than its declarations
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Reference {
+  pub name      : String,
+  pub kind      : NameKind,
+  pub span      : Span,
+  /// The index in `SourceIndex::definitions` of the first definition of the name, if it is declared in the source
+  pub definition: Option<usize>,
+}
+
+/// Where a statement is, from its keyword to its period
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct StatementSpan {
+  pub kind : StatementKind,
+  pub label: Option<String>,
+  pub span : Span,
+}
+
+/// The kinds of semantic tokens, which correspond to the standard token types of the language server protocol:
+/// `keyword`, `namespace`, `type`, `function`, `variable`, `label`, `string`, `number`, and `comment`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SemanticTokenKind {
+  Keyword,
+  Module,
+  Sort,
+  Operator,
+  Variable,
+  Label,
+  String,
+  Number,
+  Comment,
+}
+
+impl From<NameKind> for SemanticTokenKind {
+  fn from(kind: NameKind) -> Self {
+    match kind {
+      NameKind::Module   => SemanticTokenKind::Module,
+      NameKind::Sort     => SemanticTokenKind::Sort,
+      NameKind::Operator => SemanticTokenKind::Operator,
+      NameKind::Variable => SemanticTokenKind::Variable,
+      NameKind::Label    => SemanticTokenKind::Label,
+    }
+  }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SemanticToken {
+  pub span: Span,
+  pub kind: SemanticTokenKind,
+}
+
+/// The declarations, references, statements, and semantic tokens of a source. See the module documentation.
+#[derive(Clone, Default, Debug)]
+pub struct SourceIndex {
+  definitions: Vec<Definition>,
+  references : Vec<Reference>,
+  statements : Vec<StatementSpan>,
+  tokens     : Vec<SemanticToken>,
+}
+
+impl SourceIndex {
+  pub fn new(source: &str) -> SourceIndex {
+    let (tokens, comments) = lex(source);
+    let mut indexer        = Indexer {
+      tokens,
+      position: 0,
+      index   : SourceIndex::default(),
+      terms   : Vec::new(),
+      sorts   : HashMap::default(),
+    };
+    indexer.index_source();
+    let mut index = indexer.finish();
+
+    index.tokens.extend(comments.into_iter().map(|span| SemanticToken { span, kind: SemanticTokenKind::Comment }));
+    index.tokens.sort_by_key(|token| token.span.start);
+    index
+  }
+
+  /// The declarations in the source, in source order
+  #[inline(always)]
+  pub fn definitions(&self) -> &[Definition] {
+    &self.definitions
+  }
+
+  /// The occurrences of names other than their declarations, in source order
+  #[inline(always)]
+  pub fn references(&self) -> &[Reference] {
+    &self.references
+  }
+
+  /// The equations and rules, in source order
+  #[inline(always)]
+  pub fn statements(&self) -> &[StatementSpan] {
+    &self.statements
+  }
+
+  /// The tokens of the source other than punctuation, classified, in source order
+  #[inline(always)]
+  pub fn semantic_tokens(&self) -> &[SemanticToken] {
+    &self.tokens
+  }
+
+  /// The first definition of the name at `position`, which may be on a reference to the name or on a declaration of it
+  pub fn definition_at(&self, position: Position) -> Option<&Definition> {
+    let (name, kind) = self.name_at(position)?;
+    self.definitions.iter().find(|definition| definition.name == name && definition.kind == kind)
+  }
+
+  /// The spans of every occurrence of the name at `position`, its declarations among them, in source order
+  pub fn references_at(&self, position: Position) -> Vec<Span> {
+    let Some((name, kind)) = self.name_at(position) else {
+      return Vec::new();
+    };
+
+    let definitions = self.definitions
+                          .iter()
+                          .filter(|definition| definition.name == name && definition.kind == kind)
+                          .map(|definition| definition.span);
+    let references  = self.references
+                          .iter()
+                          .filter(|reference| reference.name == name && reference.kind == kind)
+                          .map(|reference| reference.span);
+    let mut spans: Vec<Span> = definitions.chain(references).collect();
+    spans.sort_by_key(|span| span.start);
+
+    spans
+  }
+
+  /// The name and kind of the definition or reference at `position`
+  fn name_at(&self, position: Position) -> Option<(&str, NameKind)> {
+    let definition = self.definitions
+                         .iter()
+                         .find(|definition| definition.span.contains(position))
+                         .map(|definition| (definition.name.as_str(), definition.kind));
+    definition.or_else(|| {
+      self.references
+          .iter()
+          .find(|reference| reference.span.contains(position))
+          .map(|reference| (reference.name.as_str(), reference.kind))
+    })
+  }
+}
+
+/// The words that begin a declaration or end a module
+const DECLARATION_KEYWORDS: &[&str] = &[
+  "mod", "fmod", "endm", "endfm", "sort", "sorts", "subsort", "subsorts", "op", "ops", "var", "vars", "eq", "rl",
+  "including", "protecting",
+];
+
+/// Builds a `SourceIndex` from the tokens of a source.
+struct Indexer {
+  tokens  : Vec<(Token, Span)>,
+  position: usize,
+  index   : SourceIndex,
+  /// The names occurring in terms, which are classified once every variable declaration has been seen
+  terms   : Vec<(String, Span)>,
+  /// The sort of each declared variable
+  sorts   : HashMap<String, String>,
+}
+
+impl Indexer {
+  // region Token helpers
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position).map(|(token, _)| token)
+  }
+
+  fn span(&self) -> Option<Span> {
+    self.tokens.get(self.position).map(|&(_, span)| span)
+  }
+
+  fn is_word(&self, word: &str) -> bool {
+    matches!(self.peek(), Some(Token::Word(next)) if next == word)
+  }
+
+  /// Whether the declaration being read goes on, that is, whether the next token is neither a period, the keyword of
+  /// the next declaration, nor the end. A declaration whose period is missing ends where the next one begins.
+  fn continues(&self) -> bool {
+    match self.peek() {
+      None | Some(Token::Period) => false,
+      Some(Token::Word(word))    => !DECLARATION_KEYWORDS.contains(&word.as_str()),
+      Some(_)                    => true,
+    }
+  }
+
+  /// Takes the next token if it is a word.
+  fn word(&mut self) -> Option<(String, Span)> {
+    match self.tokens.get(self.position) {
+      Some((Token::Word(word), span)) => {
+        let word = (word.clone(), *span);
+        self.position += 1;
+        Some(word)
+      }
+      _ => None
+    }
+  }
+
+  /// Takes the next token if it is a word that doesn't end the declaration.
+  fn declared_word(&mut self) -> Option<(String, Span)> {
+    match self.continues() {
+      true  => self.word(),
+      false => None,
+    }
+  }
+
+  fn add_token(&mut self, span: Span, kind: SemanticTokenKind) {
+    self.index.tokens.push(SemanticToken { span, kind });
+  }
+
+  fn define(&mut self, name: String, kind: NameKind, span: Span) {
+    self.add_token(span, kind.into());
+    self.index.definitions.push(Definition { name, kind, span });
+  }
+
+  fn refer(&mut self, name: String, kind: NameKind, span: Span) {
+    self.add_token(span, kind.into());
+    self.index.references.push(Reference { name, kind, span, definition: None });
+  }
+
+  /// Takes the next token as a keyword if it is the word `keyword`.
+  fn keyword(&mut self, keyword: &str) -> bool {
+    let Some(span) = self.span().filter(|_| self.is_word(keyword)) else {
+      return false;
+    };
+    self.position += 1;
+    self.add_token(span, SemanticTokenKind::Keyword);
+
+    true
+  }
+
+  /// Takes the next token if it is the punctuation `token`.
+  fn punctuation(&mut self, token: Token) -> bool {
+    if self.peek() != Some(&token) {
+      return false;
+    }
+    self.position += 1;
+
+    true
+  }
+
+  /// Takes a number as a `Number` token, or any other word or string as the kind of token it is.
+  fn literal(&mut self) {
+    let Some((token, span)) = self.tokens.get(self.position).cloned() else {
+      return;
+    };
+    self.position += 1;
+    match token {
+      Token::String(_)                                       => self.add_token(span, SemanticTokenKind::String),
+      Token::Word(word) if word.parse::<f64>().is_ok()       => self.add_token(span, SemanticTokenKind::Number),
+      Token::Word(_)                                         => self.add_token(span, SemanticTokenKind::Keyword),
+      _                                                      => {}
+    }
+  }
+
+  /// Skips the rest of a declaration, up to and including its period.
+  fn skip_declaration(&mut self) {
+    while self.continues() {
+      self.position += 1;
+    }
+    self.punctuation(Token::Period);
+  }
+
+  // endregion Token helpers
+
+  fn index_source(&mut self) {
+    while let Some(token) = self.peek().cloned() {
+      let Token::Word(keyword) = token else {
+        self.position += 1;
+        continue;
+      };
+      let start = self.span().unwrap();
+
+      match keyword.as_str() {
+        "mod" | "fmod" => {
+          self.keyword(&keyword);
+          if let Some((name, span)) = self.word() {
+            self.define(name, NameKind::Module, span);
+          }
+          self.keyword("is");
+        }
+
+        "endm" | "endfm" => {
+          self.keyword(&keyword);
+        }
+
+        "sort" | "sorts" => {
+          self.keyword(&keyword);
+          while let Some((name, span)) = self.declared_word() {
+            self.define(name, NameKind::Sort, span);
+          }
+          self.skip_declaration();
+        }
+
+        "subsort" | "subsorts" => {
+          self.keyword(&keyword);
+          while let Some((name, span)) = self.declared_word() {
+            if name != "<" {
+              self.refer(name, NameKind::Sort, span);
+            }
+          }
+          self.skip_declaration();
+        }
+
+        "op" | "ops" => {
+          self.keyword(&keyword);
+          self.operators();
+          self.skip_declaration();
+        }
+
+        "var" | "vars" => {
+          self.keyword(&keyword);
+          let mut names = Vec::new();
+          while self.continues() && !self.is_word(":") {
+            match self.word() {
+              Some((name, span)) => {
+                names.push(name.clone());
+                self.define(name, NameKind::Variable, span);
+              }
+              None => self.position += 1,
+            }
+          }
+          if self.is_word(":") {
+            self.position += 1;
+          }
+          if let Some((sort, span)) = self.word() {
+            self.sorts.extend(names.into_iter().map(|name| (name, sort.clone())));
+            self.refer(sort, NameKind::Sort, span);
+          }
+          self.skip_declaration();
+        }
+
+        "eq" | "rl" => {
+          self.keyword(&keyword);
+          let kind = match keyword.as_str() {
+            "eq" => StatementKind::Equation,
+            _    => StatementKind::Rule,
+          };
+          self.statement(kind, start);
+        }
+
+        "including" | "protecting" => {
+          self.keyword(&keyword);
+          if let Some((name, span)) = self.word() {
+            self.refer(name, NameKind::Module, span);
+          }
+          self.skip_declaration();
+        }
+
+        _ => self.skip_declaration(),
+      }
+    }
+  }
+
+  /// Reads `f₁ … : S₁ … -> S [attributes]` after `op`.
+  fn operators(&mut self) {
+    while self.continues() && !self.is_word(":") {
+      match self.word() {
+        Some((name, span)) => self.define(name, NameKind::Operator, span),
+        None               => self.position += 1,
+      }
+    }
+    if self.is_word(":") {
+      self.position += 1;
+    }
+
+    while self.continues() && self.peek() != Some(&Token::OpenBracket) {
+      match self.word() {
+        Some((arrow, _)) if arrow == "->" => {}
+        Some((sort, span))                => self.refer(sort, NameKind::Sort, span),
+        None                              => self.position += 1,
+      }
+    }
+
+    if !self.punctuation(Token::OpenBracket) {
+      return;
+    }
+    while self.continues() && self.peek() != Some(&Token::CloseBracket) {
+      if self.keyword("id:") {
+        if let Some((identity, span)) = self.word() {
+          self.refer(identity, NameKind::Operator, span);
+        }
+      } else {
+        self.literal();
+      }
+    }
+  }
+
+  /// Reads `[label] : lhs = rhs [attributes]`, or `=>` for a rule, after the keyword.
+  fn statement(&mut self, kind: StatementKind, start: Span) {
+    let mut label = None;
+    if self.punctuation(Token::OpenBracket) {
+      if let Some((name, span)) = self.word() {
+        self.define(name.clone(), NameKind::Label, span);
+        label = Some(name);
+      }
+      self.punctuation(Token::CloseBracket);
+      if self.is_word(":") {
+        self.position += 1;
+      }
+    }
+
+    // An opening bracket outside every argument list begins the attributes.
+    let mut depth = 0usize;
+    while self.continues() {
+      match self.peek() {
+        Some(Token::OpenBracket) if depth == 0 => {
+          self.position += 1;
+          while self.continues() && self.peek() != Some(&Token::CloseBracket) {
+            self.literal();
+          }
+          self.punctuation(Token::CloseBracket);
+        }
+        Some(Token::Open)      => { depth += 1; self.position += 1; }
+        Some(Token::Close)     => { depth = depth.saturating_sub(1); self.position += 1; }
+        Some(Token::String(_)) => self.literal(),
+        _ => match self.word() {
+          Some((word, _)) if word == "=" || word == "=>" => {}
+          Some(word) => self.terms.push(word),
+          None       => self.position += 1,
+        },
+      }
+    }
+
+    // The statement ends at its period, or at its last token if the period is missing.
+    if self.peek() == Some(&Token::Period) {
+      self.position += 1;
+    }
+    let end = self.tokens[self.position - 1].1;
+    self.index.statements.push(StatementSpan { kind, label, span: Span { start: start.start, end: end.end } });
+  }
+
+  /// Classifies the names occurring in terms and resolves every reference to its first definition.
+  fn finish(mut self) -> SourceIndex {
+    for (name, span) in core::mem::take(&mut self.terms) {
+      if self.sorts.contains_key(&name) {
+        self.refer(name, NameKind::Variable, span);
+        continue;
+      }
+      let is_operator = self.index.definitions.iter().any(|definition| {
+        definition.kind == NameKind::Operator && definition.name == name
+      });
+      if let Some((from, to)) = parse_retract_name(&name).filter(|_| !is_operator) {
+        // The retract `r:A>B` refers to both of its sorts.
+        let (line, column) = (span.start.line, span.start.column);
+        let from_end       = column + 2 + from.chars().count();
+        let (from, to)     = (from.to_string(), to.to_string());
+        self.refer(name, NameKind::Operator, Span::on_line(line, column, column + 1));
+        self.refer(from, NameKind::Sort, Span::on_line(line, column + 2, from_end));
+        self.refer(to, NameKind::Sort, Span::on_line(line, from_end + 1, span.end.column));
+        continue;
+      }
+      match name.split_once(':') {
+        Some((variable, sort)) if !is_operator && !variable.is_empty() && !sort.is_empty() => {
+          // The variable part and the sort part are separate tokens.
+          let length        = variable.chars().count();
+          let variable_span = Span::on_line(span.start.line, span.start.column, span.start.column + length);
+          let sort_span     = Span::on_line(span.start.line, span.start.column + length + 1, span.end.column);
+          if self.sorts.get(variable).is_some_and(|declared| declared == sort) {
+            self.refer(variable.to_string(), NameKind::Variable, variable_span);
+          } else if self.index.definitions.iter().any(|definition| definition.name == name) {
+            self.refer(name.clone(), NameKind::Variable, variable_span);
+          } else {
+            self.define(name.clone(), NameKind::Variable, variable_span);
+          }
+          self.refer(sort.to_string(), NameKind::Sort, sort_span);
+        }
+        _ => self.refer(name, NameKind::Operator, span),
+      }
+    }
+
+    let SourceIndex { definitions, references, .. } = &mut self.index;
+    definitions.sort_by_key(|definition| definition.span.start);
+    references.sort_by_key(|reference| reference.span.start);
+    for reference in references.iter_mut() {
+      reference.definition = definitions.iter().position(|definition| {
+        definition.name == reference.name && definition.kind == reference.kind
+      });
+    }
+
+    self.index
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str = "\
+mod PEANO is
+  sorts Nat Zero .
+  subsort Zero < Nat .
+  op 0 : -> Zero [ctor] .
+  op s : Nat -> Nat [ctor] .
+  op plus : Nat Nat -> Nat [assoc id: 0] .
+  var X : Nat .
+  *** addition
+  eq [plus-zero] : plus(X, 0) = X .
+  rl s(Y:Nat) => plus(Y:Nat, \"one\") [prio 2] .
+endm";
+
+  #[test]
+  fn names_resolve_to_their_declarations() {
+    let index = SourceIndex::new(SOURCE);
+
+    // The `Nat` of `s : Nat -> Nat` is a reference to the sort declared on line 2.
+    let nat = index.definition_at(Position { line: 5, column: 16 }).unwrap();
+    assert_eq!((nat.name.as_str(), nat.kind, nat.span), ("Nat", NameKind::Sort, Span::on_line(2, 8, 11)));
+    assert_eq!(index.references_at(Position { line: 2, column: 9 }).len(), 10);
+
+    // `X` is declared on line 7 and occurs twice in the equation.
+    let x = index.references_at(Position { line: 9, column: 32 });
+    assert_eq!(x, vec![Span::on_line(7, 6, 7), Span::on_line(9, 24, 25), Span::on_line(9, 32, 33)]);
+
+    // The on-the-fly `Y:Nat` is defined where it first occurs, and its sort part is a reference to `Nat`.
+    let y = index.definition_at(Position { line: 10, column: 22 }).unwrap();
+    assert_eq!((y.name.as_str(), y.span), ("Y:Nat", Span::on_line(10, 7, 8)));
+    let zero = index.definition_at(Position { line: 6, column: 38 }).unwrap();
+    assert_eq!((zero.kind, zero.span), (NameKind::Operator, Span::on_line(4, 5, 6)));
+
+    let statements = index.statements();
+    assert_eq!(statements.len(), 2);
+    assert_eq!(statements[0].label.as_deref(), Some("plus-zero"));
+    let start = Position { line: 9, column: 2 };
+    assert_eq!(statements[0].span, Span { start, end: Position { line: 9, column: 35 } });
+    assert_eq!(statements[1].kind, StatementKind::Rule);
+
+    let kinds = |line: usize| -> Vec<SemanticTokenKind> {
+      index.semantic_tokens().iter().filter(|token| token.span.start.line == line).map(|token| token.kind).collect()
+    };
+    use SemanticTokenKind::*;
+    assert_eq!(kinds(8), vec![Comment]);
+    assert_eq!(kinds(10), vec![Keyword, Operator, Variable, Sort, Operator, Variable, Sort, String, Keyword, Number]);
+  }
+
+  #[test]
+  fn sources_that_dont_parse_are_indexed() {
+    let index = SourceIndex::new("mod BROKEN is\n  sort S\n  op f : S -> S .\n  eq f(Z) =");
+    let names: Vec<&str> = index.definitions().iter().map(|definition| definition.name.as_str()).collect();
+    assert_eq!(names, ["BROKEN", "S", "f"]);
+    assert_eq!(index.statements().len(), 1);
+    // `Z` isn't declared, so it is taken for an operator declared elsewhere.
+    let z = index.references().iter().find(|reference| reference.name == "Z").unwrap();
+    assert_eq!((z.kind, z.definition), (NameKind::Operator, None));
+  }
+}
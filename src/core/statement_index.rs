@@ -0,0 +1,234 @@
+/*!
+
+A path index over the left-hand sides of a module's statements. Modules with hundreds of statements for the same symbol
+would otherwise attempt a full match against every one of them. The index records, for each statement, the symbols its
+left-hand side requires at each position in its top few levels (its *fingerprint*). Given a subject, only statements
+whose fingerprint agrees with the subject are returned as candidates for matching.
+
+A position is a path of argument indices from the top of the term. Variables match anything, so they contribute nothing
+to the fingerprint. Since the index only looks at a bounded number of levels, a candidate may still fail to match; the
+index never rejects a statement that would match.
+
+Subterms that can collapse (see `collapse`) can match subjects with other symbols, so they contribute nothing to the
+fingerprint either. A left-hand side that can collapse is also indexed under each symbol it can collapse to, and one
+that can collapse to a variable is a candidate for every subject.
+
+Statistics on the effectiveness of the index are kept in `IndexStatistics`.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::HashMap,
+  api::{
+    dag_node::DagNodePtr,
+    symbol::SymbolPtr,
+    term::Term
+  },
+  core::{
+    automata::has_associative_top,
+    collapse::{can_collapse, can_collapse_to_variable},
+    pre_equation::{PreEquation, PreEquationAttribute}
+  }
+};
+
+/// The number of levels below the top symbol that are indexed by default.
+pub const DEFAULT_INDEX_DEPTH: usize = 2;
+
+/// Counts of index lookups and outcomes.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct IndexStatistics {
+  /// The number of subjects looked up
+  pub lookups   : usize,
+  /// The number of statements returned as candidates
+  pub candidates: usize,
+  /// The number of statements with the subject's top symbol that the index ruled out without a match attempt
+  pub filtered  : usize,
+  /// The number of candidates that matched
+  pub hits      : usize,
+  /// The number of candidates that failed to match
+  pub misses    : usize,
+}
+
+/// The symbols a left-hand side requires at positions below its top.
+struct Fingerprint {
+  requirements: Vec<(Vec<usize>, SymbolPtr)>,
+}
+
+impl Fingerprint {
+  fn new(pattern: &dyn Term, depth: usize) -> Self {
+    let mut requirements = Vec::new();
+    let mut path         = Vec::new();
+    // The arguments of an associative pattern or subpattern are matched against the flattened arguments of the subject,
+    // and those of a pattern that collapses against the whole subject, so their positions say nothing about the
+    // subject's.
+    if !has_associative_top(pattern) && !can_collapse(pattern) {
+      Fingerprint::collect(pattern, depth, &mut path, &mut requirements);
+    }
+
+    Fingerprint { requirements }
+  }
+
+  /// A fingerprint that admits every subject
+  #[inline(always)]
+  fn empty() -> Self {
+    Fingerprint { requirements: Vec::new() }
+  }
+
+  fn collect(term: &dyn Term, depth: usize, path: &mut Vec<usize>, requirements: &mut Vec<(Vec<usize>, SymbolPtr)>) {
+    if path.len() >= depth {
+      return;
+    }
+
+    for (i, arg) in term.iter_args().enumerate() {
+      if arg.is_variable() || can_collapse(arg) {
+        continue;
+      }
+      path.push(i);
+      requirements.push((path.clone(), arg.symbol()));
+      if !has_associative_top(arg) {
+        Fingerprint::collect(arg, depth, path, requirements);
+      }
+      path.pop();
+    }
+  }
+
+  fn admits(&self, subject: DagNodePtr) -> bool {
+    self.requirements.iter().all(
+      |(path, symbol)| {
+        let mut node = subject;
+        for &i in path.iter() {
+          match unsafe{ &*node }.iter_args().nth(i) {
+            Some(arg) => node = arg,
+            None      => return false,
+          }
+        }
+        core::ptr::addr_eq(unsafe{ &*node }.symbol(), *symbol)
+      }
+    )
+  }
+}
+
+struct IndexEntry {
+  statement  : usize,
+  fingerprint: Fingerprint,
+}
+
+/// An index of statements by top symbol and fingerprint. Statements are referred to by their position in the slice
+/// the index was built from.
+pub struct StatementIndex {
+  entries   : HashMap<SymbolPtr, Vec<IndexEntry>>,
+  /// Statements whose left-hand side is a bare variable, or can collapse to one, are candidates for every subject.
+  unindexed : Vec<usize>,
+  statistics: IndexStatistics,
+}
+
+impl StatementIndex {
+  #[inline(always)]
+  pub fn new(statements: &[PreEquation]) -> Self {
+    Self::with_depth(statements, DEFAULT_INDEX_DEPTH)
+  }
+
+  /// Builds an index that examines `depth` levels below the top symbol.
+  pub fn with_depth(statements: &[PreEquation], depth: usize) -> Self {
+    let mut entries: HashMap<SymbolPtr, Vec<IndexEntry>> = HashMap::default();
+    let mut unindexed = Vec::new();
+
+    for (statement, pre_equation) in statements.iter().enumerate() {
+      if pre_equation.attributes.intersects(PreEquationAttribute::Bad | PreEquationAttribute::Subsumed) {
+        continue;
+      }
+      let lhs = pre_equation.lhs_term.as_ref();
+      if lhs.is_variable() || can_collapse_to_variable(lhs) {
+        unindexed.push(statement);
+        continue;
+      }
+
+      for &symbol in lhs.collapse_symbols().iter() {
+        if !core::ptr::addr_eq(symbol, lhs.symbol()) {
+          entries.entry(symbol).or_default().push(IndexEntry { statement, fingerprint: Fingerprint::empty() });
+        }
+      }
+
+      entries.entry(lhs.symbol()).or_default().push(
+        IndexEntry {
+          statement,
+          fingerprint: Fingerprint::new(lhs, depth)
+        }
+      );
+    }
+
+    StatementIndex {
+      entries,
+      unindexed,
+      statistics: IndexStatistics::default()
+    }
+  }
+
+  /// Replaces the contents of `candidates` with the statements that might match `subject`, in the order the
+  /// statements were given.
+  pub fn find_candidates(&mut self, subject: DagNodePtr, candidates: &mut Vec<usize>) {
+    candidates.clear();
+    self.statistics.lookups += 1;
+
+    if let Some(entries) = self.entries.get(&unsafe{ &*subject }.symbol()) {
+      for entry in entries.iter() {
+        if entry.fingerprint.admits(subject) {
+          candidates.push(entry.statement);
+        } else {
+          self.statistics.filtered += 1;
+        }
+      }
+    }
+
+    if !self.unindexed.is_empty() {
+      candidates.extend_from_slice(&self.unindexed);
+      candidates.sort_unstable();
+    }
+
+    self.statistics.candidates += candidates.len();
+  }
+
+  /// Like `find_candidates`, but without the fingerprints, so every statement with the subject's top symbol is a
+  /// candidate. For subjects with unreduced subterms, whose symbols may change once the subterms are reduced.
+  pub fn find_unfiltered_candidates(&mut self, subject: DagNodePtr, candidates: &mut Vec<usize>) {
+    candidates.clear();
+    self.statistics.lookups += 1;
+
+    if let Some(entries) = self.entries.get(&unsafe{ &*subject }.symbol()) {
+      candidates.extend(entries.iter().map(|entry| entry.statement));
+    }
+    if !self.unindexed.is_empty() {
+      candidates.extend_from_slice(&self.unindexed);
+      candidates.sort_unstable();
+    }
+
+    self.statistics.candidates += candidates.len();
+  }
+
+  /// Records the outcome of a match attempt against a candidate.
+  #[inline(always)]
+  pub fn record_match(&mut self, matched: bool) {
+    if matched {
+      self.statistics.hits += 1;
+    } else {
+      self.statistics.misses += 1;
+    }
+  }
+
+  #[inline(always)]
+  pub fn statistics(&self) -> &IndexStatistics {
+    &self.statistics
+  }
+
+  #[inline(always)]
+  pub fn reset_statistics(&mut self) {
+    self.statistics = IndexStatistics::default();
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_statistics(&mut self, statistics: IndexStatistics) {
+    self.statistics = statistics;
+  }
+}
@@ -0,0 +1,164 @@
+/*!
+
+Counters for tuning large, usually generated, signatures: how many symbols have been created, how often names are
+interned again, how much structural sharing the conversion of terms to DAGs finds, and how often the bound on
+duplications for idempotent symbols cut a match short. The counters are global and
+cumulative. Take a snapshot with `statistics()` and start over with `reset_statistics()`.
+
+The counts of a single module, its symbols by type, sorts, kinds, and statements, are given by
+`Module::symbol_statistics`.
+
+```ignore
+reset_statistics();
+let module = parse_module(source)?;
+let stats  = statistics();
+println!("{} symbols, {} of {} names repeated", stats.symbols_created, stats.intern_hits, stats.interned_strings);
+```
+
+*/
+
+use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+use crate::abstractions::{intern_counts, reset_intern_counts};
+
+static SYMBOLS_CREATED       : AtomicUsize = AtomicUsize::new(0);
+static CONVERSION_LOOKUPS    : AtomicUsize = AtomicUsize::new(0);
+static CONVERSION_HITS       : AtomicUsize = AtomicUsize::new(0);
+static CONVERSION_COLLISIONS : AtomicUsize = AtomicUsize::new(0);
+static DUPLICATION_BOUND_HITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts of the lookups in the cache `Term::term_to_dag` uses to share identical subterms.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct ConversionStatistics {
+  /// The number of subterms looked up, one for each subterm converted
+  pub lookups   : usize,
+  /// The number of subterms that were already converted and whose node was shared
+  pub hits      : usize,
+  /// The number of subterms whose hash was that of a different subterm already converted
+  pub collisions: usize,
+}
+
+/// A snapshot of the global counters. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct Statistics {
+  /// The number of symbols created, including variable symbols and symbols that have since been dropped
+  pub symbols_created       : usize,
+  /// The number of strings interned with `abstractions::intern`, which the parser uses for names
+  pub interned_strings      : usize,
+  /// The number of interned strings that had been interned before
+  pub intern_hits           : usize,
+  /// Lookups in the term to DAG conversion cache
+  pub conversion            : ConversionStatistics,
+  /// The number of failed match attempts cut short by the bound on duplications. See `automata::duplication`.
+  pub duplication_bound_hits: usize,
+}
+
+/// The counts of a single module. See `Module::symbol_statistics`.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct SymbolStatistics {
+  /// The number of symbols of every type
+  pub symbols   : usize,
+  /// The number of variable symbols, including those created on the fly for variables like `X:Nat`
+  pub variables : usize,
+  /// The number of symbols for data atoms
+  pub data      : usize,
+  /// The number of sorts, not counting the error sorts of kinds
+  pub sorts     : usize,
+  /// The number of kinds, or zero if the kinds have not been computed
+  pub kinds     : usize,
+  /// The number of equations, rules, and membership axioms
+  pub statements: usize,
+}
+
+/// A snapshot of the global counters.
+pub fn statistics() -> Statistics {
+  let (interned_strings, intern_hits) = intern_counts();
+
+  Statistics {
+    symbols_created       : SYMBOLS_CREATED.load(Relaxed),
+    interned_strings,
+    intern_hits,
+    conversion            : ConversionStatistics {
+      lookups   : CONVERSION_LOOKUPS.load(Relaxed),
+      hits      : CONVERSION_HITS.load(Relaxed),
+      collisions: CONVERSION_COLLISIONS.load(Relaxed),
+    },
+    duplication_bound_hits: DUPLICATION_BOUND_HITS.load(Relaxed),
+  }
+}
+
+/// Sets the global counters to zero.
+pub fn reset_statistics() {
+  SYMBOLS_CREATED.store(0, Relaxed);
+  reset_intern_counts();
+  CONVERSION_LOOKUPS.store(0, Relaxed);
+  CONVERSION_HITS.store(0, Relaxed);
+  CONVERSION_COLLISIONS.store(0, Relaxed);
+  DUPLICATION_BOUND_HITS.store(0, Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn record_symbol_created() {
+  SYMBOLS_CREATED.fetch_add(1, Relaxed);
+}
+
+/// Records a lookup in the term to DAG conversion cache. A collision is a cached node that turned out to be for a
+/// different subterm with the same hash.
+#[inline(always)]
+pub(crate) fn record_conversion_lookup(hit: bool, collision: bool) {
+  CONVERSION_LOOKUPS.fetch_add(1, Relaxed);
+  if hit {
+    CONVERSION_HITS.fetch_add(1, Relaxed);
+  }
+  if collision {
+    CONVERSION_COLLISIONS.fetch_add(1, Relaxed);
+  }
+}
+
+#[inline(always)]
+pub(crate) fn record_duplication_bound_hit() {
+  DUPLICATION_BOUND_HITS.fetch_add(1, Relaxed);
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::intern,
+    core::{
+      parser::{parse_module, parse_term},
+      statistics::statistics
+    }
+  };
+
+  // The counters are global and other tests run in parallel, so only lower bounds can be checked.
+  #[test]
+  fn counters_track_symbols_names_and_sharing() {
+    let before = statistics();
+    let module = parse_module(
+      "mod STATS is sort Nat . op 0 : -> Nat . op s : Nat -> Nat . op plus : Nat Nat -> Nat . endm"
+    ).unwrap();
+    let after  = statistics();
+    assert!(after.symbols_created >= before.symbols_created + 3);
+    // `Nat` is named four more times after its declaration.
+    assert!(after.interned_strings >= before.interned_strings + 4);
+    assert!(after.intern_hits >= before.intern_hits + 4);
+
+    let name = intern("a name no other test interns");
+    assert_eq!(intern(name.as_ref()), name);
+
+    let term   = parse_term(&module, "plus(s(0), s(0))").unwrap();
+    let before = statistics();
+    term.term_to_dag(false);
+    let after  = statistics();
+    // Four subterms are looked up, and the second `s(0)` is shared with the first.
+    assert!(after.conversion.lookups >= before.conversion.lookups + 4);
+    assert!(after.conversion.hits > before.conversion.hits);
+
+    let counts = module.symbol_statistics();
+    assert_eq!(counts.symbols, 3);
+    assert_eq!(counts.variables, 0);
+    assert_eq!(counts.sorts, 1);
+    assert_eq!(counts.statements, 0);
+  }
+}
@@ -10,17 +10,21 @@ by placing a reference to the DagNode at the index of the number. Names are numb
  */
 
 
-use std::cmp::min;
+use core::cmp::min;
+use alloc::{rc::Rc, vec::Vec};
 
 use crate::{
-  abstractions::NatSet,
   core::{
-    LocalBindings,
-    NarrowingVariableInfo,
-    VariableInfo
+    match_limits::MatchBudget,
+    LocalBindings
   },
   api::dag_node::DagNodePtr,
 };
+#[cfg(feature = "std")]
+use crate::{
+  abstractions::NatSet,
+  core::{NarrowingVariableInfo, VariableInfo}
+};
 
 pub type MaybeDagNode = Option<DagNodePtr>;
 
@@ -38,6 +42,9 @@ pub struct Substitution {
   The upshot is that we need a variable to track the size of `bindings` that is independent of its actual size.
   */
   copy_size: usize,
+
+  /// The limits on associative matches binding this substitution, shared by its clones. See `match_limits`.
+  budget: Option<Rc<MatchBudget>>,
 }
 
 impl Substitution {
@@ -51,7 +58,17 @@ impl Substitution {
     let mut bindings = Vec::with_capacity(n);
     bindings.resize(n, None);
 
-    Self { bindings, copy_size: n }
+    Self { bindings, copy_size: n, budget: None }
+  }
+
+  #[inline(always)]
+  pub(crate) fn budget(&self) -> Option<&Rc<MatchBudget>> {
+    self.budget.as_ref()
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_budget(&mut self, budget: Rc<MatchBudget>) {
+    self.budget = Some(budget);
   }
 
   #[inline(always)]
@@ -93,11 +110,11 @@ impl Substitution {
     );
 
     // The asserts give confidence but do not guarantee safety here.
-    unsafe { self.bindings.get_unchecked(index as usize).clone() }
+    unsafe { *self.bindings.get_unchecked(index as usize) }
   }
 
   #[inline(always)]
-  pub fn iter(&self) -> std::slice::Iter<Option<DagNodePtr>> {
+  pub fn iter(&self) -> core::slice::Iter<'_, Option<DagNodePtr>> {
     self.bindings.iter()
   }
 
@@ -111,7 +128,7 @@ impl Substitution {
     for (idx, (i, j)) in self.bindings.iter().zip(original.iter()).enumerate() {
       assert!(j.is_none() || i == j, "substitution inconsistency at index {}", idx);
       if let (Some(a), Some(b)) = (i, j) {
-        if !std::ptr::addr_eq(*a, *b) {
+        if !core::ptr::addr_eq(*a, *b) {
           local_bindings.add_binding(idx as i32, *a);
         }
       }
@@ -167,8 +184,9 @@ impl Substitution {
 
 
 // More specialized print functions for substitutions. These are used in narrowing.rs, trace_variant_narrowing_step in
-// rewrite_context.rs.
+// rewrite_context.rs. They print to standard output, so they need `std`.
 
+#[cfg(feature = "std")]
 pub fn print_substitution_dag(substitution: &[DagNodePtr], variable_info: &NarrowingVariableInfo) {
   for (i, var) in variable_info.iter() {
     let binding = unsafe { &*substitution[i] };
@@ -178,6 +196,7 @@ pub fn print_substitution_dag(substitution: &[DagNodePtr], variable_info: &Narro
   }
 }
 
+#[cfg(feature = "std")]
 pub fn print_substitution_narrowing(substitution: &Substitution, variable_info: &NarrowingVariableInfo) {
   let variable_count = substitution.fragile_binding_count();
 
@@ -190,15 +209,17 @@ pub fn print_substitution_narrowing(substitution: &Substitution, variable_info:
   }
 }
 
+#[cfg(feature = "std")]
 pub fn print_substitution(substitution: &Substitution, var_info: &VariableInfo) {
   print_substitution_with_ignored(substitution, var_info, &NatSet::default())
 }
 
+#[cfg(feature = "std")]
 pub fn print_substitution_with_ignored(substitution: &Substitution, var_info: &VariableInfo, ignored_indices: &NatSet) {
   let variable_count = var_info.real_variable_count();
   let mut printed_variable = false;
   for i in 0..variable_count {
-    if ignored_indices.contains(i as u8) {
+    if ignored_indices.contains(i) {
       continue;
     }
     let var = var_info.index_to_variable(i as i8);
@@ -0,0 +1,266 @@
+/*!
+
+Redundancy elimination. A statement *subsumes* another statement of the same kind if the other's left-hand side is an
+instance of its left-hand side under a substitution that also takes its conditions and right-hand side to the other's.
+Wherever the subsumed statement applies, the subsuming statement applies too, with the same result, so the subsumed
+statement is redundant. Machine-generated modules are often full of such statements.
+
+`eliminate_redundant_statements` finds the subsumed equations and rules of a module and, depending on the
+`RedundancyAction`, flags them `Subsumed`, which keeps them from being applied, or removes them. Each is reported as a
+`RedundantStatement` and logged at level 2.
+
+Instances are found by syntactic matching. That is sound modulo axioms too, but misses instances that only hold modulo
+axioms. A sorted variable of the subsuming statement is only instantiated by terms whose sort is the variable's sort or
+one of its subsorts, and a variable first bound by a condition only by a variable. Statements are only compared with
+statements with the same label, attributes, priority, and weight. Of two statements that subsume each other, the first
+is kept.
+
+*/
+
+use core::fmt::{Display, Formatter};
+use alloc::{string::{String, ToString}, vec::Vec};
+
+use crate::{
+  api::{symbol::SymbolPtr, term::Term},
+  core::{
+    module::Module,
+    pre_equation::{
+      condition::Condition,
+      PreEquation,
+      PreEquationAttribute,
+      PreEquationAttributes,
+      PreEquationKind
+    }
+  },
+  info
+};
+
+/// What `eliminate_redundant_statements` does with the statements it finds
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RedundancyAction {
+  /// Mark the statements `Subsumed`, so that they stay in the module but are never applied.
+  Flag,
+  /// Remove the statements from the module.
+  Drop,
+}
+
+/// A statement found to be redundant
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RedundantStatement {
+  /// Whether the statement is a rule rather than an equation
+  pub is_rule    : bool,
+  /// The position of the statement among the module's equations or rules, before any were removed
+  pub index      : usize,
+  /// The position of a statement that subsumes it
+  pub subsumed_by: usize,
+  /// The statement, as displayed
+  pub statement  : String,
+  /// The statement that subsumes it, as displayed
+  pub by         : String,
+}
+
+impl Display for RedundantStatement {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{} is subsumed by {}", self.statement, self.by)
+  }
+}
+
+/// Finds the equations and rules of `module` that other statements subsume and flags or removes them as `action`
+/// says. Returns the statements found, equations first, each in the order of the module.
+pub fn eliminate_redundant_statements(module: &mut Module, action: RedundancyAction) -> Vec<RedundantStatement> {
+  let mut redundant = find_redundant(&module.equations, false);
+  redundant.extend(find_redundant(&module.rules, true));
+
+  for found in redundant.iter() {
+    info!(2, "{}", found);
+  }
+
+  for is_rule in [false, true] {
+    let statements = match is_rule {
+      true  => &mut module.rules,
+      false => &mut module.equations,
+    };
+    let indices = redundant.iter().filter(|found| found.is_rule == is_rule).map(|found| found.index);
+
+    match action {
+      RedundancyAction::Flag => {
+        for index in indices {
+          statements[index].attributes.insert(PreEquationAttribute::Subsumed);
+        }
+      }
+      RedundancyAction::Drop => {
+        for index in indices.rev() {
+          statements.remove(index);
+        }
+      }
+    }
+  }
+
+  redundant
+}
+
+fn find_redundant(statements: &[PreEquation], is_rule: bool) -> Vec<RedundantStatement> {
+  let mut redundant = Vec::new();
+
+  for (index, statement) in statements.iter().enumerate() {
+    let subsumer = statements.iter().enumerate().position(
+      |(other, general)| {
+        other != index
+            && subsumes(general, statement)
+            // Of two statements that subsume each other, only the second is redundant.
+            && (other < index || !subsumes(statement, general))
+      }
+    );
+
+    if let Some(other) = subsumer {
+      redundant.push(
+        RedundantStatement {
+          is_rule,
+          index,
+          subsumed_by: other,
+          statement  : statement.to_string(),
+          by         : statements[other].to_string(),
+        }
+      );
+    }
+  }
+
+  redundant
+}
+
+/// Whether `general` subsumes `specific`. See the module documentation.
+pub fn subsumes(general: &PreEquation, specific: &PreEquation) -> bool {
+  if general.name != specific.name
+      || general.selection != specific.selection
+      || relevant_attributes(general.attributes) != relevant_attributes(specific.attributes)
+      || general.attributes.intersects(PreEquationAttribute::Bad | PreEquationAttribute::Subsumed)
+      || specific.attributes.contains(PreEquationAttribute::Bad)
+      || general.conditions.len() != specific.conditions.len()
+  {
+    return false;
+  }
+
+  let mut bindings = Vec::new();
+  if !instance_of(general.lhs_term.as_ref(), specific.lhs_term.as_ref(), &mut bindings, false) {
+    return false;
+  }
+
+  for (condition, specific_condition) in general.conditions.iter().zip(specific.conditions.iter()) {
+    if condition.operator() != specific_condition.operator() {
+      return false;
+    }
+    if let (Condition::SortMembership { sort, .. }, Condition::SortMembership { sort: specific_sort, .. })
+        = (condition.as_ref(), specific_condition.as_ref())
+    {
+      if sort.to_string() != specific_sort.to_string() {
+        return false;
+      }
+    }
+    for (term, specific_term) in condition.terms().into_iter().zip(specific_condition.terms()) {
+      if !instance_of(term.as_ref(), specific_term.as_ref(), &mut bindings, true) {
+        return false;
+      }
+    }
+  }
+
+  match (&general.kind, &specific.kind) {
+    (PreEquationKind::Equation { rhs_term }, PreEquationKind::Equation { rhs_term: specific_rhs })
+    | (PreEquationKind::Rule { rhs_term }, PreEquationKind::Rule { rhs_term: specific_rhs }) => {
+      instance_of(rhs_term.as_ref(), specific_rhs.as_ref(), &mut bindings, true)
+    }
+    _ => false
+  }
+}
+
+/// The attributes that must agree for a statement to subsume another
+#[inline(always)]
+fn relevant_attributes(attributes: PreEquationAttributes) -> PreEquationAttributes {
+  attributes & !(PreEquationAttribute::Compiled | PreEquationAttribute::Bad | PreEquationAttribute::Subsumed)
+}
+
+/// Whether `specific` is an instance of `general` under an extension of `bindings`, which is extended to the
+/// substitution. If `variables_only` is set, variables not yet bound may only be bound to variables.
+fn instance_of<'t>(
+  general       : &dyn Term,
+  specific      : &'t dyn Term,
+  bindings      : &mut Vec<(SymbolPtr, &'t dyn Term)>,
+  variables_only: bool
+) -> bool
+{
+  if general.is_variable() {
+    let variable = general.symbol();
+    if let Some((_, bound)) = bindings.iter().find(|(bound, _)| core::ptr::addr_eq(*bound, variable)) {
+      return bound.compare(specific).is_eq();
+    }
+    if variables_only && !specific.is_variable() {
+      return false;
+    }
+    if let Some(sort) = general.symbol_ref().variable_sort() {
+      match specific.compute_sort() {
+        Some(specific_sort) if unsafe{ &*specific_sort }.leq(unsafe{ &*sort }) => {}
+        _ => return false,
+      }
+    }
+
+    bindings.push((variable, specific));
+    return true;
+  }
+
+  !specific.is_variable()
+      && core::ptr::addr_eq(general.symbol(), specific.symbol())
+      && general.iter_args().count() == specific.iter_args().count()
+      && general.iter_args()
+                .zip(specific.iter_args())
+                .all(|(general_arg, specific_arg)| instance_of(general_arg, specific_arg, bindings, variables_only))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::core::{
+    parser::{parse_module, parse_term},
+    pre_equation::PreEquationAttribute,
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext,
+    subsumption::{eliminate_redundant_statements, RedundancyAction}
+  };
+
+  const MODULE: &str = "
+    mod REDUNDANT is
+      sorts Zero Nat .
+      subsort Zero < Nat .
+      op 0 : -> Zero .
+      op a : -> Nat .
+      op s : Nat -> Nat .
+      ops plus g : Nat Nat -> Nat .
+      vars X Y : Nat .
+      var Z : Zero .
+      eq plus(X, 0) = X .
+      eq plus(s(Y), 0) = s(Y) .
+      eq plus(0, 0) = a .
+      eq plus(X, s(Y)) = s(plus(X, Y)) .
+      eq plus(a, s(0)) = s(plus(a, 0)) .
+      eq g(Z, X) = X .
+      eq g(Y, X) = X .
+    endm
+  ";
+
+  #[test]
+  fn subsumed_statements_are_found() {
+    let mut module = parse_module(MODULE).unwrap();
+    let found      = eliminate_redundant_statements(&mut module, RedundancyAction::Drop);
+    let found: Vec<(usize, usize)> = found.iter().map(|found| (found.index, found.subsumed_by)).collect();
+    // `plus(0, 0) = a` disagrees with `plus(X, 0) = X` on the right-hand side, and `g(Y, X)` is more general than
+    // `g(Z, X)`, not less.
+    assert_eq!(found, vec![(1, 0), (4, 3), (5, 6)]);
+    assert_eq!(module.equations.len(), 4);
+
+    let mut module = parse_module(MODULE).unwrap();
+    eliminate_redundant_statements(&mut module, RedundancyAction::Flag);
+    assert!(module.equations[1].attributes.contains(PreEquationAttribute::Subsumed));
+
+    let mut context = RewritingContext::new(&module);
+    let term        = parse_term(&module, "plus(s(0), s(0))").unwrap();
+    assert_eq!(dag_to_string(context.reduce(term.term_to_dag(false))), "s(s(0))");
+  }
+}
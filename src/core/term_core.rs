@@ -8,27 +8,28 @@ of structural sharing, the node instances themselves are not in 1-to-1 correspon
 
 */
 
-use std::{
-  cell::Cell,
-  collections::HashMap,
-  ptr::NonNull,
+use core::{
+  cell::{Cell, RefCell},
   sync::atomic::{
     Ordering::Relaxed,
     AtomicBool,
   },
 };
-use std::collections::hash_map::Entry;
+use alloc::vec::Vec;
 use enumflags2::{bitflags, BitFlags};
-use once_cell::sync::Lazy;
 
+#[cfg(not(feature = "std"))]
+use crate::abstractions::LocalKey;
 use crate::{
-  abstractions::NatSet,
+  abstractions::{HashMap, NatSet},
   api::{
+    NONE,
     UNDEFINED,
     symbol::{Symbol, SymbolPtr, SymbolSet},
     dag_node::DagNodePtr
   },
   core::{
+    collapse::Collapse,
     sort::SortPtr,
   },
 };
@@ -38,13 +39,17 @@ use crate::{
 // pub type MaybeTerm = Option<BxTerm>;
 pub type TermSet   = HashMap<u32, usize>;
 
-static mut CONVERTED_TERMS: Lazy<TermSet> =  Lazy::new(|| {
-  TermSet::new()
-});
-
-static mut SUBDAG_CACHE : Lazy<Vec<DagNodePtr>> = Lazy::new(|| {
-  Vec::new()
-});
+// The caches used by `Term::dagify()` for structural sharing. They are thread local because the nodes they hold are
+// only valid during a single conversion on a single thread.
+#[cfg(feature = "std")]
+thread_local! {
+  static CONVERTED_TERMS: RefCell<TermSet>         = RefCell::new(TermSet::default());
+  static SUBDAG_CACHE   : RefCell<Vec<DagNodePtr>> = const { RefCell::new(Vec::new()) };
+}
+#[cfg(not(feature = "std"))]
+static CONVERTED_TERMS: LocalKey<RefCell<TermSet>>         = LocalKey::new(|| RefCell::new(TermSet::default()));
+#[cfg(not(feature = "std"))]
+static SUBDAG_CACHE   : LocalKey<RefCell<Vec<DagNodePtr>>> = LocalKey::new(|| RefCell::new(Vec::new()));
 
 static SET_SORT_INFO_FLAG: AtomicBool = AtomicBool::new(false);
 
@@ -76,14 +81,18 @@ pub enum TermAttribute {
 
 pub type TermAttributes = BitFlags<TermAttribute, u8>;
 
+#[derive(Clone)]
 pub struct TermCore {
   /// The top symbol of the term
   pub(crate) symbol: SymbolPtr,
-  pub(crate) sort  : Option<NonNull<SortPtr>>,
+  /// The sort of the term, once computed by `Term::compute_sort`
+  pub(crate) sort  : Cell<Option<SortPtr>>,
   /// The handles (indices) for the variable terms that occur in this term or its descendants
   pub(crate) occurs_set      : NatSet,
   pub(crate) context_set     : NatSet,
+  /// The symbols an instance can have on top once it has collapsed, and the ways it can collapse. See `collapse`.
   pub(crate) collapse_symbols: SymbolSet,
+  pub(crate) collapse        : Option<Collapse>,
   pub(crate) attributes      : TermAttributes,
   pub(crate) term_kind       : TermKind,
   pub(crate) save_index      : i32,            // NoneIndex = -1
@@ -97,13 +106,14 @@ impl TermCore {
   pub fn new(symbol: SymbolPtr) -> TermCore {
     TermCore {
       symbol,
-      sort            : None,
+      sort            : Cell::new(None),
       occurs_set      : Default::default(),
       context_set     : Default::default(),
       collapse_symbols: Default::default(),
+      collapse        : None,
       attributes      : TermAttributes::default(),
       term_kind       : TermKind::Free,
-      save_index      : 0,
+      save_index      : NONE,
       hash_value      : 0,
       cached_size     : Cell::new(UNDEFINED),
     }
@@ -193,30 +203,36 @@ impl TermCore {
 
 }
 
+/// Whether `Term::dagify()` gives the nodes it creates the sorts of their terms.
+#[inline(always)]
+pub(crate) fn set_sort_info() -> bool {
+  SET_SORT_INFO_FLAG.load(Relaxed)
+}
+
 /// This function is called from `Term::term_to_dag()`.
 pub fn clear_cache_and_set_sort_info(set_sort_info: bool) {
   SET_SORT_INFO_FLAG.store(set_sort_info, Relaxed);
-  unsafe { #[allow(static_mut_refs)] SUBDAG_CACHE.clear(); }
-  unsafe { #[allow(static_mut_refs)] CONVERTED_TERMS.clear(); }
+  SUBDAG_CACHE.with_borrow_mut(|cache| cache.clear());
+  CONVERTED_TERMS.with_borrow_mut(|terms| terms.clear());
 }
 
 /// This free function plays the role of `Term::dagify()`. The sub DAG cache implements structural
 /// sharing.
 pub fn lookup_node_for_term(semantic_hash: u32) -> Option<DagNodePtr> {
-  if let Entry::Occupied(occupied_entry) = unsafe{ #[allow(static_mut_refs)] CONVERTED_TERMS.entry(semantic_hash) } {
-    let idx = *occupied_entry.get();
+  let idx = CONVERTED_TERMS.with_borrow(|terms| terms.get(&semantic_hash).copied())?;
 
-    Some(unsafe{ SUBDAG_CACHE[idx] })
-  } else {
-    None
-  }
+  Some(SUBDAG_CACHE.with_borrow(|cache| cache[idx]))
 }
 
 /// This free function (along with the one above) plays the role of `Term::dagify()`.
 /// The sub DAG cache implements structural sharing.
 pub fn cache_node_for_term(semantic_hash: u32, node: DagNodePtr) {
-  let idx = unsafe{ #[allow(static_mut_refs)] SUBDAG_CACHE.len() };
-  unsafe{ #[allow(static_mut_refs)] SUBDAG_CACHE.push(node) };
+  let idx = SUBDAG_CACHE.with_borrow_mut(
+    |cache| {
+      cache.push(node);
+      cache.len() - 1
+    }
+  );
   // sub_dags.insert(self_hash, d.clone());
-  unsafe{ #[allow(static_mut_refs)] CONVERTED_TERMS.insert(semantic_hash, idx) };
+  CONVERTED_TERMS.with_borrow_mut(|terms| terms.insert(semantic_hash, idx));
 }
@@ -0,0 +1,243 @@
+/*!
+
+Quick matching of a pattern written in the module language against a DAG, with the bindings of the pattern's
+variables converted to Rust values, for scripting against the engine:
+
+```ignore
+let matched = module.match_term("pair(X, Y)", subject);
+let x: i64  = matched.bind("X")?;
+let y       = matched.bind::<String>("Y")?;
+```
+
+`Module::match_term` parses the pattern with `parse_term`, compiles it through the module's pattern cache (see
+`pattern_cache`), gives the data atoms of the subject their sorts in the module (see `sort::predicate`), and matches
+the subject against it. The result holds the bindings, or the reason there are none. `TermMatch::bind` converts the
+binding of a variable with `FromBinding`, which is implemented for the built-in data atoms' Rust types, for `Value`,
+and for the bound `DagNodePtr` itself. A variable is named as in the pattern, or by its name without its sort: the
+on-the-fly variable `Y:Nat` can also be named `Y`, and the declared variable `Y` of sort `Nat` can be named `Y:Nat`.
+
+*/
+
+use core::{
+  error::Error,
+  fmt::{Display, Formatter}
+};
+use alloc::{string::{String, ToString}, vec::Vec};
+
+use num_bigint::BigInt;
+
+use crate::{
+  api::{
+    dag_node::{as_node_ptr, DagNode, DagNodePtr},
+    float::FloatAtom,
+    integer::{integer_value, IntegerAtom},
+    string::StringAtom,
+    term::Term,
+    value::Value
+  },
+  core::{
+    module::Module,
+    parser::{parse_term, ParseError}
+  }
+};
+
+/// Why `TermMatch::bind` has no value to give
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum BindError {
+  /// The pattern doesn't parse over the module.
+  Parse(ParseError),
+  /// The pattern doesn't match the subject.
+  NoMatch,
+  /// The pattern has no variable of that name.
+  UnknownVariable(String),
+  /// The variable is bound to a DAG that isn't a value of the type asked for. Holds the DAG as displayed.
+  WrongType { variable: String, expected: &'static str, found: String },
+}
+
+impl Display for BindError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      BindError::Parse(error)             => write!(f, "{}", error),
+      BindError::NoMatch                  => write!(f, "the pattern doesn't match"),
+      BindError::UnknownVariable(name)    => write!(f, "the pattern has no variable {}", name),
+      BindError::WrongType { variable, expected, found } => {
+        write!(f, "{} is bound to {}, which is not a {}", variable, found, expected)
+      }
+    }
+  }
+}
+
+impl Error for BindError {}
+
+/// A Rust type that the binding of a variable can be converted to
+pub trait FromBinding: Sized {
+  /// The value of the DAG `node`, or `None` if it isn't a value of the type
+  fn from_binding(node: &dyn DagNode) -> Option<Self>;
+}
+
+impl FromBinding for i64 {
+  fn from_binding(node: &dyn DagNode) -> Option<Self> {
+    node.as_data::<IntegerAtom>().map(|integer| integer.0)
+  }
+}
+
+impl FromBinding for BigInt {
+  fn from_binding(node: &dyn DagNode) -> Option<Self> {
+    integer_value(node.data()?)
+  }
+}
+
+impl FromBinding for f64 {
+  fn from_binding(node: &dyn DagNode) -> Option<Self> {
+    node.as_data::<FloatAtom>().map(|float| float.0)
+  }
+}
+
+impl FromBinding for String {
+  fn from_binding(node: &dyn DagNode) -> Option<Self> {
+    node.as_data::<StringAtom>().map(|string| string.0.clone())
+  }
+}
+
+impl FromBinding for Value {
+  fn from_binding(node: &dyn DagNode) -> Option<Self> {
+    Some(node.to_value())
+  }
+}
+
+impl FromBinding for DagNodePtr {
+  fn from_binding(node: &dyn DagNode) -> Option<Self> {
+    Some(as_node_ptr(node))
+  }
+}
+
+/// The outcome of `Module::match_term`. See the module documentation.
+pub struct TermMatch {
+  /// The names of the pattern's variables paired with their bindings, or why there are none
+  bindings: Result<Vec<(String, DagNodePtr)>, BindError>,
+}
+
+impl TermMatch {
+  /// Whether the pattern parsed and matched
+  #[inline(always)]
+  pub fn is_match(&self) -> bool {
+    self.bindings.is_ok()
+  }
+
+  /// The DAG the variable `name` is bound to
+  pub fn node(&self, name: &str) -> Result<DagNodePtr, BindError> {
+    let bindings = self.bindings.as_ref().map_err(Clone::clone)?;
+    bindings.iter()
+            .find(|(variable, _)| variable == name)
+            .or_else(|| bindings.iter().find(|(variable, _)| without_sort(variable) == without_sort(name)))
+            .map(|&(_, node)| node)
+            .ok_or_else(|| BindError::UnknownVariable(name.to_string()))
+  }
+
+  /// The binding of the variable `name` converted to a `T`
+  pub fn bind<T: FromBinding>(&self, name: &str) -> Result<T, BindError> {
+    let node = unsafe{ &*self.node(name)? };
+    T::from_binding(node).ok_or_else(|| {
+      BindError::WrongType {
+        variable: name.to_string(),
+        expected: core::any::type_name::<T>(),
+        found   : node.to_string(),
+      }
+    })
+  }
+}
+
+impl Module {
+  /// Matches `subject` against the pattern written in `pattern`. See `term_match`.
+  pub fn match_term(&self, pattern: &str, subject: DagNodePtr) -> TermMatch {
+    let term = match parse_term(self, pattern) {
+      Ok(term)   => term,
+      Err(error) => return TermMatch { bindings: Err(BindError::Parse(error)) },
+    };
+
+    self.sort_data_atoms(subject);
+    // The compiled pattern reports bindings in the order in which the variables first occur.
+    let mut names = Vec::new();
+    variable_names(term.as_ref(), &mut names);
+    let bindings = match self.compile_pattern(term.as_ref()).match_(subject) {
+      Some(bindings) => Ok(names.into_iter().zip(bindings).collect()),
+      None           => Err(BindError::NoMatch),
+    };
+
+    TermMatch { bindings }
+  }
+}
+
+/// `Y` for `Y:Nat`
+fn without_sort(name: &str) -> &str {
+  name.split_once(':').map_or(name, |(name, _)| name)
+}
+
+/// Appends the names of the variables of `term` not already in `names`, in the order in which they first occur.
+fn variable_names(term: &dyn Term, names: &mut Vec<String>) {
+  if term.is_variable() {
+    let name = term.symbol_ref().name.to_string();
+    if !names.contains(&name) {
+      names.push(name);
+    }
+    return;
+  }
+
+  for arg in term.iter_args() {
+    variable_names(arg, names);
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    api::{
+      dag_node::DagNodePtr,
+      data_theory::DataDagNode,
+      free_theory::FreeDagNode,
+      integer::{integer, IntegerAtom},
+      string::StringAtom,
+      value::Value
+    },
+    core::{parser::parse_module, term_match::BindError, RootContainer}
+  };
+
+  #[test]
+  fn bindings_convert_to_rust_values() {
+    let mut module = parse_module("
+      mod PAIRS is
+        sort Elt .
+        op pair : Elt Elt -> Elt .
+        op nil : -> Elt .
+        vars X Y : Elt .
+      endm
+    ").unwrap();
+    let elt = module.arena.sorts.get_by_name(&"Elt".into()).unwrap();
+    module.add_sort_predicate(elt, |atom| atom.as_any().is::<IntegerAtom>() || atom.as_any().is::<StringAtom>());
+
+    let symbol  = |name: &str| module.arena.symbol(&name.into()).unwrap();
+    let pair    = FreeDagNode::new(symbol("pair"));
+    let nil     = FreeDagNode::new(symbol("nil"));
+    unsafe{ &mut *pair }.insert_child(DataDagNode::new(integer(42))).unwrap();
+    unsafe{ &mut *pair }.insert_child(DataDagNode::new(Box::new(StringAtom("answer".to_string())))).unwrap();
+    let _root   = RootContainer::new(pair);
+
+    let matched = module.match_term("pair(X, Y:Elt)", pair);
+    assert_eq!(matched.bind::<i64>("X"), Ok(42));
+    assert_eq!(matched.bind::<String>("Y"), Ok("answer".to_string()));
+    assert_eq!(matched.bind::<Value>("Y:Elt"), Ok(Value::String("answer".to_string())));
+    assert_eq!(
+      matched.bind::<f64>("X"),
+      Err(BindError::WrongType { variable: "X".to_string(), expected: "f64", found: "42".to_string() })
+    );
+    assert_eq!(matched.bind::<i64>("Z"), Err(BindError::UnknownVariable("Z".to_string())));
+
+    let matched = module.match_term("pair(X, X)", pair);
+    assert_eq!(matched.bind::<i64>("X"), Err(BindError::NoMatch));
+    let matched = module.match_term("pair(X, nil)", pair);
+    assert!(!matched.is_match());
+    assert!(matches!(module.match_term("pair(X", pair).bind::<i64>("X"), Err(BindError::Parse(_))));
+    assert!(std::ptr::addr_eq(module.match_term("X", nil).bind::<DagNodePtr>("X").unwrap(), nil));
+  }
+}
@@ -0,0 +1,161 @@
+/*!
+
+The theory table maps each `DagNodeTheory` to what the engine needs to handle a node of that theory when all it has is
+a thin `DagNodeCore` pointer:
+
+| Entry           | Used by                                                        |
+|:----------------|:---------------------------------------------------------------|
+| `upgrade`       | `DagNodeCore::upgrade`, to make a `dyn DagNode` pointer        |
+| `drop_in_place` | The sweep of the node allocator, to destroy an unmarked node   |
+| `with_args`     | `build_node`, to make a node of a symbol from its arguments    |
+| `name`          | Debug formatting of nodes                                      |
+
+Every node type is a `#[repr(transparent)]` wrapper around `DagNodeCore`, so a pointer to a node's core is also a
+pointer to the node. A theory's entry is generated from its node type by `TheoryEntry::of`, which gets the vtable
+from the compiler by an ordinary unsizing cast.
+
+To add a theory, add a variant to `DagNodeTheory`, register its node type in `THEORY_TABLE` at the index of the
+variant, and map the symbols of the theory to it in `theory_of`.
+
+*/
+
+use core::ptr::drop_in_place;
+use alloc::vec::Vec;
+
+use crate::{
+  api::{
+    dag_node::{DagNode, DagNodePtr},
+    symbol::{SymbolPtr, SymbolType},
+    data_theory::DataDagNode,
+    free_theory::FreeDagNode,
+    variable_theory::VariableDagNode
+  },
+  core::dag_node_core::{DagNodeTheory, ThinDagNodePtr}
+};
+
+/// The number of variants of `DagNodeTheory`
+pub const THEORY_COUNT: usize = 3;
+
+/// The operations on the nodes of one theory.
+#[derive(Copy, Clone)]
+pub struct TheoryEntry {
+  /// The name of the theory, for debug output
+  pub name         : &'static str,
+  /// Casts a thin pointer to a node of the theory to a `dyn DagNode` pointer with the vtable of the node type.
+  pub upgrade      : fn(ThinDagNodePtr) -> DagNodePtr,
+  /// Runs the destructor of the node type on a dead node of the theory.
+  pub drop_in_place: unsafe fn(ThinDagNodePtr),
+  /// Makes a node of the theory with the given top symbol and arguments, or `None` if the theory has no node with
+  /// that many arguments.
+  pub with_args    : fn(SymbolPtr, &mut Vec<DagNodePtr>) -> Option<DagNodePtr>,
+}
+
+impl TheoryEntry {
+  /// The entry for nodes of type `T`, which must be a `#[repr(transparent)]` wrapper around `DagNodeCore`.
+  pub const fn of<T: DagNode + 'static>(
+    name     : &'static str,
+    with_args: fn(SymbolPtr, &mut Vec<DagNodePtr>) -> Option<DagNodePtr>
+  ) -> TheoryEntry
+  {
+    TheoryEntry {
+      name,
+      upgrade      : upgrade_as::<T>,
+      drop_in_place: drop_as::<T>,
+      with_args,
+    }
+  }
+}
+
+/// The registered node types, indexed by `DagNodeTheory`. A theory without a node type has no entry.
+static THEORY_TABLE: [Option<TheoryEntry>; THEORY_COUNT] = [
+  Some(TheoryEntry::of::<FreeDagNode>("free", |symbol, args| Some(FreeDagNode::with_args(symbol, args)))),
+  Some(TheoryEntry::of::<VariableDagNode>("variable", |symbol, args| {
+    args.is_empty().then(|| VariableDagNode::new(symbol))
+  })),
+  // A data node holds an atom, not a symbol and arguments.
+  Some(TheoryEntry::of::<DataDagNode>("data", |_, _| None)),
+];
+
+/// The entry of `theory`. Panics if no node type is registered for `theory`.
+#[inline(always)]
+pub fn theory_entry(theory: DagNodeTheory) -> &'static TheoryEntry {
+  match &THEORY_TABLE[theory as usize] {
+    Some(entry) => entry,
+    None        => panic!("no node type is registered for the {:?} theory", theory)
+  }
+}
+
+/// The theory of the nodes whose top symbol is `symbol`
+pub fn theory_of(symbol: SymbolPtr) -> DagNodeTheory {
+  match unsafe{ &*symbol }.symbol_type {
+    SymbolType::Variable => DagNodeTheory::Variable,
+    SymbolType::Data     => DagNodeTheory::Data,
+    _                    => DagNodeTheory::Free,
+  }
+}
+
+/// A node with top symbol `symbol` and the arguments `args`, made by the theory of `symbol`, or `None` if that theory
+/// has no node with that many arguments.
+pub fn build_node(symbol: SymbolPtr, args: &mut Vec<DagNodePtr>) -> Option<DagNodePtr> {
+  (theory_entry(theory_of(symbol)).with_args)(symbol, args)
+}
+
+#[inline(always)]
+fn upgrade_as<T: DagNode + 'static>(node: ThinDagNodePtr) -> DagNodePtr {
+  node as *mut T as DagNodePtr
+}
+
+unsafe fn drop_as<T: DagNode + 'static>(node: ThinDagNodePtr) {
+  unsafe{ drop_in_place(node as *mut T) }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      free_theory::FreeDagNode,
+      symbol::Symbol,
+      variable_theory::VariableDagNode
+    },
+    core::{
+      dag_node_core::{DagNodeCore, DagNodeTheory},
+      theory_table::{build_node, theory_entry, theory_of}
+    }
+  };
+
+  #[test]
+  fn upgrade_recovers_the_node_type() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+
+    let free     = FreeDagNode::new(&mut f);
+    let variable = VariableDagNode::new(&mut x);
+    for (node, theory) in [(free, DagNodeTheory::Free), (variable, DagNodeTheory::Variable)] {
+      let thin     = unsafe{ &mut *node }.core_mut() as *mut DagNodeCore;
+      let upgraded = (theory_entry(theory).upgrade)(thin);
+      assert!(std::ptr::addr_eq(upgraded, node));
+      assert_eq!(unsafe{ &*upgraded }.as_any().is::<FreeDagNode>(), theory == DagNodeTheory::Free);
+    }
+    assert_eq!(theory_entry(DagNodeTheory::Variable).name, "variable");
+  }
+
+  #[test]
+  fn build_node_dispatches_on_the_theory_of_the_symbol() {
+    let mut f = Symbol::new(IString::from("f"), Arity::Value(1));
+    let mut a = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut x = Symbol::new_variable(IString::from("X"));
+
+    let a_node = build_node(&mut a, &mut vec![]).unwrap();
+    let f_node = build_node(&mut f, &mut vec![a_node]).unwrap();
+    assert_eq!(unsafe{ &*f_node }.core().theory(), DagNodeTheory::Free);
+    assert_eq!(unsafe{ &*f_node }.iter_args().count(), 1);
+
+    assert_eq!(theory_of(&mut x), DagNodeTheory::Variable);
+    let x_node = build_node(&mut x, &mut vec![]).unwrap();
+    assert!(unsafe{ &*x_node }.as_any().is::<VariableDagNode>());
+    assert!(build_node(&mut x, &mut vec![a_node]).is_none());
+  }
+}
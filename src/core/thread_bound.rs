@@ -0,0 +1,201 @@
+/*!
+
+The engine is not thread safe. The node allocator and the list of roots are global and guarded by mutexes, but a garbage
+collection marks every root and sweeps every unreachable node, including nodes another thread is still building.
+Symbols, sorts, terms, and DAG nodes are shared through raw pointers without synchronization. The engine must therefore
+be used by one thread at a time.
+
+## Thread-Confined Types
+
+Every type that holds a raw pointer into the engine is confined to the thread that created it. This includes
+`Module`, `Term`s, `DagNodePtr`, `Substitution`, `RewritingContext`, and sorts and kinds. The compiler enforces this, as
+raw pointers are neither `Send` nor `Sync`. The caches used while converting a term to a DAG are thread local.
+
+A few types are deliberately `Send`:
+
+ - `Symbol`, and its `SortTable`, so that data atom symbols can be statics. A symbol must not be mutated once shared.
+ - `RootContainer`, since roots live in the global list of roots regardless of which thread created them.
+ - Plain data produced by the engine, like a `ProofTrace` or a `Checkpoint`.
+
+## Moving Work Between Threads
+
+A `ThreadBoundEngine` is a handle that binds the engine to the current thread. At most one exists at a time, and it
+can't leave its thread. Work moves between threads by handing the engine over:
+
+```ignore
+// On thread A
+let engine = ThreadBoundEngine::acquire()?;
+let module = build_module();
+let parcel = unsafe{ engine.transfer(module) };
+drop(engine);
+sender.send(parcel)?;
+
+// On thread B
+let parcel = receiver.recv()?;
+let engine = ThreadBoundEngine::acquire()?;
+let module = engine.receive(parcel);
+```
+
+A `Transfer` can only be created and opened by a thread holding the engine, so the value inside it is never used by two
+threads at once.
+
+## Checking the Owner
+
+While the engine is bound, its entry points check that they are called on the thread holding it, and panic otherwise.
+The entry points are creating a `RewritingContext`, parsing a module, and collecting garbage. The other operations go
+through values confined to the thread that created them, so a context created on the owning thread is only ever used
+there. The check costs an atomic load while the engine is unbound, and using the engine without binding it is allowed:
+a program that never acquires a `ThreadBoundEngine` must keep to one thread itself.
+
+## Without `std`
+
+Without the `std` feature there are no threads to bind the engine to, so there is no `ThreadBoundEngine`, and the
+entry points don't check anything.
+
+*/
+
+#[cfg(feature = "std")]
+use core::{
+  error::Error,
+  fmt::{Display, Formatter},
+  marker::PhantomData,
+  sync::atomic::{AtomicBool, Ordering}
+};
+#[cfg(feature = "std")]
+use std::{
+  sync::Mutex,
+  thread::{self, ThreadId}
+};
+
+#[cfg(feature = "std")]
+use crate::core::{
+  allocator::{configure_allocators, ok_to_collect_garbage},
+  automata::duplication::set_max_duplications,
+  config::{ConfigError, EngineConfig}
+};
+#[cfg(feature = "std")]
+use crate::abstractions::log::set_global_logging_threshold;
+
+/// The thread holding the engine, if any
+#[cfg(feature = "std")]
+static ENGINE_OWNER: Mutex<Option<ThreadId>> = Mutex::new(None);
+/// Whether `ENGINE_OWNER` holds a thread, so that `check_owner` only takes the lock while the engine is bound
+#[cfg(feature = "std")]
+static ENGINE_BOUND: AtomicBool = AtomicBool::new(false);
+
+/// The engine is already bound to a thread.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct EngineBusy;
+
+#[cfg(feature = "std")]
+impl Display for EngineBusy {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "the engine is already bound to a thread")
+  }
+}
+
+#[cfg(feature = "std")]
+impl Error for EngineBusy {}
+
+/// A handle binding the engine to the current thread. See the module documentation.
+#[cfg(feature = "std")]
+pub struct ThreadBoundEngine {
+  // Neither `Send` nor `Sync`
+  _thread_confined: PhantomData<*mut ()>,
+}
+
+#[cfg(feature = "std")]
+impl ThreadBoundEngine {
+  /// Binds the engine to the current thread until the returned handle is dropped. Fails if the engine is already bound,
+  /// including to the current thread.
+  pub fn acquire() -> Result<ThreadBoundEngine, EngineBusy> {
+    let mut owner = ENGINE_OWNER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if owner.is_some() {
+      return Err(EngineBusy);
+    }
+
+    *owner = Some(thread::current().id());
+    ENGINE_BOUND.store(true, Ordering::Release);
+    Ok(ThreadBoundEngine { _thread_confined: PhantomData })
+  }
+
+  /// The thread the engine is bound to, if any.
+  pub fn owner() -> Option<ThreadId> {
+    *ENGINE_OWNER.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Applies the process-wide settings of `config`: the logging threshold and the allocator settings. The session
+  /// settings are applied by passing `config` to `RewritingContext::with_config`. Nothing is changed if the
+  /// configuration is invalid, or if it asks for a different allocator backend after nodes have been allocated.
+  pub fn configure(&self, config: &EngineConfig) -> Result<(), ConfigError> {
+    config.validate()?;
+    configure_allocators(config.report_gc, config.allocator)?;
+    set_global_logging_threshold(config.logging_threshold);
+    set_max_duplications(config.max_duplications);
+    Ok(())
+  }
+
+  /// Collects garbage if the allocator wants to. Only nodes reachable from roots survive.
+  #[inline(always)]
+  pub fn collect_garbage(&self) {
+    ok_to_collect_garbage();
+  }
+
+  /// Wraps `value` so that it can be sent to another thread, which opens it with `receive`.
+  ///
+  /// # Safety
+  ///
+  /// The current thread must not keep anything that shares unsynchronized state with `value`, like a clone of an `Rc`
+  /// inside it, or a pointer to a node or symbol inside it that it uses after releasing the engine.
+  #[inline(always)]
+  pub unsafe fn transfer<T>(&self, value: T) -> Transfer<T> {
+    Transfer(value)
+  }
+
+  /// Opens a `Transfer` sent from another thread.
+  #[inline(always)]
+  pub fn receive<T>(&self, transfer: Transfer<T>) -> T {
+    transfer.0
+  }
+}
+
+#[cfg(feature = "std")]
+impl Drop for ThreadBoundEngine {
+  fn drop(&mut self) {
+    let mut owner = ENGINE_OWNER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    ENGINE_BOUND.store(false, Ordering::Release);
+    *owner = None;
+  }
+}
+
+/// Panics if the engine is bound to a thread other than the current one. See the module documentation.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) fn check_owner() {
+  if ENGINE_BOUND.load(Ordering::Acquire) {
+    check_owner_locked();
+  }
+}
+
+/// Without `std` the engine is never bound to a thread.
+#[cfg(not(feature = "std"))]
+#[inline(always)]
+pub(crate) fn check_owner() {}
+
+#[cfg(feature = "std")]
+#[cold]
+fn check_owner_locked() {
+  if let Some(owner) = ThreadBoundEngine::owner() {
+    assert_eq!(owner, thread::current().id(), "the engine is bound to another thread");
+  }
+}
+
+/// A value in transit between threads, created by `ThreadBoundEngine::transfer`.
+#[cfg(feature = "std")]
+pub struct Transfer<T>(T);
+
+// The value can only be reached through a `ThreadBoundEngine`, of which there is at most one at a time.
+#[cfg(feature = "std")]
+unsafe impl<T> Send for Transfer<T> {}
+
@@ -0,0 +1,658 @@
+/*!
+
+Unification of terms modulo the structural axioms of their symbols. Two terms *unify* if some substitution, a
+*unifier*, makes them equal modulo the axioms. Modulo commutativity, or associativity and commutativity, a pair of terms
+can have several most general unifiers, and `unify` returns a complete set of them: every unifier of the terms is an
+instance of one in the set. This is what narrowing modulo axioms needs in place of matching.
+
+Symbols are handled according to their axioms:
+
+ - a free symbol is unified argument by argument;
+ - a commutative symbol is unified with its arguments in both orders;
+ - an associative and commutative symbol is unified with Stickel's algorithm. Both sides are flattened, the arguments
+   they share are cancelled, and the rest are abstracted into a linear Diophantine equation whose coefficients are the
+   multiplicities of the arguments. The minimal solutions of the equation, its *basis*, are computed, and every subset
+   of the basis that gives each argument at least one fresh variable, and each non-variable argument exactly one, is a
+   way of distributing fresh variables among the arguments. The equations between the arguments and their shares are
+   then solved in turn.
+
+Symbols with other axioms, that is, associativity without commutativity, identities, or idempotency, are treated as
+free, which loses unifiers.
+
+```ignore
+let unifiers = unify(lhs.as_ref(), subject.as_ref());
+for unifier in unifiers.iter() {
+  let instance = unifier.apply(lhs.as_ref());
+}
+if !unifiers.is_complete() {
+  // Narrowing may have missed a step.
+}
+```
+
+## Completeness
+
+AC unification problems can have a great many unifiers. `UnificationLimits` bounds the number of unifiers returned and
+the size of the Diophantine bases whose subsets are enumerated. Whenever a limit is reached, or a symbol with
+unsupported axioms is treated as free, the set of unifiers may be incomplete. The reasons are recorded in
+`Unifiers::incomplete` and logged as warnings, so that narrowing can tell an exhausted search from a truncated one.
+
+A complete set is not necessarily minimal: one unifier may be an instance of another. Sorts are ignored, so a unifier
+can bind a variable to a term outside the variable's sort.
+
+The fresh variables of unifiers are named `#1`, `#2`, and so on, and are owned by the `Unifiers`, which must outlive
+every term that uses them.
+
+*/
+
+use core::{
+  cmp::Ordering,
+  fmt::{Display, Formatter}
+};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+
+use crate::{
+  api::{
+    free_theory::FreeTerm,
+    symbol::{SymbolAttribute, SymbolPtr},
+    term::{BxTerm, Term},
+    variable_theory::VariableTerm
+  },
+  core::{
+    binders::FreshVariables,
+    format::FormatStyle
+  },
+  warning
+};
+
+/// The largest number of candidate solutions examined to compute a Diophantine basis
+const MAX_DIOPHANTINE_CANDIDATES: usize = 1 << 16;
+
+/// Bounds on the search for unifiers. See the module documentation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct UnificationLimits {
+  /// The largest number of unifiers returned
+  pub max_unifiers  : usize,
+  /// The largest Diophantine basis whose subsets are enumerated. The number of subsets is exponential in its size.
+  pub max_basis_size: usize,
+}
+
+impl Default for UnificationLimits {
+  fn default() -> Self {
+    UnificationLimits {
+      max_unifiers  : 1000,
+      max_basis_size: 16,
+    }
+  }
+}
+
+impl UnificationLimits {
+  pub fn with_max_unifiers(mut self, max_unifiers: usize) -> Self {
+    self.max_unifiers = max_unifiers;
+    self
+  }
+
+  pub fn with_max_basis_size(mut self, max_basis_size: usize) -> Self {
+    self.max_basis_size = max_basis_size;
+    self
+  }
+}
+
+/// A reason a set of unifiers may be incomplete
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Incompleteness {
+  /// `max_unifiers` unifiers were found, and the search stopped.
+  TooManyUnifiers,
+  /// The Diophantine equation of an AC problem had a basis larger than `max_basis_size`, or too many candidate
+  /// solutions to compute its basis, and was left unsolved.
+  BasisTooLarge,
+  /// The symbol has axioms that unification doesn't support and was treated as free.
+  UnsupportedAxioms(SymbolPtr),
+}
+
+impl Display for Incompleteness {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Incompleteness::TooManyUnifiers => write!(f, "the maximum number of unifiers was reached"),
+      Incompleteness::BasisTooLarge   => write!(f, "an AC problem was too large to solve"),
+      Incompleteness::UnsupportedAxioms(symbol) => {
+        write!(f, "the axioms of {} are not supported, and it was treated as free", unsafe{ &**symbol })
+      }
+    }
+  }
+}
+
+/// A substitution for the variables of a unification problem. Variables the unifier leaves alone are not listed.
+pub struct Unifier {
+  bindings: Vec<(SymbolPtr, BxTerm)>,
+}
+
+impl Unifier {
+  /// The term `variable` is bound to, if any
+  pub fn get(&self, variable: SymbolPtr) -> Option<&dyn Term> {
+    self.bindings
+        .iter()
+        .find(|(bound, _)| core::ptr::addr_eq(*bound, variable))
+        .map(|(_, value)| value.as_ref())
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (SymbolPtr, &dyn Term)> {
+    self.bindings.iter().map(|(variable, value)| (*variable, value.as_ref()))
+  }
+
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.bindings.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.bindings.is_empty()
+  }
+
+  /// The instance of `term` under the unifier
+  pub fn apply(&self, term: &dyn Term) -> BxTerm {
+    apply(term, &self.bindings)
+  }
+}
+
+impl Display for Unifier {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    let bindings: Vec<String> = self.bindings
+        .iter()
+        .map(|(variable, value)| format!("{} := {}", unsafe{ &**variable }, value.repr(FormatStyle::Default)))
+        .collect();
+    write!(f, "{{{}}}", bindings.join(", "))
+  }
+}
+
+/// A complete set of unifiers, unless `incomplete` says otherwise. See the module documentation.
+pub struct Unifiers {
+  pub unifiers  : Vec<Unifier>,
+  /// The reasons `unifiers` may be missing unifiers, each listed once. Empty if the set is complete.
+  pub incomplete: Vec<Incompleteness>,
+  /// Owns the fresh variables of `unifiers`
+  fresh         : FreshVariables,
+}
+
+impl Unifiers {
+  #[inline(always)]
+  pub fn is_complete(&self) -> bool {
+    self.incomplete.is_empty()
+  }
+
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.unifiers.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.unifiers.is_empty()
+  }
+
+  pub fn iter(&self) -> core::slice::Iter<'_, Unifier> {
+    self.unifiers.iter()
+  }
+
+  /// The number of fresh variables the unifiers were computed with
+  #[inline(always)]
+  pub fn fresh_variable_count(&self) -> usize {
+    self.fresh.len()
+  }
+}
+
+/// A complete set of unifiers of `a` and `b` within the default `UnificationLimits`.
+pub fn unify(a: &dyn Term, b: &dyn Term) -> Unifiers {
+  unify_with_limits(a, b, UnificationLimits::default())
+}
+
+/// A complete set of unifiers of `a` and `b` within `limits`, unless the result says otherwise.
+pub fn unify_with_limits(a: &dyn Term, b: &dyn Term, limits: UnificationLimits) -> Unifiers {
+  let mut variables = Vec::new();
+  collect_variables(a, &mut variables);
+  collect_variables(b, &mut variables);
+
+  let mut search = Search {
+    limits,
+    variables,
+    fresh     : FreshVariables::new(),
+    unifiers  : Vec::new(),
+    incomplete: Vec::new(),
+  };
+  search.solve(vec![(a.deep_copy(), b.deep_copy())], Vec::new());
+
+  for reason in search.incomplete.iter() {
+    warning!(
+      1,
+      "the unifiers of {} and {} may be incomplete: {}",
+      a.repr(FormatStyle::Default),
+      b.repr(FormatStyle::Default),
+      reason
+    );
+  }
+
+  Unifiers {
+    unifiers  : search.unifiers,
+    incomplete: search.incomplete,
+    fresh     : search.fresh,
+  }
+}
+
+type Equation = (BxTerm, BxTerm);
+/// Bindings are kept idempotent: no variable they bind occurs in their values.
+type Bindings = Vec<(SymbolPtr, BxTerm)>;
+
+struct Search {
+  limits    : UnificationLimits,
+  /// The variables of the problem, to which unifiers are restricted
+  variables : Vec<SymbolPtr>,
+  fresh     : FreshVariables,
+  unifiers  : Vec<Unifier>,
+  incomplete: Vec<Incompleteness>,
+}
+
+impl Search {
+  fn give_up(&mut self, reason: Incompleteness) {
+    if !self.incomplete.contains(&reason) {
+      self.incomplete.push(reason);
+    }
+  }
+
+  /// Solves `equations` under `bindings`, recording a unifier for each solution. Branches of the search are solved by
+  /// recursive calls on copies of the equations and bindings.
+  fn solve(&mut self, mut equations: Vec<Equation>, mut bindings: Bindings) {
+    if self.unifiers.len() >= self.limits.max_unifiers {
+      self.give_up(Incompleteness::TooManyUnifiers);
+      return;
+    }
+
+    while let Some((s, t)) = equations.pop() {
+      let (s, t) = (apply(s.as_ref(), &bindings), apply(t.as_ref(), &bindings));
+      if s.compare(t.as_ref()) == Ordering::Equal {
+        continue;
+      }
+
+      if s.is_variable() || t.is_variable() {
+        let (variable, value) = if s.is_variable() { (s, t) } else { (t, s) };
+        if occurs(variable.symbol(), value.as_ref()) {
+          return;
+        }
+        bind(&mut bindings, variable.symbol(), value);
+        continue;
+      }
+
+      let symbol = s.symbol();
+      if !core::ptr::addr_eq(symbol, t.symbol()) {
+        return;
+      }
+
+      let axioms = s.symbol_ref().attributes & SymbolAttribute::Axioms;
+      if axioms == SymbolAttribute::Commutative {
+        return self.solve_commutative(s.as_ref(), t.as_ref(), equations, bindings);
+      }
+      if axioms == SymbolAttribute::Associative | SymbolAttribute::Commutative {
+        return self.solve_associative_commutative(s.as_ref(), t.as_ref(), equations, bindings);
+      }
+      if !axioms.is_empty() {
+        self.give_up(Incompleteness::UnsupportedAxioms(symbol));
+      }
+
+      equations.extend(s.iter_args().zip(t.iter_args()).map(|(x, y)| (x.deep_copy(), y.deep_copy())));
+    }
+
+    let bindings = self.variables
+        .iter()
+        .filter_map(
+          |&variable| {
+            bindings.iter()
+                    .find(|(bound, _)| core::ptr::addr_eq(*bound, variable))
+                    .map(|(_, value)| (variable, value.deep_copy()))
+          }
+        )
+        .collect();
+    self.unifiers.push(Unifier { bindings });
+  }
+
+  /// Solves `s = t` for a commutative top symbol by trying both orders of the arguments of `t`.
+  fn solve_commutative(&mut self, s: &dyn Term, t: &dyn Term, equations: Vec<Equation>, bindings: Bindings) {
+    let s_args: Vec<&dyn Term> = s.iter_args().collect();
+    let t_args: Vec<&dyn Term> = t.iter_args().collect();
+
+    for (first, second) in [(0, 1), (1, 0)] {
+      let mut branch = copy_equations(&equations);
+      branch.push((s_args[0].deep_copy(), t_args[first].deep_copy()));
+      branch.push((s_args[1].deep_copy(), t_args[second].deep_copy()));
+      self.solve(branch, copy_bindings(&bindings));
+    }
+  }
+
+  /// Solves `s = t` for an associative and commutative top symbol with Stickel's algorithm. See the module
+  /// documentation.
+  fn solve_associative_commutative(
+    &mut self,
+    s        : &dyn Term,
+    t        : &dyn Term,
+    equations: Vec<Equation>,
+    bindings : Bindings
+  ) {
+    let symbol    = s.symbol();
+    let mut left  = Vec::new();
+    let mut right = Vec::new();
+    flatten(s, symbol, &mut left);
+    flatten(t, symbol, &mut right);
+
+    // Arguments on both sides cancel.
+    left.retain(
+      |arg| match right.iter().position(|other| other.compare(*arg) == Ordering::Equal) {
+        Some(index) => {
+          right.swap_remove(index);
+          false
+        }
+        None => true
+      }
+    );
+    match (left.is_empty(), right.is_empty()) {
+      (true, true)          => return self.solve(equations, bindings),
+      (true, _) | (_, true) => return,
+      _                     => {}
+    }
+
+    let (left, left_multiplicities)   = group(left);
+    let (right, right_multiplicities) = group(right);
+    let basis = match diophantine_basis(&left_multiplicities, &right_multiplicities) {
+      Some(basis) if basis.len() <= self.limits.max_basis_size.min(63) => basis,
+      _ => {
+        self.give_up(Incompleteness::BasisTooLarge);
+        return;
+      }
+    };
+
+    let args: Vec<&dyn Term> = left.into_iter().chain(right).collect();
+    for subset in 1..1u64 << basis.len() {
+      let chosen: Vec<&Vec<usize>> = basis.iter()
+                                          .enumerate()
+                                          .filter(|(index, _)| subset >> index & 1 == 1)
+                                          .map(|(_, solution)| solution)
+                                          .collect();
+      let admissible = args.iter().enumerate().all(
+        |(position, arg)| {
+          let share: usize = chosen.iter().map(|solution| solution[position]).sum();
+          share >= 1 && (arg.is_variable() || share == 1)
+        }
+      );
+      if !admissible {
+        continue;
+      }
+      if self.unifiers.len() >= self.limits.max_unifiers {
+        self.give_up(Incompleteness::TooManyUnifiers);
+        return;
+      }
+
+      let fresh: Vec<SymbolPtr> = chosen.iter().map(|_| self.fresh.anonymous()).collect();
+      let mut branch = copy_equations(&equations);
+      for (position, arg) in args.iter().enumerate() {
+        let mut share: Vec<BxTerm> = Vec::new();
+        for (solution, &variable) in chosen.iter().zip(fresh.iter()) {
+          for _ in 0..solution[position] {
+            share.push(Box::new(VariableTerm::new(variable)));
+          }
+        }
+        branch.push((arg.deep_copy(), nest_right(symbol, share)));
+      }
+      self.solve(branch, copy_bindings(&bindings));
+    }
+  }
+}
+
+/// The minimal nonzero solutions of `a·x = b·y` in the natural numbers, each given as `x` followed by `y`, or `None` if
+/// there are too many candidates to examine. Every component of a minimal solution is bounded by the largest
+/// coefficient on the other side.
+fn diophantine_basis(a: &[usize], b: &[usize]) -> Option<Vec<Vec<usize>>> {
+  let x_bound = *b.iter().max()?;
+  let y_bound = *a.iter().max()?;
+  let candidates = (x_bound + 1).checked_pow(a.len() as u32)?
+                                .checked_mul((y_bound + 1).checked_pow(b.len() as u32)?)?;
+  if candidates > MAX_DIOPHANTINE_CANDIDATES {
+    return None;
+  }
+
+  let dot = |coefficients: &[usize], vector: &[usize]| -> usize {
+    coefficients.iter().zip(vector).map(|(coefficient, component)| coefficient * component).sum()
+  };
+  let ys = vectors(y_bound, b.len());
+  let mut solutions = Vec::new();
+  for x in vectors(x_bound, a.len()) {
+    let sum = dot(a, &x);
+    if sum == 0 {
+      continue;
+    }
+    for y in ys.iter().filter(|y| dot(b, y) == sum) {
+      solutions.push(x.iter().chain(y.iter()).copied().collect::<Vec<usize>>());
+    }
+  }
+
+  // A solution is minimal if no other solution is below it componentwise. Such a solution has a smaller sum.
+  solutions.sort_by_key(|solution| solution.iter().sum::<usize>());
+  let mut basis: Vec<Vec<usize>> = Vec::new();
+  for solution in solutions {
+    if !basis.iter().any(|minimal| minimal.iter().zip(&solution).all(|(m, s)| m <= s)) {
+      basis.push(solution);
+    }
+  }
+
+  Some(basis)
+}
+
+/// Every vector of length `len` with components at most `bound`
+fn vectors(bound: usize, len: usize) -> Vec<Vec<usize>> {
+  let mut vectors = vec![Vec::with_capacity(len)];
+  for _ in 0..len {
+    vectors = vectors.into_iter()
+                     .flat_map(|vector| (0..=bound).map(move |component| {
+                       let mut longer = vector.clone();
+                       longer.push(component);
+                       longer
+                     }))
+                     .collect();
+  }
+
+  vectors
+}
+
+/// The distinct terms of `args` with their multiplicities
+fn group(args: Vec<&dyn Term>) -> (Vec<&dyn Term>, Vec<usize>) {
+  let mut distinct: Vec<&dyn Term> = Vec::new();
+  let mut multiplicities           = Vec::new();
+  for arg in args {
+    match distinct.iter().position(|other| other.compare(arg) == Ordering::Equal) {
+      Some(index) => multiplicities[index] += 1,
+      None        => {
+        distinct.push(arg);
+        multiplicities.push(1);
+      }
+    }
+  }
+
+  (distinct, multiplicities)
+}
+
+fn flatten<'t>(term: &'t dyn Term, symbol: SymbolPtr, args: &mut Vec<&'t dyn Term>) {
+  for arg in term.iter_args() {
+    if !arg.is_variable() && core::ptr::addr_eq(arg.symbol(), symbol) {
+      flatten(arg, symbol, args);
+    } else {
+      args.push(arg);
+    }
+  }
+}
+
+/// Builds `f(a₁, f(a₂, … f(aₙ₋₁, aₙ)))` from the nonempty `args`.
+fn nest_right(symbol: SymbolPtr, mut args: Vec<BxTerm>) -> BxTerm {
+  let mut result = args.pop().unwrap();
+  while let Some(arg) = args.pop() {
+    result = FreeTerm::with_args(symbol, vec![arg, result]);
+  }
+
+  result
+}
+
+/// The instance of `term` under `bindings`, which are idempotent, so a single pass suffices.
+fn apply(term: &dyn Term, bindings: &[(SymbolPtr, BxTerm)]) -> BxTerm {
+  if term.is_variable() {
+    return match bindings.iter().find(|(variable, _)| core::ptr::addr_eq(*variable, term.symbol())) {
+      Some((_, value)) => value.deep_copy(),
+      None             => term.deep_copy(),
+    };
+  }
+
+  FreeTerm::with_args(term.symbol(), term.iter_args().map(|arg| apply(arg, bindings)).collect())
+}
+
+/// Binds `variable` to `value`, which must not contain it, keeping `bindings` idempotent.
+fn bind(bindings: &mut Bindings, variable: SymbolPtr, value: BxTerm) {
+  let binding = [(variable, value)];
+  for (_, bound) in bindings.iter_mut() {
+    *bound = apply(bound.as_ref(), &binding);
+  }
+  bindings.extend(binding);
+}
+
+fn occurs(variable: SymbolPtr, term: &dyn Term) -> bool {
+  match term.is_variable() {
+    true  => core::ptr::addr_eq(term.symbol(), variable),
+    false => term.iter_args().any(|arg| occurs(variable, arg)),
+  }
+}
+
+fn collect_variables(term: &dyn Term, variables: &mut Vec<SymbolPtr>) {
+  if term.is_variable() {
+    if !variables.iter().any(|&variable| core::ptr::addr_eq(variable, term.symbol())) {
+      variables.push(term.symbol());
+    }
+    return;
+  }
+
+  for arg in term.iter_args() {
+    collect_variables(arg, variables);
+  }
+}
+
+fn copy_equations(equations: &[Equation]) -> Vec<Equation> {
+  equations.iter().map(|(s, t)| (s.deep_copy(), t.deep_copy())).collect()
+}
+
+fn copy_bindings(bindings: &Bindings) -> Bindings {
+  bindings.iter().map(|(variable, value)| (*variable, value.deep_copy())).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+  use std::cmp::Ordering;
+
+  use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      free_theory::FreeTerm,
+      symbol::{Symbol, SymbolAttribute, SymbolPtr},
+      term::BxTerm,
+      variable_theory::VariableTerm
+    },
+    core::{
+      canonicalize::canonicalize,
+      module::Module,
+      unification::{diophantine_basis, unify, unify_with_limits, Incompleteness, UnificationLimits, Unifiers}
+    }
+  };
+
+  fn variable(symbol: SymbolPtr) -> BxTerm {
+    Box::new(VariableTerm::new(symbol))
+  }
+
+  /// Checks that each unifier makes `a` and `b` equal modulo the axioms.
+  fn assert_unifiers(a: &BxTerm, b: &BxTerm, unifiers: &Unifiers) {
+    let module = Module::default();
+    for unifier in unifiers.iter() {
+      let a_instance = canonicalize(unifier.apply(a.as_ref()).as_ref(), &module);
+      let b_instance = canonicalize(unifier.apply(b.as_ref()).as_ref(), &module);
+      assert_eq!(a_instance.compare(b_instance.as_ref()), Ordering::Equal, "{} is not a unifier", unifier);
+    }
+  }
+
+  #[test]
+  fn diophantine_bases_are_minimal() {
+    // x + y = 2z
+    let mut basis = diophantine_basis(&[1, 1], &[2]).unwrap();
+    basis.sort();
+    assert_eq!(basis, vec![vec![0, 2, 1], vec![1, 1, 1], vec![2, 0, 1]]);
+  }
+
+  #[test]
+  fn unify_modulo_axioms() {
+    let mut f    = Symbol::new(IString::from("f"), Arity::Value(2));
+    let mut g    = Symbol::new(IString::from("g"), Arity::Value(2));
+    g.attributes = SymbolAttribute::Commutative.into();
+    let mut plus = Symbol::new(IString::from("plus"), Arity::Value(2));
+    plus.attributes = SymbolAttribute::Associative | SymbolAttribute::Commutative;
+    let mut cat  = Symbol::new(IString::from("cat"), Arity::Value(2));
+    cat.attributes = SymbolAttribute::Associative.into();
+    let mut a    = Symbol::new(IString::from("a"), Arity::Value(0));
+    let mut b    = Symbol::new(IString::from("b"), Arity::Value(0));
+    let mut x    = Symbol::new_variable(IString::from("X"));
+    let mut y    = Symbol::new_variable(IString::from("Y"));
+    let (f, g, plus, cat, a, b, x, y): (
+      SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr, SymbolPtr
+    ) = (&mut f, &mut g, &mut plus, &mut cat, &mut a, &mut b, &mut x, &mut y);
+    let constant = |symbol| FreeTerm::with_args(symbol, vec![]);
+
+    // f(X, b) = f(a, Y) has the single unifier {X := a, Y := b}.
+    let s        = FreeTerm::with_args(f, vec![variable(x), constant(b)]);
+    let t        = FreeTerm::with_args(f, vec![constant(a), variable(y)]);
+    let unifiers = unify(s.as_ref(), t.as_ref());
+    assert!(unifiers.is_complete());
+    assert_eq!(unifiers.len(), 1);
+    assert_eq!(unifiers.unifiers[0].get(x).unwrap().compare(constant(a).as_ref()), Ordering::Equal);
+    assert_unifiers(&s, &t, &unifiers);
+
+    // X = f(X, a) fails the occurs check.
+    let s = FreeTerm::with_args(f, vec![variable(x), constant(a)]);
+    assert!(unify(variable(x).as_ref(), s.as_ref()).is_empty());
+
+    // g(X, Y) = g(a, b) has a unifier for each order of the arguments.
+    let s        = FreeTerm::with_args(g, vec![variable(x), variable(y)]);
+    let t        = FreeTerm::with_args(g, vec![constant(a), constant(b)]);
+    let unifiers = unify(s.as_ref(), t.as_ref());
+    assert_eq!(unifiers.len(), 2);
+    assert_unifiers(&s, &t, &unifiers);
+
+    // plus(X, a) = plus(Y, b) has {X := b, Y := a} and {X := plus(b, #), Y := plus(a, #)}.
+    let s        = FreeTerm::with_args(plus, vec![variable(x), constant(a)]);
+    let t        = FreeTerm::with_args(plus, vec![variable(y), constant(b)]);
+    let unifiers = unify(s.as_ref(), t.as_ref());
+    assert!(unifiers.is_complete());
+    assert_eq!(unifiers.len(), 2);
+    assert!(unifiers.fresh_variable_count() > 0);
+    assert_unifiers(&s, &t, &unifiers);
+
+    // plus(X, Y) = plus(a, plus(a, b)) needs an argument to take two arguments of the other side.
+    let t = FreeTerm::with_args(plus, vec![constant(a), FreeTerm::with_args(plus, vec![constant(a), constant(b)])]);
+    let unifiers = unify(FreeTerm::with_args(plus, vec![variable(x), variable(y)]).as_ref(), t.as_ref());
+    assert_eq!(unifiers.len(), 4);
+    assert_unifiers(&FreeTerm::with_args(plus, vec![variable(x), variable(y)]), &t, &unifiers);
+
+    // The limits are reported as incompleteness.
+    let limited = unify_with_limits(
+      FreeTerm::with_args(plus, vec![variable(x), variable(y)]).as_ref(),
+      t.as_ref(),
+      UnificationLimits::default().with_max_unifiers(1)
+    );
+    assert_eq!(limited.len(), 1);
+    assert_eq!(limited.incomplete, vec![Incompleteness::TooManyUnifiers]);
+
+    let s = FreeTerm::with_args(cat, vec![variable(x), constant(a)]);
+    assert_eq!(unify(s.as_ref(), s.as_ref()).incomplete, vec![]);
+    let t = FreeTerm::with_args(cat, vec![constant(b), variable(y)]);
+    assert_eq!(unify(s.as_ref(), t.as_ref()).incomplete, vec![Incompleteness::UnsupportedAxioms(cat)]);
+  }
+}
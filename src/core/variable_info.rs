@@ -5,9 +5,10 @@ Information about a variable that gets passed down through the compilation funct
 */
 
 
-use std::ops::Index;
+use core::ops::Index;
+use alloc::vec::Vec;
 
-use crate::{abstractions::NatSet, debug};
+use crate::{abstractions::{IString, NatSet}, debug};
 use crate::abstractions::Graph;
 use crate::api::term::{MaybeTerm, Term};
 
@@ -25,7 +26,7 @@ struct ConstructionIndex {
   new_index:         i32,
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct VariableInfo {
   variables:                    Vec<MaybeTerm>,
   protected_variable_count:     i32,
@@ -57,12 +58,20 @@ impl VariableInfo {
   pub(crate) fn index_to_variable(&self, index: i8) -> MaybeTerm {
     if index >= 0 {
       if let Some(d) = self.variables.get(index as usize) {
-        return d.clone();
+        return *d;
       }
     }
     None
   }
 
+  /// The name of the real variable with index `index`.
+  pub(crate) fn variable_name(&self, index: usize) -> IString {
+    match self.variables.get(index) {
+      Some(Some(variable)) => variable.symbol_ref().name.clone(),
+      _                    => panic!("no variable has index {}", index),
+    }
+  }
+
   pub(crate) fn variable_to_index(&mut self, variable: &'static dyn Term) -> i32 {
     // assert!(variable != &VariableTerm::default(), "null term");
     assert!(
@@ -97,7 +106,7 @@ impl VariableInfo {
     if original >= MAX_PROTECTED_VARIABLE_COUNT {
       self.construction_indices[(original - MAX_PROTECTED_VARIABLE_COUNT) as usize].new_index
     } else {
-      original as i32
+      original
     }
   }
 
@@ -110,7 +119,7 @@ impl VariableInfo {
       ..ConstructionIndex::default()
     });
 
-    MAX_PROTECTED_VARIABLE_COUNT as i32 + construction_index_count as i32
+    MAX_PROTECTED_VARIABLE_COUNT + construction_index_count as i32
   }
 
   #[inline(always)]
@@ -125,12 +134,12 @@ impl VariableInfo {
   }
 
   pub fn use_index(&mut self, index: i32) {
-    // ToDo: What if `index < 0`? Added condition that `index>=0` to avoid negative index.
-    if index >= 0 && index >= MAX_PROTECTED_VARIABLE_COUNT {
+    // A negative index is below `MAX_PROTECTED_VARIABLE_COUNT` and so is ignored.
+    if index >= MAX_PROTECTED_VARIABLE_COUNT {
       let index = (index - MAX_PROTECTED_VARIABLE_COUNT) as usize;
 
       self.construction_indices[index].last_use_time = self.construction_indices.len() as u32;
-      self.construction_indices[index].last_use_fragment = self.fragment_number as i16;
+      self.construction_indices[index].last_use_fragment = self.fragment_number;
     }
   }
 
@@ -175,7 +184,7 @@ impl VariableInfo {
 
     // We now build a graph of conflicts between remaining construction indices.
     #[cfg(debug_assertions)]
-    if !(construction_indices_count < 100) {
+    if construction_indices_count >= 100 {
       debug!(3, "nrConstructionIndices = {}", construction_indices_count );
     }
     let mut conflicts: Graph = Graph::new(construction_indices_count);
@@ -197,16 +206,17 @@ impl VariableInfo {
           }
         }
         next_conflict_candidates.push(i);
-        std::mem::swap(&mut conflict_candidates, &mut next_conflict_candidates);
+        core::mem::swap(&mut conflict_candidates, &mut next_conflict_candidates);
       }
     }
 
     // We now use graph coloring to remap the remaining construction indices.
-    let mut coloring = vec![0; construction_indices_count];
+    let mut coloring = Vec::with_capacity(construction_indices_count);
     let color_count = conflicts.color(&mut coloring);
-    for i in 0..construction_indices_count {
-      if self.construction_indices[i].assigned_fragment == self.construction_indices[i].last_use_fragment {
-        self.construction_indices[i].new_index = self.protected_variable_count + coloring[i];
+    let protected_variable_count = self.protected_variable_count;
+    for (idx, color) in self.construction_indices.iter_mut().zip(coloring.iter()) {
+      if idx.assigned_fragment == idx.last_use_fragment {
+        idx.new_index = protected_variable_count + color;
       }
     }
 
@@ -1,16 +1,93 @@
-#![feature(ptr_as_ref_unchecked)]
-#![feature(ptr_metadata)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(dead_code)]
 
+extern crate alloc;
+
+#[cfg(all(test, not(feature = "std")))]
+compile_error!("the tests need the `std` feature");
+
 pub mod api;
 pub mod abstractions;
+#[doc(hidden)]
+pub mod bench_utils;
+#[doc(hidden)]
+pub mod macro_support;
+pub mod prelude;
+pub mod testing;
 mod core;
 
+// Lets the code the derive macros generate, which names items by `::mod2lib` paths, compile within the crate.
+extern crate self as mod2lib;
+
 // We re-export abstractions that are meant to be used publicly.
 pub use abstractions::{
   log,
   IString
 };
+pub use core::parser::ParseError;
+pub use mod2lib_derive::{module, term};
+
+use alloc::{format, string::String};
+
+use crate::{
+  api::term::{BxTerm, Term},
+  core::{
+    module::Module,
+    parser::{parse_module, parse_term},
+    proof_trace::dag_to_string,
+    rewriting_context::RewritingContext
+  }
+};
+
+/**
+Parses a module and a term, reduces the term to normal form with the module's equations, and returns the normal form in
+prefix notation. See `core::parser` for the module language.
+
+```
+let peano = "
+  mod PEANO is
+    sort Nat .
+    ops 0 : -> Nat .
+    op s : Nat -> Nat .
+    op plus : Nat Nat -> Nat .
+    vars X Y : Nat .
+    eq plus(0, Y) = Y .
+    eq plus(s(X), Y) = s(plus(X, Y)) .
+  endm
+";
+assert_eq!(mod2lib::reduce(peano, "plus(s(0), s(0))").unwrap(), "s(s(0))");
+```
+
+Reduction does not terminate if the equations don't.
+*/
+pub fn reduce(module_source: &str, term: &str) -> Result<String, ParseError> {
+  let module = parse_module(module_source)?;
+  let term   = parse_ground_term(&module, term)?;
+
+  let subject     = term.term_to_dag(false);
+  let mut context = RewritingContext::new(&module);
+  let normal_form = context.reduce(subject);
+
+  Ok(dag_to_string(normal_form))
+}
+
+/// Parses a term to reduce over the symbols of `module` from `source`. The term must not have variables.
+pub(crate) fn parse_ground_term(module: &Module, source: &str) -> Result<BxTerm, ParseError> {
+  let term = parse_term(module, source)?;
+  if let Some(variable) = find_variable(term.as_ref()) {
+    return Err(ParseError { line: 1, message: format!("the term to reduce has the variable {}", variable) });
+  }
+
+  Ok(term)
+}
+
+/// The name of the first variable in `term`, if any.
+fn find_variable(term: &dyn Term) -> Option<IString> {
+  match term.is_variable() {
+    true  => Some(term.symbol_ref().name.clone()),
+    false => term.iter_args().find_map(find_variable)
+  }
+}
 
 // Configuration
 
@@ -21,6 +98,7 @@ const UNDEFINED: i32 = -1;
 const NONE     : i32 = -1;
 const ROOT_OK  : i32 = -2;
 
+#[doc(hidden)]
 pub fn add(left: u64, right: u64) -> u64 {
   left + right
 }
@@ -0,0 +1,102 @@
+/*!
+
+What the code the `module!` and `term!` macros generate calls at run time. The macros have checked the syntax by the
+time these are called, so the only errors left are those that depend on the module, which panic like a failed
+`unwrap` would. The errors the macros catch don't compile:
+
+```compile_fail
+let module = mod2lib::module!("mod M is sort S . op a : -> T . endm");
+```
+
+*/
+
+// The code the macros generate builds boxes, strings, and vectors with these, so that it also compiles in crates
+// without `std`.
+pub use alloc::{boxed::Box, string::String, vec};
+/// The syntax trees the macros parse their input into
+pub use mod2lib_syntax as syntax;
+
+use crate::{
+  abstractions::HashMap,
+  api::term::BxTerm,
+  core::{
+    module::Module,
+    parser::{build_module, build_term},
+    thread_bound::check_owner
+  }
+};
+
+/// Builds the module a `module!` parsed and checked, as `parse_module` would. Panics if the engine is bound to another
+/// thread (see `thread_bound`).
+pub fn module(syntax: syntax::ModuleSyntax) -> Module {
+  check_owner();
+  match build_module(&syntax, &HashMap::default()) {
+    Ok(mut module) => {
+      module.compile_statements().expect("statements are checked as they are parsed");
+      module
+    }
+    Err(error) => panic!("module! failed to build the module: {}", error),
+  }
+}
+
+/// Builds the term a `term!` parsed over `module`, checking it against the module's signature as `parse_term` does.
+pub fn term(module: &Module, syntax: syntax::TermSyntax) -> BxTerm {
+  match build_term(module, &syntax) {
+    Ok(term)   => term,
+    Err(error) => panic!("term!: {}", error.message),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    core::{parser::parse_term, proof_trace::dag_to_string, rewriting_context::RewritingContext},
+    module,
+    term
+  };
+
+  #[test]
+  fn macros_build_what_the_parser_builds() {
+    let module = module!("
+      mod PEANO is
+        sort Nat .
+        ops 0 : -> Nat [ctor] .
+        op s : Nat -> Nat [ctor] .
+        op plus : Nat Nat -> Nat .
+        vars X Y : Nat .
+        eq plus(0, Y) = Y .
+        eq plus(s(X), Y) = s(plus(X, Y)) .
+      endm
+    ");
+
+    let pattern = term!(module, "plus(s(X), Y:Nat)");
+    assert_eq!(pattern.to_string(), parse_term(&module, "plus(s(X), Y:Nat)").unwrap().to_string());
+    assert!(pattern.iter_args().nth(1).unwrap().is_variable());
+
+    let subject     = term!(&module, "plus(s(0), s(0))").term_to_dag(false);
+    let mut context = RewritingContext::new(&module);
+    assert_eq!(dag_to_string(context.reduce(subject)), "s(s(0))");
+  }
+
+  #[test]
+  fn modules_declare_descent_functions() {
+    let mut module = module!("
+      mod REFLECTIVE is
+        including META-LEVEL .
+        op metaReduce : Qid Term -> ResultPair [descent] .
+      endm
+    ");
+    module.add_object_module(module!("mod UNIT is sort S . op a : -> S . op b : -> S . eq a = b . endm")).unwrap();
+
+    let subject     = term!(module, "metaReduce('UNIT, app('a, noArgs))").term_to_dag(false);
+    let mut context = RewritingContext::new(&module);
+    assert_eq!(dag_to_string(context.reduce(subject)), "pair(app('b, noArgs), 'S)");
+  }
+
+  #[test]
+  #[should_panic(expected = "term!: undeclared operator or variable times")]
+  fn undeclared_operators_panic() {
+    let module = module!("mod EMPTY is sort S . op a : -> S . endm");
+    term!(module, "times(a, a)");
+  }
+}
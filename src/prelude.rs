@@ -0,0 +1,127 @@
+/*!
+
+The stable surface of the library: the handles, builders, and engine types a program using the library needs, the
+tools built on them, like tracing, printing, snapshots, and the metalevel, and the logging macros. Import it whole:
+
+```
+use mod2lib::prelude::*;
+
+let module = parse_module("
+  mod PEANO is
+    sort Nat .
+    ops 0 : -> Nat .
+    op s : Nat -> Nat .
+    op plus : Nat Nat -> Nat .
+    vars X Y : Nat .
+    eq plus(0, Y) = Y .
+    eq plus(s(X), Y) = s(plus(X, Y)) .
+  endm
+").unwrap();
+let subject     = parse_term(&module, "plus(s(0), 0)").unwrap().term_to_dag(false);
+let mut context = RewritingContext::new(&module);
+let normal_form = context.reduce(subject);
+assert_eq!(unsafe{ &*normal_form }.symbol_ref().name.as_ref(), "s");
+```
+
+The prelude is versioned. Each version is a module, `v1` being the first, and `prelude` itself re-exports the latest.
+Items may be added to a version, but an item is never removed from one or changed incompatibly: that makes a new
+version, and the old one stays until the next major release. A program that must not break on upgrade imports a
+version by name, as in `use mod2lib::prelude::v1::*;`.
+
+Everything reachable only outside the prelude is unstable and may change in any release. That includes the theories'
+node and automaton types, the allocator, and the benchmark utilities. The internals among them that hand out raw
+pointers are hidden from the documentation.
+
+*/
+
+/// The first version of the prelude. See the `prelude` documentation.
+pub mod v1 {
+  pub use crate::{
+    abstractions::IString,
+    api::{
+      Arity,
+      Sort,
+      SortPtr,
+      assoc_view::{BinaryArg, BinaryView, FlattenedArgs},
+      atom::{Atom, AtomConversionError, DataAtom},
+      dag_node::{DagNode, DagNodePtr},
+      dag_visitor::{DagVisitor, VisitControl},
+      data_theory::DataDagNode,
+      float::FloatAtom,
+      free_theory::{FreeDagNode, FreeTerm},
+      integer::{integer, BigIntAtom, IntegerAtom},
+      string::StringAtom,
+      symbol::{Symbol, SymbolPtr},
+      term::{BxTerm, Term},
+      value::Value,
+      variable_theory::{ConstrainedVariable, VariableTerm}
+    },
+    core::{
+      module::Module,
+      parser::{parse_module, parse_term, ParseError},
+      pre_equation::{condition::Condition, PreEquation, PreEquationKind, StatementError},
+      rewriting_context::RewritingContext,
+      config::{AllocatorConfig, ConfigError, EngineConfig},
+      proof_trace::{dag_to_string, ProofTrace, RewriteStep},
+      format::{FormatStyle, Formattable, PrintSettings},
+      pretty::PrettyPrinter,
+      latex::{dag_to_latex, module_to_latex, statement_to_latex, term_to_latex},
+      canonicalize::canonicalize,
+      ingest::{ingest, ingest_all, IngestError, NodeRecord},
+      binders::FreshVariables,
+      generalization::Bindings,
+      diff::{diff, diff_terms, Difference},
+      metalevel::{MetaError, MetaLevel},
+      arc_term::{ArcTerm, ArcTermError, ArcTermTable, HashConsStatistics},
+      solution_cursor::{RuleSolution, SolutionCursor},
+      observer::{
+        add_observer,
+        remove_observer,
+        EngineLimit,
+        EngineObserver,
+        GcSummary,
+        ObserverId,
+        ReductionSummary
+      },
+      incremental_parser::{IncrementalParser, Reparse},
+      coverage::StatementKind,
+      source_index::{
+        Definition,
+        NameKind,
+        Position,
+        Reference,
+        SemanticToken,
+        SemanticTokenKind,
+        SourceIndex,
+        Span,
+        StatementSpan
+      },
+      term_match::{BindError, FromBinding, TermMatch},
+      retract::{is_retract, retract, retract_name, retracts_of},
+      RootContainer
+    },
+    reduce,
+    module,
+    term,
+    critical,
+    error,
+    warning,
+    info,
+    debug,
+    trace
+  };
+  #[cfg(feature = "std")]
+  pub use crate::core::{
+    module_resolver::{IncludePath, ModuleResolver, ModuleSource},
+    session::{LoadError, Session},
+    snapshot::{Snapshot, SnapshotError}
+  };
+  #[cfg(feature = "smt")]
+  pub use crate::core::smt::{SmtError, SmtOperator, SmtResult, SmtSignature, SmtSolver, SmtSort, SmtTerm};
+  #[cfg(feature = "expr")]
+  pub use crate::core::expr::{Expr, ExprAttribute, ExprEngine, ExprError, ExprTest};
+  #[cfg(feature = "metrics")]
+  pub use crate::core::metrics::MetricsObserver;
+}
+
+pub use v1::*;
@@ -0,0 +1,717 @@
+/*!
+
+Random generation of signatures, terms, and rule sets for property-based testing. Downstream users can use these to
+fuzz their own theory implementations against the free theory.
+
+Everything is generated from a `Choices` source, which is either a seeded random number generator or a fixed
+sequence of choices. Generators always treat choice `0` as the simplest option (a constant rather than a compound
+term, omitting a rule rather than adding it), so shrinking a choice sequence toward zeros shrinks the generated
+value. The `proptest` strategies in `strategies` (behind the `proptest` feature) are built this way.
+
+## Well-Sorted Terms
+
+A `SortedSignature` declares sorts and operators with domain and range sorts. Symbols themselves do not carry sort
+information, so the signature is the only record of it; `SortedSignature::sort_of` recovers the range sort of a
+symbol. Every sort of a random signature has a constant, so every sort is inhabited.
+
+## Rule Sets
+
+A random `RuleSet` is confluent and terminating by construction. Operators are either constructors or defined
+operators. Every rule has the form
+
+```text
+f(X1, …, c(Y1, …, Ym), …, Xn) => t
+```
+
+where `f` is a defined operator, `c` is a constructor at a fixed argument position of `f`, and the variables are
+distinct. No two rules for `f` share a constructor, so the rules are left-linear and non-overlapping, hence confluent.
+The right-hand side `t` is built from constructors and the left-hand side's variables only, so every rewrite removes a
+defined operator and rewriting terminates.
+
+## Module Terms
+
+Terms can also be generated over the sorts and operators of a `Module`, subsorts included. See `module_terms`.
+
+*/
+
+pub mod module_terms;
+#[cfg(any(test, feature = "proptest"))]
+pub mod strategies;
+
+pub use crate::core::rewriting_context::RewriteLimitReached;
+
+use core::fmt::{Debug, Display, Formatter};
+use alloc::{boxed::Box, format, string::{String, ToString}, vec, vec::Vec};
+
+use rand::{
+  rngs::StdRng,
+  Rng,
+  SeedableRng
+};
+
+use crate::{
+  abstractions::IString,
+  api::{
+    Arity,
+    dag_node::DagNodePtr,
+    free_theory::{FreeDagNode, FreeTerm},
+    symbol::{Symbol, SymbolPtr},
+    term::{BxTerm, Term},
+    variable_theory::VariableTerm
+  },
+  core::{
+    module::Module,
+    parser::{parse_module, ParseError},
+    pre_equation::{PreEquation, PreEquationKind},
+    rewriting_context::RewritingContext,
+    RootContainer
+  },
+  parse_ground_term
+};
+
+/// Recursively gives `parent` children chosen at random from `symbols`, where `symbols[i]` has arity `i`, until the
+/// tree has height `max_height` below `parent`. Each non-leaf child has between `min_width` and `max_width` arguments.
+///
+/// Nodes created by this function are not rooted, so the GC must not run while the tree is being built. Root the
+/// parent before running the GC.
+pub(crate) fn build_random_tree<R: Rng + ?Sized>(
+  rng       : &mut R,
+  symbols   : &[SymbolPtr],
+  parent    : DagNodePtr,
+  max_height: usize,
+  max_width : usize,
+  min_width : usize,
+) {
+  if max_height == 0 {
+    return; // Reached the maximum depth
+  }
+
+  // idiot-proof
+  let max_width = core::cmp::min(max_width, symbols.len() - 1);
+  let min_width = core::cmp::min(max_width, min_width);
+
+  // Get the parent node's arity from its symbol
+  let parent_mut   = unsafe{ &mut *parent };
+  let parent_arity = if let Arity::Value(v) = parent_mut.arity() { v as usize } else { 0 };
+
+  for _ in 0..parent_arity {
+    // Leaf nodes must have arity 0
+    let child_arity = if max_height == 1 { 0 } else { rng.random_range(min_width..=max_width) };
+    let child_node  = FreeDagNode::new(symbols[child_arity]);
+
+    parent_mut.insert_child_unchecked(child_node);
+    build_random_tree(rng, symbols, child_node, max_height - 1, max_width, min_width);
+  }
+}
+
+// region Choices
+
+enum ChoiceSource {
+  Random(Box<StdRng>),
+  Sequence {
+    choices: Vec<u32>,
+    next   : usize,
+  },
+}
+
+/// The source of every decision a generator makes.
+pub struct Choices {
+  source: ChoiceSource,
+}
+
+impl Choices {
+  /// Choices made by a random number generator seeded with `seed`.
+  pub fn from_seed(seed: u64) -> Self {
+    Choices {
+      source: ChoiceSource::Random(Box::new(StdRng::seed_from_u64(seed)))
+    }
+  }
+
+  /// Choices taken in order from `choices`. Once the sequence is exhausted, every choice is `0`.
+  pub fn from_sequence(choices: Vec<u32>) -> Self {
+    Choices {
+      source: ChoiceSource::Sequence { choices, next: 0 }
+    }
+  }
+
+  /// Choices read from raw bytes, four bytes per choice, as provided by a fuzzer.
+  pub fn from_bytes(data: &[u8]) -> Self {
+    let choices = data
+        .chunks(4)
+        .map(
+          |chunk| {
+            let mut bytes = [0u8; 4];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(bytes)
+          }
+        )
+        .collect();
+
+    Choices::from_sequence(choices)
+  }
+
+  /// Chooses a number in `0..n`, which must be nonempty.
+  pub fn choose(&mut self, n: usize) -> usize {
+    assert!(n > 0, "cannot choose from nothing");
+
+    match &mut self.source {
+      ChoiceSource::Random(rng) => rng.random_range(0..n),
+
+      ChoiceSource::Sequence { choices, next } => {
+        let choice = choices.get(*next).copied().unwrap_or(0);
+        *next += 1;
+        choice as usize % n
+      }
+    }
+  }
+}
+
+// endregion Choices
+
+// region Signatures
+
+/// Identifies a sort by its position in its `SortedSignature`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SortId(pub usize);
+
+/// An operator of a `SortedSignature`.
+pub struct OperatorDeclaration {
+  pub symbol        : Box<Symbol>,
+  pub domain        : Vec<SortId>,
+  pub range         : SortId,
+  /// Constructors never occur at the top of a rule's left-hand side.
+  pub is_constructor: bool,
+}
+
+impl OperatorDeclaration {
+  #[inline(always)]
+  pub fn symbol_ptr(&self) -> SymbolPtr {
+    self.symbol.as_ref() as *const Symbol as SymbolPtr
+  }
+}
+
+/// Sorts, operators, and variables for generating well-sorted terms. The signature owns its symbols, so it must
+/// outlive every term, rule set, and module generated from it.
+#[derive(Default)]
+#[allow(clippy::vec_box)]
+pub struct SortedSignature {
+  sorts    : Vec<IString>,
+  operators: Vec<OperatorDeclaration>,
+  /// The variables of each sort, indexed by sort
+  variables: Vec<Vec<Box<Symbol>>>,
+}
+
+impl SortedSignature {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// A random signature with `sort_count` sorts, a constant constructor for each sort, and `operator_count` further
+  /// operators of arity at most `max_arity`. Each sort gets enough variables for any rule `RuleSet::random` generates.
+  pub fn random(choices: &mut Choices, sort_count: usize, operator_count: usize, max_arity: usize) -> Self {
+    let mut signature = SortedSignature::new();
+    let sort_count    = sort_count.max(1);
+
+    for i in 0..sort_count {
+      signature.add_sort(IString::from(format!("S{}", i)));
+    }
+
+    for i in 0..sort_count {
+      signature.add_operator(IString::from(format!("c{}", i)), &[], SortId(i), true);
+    }
+
+    for i in sort_count..sort_count + operator_count {
+      let is_constructor = choices.choose(2) == 0;
+      let arity          = choices.choose(max_arity + 1);
+      let domain: Vec<SortId> = (0..arity).map(|_| SortId(choices.choose(sort_count))).collect();
+      let range          = SortId(choices.choose(sort_count));
+      let name           = if is_constructor { format!("c{}", i) } else { format!("d{}", i) };
+
+      signature.add_operator(IString::from(name), &domain, range, is_constructor);
+    }
+
+    // A left-hand side has at most `2*max_arity - 1` variables.
+    for sort in 0..sort_count {
+      for i in 0..2 * max_arity.max(1) {
+        signature.add_variable(IString::from(format!("{}_{}", signature.sorts[sort], i)), SortId(sort));
+      }
+    }
+
+    signature
+  }
+
+  pub fn add_sort(&mut self, name: IString) -> SortId {
+    self.sorts.push(name);
+    self.variables.push(Vec::new());
+
+    SortId(self.sorts.len() - 1)
+  }
+
+  pub fn add_operator(&mut self, name: IString, domain: &[SortId], range: SortId, is_constructor: bool) -> SymbolPtr {
+    let declaration = OperatorDeclaration {
+      symbol: Box::new(Symbol::new(name, Arity::new(domain.len()).unwrap())),
+      domain: domain.to_vec(),
+      range,
+      is_constructor
+    };
+    let symbol = declaration.symbol_ptr();
+    self.operators.push(declaration);
+
+    symbol
+  }
+
+  pub fn add_variable(&mut self, name: IString, sort: SortId) -> SymbolPtr {
+    let variable = Box::new(Symbol::new_variable(name));
+    let symbol   = variable.as_ref() as *const Symbol as SymbolPtr;
+    self.variables[sort.0].push(variable);
+
+    symbol
+  }
+
+  #[inline(always)]
+  pub fn sort_count(&self) -> usize {
+    self.sorts.len()
+  }
+
+  #[inline(always)]
+  pub fn sort_name(&self, sort: SortId) -> &IString {
+    &self.sorts[sort.0]
+  }
+
+  #[inline(always)]
+  pub fn operators(&self) -> &[OperatorDeclaration] {
+    &self.operators
+  }
+
+  /// The variables of sort `sort`.
+  pub fn variables(&self, sort: SortId) -> Vec<SymbolPtr> {
+    self.variables[sort.0]
+        .iter()
+        .map(|variable| variable.as_ref() as *const Symbol as SymbolPtr)
+        .collect()
+  }
+
+  /// The sort of `symbol`: the range of an operator or the sort of a variable. Returns `None` for symbols that do not
+  /// belong to this signature.
+  pub fn sort_of(&self, symbol: SymbolPtr) -> Option<SortId> {
+    if let Some(operator) = self.operators.iter().find(|operator| core::ptr::eq(operator.symbol_ptr(), symbol)) {
+      return Some(operator.range);
+    }
+
+    self.variables
+        .iter()
+        .position(
+          |variables| variables.iter().any(|variable| core::ptr::eq(variable.as_ref(), symbol))
+        )
+        .map(SortId)
+  }
+
+  /// A random ground term of sort `sort` and height at most `max_height` over all operators.
+  pub fn random_term(&self, choices: &mut Choices, sort: SortId, max_height: usize) -> SortedTerm {
+    SortedTerm {
+      term: self.term_aux(choices, sort, max_height, true, &mut Vec::new())
+    }
+  }
+
+  /// A random term of sort `sort` built from the variables in `available` and the constructors, or all operators if
+  /// `allow_defined` is set. Constants are ordered before variables and compound terms, so choice `0` is a constant
+  /// whenever the sort has one.
+  fn term_aux(
+    &self,
+    choices       : &mut Choices,
+    sort          : SortId,
+    height        : usize,
+    allow_defined : bool,
+    available     : &mut Vec<(SortId, SymbolPtr)>,
+  ) -> BxTerm {
+    let leaves = self.operators
+        .iter()
+        .filter(|operator| operator.range == sort && operator.domain.is_empty())
+        .filter(|operator| allow_defined || operator.is_constructor)
+        .map(|operator| operator.symbol_ptr());
+    let variables = available.iter().filter(|(variable_sort, _)| *variable_sort == sort).map(|(_, symbol)| *symbol);
+    let leaves: Vec<SymbolPtr> = leaves.chain(variables).collect();
+
+    let compound: Vec<&OperatorDeclaration> = match height {
+      0 => Vec::new(),
+      _ => {
+        self.operators
+            .iter()
+            .filter(|operator| operator.range == sort && !operator.domain.is_empty())
+            .filter(|operator| allow_defined || operator.is_constructor)
+            .collect()
+      }
+    };
+
+    assert!(
+      !leaves.is_empty() || !compound.is_empty(),
+      "sort {} has no terms of the requested height",
+      self.sorts[sort.0]
+    );
+
+    let choice = choices.choose(leaves.len() + compound.len());
+    if choice < leaves.len() {
+      let symbol = leaves[choice];
+      return match unsafe{ &*symbol }.is_variable() {
+        true  => Box::new(VariableTerm::new(symbol)),
+        false => Box::new(FreeTerm::new(symbol))
+      };
+    }
+
+    let operator = compound[choice - leaves.len()];
+    let mut term = FreeTerm::new(operator.symbol_ptr());
+    term.args    = operator.domain
+        .iter()
+        .map(|&arg_sort| self.term_aux(choices, arg_sort, height - 1, allow_defined, available))
+        .collect();
+
+    Box::new(term)
+  }
+}
+
+impl Debug for SortedSignature {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "sorts {} .", self.sorts.iter().map(|sort| sort.to_string()).collect::<Vec<_>>().join(" "))?;
+    for operator in self.operators.iter() {
+      let domain = operator.domain.iter().map(|&sort| format!("{} ", self.sorts[sort.0])).collect::<String>();
+      let kind   = if operator.is_constructor { " [ctor]" } else { "" };
+      write!(f, "\nop {} : {}-> {}{} .", operator.symbol.name, domain, self.sorts[operator.range.0], kind)?;
+    }
+
+    Ok(())
+  }
+}
+
+// endregion Signatures
+
+/// Writes `term` in prefix notation.
+fn term_to_string(term: &dyn Term) -> String {
+  let args: Vec<String> = term.iter_args().map(term_to_string).collect();
+  match args.is_empty() {
+    true  => term.symbol_ref().name.to_string(),
+    false => format!("{}({})", term.symbol_ref().name, args.join(", "))
+  }
+}
+
+/// A term generated from a `SortedSignature`.
+pub struct SortedTerm {
+  term: BxTerm,
+}
+
+impl SortedTerm {
+  /// The number of nodes in the term
+  #[inline(always)]
+  pub fn size(&self) -> usize {
+    self.term.compute_size() as usize
+  }
+
+  #[inline(always)]
+  pub(crate) fn term(&self) -> &dyn Term {
+    self.term.as_ref()
+  }
+
+  /// Converts the term to a DAG. The DAG is not rooted.
+  #[inline(always)]
+  pub(crate) fn dagify(&self) -> DagNodePtr {
+    self.term.term_to_dag(false)
+  }
+}
+
+impl Display for SortedTerm {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", term_to_string(self.term.as_ref()))
+  }
+}
+
+// region Rule sets
+
+/// A rule `lhs => rhs` of a `RuleSet`.
+pub struct RandomRule {
+  lhs: BxTerm,
+  rhs: BxTerm,
+}
+
+impl Display for RandomRule {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{} => {}", term_to_string(self.lhs.as_ref()), term_to_string(self.rhs.as_ref()))
+  }
+}
+
+/// A confluent and terminating set of rules over a `SortedSignature`. See the module documentation.
+pub struct RuleSet {
+  rules: Vec<RandomRule>,
+}
+
+impl RuleSet {
+  /// A random rule set whose right-hand sides have height at most `max_rhs_height`. For each defined operator, the
+  /// first argument whose sort has constructors is the discriminating position, and each constructor of that sort
+  /// gets at most one rule. Rules that would need more variables of a sort than the signature has are omitted.
+  pub fn random(signature: &SortedSignature, choices: &mut Choices, max_rhs_height: usize) -> Self {
+    RuleSet::random_aux(signature, choices, max_rhs_height, false)
+  }
+
+  /// Like `random`, except that right-hand sides may contain defined operators. The rules are still orthogonal, hence
+  /// confluent, but need not terminate. Use with `reduce_bounded`.
+  pub fn random_orthogonal(signature: &SortedSignature, choices: &mut Choices, max_rhs_height: usize) -> Self {
+    RuleSet::random_aux(signature, choices, max_rhs_height, true)
+  }
+
+  fn random_aux(
+    signature      : &SortedSignature,
+    choices        : &mut Choices,
+    max_rhs_height : usize,
+    allow_defined  : bool,
+  ) -> Self {
+    let mut rules = Vec::new();
+
+    for operator in signature.operators.iter().filter(|operator| !operator.is_constructor) {
+      // Defined constants have only one possible left-hand side.
+      let position = operator.domain
+          .iter()
+          .position(
+            |&sort| signature.operators.iter().any(|other| other.is_constructor && other.range == sort)
+          );
+      let constructors: Vec<Option<&OperatorDeclaration>> = match position {
+        Some(position) => {
+          signature.operators
+              .iter()
+              .filter(|other| other.is_constructor && other.range == operator.domain[position])
+              .map(Some)
+              .collect()
+        }
+        None => vec![None],
+      };
+
+      for constructor in constructors {
+        if choices.choose(2) == 0 {
+          continue;
+        }
+
+        let mut used      = vec![0usize; signature.sort_count()];
+        let mut available = Vec::new();
+        let mut fresh     = |sort: SortId, available: &mut Vec<(SortId, SymbolPtr)>| -> Option<BxTerm> {
+          let symbol = signature.variables[sort.0].get(used[sort.0])?;
+          let symbol = symbol.as_ref() as *const Symbol as SymbolPtr;
+          used[sort.0] += 1;
+          available.push((sort, symbol));
+
+          Some(Box::new(VariableTerm::new(symbol)))
+        };
+
+        let args: Option<Vec<BxTerm>> = operator.domain
+            .iter()
+            .enumerate()
+            .map(
+              |(i, &sort)| {
+                match (position, constructor) {
+                  (Some(position), Some(constructor)) if position == i => {
+                    let mut term = FreeTerm::new(constructor.symbol_ptr());
+                    term.args    = constructor.domain
+                        .iter()
+                        .map(|&sort| fresh(sort, &mut available))
+                        .collect::<Option<Vec<BxTerm>>>()?;
+                    Some(Box::new(term) as BxTerm)
+                  }
+                  _ => fresh(sort, &mut available)
+                }
+              }
+            )
+            .collect();
+        let Some(args) = args else { continue; };
+
+        let mut lhs = FreeTerm::new(operator.symbol_ptr());
+        lhs.args    = args;
+        let rhs     = signature.term_aux(choices, operator.range, max_rhs_height, allow_defined, &mut available);
+
+        rules.push(RandomRule { lhs: Box::new(lhs), rhs });
+      }
+    }
+
+    RuleSet { rules }
+  }
+
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.rules.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  /// The rules as equations.
+  pub fn into_equations(self) -> Vec<PreEquation> {
+    self.rules
+        .into_iter()
+        .map(
+          |rule| PreEquation::new(None, rule.lhs, PreEquationKind::Equation { rhs_term: rule.rhs }, vec![])
+        )
+        .collect()
+  }
+
+  /// A compiled module whose equations are the rules. The module does not own the signature's symbols, so the
+  /// signature must outlive it.
+  pub fn into_module(self) -> Module {
+    let mut module = Module {
+      equations: self.into_equations(),
+      ..Module::default()
+    };
+    module.compile_statements().expect("a generated rule has an unbound variable");
+
+    module
+  }
+}
+
+impl Display for RuleSet {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    for rule in self.rules.iter() {
+      writeln!(f, "{}", rule)?;
+    }
+
+    Ok(())
+  }
+}
+
+// endregion Rule sets
+
+/// Reduces `subject` with the rules of `rule_set`, giving up after `max_rewrites` rewrites. Returns the number of
+/// rewrites performed, or `RewriteLimitReached`. Nodes created during reduction are garbage afterward.
+pub fn reduce_bounded(rule_set: RuleSet, subject: &SortedTerm, max_rewrites: usize) -> Result<usize, RewriteLimitReached> {
+  let module  = rule_set.into_module();
+  let subject = subject.dagify();
+  let _root   = RootContainer::new(subject);
+
+  let mut context = RewritingContext::new(&module);
+  context.reduce_bounded(subject, max_rewrites)?;
+
+  Ok(context.equation_count())
+}
+
+/// Parses `source`, a module followed by a ground term over it, as in `mod M is … endm f(a)`, and reduces the term with
+/// the module's equations, giving up after `max_rewrites` rewrites. Returns the number of rewrites performed. Malformed
+/// source is reported as a `ParseError` rather than a panic, and nonterminating equations stop at the limit, so any
+/// source can be given. Nodes created during reduction are garbage afterward.
+pub fn parse_and_reduce_bounded(source: &str, max_rewrites: usize) -> Result<usize, ParseError> {
+  let (module_source, term_source) = match source.rfind("endm") {
+    Some(end) => source.split_at(end + "endm".len()),
+    None      => (source, ""),
+  };
+  let module  = parse_module(module_source)?;
+  let subject = parse_ground_term(&module, term_source)?.term_to_dag(false);
+  let _root   = RootContainer::new(subject);
+
+  let mut context = RewritingContext::new(&module);
+  // Reaching the limit is expected of nonterminating equations.
+  let _ = context.reduce_bounded(subject, max_rewrites);
+
+  Ok(context.equation_count())
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    api::term::Term,
+    core::{format::FormatStyle, parser::parse_module, sort::Sort},
+    testing::{
+      module_terms::ModuleTermGenerator,
+      parse_and_reduce_bounded,
+      Choices,
+      RuleSet,
+      SortId,
+      SortedSignature
+    }
+  };
+
+  /// Checks that every operator in `term` is applied to arguments of its domain sorts and that `term` has sort `sort`.
+  fn is_well_sorted(signature: &SortedSignature, term: &dyn Term, sort: SortId) -> bool {
+    if signature.sort_of(term.symbol()) != Some(sort) {
+      return false;
+    }
+    if term.is_variable() {
+      return true;
+    }
+
+    let operator = signature.operators()
+        .iter()
+        .find(|operator| std::ptr::eq(operator.symbol_ptr(), term.symbol()))
+        .unwrap();
+    term.iter_args()
+        .zip(operator.domain.iter())
+        .all(|(arg, &arg_sort)| is_well_sorted(signature, arg, arg_sort))
+  }
+
+  #[test]
+  fn random_terms_are_well_sorted() {
+    let mut choices = Choices::from_seed(7);
+    let signature   = SortedSignature::random(&mut choices, 3, 8, 3);
+
+    for _ in 0..100 {
+      let sort = SortId(choices.choose(signature.sort_count()));
+      let term = signature.random_term(&mut choices, sort, 4);
+      assert!(is_well_sorted(&signature, term.term(), sort), "ill-sorted term {}", term);
+    }
+  }
+
+  #[test]
+  fn exhausted_choices_generate_the_simplest_values() {
+    let mut choices = Choices::from_sequence(vec![]);
+    let signature   = SortedSignature::random(&mut choices, 2, 4, 2);
+
+    // Every zero choice picks a constant and omits every rule.
+    assert_eq!(signature.random_term(&mut choices, SortId(1), 5).size(), 1);
+    assert!(RuleSet::random(&signature, &mut choices, 3).is_empty());
+  }
+
+  #[test]
+  fn module_terms_have_the_requested_sort_and_depth() {
+    let module = parse_module("
+      mod RANDOM is
+        sorts Zero NzNat Nat Empty .
+        subsorts Zero NzNat < Nat .
+        op 0 : -> Zero .
+        op s : Nat -> NzNat .
+        op plus : Nat Nat -> Nat .
+        op never : Empty -> Nat .
+        var X : Nat .
+      endm
+    ").unwrap();
+    let sort        = |name: &str| module.arena.sorts.get_by_name(&name.into()).unwrap();
+    let generator   = ModuleTermGenerator::new(&module);
+    let mut choices = Choices::from_seed(7);
+
+    for _ in 0..50 {
+      let term = generator.generate(&mut choices, sort("Nat"), 3).unwrap();
+      assert!(term.compute_size() <= 15);
+      let least: &Sort = unsafe{ &*term.compute_sort().unwrap() };
+      assert!(least.leq(unsafe{ &*sort("Nat") }));
+    }
+    // Subsorts count, and exhausted choices give the shallowest term.
+    let mut choices = Choices::from_sequence(vec![]);
+    assert_eq!(generator.generate(&mut choices, sort("Nat"), 3).unwrap().repr(FormatStyle::Simple), "0");
+    assert_eq!(generator.generate(&mut choices, sort("NzNat"), 1).unwrap().repr(FormatStyle::Simple), "s(0)");
+
+    assert_eq!(generator.min_depth(sort("NzNat")), Some(1));
+    assert!(generator.generate(&mut choices, sort("NzNat"), 0).is_none());
+    assert!(generator.generate(&mut choices, sort("Empty"), 5).is_none());
+  }
+  #[test]
+  fn parsing_and_bounded_reduction_never_fail_by_panicking() {
+    let looping = "
+      mod LOOP is
+        sort S .
+        op a : -> S .
+        op f : S -> S .
+        var X : S .
+        eq f(X) = f(f(X)) .
+      endm
+    ";
+    assert_eq!(parse_and_reduce_bounded(&format!("{} f(a)", looping), 100), Ok(100));
+    assert_eq!(parse_and_reduce_bounded(&format!("{} a", looping), 100), Ok(0));
+
+    assert!(parse_and_reduce_bounded(looping, 100).is_err());
+    assert!(parse_and_reduce_bounded(&format!("{} f(X)", looping), 100).is_err());
+    assert!(parse_and_reduce_bounded("mod LOOP is sort S . op a : -> T . endm a", 100).is_err());
+    assert!(parse_and_reduce_bounded(")) endm (", 100).is_err());
+  }
+}
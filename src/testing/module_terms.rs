@@ -0,0 +1,138 @@
+/*!
+
+Random well-sorted terms over the operators of a `Module`, for fuzzing specifications and generating benchmark inputs.
+Where a `SortedSignature` records the sorts of its own operators, here the sorts and operator declarations are the
+module's, subsorts included. `Module::generate_random_term` builds a random ground term of a given sort and depth at
+most a given depth, using operators whose declared range is the sort or one of its subsorts, so the term's least sort
+is the requested sort or one of its subsorts.
+
+A `ModuleTermGenerator` does the same for many terms, analysing the module once. For each sort, it computes the depth
+of the shallowest term of that sort, and only chooses operators whose arguments have terms within the remaining depth,
+so generation never backtracks and never fails once the requested depth is at least the shallowest depth of the sort.
+
+Like the other generators of `testing`, it draws its decisions from `Choices`, with choice `0` the simplest option:
+declarations are ordered by the depth of the shallowest term they head, then by the name of their operator. So a
+seeded `Choices` gives the same terms in every process. The module's sorts must be closed, and the module must outlive
+the generated terms.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{
+  abstractions::HashMap,
+  api::{
+    free_theory::FreeTerm,
+    symbol::SymbolPtr,
+    term::BxTerm
+  },
+  core::{
+    module::Module,
+    sort::{sort_table::OpDeclaration, SortPtr}
+  },
+  testing::Choices
+};
+
+/// Generates random well-sorted terms over the operators of a module. See the module documentation.
+pub struct ModuleTermGenerator {
+  /// Every operator declaration heading a term, with the depth of the shallowest term it heads, simplest first
+  declarations: Vec<(SymbolPtr, OpDeclaration, usize)>,
+  /// The depth of the shallowest term of each sort that has terms
+  depths      : HashMap<SortPtr, usize>,
+}
+
+impl ModuleTermGenerator {
+  pub fn new(module: &Module) -> Self {
+    let mut symbols: Vec<SymbolPtr> = module.arena
+                                            .symbols
+                                            .values()
+                                            .copied()
+                                            .filter(|&symbol| !unsafe{ &*symbol }.is_variable())
+                                            .collect();
+    symbols.sort_by(|&a, &b| unsafe{ &*a }.name.cmp(&unsafe{ &*b }.name));
+
+    let mut declarations: Vec<(SymbolPtr, OpDeclaration, Option<usize>)> = symbols
+        .iter()
+        .flat_map(|&symbol| {
+          unsafe{ &*symbol }.sort_table
+                            .declarations()
+                            .iter()
+                            .map(move |declaration| (symbol, declaration.clone(), None))
+        })
+        .collect();
+    let sorts: Vec<SortPtr> = module.arena.sorts.iter().map(|(_, sort)| sort).collect();
+
+    // Shallowest depths only decrease, so this reaches a fixed point.
+    let mut depths: HashMap<SortPtr, usize> = HashMap::default();
+    loop {
+      let mut changed = false;
+      for (_, declaration, depth) in declarations.iter_mut() {
+        let Some(new_depth) = declaration_depth(declaration, &depths) else {
+          continue;
+        };
+        if depth.is_some_and(|depth| depth <= new_depth) {
+          continue;
+        }
+
+        *depth = Some(new_depth);
+        for &sort in sorts.iter().filter(|&&sort| leq(declaration.range, sort)) {
+          if depths.get(&sort).is_none_or(|&depth| new_depth < depth) {
+            depths.insert(sort, new_depth);
+            changed = true;
+          }
+        }
+      }
+      if !changed {
+        break;
+      }
+    }
+
+    // The sort is stable, so declarations of equal depth stay ordered by name.
+    let mut declarations: Vec<(SymbolPtr, OpDeclaration, usize)> = declarations
+        .into_iter()
+        .filter_map(|(symbol, declaration, depth)| Some((symbol, declaration, depth?)))
+        .collect();
+    declarations.sort_by_key(|(_, _, depth)| *depth);
+
+    ModuleTermGenerator { declarations, depths }
+  }
+
+  /// The depth of the shallowest term of sort `sort`, or `None` if the sort has no terms
+  #[inline(always)]
+  pub fn min_depth(&self, sort: SortPtr) -> Option<usize> {
+    self.depths.get(&sort).copied()
+  }
+
+  /// A random ground term of sort `sort` and depth at most `depth`, the depth of a constant being zero, or `None` if
+  /// there is no such term.
+  pub fn generate(&self, choices: &mut Choices, sort: SortPtr, depth: usize) -> Option<BxTerm> {
+    let candidates: Vec<&(SymbolPtr, OpDeclaration, usize)> = self.declarations
+        .iter()
+        .filter(|(_, declaration, min_depth)| *min_depth <= depth && leq(declaration.range, sort))
+        .collect();
+    if candidates.is_empty() {
+      return None;
+    }
+
+    let (symbol, declaration, _) = candidates[choices.choose(candidates.len())];
+    let args = declaration.domain
+        .iter()
+        .map(|&arg_sort| self.generate(choices, arg_sort, depth - 1).expect("an argument sort has no term of the depth"))
+        .collect();
+
+    Some(FreeTerm::with_args(*symbol, args))
+  }
+}
+
+/// The depth of the shallowest term headed by `declaration`, given the shallowest depths of sorts found so far
+fn declaration_depth(declaration: &OpDeclaration, depths: &HashMap<SortPtr, usize>) -> Option<usize> {
+  declaration.domain
+             .iter()
+             .map(|sort| depths.get(sort).map(|&depth| depth + 1))
+             .try_fold(0, |deepest, depth| depth.map(|depth| deepest.max(depth)))
+}
+
+#[inline(always)]
+fn leq(sort: SortPtr, other: SortPtr) -> bool {
+  core::ptr::eq(sort, other) || unsafe{ &*sort }.leq(unsafe{ &*other })
+}
@@ -0,0 +1,209 @@
+/*!
+
+`proptest` strategies for signatures, terms, and rule sets. Available with the `proptest` feature.
+
+Each strategy generates a sequence of choices and interprets it with the generators of the parent module, so proptest
+shrinks failing cases toward smaller terms and fewer rules. Terms and rule sets are regenerated from their choices on
+demand, because the generated values must be `Clone` and terms are not.
+
+```ignore
+proptest! {
+  #[test]
+  fn my_theory_agrees_with_free_theory(case in rewrite_system(3, 6, 3, 4)) {
+    let (rules, subject) = (case.rule_set(), case.subject());
+    // ...
+  }
+}
+```
+
+*/
+
+use core::fmt::{Debug, Formatter};
+use alloc::rc::Rc;
+
+use proptest::{
+  collection::vec,
+  prelude::*
+};
+
+use crate::testing::{Choices, RuleSet, SortId, SortedSignature, SortedTerm};
+
+/// The maximum number of choices a strategy draws. Generators choose `0` once the choices run out.
+pub const MAX_CHOICES: usize = 256;
+
+fn choices() -> impl Strategy<Value = Vec<u32>> {
+  vec(any::<u32>(), 0..=MAX_CHOICES)
+}
+
+/// Random signatures as generated by `SortedSignature::random`.
+pub fn signature(
+  sort_count    : usize,
+  operator_count: usize,
+  max_arity     : usize
+) -> impl Strategy<Value = Rc<SortedSignature>> {
+  choices().prop_map(
+    move |choices| {
+      let mut choices = Choices::from_sequence(choices);
+      Rc::new(SortedSignature::random(&mut choices, sort_count, operator_count, max_arity))
+    }
+  )
+}
+
+/// A ground term of a `SortedSignature`, regenerated from its choices by `term`.
+#[derive(Clone)]
+pub struct SortedTermCase {
+  pub signature : Rc<SortedSignature>,
+  pub sort      : SortId,
+  pub max_height: usize,
+  choices       : Vec<u32>,
+}
+
+impl SortedTermCase {
+  pub fn term(&self) -> SortedTerm {
+    let mut choices = Choices::from_sequence(self.choices.clone());
+    self.signature.random_term(&mut choices, self.sort, self.max_height)
+  }
+}
+
+impl Debug for SortedTermCase {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{} : {}", self.term(), self.signature.sort_name(self.sort))
+  }
+}
+
+/// Ground terms of sort `sort` and height at most `max_height` over `signature`.
+pub fn term(
+  signature : Rc<SortedSignature>,
+  sort      : SortId,
+  max_height: usize
+) -> impl Strategy<Value = SortedTermCase> {
+  choices().prop_map(
+    move |choices| SortedTermCase {
+      signature: signature.clone(),
+      sort,
+      max_height,
+      choices
+    }
+  )
+}
+
+/// A rule set over a `SortedSignature`, regenerated from its choices by `rule_set`.
+#[derive(Clone)]
+pub struct RuleSetCase {
+  pub signature     : Rc<SortedSignature>,
+  pub max_rhs_height: usize,
+  choices           : Vec<u32>,
+}
+
+impl RuleSetCase {
+  pub fn rule_set(&self) -> RuleSet {
+    let mut choices = Choices::from_sequence(self.choices.clone());
+    RuleSet::random(&self.signature, &mut choices, self.max_rhs_height)
+  }
+}
+
+impl Debug for RuleSetCase {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.rule_set())
+  }
+}
+
+/// Confluent and terminating rule sets over `signature` with right-hand sides of height at most `max_rhs_height`.
+pub fn rule_set(signature: Rc<SortedSignature>, max_rhs_height: usize) -> impl Strategy<Value = RuleSetCase> {
+  choices().prop_map(
+    move |choices| RuleSetCase {
+      signature: signature.clone(),
+      max_rhs_height,
+      choices
+    }
+  )
+}
+
+/// A random signature together with a rule set and a subject term over it.
+#[derive(Clone)]
+pub struct RewriteSystemCase {
+  pub rules  : RuleSetCase,
+  pub subject: SortedTermCase,
+}
+
+impl RewriteSystemCase {
+  #[inline(always)]
+  pub fn signature(&self) -> &SortedSignature {
+    &self.rules.signature
+  }
+
+  #[inline(always)]
+  pub fn rule_set(&self) -> RuleSet {
+    self.rules.rule_set()
+  }
+
+  #[inline(always)]
+  pub fn subject(&self) -> SortedTerm {
+    self.subject.term()
+  }
+}
+
+impl Debug for RewriteSystemCase {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{:?}\n{:?}\nsubject {:?}", self.rules.signature, self.rules, self.subject)
+  }
+}
+
+/// Rewrite systems over random signatures with `sort_count` sorts and `operator_count` non-constant-constructor
+/// operators. Subjects have height at most `max_height`.
+pub fn rewrite_system(
+  sort_count    : usize,
+  operator_count: usize,
+  max_arity     : usize,
+  max_height    : usize
+) -> impl Strategy<Value = RewriteSystemCase> {
+  (signature(sort_count, operator_count, max_arity), any::<prop::sample::Index>())
+      .prop_flat_map(
+        move |(signature, sort)| {
+          let sort = SortId(sort.index(signature.sort_count()));
+          (rule_set(signature.clone(), max_height), term(signature, sort, max_height))
+        }
+      )
+      .prop_map(|(rules, subject)| RewriteSystemCase { rules, subject })
+}
+
+
+#[cfg(test)]
+mod tests {
+  use proptest::prelude::*;
+
+  use crate::{
+    api::dag_node::DagNodePtr,
+    core::{
+      module::Module,
+      rewriting_context::RewritingContext,
+      substitution::Substitution,
+      RootContainer
+    },
+    testing::strategies::rewrite_system
+  };
+
+  /// Whether some equation of `module` matches some subterm of `node`.
+  fn has_redex(module: &Module, node: DagNodePtr, solution: &mut Substitution) -> bool {
+    module.equations.iter().any(|equation| equation.match_lhs(node, solution))
+        || unsafe{ &*node }.iter_args().any(|arg| has_redex(module, arg, solution))
+  }
+
+  proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Rule sets are confluent and terminating, so reduction reaches a normal form that no rule rewrites.
+    #[test]
+    fn reduction_reaches_a_normal_form(case in rewrite_system(3, 6, 3, 4)) {
+      let module  = case.rule_set().into_module();
+      let subject = case.subject().dagify();
+      let _root   = RootContainer::new(subject);
+
+      let mut context  = RewritingContext::new(&module);
+      let normal_form  = context.reduce(subject);
+      let _result_root = RootContainer::new(normal_form);
+
+      prop_assert!(!has_redex(&module, normal_form, &mut Substitution::new()));
+    }
+  }
+}
@@ -0,0 +1,30 @@
+/*!
+
+Hash-consed terms that can be shared between threads and reduced, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn arc_terms_are_shared_and_reduce() {
+  let module = Module::prelude_module("NAT").unwrap();
+  let table  = ArcTermTable::new();
+  let zero   = table.constant(IString::from("0"));
+  let one    = table.make(IString::from("s"), vec![zero.clone()]);
+  assert!(table.make(IString::from("s"), vec![zero]).ptr_eq(&one));
+
+  let subject     = table.make(IString::from("plus"), vec![one.clone(), one.clone()]);
+  let mut context = RewritingContext::new(&module);
+  let normal_form: ArcTerm = context.reduce_arc(&subject, &table).unwrap();
+  assert_eq!(normal_form.to_string(), "s(s(0))");
+  assert!(normal_form.args()[0].ptr_eq(&one));
+
+  let shared = std::thread::scope(|scope| scope.spawn(|| normal_form.args()[0].clone()).join().unwrap());
+  assert!(shared.ptr_eq(&one));
+
+  let unknown = table.constant(IString::from("nil"));
+  assert_eq!(context.reduce_arc(&unknown, &table).unwrap_err(), ArcTermError::UnknownSymbol(IString::from("nil")));
+  let statistics: HashConsStatistics = table.statistics();
+  assert!(statistics.dedup_rate() > 0.0);
+}
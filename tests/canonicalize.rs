@@ -0,0 +1,26 @@
+/*!
+
+Canonical forms modulo the axioms of the operators, from outside the crate.
+
+*/
+
+use std::cmp::Ordering;
+
+use mod2lib::prelude::*;
+
+#[test]
+fn terms_equal_modulo_axioms_have_one_canonical_form() {
+  let module = parse_module("
+    mod SET is
+      sort Set .
+      ops a b empty : -> Set .
+      op union : Set Set -> Set [assoc comm id: empty] .
+    endm
+  ").unwrap();
+  let canonical = |text: &str| canonicalize(parse_term(&module, text).unwrap().as_ref(), &module);
+
+  let left  = canonical("union(union(a, b), empty)");
+  let right = canonical("union(b, a)");
+  assert_eq!(left.compare(right.as_ref()), Ordering::Equal, "{} != {}", left, right);
+  assert_ne!(canonical("union(a, a)").compare(canonical("a").as_ref()), Ordering::Equal);
+}
@@ -0,0 +1,25 @@
+/*!
+
+Configuring a rewriting context, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn configurations_are_validated_and_change_reduction() {
+  assert_eq!(EngineConfig::default().validate(), Ok(()));
+  let tuning = AllocatorConfig { small_model_slop: 0.5, ..AllocatorConfig::default() };
+  assert!(matches!(
+    EngineConfig::default().with_allocator(tuning).validate(),
+    Err(ConfigError::InvalidAllocator(_))
+  ));
+
+  let module      = Module::prelude_module("NAT").unwrap();
+  let subject     = parse_term(&module, "plus(s(0), s(0))").unwrap();
+  let config      = EngineConfig::default().with_profile(true);
+  let mut context = RewritingContext::with_config(&module, &config);
+  assert_eq!(dag_to_string(context.reduce(subject.term_to_dag(false))), "s(s(0))");
+  assert!(context.profile().is_some());
+  assert!(RewritingContext::new(&module).profile().is_none());
+}
@@ -0,0 +1,45 @@
+/*!
+
+Walking a DAG with a `DagVisitor`, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+/// Counts the nodes visited and the nodes reached again through another parent
+#[derive(Default)]
+struct Counter {
+  visited: usize,
+  shared : usize,
+}
+
+impl DagVisitor for Counter {
+  fn pre(&mut self, _node: &dyn DagNode, _depth: usize) -> VisitControl {
+    self.visited += 1;
+    VisitControl::Continue
+  }
+
+  fn shared(&mut self, _node: &dyn DagNode, _depth: usize) -> VisitControl {
+    self.shared += 1;
+    VisitControl::Continue
+  }
+}
+
+#[test]
+fn shared_nodes_are_visited_once() {
+  let module  = Module::prelude_module("NAT").unwrap();
+  let symbols = ["0", "s", "plus"].map(|name| module.arena.symbol(&name.into()).unwrap());
+  // plus(s(0), s(0)) with s(0) shared
+  let records = [
+    NodeRecord::Symbol { symbol: 0, arity: 0 },
+    NodeRecord::Symbol { symbol: 1, arity: 1 },
+    NodeRecord::Shared(1),
+    NodeRecord::Symbol { symbol: 2, arity: 2 },
+  ];
+  let root  = ingest(&symbols, &[], &records).unwrap();
+  let _root = RootContainer::new(root);
+
+  let mut counter = Counter::default();
+  assert!(unsafe{ &*root }.visit(&mut counter));
+  assert_eq!((counter.visited, counter.shared), (3, 1));
+}
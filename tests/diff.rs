@@ -0,0 +1,25 @@
+/*!
+
+Finding where two terms or DAGs differ, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn differences_are_reported_by_position() {
+  let module = Module::prelude_module("NAT").unwrap();
+  let dag    = |text: &str| parse_term(&module, text).unwrap().term_to_dag(false);
+
+  let (left, right) = (dag("plus(s(0), s(s(0)))"), dag("plus(s(0), s(0))"));
+  let _roots        = (RootContainer::new(left), RootContainer::new(right));
+  let differences: Vec<Difference<DagNodePtr>> = diff(left, right);
+  assert_eq!(differences.len(), 1);
+  assert_eq!(differences[0].to_string(), "at [1, 0]: s(0) vs 0");
+  assert!(diff(left, left).is_empty());
+
+  let left  = parse_term(&module, "plus(0, 0)").unwrap();
+  let right = parse_term(&module, "plus(0, s(0))").unwrap();
+  let shown: Vec<String> = diff_terms(left.as_ref(), right.as_ref()).iter().map(ToString::to_string).collect();
+  assert_eq!(shown, ["at [1]: 0 vs s(0)"]);
+}
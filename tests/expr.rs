@@ -0,0 +1,34 @@
+/*!
+
+Evaluating Wolfram Language style expressions, from outside the crate.
+
+*/
+
+#![cfg(feature = "expr")]
+
+use mod2lib::prelude::*;
+
+#[test]
+fn expressions_evaluate_with_definitions() {
+  let mut engine = ExprEngine::new();
+  engine.set_attributes("hold", &[ExprAttribute::HoldAll]).unwrap();
+  engine.register_test("atomic", |expr| matches!(expr, Expr::Symbol(_)));
+
+  // double[x_?atomic] := pair[x, x]
+  engine.set_delayed(
+    &Expr::apply("double", vec![Expr::pattern_test(Expr::blank("x"), "atomic")]),
+    &Expr::apply("pair", vec![Expr::blank("x"), Expr::blank("x")])
+  ).unwrap();
+
+  let double = |arg| Expr::apply("double", vec![arg]);
+  assert_eq!(engine.evaluate(&double(Expr::symbol("a"))).unwrap().to_string(), "pair[a, a]");
+  let compound = double(Expr::apply("s", vec![Expr::symbol("a")]));
+  assert_eq!(engine.evaluate(&compound).unwrap(), compound);
+  let held = Expr::apply("hold", vec![double(Expr::symbol("a"))]);
+  assert_eq!(engine.evaluate(&held).unwrap(), held);
+
+  assert_eq!(
+    engine.set_attributes("double", &[ExprAttribute::HoldAll]).err(),
+    Some(ExprError::AlreadyInUse("double".into()))
+  );
+}
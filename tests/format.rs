@@ -0,0 +1,38 @@
+/*!
+
+Printing terms and DAGs in the pretty and LaTeX styles and within the limits of `PrintSettings`, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn terms_print_in_every_style_within_limits() {
+  let module = parse_module(r##"
+    mod PLUS is
+      sort Nat .
+      op 0 : -> Nat [ctor latex "0"] .
+      op s : Nat -> Nat [ctor latex "#1 + 1"] .
+      op plus : Nat Nat -> Nat .
+    endm
+  "##).unwrap();
+  let term  = parse_term(&module, "plus(s(s(0)), s(0))").unwrap();
+  let dag   = term.term_to_dag(false);
+  let _root = RootContainer::new(dag);
+
+  let depth = PrintSettings::default().with_max_depth(1);
+  assert_eq!(term.repr_with(FormatStyle::Simple, &depth), "plus(s(…), s(…))");
+
+  let printer = PrettyPrinter::default().with_color(false).with_width(0);
+  assert_eq!(
+    printer.term_to_tree(term.as_ref()),
+    "plus\n├── s\n│   ╰── s\n│       ╰── 0\n╰── s\n    ╰── 0"
+  );
+  assert_eq!(printer.dag_to_tree(dag), printer.term_to_tree(term.as_ref()));
+  assert_eq!(term.repr(FormatStyle::Pretty).lines().count(), 1);
+
+  assert_eq!(term.repr(FormatStyle::Latex), "\\mathsf{plus}(0 + 1 + 1, 0 + 1)");
+  assert_eq!(term_to_latex(term.as_ref(), &depth), "\\mathsf{plus}(\\ldots + 1, \\ldots + 1)");
+  assert_eq!(unsafe{ &*dag }.repr(FormatStyle::Latex), term.repr(FormatStyle::Latex));
+  assert!(module.repr(FormatStyle::Latex).ends_with("\\mathbf{endm}\n\\end{array}"));
+}
@@ -0,0 +1,29 @@
+/*!
+
+Generalizing two terms with variables from `FreshVariables`, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn generalizations_bind_fresh_variables() {
+  let module = parse_module("
+    mod GENERALIZE is
+      sort Nat .
+      ops a b : -> Nat .
+      op g : Nat -> Nat .
+      op f : Nat Nat -> Nat .
+    endm
+  ").unwrap();
+  let left  = parse_term(&module, "f(a, g(a))").unwrap();
+  let right = parse_term(&module, "f(b, g(b))").unwrap();
+
+  let mut fresh = FreshVariables::new();
+  let (generalization, left, right): (BxTerm, Bindings, Bindings) = left.generalize(right.as_ref(), &mut fresh);
+  assert_eq!(generalization.repr(FormatStyle::Simple), "f(#1, g(#1))");
+  assert_eq!(fresh.len(), 1);
+  assert_eq!(left[0].1.repr(FormatStyle::Simple), "a");
+  assert_eq!(right[0].1.repr(FormatStyle::Simple), "b");
+  assert!(std::ptr::addr_eq(left[0].0, right[0].0));
+}
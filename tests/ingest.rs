@@ -0,0 +1,35 @@
+/*!
+
+Building a DAG from a flat list of node records, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn records_build_a_shared_dag() {
+  let module  = Module::prelude_module("NAT").unwrap();
+  let symbols = ["0", "s", "plus"].map(|name| module.arena.symbol(&name.into()).unwrap());
+
+  // plus(s(0), s(0)) with s(0) shared
+  let records = [
+    NodeRecord::Symbol { symbol: 0, arity: 0 },
+    NodeRecord::Symbol { symbol: 1, arity: 1 },
+    NodeRecord::Shared(1),
+    NodeRecord::Symbol { symbol: 2, arity: 2 },
+  ];
+  let root  = ingest(&symbols, &[], &records).unwrap();
+  let _root = RootContainer::new(root);
+  assert_eq!(dag_to_string(root), "plus(s(0), s(0))");
+  let args: Vec<DagNodePtr> = unsafe{ &*root }.iter_args().collect();
+  assert!(std::ptr::addr_eq(args[0], args[1]));
+
+  let mut context = RewritingContext::new(&module);
+  assert_eq!(dag_to_string(context.reduce(root)), "s(s(0))");
+
+  assert_eq!(ingest_all(&symbols, &[], &records[..2]).unwrap().len(), 1);
+  assert_eq!(
+    ingest(&symbols, &[], &[NodeRecord::Shared(0)]),
+    Err(IngestError::ForwardReference { position: 0, target: 0 })
+  );
+}
@@ -0,0 +1,28 @@
+/*!
+
+Moving terms between the object level and the metalevel, and reducing at the metalevel, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn terms_are_reduced_at_the_metalevel() {
+  let mut meta = Module::prelude_module("META-LEVEL").unwrap();
+  meta.add_object_module(Module::prelude_module("NAT").unwrap()).unwrap();
+  let metalevel = MetaLevel::new(&meta).unwrap();
+  let object    = meta.object_modules.get(&IString::from("NAT")).unwrap();
+
+  let subject = parse_term(object, "plus(s(0), 0)").unwrap().term_to_dag(false);
+  let up      = metalevel.up_term(subject).unwrap();
+  let _roots  = (RootContainer::new(subject), RootContainer::new(up));
+  assert_eq!(
+    dag_to_string(up),
+    "app('plus, args(app('s, args(app('0, noArgs), noArgs)), args(app('0, noArgs), noArgs)))"
+  );
+  assert_eq!(dag_to_string(metalevel.down_term(object, up).unwrap().term_to_dag(false)), "plus(s(0), 0)");
+
+  let reduced = metalevel.meta_reduce(metalevel.qid("NAT").unwrap(), up).unwrap();
+  assert!(dag_to_string(reduced).starts_with("pair(app('s, args(app('0, noArgs), noArgs)), "));
+  assert_eq!(metalevel.qid("INT").err(), Some(MetaError::MissingSymbol(IString::from("'INT"))));
+}
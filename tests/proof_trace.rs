@@ -0,0 +1,26 @@
+/*!
+
+Tracing the rewrites of a reduction, from outside the crate.
+
+*/
+
+use mod2lib::prelude::*;
+
+#[test]
+fn traces_record_each_rewrite() {
+  let module      = Module::prelude_module("NAT").unwrap();
+  let subject     = parse_term(&module, "plus(s(0), 0)").unwrap().term_to_dag(false);
+  let _root       = RootContainer::new(subject);
+  let mut context = RewritingContext::new(&module);
+
+  let (result, trace): (_, ProofTrace) = context.reduce_traced(subject);
+  assert_eq!(dag_to_string(result), "s(0)");
+  assert_eq!((trace.subject.as_str(), trace.result.as_str()), ("plus(s(0), 0)", "s(0)"));
+  assert!(!trace.is_empty());
+
+  let first: &RewriteStep = &trace.steps[0];
+  assert!(first.position.is_empty());
+  assert_eq!(first.redex, "plus(s(0), 0)");
+  assert_eq!(trace.steps.last().unwrap().contractum, "0");
+  assert!(trace.to_json().starts_with("{\n  \"subject\": \"plus(s(0), 0)\",\n"));
+}
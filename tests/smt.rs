@@ -0,0 +1,75 @@
+/*!
+
+Discharging the conditions of equations with an SMT solver, from outside the crate.
+
+*/
+
+#![cfg(feature = "smt")]
+
+use mod2lib::prelude::*;
+
+/// Decides `X > c` for a variable `X` and an integer `c`, the only formulas the test asks about
+struct GreaterThanSolver;
+
+impl SmtSolver for GreaterThanSolver {
+  fn check_sat(&mut self, formula: &SmtTerm) -> SmtResult {
+    match formula {
+      SmtTerm::Apply(SmtOperator::Gt, operands) => match operands.as_slice() {
+        [SmtTerm::Variable(..), SmtTerm::Int(_)] => SmtResult::Sat,
+        [SmtTerm::Int(left), SmtTerm::Int(right)] if left > right => SmtResult::Sat,
+        _ => SmtResult::Unsat,
+      },
+      _ => SmtResult::Unknown,
+    }
+  }
+}
+
+#[test]
+fn reduction_asks_the_solver_about_conditions() {
+  let mut module = parse_module("
+    mod POSITIVE is
+      sorts Int Bool Answer .
+      op gt : Int Int -> Bool .
+      op zero : -> Int .
+      op true : -> Bool .
+      op positive : Int -> Answer .
+      op yes : -> Answer .
+      var X : Int .
+    endm
+  ").unwrap();
+  let int = module.arena.sorts.get_by_name(&"Int".into()).unwrap();
+  module.add_sort_predicate(int, |atom| atom.as_any().is::<IntegerAtom>());
+
+  // positive(X) = yes if gt(X, zero) = true
+  let term      = |text: &str| parse_term(&module, text).unwrap();
+  let condition = Condition::Equality { lhs_term: term("gt(X, zero)"), rhs_term: term("true") };
+  let equation  = PreEquation::new(
+    None,
+    term("positive(X)"),
+    PreEquationKind::Equation { rhs_term: term("yes") },
+    vec![Box::new(condition)]
+  );
+  module.add_statement(equation).unwrap();
+  let term = |text: &str| parse_term(&module, text).unwrap();
+
+  let symbol = |name: &str| module.arena.symbol(&name.into()).unwrap();
+  let mut signature = SmtSignature::new();
+  signature.operator(symbol("gt"), SmtOperator::Gt);
+  signature.literal(symbol("zero"), SmtTerm::Int(0));
+  signature.literal(symbol("true"), SmtTerm::Bool(true));
+  assert_eq!(
+    signature.translate(term("gt(zero, zero)").as_ref()).unwrap(),
+    SmtTerm::Apply(SmtOperator::Gt, vec![SmtTerm::Int(0), SmtTerm::Int(0)])
+  );
+  assert!(matches!(signature.translate(term("yes").as_ref()), Err(SmtError::UninterpretedSymbol(_))));
+
+  let positive = |value: i64| {
+    let node = FreeDagNode::new(symbol("positive"));
+    unsafe{ &mut *node }.insert_child(DataDagNode::new(integer(value))).unwrap();
+    node
+  };
+  let mut context = RewritingContext::new(&module);
+  context.set_smt_solver(signature, GreaterThanSolver);
+  assert_eq!(dag_to_string(context.reduce(positive(5))), "yes");
+  assert_ne!(dag_to_string(context.reduce(positive(-3))), "yes");
+}
@@ -0,0 +1,26 @@
+/*!
+
+Saving a module and the term being worked on, and restoring them, from outside the crate.
+
+*/
+
+#![cfg(feature = "std")]
+
+use mod2lib::prelude::*;
+
+#[test]
+fn a_restored_snapshot_reduces_its_term() {
+  let module  = Module::prelude_module("NAT").unwrap();
+  let subject = parse_term(&module, "plus(s(0), s(0))").unwrap().term_to_dag(false);
+  let _root   = RootContainer::new(subject);
+
+  let mut saved = Vec::new();
+  Snapshot::write(&mut saved, &module, Some(subject)).unwrap();
+  let restored = Snapshot::read(saved.as_slice()).unwrap();
+  assert_eq!(restored.module.name, module.name);
+
+  let mut context = RewritingContext::new(&restored.module);
+  assert_eq!(dag_to_string(context.reduce(restored.term().unwrap())), "s(s(0))");
+
+  assert!(matches!(Snapshot::read("not a snapshot".as_bytes()), Err(SnapshotError::Malformed { .. })));
+}
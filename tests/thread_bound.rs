@@ -0,0 +1,52 @@
+/*!
+
+Binding the engine to a thread. These tests are kept out of the library's unit tests, which use the engine from many
+threads at once without binding it: while a test here holds the engine, any other thread using it would panic.
+
+*/
+
+#![cfg(feature = "std")]
+
+use std::{sync::mpsc, thread};
+
+use mod2lib::{
+  api::{Arity, EngineBusy, ThreadBoundEngine, symbol::Symbol},
+  prelude::*
+};
+
+#[test]
+fn work_moves_between_threads_with_the_engine() {
+  let (sender, receiver) = mpsc::channel();
+
+  let worker = thread::spawn(
+    move || {
+      let parcel = receiver.recv().unwrap();
+      // The sending thread releases the engine before sending.
+      let engine = ThreadBoundEngine::acquire().unwrap();
+      let module: Module = engine.receive(parcel);
+      module.arena.symbols.len()
+    }
+  );
+
+  let engine     = ThreadBoundEngine::acquire().unwrap();
+  assert_eq!(ThreadBoundEngine::acquire().err(), Some(EngineBusy));
+  assert_eq!(ThreadBoundEngine::owner(), Some(thread::current().id()));
+
+  let mut module = Module::default();
+  module.arena.add_symbol(Symbol::new(IString::from("a"), Arity::Value(0)));
+  let parcel = unsafe{ engine.transfer(module) };
+  drop(engine);
+  sender.send(parcel).unwrap();
+
+  assert_eq!(worker.join().unwrap(), 1);
+  assert_eq!(ThreadBoundEngine::owner(), None);
+
+  // The entry points refuse a thread that doesn't hold the engine while another thread does.
+  let engine   = ThreadBoundEngine::acquire().unwrap();
+  let intruder = thread::spawn(|| parse_module("mod M is sort A . op a : -> A . endm").map(|_| ()));
+  assert!(intruder.join().is_err());
+
+  let module = parse_module("mod M is sort A . op a : -> A . endm").unwrap();
+  let _context = RewritingContext::new(&module);
+  engine.collect_garbage();
+}